@@ -17,10 +17,15 @@
 
 use super::{ErrorCode, FfiResult};
 use super::callback::{Callback, CallbackArgs};
+use std::cell::RefCell;
 use std::fmt::{Debug, Display};
 use std::os::raw::c_void;
 use std::panic::{self, AssertUnwindSafe};
 
+thread_local! {
+    static LAST_ERROR_DESCRIPTION: RefCell<Option<String>> = RefCell::new(None);
+}
+
 fn catch_unwind_result<'a, F, T, E>(f: F) -> Result<T, E>
 where
     F: FnOnce() -> Result<T, E>,
@@ -32,13 +37,25 @@ where
     }
 }
 
-/// Catch panics. On error return the error code.
+/// Catch panics. On error return the error code and remember the error
+/// description, so it can be retrieved with `last_error_description`.
 pub fn catch_unwind_error_code<'a, F, E>(f: F) -> i32
 where
     F: FnOnce() -> Result<(), E>,
-    E: Debug + ErrorCode + From<&'a str>,
+    E: Debug + Display + ErrorCode + From<&'a str>,
 {
-    ffi_result_code!(catch_unwind_result(f))
+    let res = catch_unwind_result(f);
+    if let Err(ref err) = res {
+        let description = format!("{}", err);
+        LAST_ERROR_DESCRIPTION.with(|last| *last.borrow_mut() = Some(description));
+    }
+    ffi_result_code!(res)
+}
+
+/// Description of the most recent error returned as a plain error code by a
+/// function wrapped in `catch_unwind_error_code` on the current thread.
+pub fn last_error_description() -> Option<String> {
+    LAST_ERROR_DESCRIPTION.with(|last| last.borrow().clone())
 }
 
 /// Catch panics. On error call the callback.
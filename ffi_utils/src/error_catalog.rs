@@ -0,0 +1,125 @@
+// Copyright 2017 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under (1) the MaidSafe.net Commercial License,
+// version 1.0 or later, or (2) The General Public License (GPL), version 3, depending on which
+// licence you accepted on initial access to the Software (the "Licences").
+//
+// By contributing code to the SAFE Network Software, or to this project generally, you agree to be
+// bound by the terms of the MaidSafe Contributor Agreement.  This, along with the Licenses can be
+// found in the root directory of this project at LICENSE, COPYING and CONTRIBUTOR.
+//
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.
+//
+// Please review the Licences for the specific language governing permissions and limitations
+// relating to use of the SAFE Network Software.
+
+//! Machine-readable catalog of error codes.
+
+/// Single entry of an error catalog: symbolic name, error code and description.
+pub type ErrorCatalogEntry = (&'static str, i32, &'static str);
+
+/// Render the catalog as a JSON array of `{"name", "code", "description"}`
+/// objects, suitable for generating typed error enums in foreign languages.
+pub fn error_catalog_to_json(catalog: &[ErrorCatalogEntry]) -> String {
+    let entries: Vec<_> = catalog
+        .iter()
+        .map(|&(name, code, description)| {
+            format!(
+                "{{\"name\":\"{}\",\"code\":{},\"description\":\"{}\"}}",
+                escape_json(name),
+                code,
+                escape_json(description)
+            )
+        })
+        .collect();
+
+    format!("[{}]", entries.join(","))
+}
+
+/// Look up the symbolic name of `code` in the catalog.
+pub fn error_code_name(catalog: &[ErrorCatalogEntry], code: i32) -> Option<&'static str> {
+    catalog
+        .iter()
+        .find(|&&(_, entry_code, _)| entry_code == code)
+        .map(|&(name, _, _)| name)
+}
+
+fn escape_json(input: &str) -> String {
+    let mut output = String::with_capacity(input.len());
+
+    for c in input.chars() {
+        match c {
+            '"' => output.push_str("\\\""),
+            '\\' => output.push_str("\\\\"),
+            '\n' => output.push_str("\\n"),
+            '\r' => output.push_str("\\r"),
+            '\t' => output.push_str("\\t"),
+            c if (c as u32) < 0x20 => output.push_str(&format!("\\u{:04x}", c as u32)),
+            c => output.push(c),
+        }
+    }
+
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ERR_FIRST: i32 = -1;
+    const ERR_SECOND: i32 = -2;
+
+    #[test]
+    fn to_json() {
+        let catalog = [
+            error_catalog_entry!(ERR_FIRST, "First error"),
+            error_catalog_entry!(ERR_SECOND, "Second \"quoted\"\nerror"),
+        ];
+
+        assert_eq!(
+            error_catalog_to_json(&catalog),
+            "[{\"name\":\"ERR_FIRST\",\"code\":-1,\"description\":\"First error\"},\
+             {\"name\":\"ERR_SECOND\",\"code\":-2,\
+             \"description\":\"Second \\\"quoted\\\"\\nerror\"}]"
+        );
+        assert_eq!(error_catalog_to_json(&[]), "[]");
+    }
+
+    #[test]
+    fn code_name() {
+        let catalog = [
+            error_catalog_entry!(ERR_FIRST, "First error"),
+            error_catalog_entry!(ERR_SECOND, "Second error"),
+        ];
+
+        assert_eq!(error_code_name(&catalog, ERR_SECOND), Some("ERR_SECOND"));
+        assert_eq!(error_code_name(&catalog, -3), None);
+    }
+
+    mod codes {
+        error_codes! {
+            CATALOG;
+            c_name;
+
+            ERR_THIRD = -3, "Third error";
+            ERR_FOURTH = -4, "Fourth error";
+        }
+    }
+
+    #[test]
+    fn generated_codes() {
+        use std::ffi::CStr;
+
+        assert_eq!(codes::ERR_FOURTH, -4);
+        assert_eq!(
+            codes::CATALOG,
+            &[("ERR_THIRD", -3, "Third error"), ("ERR_FOURTH", -4, "Fourth error")][..]
+        );
+
+        let name = unsafe { CStr::from_ptr(codes::c_name(codes::ERR_THIRD)) };
+        assert_eq!(unwrap!(name.to_str()), "ERR_THIRD");
+        assert!(codes::c_name(-5).is_null());
+    }
+}
@@ -53,6 +53,7 @@ extern crate unwrap;
 mod macros;
 mod base64;
 mod catch_unwind;
+mod error_catalog;
 mod repr_c;
 mod vec;
 
@@ -62,7 +63,8 @@ pub mod string;
 pub mod header_gen;
 
 pub use self::base64::{base64_decode, base64_encode};
-pub use self::catch_unwind::{catch_unwind_cb, catch_unwind_error_code};
+pub use self::catch_unwind::{catch_unwind_cb, catch_unwind_error_code, last_error_description};
+pub use self::error_catalog::{ErrorCatalogEntry, error_catalog_to_json, error_code_name};
 pub use self::repr_c::ReprC;
 pub use self::string::{StringError, from_c_str};
 pub use self::vec::{SafePtr, vec_clone_from_raw_parts, vec_into_raw_parts};
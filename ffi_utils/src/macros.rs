@@ -90,3 +90,59 @@ macro_rules! try_cb {
         }
     }
 }
+
+/// Build an `ErrorCatalogEntry` from an error code constant and its description.
+#[macro_export]
+macro_rules! error_catalog_entry {
+    ($code:ident, $description:expr) => {
+        (stringify!($code), $code, $description)
+    }
+}
+
+/// Define the error code constants together with their catalog and a function
+/// returning the symbolic name of a code as a static NUL-terminated C string
+/// (or null if the code is unknown). Everything is generated from the single
+/// list of codes, so no code can be left out of the catalog, and two codes
+/// with the same value fail to compile as unreachable patterns.
+///
+/// ```ignore
+/// error_codes! {
+///     /// Catalog of all the error codes.
+///     ERROR_CATALOG;
+///     /// Symbolic name of the error code.
+///     error_code_c_name;
+///
+///     ERR_FIRST = -1, "First error";
+///     ERR_SECOND = -2, "Second error";
+/// }
+/// ```
+#[macro_export]
+macro_rules! error_codes {
+    (
+        $(#[$catalog_attr:meta])*
+        $catalog:ident;
+        $(#[$name_fn_attr:meta])*
+        $name_fn:ident;
+
+        $($code:ident = $value:expr, $description:expr;)*
+    ) => {
+        $(
+            #[allow(missing_docs)]
+            pub const $code: i32 = $value;
+        )*
+
+        $(#[$catalog_attr])*
+        pub const $catalog: &[$crate::ErrorCatalogEntry] = &[
+            $((stringify!($code), $code, $description),)*
+        ];
+
+        $(#[$name_fn_attr])*
+        #[deny(unreachable_patterns)]
+        pub fn $name_fn(code: i32) -> *const ::std::os::raw::c_char {
+            match code {
+                $($code => concat!(stringify!($code), "\0").as_ptr() as *const _,)*
+                _ => ::std::ptr::null(),
+            }
+        }
+    }
+}
@@ -94,3 +94,13 @@ impl ReprC for [u8; 24] {
         Ok(*c_repr)
     }
 }
+
+/// Secret sign key
+impl ReprC for [u8; 64] {
+    type C = *const [u8; 64];
+    type Error = ();
+
+    unsafe fn clone_from_repr_c(c_repr: *const [u8; 64]) -> Result<[u8; 64], Self::Error> {
+        Ok(*c_repr)
+    }
+}
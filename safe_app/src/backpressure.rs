@@ -0,0 +1,181 @@
+// Copyright 2017 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under (1) the MaidSafe.net Commercial License,
+// version 1.0 or later, or (2) The General Public License (GPL), version 3, depending on which
+// licence you accepted on initial access to the Software (the "Licences").
+//
+// By contributing code to the SAFE Network Software, or to this project generally, you agree to be
+// bound by the terms of the MaidSafe Contributor Agreement.  This, along with the Licenses can be
+// found in the root directory of this project at LICENSE, COPYING and CONTRIBUTOR.
+//
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.
+//
+// Please review the Licences for the specific language governing permissions and limitations
+// relating to use of the SAFE Network Software.
+
+//! Limit of the operations in flight in the app's event loop, so that an app
+//! firing requests faster than the network serves them doesn't exhaust the
+//! memory of the device.
+
+use errors::AppError;
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread::{self, ThreadId};
+
+/// What to do with an operation sent while the limit is reached.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OverflowPolicy {
+    /// Fail the operation with `AppError::TooManyRequests`.
+    Reject,
+    /// Block the calling thread until another operation completes. Operations
+    /// sent from the event loop thread itself (e.g. from callbacks) are
+    /// rejected instead, as blocking it would deadlock.
+    Block,
+}
+
+/// Counts the operations in flight and applies the backpressure once their
+/// number reaches the limit.
+pub struct RequestLimiter {
+    state: Mutex<State>,
+    freed: Condvar,
+}
+
+struct State {
+    max: Option<usize>,
+    policy: OverflowPolicy,
+    in_flight: usize,
+    event_loop_thread: Option<ThreadId>,
+}
+
+impl RequestLimiter {
+    /// Create limiter with no limit.
+    pub fn new() -> Self {
+        RequestLimiter {
+            state: Mutex::new(State {
+                max: None,
+                policy: OverflowPolicy::Reject,
+                in_flight: 0,
+                event_loop_thread: None,
+            }),
+            freed: Condvar::new(),
+        }
+    }
+
+    /// Limit the number of operations in flight to `max` (`None` for no limit)
+    /// applying `policy` to the operations over it.
+    pub fn configure(&self, max: Option<usize>, policy: OverflowPolicy) {
+        {
+            let mut state = unwrap!(self.state.lock());
+            state.max = max;
+            state.policy = policy;
+        }
+        self.freed.notify_all();
+    }
+
+    /// Record the calling thread as the event loop thread, which is never
+    /// blocked.
+    pub fn set_event_loop_thread(&self) {
+        unwrap!(self.state.lock()).event_loop_thread = Some(thread::current().id());
+    }
+
+    /// Number of operations in flight.
+    pub fn in_flight(&self) -> usize {
+        unwrap!(self.state.lock()).in_flight
+    }
+
+    /// Take a slot for an operation, applying the overflow policy if there is
+    /// none free. The slot is freed when the returned guard is dropped.
+    pub fn acquire(limiter: &Arc<Self>) -> Result<Slot, AppError> {
+        let mut state = unwrap!(limiter.state.lock());
+
+        while state.max.map_or(false, |max| state.in_flight >= max) {
+            let on_event_loop = state.event_loop_thread == Some(thread::current().id());
+            if state.policy == OverflowPolicy::Reject || on_event_loop {
+                return Err(AppError::TooManyRequests);
+            }
+            state = unwrap!(limiter.freed.wait(state));
+        }
+
+        state.in_flight += 1;
+        Ok(Slot(Arc::clone(limiter)))
+    }
+
+    fn release(&self) {
+        {
+            let mut state = unwrap!(self.state.lock());
+            state.in_flight = state.in_flight.saturating_sub(1);
+        }
+        self.freed.notify_one();
+    }
+}
+
+impl Default for RequestLimiter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Slot of an operation in flight, freed when dropped.
+pub struct Slot(Arc<RequestLimiter>);
+
+impl Drop for Slot {
+    fn drop(&mut self) {
+        self.0.release();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::mpsc;
+    use std::time::Duration;
+
+    #[test]
+    fn reject() {
+        let limiter = Arc::new(RequestLimiter::new());
+        limiter.configure(Some(1), OverflowPolicy::Reject);
+
+        let slot = unwrap!(RequestLimiter::acquire(&limiter));
+        match RequestLimiter::acquire(&limiter) {
+            Err(AppError::TooManyRequests) => (),
+            Err(err) => panic!("Unexpected {:?}", err),
+            Ok(_) => panic!("Unexpected success"),
+        }
+        assert_eq!(limiter.in_flight(), 1);
+
+        drop(slot);
+        assert_eq!(limiter.in_flight(), 0);
+        let _slot = unwrap!(RequestLimiter::acquire(&limiter));
+    }
+
+    #[test]
+    fn block() {
+        let limiter = Arc::new(RequestLimiter::new());
+        limiter.configure(Some(1), OverflowPolicy::Block);
+
+        let slot = unwrap!(RequestLimiter::acquire(&limiter));
+
+        let (tx, rx) = mpsc::channel();
+        let limiter2 = Arc::clone(&limiter);
+        let joiner = thread::spawn(move || {
+            let _slot = unwrap!(RequestLimiter::acquire(&limiter2));
+            unwrap!(tx.send(()));
+        });
+
+        // The second operation waits until the first one is done.
+        assert!(rx.recv_timeout(Duration::from_millis(100)).is_err());
+        drop(slot);
+        unwrap!(rx.recv_timeout(Duration::from_secs(5)));
+        unwrap!(joiner.join());
+
+        // The event loop thread is never blocked.
+        limiter.set_event_loop_thread();
+        let _slot = unwrap!(RequestLimiter::acquire(&limiter));
+        match RequestLimiter::acquire(&limiter) {
+            Err(AppError::TooManyRequests) => (),
+            Err(err) => panic!("Unexpected {:?}", err),
+            Ok(_) => panic!("Unexpected success"),
+        }
+    }
+}
@@ -0,0 +1,351 @@
+// Copyright 2016 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under (1) the MaidSafe.net Commercial License,
+// version 1.0 or later, or (2) The General Public License (GPL), version 3, depending on which
+// licence you accepted on initial access to the Software (the "Licences").
+//
+// By contributing code to the SAFE Network Software, or to this project generally, you agree to be
+// bound by the terms of the MaidSafe Contributor Agreement.  This, along with the Licenses can be
+// found in the root directory of this project at LICENSE, COPYING and CONTRIBUTOR.
+//
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.
+//
+// Please review the Licences for the specific language governing permissions and limitations
+// relating to use of the SAFE Network Software.
+
+//! Encrypted backup of the data reachable from the app's access container.
+//! The entries of all the containers, together with the contents of the NFS
+//! files stored in them, are exported into a single archive encrypted with a
+//! symmetric key, which can later be restored into another account or app.
+
+use {AccessContainerEntry, AppContext, AppFuture};
+use errors::AppError;
+use futures::{Future, future};
+use futures::future::Loop;
+use maidsafe_utilities::serialisation::{deserialise, serialise};
+use safe_core::{Client, FutureExt, MDataInfo, mdata_info, utils};
+use safe_core::crypto::shared_secretbox;
+use safe_core::nfs::{File, Mode, file_helper};
+use safe_core::structures::{LastWriterWins, edit};
+use std::collections::BTreeMap;
+
+const OWN_CONTAINER_PREFIX: &'static str = "apps/";
+
+#[derive(Serialize, Deserialize)]
+struct Archive {
+    containers: BTreeMap<String, Vec<ArchivedEntry>>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct ArchivedEntry {
+    key: Vec<u8>,
+    value: Vec<u8>,
+    // Content of the file, if the entry is an NFS file.
+    content: Option<Vec<u8>>,
+}
+
+/// Export all the entries of the containers the app has access to, including
+/// the contents of the files, into an archive encrypted with `key`.
+/// `progress` is called with the number of entries exported so far and the
+/// total number of entries.
+pub fn export<F>(
+    client: &Client<AppContext>,
+    context: &AppContext,
+    key: shared_secretbox::Key,
+    progress: F,
+) -> Box<AppFuture<Vec<u8>>>
+where
+    F: FnMut(u64, u64) + 'static,
+{
+    let client = client.clone();
+    let client2 = client.clone();
+
+    context
+        .get_access_info(&client)
+        .and_then(move |access_info| {
+            let futures: Vec<_> = access_info
+                .into_iter()
+                .map(|(name, (info, _))| {
+                    let info2 = info.clone();
+                    client
+                        .list_mdata_entries(info.name, info.type_tag)
+                        .and_then(move |entries| {
+                            // Skip deleted entries.
+                            let entries = entries
+                                .into_iter()
+                                .filter(|&(_, ref value)| !value.content.is_empty())
+                                .collect();
+                            mdata_info::decrypt_entries(&info2, &entries)
+                        })
+                        .map_err(AppError::from)
+                        .map(move |entries| (name, info, entries))
+                })
+                .collect();
+
+            future::join_all(futures)
+        })
+        .and_then(move |containers| {
+            let mut items = Vec::new();
+
+            for (name, info, entries) in containers {
+                for (key, value) in entries {
+                    items.push((name.clone(), info.clone(), key, value.content));
+                }
+            }
+
+            let total = items.len() as u64;
+            let archive = Archive { containers: BTreeMap::new() };
+
+            future::loop_fn(
+                (items.into_iter(), archive, progress, 0),
+                move |(mut items, mut archive, mut progress, done)| {
+                    let (name, info, key, value) = match items.next() {
+                        Some(item) => item,
+                        None => return ok!(Loop::Break(archive)),
+                    };
+
+                    read_content(&client2, &info, &value)
+                        .map(move |content| {
+                            archive
+                                .containers
+                                .entry(name)
+                                .or_insert_with(Vec::new)
+                                .push(ArchivedEntry {
+                                    key,
+                                    value,
+                                    content,
+                                });
+                            progress(done + 1, total);
+                            Loop::Continue((items, archive, progress, done + 1))
+                        })
+                        .into_box()
+                },
+            )
+        })
+        .and_then(move |archive| {
+            let encoded = serialise(&archive)?;
+            Ok(utils::symmetric_encrypt(&encoded, &key, None)?)
+        })
+        .into_box()
+}
+
+/// Restore the archive created by `export` into the containers of this app.
+/// Containers the app has no access to are skipped, except for the own
+/// container of the exporting app, which is restored into the own container
+/// of this app. The files are re-uploaded, so the restored data does not
+/// depend on the exporting account. `progress` is called with the number of
+/// entries restored so far and the total number of entries.
+pub fn restore<F>(
+    client: &Client<AppContext>,
+    context: &AppContext,
+    archive: &[u8],
+    key: &shared_secretbox::Key,
+    progress: F,
+) -> Box<AppFuture<()>>
+where
+    F: FnMut(u64, u64) + 'static,
+{
+    let encoded = fry!(utils::symmetric_decrypt(archive, key));
+    let archive: Archive = fry!(deserialise(&encoded));
+
+    let client = client.clone();
+    let client2 = client.clone();
+
+    context
+        .get_access_info(&client)
+        .and_then(move |access_info| {
+            let mut items = Vec::new();
+
+            for (name, entries) in archive.containers {
+                let target = target_container(&access_info, &name);
+
+                if let Some(info) = target {
+                    for entry in entries {
+                        items.push((info.clone(), entry));
+                    }
+                }
+            }
+
+            let total = items.len() as u64;
+            let restored: BTreeMap<_, (MDataInfo, BTreeMap<_, _>)> = BTreeMap::new();
+
+            future::loop_fn(
+                (items.into_iter(), restored, progress, 0),
+                move |(mut items, mut restored, mut progress, done)| {
+                    let (info, entry) = match items.next() {
+                        Some(item) => item,
+                        None => return ok!(Loop::Break(restored)),
+                    };
+
+                    write_content(&client, &info, entry.value, entry.content)
+                        .map(move |value| {
+                            let _ = restored
+                                .entry(info.name)
+                                .or_insert_with(|| (info, BTreeMap::new()))
+                                .1
+                                .insert(entry.key, value);
+                            progress(done + 1, total);
+                            Loop::Continue((items, restored, progress, done + 1))
+                        })
+                        .into_box()
+                },
+            )
+        })
+        .and_then(move |restored| {
+            let futures: Vec<_> = restored
+                .into_iter()
+                .map(|(_, (info, values))| {
+                    edit(
+                        &client2,
+                        info,
+                        move |entries| entries.extend(values),
+                        LastWriterWins,
+                    ).map_err(AppError::from)
+                })
+                .collect();
+
+            future::join_all(futures).map(|_| ())
+        })
+        .into_box()
+}
+
+// Find the container to restore the archived container `name` into. The own
+// container of the exporting app maps to the own container of this app.
+fn target_container<'a>(
+    access_info: &'a AccessContainerEntry,
+    name: &str,
+) -> Option<&'a MDataInfo> {
+    if let Some(&(ref info, _)) = access_info.get(name) {
+        return Some(info);
+    }
+
+    if name.starts_with(OWN_CONTAINER_PREFIX) {
+        access_info
+            .iter()
+            .find(|&(container, _)| container.starts_with(OWN_CONTAINER_PREFIX))
+            .map(|(_, &(ref info, _))| info)
+    } else {
+        None
+    }
+}
+
+// If `value` is an NFS file, read its content.
+fn read_content(
+    client: &Client<AppContext>,
+    info: &MDataInfo,
+    value: &[u8],
+) -> Box<AppFuture<Option<Vec<u8>>>> {
+    let file: File = match deserialise(value) {
+        Ok(file) => file,
+        Err(_) => return ok!(None),
+    };
+
+    file_helper::read(client.clone(), &file, info.enc_key().cloned())
+        .and_then(|reader| reader.read(0, reader.size()))
+        .map(Some)
+        .map_err(AppError::from)
+        .into_box()
+}
+
+// Upload the content of the file and return the entry value referring to it.
+fn write_content(
+    client: &Client<AppContext>,
+    info: &MDataInfo,
+    value: Vec<u8>,
+    content: Option<Vec<u8>>,
+) -> Box<AppFuture<Vec<u8>>> {
+    let content = match content {
+        Some(content) => content,
+        None => return ok!(value),
+    };
+    let file: File = fry!(deserialise(&value));
+
+    file_helper::write(
+        client.clone(),
+        file,
+        Mode::Overwrite,
+        info.enc_key().cloned(),
+    ).and_then(move |writer| {
+            writer.write(&content).and_then(move |_| writer.close())
+        })
+        .map_err(AppError::from)
+        .and_then(|file| Ok(serialise(&file)?))
+        .into_box()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+    use std::sync::mpsc;
+    use test_utils::{create_app_with_access, run};
+
+    // Export the data of one app and restore it into an app of another account.
+    #[test]
+    fn export_and_restore() {
+        let app = create_app_with_access(HashMap::new());
+        let key = shared_secretbox::gen_key();
+
+        run(&app, |client, context| {
+            let client2 = client.clone();
+            let client3 = client.clone();
+
+            context.get_own_container(client).and_then(move |info| {
+                let enc_key = info.enc_key().cloned();
+
+                file_helper::write(client2, File::new(b"meta".to_vec()), Mode::Overwrite, enc_key)
+                    .and_then(|writer| {
+                        writer.write(b"hello world").and_then(move |_| writer.close())
+                    })
+                    .and_then(move |file| file_helper::insert(client3, info, "hello.txt", &file))
+                    .map_err(AppError::from)
+            })
+        });
+
+        let (tx, rx) = mpsc::channel();
+        let key2 = key.clone();
+        let archive = run(&app, move |client, context| {
+            export(client, context, key2, move |done, total| {
+                unwrap!(tx.send((done, total)));
+            })
+        });
+        assert_eq!(rx.try_iter().last(), Some((1, 1)));
+
+        // Restoring with a wrong key fails.
+        let app2 = create_app_with_access(HashMap::new());
+        let archive2 = archive.clone();
+        let failed = run(&app2, move |client, context| {
+            let wrong_key = shared_secretbox::gen_key();
+            restore(client, context, &archive2, &wrong_key, |_, _| ())
+                .then(|res| Ok::<_, AppError>(res.is_err()))
+        });
+        assert!(failed);
+
+        run(&app2, move |client, context| {
+            restore(client, context, &archive, &key, |_, _| ())
+        });
+
+        let (content, metadata) = run(&app2, |client, context| {
+            let client2 = client.clone();
+            let client3 = client.clone();
+
+            context
+                .get_own_container(client)
+                .and_then(move |info| {
+                    let enc_key = info.enc_key().cloned();
+
+                    file_helper::fetch(client2, info, "hello.txt")
+                        .and_then(move |(_, file)| {
+                            file_helper::read(client3, &file, enc_key)
+                                .and_then(|reader| reader.read(0, reader.size()))
+                                .map(move |content| (content, file.user_metadata().to_vec()))
+                        })
+                        .map_err(AppError::from)
+                })
+        });
+        assert_eq!(content, b"hello world".to_vec());
+        assert_eq!(metadata, b"meta".to_vec());
+    }
+}
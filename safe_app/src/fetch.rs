@@ -0,0 +1,208 @@
+// Copyright 2016 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under (1) the MaidSafe.net Commercial License,
+// version 1.0 or later, or (2) The General Public License (GPL), version 3, depending on which
+// licence you accepted on initial access to the Software (the "Licences").
+//
+// By contributing code to the SAFE Network Software, or to this project generally, you agree to be
+// bound by the terms of the MaidSafe Contributor Agreement.  This, along with the Licenses can be
+// found in the root directory of this project at LICENSE, COPYING and CONTRIBUTOR.
+//
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.
+//
+// Please review the Licences for the specific language governing permissions and limitations
+// relating to use of the SAFE Network Software.
+
+//! Fetching content by `safe://` URL. The URL is either a XOR-URL addressing
+//! the data directly, or `safe://[<service>.]<public name>/<path>` resolved
+//! through the public names (DNS). XOR-URLs of `MutableData` with a path are
+//! resolved as NFS directories.
+
+use {AppContext, AppFuture};
+use errors::AppError;
+use futures::Future;
+use routing::{Value, XorName};
+use safe_core::{Client, FutureExt, MDataInfo, immutable_data};
+use safe_core::nfs::{File, file_helper};
+use safe_core::structures::dns;
+use safe_core::xor_url::{XorUrl, split_url};
+use std::collections::BTreeMap;
+
+/// Service of the public name used when the URL doesn't name one.
+pub const DEFAULT_SERVICE: &'static str = "www";
+/// File fetched when the URL addresses a directory without a path.
+pub const DEFAULT_FILE: &'static str = "index.html";
+
+/// Content the URL resolved to.
+#[derive(Debug)]
+pub enum FetchedData {
+    /// Value of `ImmutableData`.
+    ImmutableData {
+        /// Name of the data
+        name: XorName,
+        /// Value of the data
+        content: Vec<u8>,
+    },
+    /// Entries of `MutableData`.
+    MutableData {
+        /// Name of the data
+        name: XorName,
+        /// Type tag of the data
+        type_tag: u64,
+        /// Version of the data
+        version: u64,
+        /// Entries of the data
+        entries: BTreeMap<Vec<u8>, Value>,
+    },
+    /// NFS file.
+    File {
+        /// Metadata of the file
+        file: File,
+        /// Content of the file
+        content: Vec<u8>,
+    },
+}
+
+/// Resolve the URL and fetch the content it addresses.
+pub fn fetch(client: &Client<AppContext>, url: &str) -> Box<AppFuture<FetchedData>> {
+    let (host, path) = split_url(url);
+    let path = path.to_string();
+
+    match XorUrl::decode(url) {
+        Some(XorUrl::ImmutableData(name)) => {
+            immutable_data::get_value(client, &name, None)
+                .map(move |content| FetchedData::ImmutableData { name, content })
+                .map_err(AppError::from)
+                .into_box()
+        }
+        Some(XorUrl::MutableData(name, type_tag)) => {
+            if path.is_empty() {
+                fetch_mdata(client, name, type_tag)
+            } else {
+                fetch_file(client, MDataInfo::new_public(name, type_tag), path)
+            }
+        }
+        None => {
+            let (service, public_name) = match host.find('.') {
+                Some(index) => (&host[..index], &host[index + 1..]),
+                None => (DEFAULT_SERVICE, host),
+            };
+            let client = client.clone();
+
+            dns::lookup(&client, public_name, service)
+                .map_err(AppError::from)
+                .and_then(move |dir| fetch_file(&client, dir, path))
+                .into_box()
+        }
+    }
+}
+
+fn fetch_mdata(
+    client: &Client<AppContext>,
+    name: XorName,
+    type_tag: u64,
+) -> Box<AppFuture<FetchedData>> {
+    client
+        .get_mdata_version(name, type_tag)
+        .join(client.list_mdata_entries(name, type_tag))
+        .map(move |(version, entries)| {
+            FetchedData::MutableData {
+                name,
+                type_tag,
+                version,
+                entries,
+            }
+        })
+        .map_err(AppError::from)
+        .into_box()
+}
+
+fn fetch_file(
+    client: &Client<AppContext>,
+    dir: MDataInfo,
+    path: String,
+) -> Box<AppFuture<FetchedData>> {
+    let path = if path.is_empty() {
+        DEFAULT_FILE.to_string()
+    } else {
+        path
+    };
+    let client2 = client.clone();
+    let encryption_key = dir.enc_key().cloned();
+
+    file_helper::fetch(client.clone(), dir, path)
+        .and_then(move |(_, file)| {
+            file_helper::read(client2, &file, encryption_key)
+                .and_then(|reader| reader.read(0, reader.size()))
+                .map(move |content| FetchedData::File { file, content })
+        })
+        .map_err(AppError::from)
+        .into_box()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use routing::{Action, PermissionSet, User};
+    use safe_core::DIR_TAG;
+    use safe_core::nfs::{Mode, create_dir};
+    use safe_core::utils::generate_random_string;
+    use test_utils::{create_app, run};
+
+    // The file is fetched both by the XOR-URL of its directory and through a
+    // public name.
+    #[test]
+    fn fetch_file() {
+        let app = create_app();
+        let public_name = unwrap!(generate_random_string(10));
+        let public_name2 = public_name.clone();
+
+        let dir = run(&app, move |client, _| {
+            let client2 = client.clone();
+            let client3 = client.clone();
+            let client4 = client.clone();
+
+            let dir = unwrap!(MDataInfo::random_public(DIR_TAG));
+            let dir2 = dir.clone();
+            let dir3 = dir.clone();
+            let perms = btree_map![
+                User::Key(unwrap!(client.public_signing_key())) =>
+                    PermissionSet::new().allow(Action::Insert)
+            ];
+
+            create_dir(client, &dir, btree_map![], perms)
+                .and_then(move |_| {
+                    file_helper::write(client2, File::new(Vec::new()), Mode::Overwrite, None)
+                })
+                .and_then(move |writer| {
+                    writer.write(b"hello").and_then(move |_| writer.close())
+                })
+                .and_then(move |file| file_helper::insert(client3, dir2, DEFAULT_FILE, &file))
+                .map_err(AppError::from)
+                .and_then(move |_| {
+                    let services = btree_map![DEFAULT_SERVICE.to_string() => dir3];
+                    dns::register(&client4, &public_name2, services).map_err(AppError::from)
+                })
+                .map(move |_| dir)
+        });
+
+        let xor_url = XorUrl::MutableData(dir.name, dir.type_tag).encode();
+        let urls = vec![
+            format!("{}/{}", xor_url, DEFAULT_FILE),
+            format!("safe://{}", public_name),
+            format!("safe://{}.{}/{}", DEFAULT_SERVICE, public_name, DEFAULT_FILE),
+        ];
+
+        for url in urls {
+            let content = run(&app, move |client, _| {
+                fetch(client, &url).map(|data| match data {
+                    FetchedData::File { content, .. } => content,
+                    data => panic!("Unexpected {:?}", data),
+                })
+            });
+            assert_eq!(content, b"hello".to_vec());
+        }
+    }
+}
@@ -126,6 +126,39 @@ pub unsafe extern "C" fn access_container_get_container_mdata_info(
     })
 }
 
+/// Retrieve `MDataInfo` of the app's own container (`apps/<app-id>`), which is
+/// created by the authenticator when the app requests it.
+///
+/// Callback parameters: user data, error code, mdata info handle
+#[no_mangle]
+pub unsafe extern "C" fn access_container_get_own_container_mdata_info(
+    app: *const App,
+    user_data: *mut c_void,
+    o_cb: extern "C" fn(user_data: *mut c_void,
+                        result: FfiResult,
+                        mdata_info_h: MDataInfoHandle),
+) {
+    catch_unwind_cb(user_data, o_cb, || {
+        let user_data = OpaqueCtx(user_data);
+
+        (*app).send(move |client, context| {
+            let context = context.clone();
+
+            context
+                .get_own_container(client)
+                .map(move |mdata_info| {
+                    let handle = context.object_cache().insert_mdata_info(mdata_info);
+                    o_cb(user_data.0, FFI_RESULT_OK, handle);
+                })
+                .map_err(move |err| {
+                    call_result_cb!(Err::<(), _>(err), user_data, o_cb);
+                })
+                .into_box()
+                .into()
+        })
+    })
+}
+
 #[cfg(test)]
 mod tests {
     use errors::AppError;
@@ -209,6 +242,27 @@ mod tests {
         })
     }
 
+    // Test getting the info of the app's own container.
+    #[test]
+    fn get_own_container_mdata_info() {
+        let app = create_app_with_access(HashMap::new());
+
+        let md_info_h = unsafe {
+            unwrap!(call_1(|ud, cb| {
+                access_container_get_own_container_mdata_info(&app, ud, cb)
+            }))
+        };
+
+        run_now(&app, move |_, context| {
+            let reg = unwrap!(context.as_registered()).clone();
+            let access_info = reg.access_info.borrow();
+            let expected = &unwrap!(access_info.get(&format!("apps/{}", reg.app_id))).0;
+
+            let info = unwrap!(context.object_cache().get_mdata_info(md_info_h));
+            assert_eq!(*info, *expected);
+        })
+    }
+
     struct PermSet(String, BTreeSet<Permission>);
 
     impl ReprC for PermSet {
@@ -0,0 +1,234 @@
+// Copyright 2017 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under (1) the MaidSafe.net Commercial License,
+// version 1.0 or later, or (2) The General Public License (GPL), version 3, depending on which
+// licence you accepted on initial access to the Software (the "Licences").
+//
+// By contributing code to the SAFE Network Software, or to this project generally, you agree to be
+// bound by the terms of the MaidSafe Contributor Agreement.  This, along with the Licenses can be
+// found in the root directory of this project at LICENSE, COPYING and CONTRIBUTOR.
+//
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.
+//
+// Please review the Licences for the specific language governing permissions and limitations
+// relating to use of the SAFE Network Software.
+
+use App;
+use errors::AppError;
+use ffi::helper::{get_permissions, send_with_mdata_info};
+use ffi_utils::{FFI_RESULT_OK, FfiResult, OpaqueCtx, SafePtr, catch_unwind_cb,
+                vec_clone_from_raw_parts};
+use futures::Future;
+use object_cache::{MDataInfoHandle, MDataPermissionsHandle};
+use safe_core::FutureExt;
+use safe_core::structures::AppendLog;
+use std::os::raw::c_void;
+
+/// Item read from an append-only log.
+#[repr(C)]
+pub struct AppendLogItem {
+    /// Pointer to the item content
+    pub data_ptr: *const u8,
+    /// Length of the item content
+    pub data_len: usize,
+}
+
+/// Create new empty append-only log rooted at the mutable data with the given
+/// info. `permissions_h` is applied to the root and to all the segments the
+/// log gets extended with, so it has to allow inserts and updates to everyone
+/// who should be able to append.
+///
+/// Callback parameters: user data, error code
+#[no_mangle]
+pub unsafe extern "C" fn append_log_create(
+    app: *const App,
+    info_h: MDataInfoHandle,
+    permissions_h: MDataPermissionsHandle,
+    user_data: *mut c_void,
+    o_cb: extern "C" fn(user_data: *mut c_void, result: FfiResult),
+) {
+    catch_unwind_cb(user_data, o_cb, || {
+        send_with_mdata_info(app, info_h, user_data, o_cb, move |client, context, info| {
+            let perms = match get_permissions(context.object_cache(), permissions_h) {
+                Ok(perms) => perms,
+                Err(err) => return err!(err),
+            };
+
+            AppendLog::create(client, info.clone(), perms)
+                .map(|_| ())
+                .map_err(AppError::from)
+                .into_box()
+        })
+    })
+}
+
+/// Append the item to the end of the log.
+///
+/// Callback parameters: user data, error code, index of the item in the log
+#[no_mangle]
+pub unsafe extern "C" fn append_log_append(
+    app: *const App,
+    info_h: MDataInfoHandle,
+    data_ptr: *const u8,
+    data_len: usize,
+    user_data: *mut c_void,
+    o_cb: extern "C" fn(user_data: *mut c_void, result: FfiResult, index: u64),
+) {
+    catch_unwind_cb(user_data, o_cb, || {
+        let data = vec_clone_from_raw_parts(data_ptr, data_len);
+
+        send_with_mdata_info(app, info_h, user_data, o_cb, move |client, _, info| {
+            let client = client.clone();
+            AppendLog::open(&client, info.clone()).and_then(move |log| log.append(&client, data))
+        })
+    })
+}
+
+/// Get the number of items in the log.
+///
+/// Callback parameters: user data, error code, number of items
+#[no_mangle]
+pub unsafe extern "C" fn append_log_len(
+    app: *const App,
+    info_h: MDataInfoHandle,
+    user_data: *mut c_void,
+    o_cb: extern "C" fn(user_data: *mut c_void, result: FfiResult, len: u64),
+) {
+    catch_unwind_cb(user_data, o_cb, || {
+        send_with_mdata_info(app, info_h, user_data, o_cb, |client, _, info| {
+            AppendLog::new(info.clone(), Default::default()).len(client)
+        })
+    })
+}
+
+/// Read all the items of the log starting at `index`.
+///
+/// Callback parameters: user data, error code, items array, array length
+#[no_mangle]
+pub unsafe extern "C" fn append_log_read_from(
+    app: *const App,
+    info_h: MDataInfoHandle,
+    index: u64,
+    user_data: *mut c_void,
+    o_cb: extern "C" fn(user_data: *mut c_void,
+                        result: FfiResult,
+                        items_ptr: *const AppendLogItem,
+                        items_len: usize),
+) {
+    catch_unwind_cb(user_data, o_cb, || {
+        let user_data = OpaqueCtx(user_data);
+
+        (*app).send(move |client, context| {
+            let info = try_cb!(
+                context.object_cache().get_mdata_info(info_h),
+                user_data,
+                o_cb
+            );
+
+            AppendLog::new(info.clone(), Default::default())
+                .read_from(client, index)
+                .map(move |items| {
+                    let ffi_items: Vec<_> = items
+                        .iter()
+                        .map(|item| {
+                            AppendLogItem {
+                                data_ptr: item.as_safe_ptr(),
+                                data_len: item.len(),
+                            }
+                        })
+                        .collect();
+
+                    o_cb(
+                        user_data.0,
+                        FFI_RESULT_OK,
+                        ffi_items.as_safe_ptr(),
+                        ffi_items.len(),
+                    );
+                })
+                .map_err(AppError::from)
+                .map_err(move |err| {
+                    call_result_cb!(Err::<(), _>(err), user_data, o_cb);
+                })
+                .into_box()
+                .into()
+        })
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ffi::mutable_data::permissions::{MDataAction, USER_ANYONE, mdata_permission_set_allow,
+                                         mdata_permission_set_new, mdata_permissions_insert,
+                                         mdata_permissions_new};
+    use ffi_utils::test_utils::{call_0, call_1, send_via_user_data, sender_as_user_data};
+    use safe_core::{DIR_TAG, MDataInfo};
+    use std::slice;
+    use std::sync::mpsc;
+    use test_utils::{create_app, run_now};
+
+    // Items appended through the FFI can be read back from a given index.
+    #[test]
+    fn append_and_read() {
+        let app = create_app();
+
+        let info = unwrap!(MDataInfo::random_public(DIR_TAG));
+        let info_h = run_now(&app, move |_, context| {
+            context.object_cache().insert_mdata_info(info)
+        });
+
+        let perms_h = unsafe {
+            let set_h = unwrap!(call_1(|ud, cb| mdata_permission_set_new(&app, ud, cb)));
+            unwrap!(call_0(|ud, cb| {
+                mdata_permission_set_allow(&app, set_h, MDataAction::Insert, ud, cb)
+            }));
+            unwrap!(call_0(|ud, cb| {
+                mdata_permission_set_allow(&app, set_h, MDataAction::Update, ud, cb)
+            }));
+
+            let perms_h = unwrap!(call_1(|ud, cb| mdata_permissions_new(&app, ud, cb)));
+            unwrap!(call_0(|ud, cb| {
+                mdata_permissions_insert(&app, perms_h, USER_ANYONE, set_h, ud, cb)
+            }));
+            perms_h
+        };
+
+        unsafe {
+            unwrap!(call_0(|ud, cb| append_log_create(&app, info_h, perms_h, ud, cb)));
+        }
+
+        for (expected, item) in [b"first", b"other"].iter().enumerate() {
+            let index: u64 = unsafe {
+                unwrap!(call_1(|ud, cb| {
+                    append_log_append(&app, info_h, item.as_ptr(), item.len(), ud, cb)
+                }))
+            };
+            assert_eq!(index, expected as u64);
+        }
+
+        let len: u64 = unsafe { unwrap!(call_1(|ud, cb| append_log_len(&app, info_h, ud, cb))) };
+        assert_eq!(len, 2);
+
+        let (tx, rx) = mpsc::channel::<Vec<Vec<u8>>>();
+        unsafe { append_log_read_from(&app, info_h, 1, sender_as_user_data(&tx), read_cb) };
+        assert_eq!(unwrap!(rx.recv()), vec![b"other".to_vec()]);
+
+        extern "C" fn read_cb(
+            user_data: *mut c_void,
+            res: FfiResult,
+            items_ptr: *const AppendLogItem,
+            items_len: usize,
+        ) {
+            assert_eq!(res.error_code, 0);
+            unsafe {
+                let items = slice::from_raw_parts(items_ptr, items_len)
+                    .iter()
+                    .map(|item| vec_clone_from_raw_parts(item.data_ptr, item.data_len))
+                    .collect::<Vec<_>>();
+                send_via_user_data(user_data, items);
+            }
+        }
+    }
+}
@@ -0,0 +1,282 @@
+// Copyright 2017 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under (1) the MaidSafe.net Commercial License,
+// version 1.0 or later, or (2) The General Public License (GPL), version 3, depending on which
+// licence you accepted on initial access to the Software (the "Licences").
+//
+// By contributing code to the SAFE Network Software, or to this project generally, you agree to be
+// bound by the terms of the MaidSafe Contributor Agreement.  This, along with the Licenses can be
+// found in the root directory of this project at LICENSE, COPYING and CONTRIBUTOR.
+//
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.
+//
+// Please review the Licences for the specific language governing permissions and limitations
+// relating to use of the SAFE Network Software.
+
+//! FFI for the experimental `AppendableData` type.
+
+use App;
+use errors::AppError;
+use ffi::helper::send_sync;
+use ffi_utils::{FFI_RESULT_OK, FfiResult, OpaqueCtx, SafePtr, catch_unwind_cb,
+                vec_clone_from_raw_parts};
+use futures::Future;
+use object_cache::{ADataHandle, SignKeyHandle};
+use routing::{ClientError, XorName};
+use safe_core::{CoreError, FutureExt};
+use safe_core::appendable_data::{AppendableData, AppendedEntry, Filter};
+use safe_core::ffi::arrays::XorNameArray;
+use std::os::raw::c_void;
+
+/// Create new `AppendableData` owned by the user and put it on the network.
+/// Anyone is allowed to append to it.
+///
+/// Callback parameters: user data, error code
+#[no_mangle]
+pub unsafe extern "C" fn adata_put(
+    app: *const App,
+    name: *const XorNameArray,
+    type_tag: u64,
+    user_data: *mut c_void,
+    o_cb: extern "C" fn(user_data: *mut c_void, result: FfiResult),
+) {
+    catch_unwind_cb(user_data, o_cb, || {
+        let user_data = OpaqueCtx(user_data);
+        let name = XorName(*name);
+
+        (*app).send(move |client, _| {
+            let owner = try_cb!(
+                client.owner_key().map_err(AppError::from),
+                user_data,
+                o_cb
+            );
+            let data = try_cb!(
+                AppendableData::new(name, type_tag, btree_set![owner], Filter::default())
+                    .map_err(|err| AppError::from(CoreError::from(err))),
+                user_data,
+                o_cb
+            );
+
+            client
+                .put_adata(data)
+                .map_err(AppError::from)
+                .then(move |res| {
+                    call_result_cb!(res, user_data, o_cb);
+                    Ok(())
+                })
+                .into_box()
+                .into()
+        })
+    })
+}
+
+/// Fetch `AppendableData` from the network.
+///
+/// Callback parameters: user data, error code, appendable data handle
+#[no_mangle]
+pub unsafe extern "C" fn adata_fetch(
+    app: *const App,
+    name: *const XorNameArray,
+    type_tag: u64,
+    user_data: *mut c_void,
+    o_cb: extern "C" fn(user_data: *mut c_void,
+                        result: FfiResult,
+                        adata_h: ADataHandle),
+) {
+    catch_unwind_cb(user_data, o_cb, || {
+        let user_data = OpaqueCtx(user_data);
+        let name = XorName(*name);
+
+        (*app).send(move |client, context| {
+            let context = context.clone();
+
+            client
+                .get_adata(name, type_tag)
+                .map(move |data| {
+                    let handle = context.object_cache().insert_adata(data);
+                    o_cb(user_data.0, FFI_RESULT_OK, handle);
+                })
+                .map_err(AppError::from)
+                .map_err(move |err| {
+                    call_result_cb!(Err::<(), _>(err), user_data, o_cb);
+                })
+                .into_box()
+                .into()
+        })
+    })
+}
+
+/// Append an entry to `AppendableData`.
+///
+/// Callback parameters: user data, error code
+#[no_mangle]
+pub unsafe extern "C" fn adata_append(
+    app: *const App,
+    name: *const XorNameArray,
+    type_tag: u64,
+    content_ptr: *const u8,
+    content_len: usize,
+    user_data: *mut c_void,
+    o_cb: extern "C" fn(user_data: *mut c_void, result: FfiResult),
+) {
+    catch_unwind_cb(user_data, o_cb, || {
+        let user_data = OpaqueCtx(user_data);
+        let name = XorName(*name);
+        let content = vec_clone_from_raw_parts(content_ptr, content_len);
+
+        (*app).send(move |client, _| {
+            client
+                .append_adata(name, type_tag, content)
+                .map_err(AppError::from)
+                .then(move |res| {
+                    call_result_cb!(res, user_data, o_cb);
+                    Ok(())
+                })
+                .into_box()
+                .into()
+        })
+    })
+}
+
+/// Get the number of entries in the fetched `AppendableData`.
+///
+/// Callback parameters: user data, error code, number of entries
+#[no_mangle]
+pub unsafe extern "C" fn adata_num_entries(
+    app: *const App,
+    adata_h: ADataHandle,
+    user_data: *mut c_void,
+    o_cb: extern "C" fn(user_data: *mut c_void, result: FfiResult, len: usize),
+) {
+    catch_unwind_cb(user_data, o_cb, || {
+        send_sync(app, user_data, o_cb, move |_, context| {
+            let data = context.object_cache().get_adata(adata_h)?;
+            Ok(data.entries().len())
+        })
+    })
+}
+
+/// Get the key of the author of the entry at the given index.
+///
+/// Callback parameters: user data, error code, sign key handle
+#[no_mangle]
+pub unsafe extern "C" fn adata_entry_author(
+    app: *const App,
+    adata_h: ADataHandle,
+    index: usize,
+    user_data: *mut c_void,
+    o_cb: extern "C" fn(user_data: *mut c_void,
+                        result: FfiResult,
+                        author_h: SignKeyHandle),
+) {
+    catch_unwind_cb(user_data, o_cb, || {
+        send_sync(app, user_data, o_cb, move |_, context| {
+            let author = entry(&*context.object_cache().get_adata(adata_h)?, index)?.author;
+            Ok(context.object_cache().insert_sign_key(author))
+        })
+    })
+}
+
+/// Get the content of the entry at the given index.
+///
+/// Callback parameters: user data, error code, content, content length
+#[no_mangle]
+pub unsafe extern "C" fn adata_entry_content(
+    app: *const App,
+    adata_h: ADataHandle,
+    index: usize,
+    user_data: *mut c_void,
+    o_cb: extern "C" fn(user_data: *mut c_void,
+                        result: FfiResult,
+                        content_ptr: *const u8,
+                        content_len: usize),
+) {
+    catch_unwind_cb(user_data, o_cb, || {
+        let user_data = OpaqueCtx(user_data);
+
+        (*app).send(move |_, context| {
+            let data = try_cb!(context.object_cache().get_adata(adata_h), user_data, o_cb);
+            let entry = try_cb!(entry(&*data, index), user_data, o_cb);
+            o_cb(
+                user_data.0,
+                FFI_RESULT_OK,
+                entry.content.as_safe_ptr(),
+                entry.content.len(),
+            );
+            None
+        })
+    })
+}
+
+/// Free `AppendableData` handle.
+///
+/// Callback parameters: user data, error code
+#[no_mangle]
+pub unsafe extern "C" fn adata_free(
+    app: *const App,
+    adata_h: ADataHandle,
+    user_data: *mut c_void,
+    o_cb: extern "C" fn(user_data: *mut c_void, result: FfiResult),
+) {
+    catch_unwind_cb(user_data, o_cb, || {
+        send_sync(app, user_data, o_cb, move |_, context| {
+            let _ = context.object_cache().remove_adata(adata_h)?;
+            Ok(())
+        })
+    })
+}
+
+fn entry(data: &AppendableData, index: usize) -> Result<AppendedEntry, AppError> {
+    data.entries().get(index).cloned().ok_or_else(|| {
+        AppError::from(CoreError::from(ClientError::NoSuchEntry))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ffi::crypto::app_pub_sign_key;
+    use ffi_utils::test_utils::{call_0, call_1, call_vec_u8};
+    use rand;
+    use test_utils::{create_app, run_now};
+
+    // Append to `AppendableData` and read the entries back.
+    #[test]
+    fn put_append_fetch() {
+        let app = create_app();
+        let name: XorNameArray = rand::random();
+        let tag = 15_001;
+        let content = b"hello".to_vec();
+
+        unsafe {
+            unwrap!(call_0(|ud, cb| adata_put(&app, &name, tag, ud, cb)));
+            unwrap!(call_0(|ud, cb| {
+                adata_append(&app, &name, tag, content.as_ptr(), content.len(), ud, cb)
+            }));
+
+            let adata_h = unwrap!(call_1(|ud, cb| adata_fetch(&app, &name, tag, ud, cb)));
+            let len: usize = unwrap!(call_1(|ud, cb| adata_num_entries(&app, adata_h, ud, cb)));
+            assert_eq!(len, 1);
+
+            let fetched = unwrap!(call_vec_u8(
+                |ud, cb| adata_entry_content(&app, adata_h, 0, ud, cb),
+            ));
+            assert_eq!(fetched, content);
+
+            let author_h = unwrap!(call_1(|ud, cb| adata_entry_author(&app, adata_h, 0, ud, cb)));
+            let app_key_h = unwrap!(call_1(|ud, cb| app_pub_sign_key(&app, ud, cb)));
+            run_now(&app, move |_, context| {
+                let author = unwrap!(context.object_cache().get_sign_key(author_h));
+                let app_key = unwrap!(context.object_cache().get_sign_key(app_key_h));
+                assert_eq!(*author, *app_key);
+            });
+
+            let res = call_vec_u8(|ud, cb| adata_entry_content(&app, adata_h, 1, ud, cb));
+            assert!(res.is_err());
+
+            unwrap!(call_0(|ud, cb| adata_free(&app, adata_h, ud, cb)));
+        }
+    }
+}
@@ -0,0 +1,36 @@
+// Copyright 2017 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under (1) the MaidSafe.net Commercial License,
+// version 1.0 or later, or (2) The General Public License (GPL), version 3, depending on which
+// licence you accepted on initial access to the Software (the "Licences").
+//
+// By contributing code to the SAFE Network Software, or to this project generally, you agree to be
+// bound by the terms of the MaidSafe Contributor Agreement.  This, along with the Licenses can be
+// found in the root directory of this project at LICENSE, COPYING and CONTRIBUTOR.
+//
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.
+//
+// Please review the Licences for the specific language governing permissions and limitations
+// relating to use of the SAFE Network Software.
+
+//! Cancellation of in-flight operations. The `*_cancellable` functions write an
+//! operation id into their `o_op_id` parameter before returning. Passing that id
+//! to `operation_cancel` aborts the operation, and its callback then gets called
+//! with `ERR_OPERATION_CANCELLED` instead of the result.
+
+use App;
+use errors::AppError;
+use ffi_utils::catch_unwind_error_code;
+use operations::OperationId;
+
+/// Cancel an in-flight operation. Its pending future is dropped and the
+/// callback is called with `ERR_OPERATION_CANCELLED`.
+///
+/// Returns `ERR_INVALID_OPERATION_ID` if the operation is unknown or has
+/// already completed.
+#[no_mangle]
+pub unsafe extern "C" fn operation_cancel(app: *const App, op_id: OperationId) -> i32 {
+    catch_unwind_error_code(|| -> Result<(), AppError> { (*app).cancel(op_id) })
+}
@@ -0,0 +1,180 @@
+// Copyright 2017 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under (1) the MaidSafe.net Commercial License,
+// version 1.0 or later, or (2) The General Public License (GPL), version 3, depending on which
+// licence you accepted on initial access to the Software (the "Licences").
+//
+// By contributing code to the SAFE Network Software, or to this project generally, you agree to be
+// bound by the terms of the MaidSafe Contributor Agreement.  This, along with the Licenses can be
+// found in the root directory of this project at LICENSE, COPYING and CONTRIBUTOR.
+//
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.
+//
+// Please review the Licences for the specific language governing permissions and limitations
+// relating to use of the SAFE Network Software.
+
+use App;
+use errors::AppError;
+use ffi::helper::send_with_mdata_info;
+use ffi_utils::{FFI_RESULT_OK, FfiResult, OpaqueCtx, SafePtr, catch_unwind_cb,
+                vec_clone_from_raw_parts};
+use futures::Future;
+use object_cache::{EncryptPubKeyHandle, MDataInfoHandle};
+use safe_core::FutureExt;
+use safe_core::structures::Channel;
+use std::os::raw::c_void;
+
+/// Message received over a channel.
+#[repr(C)]
+pub struct ChannelMessage {
+    /// Pointer to the decrypted message content
+    pub data_ptr: *const u8,
+    /// Length of the message content
+    pub data_len: usize,
+}
+
+/// Create new channel rooted at the mutable data with the given info. The info
+/// should be public, as the messages are encrypted individually.
+///
+/// Callback parameters: user data, error code
+#[no_mangle]
+pub unsafe extern "C" fn channel_create(
+    app: *const App,
+    info_h: MDataInfoHandle,
+    user_data: *mut c_void,
+    o_cb: extern "C" fn(user_data: *mut c_void, result: FfiResult),
+) {
+    catch_unwind_cb(user_data, o_cb, || {
+        send_with_mdata_info(app, info_h, user_data, o_cb, |client, _, info| {
+            Channel::create(client, info.clone()).map(|_| ())
+        })
+    })
+}
+
+/// Send message to the owner of the given public encryption key.
+///
+/// Callback parameters: user data, error code, position of the message in the channel
+#[no_mangle]
+pub unsafe extern "C" fn channel_send(
+    app: *const App,
+    info_h: MDataInfoHandle,
+    recipient_h: EncryptPubKeyHandle,
+    data_ptr: *const u8,
+    data_len: usize,
+    user_data: *mut c_void,
+    o_cb: extern "C" fn(user_data: *mut c_void, result: FfiResult, index: u64),
+) {
+    catch_unwind_cb(user_data, o_cb, || {
+        let data = vec_clone_from_raw_parts(data_ptr, data_len);
+
+        send_with_mdata_info(app, info_h, user_data, o_cb, move |client, context, info| {
+            let recipient = match context.object_cache().get_encrypt_key(recipient_h) {
+                Ok(key) => *key,
+                Err(err) => return err!(err),
+            };
+
+            Channel::new(info.clone())
+                .send(client, &recipient, &data)
+                .map_err(AppError::from)
+                .into_box()
+        })
+    })
+}
+
+/// Fetch messages addressed to this app which were sent at or after `cursor`.
+/// Pass the returned cursor to the next call to receive only newer messages.
+///
+/// Callback parameters: user data, error code, messages array, array length,
+/// next cursor
+#[no_mangle]
+pub unsafe extern "C" fn channel_poll(
+    app: *const App,
+    info_h: MDataInfoHandle,
+    cursor: u64,
+    user_data: *mut c_void,
+    o_cb: extern "C" fn(user_data: *mut c_void,
+                        result: FfiResult,
+                        messages_ptr: *const ChannelMessage,
+                        messages_len: usize,
+                        next_cursor: u64),
+) {
+    catch_unwind_cb(user_data, o_cb, || {
+        let user_data = OpaqueCtx(user_data);
+
+        (*app).send(move |client, context| {
+            let info = try_cb!(
+                context.object_cache().get_mdata_info(info_h),
+                user_data,
+                o_cb
+            );
+            let (pk, sk) = try_cb!(
+                client.encryption_keypair().map_err(AppError::from),
+                user_data,
+                o_cb
+            );
+
+            Channel::new(info.clone())
+                .poll(client, pk, sk, cursor)
+                .map(move |(messages, next_cursor)| {
+                    let ffi_messages: Vec<_> = messages
+                        .iter()
+                        .map(|message| {
+                            ChannelMessage {
+                                data_ptr: message.as_safe_ptr(),
+                                data_len: message.len(),
+                            }
+                        })
+                        .collect();
+
+                    o_cb(
+                        user_data.0,
+                        FFI_RESULT_OK,
+                        ffi_messages.as_safe_ptr(),
+                        ffi_messages.len(),
+                        next_cursor,
+                    );
+                })
+                .map_err(AppError::from)
+                .map_err(move |err| {
+                    call_result_cb!(Err::<(), _>(err), user_data, o_cb);
+                })
+                .into_box()
+                .into()
+        })
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ffi::crypto::app_pub_enc_key;
+    use ffi_utils::test_utils::{call_0, call_1};
+    use safe_core::{DIR_TAG, MDataInfo};
+    use test_utils::{create_app, run_now};
+
+    // Messages can be sent to the app's own encryption key.
+    #[test]
+    fn send_and_poll() {
+        let app = create_app();
+
+        let info = unwrap!(MDataInfo::random_public(DIR_TAG));
+        let info_h = run_now(&app, move |_, context| {
+            context.object_cache().insert_mdata_info(info)
+        });
+
+        unsafe {
+            unwrap!(call_0(|ud, cb| channel_create(&app, info_h, ud, cb)));
+        }
+
+        let pk_h = unsafe { unwrap!(call_1(|ud, cb| app_pub_enc_key(&app, ud, cb))) };
+        let msg = b"hello".to_vec();
+        let index: u64 = unsafe {
+            unwrap!(call_1(|ud, cb| {
+                channel_send(&app, info_h, pk_h, msg.as_ptr(), msg.len(), ud, cb)
+            }))
+        };
+        assert_eq!(index, 0);
+    }
+}
@@ -20,10 +20,11 @@ use errors::AppError;
 use ffi::helper::send_sync;
 use ffi_utils::{FFI_RESULT_OK, FfiResult, OpaqueCtx, catch_unwind_cb, vec_clone_from_raw_parts};
 use maidsafe_utilities::serialisation::{deserialise, serialise};
-use object_cache::{EncryptPubKeyHandle, EncryptSecKeyHandle, SignKeyHandle};
+use object_cache::{EncryptPubKeyHandle, EncryptSecKeyHandle, SignKeyHandle, SignSecKeyHandle};
 use rust_sodium::crypto::{box_, sealedbox, sign};
-use safe_core::crypto::shared_box;
-use safe_core::ffi::arrays::{AsymNonce, AsymPublicKey, AsymSecretKey, SignPublicKey};
+use safe_core::crypto::{shared_box, shared_sign};
+use safe_core::ffi::arrays::{AsymNonce, AsymPublicKey, AsymSecretKey, SignPublicKey,
+                             SignSecretKey, SymSecretKey};
 use std::os::raw::c_void;
 use std::slice;
 use tiny_keccak::sha3_256;
@@ -105,6 +106,263 @@ pub unsafe extern "C" fn sign_key_free(
     })
 }
 
+/// Get the secret signing key of the app.
+///
+/// Callback parameters: user data, error code, secret sign key handle
+#[no_mangle]
+pub unsafe extern "C" fn app_sec_sign_key(
+    app: *const App,
+    user_data: *mut c_void,
+    o_cb: extern "C" fn(user_data: *mut c_void,
+                        result: FfiResult,
+                        handle: SignSecKeyHandle),
+) {
+    catch_unwind_cb(user_data, o_cb, || {
+        send_sync(app, user_data, o_cb, move |client, context| {
+            let key = client.secret_signing_key()?;
+            Ok(context.object_cache().insert_sign_sec_key(key))
+        })
+    })
+}
+
+/// Generate a new signing key pair (public & secret key).
+///
+/// Callback parameters: user data, error code, public sign key handle, secret sign key handle
+#[no_mangle]
+pub unsafe extern "C" fn sign_generate_key_pair(
+    app: *const App,
+    user_data: *mut c_void,
+    o_cb: extern "C" fn(user_data: *mut c_void,
+                        result: FfiResult,
+                        pk_h: SignKeyHandle,
+                        sk_h: SignSecKeyHandle),
+) {
+    catch_unwind_cb(user_data, o_cb, || {
+        let (pk, sk) = shared_sign::gen_keypair();
+        let user_data = OpaqueCtx(user_data);
+
+        (*app).send(move |_, context| {
+            let pk_h = context.object_cache().insert_sign_key(pk);
+            let sk_h = context.object_cache().insert_sign_sec_key(sk);
+
+            o_cb(user_data.0, FFI_RESULT_OK, pk_h, sk_h);
+
+            None
+        })
+    })
+}
+
+/// Create new secret signing key from raw array.
+///
+/// Callback parameters: user data, error code, secret sign key handle
+#[no_mangle]
+pub unsafe extern "C" fn sign_sec_key_new(
+    app: *const App,
+    data: *const SignSecretKey,
+    user_data: *mut c_void,
+    o_cb: extern "C" fn(user_data: *mut c_void,
+                        result: FfiResult,
+                        handle: SignSecKeyHandle),
+) {
+    catch_unwind_cb(user_data, o_cb, || {
+        let key = shared_sign::SecretKey::from_raw(&*data);
+        send_sync(app, user_data, o_cb, move |_, context| {
+            Ok(context.object_cache().insert_sign_sec_key(key))
+        })
+    })
+}
+
+/// Retrieve the secret signing key as raw array.
+///
+/// Callback parameters: user data, error code, secret sign key
+#[no_mangle]
+pub unsafe extern "C" fn sign_sec_key_get(
+    app: *const App,
+    handle: SignSecKeyHandle,
+    user_data: *mut c_void,
+    o_cb: extern "C" fn(user_data: *mut c_void,
+                        result: FfiResult,
+                        sec_sign_key: *const SignSecretKey),
+) {
+    catch_unwind_cb(user_data, o_cb, || {
+        send_sync(app, user_data, o_cb, move |_, context| {
+            let key = context.object_cache().get_sign_sec_key(handle)?;
+            Ok(&key.0)
+        })
+    })
+}
+
+/// Free secret signing key from memory.
+///
+/// Callback parameters: user data, error code
+#[no_mangle]
+pub unsafe extern "C" fn sign_sec_key_free(
+    app: *const App,
+    handle: SignSecKeyHandle,
+    user_data: *mut c_void,
+    o_cb: extern "C" fn(user_data: *mut c_void, result: FfiResult),
+) {
+    catch_unwind_cb(user_data, o_cb, || {
+        send_sync(app, user_data, o_cb, move |_, context| {
+            let _ = context.object_cache().remove_sign_sec_key(handle)?;
+            Ok(())
+        })
+    })
+}
+
+/// Signs arbitrary data using a given secret sign key. The result is the
+/// signature followed by the data.
+///
+/// Callback parameters: user data, error code, signed data vector, vector size
+#[no_mangle]
+pub unsafe extern "C" fn sign(
+    app: *const App,
+    data: *const u8,
+    len: usize,
+    sign_sk_h: SignSecKeyHandle,
+    user_data: *mut c_void,
+    o_cb: extern "C" fn(user_data: *mut c_void,
+                        result: FfiResult,
+                        signed_data_ptr: *const u8,
+                        signed_data_len: usize),
+) {
+    catch_unwind_cb(user_data, o_cb, || {
+        let user_data = OpaqueCtx(user_data);
+        let data = vec_clone_from_raw_parts(data, len);
+
+        (*app).send(move |_, context| {
+            let sk = try_cb!(
+                context.object_cache().get_sign_sec_key(sign_sk_h),
+                user_data,
+                o_cb
+            );
+
+            let signed_data = sign::sign(&data, &sk);
+            o_cb(
+                user_data.0,
+                FFI_RESULT_OK,
+                signed_data.as_ptr(),
+                signed_data.len(),
+            );
+
+            None
+        })
+    })
+}
+
+/// Verifies data signed with `sign` using the public sign key of the signer.
+/// Fails with `ERR_INVALID_SIGNATURE` if the signature doesn't match.
+///
+/// Callback parameters: user data, error code, verified data vector, vector size
+#[no_mangle]
+pub unsafe extern "C" fn verify(
+    app: *const App,
+    signed_data: *const u8,
+    len: usize,
+    sign_pk_h: SignKeyHandle,
+    user_data: *mut c_void,
+    o_cb: extern "C" fn(user_data: *mut c_void,
+                        result: FfiResult,
+                        verified_data_ptr: *const u8,
+                        verified_data_len: usize),
+) {
+    catch_unwind_cb(user_data, o_cb, || {
+        let user_data = OpaqueCtx(user_data);
+        let signed_data = vec_clone_from_raw_parts(signed_data, len);
+
+        (*app).send(move |_, context| {
+            let pk = try_cb!(context.object_cache().get_sign_key(sign_pk_h), user_data, o_cb);
+
+            let verified_data = try_cb!(sign::verify(&signed_data, &pk)
+                                            .map_err(|()| AppError::InvalidSignature),
+                                        user_data,
+                                        o_cb);
+            o_cb(
+                user_data.0,
+                FFI_RESULT_OK,
+                verified_data.as_ptr(),
+                verified_data.len(),
+            );
+
+            None
+        })
+    })
+}
+
+/// Creates a detached signature of arbitrary data using a given secret sign
+/// key.
+///
+/// Callback parameters: user data, error code, signature vector, vector size
+#[no_mangle]
+pub unsafe extern "C" fn sign_detached(
+    app: *const App,
+    data: *const u8,
+    len: usize,
+    sign_sk_h: SignSecKeyHandle,
+    user_data: *mut c_void,
+    o_cb: extern "C" fn(user_data: *mut c_void,
+                        result: FfiResult,
+                        signature_ptr: *const u8,
+                        signature_len: usize),
+) {
+    catch_unwind_cb(user_data, o_cb, || {
+        let user_data = OpaqueCtx(user_data);
+        let data = vec_clone_from_raw_parts(data, len);
+
+        (*app).send(move |_, context| {
+            let sk = try_cb!(
+                context.object_cache().get_sign_sec_key(sign_sk_h),
+                user_data,
+                o_cb
+            );
+
+            let signature = sign::sign_detached(&data, &sk);
+            o_cb(
+                user_data.0,
+                FFI_RESULT_OK,
+                signature.0.as_ptr(),
+                signature.0.len(),
+            );
+
+            None
+        })
+    })
+}
+
+/// Verifies a detached signature of arbitrary data using the public sign key
+/// of the signer. Fails with `ERR_INVALID_SIGNATURE` if the signature doesn't
+/// match.
+///
+/// Callback parameters: user data, error code
+#[no_mangle]
+pub unsafe extern "C" fn verify_detached(
+    app: *const App,
+    data: *const u8,
+    len: usize,
+    signature: *const u8,
+    signature_len: usize,
+    sign_pk_h: SignKeyHandle,
+    user_data: *mut c_void,
+    o_cb: extern "C" fn(user_data: *mut c_void, result: FfiResult),
+) {
+    catch_unwind_cb(user_data, o_cb, || {
+        let data = vec_clone_from_raw_parts(data, len);
+        let signature = slice::from_raw_parts(signature, signature_len);
+        let signature = sign::Signature::from_slice(signature).ok_or(
+            AppError::InvalidSignature,
+        )?;
+
+        send_sync(app, user_data, o_cb, move |_, context| {
+            let pk = context.object_cache().get_sign_key(sign_pk_h)?;
+            if sign::verify_detached(&signature, &data, &pk) {
+                Ok(())
+            } else {
+                Err(AppError::InvalidSignature)
+            }
+        })
+    })
+}
+
 /// Get the public encryption key of the app.
 ///
 /// Callback parameters: user data, error code, public encrypt key handle
@@ -447,6 +705,39 @@ pub unsafe extern "C" fn decrypt_sealed_box(
     })
 }
 
+/// Derives a symmetric key for the given context and index from the keys of
+/// the app. The same key is always derived for the same context and index,
+/// so it doesn't need to be stored.
+///
+/// Callback parameters: user data, error code, secret symmetric key
+#[no_mangle]
+pub unsafe extern "C" fn app_derive_subkey(
+    app: *const App,
+    context: *const u8,
+    context_len: usize,
+    index: u64,
+    user_data: *mut c_void,
+    o_cb: extern "C" fn(user_data: *mut c_void,
+                        result: FfiResult,
+                        key: *const SymSecretKey),
+) {
+    catch_unwind_cb(user_data, o_cb, || {
+        let user_data = OpaqueCtx(user_data);
+        let context = vec_clone_from_raw_parts(context, context_len);
+
+        (*app).send(move |client, _| {
+            let key = try_cb!(
+                client.derive_subkey(&context, index).map_err(AppError::from),
+                user_data,
+                o_cb
+            );
+            o_cb(user_data.0, FFI_RESULT_OK, &key.0);
+
+            None
+        })
+    })
+}
+
 /// Returns a sha3 hash for a given data.
 ///
 /// Callback parameters: user data, error code, hash vector, vector size
@@ -491,9 +782,11 @@ pub unsafe extern "C" fn generate_nonce(
 #[cfg(test)]
 mod tests {
     use super::*;
+    use ffi_utils::ErrorCode;
     use ffi_utils::test_utils::{call_0, call_1, call_2, call_vec_u8};
     use rust_sodium::crypto::box_;
-    use safe_core::arrays::{AsymNonce, AsymPublicKey, SignPublicKey};
+    use safe_core::arrays::{AsymNonce, AsymPublicKey, SignPublicKey, SignSecretKey,
+                            SymSecretKey};
     use test_utils::{create_app, run_now};
 
     // Test encrypting and decrypting messages between apps.
@@ -596,6 +889,161 @@ mod tests {
         assert_eq!(&decrypted, data);
     }
 
+    // Test signing messages in one app and verifying them in another.
+    #[test]
+    fn sign_verify() {
+        let app1 = create_app();
+        let app2 = create_app();
+
+        let (app1_pk1_h, app1_sk1_h): (SignKeyHandle, SignSecKeyHandle) =
+            unsafe { unwrap!(call_2(|ud, cb| sign_generate_key_pair(&app1, ud, cb))) };
+
+        // Copying app1 pubkey to app2 object cache
+        let pk1_raw: SignPublicKey =
+            unsafe { unwrap!(call_1(|ud, cb| sign_key_get(&app1, app1_pk1_h, ud, cb))) };
+        let app2_pk1_h = unsafe { unwrap!(call_1(|ud, cb| sign_key_new(&app2, &pk1_raw, ud, cb))) };
+
+        let data = b"signed message";
+        let signed = unsafe {
+            unwrap!(call_vec_u8(|ud, cb| {
+                sign(&app1, data.as_ptr(), data.len(), app1_sk1_h, ud, cb)
+            }))
+        };
+
+        let verified = unsafe {
+            unwrap!(call_vec_u8(|ud, cb| {
+                verify(&app2, signed.as_ptr(), signed.len(), app2_pk1_h, ud, cb)
+            }))
+        };
+        assert_eq!(&verified, data);
+
+        let mut tampered = signed.clone();
+        let last = tampered.len() - 1;
+        tampered[last] ^= 1;
+        let res = unsafe {
+            call_vec_u8(|ud, cb| {
+                verify(&app2, tampered.as_ptr(), tampered.len(), app2_pk1_h, ud, cb)
+            })
+        };
+        match res {
+            Err(code) if code == AppError::InvalidSignature.error_code() => (),
+            x => panic!("Unexpected {:?}", x),
+        }
+
+        // Detached signatures.
+        let signature = unsafe {
+            unwrap!(call_vec_u8(|ud, cb| {
+                sign_detached(&app1, data.as_ptr(), data.len(), app1_sk1_h, ud, cb)
+            }))
+        };
+        assert_eq!(&signed[..signature.len()], &signature[..]);
+
+        unsafe {
+            unwrap!(call_0(|ud, cb| {
+                verify_detached(
+                    &app2,
+                    data.as_ptr(),
+                    data.len(),
+                    signature.as_ptr(),
+                    signature.len(),
+                    app2_pk1_h,
+                    ud,
+                    cb,
+                )
+            }))
+        };
+
+        let other = b"other message";
+        let res = unsafe {
+            call_0(|ud, cb| {
+                verify_detached(
+                    &app2,
+                    other.as_ptr(),
+                    other.len(),
+                    signature.as_ptr(),
+                    signature.len(),
+                    app2_pk1_h,
+                    ud,
+                    cb,
+                )
+            })
+        };
+        match res {
+            Err(code) if code == AppError::InvalidSignature.error_code() => (),
+            x => panic!("Unexpected {:?}", x),
+        }
+    }
+
+    // Test signing with the app's own keys and creating and fetching secret
+    // sign keys.
+    #[test]
+    fn sign_sec_key_basics() {
+        let app = create_app();
+        let app_pk_h = unsafe { unwrap!(call_1(|ud, cb| app_pub_sign_key(&app, ud, cb))) };
+        let app_sk1_h = unsafe { unwrap!(call_1(|ud, cb| app_sec_sign_key(&app, ud, cb))) };
+
+        let app_sk1 = run_now(&app, move |client, context| {
+            let app_sk1 = unwrap!(client.secret_signing_key());
+            let app_sk2 = unwrap!(context.object_cache().get_sign_sec_key(app_sk1_h));
+            assert_eq!(&app_sk1.0[..], &app_sk2.0[..]);
+
+            app_sk1.0
+        });
+
+        let app_sk1_raw: SignSecretKey =
+            unsafe { unwrap!(call_1(|ud, cb| sign_sec_key_get(&app, app_sk1_h, ud, cb))) };
+        assert_eq!(app_sk1_raw[..], app_sk1[..]);
+
+        let app_sk2_h =
+            unsafe { unwrap!(call_1(|ud, cb| sign_sec_key_new(&app, &app_sk1_raw, ud, cb))) };
+
+        let data = b"signed by the app";
+        let signed = unsafe {
+            unwrap!(call_vec_u8(|ud, cb| {
+                sign(&app, data.as_ptr(), data.len(), app_sk2_h, ud, cb)
+            }))
+        };
+        let verified = unsafe {
+            unwrap!(call_vec_u8(|ud, cb| {
+                verify(&app, signed.as_ptr(), signed.len(), app_pk_h, ud, cb)
+            }))
+        };
+        assert_eq!(&verified, data);
+
+        unsafe {
+            unwrap!(call_0(|ud, cb| sign_sec_key_free(&app, app_sk1_h, ud, cb)));
+            unwrap!(call_0(|ud, cb| sign_sec_key_free(&app, app_sk2_h, ud, cb)));
+        }
+
+        let res = unsafe { call_0(|ud, cb| sign_sec_key_free(&app, app_sk2_h, ud, cb)) };
+        match res {
+            Err(code) if code == AppError::InvalidSignSecKeyHandle.error_code() => (),
+            x => panic!("Unexpected {:?}", x),
+        }
+    }
+
+    // Test deriving subkeys from the app keys.
+    #[test]
+    fn derive_subkey() {
+        let app = create_app();
+        let context = b"inbox";
+
+        let key0: SymSecretKey = unsafe {
+            unwrap!(call_1(|ud, cb| {
+                app_derive_subkey(&app, context.as_ptr(), context.len(), 0, ud, cb)
+            }))
+        };
+        let key1: SymSecretKey = unsafe {
+            unwrap!(call_1(|ud, cb| {
+                app_derive_subkey(&app, context.as_ptr(), context.len(), 1, ud, cb)
+            }))
+        };
+        assert_ne!(key0, key1);
+
+        let expected = run_now(&app, move |client, _| unwrap!(client.derive_subkey(context, 0)));
+        assert_eq!(key0, expected.0);
+    }
+
     // Test creating and fetching sign keys.
     #[test]
     fn sign_key_basics() {
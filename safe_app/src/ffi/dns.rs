@@ -0,0 +1,210 @@
+// Copyright 2017 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under (1) the MaidSafe.net Commercial License,
+// version 1.0 or later, or (2) The General Public License (GPL), version 3, depending on which
+// licence you accepted on initial access to the Software (the "Licences").
+//
+// By contributing code to the SAFE Network Software, or to this project generally, you agree to be
+// bound by the terms of the MaidSafe Contributor Agreement.  This, along with the Licenses can be
+// found in the root directory of this project at LICENSE, COPYING and CONTRIBUTOR.
+//
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.
+//
+// Please review the Licences for the specific language governing permissions and limitations
+// relating to use of the SAFE Network Software.
+
+use App;
+use errors::AppError;
+use ffi_utils::{FFI_RESULT_OK, FfiResult, OpaqueCtx, catch_unwind_cb, from_c_str};
+use futures::Future;
+use object_cache::MDataInfoHandle;
+use safe_core::FutureExt;
+use safe_core::structures::dns;
+use std::os::raw::{c_char, c_void};
+
+/// Register the public name, owned by the user's account, without any
+/// services. Fails if the name is already taken.
+///
+/// Callback parameters: user data, error code
+#[no_mangle]
+pub unsafe extern "C" fn dns_register(
+    app: *const App,
+    public_name: *const c_char,
+    user_data: *mut c_void,
+    o_cb: extern "C" fn(user_data: *mut c_void, result: FfiResult),
+) {
+    catch_unwind_cb(user_data, o_cb, || {
+        let user_data = OpaqueCtx(user_data);
+        let public_name = from_c_str(public_name)?;
+
+        (*app).send(move |client, _| {
+            dns::register(client, &public_name, Default::default())
+                .map(move |_| o_cb(user_data.0, FFI_RESULT_OK))
+                .map_err(AppError::from)
+                .map_err(move |err| {
+                    call_result_cb!(Err::<(), _>(err), user_data, o_cb);
+                })
+                .into_box()
+                .into()
+        })
+    })
+}
+
+/// Map the service (e.g. `www`) of the public name to the directory, replacing
+/// the existing mapping if any. The directory should be public, as its
+/// `MDataInfo` gets published in plain.
+///
+/// Callback parameters: user data, error code
+#[no_mangle]
+pub unsafe extern "C" fn dns_add_service(
+    app: *const App,
+    public_name: *const c_char,
+    service_name: *const c_char,
+    dir_h: MDataInfoHandle,
+    user_data: *mut c_void,
+    o_cb: extern "C" fn(user_data: *mut c_void, result: FfiResult),
+) {
+    catch_unwind_cb(user_data, o_cb, || {
+        let user_data = OpaqueCtx(user_data);
+        let public_name = from_c_str(public_name)?;
+        let service_name = from_c_str(service_name)?;
+
+        (*app).send(move |client, context| {
+            let dir = try_cb!(context.object_cache().get_mdata_info(dir_h), user_data, o_cb);
+
+            dns::add_service(client, &public_name, &service_name, &*dir)
+                .map(move |_| o_cb(user_data.0, FFI_RESULT_OK))
+                .map_err(AppError::from)
+                .map_err(move |err| {
+                    call_result_cb!(Err::<(), _>(err), user_data, o_cb);
+                })
+                .into_box()
+                .into()
+        })
+    })
+}
+
+/// Remove the service of the public name.
+///
+/// Callback parameters: user data, error code
+#[no_mangle]
+pub unsafe extern "C" fn dns_remove_service(
+    app: *const App,
+    public_name: *const c_char,
+    service_name: *const c_char,
+    user_data: *mut c_void,
+    o_cb: extern "C" fn(user_data: *mut c_void, result: FfiResult),
+) {
+    catch_unwind_cb(user_data, o_cb, || {
+        let user_data = OpaqueCtx(user_data);
+        let public_name = from_c_str(public_name)?;
+        let service_name = from_c_str(service_name)?;
+
+        (*app).send(move |client, _| {
+            dns::remove_service(client, &public_name, &service_name)
+                .map(move |_| o_cb(user_data.0, FFI_RESULT_OK))
+                .map_err(AppError::from)
+                .map_err(move |err| {
+                    call_result_cb!(Err::<(), _>(err), user_data, o_cb);
+                })
+                .into_box()
+                .into()
+        })
+    })
+}
+
+/// Look up the directory serving the service of the public name.
+///
+/// Callback parameters: user data, error code, directory handle
+#[no_mangle]
+pub unsafe extern "C" fn dns_lookup(
+    app: *const App,
+    public_name: *const c_char,
+    service_name: *const c_char,
+    user_data: *mut c_void,
+    o_cb: extern "C" fn(user_data: *mut c_void, result: FfiResult, dir_h: MDataInfoHandle),
+) {
+    catch_unwind_cb(user_data, o_cb, || {
+        let user_data = OpaqueCtx(user_data);
+        let public_name = from_c_str(public_name)?;
+        let service_name = from_c_str(service_name)?;
+
+        (*app).send(move |client, context| {
+            let context = context.clone();
+
+            dns::lookup(client, &public_name, &service_name)
+                .map(move |dir| {
+                    let dir_h = context.object_cache().insert_mdata_info(dir);
+                    o_cb(user_data.0, FFI_RESULT_OK, dir_h);
+                })
+                .map_err(AppError::from)
+                .map_err(move |err| {
+                    call_result_cb!(Err::<(), _>(err), user_data, o_cb);
+                })
+                .into_box()
+                .into()
+        })
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ffi_utils::test_utils::{call_0, call_1};
+    use safe_core::{DIR_TAG, MDataInfo, utils};
+    use std::ffi::CString;
+    use test_utils::{create_app, run_now};
+
+    // Register a public name, add a service to it and look it up.
+    #[test]
+    fn register_and_lookup() {
+        let app = create_app();
+        let public_name = unwrap!(CString::new(unwrap!(utils::generate_random_string(10))));
+        let service_name = unwrap!(CString::new("www"));
+
+        let dir = unwrap!(MDataInfo::random_public(DIR_TAG));
+        let dir2 = dir.clone();
+        let dir_h = run_now(&app, move |_, context| {
+            context.object_cache().insert_mdata_info(dir2)
+        });
+
+        unsafe {
+            unwrap!(call_0(|ud, cb| dns_register(&app, public_name.as_ptr(), ud, cb)));
+            unwrap!(call_0(|ud, cb| {
+                dns_add_service(
+                    &app,
+                    public_name.as_ptr(),
+                    service_name.as_ptr(),
+                    dir_h,
+                    ud,
+                    cb,
+                )
+            }));
+        }
+
+        let found_h: MDataInfoHandle = unsafe {
+            unwrap!(call_1(|ud, cb| {
+                dns_lookup(&app, public_name.as_ptr(), service_name.as_ptr(), ud, cb)
+            }))
+        };
+        let found = run_now(&app, move |_, context| {
+            unwrap!(context.object_cache().get_mdata_info(found_h)).clone()
+        });
+        assert_eq!(found, dir);
+
+        unsafe {
+            unwrap!(call_0(|ud, cb| {
+                dns_remove_service(&app, public_name.as_ptr(), service_name.as_ptr(), ud, cb)
+            }))
+        };
+
+        let res: Result<MDataInfoHandle, i32> = unsafe {
+            call_1(|ud, cb| {
+                dns_lookup(&app, public_name.as_ptr(), service_name.as_ptr(), ud, cb)
+            })
+        };
+        assert!(res.is_err());
+    }
+}
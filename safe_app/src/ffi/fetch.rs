@@ -0,0 +1,226 @@
+// Copyright 2017 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under (1) the MaidSafe.net Commercial License,
+// version 1.0 or later, or (2) The General Public License (GPL), version 3, depending on which
+// licence you accepted on initial access to the Software (the "Licences").
+//
+// By contributing code to the SAFE Network Software, or to this project generally, you agree to be
+// bound by the terms of the MaidSafe Contributor Agreement.  This, along with the Licenses can be
+// found in the root directory of this project at LICENSE, COPYING and CONTRIBUTOR.
+//
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.
+//
+// Please review the Licences for the specific language governing permissions and limitations
+// relating to use of the SAFE Network Software.
+
+use App;
+use errors::AppError;
+use fetch::{self, FetchedData};
+use ffi::helper::with_call_timeout;
+use ffi_utils::{FFI_RESULT_OK, FfiResult, OpaqueCtx, SafePtr, catch_unwind_cb, from_c_str};
+use futures::Future;
+use object_cache::MDataEntriesHandle;
+use routing::XorName;
+use safe_core::FutureExt;
+use safe_core::ffi::arrays::XorNameArray;
+use safe_core::ffi::nfs::File;
+use safe_core::xor_url::XorUrl;
+use std::ffi::CString;
+use std::os::raw::{c_char, c_void};
+use std::ptr;
+
+/// Kind of the data a URL resolved to.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum FetchedDataKind {
+    /// `ImmutableData`
+    ImmutableData,
+    /// `MutableData`
+    MutableData,
+    /// NFS file
+    File,
+}
+
+/// Content a URL resolved to.
+#[repr(C)]
+pub struct FetchedContent {
+    /// Kind of the data
+    pub kind: FetchedDataKind,
+    /// Pointer to the value of `ImmutableData` or to the content of the file
+    pub content_ptr: *const u8,
+    /// Length of the content
+    pub content_len: usize,
+    /// Handle to the entries of `MutableData`, 0 for other kinds
+    pub entries_h: MDataEntriesHandle,
+    /// Version of `MutableData`, 0 for other kinds
+    pub version: u64,
+    /// Metadata of the file, null for other kinds
+    pub file: *const File,
+}
+
+/// Fetch the content addressed by the `safe://` URL, which is either a
+/// XOR-URL or `safe://[<service>.]<public name>/<path>`. A single entry point
+/// for immutable data, mutable data, NFS files and public names.
+///
+/// `timeout_ms` is the time the requests sent by this call wait for their
+/// response, in milliseconds. Zero uses the timeout of the app.
+///
+/// Callback parameters: user data, error code, fetched content
+#[no_mangle]
+pub unsafe extern "C" fn app_fetch_url(
+    app: *const App,
+    url: *const c_char,
+    timeout_ms: u64,
+    user_data: *mut c_void,
+    o_cb: extern "C" fn(user_data: *mut c_void,
+                        result: FfiResult,
+                        content: *const FetchedContent),
+) {
+    catch_unwind_cb(user_data, o_cb, || {
+        let user_data = OpaqueCtx(user_data);
+        let url = from_c_str(url)?;
+
+        (*app).send(move |client, context| {
+            let context = context.clone();
+
+            with_call_timeout(client, timeout_ms, |client| fetch::fetch(client, &url))
+                .map(move |data| {
+                    let mut content = FetchedContent {
+                        kind: FetchedDataKind::ImmutableData,
+                        content_ptr: ptr::null(),
+                        content_len: 0,
+                        entries_h: 0,
+                        version: 0,
+                        file: ptr::null(),
+                    };
+
+                    match data {
+                        FetchedData::ImmutableData { content: value, .. } => {
+                            content.content_ptr = value.as_safe_ptr();
+                            content.content_len = value.len();
+                            o_cb(user_data.0, FFI_RESULT_OK, &content);
+                        }
+                        FetchedData::MutableData { version, entries, .. } => {
+                            let entries_h = context.object_cache().insert_mdata_entries(entries);
+                            content.kind = FetchedDataKind::MutableData;
+                            content.entries_h = entries_h;
+                            content.version = version;
+                            o_cb(user_data.0, FFI_RESULT_OK, &content);
+                        }
+                        FetchedData::File {
+                            file,
+                            content: file_content,
+                        } => {
+                            let ffi_file = file.into_repr_c();
+                            content.kind = FetchedDataKind::File;
+                            content.content_ptr = file_content.as_safe_ptr();
+                            content.content_len = file_content.len();
+                            content.file = &ffi_file;
+                            o_cb(user_data.0, FFI_RESULT_OK, &content);
+                        }
+                    }
+                })
+                .map_err(AppError::from)
+                .map_err(move |err| {
+                    call_result_cb!(Err::<(), _>(err), user_data, o_cb);
+                })
+                .into_box()
+                .into()
+        })
+    })
+}
+
+/// Encode the name and type tag of `MutableData` as a XOR-URL.
+///
+/// Callback parameters: user data, error code, XOR-URL
+#[no_mangle]
+pub unsafe extern "C" fn xor_url_from_mdata(
+    name: *const XorNameArray,
+    type_tag: u64,
+    user_data: *mut c_void,
+    o_cb: extern "C" fn(user_data: *mut c_void, result: FfiResult, url: *const c_char),
+) {
+    catch_unwind_cb(user_data, o_cb, || -> Result<_, AppError> {
+        let url = XorUrl::MutableData(XorName(*name), type_tag).encode();
+        let url = CString::new(url)?;
+        o_cb(user_data, FFI_RESULT_OK, url.as_ptr());
+        Ok(())
+    })
+}
+
+/// Encode the name of `ImmutableData` as a XOR-URL.
+///
+/// Callback parameters: user data, error code, XOR-URL
+#[no_mangle]
+pub unsafe extern "C" fn xor_url_from_idata(
+    name: *const XorNameArray,
+    user_data: *mut c_void,
+    o_cb: extern "C" fn(user_data: *mut c_void, result: FfiResult, url: *const c_char),
+) {
+    catch_unwind_cb(user_data, o_cb, || -> Result<_, AppError> {
+        let url = XorUrl::ImmutableData(XorName(*name)).encode();
+        let url = CString::new(url)?;
+        o_cb(user_data, FFI_RESULT_OK, url.as_ptr());
+        Ok(())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ffi_utils::test_utils::{send_via_user_data, sender_as_user_data};
+    use rand;
+    use routing::MutableData;
+    use safe_core::DIR_TAG;
+    use std::ffi::CStr;
+    use std::sync::mpsc;
+    use test_utils::{create_app, run};
+
+    // `MutableData` is fetched by the XOR-URL encoded through the FFI.
+    #[test]
+    fn fetch_mdata() {
+        let app = create_app();
+        let name: XorNameArray = rand::random();
+
+        run(&app, move |client, _| {
+            let owners = btree_set![unwrap!(client.owner_key())];
+            let data = unwrap!(MutableData::new(
+                XorName(name),
+                DIR_TAG,
+                btree_map![],
+                btree_map![],
+                owners,
+            ));
+            client.put_mdata(data).map_err(AppError::from)
+        });
+
+        let (tx, rx) = mpsc::channel::<String>();
+        unsafe { xor_url_from_mdata(&name, DIR_TAG, sender_as_user_data(&tx), url_cb) };
+        let url = unwrap!(CString::new(unwrap!(rx.recv())));
+
+        let (tx, rx) = mpsc::channel::<(FetchedDataKind, u64)>();
+        unsafe { app_fetch_url(&app, url.as_ptr(), 0, sender_as_user_data(&tx), fetch_cb) };
+        assert_eq!(unwrap!(rx.recv()), (FetchedDataKind::MutableData, 0));
+
+        extern "C" fn url_cb(user_data: *mut c_void, res: FfiResult, url: *const c_char) {
+            assert_eq!(res.error_code, 0);
+            unsafe {
+                let url = unwrap!(CStr::from_ptr(url).to_str()).to_string();
+                send_via_user_data(user_data, url);
+            }
+        }
+
+        extern "C" fn fetch_cb(
+            user_data: *mut c_void,
+            res: FfiResult,
+            content: *const FetchedContent,
+        ) {
+            assert_eq!(res.error_code, 0);
+            unsafe {
+                send_via_user_data(user_data, ((*content).kind, (*content).version));
+            }
+        }
+    }
+}
@@ -21,10 +21,64 @@ use errors::AppError;
 use ffi_utils::{FFI_RESULT_OK, FfiResult, OpaqueCtx};
 use ffi_utils::callback::Callback;
 use futures::Future;
-use object_cache::MDataInfoHandle;
+use maidsafe_utilities::serialisation::{deserialise, serialise};
+use object_cache::{MDataInfoHandle, MDataPermissionsHandle, ObjectCache};
+use operations::OperationId;
+use routing::{PermissionSet, User};
 use safe_core::{Client, FutureExt, MDataInfo};
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use std::collections::BTreeMap;
 use std::fmt::Debug;
 use std::os::raw::c_void;
+use std::time::Duration;
+
+// Envelope of the objects serialised for passing to other processes. The
+// variant tags the version of the format.
+#[derive(Serialize, Deserialize)]
+enum Versioned<T> {
+    V1(T),
+}
+
+// Serialise the object in the current version of the format.
+pub fn serialise_versioned<T: Serialize>(value: &T) -> Result<Vec<u8>, AppError> {
+    Ok(serialise(&Versioned::V1(value))?)
+}
+
+// Deserialise the object serialised by `serialise_versioned`.
+pub fn deserialise_versioned<T: DeserializeOwned>(encoded: &[u8]) -> Result<T, AppError> {
+    let Versioned::V1(value) = deserialise(encoded)?;
+    Ok(value)
+}
+
+// Retrieve permissions from the object cache.
+pub fn get_permissions(
+    object_cache: &ObjectCache,
+    handle: MDataPermissionsHandle,
+) -> Result<BTreeMap<User, PermissionSet>, AppError> {
+    let input = object_cache.get_mdata_permissions(handle)?.clone();
+    let mut output = BTreeMap::new();
+
+    for (user, permission_set_h) in input {
+        let permission_set = *object_cache.get_mdata_permission_set(permission_set_h)?;
+        let _ = output.insert(user, permission_set);
+    }
+
+    Ok(output)
+}
+
+// Issue the requests made by `f` with the timeout passed to an FFI call in
+// milliseconds, or with the app's timeout if it's zero.
+pub fn with_call_timeout<F, R>(client: &Client<AppContext>, timeout_ms: u64, f: F) -> R
+where
+    F: FnOnce(&Client<AppContext>) -> R,
+{
+    if timeout_ms == 0 {
+        f(client)
+    } else {
+        client.with_timeout(Duration::from_millis(timeout_ms), f)
+    }
+}
 
 // Convenience wrapper around `App::send` which automatically handles the callback
 // boilerplate.
@@ -86,3 +140,36 @@ where
             .into()
     })
 }
+
+// Like `send_with_mdata_info`, but the operation can be aborted with
+// `operation_cancel` using the returned id, in which case the callback gets
+// called with `ERR_OPERATION_CANCELLED`.
+pub unsafe fn send_cancellable_with_mdata_info<C, F, U, E>(
+    app: *const App,
+    info_h: MDataInfoHandle,
+    user_data: *mut c_void,
+    o_cb: C,
+    f: F,
+) -> Result<OperationId, AppError>
+where
+    C: Callback + Copy + Send + 'static,
+    F: FnOnce(&Client<AppContext>, &AppContext, &MDataInfo) -> U + Send + 'static,
+    U: Future<Item = C::Args, Error = E> + 'static,
+    E: Debug + 'static,
+    AppError: From<E>,
+{
+    let user_data = OpaqueCtx(user_data);
+
+    (*app).send_cancellable(
+        move |client, context| {
+            let info = fry!(context.object_cache().get_mdata_info(info_h));
+            f(client, context, &*info).map_err(AppError::from).into_box()
+        },
+        move |res| match res {
+            Ok(args) => o_cb.call(user_data.0, FFI_RESULT_OK, args),
+            res @ Err(..) => {
+                call_result_cb!(res, user_data, o_cb);
+            }
+        },
+    )
+}
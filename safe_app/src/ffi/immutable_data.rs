@@ -20,12 +20,15 @@ use App;
 use errors::AppError;
 use ffi_utils::{FFI_RESULT_OK, FfiResult, OpaqueCtx, catch_unwind_cb, vec_clone_from_raw_parts};
 use futures::Future;
+use futures::future::{self, Loop};
 use maidsafe_utilities::serialisation::{deserialise, serialise};
 use object_cache::{CipherOptHandle, SelfEncryptorReaderHandle, SelfEncryptorWriterHandle};
+use operations::OperationId;
 use routing::XorName;
 use safe_core::{FutureExt, SelfEncryptionStorage, immutable_data};
 use safe_core::ffi::arrays::XorNameArray;
 use self_encryption::{SelfEncryptor, SequentialEncryptor};
+use std::cmp;
 use std::os::raw::c_void;
 
 /// Handle of a Self Encryptor Writer object
@@ -33,6 +36,13 @@ pub type SEWriterHandle = SelfEncryptorWriterHandle;
 /// Handle of a Self Encryptor Reader object
 pub type SEReaderHandle = SelfEncryptorReaderHandle;
 
+/// Number of bytes passed through the Self Encryptor between two invocations of
+/// the progress callback. Matches the maximum chunk size of self-encryption.
+pub const PROGRESS_PIECE_SIZE: u64 = 1024 * 1024;
+
+/// Progress callback parameters: user data, bytes processed so far, total bytes
+pub type ProgressCb = extern "C" fn(user_data: *mut c_void, bytes_done: u64, bytes_total: u64);
+
 /// Get a Self Encryptor.
 ///
 /// Callback parameters: user data, error code, SE handle
@@ -105,6 +115,91 @@ pub unsafe extern "C" fn idata_write_to_self_encryptor(
     });
 }
 
+/// Write to Self Encryptor, reporting progress through `o_progress_cb` (if not
+/// null) each time a piece of up to `PROGRESS_PIECE_SIZE` bytes has been written.
+/// `o_cb` is called once the whole data has been written, or in case of error.
+///
+/// Callback parameters: user data, error code
+#[no_mangle]
+pub unsafe extern "C" fn idata_write_to_self_encryptor_with_progress(
+    app: *const App,
+    se_h: SEWriterHandle,
+    data: *const u8,
+    size: usize,
+    user_data: *mut c_void,
+    o_progress_cb: Option<ProgressCb>,
+    o_cb: extern "C" fn(user_data: *mut c_void, result: FfiResult),
+) {
+    let user_data = OpaqueCtx(user_data);
+
+    catch_unwind_cb(user_data, o_cb, || {
+        let data = vec_clone_from_raw_parts(data, size);
+        let total = data.len() as u64;
+
+        (*app).send(move |_, context| {
+            let context = context.clone();
+
+            future::loop_fn((data, 0), move |(data, done)| {
+                let end = cmp::min(done + PROGRESS_PIECE_SIZE, total);
+                let writer = fry!(context.object_cache().get_se_writer(se_h));
+                let fut = writer.write(&data[done as usize..end as usize]);
+
+                fut.map_err(AppError::from)
+                    .map(move |_| {
+                        if let Some(progress_cb) = o_progress_cb {
+                            progress_cb(user_data.0, end, total);
+                        }
+
+                        if end == total {
+                            Loop::Break(())
+                        } else {
+                            Loop::Continue((data, end))
+                        }
+                    })
+                    .into_box()
+            }).then(move |res| {
+                    call_result_cb!(res, user_data, o_cb);
+                    Ok(())
+                })
+                .into_box()
+                .into()
+        })
+    });
+}
+
+/// Cancellable version of `idata_write_to_self_encryptor`. The id to pass to
+/// `operation_cancel` is written into `o_op_id`. Data already handed over to
+/// the Self Encryptor before the cancellation is not rolled back.
+///
+/// Callback parameters: user data, error code
+#[no_mangle]
+pub unsafe extern "C" fn idata_write_to_self_encryptor_cancellable(
+    app: *const App,
+    se_h: SEWriterHandle,
+    data: *const u8,
+    size: usize,
+    o_op_id: *mut OperationId,
+    user_data: *mut c_void,
+    o_cb: extern "C" fn(user_data: *mut c_void, result: FfiResult),
+) {
+    let user_data = OpaqueCtx(user_data);
+
+    catch_unwind_cb(user_data, o_cb, || {
+        let data_slice = vec_clone_from_raw_parts(data, size);
+
+        *o_op_id = (*app).send_cancellable(
+            move |_, context| {
+                let writer = fry!(context.object_cache().get_se_writer(se_h));
+                writer.write(&data_slice).map_err(AppError::from).into_box()
+            },
+            move |res| {
+                call_result_cb!(res, user_data, o_cb);
+            },
+        )?;
+        Ok(())
+    });
+}
+
 /// Close Self Encryptor and free the Self Encryptor Writer handle.
 ///
 /// Callback parameters: user data, error code, xor name
@@ -326,6 +421,82 @@ pub unsafe extern "C" fn idata_read_from_self_encryptor(
     });
 }
 
+/// Read from Self Encryptor, reporting progress through `o_progress_cb` (if not
+/// null) each time a piece of up to `PROGRESS_PIECE_SIZE` bytes has been read.
+/// `o_cb` is called with the whole data once it has been read, or in case of
+/// error.
+///
+/// Callback parameters: user data, error code, data, size
+#[no_mangle]
+pub unsafe extern "C" fn idata_read_from_self_encryptor_with_progress(
+    app: *const App,
+    se_h: SEReaderHandle,
+    from_pos: u64,
+    len: u64,
+    user_data: *mut c_void,
+    o_progress_cb: Option<ProgressCb>,
+    o_cb: extern "C" fn(user_data: *mut c_void,
+                        result: FfiResult,
+                        data_ptr: *const u8,
+                        data_len: usize),
+) {
+    let user_data = OpaqueCtx(user_data);
+
+    catch_unwind_cb(user_data, o_cb, || {
+        (*app).send(move |_, context| {
+            let size = match context.object_cache().get_se_reader(se_h) {
+                Ok(se) => se.len(),
+                res @ Err(..) => {
+                    call_result_cb!(res, user_data, o_cb);
+                    return None;
+                }
+            };
+
+            if from_pos + len > size {
+                call_result_cb!(
+                    Err::<(), _>(AppError::InvalidSelfEncryptorReadOffsets),
+                    user_data,
+                    o_cb
+                );
+                return None;
+            }
+
+            let context = context.clone();
+
+            future::loop_fn(Vec::with_capacity(len as usize), move |mut data| {
+                let done = data.len() as u64;
+                let piece_len = cmp::min(PROGRESS_PIECE_SIZE, len - done);
+                let reader = fry!(context.object_cache().get_se_reader(se_h));
+                let fut = reader.read(from_pos + done, piece_len);
+
+                fut.map_err(AppError::from)
+                    .map(move |piece| {
+                        data.extend_from_slice(&piece);
+                        let done = done + piece_len;
+
+                        if let Some(progress_cb) = o_progress_cb {
+                            progress_cb(user_data.0, done, len);
+                        }
+
+                        if done == len {
+                            Loop::Break(data)
+                        } else {
+                            Loop::Continue(data)
+                        }
+                    })
+                    .into_box()
+            }).map(move |data| {
+                    o_cb(user_data.0, FFI_RESULT_OK, data.as_ptr(), data.len());
+                })
+                .map_err(move |e| {
+                    call_result_cb!(Err::<(), _>(e), user_data, o_cb);
+                })
+                .into_box()
+                .into()
+        })
+    });
+}
+
 /// Free Self Encryptor Writer handle.
 ///
 /// Callback parameters: user data, error code
@@ -376,6 +547,7 @@ mod tests {
     use ffi_utils::ErrorCode;
     use ffi_utils::test_utils::{call_0, call_1, call_vec_u8};
     use safe_core::utils;
+    use std::sync::mpsc::{self, Sender};
     use test_utils::create_app;
 
     // Test immutable data operations.
@@ -471,4 +643,105 @@ mod tests {
             unwrap!(call_0(|ud, cb| cipher_opt_free(&app, cipher_opt_h, ud, cb)));
         }
     }
+
+    // Test progress reporting of self encryptor writes and reads spanning
+    // several pieces.
+    #[test]
+    fn progress() {
+        let app = create_app();
+        let size = 2 * PROGRESS_PIECE_SIZE + 10;
+        let plain_text = unwrap!(utils::generate_random_vector::<u8>(size as usize));
+
+        type UserData = (Sender<Vec<u8>>, Vec<(u64, u64)>);
+
+        extern "C" fn progress_cb(user_data: *mut c_void, bytes_done: u64, bytes_total: u64) {
+            let user_data = user_data as *mut UserData;
+            unsafe { (*user_data).1.push((bytes_done, bytes_total)) }
+        }
+
+        extern "C" fn write_cb(user_data: *mut c_void, res: FfiResult) {
+            assert_eq!(res.error_code, 0);
+            let user_data = user_data as *mut UserData;
+            unsafe { unwrap!((*user_data).0.send(Vec::new())) }
+        }
+
+        extern "C" fn read_cb(
+            user_data: *mut c_void,
+            res: FfiResult,
+            data_ptr: *const u8,
+            data_len: usize,
+        ) {
+            assert_eq!(res.error_code, 0);
+            let user_data = user_data as *mut UserData;
+            unsafe {
+                let data = vec_clone_from_raw_parts(data_ptr, data_len);
+                unwrap!((*user_data).0.send(data))
+            }
+        }
+
+        let expected = vec![
+            (PROGRESS_PIECE_SIZE, size),
+            (2 * PROGRESS_PIECE_SIZE, size),
+            (size, size),
+        ];
+
+        unsafe {
+            let cipher_opt_h = unwrap!(call_1(|ud, cb| cipher_opt_new_plaintext(&app, ud, cb)));
+            let se_writer_h = unwrap!(call_1(|ud, cb| idata_new_self_encryptor(&app, ud, cb)));
+
+            let (tx, rx) = mpsc::channel();
+            let mut user_data: UserData = (tx, Vec::new());
+            idata_write_to_self_encryptor_with_progress(
+                &app,
+                se_writer_h,
+                plain_text.as_ptr(),
+                plain_text.len(),
+                &mut user_data as *mut UserData as *mut c_void,
+                Some(progress_cb),
+                write_cb,
+            );
+            let _ = unwrap!(rx.recv());
+            assert_eq!(user_data.1, expected);
+
+            let name: XorNameArray = unwrap!(call_1(|ud, cb| {
+                idata_close_self_encryptor(&app, se_writer_h, cipher_opt_h, ud, cb)
+            }));
+            let se_reader_h = unwrap!(call_1(
+                |ud, cb| idata_fetch_self_encryptor(&app, &name, ud, cb),
+            ));
+
+            let (tx, rx) = mpsc::channel();
+            let mut user_data: UserData = (tx, Vec::new());
+            idata_read_from_self_encryptor_with_progress(
+                &app,
+                se_reader_h,
+                0,
+                size,
+                &mut user_data as *mut UserData as *mut c_void,
+                Some(progress_cb),
+                read_cb,
+            );
+            assert_eq!(unwrap!(rx.recv()), plain_text);
+            assert_eq!(user_data.1, expected);
+
+            // The progress callback is optional.
+            let received_plain_text = call_vec_u8(|ud, cb| {
+                idata_read_from_self_encryptor_with_progress(
+                    &app,
+                    se_reader_h,
+                    0,
+                    size,
+                    ud,
+                    None,
+                    cb,
+                )
+            });
+            assert_eq!(plain_text, unwrap!(received_plain_text));
+
+            unwrap!(call_0(|ud, cb| {
+                idata_self_encryptor_reader_free(&app, se_reader_h, ud, cb)
+            }));
+            unwrap!(call_0(|ud, cb| cipher_opt_free(&app, cipher_opt_h, ud, cb)));
+        }
+    }
 }
@@ -25,6 +25,7 @@ use safe_core::ffi::ipc::req::{AuthReq as FfiAuthReq, ContainersReq as FfiContai
 use safe_core::ffi::ipc::resp::AuthGranted as FfiAuthGranted;
 use safe_core::ipc::{self, AuthReq, ContainersReq, IpcError, IpcMsg, IpcReq, IpcResp,
                      ShareMDataReq};
+use safe_core::ipc::uri_scheme::SAFE_AUTH_SCHEME;
 use std::ffi::CString;
 use std::os::raw::{c_char, c_void};
 
@@ -114,7 +115,7 @@ pub unsafe extern "C" fn encode_share_mdata_req(
 }
 
 fn encode_ipc(req_id: u32, req: IpcReq) -> Result<CString, AppError> {
-    let encoded = ipc::encode_msg(&IpcMsg::Req { req_id, req }, "safe-auth")?;
+    let encoded = ipc::encode_msg(&IpcMsg::Req { req_id, req }, SAFE_AUTH_SCHEME)?;
     Ok(CString::new(encoded)?)
 }
 
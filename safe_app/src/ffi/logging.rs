@@ -21,6 +21,8 @@ use super::AppError;
 use config_file_handler::FileHandler;
 use ffi_utils::{FFI_RESULT_OK, FfiResult, catch_unwind_cb, from_c_str};
 use maidsafe_utilities::log;
+use safe_core::config;
+use safe_core::logging::{self, LogConfig, LogFormat};
 use std::ffi::CString;
 use std::os::raw::{c_char, c_void};
 
@@ -80,6 +82,58 @@ pub unsafe extern "C" fn app_output_log_path(
     })
 }
 
+/// Enable structured logging to stderr. `filter` is a comma-separated list of
+/// directives, each being either a log level or `module=level` (e.g.
+/// `"warn,safe_core::client=debug"`). If `filter` is null, the `log_filter`
+/// of the safe_core configuration file is used. If `json` is true, every
+/// record is written as a JSON object. The last `history_size` records are also kept in
+/// memory and can be retrieved with `app_log_history`.
+///
+/// Callback parameters: user data, error code
+#[no_mangle]
+pub unsafe extern "C" fn app_init_structured_logging(
+    filter: *const c_char,
+    json: bool,
+    history_size: usize,
+    user_data: *mut c_void,
+    o_cb: extern "C" fn(user_data: *mut c_void, result: FfiResult),
+) {
+    catch_unwind_cb(user_data, o_cb, || -> Result<(), AppError> {
+        let format = if json {
+            LogFormat::Json
+        } else {
+            LogFormat::Plain
+        };
+        let config = if filter.is_null() {
+            config::get().log_config()?
+        } else {
+            LogConfig::new().filter(&from_c_str(filter)?)?
+        };
+
+        logging::init(config.format(format).history_size(history_size))?;
+        o_cb(user_data, FFI_RESULT_OK);
+        Ok(())
+    });
+}
+
+/// Retrieve the log records kept in memory by the structured logger, oldest
+/// first, separated by newlines.
+///
+/// Callback parameters: user data, error code, log records
+#[no_mangle]
+pub unsafe extern "C" fn app_log_history(
+    user_data: *mut c_void,
+    o_cb: extern "C" fn(user_data: *mut c_void,
+                        result: FfiResult,
+                        records: *const c_char),
+) {
+    catch_unwind_cb(user_data, o_cb, || -> Result<(), AppError> {
+        let records = CString::new(logging::history().join("\n"))?;
+        o_cb(user_data, FFI_RESULT_OK, records.as_ptr());
+        Ok(())
+    })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -17,10 +17,10 @@
 
 use App;
 use errors::AppError;
-use ffi::helper::send_sync;
+use ffi::helper::{deserialise_versioned, send_sync, serialise_versioned};
 use ffi_utils::{FFI_RESULT_OK, FfiResult, OpaqueCtx, SafePtr, catch_unwind_cb,
                 vec_clone_from_raw_parts};
-use maidsafe_utilities::serialisation::{deserialise, serialise};
+use maidsafe_utilities::serialisation::deserialise;
 use object_cache::MDataInfoHandle;
 use routing::XorName;
 use rust_sodium::crypto::secretbox;
@@ -261,7 +261,8 @@ pub unsafe extern "C" fn mdata_info_extract_name_and_type_tag(
     })
 }
 
-/// Serialise `MDataInfo`.
+/// Serialise `MDataInfo` into a versioned blob, e.g. for passing to another
+/// process.
 ///
 /// Callback parameters: user data, error code, serialised mdata info
 #[no_mangle]
@@ -283,7 +284,7 @@ pub unsafe extern "C" fn mdata_info_serialise(
                 user_data,
                 o_cb
             );
-            let encoded = try_cb!(serialise(&*info).map_err(AppError::from), user_data, o_cb);
+            let encoded = try_cb!(serialise_versioned(&*info), user_data, o_cb);
 
             o_cb(
                 user_data.0,
@@ -296,7 +297,8 @@ pub unsafe extern "C" fn mdata_info_serialise(
     })
 }
 
-/// Deserialise `MDataInfo`.
+/// Deserialise `MDataInfo` serialised by `mdata_info_serialise`. Blobs produced
+/// before the format was versioned are accepted too.
 ///
 /// Callback parameters: user data, error code, mdata info handle
 #[no_mangle]
@@ -313,7 +315,10 @@ pub unsafe extern "C" fn mdata_info_deserialise(
         let encoded = vec_clone_from_raw_parts(ptr, len);
 
         send_sync(app, user_data, o_cb, move |_, context| {
-            let info = deserialise(&encoded)?;
+            let info = match deserialise_versioned(&encoded) {
+                Ok(info) => info,
+                Err(_) => deserialise(&encoded)?,
+            };
             Ok(context.object_cache().insert_mdata_info(info))
         })
     })
@@ -342,6 +347,7 @@ pub unsafe extern "C" fn mdata_info_free(
 mod tests {
     use super::*;
     use ffi_utils::test_utils::{call_0, call_1, call_vec_u8};
+    use maidsafe_utilities::serialisation::serialise;
     use rand;
     use routing::XOR_NAME_LEN;
     use rust_sodium::crypto::secretbox;
@@ -435,14 +441,25 @@ mod tests {
             unwrap!(res)
         };
 
+        // Blobs serialised before the format was versioned.
+        let legacy = unwrap!(serialise(&info1));
+        let info3_h = unsafe {
+            unwrap!(call_1(|ud, cb| {
+                mdata_info_deserialise(&app, legacy.as_ptr(), legacy.len(), ud, cb)
+            }))
+        };
+
         run_now(&app, move |_, context| {
             let info2 = unwrap!(context.object_cache().get_mdata_info(info2_h));
             assert_eq!(info1, *info2);
+            let info3 = unwrap!(context.object_cache().get_mdata_info(info3_h));
+            assert_eq!(info1, *info3);
         });
 
         unsafe {
             unwrap!(call_0(|ud, cb| mdata_info_free(&app, info1_h, ud, cb)));
             unwrap!(call_0(|ud, cb| mdata_info_free(&app, info2_h, ud, cb)));
+            unwrap!(call_0(|ud, cb| mdata_info_free(&app, info3_h, ud, cb)));
         }
     }
 }
@@ -0,0 +1,356 @@
+// Copyright 2017 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under (1) the MaidSafe.net Commercial License,
+// version 1.0 or later, or (2) The General Public License (GPL), version 3, depending on which
+// licence you accepted on initial access to the Software (the "Licences").
+//
+// By contributing code to the SAFE Network Software, or to this project generally, you agree to be
+// bound by the terms of the MaidSafe Contributor Agreement.  This, along with the Licenses can be
+// found in the root directory of this project at LICENSE, COPYING and CONTRIBUTOR.
+//
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.
+//
+// Please review the Licences for the specific language governing permissions and limitations
+// relating to use of the SAFE Network Software.
+
+use App;
+use AppContext;
+use errors::AppError;
+use ffi_utils::{FFI_RESULT_OK, FfiResult, OpaqueCtx, SafePtr, catch_unwind_cb,
+                vec_clone_from_raw_parts};
+use futures::Future;
+use maidsafe_utilities::serialisation::{deserialise, serialise};
+use object_cache::MDataInfoHandle;
+use routing::XorName;
+use safe_core::FutureExt;
+use safe_core::ffi::arrays::XorNameArray;
+use safe_core::structures::{Mailbox, MessagingAddress};
+use std::os::raw::c_void;
+
+/// Message received into the inbox.
+#[repr(C)]
+pub struct MailboxMessage {
+    /// Message identifier
+    pub id: XorNameArray,
+    /// Serialised address of the sender, to be used for replies
+    pub sender_ptr: *const u8,
+    /// Length of the sender address
+    pub sender_len: usize,
+    /// Pointer to the message body
+    pub body_ptr: *const u8,
+    /// Length of the message body
+    pub body_len: usize,
+}
+
+/// Create new mailbox consisting of an inbox and an outbox.
+///
+/// Callback parameters: user data, error code, inbox info handle, outbox info handle
+#[no_mangle]
+pub unsafe extern "C" fn mailbox_create(
+    app: *const App,
+    user_data: *mut c_void,
+    o_cb: extern "C" fn(user_data: *mut c_void,
+                        result: FfiResult,
+                        inbox_h: MDataInfoHandle,
+                        outbox_h: MDataInfoHandle),
+) {
+    catch_unwind_cb(user_data, o_cb, || {
+        let user_data = OpaqueCtx(user_data);
+
+        (*app).send(move |client, context| {
+            let context = context.clone();
+
+            Mailbox::create(client)
+                .map(move |mailbox| {
+                    let inbox_h = context.object_cache().insert_mdata_info(
+                        mailbox.inbox().clone(),
+                    );
+                    let outbox_h = context.object_cache().insert_mdata_info(
+                        mailbox.outbox().clone(),
+                    );
+                    o_cb(user_data.0, FFI_RESULT_OK, inbox_h, outbox_h);
+                })
+                .map_err(AppError::from)
+                .map_err(move |err| {
+                    call_result_cb!(Err::<(), _>(err), user_data, o_cb);
+                })
+                .into_box()
+                .into()
+        })
+    })
+}
+
+/// Get the serialised address other users can send messages to this mailbox at.
+///
+/// Callback parameters: user data, error code, address vector, vector size
+#[no_mangle]
+pub unsafe extern "C" fn mailbox_address(
+    app: *const App,
+    inbox_h: MDataInfoHandle,
+    outbox_h: MDataInfoHandle,
+    user_data: *mut c_void,
+    o_cb: extern "C" fn(user_data: *mut c_void,
+                        result: FfiResult,
+                        address_ptr: *const u8,
+                        address_len: usize),
+) {
+    catch_unwind_cb(user_data, o_cb, || {
+        let user_data = OpaqueCtx(user_data);
+
+        (*app).send(move |client, context| {
+            let mailbox = try_cb!(get_mailbox(context, inbox_h, outbox_h), user_data, o_cb);
+            let address = try_cb!(
+                mailbox.address(client).map_err(AppError::from),
+                user_data,
+                o_cb
+            );
+            let address = try_cb!(
+                serialise(&address).map_err(AppError::from),
+                user_data,
+                o_cb
+            );
+
+            o_cb(
+                user_data.0,
+                FFI_RESULT_OK,
+                address.as_safe_ptr(),
+                address.len(),
+            );
+            None
+        })
+    })
+}
+
+/// Send message to the serialised address and record it in the outbox.
+///
+/// Callback parameters: user data, error code, message id
+#[no_mangle]
+pub unsafe extern "C" fn mailbox_send(
+    app: *const App,
+    inbox_h: MDataInfoHandle,
+    outbox_h: MDataInfoHandle,
+    address_ptr: *const u8,
+    address_len: usize,
+    body_ptr: *const u8,
+    body_len: usize,
+    user_data: *mut c_void,
+    o_cb: extern "C" fn(user_data: *mut c_void,
+                        result: FfiResult,
+                        id: *const XorNameArray),
+) {
+    catch_unwind_cb(user_data, o_cb, || {
+        let user_data = OpaqueCtx(user_data);
+        let address = vec_clone_from_raw_parts(address_ptr, address_len);
+        let address: MessagingAddress = deserialise(&address)?;
+        let body = vec_clone_from_raw_parts(body_ptr, body_len);
+
+        (*app).send(move |client, context| {
+            let mailbox = try_cb!(get_mailbox(context, inbox_h, outbox_h), user_data, o_cb);
+
+            mailbox
+                .send(client, address, body)
+                .map(move |id| { o_cb(user_data.0, FFI_RESULT_OK, &id.0); })
+                .map_err(AppError::from)
+                .map_err(move |err| {
+                    call_result_cb!(Err::<(), _>(err), user_data, o_cb);
+                })
+                .into_box()
+                .into()
+        })
+    })
+}
+
+/// List messages in the inbox.
+///
+/// Callback parameters: user data, error code, messages array, array length
+#[no_mangle]
+pub unsafe extern "C" fn mailbox_list(
+    app: *const App,
+    inbox_h: MDataInfoHandle,
+    outbox_h: MDataInfoHandle,
+    user_data: *mut c_void,
+    o_cb: extern "C" fn(user_data: *mut c_void,
+                        result: FfiResult,
+                        messages_ptr: *const MailboxMessage,
+                        messages_len: usize),
+) {
+    catch_unwind_cb(user_data, o_cb, || {
+        let user_data = OpaqueCtx(user_data);
+
+        (*app).send(move |client, context| {
+            let mailbox = try_cb!(get_mailbox(context, inbox_h, outbox_h), user_data, o_cb);
+
+            mailbox
+                .messages(client)
+                .map_err(AppError::from)
+                .and_then(|messages| {
+                    let mut senders = Vec::with_capacity(messages.len());
+                    for &(_, ref message) in &messages {
+                        senders.push(serialise(&message.sender)?);
+                    }
+                    Ok((messages, senders))
+                })
+                .map(move |(messages, senders)| {
+                    let ffi_messages: Vec<_> = messages
+                        .iter()
+                        .zip(senders.iter())
+                        .map(|(&(ref id, ref message), sender)| {
+                            MailboxMessage {
+                                id: id.0,
+                                sender_ptr: sender.as_safe_ptr(),
+                                sender_len: sender.len(),
+                                body_ptr: message.body.as_safe_ptr(),
+                                body_len: message.body.len(),
+                            }
+                        })
+                        .collect();
+
+                    o_cb(
+                        user_data.0,
+                        FFI_RESULT_OK,
+                        ffi_messages.as_safe_ptr(),
+                        ffi_messages.len(),
+                    );
+                })
+                .map_err(move |err| {
+                    call_result_cb!(Err::<(), _>(err), user_data, o_cb);
+                })
+                .into_box()
+                .into()
+        })
+    })
+}
+
+/// Delete the message from the inbox, marking it as read for its sender.
+///
+/// Callback parameters: user data, error code
+#[no_mangle]
+pub unsafe extern "C" fn mailbox_delete(
+    app: *const App,
+    inbox_h: MDataInfoHandle,
+    outbox_h: MDataInfoHandle,
+    id: *const XorNameArray,
+    user_data: *mut c_void,
+    o_cb: extern "C" fn(user_data: *mut c_void, result: FfiResult),
+) {
+    catch_unwind_cb(user_data, o_cb, || {
+        let user_data = OpaqueCtx(user_data);
+        let id = XorName(*id);
+
+        (*app).send(move |client, context| {
+            let mailbox = try_cb!(get_mailbox(context, inbox_h, outbox_h), user_data, o_cb);
+
+            mailbox
+                .delete(client, id)
+                .map(move |_| o_cb(user_data.0, FFI_RESULT_OK))
+                .map_err(AppError::from)
+                .map_err(move |err| {
+                    call_result_cb!(Err::<(), _>(err), user_data, o_cb);
+                })
+                .into_box()
+                .into()
+        })
+    })
+}
+
+fn get_mailbox(
+    context: &AppContext,
+    inbox_h: MDataInfoHandle,
+    outbox_h: MDataInfoHandle,
+) -> Result<Mailbox, AppError> {
+    let inbox = context.object_cache().get_mdata_info(inbox_h)?.clone();
+    let outbox = context.object_cache().get_mdata_info(outbox_h)?.clone();
+    Ok(Mailbox::new(inbox, outbox))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ffi_utils::test_utils::{call_0, call_1, call_2, call_vec_u8};
+    use test_utils::create_app;
+
+    // Message sent to own mailbox shows up in the inbox and can be deleted.
+    #[test]
+    fn send_list_delete() {
+        let app = create_app();
+
+        let (inbox_h, outbox_h) = unsafe {
+            unwrap!(call_2(|ud, cb| mailbox_create(&app, ud, cb)))
+        };
+        let address = unsafe {
+            unwrap!(call_vec_u8(
+                |ud, cb| mailbox_address(&app, inbox_h, outbox_h, ud, cb),
+            ))
+        };
+
+        let body = b"hello".to_vec();
+        let id: XorNameArray = unsafe {
+            unwrap!(call_1(|ud, cb| {
+                mailbox_send(
+                    &app,
+                    inbox_h,
+                    outbox_h,
+                    address.as_ptr(),
+                    address.len(),
+                    body.as_ptr(),
+                    body.len(),
+                    ud,
+                    cb,
+                )
+            }))
+        };
+
+        unsafe {
+            unwrap!(call_0(
+                |ud, cb| mailbox_delete(&app, inbox_h, outbox_h, &id, ud, cb),
+            ))
+        };
+    }
+
+    // Mailbox created by an app lets the app record its sent messages and
+    // delete the received ones, even though the account owns the mailbox.
+    #[test]
+    fn app_permissions() {
+        let app = create_app();
+
+        let (inbox_h, outbox_h) = unsafe {
+            unwrap!(call_2(|ud, cb| mailbox_create(&app, ud, cb)))
+        };
+        let address = unsafe {
+            unwrap!(call_vec_u8(
+                |ud, cb| mailbox_address(&app, inbox_h, outbox_h, ud, cb),
+            ))
+        };
+
+        let send = |body: &[u8]| -> XorNameArray {
+            unsafe {
+                unwrap!(call_1(|ud, cb| {
+                    mailbox_send(
+                        &app,
+                        inbox_h,
+                        outbox_h,
+                        address.as_ptr(),
+                        address.len(),
+                        body.as_ptr(),
+                        body.len(),
+                        ud,
+                        cb,
+                    )
+                }))
+            }
+        };
+        let id0 = send(b"first");
+        let id1 = send(b"second");
+
+        let delete = |id: &XorNameArray| unsafe {
+            call_0(|ud, cb| mailbox_delete(&app, inbox_h, outbox_h, id, ud, cb))
+        };
+        unwrap!(delete(&id0));
+        unwrap!(delete(&id1));
+
+        // The messages are gone now.
+        assert!(delete(&id0).is_err());
+    }
+}
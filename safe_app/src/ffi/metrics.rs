@@ -0,0 +1,292 @@
+// Copyright 2017 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under (1) the MaidSafe.net Commercial License,
+// version 1.0 or later, or (2) The General Public License (GPL), version 3, depending on which
+// licence you accepted on initial access to the Software (the "Licences").
+//
+// By contributing code to the SAFE Network Software, or to this project generally, you agree to be
+// bound by the terms of the MaidSafe Contributor Agreement.  This, along with the Licenses can be
+// found in the root directory of this project at LICENSE, COPYING and CONTRIBUTOR.
+//
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.
+//
+// Please review the Licences for the specific language governing permissions and limitations
+// relating to use of the SAFE Network Software.
+
+use App;
+use errors::AppError;
+use ffi::helper::send_sync;
+use ffi_utils::{FFI_RESULT_OK, FfiResult, OpaqueCtx, catch_unwind_cb};
+use maidsafe_utilities::serialisation::serialise;
+#[cfg(any(test, feature = "testing"))]
+use std::ffi::CString;
+#[cfg(any(test, feature = "testing"))]
+use std::os::raw::c_char;
+use std::os::raw::c_void;
+#[cfg(any(test, feature = "testing"))]
+use std::time::Instant;
+
+/// Aggregated statistics of a single operation type.
+#[repr(C)]
+#[derive(Clone, Copy, Debug)]
+pub struct OperationStats {
+    /// Numeric code of the operation (see `safe_core::metrics::Operation`)
+    pub operation: u32,
+    /// Number of requests sent
+    pub started: u64,
+    /// Number of requests which succeeded
+    pub succeeded: u64,
+    /// Number of requests which failed
+    pub failed: u64,
+    /// Total number of bytes sent or received
+    pub bytes: u64,
+    /// Total latency of the completed requests in milliseconds
+    pub latency_ms: u64,
+}
+
+/// Start collecting metrics of the network operations performed by the app.
+///
+/// Callback parameters: user data, error code
+#[no_mangle]
+pub unsafe extern "C" fn app_metrics_enable(
+    app: *const App,
+    user_data: *mut c_void,
+    o_cb: extern "C" fn(user_data: *mut c_void, result: FfiResult),
+) {
+    catch_unwind_cb(user_data, o_cb, || {
+        send_sync(app, user_data, o_cb, |client, context| {
+            client.set_metrics(context.metrics().clone());
+            Ok(())
+        })
+    })
+}
+
+/// Get the metrics collected since they were enabled or last reset.
+///
+/// Callback parameters: user data, error code, stats vector, vector size
+#[no_mangle]
+pub unsafe extern "C" fn app_metrics(
+    app: *const App,
+    user_data: *mut c_void,
+    o_cb: extern "C" fn(user_data: *mut c_void,
+                        result: FfiResult,
+                        stats: *const OperationStats,
+                        stats_len: usize),
+) {
+    catch_unwind_cb(user_data, o_cb, || {
+        let user_data = OpaqueCtx(user_data);
+
+        (*app).send(move |_, context| {
+            let stats: Vec<_> = context
+                .metrics()
+                .stats()
+                .into_iter()
+                .map(|(op, stats)| {
+                    OperationStats {
+                        operation: op.code(),
+                        started: stats.started,
+                        succeeded: stats.succeeded,
+                        failed: stats.failed,
+                        bytes: stats.bytes,
+                        latency_ms: stats.latency.as_secs() * 1000 +
+                            u64::from(stats.latency.subsec_nanos() / 1_000_000),
+                    }
+                })
+                .collect();
+
+            o_cb(user_data.0, FFI_RESULT_OK, stats.as_ptr(), stats.len());
+            None
+        })
+    })
+}
+
+/// Reset the collected metrics.
+///
+/// Callback parameters: user data, error code
+#[no_mangle]
+pub unsafe extern "C" fn app_metrics_reset(
+    app: *const App,
+    user_data: *mut c_void,
+    o_cb: extern "C" fn(user_data: *mut c_void, result: FfiResult),
+) {
+    catch_unwind_cb(user_data, o_cb, || {
+        send_sync(app, user_data, o_cb, |_, context| {
+            context.metrics().reset();
+            Ok(())
+        })
+    })
+}
+
+/// Get the totals of the network operations performed by the app during this
+/// session, serialised `safe_core::metrics::SessionStats`. Unlike the metrics
+/// above, these are always collected.
+///
+/// Callback parameters: user data, error code, serialised stats, size
+#[no_mangle]
+pub unsafe extern "C" fn app_session_stats(
+    app: *const App,
+    user_data: *mut c_void,
+    o_cb: extern "C" fn(user_data: *mut c_void,
+                        result: FfiResult,
+                        stats_ptr: *const u8,
+                        stats_len: usize),
+) {
+    catch_unwind_cb(user_data, o_cb, || {
+        let user_data = OpaqueCtx(user_data);
+
+        (*app).send(move |client, _| {
+            let stats = try_cb!(
+                serialise(&client.stats()).map_err(AppError::from),
+                user_data,
+                o_cb
+            );
+            o_cb(user_data.0, FFI_RESULT_OK, stats.as_ptr(), stats.len());
+            None
+        })
+    })
+}
+
+/// Describe the requests of the app sent to the network and not yet
+/// completed, one per line, oldest first. Meant for debugging responses which
+/// never arrive.
+///
+/// Callback parameters: user data, error code, description
+#[cfg(any(test, feature = "testing"))]
+#[no_mangle]
+pub unsafe extern "C" fn app_dump_pending_requests(
+    app: *const App,
+    user_data: *mut c_void,
+    o_cb: extern "C" fn(user_data: *mut c_void, result: FfiResult, dump: *const c_char),
+) {
+    catch_unwind_cb(user_data, o_cb, || {
+        let user_data = OpaqueCtx(user_data);
+
+        (*app).send(move |client, _| {
+            let now = Instant::now();
+            let dump: String = client
+                .pending_requests()
+                .into_iter()
+                .map(|request| {
+                    let age = now.duration_since(request.sent_at);
+                    format!(
+                        "{:?} {:?} attempt {} {:?} for {} ms\n",
+                        request.msg_id,
+                        request.operation,
+                        request.attempt,
+                        request.state,
+                        age.as_secs() * 1000 + u64::from(age.subsec_nanos() / 1_000_000)
+                    )
+                })
+                .collect();
+
+            let dump = try_cb!(CString::new(dump).map_err(AppError::from), user_data, o_cb);
+            o_cb(user_data.0, FFI_RESULT_OK, dump.as_ptr());
+            None
+        })
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ffi_utils::test_utils::{call_0, call_vec_u8, send_via_user_data, sender_as_user_data};
+    use futures::Future;
+    use maidsafe_utilities::serialisation::deserialise;
+    use safe_core::metrics::{Operation, SessionStats};
+    use std::slice;
+    use std::sync::mpsc;
+    use std::time::Duration;
+    use test_utils::create_app;
+
+    // Collect the metrics of the app's requests.
+    #[test]
+    fn collect_metrics() {
+        let app = create_app();
+
+        unsafe {
+            unwrap!(call_0(|ud, cb| app_metrics_enable(&app, ud, cb)));
+        }
+
+        unwrap!(app.account_info().wait());
+
+        let stats = get_metrics(&app);
+        let op = Operation::GetAccountInfo.code();
+        let account_info = unwrap!(stats.iter().find(|stats| stats.operation == op));
+        assert_eq!(account_info.started, 1);
+        assert_eq!(account_info.succeeded, 1);
+
+        unsafe {
+            unwrap!(call_0(|ud, cb| app_metrics_reset(&app, ud, cb)));
+        }
+        assert!(get_metrics(&app).is_empty());
+    }
+
+    // Session totals are available without enabling the metrics.
+    #[test]
+    fn session_stats() {
+        let app = create_app();
+
+        let stats = unsafe { unwrap!(call_vec_u8(|ud, cb| app_session_stats(&app, ud, cb))) };
+        let before: SessionStats = unwrap!(deserialise(&stats));
+
+        unwrap!(app.account_info().wait());
+
+        let stats = unsafe { unwrap!(call_vec_u8(|ud, cb| app_session_stats(&app, ud, cb))) };
+        let after: SessionStats = unwrap!(deserialise(&stats));
+        assert_eq!(after.gets, before.gets + 1);
+        assert_eq!(after.completed, before.completed + 1);
+        assert_eq!(after.puts, before.puts);
+    }
+
+    // The requests in flight are listed in the dump.
+    #[cfg(feature = "use-mock-routing")]
+    #[test]
+    fn dump_pending_requests() {
+        use ffi_utils::test_utils::call_1;
+        use rand;
+        use safe_core::FutureExt;
+        use std::thread;
+        use test_utils::run_now;
+
+        let app = create_app();
+
+        let dump: String =
+            unsafe { unwrap!(call_1(|ud, cb| app_dump_pending_requests(&app, ud, cb))) };
+        assert!(dump.is_empty());
+
+        unwrap!(app.send(|client, _| {
+            client.set_simulate_timeout(true);
+            Some(client.get_idata(rand::random()).then(|_| Ok::<_, ()>(())).into_box())
+        }));
+
+        // Wait until the request is actually in flight.
+        while run_now(&app, |client, _| client.pending_requests().is_empty()) {
+            thread::sleep(Duration::from_millis(10));
+        }
+
+        let dump: String =
+            unsafe { unwrap!(call_1(|ud, cb| app_dump_pending_requests(&app, ud, cb))) };
+        assert_eq!(dump.lines().count(), 1);
+        assert!(dump.contains("GetIData"));
+    }
+
+    fn get_metrics(app: &App) -> Vec<OperationStats> {
+        let (tx, rx) = mpsc::channel::<Vec<OperationStats>>();
+
+        extern "C" fn cb(
+            user_data: *mut c_void,
+            result: FfiResult,
+            stats: *const OperationStats,
+            stats_len: usize,
+        ) {
+            assert_eq!(result.error_code, 0);
+            let stats = unsafe { slice::from_raw_parts(stats, stats_len).to_vec() };
+            unsafe { send_via_user_data(user_data, stats) }
+        }
+
+        unsafe { app_metrics(app, sender_as_user_data(&tx), cb) };
+        unwrap!(rx.recv_timeout(Duration::from_secs(15)))
+    }
+}
@@ -21,6 +21,15 @@
 
 /// Access container
 pub mod access_container;
+/// Append-only logs
+pub mod append_log;
+/// Cancellation of in-flight operations
+pub mod cancel;
+/// Experimental append-only data
+#[cfg(feature = "unstable-data-types")]
+pub mod appendable_data;
+/// Messaging channel between apps
+pub mod channel;
 /// Cipher Options
 pub mod cipher_opt;
 /// Low level manipulation of `ImmutableData`
@@ -29,32 +38,52 @@ pub mod immutable_data;
 pub mod ipc;
 /// Logging operations
 pub mod logging;
+/// User-to-user messaging
+pub mod messaging;
+/// Metrics of the network operations
+pub mod metrics;
 /// `MDataInfo` operations
 pub mod mdata_info;
 /// Crypto-related routines
 pub mod crypto;
+/// Public names (DNS) and their services
+pub mod dns;
+/// Fetching content by `safe://` URL
+pub mod fetch;
 /// Low level manipulation of `MutableData`
 pub mod mutable_data;
 /// NFS API
 pub mod nfs;
+/// Capacity and pinning of the object cache
+pub mod object_cache;
+/// Polling-based alternative to callbacks
+pub mod poll;
+/// Public profile of the user
+pub mod profile;
+/// Containers shared among a group of users
+pub mod shared_container;
 
 mod helper;
 #[cfg(test)]
 mod tests;
 
 use super::App;
-use super::errors::AppError;
+use super::backpressure::OverflowPolicy;
+use super::errors::{AppError, error_catalog_json, error_code_c_name};
 use config_file_handler;
-use ffi_utils::{FFI_RESULT_OK, FfiResult, OpaqueCtx, ReprC, catch_unwind_cb, from_c_str};
+use ffi_utils::{FFI_RESULT_OK, FfiResult, OpaqueCtx, ReprC, catch_unwind_cb, from_c_str,
+                last_error_description};
 use futures::Future;
 use maidsafe_utilities::serialisation::deserialise;
-use safe_core::{FutureExt, NetworkEvent};
+use safe_core::{FutureExt, NetworkEvent, Priority, RetryPolicy, config};
 use safe_core::ffi::AccountInfo as FfiAccountInfo;
 use safe_core::ffi::ipc::resp::AuthGranted as FfiAuthGranted;
 use safe_core::ipc::{AuthGranted, BootstrapConfig};
+use safe_core::ipc::uri_scheme;
 use std::ffi::{CStr, CString, OsStr};
 use std::os::raw::{c_char, c_void};
 use std::slice;
+use std::time::Duration;
 
 /// Create unregistered app.
 /// The `user_data` parameter corresponds to the first parameter of the
@@ -182,6 +211,176 @@ pub unsafe extern "C" fn app_account_info(
     })
 }
 
+/// Raise a low balance network event (passed to the network observer
+/// callback) when the number of mutations available to the account drops
+/// below `threshold`. Zero disables the warning.
+///
+/// Callback parameters: user data, error code
+#[no_mangle]
+pub unsafe extern "C" fn app_set_low_balance_threshold(
+    app: *mut App,
+    threshold: u64,
+    user_data: *mut c_void,
+    o_cb: extern "C" fn(user_data: *mut c_void, result: FfiResult),
+) {
+    catch_unwind_cb(user_data, o_cb, || -> Result<_, AppError> {
+        let user_data = OpaqueCtx(user_data);
+        (*app).send(move |client, _| {
+            let threshold = if threshold == 0 { None } else { Some(threshold) };
+            client.set_low_balance_threshold(threshold);
+            o_cb(user_data.0, FFI_RESULT_OK);
+            None
+        })
+    })
+}
+
+/// Retry the requests failed with transient errors (timeouts and network
+/// errors) up to `max_attempts` times in total, waiting `initial_backoff_ms`
+/// milliseconds before the first retry and doubling the wait before each
+/// further one, up to `max_backoff_ms`. Only reads and the mutations checked
+/// against the version of the data are retried. `max_attempts` of 1 or less
+/// disables the retries.
+///
+/// Callback parameters: user data, error code
+#[no_mangle]
+pub unsafe extern "C" fn app_set_retry_policy(
+    app: *mut App,
+    max_attempts: u32,
+    initial_backoff_ms: u64,
+    max_backoff_ms: u64,
+    user_data: *mut c_void,
+    o_cb: extern "C" fn(user_data: *mut c_void, result: FfiResult),
+) {
+    catch_unwind_cb(user_data, o_cb, || -> Result<_, AppError> {
+        let user_data = OpaqueCtx(user_data);
+        (*app).send(move |client, _| {
+            let policy = if max_attempts > 1 {
+                Some(RetryPolicy {
+                    max_attempts,
+                    initial_backoff: Duration::from_millis(initial_backoff_ms),
+                    max_backoff: Duration::from_millis(max_backoff_ms),
+                })
+            } else {
+                None
+            };
+            client.set_retry_policy(policy);
+            o_cb(user_data.0, FFI_RESULT_OK);
+            None
+        })
+    })
+}
+
+/// Limit the number of operations of the app in flight to `max`, protecting
+/// the memory of the device from apps firing requests faster than the network
+/// serves them. Operations over the limit fail with the "too many requests"
+/// error, or if `block` is true, block the calling thread until another
+/// operation completes (operations started from callbacks always fail, to not
+/// block the event loop). Zero removes the limit.
+///
+/// Callback parameters: user data, error code
+#[no_mangle]
+pub unsafe extern "C" fn app_set_request_limit(
+    app: *mut App,
+    max: u32,
+    block: bool,
+    user_data: *mut c_void,
+    o_cb: extern "C" fn(user_data: *mut c_void, result: FfiResult),
+) {
+    catch_unwind_cb(user_data, o_cb, || -> Result<_, AppError> {
+        let max = if max == 0 { None } else { Some(max as usize) };
+        let policy = if block {
+            OverflowPolicy::Block
+        } else {
+            OverflowPolicy::Reject
+        };
+
+        (*app).set_request_limit(max, policy);
+        o_cb(user_data, FFI_RESULT_OK);
+        Ok(())
+    })
+}
+
+/// Limit the number of requests sent to the network at the same time to `max`,
+/// queueing the others by their priority (see `app_set_request_priority`).
+/// Zero removes the limit.
+///
+/// Callback parameters: user data, error code
+#[no_mangle]
+pub unsafe extern "C" fn app_set_max_requests_in_flight(
+    app: *const App,
+    max: u32,
+    user_data: *mut c_void,
+    o_cb: extern "C" fn(user_data: *mut c_void, result: FfiResult),
+) {
+    catch_unwind_cb(user_data, o_cb, || -> Result<_, AppError> {
+        let user_data = OpaqueCtx(user_data);
+        (*app).send(move |client, _| {
+            let max = if max == 0 { None } else { Some(max as usize) };
+            client.set_max_requests_in_flight(max);
+            o_cb(user_data.0, FFI_RESULT_OK);
+            None
+        })
+    })
+}
+
+/// Set the priority of the requests made by the subsequent calls: 0 for high
+/// (e.g. reads the user is waiting for), 1 for normal (the default) and 2 for
+/// low (e.g. background uploads). Only matters when the number of requests in
+/// flight is limited.
+///
+/// Callback parameters: user data, error code
+#[no_mangle]
+pub unsafe extern "C" fn app_set_request_priority(
+    app: *const App,
+    priority: u32,
+    user_data: *mut c_void,
+    o_cb: extern "C" fn(user_data: *mut c_void, result: FfiResult),
+) {
+    catch_unwind_cb(user_data, o_cb, || -> Result<_, AppError> {
+        let priority = match priority {
+            0 => Priority::High,
+            1 => Priority::Normal,
+            2 => Priority::Low,
+            _ => return Err(AppError::from("Invalid request priority")),
+        };
+
+        let user_data = OpaqueCtx(user_data);
+        (*app).send(move |client, _| {
+            client.set_priority(priority);
+            o_cb(user_data.0, FFI_RESULT_OK);
+            None
+        })
+    })
+}
+
+/// Set the time the requests made by the subsequent calls wait for their
+/// response, in milliseconds, before failing with the "request timeout" error.
+/// Zero restores the configured default.
+///
+/// Callback parameters: user data, error code
+#[no_mangle]
+pub unsafe extern "C" fn app_set_request_timeout(
+    app: *const App,
+    timeout_ms: u64,
+    user_data: *mut c_void,
+    o_cb: extern "C" fn(user_data: *mut c_void, result: FfiResult),
+) {
+    catch_unwind_cb(user_data, o_cb, || -> Result<_, AppError> {
+        let timeout = if timeout_ms == 0 {
+            config::get().get_request_timeout()
+        } else {
+            Duration::from_millis(timeout_ms)
+        };
+
+        let user_data = OpaqueCtx(user_data);
+        (*app).send(move |client, _| {
+            client.set_timeout(timeout);
+            o_cb(user_data.0, FFI_RESULT_OK);
+            None
+        })
+    })
+}
+
 /// Returns the expected name for the application executable without an extension
 #[no_mangle]
 pub unsafe extern "C" fn app_exe_file_stem(
@@ -209,6 +408,7 @@ pub unsafe extern "C" fn app_exe_file_stem(
 }
 
 /// Sets the additional path in `config_file_handler` to to search for files
+/// and re-reads the `safe_core.config` configuration file.
 #[no_mangle]
 pub unsafe extern "C" fn app_set_additional_search_path(
     new_path: *const c_char,
@@ -218,11 +418,117 @@ pub unsafe extern "C" fn app_set_additional_search_path(
     catch_unwind_cb(user_data, o_cb, || -> Result<_, AppError> {
         let new_path = CStr::from_ptr(new_path).to_str()?;
         config_file_handler::set_additional_search_path(OsStr::new(new_path));
+        if let Err(err) = config::reload() {
+            warn!("Couldn't reload the configuration: {:?}", err);
+        }
+        o_cb(user_data, FFI_RESULT_OK);
+        Ok(())
+    });
+}
+
+/// Register the current executable as the handler of the given URI scheme
+/// (e.g. the scheme the authenticator uses to send responses to this app).
+/// `name` is the human readable name of the app.
+#[no_mangle]
+pub unsafe extern "C" fn app_register_uri_scheme(
+    scheme: *const c_char,
+    name: *const c_char,
+    user_data: *mut c_void,
+    o_cb: extern "C" fn(user_data: *mut c_void, result: FfiResult),
+) {
+    catch_unwind_cb(user_data, o_cb, || -> Result<_, AppError> {
+        let scheme = CStr::from_ptr(scheme).to_str()?;
+        let name = CStr::from_ptr(name).to_str()?;
+        uri_scheme::register(scheme, name)?;
+        o_cb(user_data, FFI_RESULT_OK);
+        Ok(())
+    });
+}
+
+/// Remove the registration of the current executable as the handler of the
+/// given URI scheme.
+#[no_mangle]
+pub unsafe extern "C" fn app_deregister_uri_scheme(
+    scheme: *const c_char,
+    user_data: *mut c_void,
+    o_cb: extern "C" fn(user_data: *mut c_void, result: FfiResult),
+) {
+    catch_unwind_cb(user_data, o_cb, || -> Result<_, AppError> {
+        let scheme = CStr::from_ptr(scheme).to_str()?;
+        uri_scheme::deregister(scheme)?;
         o_cb(user_data, FFI_RESULT_OK);
         Ok(())
     });
 }
 
+/// Returns the catalog of all error codes as a JSON array of objects with
+/// `name`, `code` and `description` fields.
+///
+/// Callback parameters: user data, error code, catalog JSON
+#[no_mangle]
+pub unsafe extern "C" fn app_error_catalog(
+    user_data: *mut c_void,
+    o_cb: extern "C" fn(user_data: *mut c_void,
+                        result: FfiResult,
+                        catalog_json: *const c_char),
+) {
+    catch_unwind_cb(user_data, o_cb, || -> Result<_, AppError> {
+        let catalog_json = CString::new(error_catalog_json())?;
+        o_cb(user_data, FFI_RESULT_OK, catalog_json.as_ptr());
+        Ok(())
+    });
+}
+
+/// Returns the symbolic name (e.g. `ERR_NO_SUCH_ENTRY`) of the error code, as
+/// listed in the catalog returned by `app_error_catalog`, or null if the code is
+/// unknown. The name is a static NUL-terminated string, so the caller must NOT
+/// free it.
+#[no_mangle]
+pub extern "C" fn ffi_error_code_name(code: i32) -> *const c_char {
+    error_code_c_name(code)
+}
+
+/// Returns the human-readable description of the most recent error reported
+/// by a function of this library which returns its error code directly instead
+/// of through a callback (e.g. `operation_status`). Errors reported through a
+/// callback carry their description in the `FfiResult` already. The
+/// description is tracked per thread and is empty if no error occurred yet.
+///
+/// Callback parameters: user data, error code, error description
+#[no_mangle]
+pub unsafe extern "C" fn app_last_error_description(
+    user_data: *mut c_void,
+    o_cb: extern "C" fn(user_data: *mut c_void,
+                        result: FfiResult,
+                        description: *const c_char),
+) {
+    catch_unwind_cb(user_data, o_cb, || -> Result<_, AppError> {
+        let description = CString::new(last_error_description().unwrap_or_default())?;
+        o_cb(user_data, FFI_RESULT_OK, description.as_ptr());
+        Ok(())
+    });
+}
+
+/// Gracefully shut the app down. New operations are rejected, the ones in
+/// flight are given up to `timeout_ms` milliseconds to complete and call
+/// their callbacks, then the event loop is stopped. The callback is called
+/// once that's done; `app_free` still has to be called afterwards.
+///
+/// Callback parameters: user data, error code
+#[no_mangle]
+pub unsafe extern "C" fn app_shutdown(
+    app: *mut App,
+    timeout_ms: u64,
+    user_data: *mut c_void,
+    o_cb: extern "C" fn(user_data: *mut c_void, result: FfiResult),
+) {
+    catch_unwind_cb(user_data, o_cb, || -> Result<_, AppError> {
+        let user_data = OpaqueCtx(user_data);
+        let timeout = Duration::from_millis(timeout_ms);
+        (*app).shutdown(timeout, move || o_cb(user_data.0, FFI_RESULT_OK))
+    })
+}
+
 /// Discard and clean up the previously allocated app instance.
 /// Use this only if the app is obtained from one of the auth
 /// functions in this crate. Using `app` after a call to this
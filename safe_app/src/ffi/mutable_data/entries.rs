@@ -17,18 +17,36 @@
 
 //! FFI for mutable data entries, keys and values.
 
-use App;
+use {App, AppContext, AppFuture};
 use errors::AppError;
-use ffi::helper::send_sync;
+use ffi::helper::{deserialise_versioned, send_sync, send_with_mdata_info, serialise_versioned};
 use ffi_utils::{FFI_RESULT_OK, FfiResult, OpaqueCtx, SafePtr, catch_unwind_cb,
                 vec_clone_from_raw_parts};
 use ffi_utils::callback::Callback;
-use object_cache::{MDataEntriesHandle, MDataKeysHandle, MDataValuesHandle};
+use futures::Future;
+use futures::future::{self, Loop};
+use object_cache::{MDataEntriesHandle, MDataEntriesIteratorHandle, MDataInfoHandle,
+                   MDataKeysHandle, MDataValuesHandle};
 use routing::{ClientError, Value};
-use safe_core::CoreError;
-use std::collections::{BTreeMap, BTreeSet};
+use safe_core::{Client, CoreError, FutureExt, MDataEntryPages};
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
 use std::os::raw::c_void;
 
+/// Number of entries the entries iterator fetches from the network at once.
+pub const ENTRIES_ITER_PAGE_SIZE: usize = 100;
+
+/// Lazy walk over the entries of a `MutableData`.
+///
+/// The keys are listed when the iterator is created, the values are fetched
+/// a page of `ENTRIES_ITER_PAGE_SIZE` entries at a time, as the iterator
+/// advances, so only the current page is held in memory. Entries inserted
+/// after the iterator was created are not visited and deleted entries are
+/// skipped.
+pub struct MDataEntriesIter {
+    pages: MDataEntryPages,
+    page: VecDeque<(Vec<u8>, Value)>,
+}
+
 /// Create new empty entries.
 ///
 /// Callback parameters: user data, error code, entries handle
@@ -181,6 +199,65 @@ pub unsafe extern "C" fn mdata_entries_for_each(
     })
 }
 
+/// Serialise the entries into a versioned blob, e.g. for passing to another
+/// process.
+///
+/// Callback parameters: user data, error code, serialised entries
+#[no_mangle]
+pub unsafe extern "C" fn mdata_entries_serialise(
+    app: *const App,
+    entries_h: MDataEntriesHandle,
+    user_data: *mut c_void,
+    o_cb: extern "C" fn(user_data: *mut c_void,
+                        result: FfiResult,
+                        encoded_ptr: *const u8,
+                        encoded_len: usize),
+) {
+    catch_unwind_cb(user_data, o_cb, || {
+        let user_data = OpaqueCtx(user_data);
+
+        (*app).send(move |_, context| {
+            let entries = try_cb!(
+                context.object_cache().get_mdata_entries(entries_h),
+                user_data,
+                o_cb
+            );
+            let encoded = try_cb!(serialise_versioned(&*entries), user_data, o_cb);
+
+            o_cb(
+                user_data.0,
+                FFI_RESULT_OK,
+                encoded.as_safe_ptr(),
+                encoded.len(),
+            );
+            None
+        })
+    })
+}
+
+/// Deserialise the entries serialised by `mdata_entries_serialise`.
+///
+/// Callback parameters: user data, error code, entries handle
+#[no_mangle]
+pub unsafe extern "C" fn mdata_entries_deserialise(
+    app: *const App,
+    ptr: *const u8,
+    len: usize,
+    user_data: *mut c_void,
+    o_cb: extern "C" fn(user_data: *mut c_void,
+                        result: FfiResult,
+                        entries_h: MDataEntriesHandle),
+) {
+    catch_unwind_cb(user_data, o_cb, || {
+        let encoded = vec_clone_from_raw_parts(ptr, len);
+
+        send_sync(app, user_data, o_cb, move |_, context| {
+            let entries = deserialise_versioned(&encoded)?;
+            Ok(context.object_cache().insert_mdata_entries(entries))
+        })
+    })
+}
+
 /// Free the entries from memory.
 ///
 /// Callback parameters: user data, error code
@@ -199,6 +276,108 @@ pub unsafe extern "C" fn mdata_entries_free(
     })
 }
 
+/// Create an iterator over the entries of the mutable data, starting before
+/// the first key. All the keys are listed right away, the values are fetched
+/// from the network lazily, as the iterator advances.
+///
+/// Callback parameters: user data, error code, entries iterator handle
+#[no_mangle]
+pub unsafe extern "C" fn mdata_entries_iter_new(
+    app: *const App,
+    info_h: MDataInfoHandle,
+    user_data: *mut c_void,
+    o_cb: extern "C" fn(user_data: *mut c_void,
+                        result: FfiResult,
+                        iter_h: MDataEntriesIteratorHandle),
+) {
+    catch_unwind_cb(user_data, o_cb, || {
+        send_with_mdata_info(app, info_h, user_data, o_cb, |client, context, info| {
+            let context = context.clone();
+
+            client
+                .list_mdata_entry_pages(info.name, info.type_tag, None)
+                .map(move |pages| {
+                    context.object_cache().insert_mdata_entries_iter(
+                        MDataEntriesIter {
+                            pages: pages,
+                            page: VecDeque::new(),
+                        },
+                    )
+                })
+        })
+    })
+}
+
+/// Advance the iterator by one entry, fetching the next page of entries from
+/// the network if needed. The iterator must not be advanced again before
+/// `o_done_cb` is called.
+///
+/// The `o_entry_cb` callback is invoked with the next entry, if there is one,
+/// passing user data, pointer to key, key length, pointer to value, value length
+/// and entry version in that order. The caller must NOT free the pointers.
+///
+/// The `o_done_cb` callback is invoked afterwards, or in case of error, with
+/// user data, error code and a flag which is `true` once the iterator is
+/// exhausted (in which case `o_entry_cb` was not called).
+#[no_mangle]
+pub unsafe extern "C" fn mdata_entries_iter_next(
+    app: *const App,
+    iter_h: MDataEntriesIteratorHandle,
+    user_data: *mut c_void,
+    o_entry_cb: extern "C" fn(user_data: *mut c_void,
+                              key_ptr: *const u8,
+                              key_len: usize,
+                              value_ptr: *const u8,
+                              value_len: usize,
+                              entry_version: u64),
+    o_done_cb: extern "C" fn(user_data: *mut c_void, result: FfiResult, done: bool),
+) {
+    catch_unwind_cb(user_data, o_done_cb, || {
+        let user_data = OpaqueCtx(user_data);
+
+        (*app).send(move |client, context| {
+            next_entry(client, context, iter_h)
+                .map(move |entry| match entry {
+                    Some((key, value)) => {
+                        o_entry_cb(
+                            user_data.0,
+                            key.as_safe_ptr(),
+                            key.len(),
+                            value.content.as_safe_ptr(),
+                            value.content.len(),
+                            value.entry_version,
+                        );
+                        o_done_cb(user_data.0, FFI_RESULT_OK, false);
+                    }
+                    None => o_done_cb(user_data.0, FFI_RESULT_OK, true),
+                })
+                .map_err(move |err| {
+                    call_result_cb!(Err::<(), _>(err), user_data, o_done_cb);
+                })
+                .into_box()
+                .into()
+        })
+    })
+}
+
+/// Free the entries iterator from memory.
+///
+/// Callback parameters: user data, error code
+#[no_mangle]
+pub unsafe extern "C" fn mdata_entries_iter_free(
+    app: *const App,
+    iter_h: MDataEntriesIteratorHandle,
+    user_data: *mut c_void,
+    o_cb: extern "C" fn(user_data: *mut c_void, result: FfiResult),
+) {
+    catch_unwind_cb(user_data, o_cb, || {
+        send_sync(app, user_data, o_cb, move |_, context| {
+            let _ = context.object_cache().remove_mdata_entries_iter(iter_h)?;
+            Ok(())
+        })
+    })
+}
+
 /// Returns the number of keys.
 ///
 /// Callback parameters: user data, error code, length
@@ -380,6 +559,43 @@ where
     })
 }
 
+// Pop the next entry of the iterator, fetching further pages from the network
+// once the current one is exhausted. Pages can come back empty if all their
+// entries were deleted.
+fn next_entry(
+    client: &Client<AppContext>,
+    context: &AppContext,
+    iter_h: MDataEntriesIteratorHandle,
+) -> Box<AppFuture<Option<(Vec<u8>, Value)>>> {
+    let client = client.clone();
+    let context = context.clone();
+
+    future::loop_fn((), move |_| {
+        let pages = {
+            let mut iter = fry!(context.object_cache().get_mdata_entries_iter(iter_h));
+            if let Some(entry) = iter.page.pop_front() {
+                return ok!(Loop::Break(Some(entry)));
+            }
+            if iter.pages.is_done() {
+                return ok!(Loop::Break(None));
+            }
+            iter.pages.clone()
+        };
+        let context = context.clone();
+
+        client
+            .next_mdata_entry_page(pages, ENTRIES_ITER_PAGE_SIZE)
+            .map_err(AppError::from)
+            .and_then(move |(page, pages)| {
+                let mut iter = context.object_cache().get_mdata_entries_iter(iter_h)?;
+                iter.pages = pages;
+                iter.page = page.into_iter().collect();
+                Ok(Loop::Continue(()))
+            })
+            .into_box()
+    }).into_box()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -387,9 +603,10 @@ mod tests {
     use ffi::mutable_data::*;
     use ffi::mutable_data::entry_actions::*;
     use ffi::mutable_data::permissions::*;
+    use ffi_utils::ErrorCode;
     use ffi_utils::test_utils::{call_0, call_1, send_via_user_data, sender_as_user_data};
     use ffi_utils::vec_clone_from_raw_parts;
-    use object_cache::{MDataEntryActionsHandle, MDataInfoHandle};
+    use object_cache::{MDataEntryActionsHandle, MDataInfoHandle, MDataPermissionsHandle};
     use routing::Value;
     use safe_core::utils;
     use std::collections::BTreeMap;
@@ -563,6 +780,106 @@ mod tests {
         }
     }
 
+    // Test walking mdata entries one at a time through an iterator handle.
+    #[test]
+    fn entries_iterator() {
+        let app = create_app();
+
+        let key0 = b"key0".to_vec();
+        let key1 = b"key1".to_vec();
+        let key2 = b"key2".to_vec();
+
+        let value0 = Value {
+            content: unwrap!(utils::generate_random_vector(10)),
+            entry_version: 0,
+        };
+
+        let value1 = Value {
+            content: Vec::new(),
+            entry_version: 1,
+        };
+
+        let value2 = Value {
+            content: unwrap!(utils::generate_random_vector(10)),
+            entry_version: 3,
+        };
+
+        let entries = btree_map![key0.clone() => value0.clone(),
+                                 key1.clone() => value1,
+                                 key2.clone() => value2.clone()];
+
+        let entries_h = run_now(&app, move |_, context| {
+            context.object_cache().insert_mdata_entries(entries)
+        });
+        let perms_h: MDataPermissionsHandle =
+            unsafe { unwrap!(call_1(|ud, cb| mdata_permissions_new(&app, ud, cb))) };
+        let info_h: MDataInfoHandle =
+            unsafe { unwrap!(call_1(|ud, cb| mdata_info_random_public(&app, 10_000, ud, cb))) };
+
+        unsafe {
+            unwrap!(call_0(|ud, cb| mdata_put(&app, info_h, perms_h, entries_h, ud, cb)));
+            unwrap!(call_0(|ud, cb| mdata_entries_free(&app, entries_h, ud, cb)));
+        }
+
+        let iter_h: MDataEntriesIteratorHandle =
+            unsafe { unwrap!(call_1(|ud, cb| mdata_entries_iter_new(&app, info_h, ud, cb))) };
+
+        extern "C" fn entry_cb(
+            user_data: *mut c_void,
+            key_ptr: *const u8,
+            key_len: usize,
+            value_ptr: *const u8,
+            value_len: usize,
+            entry_version: u64,
+        ) {
+            unsafe {
+                let key = vec_clone_from_raw_parts(key_ptr, key_len);
+                let value = Value {
+                    content: vec_clone_from_raw_parts(value_ptr, value_len),
+                    entry_version: entry_version,
+                };
+
+                let user_data = user_data as *mut (Sender<bool>, Vec<(Vec<u8>, Value)>);
+                (*user_data).1.push((key, value));
+            }
+        }
+
+        extern "C" fn done_cb(user_data: *mut c_void, res: FfiResult, done: bool) {
+            assert_eq!(res.error_code, 0);
+            let user_data = user_data as *mut (Sender<bool>, Vec<(Vec<u8>, Value)>);
+
+            unsafe {
+                unwrap!((*user_data).0.send(done));
+            }
+        }
+
+        let (tx, rx) = mpsc::channel::<bool>();
+        let mut user_data = (tx, Vec::new());
+
+        // Two live entries, in key order, skipping the deleted one, then the
+        // iterator reports it's exhausted.
+        for expected_done in &[false, false, true, true] {
+            unsafe {
+                let user_data: *mut _ = &mut user_data;
+                mdata_entries_iter_next(&app, iter_h, user_data as *mut c_void, entry_cb, done_cb)
+            }
+            assert_eq!(unwrap!(rx.recv()), *expected_done);
+        }
+
+        assert_eq!(user_data.1, vec![(key0, value0), (key2, value2)]);
+
+        // Free
+        unsafe {
+            unwrap!(call_0(|ud, cb| mdata_entries_iter_free(&app, iter_h, ud, cb)));
+        }
+
+        // Freed iterators are removed from the object cache.
+        let freed = run_now(&app, move |_, context| {
+            context.object_cache().get_mdata_entries_iter(iter_h).is_err()
+        });
+        assert!(freed);
+    }
+
     // Test mdata keys operations.
     #[test]
     fn keys_and_values() {
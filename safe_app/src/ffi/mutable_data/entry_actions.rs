@@ -18,10 +18,14 @@
 //! FFI for mutable data entry actions.
 
 use App;
+use errors::AppError;
 use ffi::helper::send_sync;
-use ffi_utils::{FfiResult, catch_unwind_cb, vec_clone_from_raw_parts};
+use ffi_utils::{FFI_RESULT_OK, FfiResult, OpaqueCtx, SafePtr, catch_unwind_cb,
+                vec_clone_from_raw_parts};
+use maidsafe_utilities::serialisation::{deserialise, serialise};
 use object_cache::MDataEntryActionsHandle;
 use routing::{EntryAction, Value};
+use std::collections::BTreeMap;
 use std::os::raw::c_void;
 
 /// Create new entry actions.
@@ -106,6 +110,66 @@ pub unsafe extern "C" fn mdata_entry_actions_delete(
     })
 }
 
+/// Serialise the entry actions, so they can later be rebuilt in a single call
+/// with `mdata_entry_actions_from_bytes`.
+///
+/// Callback parameters: user data, error code, serialised entry actions
+#[no_mangle]
+pub unsafe extern "C" fn mdata_entry_actions_to_bytes(
+    app: *const App,
+    actions_h: MDataEntryActionsHandle,
+    user_data: *mut c_void,
+    o_cb: extern "C" fn(user_data: *mut c_void,
+                        result: FfiResult,
+                        encoded_ptr: *const u8,
+                        encoded_len: usize),
+) {
+    catch_unwind_cb(user_data, o_cb, || {
+        let user_data = OpaqueCtx(user_data);
+
+        (*app).send(move |_, context| {
+            let actions = try_cb!(
+                context.object_cache().get_mdata_entry_actions(actions_h),
+                user_data,
+                o_cb
+            );
+            let encoded = try_cb!(serialise(&*actions).map_err(AppError::from), user_data, o_cb);
+
+            o_cb(
+                user_data.0,
+                FFI_RESULT_OK,
+                encoded.as_safe_ptr(),
+                encoded.len(),
+            );
+            None
+        })
+    })
+}
+
+/// Create new entry actions from a serialised `BTreeMap<Vec<u8>, EntryAction>`,
+/// building the whole set in one call instead of one call per key.
+///
+/// Callback parameters: user data, error code, entry actions handle
+#[no_mangle]
+pub unsafe extern "C" fn mdata_entry_actions_from_bytes(
+    app: *const App,
+    ptr: *const u8,
+    len: usize,
+    user_data: *mut c_void,
+    o_cb: extern "C" fn(user_data: *mut c_void,
+                        result: FfiResult,
+                        entry_actions_h: MDataEntryActionsHandle),
+) {
+    catch_unwind_cb(user_data, o_cb, || {
+        let encoded = vec_clone_from_raw_parts(ptr, len);
+
+        send_sync(app, user_data, o_cb, move |_, context| {
+            let actions: BTreeMap<Vec<u8>, EntryAction> = deserialise(&encoded)?;
+            Ok(context.object_cache().insert_mdata_entry_actions(actions))
+        })
+    })
+}
+
 /// Free the entry actions from memory
 ///
 /// Callback parameters: user data, error code
@@ -152,7 +216,7 @@ unsafe fn add_action<F>(
 #[cfg(test)]
 mod tests {
     use super::*;
-    use ffi_utils::test_utils::{call_0, call_1};
+    use ffi_utils::test_utils::{call_0, call_1, call_vec_u8};
     use routing::{EntryAction, Value};
     use safe_core::utils;
     use test_utils::{create_app, run_now};
@@ -261,4 +325,57 @@ mod tests {
             )
         });
     }
+
+    // Test building entry actions from a serialised blob and back.
+    #[test]
+    fn to_and_from_bytes() {
+        let app = create_app();
+
+        let actions = btree_map![
+            b"key0".to_vec() => EntryAction::Ins(Value {
+                content: unwrap!(utils::generate_random_vector(10)),
+                entry_version: 0,
+            }),
+            b"key1".to_vec() => EntryAction::Update(Value {
+                content: unwrap!(utils::generate_random_vector(10)),
+                entry_version: 3,
+            }),
+            b"key2".to_vec() => EntryAction::Del(5)
+        ];
+        let encoded = unwrap!(serialise(&actions));
+
+        let handle = unsafe {
+            unwrap!(call_1(|ud, cb| {
+                mdata_entry_actions_from_bytes(&app, encoded.as_ptr(), encoded.len(), ud, cb)
+            }))
+        };
+
+        let expected = actions.clone();
+        run_now(&app, move |_, context| {
+            let actions = unwrap!(context.object_cache().get_mdata_entry_actions(handle));
+            assert_eq!(*actions, expected);
+        });
+
+        let reencoded = unsafe {
+            unwrap!(call_vec_u8(
+                |ud, cb| mdata_entry_actions_to_bytes(&app, handle, ud, cb),
+            ))
+        };
+        assert_eq!(unwrap!(deserialise::<BTreeMap<_, _>>(&reencoded)), actions);
+
+        // Garbage doesn't produce a handle.
+        let garbage = b"not entry actions".to_vec();
+        let res: Result<MDataEntryActionsHandle, _> = unsafe {
+            call_1(|ud, cb| {
+                mdata_entry_actions_from_bytes(&app, garbage.as_ptr(), garbage.len(), ud, cb)
+            })
+        };
+        assert!(res.is_err());
+
+        unsafe {
+            unwrap!(call_0(
+                |ud, cb| mdata_entry_actions_free(&app, handle, ud, cb),
+            ))
+        };
+    }
 }
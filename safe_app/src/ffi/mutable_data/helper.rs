@@ -18,7 +18,7 @@
 use errors::AppError;
 use ffi::mutable_data::permissions::USER_ANYONE;
 use object_cache::{MDataPermissionsHandle, ObjectCache, SignKeyHandle};
-use routing::{PermissionSet, User};
+use routing::{EntryAction, EntryActions, PermissionSet, User, Value};
 use std::collections::BTreeMap;
 
 // Retrieve the sign key corresponding to the handle from the object cache and wrap it
@@ -50,18 +50,28 @@ pub fn insert_permissions(
     object_cache.insert_mdata_permissions(permissions)
 }
 
-// Retrieve permissions from the object cache.
-pub fn get_permissions(
-    object_cache: &ObjectCache,
-    handle: MDataPermissionsHandle,
-) -> Result<BTreeMap<User, PermissionSet>, AppError> {
-    let input = object_cache.get_mdata_permissions(handle)?.clone();
-    let mut output = BTreeMap::new();
-
-    for (user, permission_set_h) in input {
-        let permission_set = *object_cache.get_mdata_permission_set(permission_set_h)?;
-        let _ = output.insert(user, permission_set);
-    }
-
-    Ok(output)
+// Adjust the entry actions to the current entries: the versions are set to
+// succeed the current ones, inserts of existing entries become updates and
+// vice versa, and deletes of missing entries are dropped.
+pub fn rebase_entry_actions(
+    actions: &BTreeMap<Vec<u8>, EntryAction>,
+    entries: &BTreeMap<Vec<u8>, Value>,
+) -> EntryActions {
+    actions.iter().fold(
+        EntryActions::new(),
+        |result, (key, action)| match (action, entries.get(key)) {
+            (&EntryAction::Ins(ref value), Some(current)) |
+            (&EntryAction::Update(ref value), Some(current)) => {
+                result.update(key.clone(), value.content.clone(), current.entry_version + 1)
+            }
+            (&EntryAction::Ins(ref value), None) |
+            (&EntryAction::Update(ref value), None) => {
+                result.ins(key.clone(), value.content.clone(), 0)
+            }
+            (&EntryAction::Del(_), Some(current)) if !current.content.is_empty() => {
+                result.del(key.clone(), current.entry_version + 1)
+            }
+            (&EntryAction::Del(_), _) => result,
+        },
+    )
 }
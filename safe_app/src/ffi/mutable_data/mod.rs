@@ -25,16 +25,19 @@ mod tests;
 
 use App;
 use errors::AppError;
-use ffi::helper::send_with_mdata_info;
+use ffi::helper::{get_permissions, send_cancellable_with_mdata_info, send_with_mdata_info};
 use ffi_utils::{FFI_RESULT_OK, FfiResult, OpaqueCtx, SafePtr, catch_unwind_cb,
-                vec_clone_from_raw_parts};
-use futures::Future;
+                catch_unwind_error_code, vec_clone_from_raw_parts};
+use futures::{Future, Stream};
 use object_cache::{MDataEntriesHandle, MDataEntryActionsHandle, MDataInfoHandle, MDataKeysHandle,
                    MDataPermissionSetHandle, MDataPermissionsHandle, MDataValuesHandle,
                    SignKeyHandle};
+use operations::OperationId;
 use routing::MutableData;
 use safe_core::{CoreError, FutureExt};
 use std::os::raw::c_void;
+use std::ptr;
+use std::time::Duration;
 
 /// Special value that represents an empty permission set.
 #[no_mangle]
@@ -75,7 +78,7 @@ pub unsafe extern "C" fn mdata_put(
 
             let permissions = if permissions_h != 0 {
                 try_cb!(
-                    helper::get_permissions(context.object_cache(), permissions_h),
+                    get_permissions(context.object_cache(), permissions_h),
                     user_data,
                     o_cb
                 )
@@ -119,6 +122,70 @@ pub unsafe extern "C" fn mdata_put(
     })
 }
 
+/// Create new private mutable data owned by the app and put it on the network.
+///
+/// The entries given by `entries_h` are in plain text and get encrypted with
+/// freshly generated keys, which are returned as a new mdata info handle. The
+/// mdata info should be stored (e.g. in a container), as it is needed to read
+/// the data back.
+///
+/// `permissions_h` and `entries_h` can be `PERMISSIONS_EMPTY` and
+/// `ENTRIES_EMPTY`, as in `mdata_put`.
+///
+/// Callback parameters: user data, error code, mdata info handle
+#[no_mangle]
+pub unsafe extern "C" fn mdata_put_private(
+    app: *const App,
+    type_tag: u64,
+    permissions_h: MDataPermissionsHandle,
+    entries_h: MDataEntriesHandle,
+    user_data: *mut c_void,
+    o_cb: extern "C" fn(user_data: *mut c_void,
+                        result: FfiResult,
+                        mdata_info_h: MDataInfoHandle),
+) {
+    catch_unwind_cb(user_data, o_cb, || {
+        let user_data = OpaqueCtx(user_data);
+
+        (*app).send(move |client, context| {
+            let permissions = if permissions_h != 0 {
+                try_cb!(
+                    get_permissions(context.object_cache(), permissions_h),
+                    user_data,
+                    o_cb
+                )
+            } else {
+                Default::default()
+            };
+
+            let entries = if entries_h != 0 {
+                try_cb!(
+                    context.object_cache().get_mdata_entries(entries_h),
+                    user_data,
+                    o_cb
+                ).clone()
+            } else {
+                Default::default()
+            };
+
+            let context = context.clone();
+
+            client
+                .create_private_mdata(type_tag, permissions, entries)
+                .map(move |info| {
+                    let handle = context.object_cache().insert_mdata_info(info);
+                    o_cb(user_data.0, FFI_RESULT_OK, handle);
+                })
+                .map_err(AppError::from)
+                .map_err(move |err| {
+                    call_result_cb!(Err::<(), _>(err), user_data, o_cb);
+                })
+                .into_box()
+                .into()
+        })
+    })
+}
+
 /// Get version of the mutable data.
 ///
 /// Callback parameters: user data, error code, version
@@ -242,6 +309,111 @@ pub unsafe extern "C" fn mdata_list_entries(
     })
 }
 
+/// Cancellable version of `mdata_list_entries`. The id to pass to
+/// `operation_cancel` is written into `o_op_id`.
+///
+/// Callback parameters: user data, error code, entries handle
+#[no_mangle]
+pub unsafe extern "C" fn mdata_list_entries_cancellable(
+    app: *const App,
+    info_h: MDataInfoHandle,
+    o_op_id: *mut OperationId,
+    user_data: *mut c_void,
+    o_cb: extern "C" fn(user_data: *mut c_void,
+                        result: FfiResult,
+                        entries_h: MDataEntriesHandle),
+) {
+    catch_unwind_cb(user_data, o_cb, || {
+        *o_op_id = send_cancellable_with_mdata_info(
+            app,
+            info_h,
+            user_data,
+            o_cb,
+            move |client, context, info| {
+                let context = context.clone();
+
+                client
+                    .list_mdata_entries(info.name, info.type_tag)
+                    .map_err(AppError::from)
+                    .and_then(move |entries| {
+                        Ok(context.object_cache().insert_mdata_entries(entries))
+                    })
+            },
+        )?;
+        Ok(())
+    })
+}
+
+/// Get a page of up to `limit` entries of the mutable data, in the order of
+/// their keys, starting at the key given by `start_key_ptr` and
+/// `start_key_len` (or the first key if `start_key_ptr` is null). The callback
+/// is given the key the next page starts at, or a null pointer if this is the
+/// last page. `limit` must be greater than zero. Deleted entries are skipped,
+/// so a page can hold fewer than `limit` entries even if it's not the last
+/// one. The paging is done on the client side: every page lists all the keys
+/// of the mutable data, but only the values in the page are fetched from the
+/// network. To walk all the entries, `mdata_entries_iter_new` lists the keys
+/// only once.
+///
+/// Callback parameters: user data, error code, entries handle, next key,
+/// next key length
+#[no_mangle]
+pub unsafe extern "C" fn mdata_list_entries_range(
+    app: *const App,
+    info_h: MDataInfoHandle,
+    start_key_ptr: *const u8,
+    start_key_len: usize,
+    limit: u64,
+    user_data: *mut c_void,
+    o_cb: extern "C" fn(user_data: *mut c_void,
+                        result: FfiResult,
+                        entries_h: MDataEntriesHandle,
+                        next_key_ptr: *const u8,
+                        next_key_len: usize),
+) {
+    catch_unwind_cb(user_data, o_cb, || {
+        let user_data = OpaqueCtx(user_data);
+        let start_key = if start_key_ptr.is_null() {
+            None
+        } else {
+            Some(vec_clone_from_raw_parts(start_key_ptr, start_key_len))
+        };
+
+        (*app).send(move |client, context| {
+            let info = try_cb!(
+                context.object_cache().get_mdata_info(info_h),
+                user_data,
+                o_cb
+            );
+            let context = context.clone();
+
+            client
+                .list_mdata_entries_range(info.name, info.type_tag, start_key, limit as usize)
+                .map(move |(entries, next_key)| {
+                    let entries_h = context.object_cache().insert_mdata_entries(entries);
+                    match next_key {
+                        Some(next_key) => {
+                            o_cb(
+                                user_data.0,
+                                FFI_RESULT_OK,
+                                entries_h,
+                                next_key.as_safe_ptr(),
+                                next_key.len(),
+                            )
+                        }
+                        None => o_cb(user_data.0, FFI_RESULT_OK, entries_h, ptr::null(), 0),
+                    }
+                })
+                .map_err(AppError::from)
+                .map_err(move |err| {
+                    call_result_cb!(Err::<(), _>(err), user_data, o_cb);
+                })
+                .into_box()
+                .into()
+        })
+    })
+}
+
 /// Get list of keys in the mutable data.
 ///
 /// Callback parameters: user data, error code, keys handle
@@ -334,6 +506,115 @@ pub unsafe extern "C" fn mdata_mutate_entries(
     })
 }
 
+/// Mutate entries of the mutable data, adjusting the actions to the entries
+/// current at the time of the mutation: the entry versions of the actions are
+/// ignored and set to succeed the current ones, inserts of existing entries
+/// become updates and vice versa, and deletes of missing entries are skipped.
+/// If the entries are modified concurrently, they are fetched again and the
+/// mutation retried a limited number of times.
+///
+/// Callback parameters: user data, error code
+#[no_mangle]
+pub unsafe extern "C" fn mdata_mutate_entries_transact(
+    app: *const App,
+    info_h: MDataInfoHandle,
+    actions_h: MDataEntryActionsHandle,
+    user_data: *mut c_void,
+    o_cb: extern "C" fn(user_data: *mut c_void, result: FfiResult),
+) {
+    catch_unwind_cb(user_data, o_cb, || {
+        let user_data = OpaqueCtx(user_data);
+
+        (*app).send(move |client, context| {
+            let info = try_cb!(
+                context.object_cache().get_mdata_info(info_h),
+                user_data,
+                o_cb
+            );
+            let actions = try_cb!(
+                context.object_cache().get_mdata_entry_actions(actions_h),
+                user_data,
+                o_cb
+            ).clone();
+
+            client
+                .mutate_mdata_transact(info.name, info.type_tag, move |entries| {
+                    helper::rebase_entry_actions(&actions, entries)
+                })
+                .map_err(AppError::from)
+                .then(move |result| {
+                    call_result_cb!(result, user_data, o_cb);
+                    Ok(())
+                })
+                .into_box()
+                .into()
+        })
+    })
+}
+
+/// Watch the mutable data for changes of its entries, polling it every
+/// `interval_ms` milliseconds. The callback is called for every poll which
+/// finds changes with the handles of the added and updated entries and of the
+/// keys of the deleted ones, which have to be freed by the caller. The id to
+/// pass to `mdata_watch_cancel` (or `operation_cancel`) to stop watching is
+/// written into `o_watch_h`. The callback is called one last time with an
+/// error once the watching stops, which is `ERR_OPERATION_CANCELLED` if it
+/// was cancelled.
+///
+/// Callback parameters: user data, error code, added entries handle, updated
+/// entries handle, deleted keys handle
+#[no_mangle]
+pub unsafe extern "C" fn mdata_watch(
+    app: *const App,
+    info_h: MDataInfoHandle,
+    interval_ms: u64,
+    o_watch_h: *mut OperationId,
+    user_data: *mut c_void,
+    o_cb: extern "C" fn(user_data: *mut c_void,
+                        result: FfiResult,
+                        added_h: MDataEntriesHandle,
+                        updated_h: MDataEntriesHandle,
+                        deleted_h: MDataKeysHandle),
+) {
+    catch_unwind_cb(user_data, o_cb, || {
+        let user_data = OpaqueCtx(user_data);
+        let interval = Duration::from_millis(interval_ms);
+
+        *o_watch_h = (*app).send_cancellable(
+            move |client, context| {
+                let context = context.clone();
+                let info = fry!(context.object_cache().get_mdata_info(info_h)).clone();
+
+                client
+                    .watch_mdata(info.name, info.type_tag, interval)
+                    .for_each(move |diff| {
+                        let added_h = context.object_cache().insert_mdata_entries(diff.added);
+                        let updated_h = context.object_cache().insert_mdata_entries(diff.updated);
+                        let deleted_h = context.object_cache().insert_mdata_keys(diff.deleted);
+                        o_cb(user_data.0, FFI_RESULT_OK, added_h, updated_h, deleted_h);
+                        Ok(())
+                    })
+                    .map_err(AppError::from)
+                    .into_box()
+            },
+            move |res| {
+                call_result_cb!(res, user_data, o_cb);
+            },
+        )?;
+        Ok(())
+    })
+}
+
+/// Stop watching the mutable data. The callback of `mdata_watch` is then
+/// called with `ERR_OPERATION_CANCELLED`.
+///
+/// Returns `ERR_INVALID_OPERATION_ID` if the watch is unknown or has already
+/// stopped.
+#[no_mangle]
+pub unsafe extern "C" fn mdata_watch_cancel(app: *const App, watch_h: OperationId) -> i32 {
+    catch_unwind_error_code(|| -> Result<(), AppError> { (*app).cancel(watch_h) })
+}
+
 /// Get list of all permissions set on the mutable data
 ///
 /// Callback parameters: user data, error code, permission handle
@@ -19,9 +19,10 @@
 
 use App;
 use errors::AppError;
-use ffi::helper::send_sync;
+use ffi::helper::{deserialise_versioned, get_permissions, send_sync, serialise_versioned};
 use ffi::mutable_data::helper;
-use ffi_utils::{FfiResult, OpaqueCtx, ReprC, catch_unwind_cb};
+use ffi_utils::{FFI_RESULT_OK, FfiResult, OpaqueCtx, ReprC, SafePtr, catch_unwind_cb,
+                vec_clone_from_raw_parts};
 use ffi_utils::callback::CallbackArgs;
 use object_cache::{MDataPermissionSetHandle, MDataPermissionsHandle, SignKeyHandle};
 use routing::{Action, PermissionSet, User};
@@ -319,6 +320,70 @@ pub unsafe extern "C" fn mdata_permissions_insert(
     })
 }
 
+/// Serialise the permissions, including their permission sets, into a
+/// versioned blob, e.g. for passing to another process.
+///
+/// Callback parameters: user data, error code, serialised permissions
+#[no_mangle]
+pub unsafe extern "C" fn mdata_permissions_serialise(
+    app: *const App,
+    permissions_h: MDataPermissionsHandle,
+    user_data: *mut c_void,
+    o_cb: extern "C" fn(user_data: *mut c_void,
+                        result: FfiResult,
+                        encoded_ptr: *const u8,
+                        encoded_len: usize),
+) {
+    catch_unwind_cb(user_data, o_cb, || {
+        let user_data = OpaqueCtx(user_data);
+
+        (*app).send(move |_, context| {
+            let permissions = try_cb!(
+                get_permissions(context.object_cache(), permissions_h),
+                user_data,
+                o_cb
+            );
+            let encoded = try_cb!(serialise_versioned(&permissions), user_data, o_cb);
+
+            o_cb(
+                user_data.0,
+                FFI_RESULT_OK,
+                encoded.as_safe_ptr(),
+                encoded.len(),
+            );
+            None
+        })
+    })
+}
+
+/// Deserialise the permissions serialised by `mdata_permissions_serialise`.
+/// The permission sets get new handles, which have to be freed together with
+/// the permissions.
+///
+/// Callback parameters: user data, error code, permissions handle
+#[no_mangle]
+pub unsafe extern "C" fn mdata_permissions_deserialise(
+    app: *const App,
+    ptr: *const u8,
+    len: usize,
+    user_data: *mut c_void,
+    o_cb: extern "C" fn(user_data: *mut c_void,
+                        result: FfiResult,
+                        perm_h: MDataPermissionsHandle),
+) {
+    catch_unwind_cb(user_data, o_cb, || {
+        let encoded = vec_clone_from_raw_parts(ptr, len);
+
+        send_sync(app, user_data, o_cb, move |_, context| {
+            let permissions = deserialise_versioned(&encoded)?;
+            Ok(helper::insert_permissions(
+                context.object_cache(),
+                permissions,
+            ))
+        })
+    })
+}
+
 /// Free the permissions from memory.
 ///
 /// Note: this doesn't free the individual permission sets. Those have to be
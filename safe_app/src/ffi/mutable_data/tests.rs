@@ -15,7 +15,8 @@
 // Please review the Licences for the specific language governing permissions and limitations
 // relating to use of the SAFE Network Software.
 
-use errors::{ERR_ACCESS_DENIED, ERR_INVALID_SUCCESSOR, ERR_NO_SUCH_ENTRY, ERR_NO_SUCH_KEY};
+use errors::{ERR_ACCESS_DENIED, ERR_INVALID_SUCCESSOR, ERR_NO_SUCH_ENTRY, ERR_NO_SUCH_KEY,
+             ERR_OPERATION_CANCELLED};
 use ffi::crypto::sign_key_new;
 use ffi::mdata_info::*;
 use ffi::mutable_data::*;
@@ -819,6 +820,279 @@ fn entries_crud_ffi() {
     }
 }
 
+// Test creating private mutable data from plain text entries.
+#[test]
+fn put_private_ffi() {
+    let app = create_app();
+
+    const KEY: &[u8] = b"hello";
+    const VALUE: &[u8] = b"world";
+
+    let entries_h = unsafe { unwrap!(call_1(|ud, cb| mdata_entries_new(&app, ud, cb))) };
+    unsafe {
+        unwrap!(call_0(|ud, cb| {
+            mdata_entries_insert(
+                &app,
+                entries_h,
+                KEY.as_ptr(),
+                KEY.len(),
+                VALUE.as_ptr(),
+                VALUE.len(),
+                ud,
+                cb,
+            )
+        }))
+    };
+
+    let md_info_h: MDataInfoHandle = unsafe {
+        unwrap!(call_1(|ud, cb| {
+            mdata_put_private(&app, 10000, PERMISSIONS_EMPTY, entries_h, ud, cb)
+        }))
+    };
+
+    // The plain text key is not stored in the network.
+    let (tx, rx) = mpsc::channel::<Result<Vec<u8>, i32>>();
+    unsafe {
+        mdata_get_value(
+            &app,
+            md_info_h,
+            KEY.as_ptr(),
+            KEY.len(),
+            sender_as_user_data(&tx),
+            get_value_cb,
+        )
+    };
+    assert_eq!(unwrap!(rx.recv()), Err(ERR_NO_SUCH_ENTRY));
+
+    let key_enc = unsafe {
+        unwrap!(call_vec_u8(|ud, cb| {
+            mdata_info_encrypt_entry_key(&app, md_info_h, KEY.as_ptr(), KEY.len(), ud, cb)
+        }))
+    };
+    unsafe {
+        mdata_get_value(
+            &app,
+            md_info_h,
+            key_enc.as_ptr(),
+            key_enc.len(),
+            sender_as_user_data(&tx),
+            get_value_cb,
+        )
+    };
+    let value_enc = unwrap!(unwrap!(rx.recv()));
+
+    let value = unsafe {
+        unwrap!(call_vec_u8(|ud, cb| {
+            mdata_info_decrypt(&app, md_info_h, value_enc.as_ptr(), value_enc.len(), ud, cb)
+        }))
+    };
+    assert_eq!(&value, &VALUE);
+
+    extern "C" fn get_value_cb(
+        user_data: *mut c_void,
+        res: FfiResult,
+        val: *const u8,
+        len: usize,
+        _version: u64,
+    ) {
+        let result: Result<Vec<u8>, i32> = if res.error_code == 0 {
+            Ok(unsafe { vec_clone_from_raw_parts(val, len) })
+        } else {
+            Err(res.error_code)
+        };
+        unsafe {
+            send_via_user_data(user_data, result);
+        }
+    }
+}
+
+// Test mutating entries with actions whose versions don't match the current entries.
+#[test]
+fn entries_transact_ffi() {
+    let app = create_app();
+
+    const KEY: &[u8] = b"hello";
+
+    // The app needs permissions to insert, update and delete the entries.
+    let perm_set_h: MDataPermissionSetHandle =
+        unsafe { unwrap!(call_1(|ud, cb| mdata_permission_set_new(&app, ud, cb))) };
+    let perms_h: MDataPermissionsHandle =
+        unsafe { unwrap!(call_1(|ud, cb| mdata_permissions_new(&app, ud, cb))) };
+
+    unsafe {
+        unwrap!(call_0(|ud, cb| {
+            mdata_permission_set_allow(&app, perm_set_h, MDataAction::Insert, ud, cb)
+        }));
+        unwrap!(call_0(|ud, cb| {
+            mdata_permission_set_allow(&app, perm_set_h, MDataAction::Update, ud, cb)
+        }));
+        unwrap!(call_0(|ud, cb| {
+            mdata_permission_set_allow(&app, perm_set_h, MDataAction::Delete, ud, cb)
+        }));
+        unwrap!(call_0(|ud, cb| {
+            mdata_permissions_insert(&app, perms_h, USER_ANYONE, perm_set_h, ud, cb)
+        }));
+    }
+
+    let md_info_h: MDataInfoHandle =
+        unsafe { unwrap!(call_1(|ud, cb| mdata_info_random_public(&app, 10000, ud, cb))) };
+
+    unsafe {
+        unwrap!(call_0(|ud, cb| {
+            mdata_put(&app, md_info_h, perms_h, ENTRIES_EMPTY, ud, cb)
+        }))
+    };
+
+    let transact = |value: &[u8]| {
+        let actions_h: MDataEntryActionsHandle =
+            unsafe { unwrap!(call_1(|ud, cb| mdata_entry_actions_new(&app, ud, cb))) };
+
+        unsafe {
+            unwrap!(call_0(|ud, cb| {
+                mdata_entry_actions_insert(
+                    &app,
+                    actions_h,
+                    KEY.as_ptr(),
+                    KEY.len(),
+                    value.as_ptr(),
+                    value.len(),
+                    ud,
+                    cb,
+                )
+            }));
+            unwrap!(call_0(|ud, cb| {
+                mdata_entry_actions_delete(&app, actions_h, b"missing".as_ptr(), 7, 0, ud, cb)
+            }));
+            unwrap!(call_0(|ud, cb| {
+                mdata_mutate_entries_transact(&app, md_info_h, actions_h, ud, cb)
+            }));
+            unwrap!(call_0(|ud, cb| mdata_entry_actions_free(&app, actions_h, ud, cb)));
+        }
+    };
+
+    let get_value = || {
+        let (tx, rx) = mpsc::channel::<(Vec<u8>, u64)>();
+        unsafe {
+            mdata_get_value(
+                &app,
+                md_info_h,
+                KEY.as_ptr(),
+                KEY.len(),
+                sender_as_user_data(&tx),
+                get_value_cb,
+            )
+        };
+        unwrap!(rx.recv())
+    };
+
+    // The first insert creates the entry, the second one updates it.
+    transact(b"first");
+    assert_eq!(get_value(), (b"first".to_vec(), 0));
+
+    transact(b"second");
+    assert_eq!(get_value(), (b"second".to_vec(), 1));
+
+    extern "C" fn get_value_cb(
+        user_data: *mut c_void,
+        res: FfiResult,
+        val: *const u8,
+        len: usize,
+        version: u64,
+    ) {
+        assert_eq!(res.error_code, 0);
+        unsafe {
+            send_via_user_data(user_data, (vec_clone_from_raw_parts(val, len), version));
+        }
+    }
+}
+
+// Test watching mutable data for changes until the watch is cancelled.
+#[test]
+fn watch_ffi() {
+    let app = create_app();
+
+    // The app needs permission to insert the entry.
+    let perm_set_h: MDataPermissionSetHandle =
+        unsafe { unwrap!(call_1(|ud, cb| mdata_permission_set_new(&app, ud, cb))) };
+    let perms_h: MDataPermissionsHandle =
+        unsafe { unwrap!(call_1(|ud, cb| mdata_permissions_new(&app, ud, cb))) };
+
+    unsafe {
+        unwrap!(call_0(|ud, cb| {
+            mdata_permission_set_allow(&app, perm_set_h, MDataAction::Insert, ud, cb)
+        }));
+        unwrap!(call_0(|ud, cb| {
+            mdata_permissions_insert(&app, perms_h, USER_ANYONE, perm_set_h, ud, cb)
+        }));
+    }
+
+    let md_info_h: MDataInfoHandle =
+        unsafe { unwrap!(call_1(|ud, cb| mdata_info_random_public(&app, 10000, ud, cb))) };
+
+    unsafe {
+        unwrap!(call_0(|ud, cb| {
+            mdata_put(&app, md_info_h, perms_h, ENTRIES_EMPTY, ud, cb)
+        }))
+    };
+
+    type Diff = (i32, MDataEntriesHandle, MDataEntriesHandle, MDataKeysHandle);
+    let (tx, rx) = mpsc::channel::<Diff>();
+    let mut watch_h = 0;
+    unsafe { mdata_watch(&app, md_info_h, 10, &mut watch_h, sender_as_user_data(&tx), watch_cb) };
+
+    let actions_h: MDataEntryActionsHandle =
+        unsafe { unwrap!(call_1(|ud, cb| mdata_entry_actions_new(&app, ud, cb))) };
+
+    unsafe {
+        unwrap!(call_0(|ud, cb| {
+            mdata_entry_actions_insert(
+                &app,
+                actions_h,
+                b"key".as_ptr(),
+                3,
+                b"value".as_ptr(),
+                5,
+                ud,
+                cb,
+            )
+        }));
+        unwrap!(call_0(|ud, cb| mdata_mutate_entries(&app, md_info_h, actions_h, ud, cb)));
+        unwrap!(call_0(|ud, cb| mdata_entry_actions_free(&app, actions_h, ud, cb)));
+    }
+
+    let (error_code, added_h, updated_h, deleted_h) = unwrap!(rx.recv());
+    assert_eq!(error_code, 0);
+
+    unsafe {
+        let len: usize = unwrap!(call_1(|ud, cb| mdata_entries_len(&app, added_h, ud, cb)));
+        assert_eq!(len, 1);
+        let len: usize = unwrap!(call_1(|ud, cb| mdata_entries_len(&app, updated_h, ud, cb)));
+        assert_eq!(len, 0);
+        let len: usize = unwrap!(call_1(|ud, cb| mdata_keys_len(&app, deleted_h, ud, cb)));
+        assert_eq!(len, 0);
+
+        unwrap!(call_0(|ud, cb| mdata_entries_free(&app, added_h, ud, cb)));
+        unwrap!(call_0(|ud, cb| mdata_entries_free(&app, updated_h, ud, cb)));
+        unwrap!(call_0(|ud, cb| mdata_keys_free(&app, deleted_h, ud, cb)));
+    }
+
+    assert_eq!(unsafe { mdata_watch_cancel(&app, watch_h) }, 0);
+    let (error_code, ..) = unwrap!(rx.recv());
+    assert_eq!(error_code, ERR_OPERATION_CANCELLED);
+
+    extern "C" fn watch_cb(
+        user_data: *mut c_void,
+        res: FfiResult,
+        added_h: MDataEntriesHandle,
+        updated_h: MDataEntriesHandle,
+        deleted_h: MDataKeysHandle,
+    ) {
+        unsafe {
+            send_via_user_data(user_data, (res.error_code, added_h, updated_h, deleted_h));
+        }
+    }
+}
+
 // Helper function to call FFI function that iterates over permission sets in permissions.
 unsafe fn call_permissions<F>(f: F) -> Vec<(SignKeyHandle, MDataPermissionSetHandle)>
 where
@@ -965,3 +1239,96 @@ impl KeyValueEntriesContext {
         }
     }
 }
+
+// Test passing entries and permissions through their serialised form.
+#[test]
+fn serialise_entries_and_permissions_ffi() {
+    use ffi::helper::get_permissions;
+    use object_cache::MDataEntriesHandle;
+    use test_utils::run_now;
+
+    let app = create_app();
+
+    let key = b"key".to_vec();
+    let value = b"value".to_vec();
+
+    let entries_h: MDataEntriesHandle =
+        unsafe { unwrap!(call_1(|ud, cb| mdata_entries_new(&app, ud, cb))) };
+    unsafe {
+        unwrap!(call_0(|ud, cb| {
+            mdata_entries_insert(
+                &app,
+                entries_h,
+                key.as_ptr(),
+                key.len(),
+                value.as_ptr(),
+                value.len(),
+                ud,
+                cb,
+            )
+        }));
+    }
+
+    let encoded = unsafe {
+        unwrap!(call_vec_u8(
+            |ud, cb| mdata_entries_serialise(&app, entries_h, ud, cb),
+        ))
+    };
+    let entries2_h: MDataEntriesHandle = unsafe {
+        unwrap!(call_1(|ud, cb| {
+            mdata_entries_deserialise(&app, encoded.as_ptr(), encoded.len(), ud, cb)
+        }))
+    };
+
+    let perm_set_h: MDataPermissionSetHandle =
+        unsafe { unwrap!(call_1(|ud, cb| mdata_permission_set_new(&app, ud, cb))) };
+    let perms_h: MDataPermissionsHandle =
+        unsafe { unwrap!(call_1(|ud, cb| mdata_permissions_new(&app, ud, cb))) };
+    unsafe {
+        unwrap!(call_0(|ud, cb| {
+            mdata_permission_set_allow(&app, perm_set_h, MDataAction::Insert, ud, cb)
+        }));
+        unwrap!(call_0(|ud, cb| {
+            mdata_permissions_insert(&app, perms_h, USER_ANYONE, perm_set_h, ud, cb)
+        }));
+    }
+
+    let encoded = unsafe {
+        unwrap!(call_vec_u8(
+            |ud, cb| mdata_permissions_serialise(&app, perms_h, ud, cb),
+        ))
+    };
+    let perms2_h: MDataPermissionsHandle = unsafe {
+        unwrap!(call_1(|ud, cb| {
+            mdata_permissions_deserialise(&app, encoded.as_ptr(), encoded.len(), ud, cb)
+        }))
+    };
+
+    run_now(&app, move |_, context| {
+        let entries = unwrap!(context.object_cache().get_mdata_entries(entries_h)).clone();
+        let entries2 = unwrap!(context.object_cache().get_mdata_entries(entries2_h)).clone();
+        assert_eq!(entries, entries2);
+        assert_eq!(entries[&key].content, value);
+
+        let perms = unwrap!(get_permissions(context.object_cache(), perms_h));
+        let perms2 = unwrap!(get_permissions(context.object_cache(), perms2_h));
+        assert_eq!(perms, perms2);
+    });
+
+    // Garbage is rejected.
+    let garbage = [1u8, 2, 3];
+    let res: Result<MDataEntriesHandle, _> = unsafe {
+        call_1(|ud, cb| {
+            mdata_entries_deserialise(&app, garbage.as_ptr(), garbage.len(), ud, cb)
+        })
+    };
+    assert!(res.is_err());
+
+    unsafe {
+        unwrap!(call_0(|ud, cb| mdata_entries_free(&app, entries_h, ud, cb)));
+        unwrap!(call_0(|ud, cb| mdata_entries_free(&app, entries2_h, ud, cb)));
+        unwrap!(call_0(|ud, cb| mdata_permissions_free(&app, perms_h, ud, cb)));
+        unwrap!(call_0(|ud, cb| mdata_permissions_free(&app, perms2_h, ud, cb)));
+        unwrap!(call_0(|ud, cb| mdata_permission_set_free(&app, perm_set_h, ud, cb)));
+    }
+}
@@ -17,22 +17,27 @@
 
 use {App, AppContext};
 use errors::AppError;
-use ffi::helper::send_with_mdata_info;
+use ffi::helper::{send_with_mdata_info, with_call_timeout};
 use ffi_utils::{FFI_RESULT_OK, FfiResult, OpaqueCtx, ReprC, SafePtr, catch_unwind_cb, from_c_str,
                 vec_clone_from_raw_parts};
 use futures::Future;
 use futures::future::{self, Either};
 use object_cache::{FileContextHandle, MDataInfoHandle};
+use routing::{Action, PermissionSet, User};
 use safe_core::FutureExt;
 use safe_core::ffi::nfs::File;
-use safe_core::nfs::{Mode, Reader, Writer, file_helper};
+use safe_core::nfs::{Mode, NfsFileWriter, Reader, Writer, create_dir, file_helper};
+use safe_core::nfs::file_helper::SearchQuery;
 use safe_core::nfs::File as NativeFile;
+use std::ffi::CString;
 use std::os::raw::{c_char, c_void};
+use std::slice;
 
 /// Holds context for file operations, depending on the mode.
 pub struct FileContext {
     reader: Option<Reader<AppContext>>,
     writer: Option<Writer<AppContext>>,
+    resumable: Option<NfsFileWriter<AppContext>>,
     original_file: NativeFile,
 }
 
@@ -45,14 +50,48 @@ pub static OPEN_MODE_READ: u64 = 4;
 /// Read entire contents of a file.
 pub static FILE_READ_TO_END: u64 = 0;
 
+/// Create a new, empty directory at the location described by `dir_h` (e.g.
+/// obtained from `mdata_info_random_private`). The app is given full access to
+/// it. Creating a directory which already exists is not an error.
+///
+/// Callback parameters: user data, error code
+#[no_mangle]
+pub unsafe extern "C" fn dir_create(
+    app: *const App,
+    dir_h: MDataInfoHandle,
+    user_data: *mut c_void,
+    o_cb: extern "C" fn(user_data: *mut c_void, result: FfiResult),
+) {
+    catch_unwind_cb(user_data, o_cb, || {
+        send_with_mdata_info(app, dir_h, user_data, o_cb, move |client, _, dir| {
+            let sign_pk = fry!(client.public_signing_key().map_err(AppError::from));
+            let perms = btree_map![
+                User::Key(sign_pk) => PermissionSet::new()
+                    .allow(Action::Insert)
+                    .allow(Action::Update)
+                    .allow(Action::Delete)
+                    .allow(Action::ManagePermissions)
+            ];
+
+            create_dir(client, dir, btree_map![], perms)
+                .map_err(AppError::from)
+                .into_box()
+        })
+    })
+}
+
 /// Retrieve file with the given name, and its version, from the directory.
 ///
+/// `timeout_ms` is the time the requests sent by this call wait for their
+/// response, in milliseconds. Zero uses the timeout of the app.
+///
 /// Callback parameters: user data, error code, file, version
 #[no_mangle]
 pub unsafe extern "C" fn dir_fetch_file(
     app: *const App,
     parent_h: MDataInfoHandle,
     file_name: *const c_char,
+    timeout_ms: u64,
     user_data: *mut c_void,
     o_cb: extern "C" fn(user_data: *mut c_void,
                         result: FfiResult,
@@ -70,7 +109,11 @@ pub unsafe extern "C" fn dir_fetch_file(
                 o_cb
             );
 
-            file_helper::fetch(client.clone(), parent.clone(), file_name)
+            let fetch = with_call_timeout(client, timeout_ms, |client| {
+                file_helper::fetch(client.clone(), parent.clone(), file_name)
+            });
+
+            fetch
                 .map(move |(version, file)| {
                     let ffi_file = file.into_repr_c();
                     o_cb(user_data.0, FFI_RESULT_OK, &ffi_file, version)
@@ -85,8 +128,96 @@ pub unsafe extern "C" fn dir_fetch_file(
     })
 }
 
+/// Retrieve the given version of the file from the directory. Previous
+/// versions are kept when the file is replaced with `dir_update_file`.
+///
+/// Callback parameters: user data, error code, file
+#[no_mangle]
+pub unsafe extern "C" fn dir_fetch_file_version(
+    app: *const App,
+    parent_h: MDataInfoHandle,
+    file_name: *const c_char,
+    version: u64,
+    user_data: *mut c_void,
+    o_cb: extern "C" fn(user_data: *mut c_void, result: FfiResult, file: *const File),
+) {
+    catch_unwind_cb(user_data, o_cb, || {
+        let file_name = from_c_str(file_name)?;
+        let user_data = OpaqueCtx(user_data);
+
+        (*app).send(move |client, context| {
+            let parent = try_cb!(
+                context.object_cache().get_mdata_info(parent_h),
+                user_data.0,
+                o_cb
+            );
+
+            file_helper::fetch_version(client, &*parent, file_name, version)
+                .map(move |file| {
+                    let ffi_file = file.into_repr_c();
+                    o_cb(user_data.0, FFI_RESULT_OK, &ffi_file)
+                })
+                .map_err(AppError::from)
+                .map_err(move |err| {
+                    call_result_cb!(Err::<(), _>(err), user_data, o_cb);
+                })
+                .into_box()
+                .into()
+        })
+    })
+}
+
+/// List the versions of the file which can be retrieved with
+/// `dir_fetch_file_version`, in ascending order. The last one is the current
+/// version, unless the file has been deleted.
+///
+/// Callback parameters: user data, error code, versions, number of versions
+#[no_mangle]
+pub unsafe extern "C" fn dir_list_file_versions(
+    app: *const App,
+    parent_h: MDataInfoHandle,
+    file_name: *const c_char,
+    user_data: *mut c_void,
+    o_cb: extern "C" fn(user_data: *mut c_void,
+                        result: FfiResult,
+                        versions_ptr: *const u64,
+                        versions_len: usize),
+) {
+    catch_unwind_cb(user_data, o_cb, || {
+        let file_name = from_c_str(file_name)?;
+        let user_data = OpaqueCtx(user_data);
+
+        (*app).send(move |client, context| {
+            let parent = try_cb!(
+                context.object_cache().get_mdata_info(parent_h),
+                user_data.0,
+                o_cb
+            );
+
+            file_helper::list_versions(client, &*parent, file_name)
+                .map(move |versions| {
+                    o_cb(
+                        user_data.0,
+                        FFI_RESULT_OK,
+                        versions.as_safe_ptr(),
+                        versions.len(),
+                    )
+                })
+                .map_err(AppError::from)
+                .map_err(move |err| {
+                    call_result_cb!(Err::<(), _>(err), user_data, o_cb);
+                })
+                .into_box()
+                .into()
+        })
+    })
+}
+
 /// Insert the file into the parent directory.
 ///
+/// `timeout_ms` is the time the requests sent by this call wait for their
+/// response, in milliseconds. Zero uses the timeout of the app.
+///
 /// Callback parameters: user data, error code
 #[no_mangle]
 pub unsafe extern "C" fn dir_insert_file(
@@ -94,6 +225,7 @@ pub unsafe extern "C" fn dir_insert_file(
     parent_h: MDataInfoHandle,
     file_name: *const c_char,
     file: *const File,
+    timeout_ms: u64,
     user_data: *mut c_void,
     o_cb: extern "C" fn(user_data: *mut c_void, result: FfiResult),
 ) {
@@ -102,7 +234,9 @@ pub unsafe extern "C" fn dir_insert_file(
         let file_name = from_c_str(file_name)?;
 
         send_with_mdata_info(app, parent_h, user_data, o_cb, move |client, _, parent| {
-            file_helper::insert(client.clone(), parent.clone(), file_name, &file)
+            with_call_timeout(client, timeout_ms, |client| {
+                file_helper::insert(client.clone(), parent.clone(), file_name, &file)
+            })
         })
     })
 }
@@ -110,6 +244,9 @@ pub unsafe extern "C" fn dir_insert_file(
 /// Replace the file in the parent directory.
 /// If `version` is 0, the correct version is obtained automatically.
 ///
+/// `timeout_ms` is the time the requests sent by this call wait for their
+/// response, in milliseconds. Zero uses the timeout of the app.
+///
 /// Callback parameters: user data, error code
 #[no_mangle]
 pub unsafe extern "C" fn dir_update_file(
@@ -118,6 +255,7 @@ pub unsafe extern "C" fn dir_update_file(
     file_name: *const c_char,
     file: *const File,
     version: u64,
+    timeout_ms: u64,
     user_data: *mut c_void,
     o_cb: extern "C" fn(user_data: *mut c_void, result: FfiResult),
 ) {
@@ -126,13 +264,18 @@ pub unsafe extern "C" fn dir_update_file(
         let file_name = from_c_str(file_name)?;
 
         send_with_mdata_info(app, parent_h, user_data, o_cb, move |client, _, parent| {
-            file_helper::update(client.clone(), parent.clone(), file_name, &file, version)
+            with_call_timeout(client, timeout_ms, |client| {
+                file_helper::update(client.clone(), parent.clone(), file_name, &file, version)
+            })
         })
     })
 }
 
 /// Delete the file in the parent directory.
 ///
+/// `timeout_ms` is the time the requests sent by this call wait for their
+/// response, in milliseconds. Zero uses the timeout of the app.
+///
 /// Callback parameters: user data, error code
 #[no_mangle]
 pub unsafe extern "C" fn dir_delete_file(
@@ -140,13 +283,89 @@ pub unsafe extern "C" fn dir_delete_file(
     parent_h: MDataInfoHandle,
     file_name: *const c_char,
     version: u64,
+    timeout_ms: u64,
     user_data: *mut c_void,
     o_cb: extern "C" fn(user_data: *mut c_void, result: FfiResult),
 ) {
     catch_unwind_cb(user_data, o_cb, || {
         let file_name = from_c_str(file_name)?;
         send_with_mdata_info(app, parent_h, user_data, o_cb, move |client, _, parent| {
-            file_helper::delete(client, parent, file_name, version)
+            with_call_timeout(client, timeout_ms, |client| {
+                file_helper::delete(client, parent, file_name, version)
+            })
+        })
+    })
+}
+
+/// Search the directories for files whose name contains `name` (case
+/// insensitive) and whose user metadata contains the given bytes. Null `name`
+/// or `user_metadata_ptr` disable the corresponding criterion. Only the file
+/// metadata is scanned, the content is not downloaded.
+///
+/// `o_match_cb` is called for every match, as soon as the directory containing
+/// it is scanned.
+/// Match callback parameters: user data, index of the directory in `dirs`,
+/// file name, file
+///
+/// Callback parameters: user data, error code, total number of matches
+#[no_mangle]
+pub unsafe extern "C" fn dir_search(
+    app: *const App,
+    dirs_ptr: *const MDataInfoHandle,
+    dirs_len: usize,
+    name: *const c_char,
+    user_metadata_ptr: *const u8,
+    user_metadata_len: usize,
+    user_data: *mut c_void,
+    o_match_cb: extern "C" fn(user_data: *mut c_void,
+                              dir_index: usize,
+                              file_name: *const c_char,
+                              file: *const File),
+    o_cb: extern "C" fn(user_data: *mut c_void, result: FfiResult, count: u64),
+) {
+    catch_unwind_cb(user_data, o_cb, || {
+        let user_data = OpaqueCtx(user_data);
+        let handles = slice::from_raw_parts(dirs_ptr, dirs_len).to_vec();
+        let query = SearchQuery {
+            name: if name.is_null() {
+                None
+            } else {
+                Some(from_c_str(name)?)
+            },
+            user_metadata: if user_metadata_ptr.is_null() {
+                None
+            } else {
+                Some(vec_clone_from_raw_parts(user_metadata_ptr, user_metadata_len))
+            },
+            include_versions: false,
+            include_uploads: false,
+        };
+
+        (*app).send(move |client, context| {
+            let mut dirs = Vec::with_capacity(handles.len());
+            for handle in handles {
+                let dir = try_cb!(
+                    context.object_cache().get_mdata_info(handle),
+                    user_data.0,
+                    o_cb
+                );
+                dirs.push(dir.clone());
+            }
+
+            file_helper::search(client.clone(), dirs, query, move |index, name, file| {
+                let name = match CString::new(name) {
+                    Ok(name) => name,
+                    Err(_) => return,
+                };
+                let ffi_file = file.clone().into_repr_c();
+                o_match_cb(user_data.0, index, name.as_ptr(), &ffi_file);
+            }).map(move |count| o_cb(user_data.0, FFI_RESULT_OK, count))
+                .map_err(AppError::from)
+                .map_err(move |err| {
+                    call_result_cb!(Err::<(), _>(err), user_data, o_cb);
+                })
+                .into_box()
+                .into()
         })
     })
 }
@@ -208,6 +427,7 @@ pub unsafe extern "C" fn file_open(
                     let file_ctx = FileContext {
                         reader,
                         writer,
+                        resumable: None,
                         original_file,
                     };
                     context.object_cache().insert_file(file_ctx)
@@ -217,6 +437,61 @@ pub unsafe extern "C" fn file_open(
     })
 }
 
+/// Open the file for appending data in chunks with `file_write_chunk`. The
+/// progress is stored in the parent directory after every chunk, so if the
+/// upload gets interrupted (e.g. the app is restarted), opening the file with
+/// the same name again resumes it and `file` is ignored. `stored_size` tells
+/// how much data has already been stored, so the caller knows where to
+/// continue from.
+///
+/// Callback parameters: user data, error code, file context handle, stored size
+#[no_mangle]
+pub unsafe extern "C" fn file_open_for_append(
+    app: *const App,
+    parent_h: MDataInfoHandle,
+    file_name: *const c_char,
+    file: *const File,
+    user_data: *mut c_void,
+    o_cb: extern "C" fn(user_data: *mut c_void,
+                        result: FfiResult,
+                        file_h: FileContextHandle,
+                        stored_size: u64),
+) {
+    catch_unwind_cb(user_data, o_cb, || {
+        let file_name = from_c_str(file_name)?;
+        let file = NativeFile::clone_from_repr_c(file)?;
+        let user_data = OpaqueCtx(user_data);
+
+        (*app).send(move |client, context| {
+            let parent = try_cb!(
+                context.object_cache().get_mdata_info(parent_h),
+                user_data,
+                o_cb
+            );
+            let context = context.clone();
+            let original_file = file.clone();
+
+            NfsFileWriter::open(client.clone(), parent.clone(), file_name, file)
+                .map(move |writer| {
+                    let size = writer.size();
+                    let file_ctx = FileContext {
+                        reader: None,
+                        writer: None,
+                        resumable: Some(writer),
+                        original_file,
+                    };
+                    let file_h = context.object_cache().insert_file(file_ctx);
+                    o_cb(user_data.0, FFI_RESULT_OK, file_h, size);
+                })
+                .map_err(move |err| {
+                    call_result_cb!(Err::<(), _>(AppError::from(err)), user_data, o_cb);
+                })
+                .into_box()
+                .into()
+        })
+    })
+}
+
 /// Get a size of file opened for read.
 ///
 /// Callback parameters: user data, error code, file size
@@ -326,11 +601,52 @@ pub unsafe extern "C" fn file_write(
     })
 }
 
+/// Write the next chunk of data to the file opened with
+/// `file_open_for_append` and store the progress. Chunks must be written one
+/// at a time. If writing fails, the file has to be opened again to resume
+/// the upload from the last stored chunk.
+///
+/// Callback parameters: user data, error code
+#[no_mangle]
+pub unsafe extern "C" fn file_write_chunk(
+    app: *const App,
+    file_h: FileContextHandle,
+    data: *const u8,
+    size: usize,
+    user_data: *mut c_void,
+    o_cb: extern "C" fn(user_data: *mut c_void, result: FfiResult),
+) {
+    catch_unwind_cb(user_data, o_cb, || {
+        let user_data = OpaqueCtx(user_data);
+        let data = vec_clone_from_raw_parts(data, size);
+
+        (*app).send(move |_client, context| {
+            let file_ctx = try_cb!(context.object_cache().get_file(file_h), user_data, o_cb);
+
+            if let Some(ref writer) = file_ctx.resumable {
+                writer
+                    .write_chunk(&data)
+                    .then(move |res| {
+                        call_result_cb!(res.map_err(AppError::from), user_data, o_cb);
+                        Ok(())
+                    })
+                    .into_box()
+                    .into()
+            } else {
+                call_result_cb!(Err::<(), _>(AppError::InvalidFileMode), user_data, o_cb);
+                None
+            }
+        })
+    })
+}
+
 /// Close is invoked only after all the data is completely written. The
 /// file is saved only when `close` is invoked.
 ///
-/// If the file was opened in any of the read modes, returns the modified
-/// file structure as a result. If the file was opened in the read mode,
+/// If the file was opened in any of the write modes or with
+/// `file_open_for_append`, returns the modified file structure as a result.
+/// The stored progress of an upload started with `file_open_for_append` is
+/// removed. If the file was opened in the read mode,
 /// returns the original file structure that was passed as an argument to
 /// `file_open`.
 ///
@@ -350,7 +666,18 @@ pub unsafe extern "C" fn file_close(
         (*app).send(move |_client, context| {
             let file_ctx = try_cb!(context.object_cache().remove_file(file_h), user_data, o_cb);
 
-            if let Some(writer) = file_ctx.writer {
+            if let Some(writer) = file_ctx.resumable {
+                writer
+                    .close()
+                    .map(move |file| {
+                        o_cb(user_data.0, FFI_RESULT_OK, &file.into_repr_c());
+                    })
+                    .map_err(move |err| {
+                        call_result_cb!(Err::<(), _>(AppError::from(err)), user_data, o_cb);
+                    })
+                    .into_box()
+                    .into()
+            } else if let Some(writer) = file_ctx.writer {
                 writer
                     .close()
                     .map(move |file| {
@@ -0,0 +1,137 @@
+// Copyright 2017 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under (1) the MaidSafe.net Commercial License,
+// version 1.0 or later, or (2) The General Public License (GPL), version 3, depending on which
+// licence you accepted on initial access to the Software (the "Licences").
+//
+// By contributing code to the SAFE Network Software, or to this project generally, you agree to be
+// bound by the terms of the MaidSafe Contributor Agreement.  This, along with the Licenses can be
+// found in the root directory of this project at LICENSE, COPYING and CONTRIBUTOR.
+//
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.
+//
+// Please review the Licences for the specific language governing permissions and limitations
+// relating to use of the SAFE Network Software.
+
+//! Control over the lifetime of the objects behind the handles returned by
+//! the other functions. The objects of each kind are kept up to a capacity,
+//! beyond which the least recently used ones get evicted unless pinned.
+
+use App;
+use errors::AppError;
+use ffi_utils::{FFI_RESULT_OK, FfiResult, OpaqueCtx, catch_unwind_cb};
+use object_cache::{ObjectCacheStats, ObjectHandle};
+use std::os::raw::c_void;
+
+/// Set the maximum number of objects of each kind kept in the object cache.
+/// The least recently used objects beyond it are evicted, unless pinned.
+///
+/// Callback parameters: user data, error code
+#[no_mangle]
+pub unsafe extern "C" fn app_set_object_cache_capacity(
+    app: *const App,
+    capacity: usize,
+    user_data: *mut c_void,
+    o_cb: extern "C" fn(user_data: *mut c_void, result: FfiResult),
+) {
+    catch_unwind_cb(user_data, o_cb, || -> Result<_, AppError> {
+        let user_data = OpaqueCtx(user_data);
+        (*app).send(move |_, context| {
+            context.object_cache().set_capacity(capacity);
+            o_cb(user_data.0, FFI_RESULT_OK);
+            None
+        })
+    })
+}
+
+/// Register the callback called with the handle of every object evicted from
+/// the object cache. Using the handle afterwards fails with the invalid
+/// handle error of its kind. The `evict_user_data` parameter corresponds to
+/// the first parameter of `o_evict_cb`.
+///
+/// Callback parameters: user data, error code
+#[no_mangle]
+pub unsafe extern "C" fn app_set_object_eviction_cb(
+    app: *const App,
+    evict_user_data: *mut c_void,
+    o_evict_cb: extern "C" fn(user_data: *mut c_void, handle: ObjectHandle),
+    user_data: *mut c_void,
+    o_cb: extern "C" fn(user_data: *mut c_void, result: FfiResult),
+) {
+    catch_unwind_cb(user_data, o_cb, || -> Result<_, AppError> {
+        let evict_user_data = OpaqueCtx(evict_user_data);
+        let user_data = OpaqueCtx(user_data);
+        (*app).send(move |_, context| {
+            context.object_cache().set_eviction_callback(move |handle| {
+                o_evict_cb(evict_user_data.0, handle)
+            });
+            o_cb(user_data.0, FFI_RESULT_OK);
+            None
+        })
+    })
+}
+
+/// Protect the object behind the handle from being evicted from the object
+/// cache. It's kept until freed or unpinned.
+///
+/// Callback parameters: user data, error code
+#[no_mangle]
+pub unsafe extern "C" fn handle_pin(
+    app: *const App,
+    handle: ObjectHandle,
+    user_data: *mut c_void,
+    o_cb: extern "C" fn(user_data: *mut c_void, result: FfiResult),
+) {
+    catch_unwind_cb(user_data, o_cb, || -> Result<_, AppError> {
+        let user_data = OpaqueCtx(user_data);
+        (*app).send(move |_, context| {
+            let res = context.object_cache().pin(handle);
+            call_result_cb!(res, user_data, o_cb);
+            None
+        })
+    })
+}
+
+/// Allow the object behind the pinned handle to be evicted again.
+///
+/// Callback parameters: user data, error code
+#[no_mangle]
+pub unsafe extern "C" fn handle_unpin(
+    app: *const App,
+    handle: ObjectHandle,
+    user_data: *mut c_void,
+    o_cb: extern "C" fn(user_data: *mut c_void, result: FfiResult),
+) {
+    catch_unwind_cb(user_data, o_cb, || -> Result<_, AppError> {
+        let user_data = OpaqueCtx(user_data);
+        (*app).send(move |_, context| {
+            let res = context.object_cache().unpin(handle);
+            call_result_cb!(res, user_data, o_cb);
+            None
+        })
+    })
+}
+
+/// Get the numbers of the objects of each kind currently held in the object
+/// cache. Useful for finding handles which are never freed.
+///
+/// Callback parameters: user data, error code, stats
+#[no_mangle]
+pub unsafe extern "C" fn app_object_cache_stats(
+    app: *const App,
+    user_data: *mut c_void,
+    o_cb: extern "C" fn(user_data: *mut c_void,
+                        result: FfiResult,
+                        stats: *const ObjectCacheStats),
+) {
+    catch_unwind_cb(user_data, o_cb, || -> Result<_, AppError> {
+        let user_data = OpaqueCtx(user_data);
+        (*app).send(move |_, context| {
+            let stats = context.object_cache().stats();
+            o_cb(user_data.0, FFI_RESULT_OK, &stats);
+            None
+        })
+    })
+}
@@ -0,0 +1,157 @@
+// Copyright 2017 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under (1) the MaidSafe.net Commercial License,
+// version 1.0 or later, or (2) The General Public License (GPL), version 3, depending on which
+// licence you accepted on initial access to the Software (the "Licences").
+//
+// By contributing code to the SAFE Network Software, or to this project generally, you agree to be
+// bound by the terms of the MaidSafe Contributor Agreement.  This, along with the Licenses can be
+// found in the root directory of this project at LICENSE, COPYING and CONTRIBUTOR.
+//
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.
+//
+// Please review the Licences for the specific language governing permissions and limitations
+// relating to use of the SAFE Network Software.
+
+//! Polling-based completion API. Instead of taking a callback, the `*_polled`
+//! functions return an operation id immediately and the result is collected
+//! later with `operation_status` and `operation_result`. Useful for hosts
+//! which can't receive calls from foreign threads.
+
+use App;
+use errors::AppError;
+use ffi_utils::{catch_unwind_error_code, vec_clone_from_raw_parts, vec_into_raw_parts};
+use futures::Future;
+use maidsafe_utilities::serialisation::serialise;
+use object_cache::MDataInfoHandle;
+use operations::OperationId;
+use safe_core::FutureExt;
+
+/// The operation is still in progress.
+#[no_mangle]
+pub static OPERATION_PENDING: i32 = 0;
+
+/// The operation has completed and its result can be collected.
+#[no_mangle]
+pub static OPERATION_COMPLETE: i32 = 1;
+
+/// Query the status of a polled operation. Writes either `OPERATION_PENDING`
+/// or `OPERATION_COMPLETE` into `o_status`.
+#[no_mangle]
+pub unsafe extern "C" fn operation_status(
+    app: *const App,
+    op_id: OperationId,
+    o_status: *mut i32,
+) -> i32 {
+    catch_unwind_error_code(|| -> Result<(), AppError> {
+        let complete = (*app).operations().is_complete(op_id).ok_or(
+            AppError::InvalidOperationId,
+        )?;
+        *o_status = if complete {
+            OPERATION_COMPLETE
+        } else {
+            OPERATION_PENDING
+        };
+        Ok(())
+    })
+}
+
+/// Collect the result of a completed operation and remove it from the app.
+///
+/// The error code of the operation itself is written into `o_error_code`. On
+/// success the serialised result is returned through `o_payload_ptr`,
+/// `o_payload_len` and `o_payload_cap` and has to be released with
+/// `operation_payload_free`.
+///
+/// Returns `ERR_OPERATION_IN_PROGRESS` if the operation has not completed yet.
+#[no_mangle]
+pub unsafe extern "C" fn operation_result(
+    app: *const App,
+    op_id: OperationId,
+    o_error_code: *mut i32,
+    o_payload_ptr: *mut *mut u8,
+    o_payload_len: *mut usize,
+    o_payload_cap: *mut usize,
+) -> i32 {
+    catch_unwind_error_code(|| -> Result<(), AppError> {
+        let result = (*app).operations().take_result(op_id)?;
+        let (ptr, len, cap) = vec_into_raw_parts(result.payload);
+
+        *o_error_code = result.error_code;
+        *o_payload_ptr = ptr;
+        *o_payload_len = len;
+        *o_payload_cap = cap;
+        Ok(())
+    })
+}
+
+/// Discard a polled operation. Its result, if any, is dropped once it
+/// completes.
+#[no_mangle]
+pub unsafe extern "C" fn operation_discard(app: *const App, op_id: OperationId) -> i32 {
+    catch_unwind_error_code(|| -> Result<(), AppError> {
+        if (*app).operations().remove(op_id) {
+            Ok(())
+        } else {
+            Err(AppError::InvalidOperationId)
+        }
+    })
+}
+
+/// Free the payload returned by `operation_result`.
+#[no_mangle]
+pub unsafe extern "C" fn operation_payload_free(ptr: *mut u8, len: usize, cap: usize) {
+    let _ = Vec::from_raw_parts(ptr, len, cap);
+}
+
+/// Polled version of `app_account_info`. The payload is the serialised
+/// `(mutations_done, mutations_available)` pair.
+#[no_mangle]
+pub unsafe extern "C" fn app_account_info_polled(
+    app: *const App,
+    o_op_id: *mut OperationId,
+) -> i32 {
+    catch_unwind_error_code(|| -> Result<(), AppError> {
+        *o_op_id = (*app).send_polled(|client, _| {
+            client
+                .get_account_info()
+                .map_err(AppError::from)
+                .and_then(|acc_info| {
+                    serialise(&(acc_info.mutations_done, acc_info.mutations_available))
+                        .map_err(AppError::from)
+                })
+                .into_box()
+        })?;
+        Ok(())
+    })
+}
+
+/// Polled version of `mdata_get_value`. The payload is the serialised
+/// `(content, entry_version)` pair.
+#[no_mangle]
+pub unsafe extern "C" fn mdata_get_value_polled(
+    app: *const App,
+    info_h: MDataInfoHandle,
+    key_ptr: *const u8,
+    key_len: usize,
+    o_op_id: *mut OperationId,
+) -> i32 {
+    catch_unwind_error_code(|| -> Result<(), AppError> {
+        let key = vec_clone_from_raw_parts(key_ptr, key_len);
+
+        *o_op_id = (*app).send_polled(move |client, context| {
+            let info = fry!(context.object_cache().get_mdata_info(info_h)).clone();
+
+            client
+                .get_mdata_value(info.name, info.type_tag, key)
+                .map_err(AppError::from)
+                .and_then(|value| {
+                    serialise(&(value.content, value.entry_version)).map_err(AppError::from)
+                })
+                .into_box()
+        })?;
+        Ok(())
+    })
+}
@@ -0,0 +1,186 @@
+// Copyright 2017 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under (1) the MaidSafe.net Commercial License,
+// version 1.0 or later, or (2) The General Public License (GPL), version 3, depending on which
+// licence you accepted on initial access to the Software (the "Licences").
+//
+// By contributing code to the SAFE Network Software, or to this project generally, you agree to be
+// bound by the terms of the MaidSafe Contributor Agreement.  This, along with the Licenses can be
+// found in the root directory of this project at LICENSE, COPYING and CONTRIBUTOR.
+//
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.
+//
+// Please review the Licences for the specific language governing permissions and limitations
+// relating to use of the SAFE Network Software.
+
+use App;
+use errors::AppError;
+use ffi_utils::{FFI_RESULT_OK, FfiResult, OpaqueCtx, catch_unwind_cb, from_c_str};
+use futures::Future;
+use object_cache::SignKeyHandle;
+use routing::XorName;
+use safe_core::FutureExt;
+use safe_core::ffi::arrays::{AsymPublicKey, SignPublicKey, XorNameArray};
+use safe_core::structures::Profile;
+use std::ffi::CString;
+use std::os::raw::{c_char, c_void};
+use std::ptr;
+
+/// Public profile of a user.
+#[repr(C)]
+pub struct PublicProfile {
+    /// Name to display to other users
+    pub display_name: *const c_char,
+    /// Name of the `ImmutableData` holding the data map of the avatar image,
+    /// or null if there's no avatar
+    pub avatar: *const XorNameArray,
+    /// Owner key of the account the profile belongs to
+    pub sign_key: SignPublicKey,
+    /// Public encryption key of the account, or null if only apps have
+    /// published the profile so far
+    pub enc_key: *const AsymPublicKey,
+}
+
+/// Publish the public profile of the user's account, replacing the existing
+/// one if any. `avatar` can be null. The encryption key of the account isn't
+/// known to apps, so the one already published by the account is kept.
+///
+/// Callback parameters: user data, error code
+#[no_mangle]
+pub unsafe extern "C" fn profile_publish(
+    app: *const App,
+    display_name: *const c_char,
+    avatar: *const XorNameArray,
+    user_data: *mut c_void,
+    o_cb: extern "C" fn(user_data: *mut c_void, result: FfiResult),
+) {
+    catch_unwind_cb(user_data, o_cb, || {
+        let user_data = OpaqueCtx(user_data);
+        let display_name = from_c_str(display_name)?;
+        let avatar = if avatar.is_null() {
+            None
+        } else {
+            Some(XorName(*avatar))
+        };
+
+        (*app).send(move |client, _| {
+            let profile = try_cb!(
+                Profile::new(client, display_name, avatar).map_err(AppError::from),
+                user_data,
+                o_cb
+            );
+
+            profile
+                .publish(client)
+                .map(move |_| o_cb(user_data.0, FFI_RESULT_OK))
+                .map_err(AppError::from)
+                .map_err(move |err| {
+                    call_result_cb!(Err::<(), _>(err), user_data, o_cb);
+                })
+                .into_box()
+                .into()
+        })
+    })
+}
+
+/// Fetch the public profile of the account with the given owner key.
+///
+/// Callback parameters: user data, error code, profile
+#[no_mangle]
+pub unsafe extern "C" fn profile_fetch(
+    app: *const App,
+    owner_key_h: SignKeyHandle,
+    user_data: *mut c_void,
+    o_cb: extern "C" fn(user_data: *mut c_void,
+                        result: FfiResult,
+                        profile: *const PublicProfile),
+) {
+    catch_unwind_cb(user_data, o_cb, || {
+        let user_data = OpaqueCtx(user_data);
+
+        (*app).send(move |client, context| {
+            let owner_key = *try_cb!(
+                context.object_cache().get_sign_key(owner_key_h),
+                user_data,
+                o_cb
+            );
+
+            Profile::fetch(client, &owner_key)
+                .map_err(AppError::from)
+                .and_then(|profile| {
+                    let display_name = CString::new(profile.display_name.clone())?;
+                    Ok((profile, display_name))
+                })
+                .map(move |(profile, display_name)| {
+                    let avatar = profile.avatar.map(|name| name.0);
+                    let enc_key = profile.enc_key.map(|key| key.0);
+                    let ffi_profile = PublicProfile {
+                        display_name: display_name.as_ptr(),
+                        avatar: avatar.as_ref().map_or(ptr::null(), |name| name as *const _),
+                        sign_key: profile.sign_key.0,
+                        enc_key: enc_key.as_ref().map_or(ptr::null(), |key| key as *const _),
+                    };
+                    o_cb(user_data.0, FFI_RESULT_OK, &ffi_profile);
+                })
+                .map_err(move |err| {
+                    call_result_cb!(Err::<(), _>(err), user_data, o_cb);
+                })
+                .into_box()
+                .into()
+        })
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ffi_utils::test_utils::{call_0, send_via_user_data, sender_as_user_data};
+    use std::ffi::CStr;
+    use std::sync::mpsc;
+    use test_utils::{create_app, run_now};
+
+    // Publish the profile, update it and fetch it using the owner key.
+    #[test]
+    fn publish_and_fetch() {
+        let app = create_app();
+        let display_name = unwrap!(CString::new("Alice"));
+        let new_display_name = unwrap!(CString::new("Alice Liddell"));
+
+        unsafe {
+            unwrap!(call_0(|ud, cb| {
+                profile_publish(&app, display_name.as_ptr(), ptr::null(), ud, cb)
+            }));
+            unwrap!(call_0(|ud, cb| {
+                profile_publish(&app, new_display_name.as_ptr(), ptr::null(), ud, cb)
+            }));
+        };
+
+        let owner_key_h = run_now(&app, |client, context| {
+            context.object_cache().insert_sign_key(unwrap!(client.owner_key()))
+        });
+
+        extern "C" fn fetch_cb(
+            user_data: *mut c_void,
+            res: FfiResult,
+            profile: *const PublicProfile,
+        ) {
+            unsafe {
+                assert_eq!(res.error_code, 0);
+                let name = unwrap!(CStr::from_ptr((*profile).display_name).to_str());
+                let has_avatar = !(*profile).avatar.is_null();
+                let has_enc_key = !(*profile).enc_key.is_null();
+                send_via_user_data(user_data, (name.to_string(), has_avatar, has_enc_key));
+            }
+        }
+
+        let (tx, rx) = mpsc::channel::<(String, bool, bool)>();
+        unsafe { profile_fetch(&app, owner_key_h, sender_as_user_data(&tx), fetch_cb) };
+        let (name, has_avatar, has_enc_key) = unwrap!(rx.recv());
+
+        assert_eq!(name, "Alice Liddell");
+        assert!(!has_avatar);
+        assert!(!has_enc_key);
+    }
+}
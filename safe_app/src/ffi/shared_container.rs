@@ -0,0 +1,240 @@
+// Copyright 2017 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under (1) the MaidSafe.net Commercial License,
+// version 1.0 or later, or (2) The General Public License (GPL), version 3, depending on which
+// licence you accepted on initial access to the Software (the "Licences").
+//
+// By contributing code to the SAFE Network Software, or to this project generally, you agree to be
+// bound by the terms of the MaidSafe Contributor Agreement.  This, along with the Licenses can be
+// found in the root directory of this project at LICENSE, COPYING and CONTRIBUTOR.
+//
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.
+//
+// Please review the Licences for the specific language governing permissions and limitations
+// relating to use of the SAFE Network Software.
+
+use App;
+use AppContext;
+use errors::AppError;
+use ffi::helper::send_with_mdata_info;
+use ffi_utils::{FFI_RESULT_OK, FfiResult, OpaqueCtx, catch_unwind_cb};
+use futures::Future;
+use object_cache::{EncryptPubKeyHandle, MDataInfoHandle, SignKeyHandle};
+use routing::XorName;
+use safe_core::FutureExt;
+use safe_core::ffi::arrays::XorNameArray;
+use safe_core::structures::{Member, SharedContainer};
+use std::os::raw::c_void;
+
+/// Create new container shared among a group of users, with the app as its
+/// owner and only member. The returned name of the members `MutableData`
+/// together with the type tag is needed by the other members to open it.
+///
+/// Callback parameters: user data, error code, shared data info handle,
+/// members data name
+#[no_mangle]
+pub unsafe extern "C" fn shared_container_create(
+    app: *const App,
+    type_tag: u64,
+    user_data: *mut c_void,
+    o_cb: extern "C" fn(user_data: *mut c_void,
+                        result: FfiResult,
+                        info_h: MDataInfoHandle,
+                        members_name: *const XorNameArray),
+) {
+    catch_unwind_cb(user_data, o_cb, || {
+        let user_data = OpaqueCtx(user_data);
+
+        (*app).send(move |client, context| {
+            let context = context.clone();
+
+            SharedContainer::create(client, type_tag)
+                .map(move |container| {
+                    let info_h = context.object_cache().insert_mdata_info(
+                        container.data().clone(),
+                    );
+                    let members_name = container.members_name();
+                    o_cb(user_data.0, FFI_RESULT_OK, info_h, &members_name.0);
+                })
+                .map_err(AppError::from)
+                .map_err(move |err| {
+                    call_result_cb!(Err::<(), _>(err), user_data, o_cb);
+                })
+                .into_box()
+                .into()
+        })
+    })
+}
+
+/// Open shared container the app is a member of.
+///
+/// Callback parameters: user data, error code, shared data info handle
+#[no_mangle]
+pub unsafe extern "C" fn shared_container_open(
+    app: *const App,
+    members_name: *const XorNameArray,
+    type_tag: u64,
+    user_data: *mut c_void,
+    o_cb: extern "C" fn(user_data: *mut c_void,
+                        result: FfiResult,
+                        info_h: MDataInfoHandle),
+) {
+    catch_unwind_cb(user_data, o_cb, || {
+        let user_data = OpaqueCtx(user_data);
+        let members_name = XorName(*members_name);
+
+        (*app).send(move |client, context| {
+            let context = context.clone();
+
+            SharedContainer::open(client, members_name, type_tag)
+                .map(move |container| {
+                    let info_h = context.object_cache().insert_mdata_info(
+                        container.data().clone(),
+                    );
+                    o_cb(user_data.0, FFI_RESULT_OK, info_h);
+                })
+                .map_err(AppError::from)
+                .map_err(move |err| {
+                    call_result_cb!(Err::<(), _>(err), user_data, o_cb);
+                })
+                .into_box()
+                .into()
+        })
+    })
+}
+
+/// Add member to the shared container. Only the owner can manage the members.
+///
+/// Callback parameters: user data, error code
+#[no_mangle]
+pub unsafe extern "C" fn shared_container_add_member(
+    app: *const App,
+    info_h: MDataInfoHandle,
+    members_name: *const XorNameArray,
+    sign_pk_h: SignKeyHandle,
+    enc_pk_h: EncryptPubKeyHandle,
+    user_data: *mut c_void,
+    o_cb: extern "C" fn(user_data: *mut c_void, result: FfiResult),
+) {
+    catch_unwind_cb(user_data, o_cb, || {
+        let members_name = XorName(*members_name);
+
+        send_with_mdata_info(app, info_h, user_data, o_cb, move |client, context, info| {
+            let member = match get_member(context, sign_pk_h, enc_pk_h) {
+                Ok(member) => member,
+                Err(err) => return err!(err),
+            };
+
+            SharedContainer::new(info.clone(), members_name)
+                .add_member(client, member)
+                .map_err(AppError::from)
+                .into_box()
+        })
+    })
+}
+
+/// Remove member from the shared container and rotate its encryption key.
+/// The old shared data info handle is no longer usable for writing.
+///
+/// Callback parameters: user data, error code, new shared data info handle
+#[no_mangle]
+pub unsafe extern "C" fn shared_container_remove_member(
+    app: *const App,
+    info_h: MDataInfoHandle,
+    members_name: *const XorNameArray,
+    sign_pk_h: SignKeyHandle,
+    user_data: *mut c_void,
+    o_cb: extern "C" fn(user_data: *mut c_void,
+                        result: FfiResult,
+                        new_info_h: MDataInfoHandle),
+) {
+    catch_unwind_cb(user_data, o_cb, || {
+        let members_name = XorName(*members_name);
+
+        send_with_mdata_info(app, info_h, user_data, o_cb, move |client, context, info| {
+            let sign_pk = match context.object_cache().get_sign_key(sign_pk_h) {
+                Ok(key) => *key,
+                Err(err) => return err!(err),
+            };
+            let context = context.clone();
+
+            SharedContainer::new(info.clone(), members_name)
+                .remove_member(client, sign_pk)
+                .map(move |container| {
+                    context.object_cache().insert_mdata_info(
+                        container.data().clone(),
+                    )
+                })
+                .map_err(AppError::from)
+                .into_box()
+        })
+    })
+}
+
+fn get_member(
+    context: &AppContext,
+    sign_pk_h: SignKeyHandle,
+    enc_pk_h: EncryptPubKeyHandle,
+) -> Result<Member, AppError> {
+    Ok(Member {
+        sign_pk: *context.object_cache().get_sign_key(sign_pk_h)?,
+        enc_pk: *context.object_cache().get_encrypt_key(enc_pk_h)?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ffi_utils::test_utils::{call_0, call_1, call_2};
+    use rust_sodium::crypto::{box_, sign};
+    use safe_core::DIR_TAG;
+    use test_utils::{create_app, run_now};
+
+    // Create a container, then add and remove a member.
+    #[test]
+    fn add_remove_member() {
+        let app = create_app();
+
+        let (info_h, members_name): (MDataInfoHandle, XorNameArray) = unsafe {
+            unwrap!(call_2(
+                |ud, cb| shared_container_create(&app, DIR_TAG, ud, cb),
+            ))
+        };
+
+        let (sign_pk_h, enc_pk_h) = run_now(&app, |_, context| {
+            let sign_pk_h = context.object_cache().insert_sign_key(sign::gen_keypair().0);
+            let enc_pk_h = context.object_cache().insert_encrypt_key(box_::gen_keypair().0);
+            (sign_pk_h, enc_pk_h)
+        });
+
+        unsafe {
+            unwrap!(call_0(|ud, cb| {
+                shared_container_add_member(
+                    &app,
+                    info_h,
+                    &members_name,
+                    sign_pk_h,
+                    enc_pk_h,
+                    ud,
+                    cb,
+                )
+            }))
+        }
+
+        let new_info_h: MDataInfoHandle = unsafe {
+            unwrap!(call_1(|ud, cb| {
+                shared_container_remove_member(&app, info_h, &members_name, sign_pk_h, ud, cb)
+            }))
+        };
+        assert_ne!(new_info_h, info_h);
+
+        // The app is still a member, so it can open the container.
+        let _: MDataInfoHandle = unsafe {
+            unwrap!(call_1(|ud, cb| {
+                shared_container_open(&app, &members_name, DIR_TAG, ud, cb)
+            }))
+        };
+    }
+}
@@ -0,0 +1,71 @@
+// Copyright 2017 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under (1) the MaidSafe.net Commercial License,
+// version 1.0 or later, or (2) The General Public License (GPL), version 3, depending on which
+// licence you accepted on initial access to the Software (the "Licences").
+//
+// By contributing code to the SAFE Network Software, or to this project generally, you agree to be
+// bound by the terms of the MaidSafe Contributor Agreement.  This, along with the Licenses can be
+// found in the root directory of this project at LICENSE, COPYING and CONTRIBUTOR.
+//
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.
+//
+// Please review the Licences for the specific language governing permissions and limitations
+// relating to use of the SAFE Network Software.
+
+use errors::{AppError, ERR_INVALID_MDATA_INFO_HANDLE, ERR_INVALID_OPERATION_ID};
+use ffi::cancel::*;
+use ffi::mutable_data::mdata_list_entries_cancellable;
+use ffi_utils::test_utils::call_1;
+use futures::future;
+use object_cache::MDataEntriesHandle;
+use safe_core::FutureExt;
+use std::sync::mpsc;
+use test_utils::create_app;
+
+// Cancelling a pending operation drops it and reports the cancellation.
+#[test]
+fn cancel_pending_operation() {
+    let app = create_app();
+    let (tx, rx) = mpsc::channel();
+
+    let op_id = unwrap!(app.send_cancellable(
+        |_, _| future::empty::<(), AppError>().into_box(),
+        move |res| unwrap!(tx.send(res)),
+    ));
+
+    assert_eq!(unsafe { operation_cancel(&app, op_id) }, 0);
+
+    match unwrap!(rx.recv()) {
+        Err(AppError::Cancelled) => (),
+        x => panic!("Unexpected {:?}", x),
+    }
+
+    // The operation is gone once cancelled.
+    assert_eq!(
+        unsafe { operation_cancel(&app, op_id) },
+        ERR_INVALID_OPERATION_ID
+    );
+}
+
+// Completed operations can no longer be cancelled.
+#[test]
+fn cancel_completed_operation() {
+    let app = create_app();
+    let mut op_id = 0;
+
+    let res: Result<MDataEntriesHandle, _> = unsafe {
+        call_1(|ud, cb| mdata_list_entries_cancellable(&app, 1234, &mut op_id, ud, cb))
+    };
+    match res {
+        Err(code) => assert_eq!(code, ERR_INVALID_MDATA_INFO_HANDLE),
+        Ok(_) => panic!("Unexpected success"),
+    }
+
+    assert_eq!(
+        unsafe { operation_cancel(&app, op_id) },
+        ERR_INVALID_OPERATION_ID
+    );
+}
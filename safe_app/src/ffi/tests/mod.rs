@@ -15,7 +15,10 @@
 // Please review the Licences for the specific language governing permissions and limitations
 // relating to use of the SAFE Network Software.
 
+mod cancel;
 mod nfs;
+mod object_cache;
+mod poll;
 
 use super::*;
 use ffi_utils::test_utils::call_1;
@@ -53,6 +56,94 @@ fn account_info() {
     unsafe { app_free(app) };
 }
 
+// Test setting the request priority and the limit of requests in flight.
+#[test]
+fn request_priority() {
+    use ffi_utils::test_utils::call_0;
+
+    let app = create_app();
+    let app = Box::into_raw(Box::new(app));
+
+    unsafe {
+        unwrap!(call_0(|ud, cb| app_set_max_requests_in_flight(app, 1, ud, cb)));
+        unwrap!(call_0(|ud, cb| app_set_request_priority(app, 0, ud, cb)));
+
+        let stats: AccountInfo = unwrap!(call_1(|ud, cb| app_account_info(app, ud, cb)));
+        assert!(stats.mutations_available > 0);
+
+        let res = call_0(|ud, cb| app_set_request_priority(app, 3, ud, cb));
+        assert!(res.is_err());
+
+        unwrap!(call_0(|ud, cb| app_set_max_requests_in_flight(app, 0, ud, cb)));
+        app_free(app);
+    }
+}
+
+// Test the requests made after setting the request timeout fail on it.
+#[cfg(feature = "use-mock-routing")]
+#[test]
+fn request_timeout() {
+    use errors::ERR_REQUEST_TIMEOUT;
+    use ffi_utils::test_utils::call_0;
+
+    let app = create_app();
+    let app = Box::into_raw(Box::new(app));
+
+    unsafe {
+        unwrap!((*app).send(|client, _| {
+            client.set_simulate_timeout(true);
+            None
+        }));
+        unwrap!(call_0(|ud, cb| app_set_request_timeout(app, 250, ud, cb)));
+
+        let res: Result<AccountInfo, _> = call_1(|ud, cb| app_account_info(app, ud, cb));
+        assert_eq!(res.err(), Some(ERR_REQUEST_TIMEOUT));
+
+        unwrap!((*app).send(|client, _| {
+            client.set_simulate_timeout(false);
+            None
+        }));
+        unwrap!(call_0(|ud, cb| app_set_request_timeout(app, 0, ud, cb)));
+
+        let stats: AccountInfo = unwrap!(call_1(|ud, cb| app_account_info(app, ud, cb)));
+        assert!(stats.mutations_available > 0);
+
+        app_free(app);
+    }
+}
+
+// Test the in-flight operations complete before the app is shut down, and
+// the operations sent afterwards are rejected.
+#[test]
+fn shutdown() {
+    use ffi_utils::test_utils::call_0;
+    use std::sync::mpsc;
+
+    let app = create_app();
+    let app = Box::into_raw(Box::new(app));
+    let (tx, rx) = mpsc::channel();
+
+    unsafe {
+        unwrap!((*app).send(move |client, _| {
+            client
+                .put_idata(ImmutableData::new(vec![1, 2, 3]))
+                .then(move |res| {
+                    unwrap!(tx.send(res.is_ok()));
+                    Ok(())
+                })
+                .into_box()
+                .into()
+        }));
+
+        unwrap!(call_0(|ud, cb| app_shutdown(app, 10_000, ud, cb)));
+    }
+
+    assert!(unwrap!(rx.try_recv()));
+    assert!(unsafe { (*app).send(|_, _| None) }.is_err());
+
+    unsafe { app_free(app) };
+}
+
 // Test disconnection and reconnection with apps.
 #[cfg(all(test, feature = "use-mock-routing"))]
 #[test]
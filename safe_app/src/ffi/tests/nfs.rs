@@ -17,6 +17,7 @@
 
 use App;
 use errors::AppError;
+use ffi::mdata_info::mdata_info_random_private;
 use ffi::nfs::*;
 use ffi_utils::ErrorCode;
 use ffi_utils::test_utils::{call_0, call_1, call_2, call_vec_u8};
@@ -28,7 +29,7 @@ use safe_core::nfs::File as NativeFile;
 use safe_core::nfs::NfsError;
 use std::collections::HashMap;
 use std::ffi::CString;
-use test_utils::{create_app_with_access, run};
+use test_utils::{create_app, create_app_with_access, run};
 
 fn setup() -> (App, MDataInfoHandle) {
     let mut container_permissions = HashMap::new();
@@ -72,7 +73,7 @@ fn basics() {
     // fetching non-existing file fails.
     let res: Result<(NativeFile, u64), i32> = unsafe {
         call_2(|ud, cb| {
-            dir_fetch_file(&app, container_info_h, ffi_file_name0.as_ptr(), ud, cb)
+            dir_fetch_file(&app, container_info_h, ffi_file_name0.as_ptr(), 0, ud, cb)
         })
     };
 
@@ -94,6 +95,7 @@ fn basics() {
                 container_info_h,
                 ffi_file_name0.as_ptr(),
                 &ffi_file,
+                0,
                 ud,
                 cb,
             )
@@ -103,7 +105,7 @@ fn basics() {
     // Fetch it back.
     let (retrieved_file, retrieved_version): (NativeFile, u64) = unsafe {
         unwrap!(call_2(|ud, cb| {
-            dir_fetch_file(&app, container_info_h, ffi_file_name0.as_ptr(), ud, cb)
+            dir_fetch_file(&app, container_info_h, ffi_file_name0.as_ptr(), 0, ud, cb)
         }))
     };
     assert_eq!(retrieved_file.user_metadata(), &user_metadata[..]);
@@ -113,11 +115,72 @@ fn basics() {
     // Delete file.
     unsafe {
         unwrap!(call_0(|ud, cb| {
-            dir_delete_file(&app, container_info_h, ffi_file_name0.as_ptr(), 1, ud, cb)
+            dir_delete_file(&app, container_info_h, ffi_file_name0.as_ptr(), 1, 0, ud, cb)
         }))
     }
 }
 
+// Test creating a new directory and storing files in it.
+// 1. Create a directory at a random location.
+// 2. Insert a file into it and fetch it back.
+// 3. Creating the same directory again is a no-op.
+#[test]
+fn create_dir_and_insert_file() {
+    let app = create_app();
+
+    let dir_h: MDataInfoHandle =
+        unsafe { unwrap!(call_1(|ud, cb| mdata_info_random_private(&app, 15_000, ud, cb))) };
+    unsafe { unwrap!(call_0(|ud, cb| dir_create(&app, dir_h, ud, cb))) };
+
+    let file_name = unwrap!(CString::new("file.txt"));
+    let ffi_file = NativeFile::new(b"metadata".to_vec()).into_repr_c();
+
+    unsafe {
+        unwrap!(call_0(|ud, cb| {
+            dir_insert_file(&app, dir_h, file_name.as_ptr(), &ffi_file, 0, ud, cb)
+        }))
+    }
+
+    let (file, version): (NativeFile, u64) = unsafe {
+        unwrap!(call_2(|ud, cb| {
+            dir_fetch_file(&app, dir_h, file_name.as_ptr(), 0, ud, cb)
+        }))
+    };
+    assert_eq!(file.user_metadata(), b"metadata");
+    assert_eq!(version, 0);
+
+    unsafe { unwrap!(call_0(|ud, cb| dir_create(&app, dir_h, ud, cb))) };
+
+    let (_, version): (NativeFile, u64) = unsafe {
+        unwrap!(call_2(|ud, cb| {
+            dir_fetch_file(&app, dir_h, file_name.as_ptr(), 0, ud, cb)
+        }))
+    };
+    assert_eq!(version, 0);
+}
+
+// Test the timeout passed to a call applies to the requests it sends.
+#[cfg(feature = "use-mock-routing")]
+#[test]
+fn call_timeout() {
+    use errors::ERR_REQUEST_TIMEOUT;
+
+    let (app, container_info_h) = setup();
+    let file_name = unwrap!(CString::new("file.txt"));
+
+    unwrap!(app.send(|client, _| {
+        client.set_simulate_timeout(true);
+        None
+    }));
+
+    let res: Result<(NativeFile, u64), i32> = unsafe {
+        call_2(|ud, cb| {
+            dir_fetch_file(&app, container_info_h, file_name.as_ptr(), 250, ud, cb)
+        })
+    };
+    assert_eq!(res.err(), Some(ERR_REQUEST_TIMEOUT));
+}
+
 // Test NFS functions for writing and updating file contents.
 // 1. Create an empty file, open it for writing, write contents.
 // 2. Insert file into a container.
@@ -181,6 +244,7 @@ fn open_file() {
                 container_info_h,
                 ffi_file_name1.as_ptr(),
                 &written_file.into_repr_c(),
+                0,
                 ud,
                 cb,
             )
@@ -191,7 +255,7 @@ fn open_file() {
     let (file, version): (NativeFile, u64) = {
         unsafe {
             unwrap!(call_2(|ud, cb| {
-                dir_fetch_file(&app, container_info_h, ffi_file_name1.as_ptr(), ud, cb)
+                dir_fetch_file(&app, container_info_h, ffi_file_name1.as_ptr(), 0, ud, cb)
             }))
         }
     };
@@ -227,7 +291,7 @@ fn open_file() {
     let (file, _version): (NativeFile, u64) = {
         unsafe {
             unwrap!(call_2(|ud, cb| {
-                dir_fetch_file(&app, container_info_h, ffi_file_name1.as_ptr(), ud, cb)
+                dir_fetch_file(&app, container_info_h, ffi_file_name1.as_ptr(), 0, ud, cb)
             }))
         }
     };
@@ -262,6 +326,7 @@ fn open_file() {
                 ffi_file_name1.as_ptr(),
                 &written_file.into_repr_c(),
                 1,
+                0,
                 ud,
                 cb,
             )
@@ -272,7 +337,7 @@ fn open_file() {
     let (file, version): (NativeFile, u64) = {
         unsafe {
             unwrap!(call_2(|ud, cb| {
-                dir_fetch_file(&app, container_info_h, ffi_file_name1.as_ptr(), ud, cb)
+                dir_fetch_file(&app, container_info_h, ffi_file_name1.as_ptr(), 0, ud, cb)
             }))
         }
     };
@@ -355,6 +420,7 @@ fn file_read_chunks() {
                 container_info_h,
                 ffi_file_name.as_ptr(),
                 &written_file.into_repr_c(),
+                0,
                 ud,
                 cb,
             )
@@ -365,7 +431,7 @@ fn file_read_chunks() {
     let (file, version): (NativeFile, u64) = {
         unsafe {
             unwrap!(call_2(|ud, cb| {
-                dir_fetch_file(&app, container_info_h, ffi_file_name.as_ptr(), ud, cb)
+                dir_fetch_file(&app, container_info_h, ffi_file_name.as_ptr(), 0, ud, cb)
             }))
         }
     };
@@ -515,6 +581,7 @@ fn file_write_chunks() {
                 container_info_h,
                 ffi_file_name.as_ptr(),
                 &written_file.into_repr_c(),
+                0,
                 ud,
                 cb,
             )
@@ -525,7 +592,7 @@ fn file_write_chunks() {
     let (file, version): (NativeFile, u64) = {
         unsafe {
             unwrap!(call_2(|ud, cb| {
-                dir_fetch_file(&app, container_info_h, ffi_file_name.as_ptr(), ud, cb)
+                dir_fetch_file(&app, container_info_h, ffi_file_name.as_ptr(), 0, ud, cb)
             }))
         }
     };
@@ -557,7 +624,7 @@ fn file_write_chunks() {
 
     let (file, version): (NativeFile, u64) = unsafe {
         unwrap!(call_2(|ud, cb| {
-            dir_fetch_file(&app, container_info_h, ffi_file_name.as_ptr(), ud, cb)
+            dir_fetch_file(&app, container_info_h, ffi_file_name.as_ptr(), 0, ud, cb)
         }))
     };
     assert_eq!(version, 0);
@@ -650,6 +717,7 @@ fn delete_then_open_file() {
                 container_info_h,
                 ffi_file_name2.as_ptr(),
                 &written_file.into_repr_c(),
+                0,
                 ud,
                 cb,
             )
@@ -659,7 +727,7 @@ fn delete_then_open_file() {
     // Delete file.
     unsafe {
         unwrap!(call_0(|ud, cb| {
-            dir_delete_file(&app, container_info_h, ffi_file_name2.as_ptr(), 1, ud, cb)
+            dir_delete_file(&app, container_info_h, ffi_file_name2.as_ptr(), 1, 0, ud, cb)
         }))
     }
 
@@ -705,6 +773,7 @@ fn delete_then_open_file() {
                 ffi_file_name2.as_ptr(),
                 &new_file.into_repr_c(),
                 2,
+                0,
                 ud,
                 cb,
             )
@@ -715,7 +784,7 @@ fn delete_then_open_file() {
     let (file, version): (NativeFile, u64) = {
         unsafe {
             unwrap!(call_2(|ud, cb| {
-                dir_fetch_file(&app, container_info_h, ffi_file_name2.as_ptr(), ud, cb)
+                dir_fetch_file(&app, container_info_h, ffi_file_name2.as_ptr(), 0, ud, cb)
             }))
         }
     };
@@ -790,6 +859,7 @@ fn open_close_file() {
                 container_info_h,
                 ffi_file_name.as_ptr(),
                 &written_file.into_repr_c(),
+                0,
                 ud,
                 cb,
             )
@@ -800,7 +870,7 @@ fn open_close_file() {
     let (file, _version): (NativeFile, u64) = {
         unsafe {
             unwrap!(call_2(|ud, cb| {
-                dir_fetch_file(&app, container_info_h, ffi_file_name.as_ptr(), ud, cb)
+                dir_fetch_file(&app, container_info_h, ffi_file_name.as_ptr(), 0, ud, cb)
             }))
         }
     };
@@ -825,7 +895,7 @@ fn open_close_file() {
     let (file, _version): (NativeFile, u64) = {
         unsafe {
             unwrap!(call_2(|ud, cb| {
-                dir_fetch_file(&app, container_info_h, ffi_file_name.as_ptr(), ud, cb)
+                dir_fetch_file(&app, container_info_h, ffi_file_name.as_ptr(), 0, ud, cb)
             }))
         }
     };
@@ -850,7 +920,7 @@ fn open_close_file() {
     let (file, _version): (NativeFile, u64) = {
         unsafe {
             unwrap!(call_2(|ud, cb| {
-                dir_fetch_file(&app, container_info_h, ffi_file_name.as_ptr(), ud, cb)
+                dir_fetch_file(&app, container_info_h, ffi_file_name.as_ptr(), 0, ud, cb)
             }))
         }
     };
@@ -871,3 +941,74 @@ fn open_close_file() {
 
     let _: NativeFile = unsafe { unwrap!(call_1(|ud, cb| file_close(&app, append_h, ud, cb))) };
 }
+
+// Test resuming an interrupted upload.
+// 1. Open a new file for appending and write a chunk, but don't close it.
+// 2. Open the file again - the upload is resumed after the stored chunk.
+// 3. Write another chunk, close the file and insert it into the directory.
+// 4. Read the file back and check it contains both chunks.
+#[test]
+fn resume_upload() {
+    let app = create_app();
+
+    let dir_h: MDataInfoHandle =
+        unsafe { unwrap!(call_1(|ud, cb| mdata_info_random_private(&app, 15_000, ud, cb))) };
+    unsafe { unwrap!(call_0(|ud, cb| dir_create(&app, dir_h, ud, cb))) };
+
+    let file_name = unwrap!(CString::new("upload.txt"));
+    let ffi_file = NativeFile::new(Vec::new()).into_repr_c();
+
+    let (write_h, stored_size): (FileContextHandle, u64) = unsafe {
+        unwrap!(call_2(|ud, cb| {
+            file_open_for_append(&app, dir_h, file_name.as_ptr(), &ffi_file, ud, cb)
+        }))
+    };
+    assert_eq!(stored_size, 0);
+
+    let chunk = b"hello ";
+    unsafe {
+        unwrap!(call_0(|ud, cb| {
+            file_write_chunk(&app, write_h, chunk.as_ptr(), chunk.len(), ud, cb)
+        }))
+    };
+
+    // The first handle is never closed, as if the app was restarted.
+    let (write_h, stored_size): (FileContextHandle, u64) = unsafe {
+        unwrap!(call_2(|ud, cb| {
+            file_open_for_append(&app, dir_h, file_name.as_ptr(), &ffi_file, ud, cb)
+        }))
+    };
+    assert_eq!(stored_size, chunk.len() as u64);
+
+    let chunk = b"world";
+    unsafe {
+        unwrap!(call_0(|ud, cb| {
+            file_write_chunk(&app, write_h, chunk.as_ptr(), chunk.len(), ud, cb)
+        }))
+    };
+
+    let written_file: NativeFile =
+        unsafe { unwrap!(call_1(|ud, cb| file_close(&app, write_h, ud, cb))) };
+    assert_eq!(written_file.size(), 11);
+
+    let ffi_file = written_file.into_repr_c();
+    unsafe {
+        unwrap!(call_0(|ud, cb| {
+            dir_insert_file(&app, dir_h, file_name.as_ptr(), &ffi_file, 0, ud, cb)
+        }))
+    }
+
+    let read_h: FileContextHandle = unsafe {
+        unwrap!(call_1(|ud, cb| {
+            file_open(&app, dir_h, &ffi_file, OPEN_MODE_READ, ud, cb)
+        }))
+    };
+    let content = unsafe {
+        unwrap!(call_vec_u8(|ud, cb| {
+            file_read(&app, read_h, 0, FILE_READ_TO_END, ud, cb)
+        }))
+    };
+    assert_eq!(content, b"hello world");
+
+    let _: NativeFile = unsafe { unwrap!(call_1(|ud, cb| file_close(&app, read_h, ud, cb))) };
+}
@@ -0,0 +1,82 @@
+// Copyright 2017 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under (1) the MaidSafe.net Commercial License,
+// version 1.0 or later, or (2) The General Public License (GPL), version 3, depending on which
+// licence you accepted on initial access to the Software (the "Licences").
+//
+// By contributing code to the SAFE Network Software, or to this project generally, you agree to be
+// bound by the terms of the MaidSafe Contributor Agreement.  This, along with the Licenses can be
+// found in the root directory of this project at LICENSE, COPYING and CONTRIBUTOR.
+//
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.
+//
+// Please review the Licences for the specific language governing permissions and limitations
+// relating to use of the SAFE Network Software.
+
+use errors::ERR_INVALID_OBJECT_HANDLE;
+use ffi::crypto::{app_pub_sign_key, sign_key_free};
+use ffi::object_cache::*;
+use ffi_utils::test_utils::{call_0, call_1, send_via_user_data, sender_as_user_data};
+use object_cache::{ObjectCacheStats, ObjectHandle, SignKeyHandle};
+use std::os::raw::c_void;
+use std::sync::mpsc;
+use test_utils::create_app;
+
+// Objects beyond the capacity are evicted and reported, unless pinned.
+#[test]
+fn evict_and_pin() {
+    let app = create_app();
+    let (tx, rx) = mpsc::channel::<ObjectHandle>();
+
+    unsafe {
+        unwrap!(call_0(|ud, cb| {
+            app_set_object_eviction_cb(&app, sender_as_user_data(&tx), evict_cb, ud, cb)
+        }));
+        unwrap!(call_0(|ud, cb| app_set_object_cache_capacity(&app, 1, ud, cb)));
+    }
+
+    let new_handle = || -> SignKeyHandle {
+        unsafe { unwrap!(call_1(|ud, cb| app_pub_sign_key(&app, ud, cb))) }
+    };
+
+    let handle0 = new_handle();
+    unsafe { unwrap!(call_0(|ud, cb| handle_pin(&app, handle0, ud, cb))) };
+
+    // `handle0` is pinned, so the store may exceed its capacity.
+    let handle1 = new_handle();
+    let _ = new_handle();
+    assert_eq!(unwrap!(rx.recv()), handle1);
+
+    unsafe {
+        unwrap!(call_0(|ud, cb| handle_unpin(&app, handle0, ud, cb)));
+
+        let res = call_0(|ud, cb| handle_unpin(&app, handle0, ud, cb));
+        assert_eq!(res, Err(ERR_INVALID_OBJECT_HANDLE));
+
+        let res = call_0(|ud, cb| handle_pin(&app, handle1, ud, cb));
+        assert_eq!(res, Err(ERR_INVALID_OBJECT_HANDLE));
+    }
+
+    extern "C" fn evict_cb(user_data: *mut c_void, handle: ObjectHandle) {
+        unsafe { send_via_user_data(user_data, handle) }
+    }
+}
+
+// The stats reflect the objects created and freed.
+#[test]
+fn stats() {
+    let app = create_app();
+
+    let stats = || -> ObjectCacheStats {
+        unsafe { unwrap!(call_1(|ud, cb| app_object_cache_stats(&app, ud, cb))) }
+    };
+    let initial = stats();
+
+    let handle: SignKeyHandle = unsafe { unwrap!(call_1(|ud, cb| app_pub_sign_key(&app, ud, cb))) };
+    assert_eq!(stats().sign_key, initial.sign_key + 1);
+
+    unsafe { unwrap!(call_0(|ud, cb| sign_key_free(&app, handle, ud, cb))) };
+    assert_eq!(stats(), initial);
+}
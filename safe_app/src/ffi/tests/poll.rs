@@ -0,0 +1,135 @@
+// Copyright 2017 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under (1) the MaidSafe.net Commercial License,
+// version 1.0 or later, or (2) The General Public License (GPL), version 3, depending on which
+// licence you accepted on initial access to the Software (the "Licences").
+//
+// By contributing code to the SAFE Network Software, or to this project generally, you agree to be
+// bound by the terms of the MaidSafe Contributor Agreement.  This, along with the Licenses can be
+// found in the root directory of this project at LICENSE, COPYING and CONTRIBUTOR.
+//
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.
+//
+// Please review the Licences for the specific language governing permissions and limitations
+// relating to use of the SAFE Network Software.
+
+use App;
+use errors::{ERR_INVALID_MDATA_INFO_HANDLE, ERR_INVALID_OPERATION_ID};
+use ffi::poll::*;
+use maidsafe_utilities::serialisation::deserialise;
+use operations::OperationId;
+use std::ptr;
+use std::thread;
+use std::time::Duration;
+use test_utils::create_app;
+
+// Poll until the operation completes, then collect its error code and payload.
+fn wait_for_result(app: &App, op_id: OperationId) -> (i32, Vec<u8>) {
+    loop {
+        let mut status = OPERATION_PENDING;
+        assert_eq!(unsafe { operation_status(app, op_id, &mut status) }, 0);
+        if status == OPERATION_COMPLETE {
+            break;
+        }
+        thread::sleep(Duration::from_millis(10));
+    }
+
+    let mut error_code = 0;
+    let mut ptr = ptr::null_mut();
+    let mut len = 0;
+    let mut cap = 0;
+
+    unsafe {
+        assert_eq!(
+            operation_result(app, op_id, &mut error_code, &mut ptr, &mut len, &mut cap),
+            0
+        );
+        let payload = ::std::slice::from_raw_parts(ptr, len).to_vec();
+        operation_payload_free(ptr, len, cap);
+        (error_code, payload)
+    }
+}
+
+// Collect account info through the polling API.
+#[test]
+fn account_info_polled() {
+    let app = create_app();
+
+    let mut op_id = 0;
+    assert_eq!(unsafe { app_account_info_polled(&app, &mut op_id) }, 0);
+
+    let (error_code, payload) = wait_for_result(&app, op_id);
+    assert_eq!(error_code, 0);
+
+    let (_, mutations_available): (u64, u64) = unwrap!(deserialise(&payload));
+    assert!(mutations_available > 0);
+
+    // The result can only be collected once.
+    let mut status = OPERATION_PENDING;
+    assert_eq!(
+        unsafe { operation_status(&app, op_id, &mut status) },
+        ERR_INVALID_OPERATION_ID
+    );
+}
+
+// Errors of the operation are reported through the result, not the poll.
+#[test]
+fn failed_operation_polled() {
+    let app = create_app();
+    let key = b"key".to_vec();
+
+    let mut op_id = 0;
+    assert_eq!(
+        unsafe { mdata_get_value_polled(&app, 1234, key.as_ptr(), key.len(), &mut op_id) },
+        0
+    );
+
+    let (error_code, payload) = wait_for_result(&app, op_id);
+    assert_eq!(error_code, ERR_INVALID_MDATA_INFO_HANDLE);
+    assert!(payload.is_empty());
+
+    // Discarding unknown operation fails.
+    assert_eq!(
+        unsafe { operation_discard(&app, op_id) },
+        ERR_INVALID_OPERATION_ID
+    );
+}
+
+// Functions returning plain error codes leave a description of the error behind.
+#[test]
+fn last_error_description() {
+    use ffi::app_last_error_description;
+    use ffi_utils::FfiResult;
+    use std::ffi::CStr;
+    use std::os::raw::{c_char, c_void};
+    use std::sync::mpsc::{self, Sender};
+
+    let app = create_app();
+
+    let mut status = OPERATION_PENDING;
+    assert_eq!(
+        unsafe { operation_status(&app, 1234, &mut status) },
+        ERR_INVALID_OPERATION_ID
+    );
+
+    extern "C" fn description_cb(
+        user_data: *mut c_void,
+        res: FfiResult,
+        description: *const c_char,
+    ) {
+        assert_eq!(res.error_code, 0);
+        unsafe {
+            let description = unwrap!(CStr::from_ptr(description).to_str()).to_owned();
+            let tx = user_data as *mut Sender<String>;
+            unwrap!((*tx).send(description));
+        }
+    }
+
+    let (mut tx, rx) = mpsc::channel();
+    unsafe {
+        app_last_error_description(&mut tx as *mut Sender<String> as *mut c_void, description_cb)
+    };
+    assert_eq!(unwrap!(rx.recv()), "Invalid operation id");
+}
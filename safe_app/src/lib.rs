@@ -54,6 +54,7 @@ extern crate safe_authenticator;
 #[macro_use]
 extern crate safe_core;
 extern crate self_encryption;
+extern crate serde;
 #[macro_use]
 extern crate serde_derive;
 extern crate tiny_keccak;
@@ -65,11 +66,16 @@ pub mod ffi;
 
 pub use ffi::*;
 pub use ffi::access_container::*;
+#[cfg(feature = "unstable-data-types")]
+pub use ffi::appendable_data::*;
+pub use ffi::channel::*;
 pub use ffi::cipher_opt::*;
 pub use ffi::crypto::*;
 pub use ffi::immutable_data::*;
 pub use ffi::ipc::*;
 pub use ffi::logging::*;
+pub use ffi::messaging::*;
+pub use ffi::metrics::*;
 pub use ffi::mdata_info::*;
 pub use ffi::mutable_data::*;
 pub use ffi::mutable_data::entries::*;
@@ -77,9 +83,18 @@ pub use ffi::mutable_data::entry_actions::*;
 pub use ffi::mutable_data::metadata::*;
 pub use ffi::mutable_data::permissions::*;
 pub use ffi::nfs::*;
+pub use ffi::poll::*;
+pub use ffi::profile::*;
+pub use ffi::shared_container::*;
 
+pub mod backpressure;
+pub mod backup;
+pub mod fetch;
 mod errors;
+pub mod native;
 pub mod object_cache;
+pub mod operations;
+pub mod settings;
 #[cfg(test)]
 mod tests;
 
@@ -88,7 +103,9 @@ mod tests;
 pub mod test_utils;
 
 pub use self::errors::*;
+use self::backpressure::{OverflowPolicy, RequestLimiter};
 use self::object_cache::ObjectCache;
+use self::operations::{OperationId, Operations};
 use futures::{Future, future};
 use futures::stream::Stream;
 use futures::sync::mpsc as futures_mpsc;
@@ -101,11 +118,13 @@ use safe_core::MockRouting as Routing;
 use safe_core::crypto::shared_secretbox;
 use safe_core::ipc::{AccessContInfo, AppKeys, AuthGranted, BootstrapConfig, Permission};
 use safe_core::ipc::resp::access_container_enc_key;
+use safe_core::metrics::AggregateMetrics;
 use std::cell::RefCell;
 use std::collections::{BTreeSet, HashMap};
 use std::rc::Rc;
-use std::sync::Mutex;
+use std::sync::{Arc, Mutex};
 use std::sync::mpsc as std_mpsc;
+use std::time::Duration;
 #[cfg(feature = "testing")]
 pub use test_utils::{test_create_app, test_create_app_with_access};
 use tokio_core::reactor::{Core, Handle};
@@ -122,11 +141,13 @@ macro_rules! try_tx {
 type AppFuture<T> = Future<Item = T, Error = AppError>;
 
 /// Represents an entry for a single app in the access container
-type AccessContainerEntry = HashMap<String, (MDataInfo, BTreeSet<Permission>)>;
+pub type AccessContainerEntry = HashMap<String, (MDataInfo, BTreeSet<Permission>)>;
 
 /// Handle to an application instance.
 pub struct App {
     core_tx: Mutex<CoreMsgTx<AppContext>>,
+    operations: Arc<Operations>,
+    limiter: Arc<RequestLimiter>,
     _core_joiner: Joiner,
 }
 
@@ -259,8 +280,11 @@ impl App {
             + 'static,
     {
         let (tx, rx) = std_mpsc::sync_channel(0);
+        let limiter = Arc::new(RequestLimiter::new());
+        let limiter2 = Arc::clone(&limiter);
 
         let joiner = thread::named("App Event Loop", move || {
+            limiter2.set_event_loop_thread();
             let el = try_tx!(Core::new(), tx);
             let el_h = el.handle();
 
@@ -285,21 +309,139 @@ impl App {
 
         Ok(App {
             core_tx: Mutex::new(core_tx),
+            operations: Arc::new(Operations::new()),
+            limiter,
             _core_joiner: joiner,
         })
     }
 
-    /// Send a message to app's event loop
+    /// Send a message to app's event loop. The operation counts against the
+    /// limit of operations in flight (see `set_request_limit`) until the
+    /// future returned by `f` completes.
     pub fn send<F>(&self, f: F) -> Result<(), AppError>
     where
         F: FnOnce(&Client<AppContext>, &AppContext) -> Option<Box<Future<Item = (), Error = ()>>>
             + Send
             + 'static,
     {
-        let msg = CoreMsg::new(f);
+        let slot = RequestLimiter::acquire(&self.limiter)?;
+        let msg = CoreMsg::new(move |client, context| {
+            f(client, context).map(|fut| {
+                fut.then(move |res| {
+                    drop(slot);
+                    res
+                }).into_box()
+            })
+        });
         let core_tx = unwrap!(self.core_tx.lock());
         core_tx.unbounded_send(msg).map_err(AppError::from)
     }
+
+    /// Limit the number of operations in flight to `max`, applying `policy`
+    /// to the operations sent over it. `None` removes the limit.
+    pub fn set_request_limit(&self, max: Option<usize>, policy: OverflowPolicy) {
+        self.limiter.configure(max, policy);
+    }
+
+    /// Number of operations in flight.
+    pub fn requests_in_flight(&self) -> usize {
+        self.limiter.in_flight()
+    }
+
+    /// Start an operation in the app's event loop whose result is collected by
+    /// polling (see `Operations`) instead of through a callback. The future
+    /// returned by `f` should resolve to the serialised result.
+    pub fn send_polled<F>(&self, f: F) -> Result<OperationId, AppError>
+    where
+        F: FnOnce(&Client<AppContext>, &AppContext) -> Box<AppFuture<Vec<u8>>> + Send + 'static,
+    {
+        let id = self.operations.register();
+        let operations = Arc::clone(&self.operations);
+
+        let res = self.send(move |client, context| {
+            f(client, context)
+                .then(move |res| {
+                    operations.complete(id, res);
+                    Ok(())
+                })
+                .into_box()
+                .into()
+        });
+
+        if let Err(err) = res {
+            let _ = self.operations.remove(id);
+            return Err(err);
+        }
+
+        Ok(id)
+    }
+
+    /// Start an operation in the app's event loop which can be aborted with
+    /// `cancel` using the returned id. `on_done` is called from the event loop
+    /// with the result of the future returned by `f`, or with
+    /// `AppError::Cancelled` if the operation got cancelled first, in which
+    /// case the pending future is dropped.
+    pub fn send_cancellable<F, G, T>(&self, f: F, on_done: G) -> Result<OperationId, AppError>
+    where
+        F: FnOnce(&Client<AppContext>, &AppContext) -> Box<AppFuture<T>> + Send + 'static,
+        G: FnOnce(Result<T, AppError>) + Send + 'static,
+        T: 'static,
+    {
+        let (id, cancel_rx) = self.operations.register_cancellable();
+        let operations = Arc::clone(&self.operations);
+
+        let res = self.send(move |client, context| {
+            let cancelled = cancel_rx.then(|_| Err(AppError::Cancelled));
+
+            f(client, context)
+                .select(cancelled)
+                .then(move |res| {
+                    operations.finish_cancellable(id);
+                    on_done(res.map(|(value, _)| value).map_err(|(err, _)| err));
+                    Ok(())
+                })
+                .into_box()
+                .into()
+        });
+
+        if let Err(err) = res {
+            self.operations.finish_cancellable(id);
+            return Err(err);
+        }
+
+        Ok(id)
+    }
+
+    /// Cancel an operation started with `send_cancellable`. Returns
+    /// `AppError::InvalidOperationId` if the operation is unknown or has
+    /// already completed.
+    pub fn cancel(&self, id: OperationId) -> Result<(), AppError> {
+        if self.operations.cancel(id) {
+            Ok(())
+        } else {
+            Err(AppError::InvalidOperationId)
+        }
+    }
+
+    /// Shut the app down gracefully: stop accepting new operations, wait up
+    /// to `timeout` for the in-flight requests and their callbacks to
+    /// complete and then stop the event loop. `on_done` is called from the
+    /// event loop thread once it's done. Operations sent afterwards fail
+    /// with an error instead of being cut off mid-flight.
+    pub fn shutdown<F>(&self, timeout: Duration, on_done: F) -> Result<(), AppError>
+    where
+        F: FnOnce() + Send + 'static,
+    {
+        let msg = CoreMsg::build_drainer(timeout, on_done);
+        let core_tx = unwrap!(self.core_tx.lock());
+        core_tx.unbounded_send(msg).map_err(AppError::from)
+    }
+
+    /// Registry of the operations started with `send_polled` and
+    /// `send_cancellable`.
+    pub fn operations(&self) -> &Operations {
+        &self.operations
+    }
 }
 
 impl Drop for App {
@@ -331,11 +473,13 @@ pub enum AppContext {
 #[allow(missing_docs)]
 pub struct Unregistered {
     object_cache: ObjectCache,
+    metrics: AggregateMetrics,
 }
 
 #[allow(missing_docs)]
 pub struct Registered {
     object_cache: ObjectCache,
+    metrics: AggregateMetrics,
     app_id: String,
     sym_enc_key: shared_secretbox::Key,
     access_container_info: AccessContInfo,
@@ -344,7 +488,10 @@ pub struct Registered {
 
 impl AppContext {
     fn unregistered() -> Self {
-        AppContext::Unregistered(Rc::new(Unregistered { object_cache: ObjectCache::new() }))
+        AppContext::Unregistered(Rc::new(Unregistered {
+            object_cache: ObjectCache::new(),
+            metrics: AggregateMetrics::new(),
+        }))
     }
 
     fn registered(
@@ -354,6 +501,7 @@ impl AppContext {
     ) -> Self {
         AppContext::Registered(Rc::new(Registered {
             object_cache: ObjectCache::new(),
+            metrics: AggregateMetrics::new(),
             app_id: app_id,
             sym_enc_key: sym_enc_key,
             access_container_info: access_container_info,
@@ -369,6 +517,15 @@ impl AppContext {
         }
     }
 
+    /// Aggregated metrics of the network operations. They are collected only
+    /// after being installed into the client with `Client::set_metrics`.
+    pub fn metrics(&self) -> &AggregateMetrics {
+        match *self {
+            AppContext::Unregistered(ref context) => &context.metrics,
+            AppContext::Registered(ref context) => &context.metrics,
+        }
+    }
+
     /// Symmetric encryption/decryption key.
     pub fn sym_enc_key(&self) -> Result<&shared_secretbox::Key, AppError> {
         Ok(&self.as_registered()?.sym_enc_key)
@@ -395,6 +552,20 @@ impl AppContext {
             .into_box()
     }
 
+    /// Fetch the `MDataInfo` of the app's own container.
+    pub fn get_own_container(&self, client: &Client<AppContext>) -> Box<AppFuture<MDataInfo>> {
+        let name = format!("apps/{}", fry!(self.as_registered()).app_id);
+
+        self.get_access_info(client)
+            .and_then(move |mut access_info| {
+                access_info
+                    .remove(&name)
+                    .map(|(info, _)| info)
+                    .ok_or(AppError::NoSuchContainer)
+            })
+            .into_box()
+    }
+
     fn as_registered(&self) -> Result<&Rc<Registered>, AppError> {
         match *self {
             AppContext::Registered(ref a) => Ok(a),
@@ -0,0 +1,325 @@
+// Copyright 2017 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under (1) the MaidSafe.net Commercial License,
+// version 1.0 or later, or (2) The General Public License (GPL), version 3, depending on which
+// licence you accepted on initial access to the Software (the "Licences").
+//
+// By contributing code to the SAFE Network Software, or to this project generally, you agree to be
+// bound by the terms of the MaidSafe Contributor Agreement.  This, along with the Licenses can be
+// found in the root directory of this project at LICENSE, COPYING and CONTRIBUTOR.
+//
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.
+//
+// Please review the Licences for the specific language governing permissions and limitations
+// relating to use of the SAFE Network Software.
+
+//! Native Rust API. Provides the app-facing functionality as methods returning
+//! futures, so Rust applications can use this library directly instead of
+//! going through the `extern "C"` layer.
+
+use {AccessContainerEntry, App, AppContext};
+use backup;
+use errors::AppError;
+use futures::{Future, IntoFuture, future};
+use futures::sync::oneshot;
+use routing::{AccountInfo, EntryAction, ImmutableData, Value, XorName};
+use safe_core::{Client, FutureExt, MDataInfo, immutable_data, mdata_info};
+use safe_core::crypto::shared_secretbox;
+use safe_core::ipc::{self, IpcMsg, IpcReq};
+use safe_core::ipc::uri_scheme::SAFE_AUTH_SCHEME;
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::{Read, Write};
+use std::path::Path;
+
+/// Future returned by the native API. Unlike the futures running inside the
+/// app's event loop, it can be driven from any thread.
+pub type NativeFuture<T> = Future<Item = T, Error = AppError> + Send;
+
+impl App {
+    /// Run `f` in the app's event loop and return a future which resolves to
+    /// the result of the future returned by `f`.
+    pub fn exec<F, I, T>(&self, f: F) -> Box<NativeFuture<T>>
+    where
+        F: FnOnce(&Client<AppContext>, &AppContext) -> I + Send + 'static,
+        I: IntoFuture<Item = T, Error = AppError> + 'static,
+        T: Send + 'static,
+    {
+        let (tx, rx) = oneshot::channel();
+
+        let res = self.send(move |client, context| {
+            f(client, context)
+                .into_future()
+                .then(move |res| {
+                    let _ = tx.send(res);
+                    Ok(())
+                })
+                .into_box()
+                .into()
+        });
+
+        if let Err(err) = res {
+            return Box::new(future::err(err));
+        }
+
+        Box::new(rx.then(|res| match res {
+            Ok(res) => res,
+            Err(_) => Err(AppError::from("App event loop terminated")),
+        }))
+    }
+
+    /// Get the account usage statistics.
+    pub fn account_info(&self) -> Box<NativeFuture<AccountInfo>> {
+        self.exec(|client, _| client.get_account_info().map_err(AppError::from))
+    }
+
+    /// Fetch the containers this app has access to, together with the granted
+    /// permissions.
+    pub fn access_info(&self) -> Box<NativeFuture<AccessContainerEntry>> {
+        self.exec(|client, context| context.get_access_info(client))
+    }
+
+    /// Fetch the `MDataInfo` of the container with the given name.
+    pub fn container(&self, name: &str) -> Box<NativeFuture<MDataInfo>> {
+        let name = name.to_string();
+
+        self.exec(move |client, context| {
+            context.get_access_info(client).and_then(move |mut access_info| {
+                access_info
+                    .remove(&name)
+                    .map(|(info, _)| info)
+                    .ok_or(AppError::NoSuchContainer)
+            })
+        })
+    }
+
+    /// Store `value` on the network as immutable data, optionally encrypted
+    /// with `encryption_key`, and return the name it can be fetched with.
+    pub fn put_idata(
+        &self,
+        value: Vec<u8>,
+        encryption_key: Option<shared_secretbox::Key>,
+    ) -> Box<NativeFuture<XorName>> {
+        self.exec(move |client, _| {
+            let client2 = client.clone();
+
+            immutable_data::create(client, &value, encryption_key)
+                .and_then(move |data| {
+                    let name = *data.name();
+                    client2.put_idata(data).map(move |_| name)
+                })
+                .map_err(AppError::from)
+        })
+    }
+
+    /// Fetch the value stored with `put_idata`.
+    pub fn get_idata(
+        &self,
+        name: XorName,
+        decryption_key: Option<shared_secretbox::Key>,
+    ) -> Box<NativeFuture<Vec<u8>>> {
+        self.exec(move |client, _| {
+            immutable_data::get_value(client, &name, decryption_key).map_err(AppError::from)
+        })
+    }
+
+    /// Put raw immutable data on the network.
+    pub fn put_raw_idata(&self, data: ImmutableData) -> Box<NativeFuture<()>> {
+        self.exec(move |client, _| client.put_idata(data).map_err(AppError::from))
+    }
+
+    /// Get value of the entry with the given key. If `info` is private, both
+    /// the key and the value are encrypted/decrypted transparently.
+    pub fn get_mdata_value(&self, info: MDataInfo, key: Vec<u8>) -> Box<NativeFuture<Value>> {
+        self.exec(move |client, _| {
+            let key = fry!(info.enc_entry_key(&key));
+
+            client
+                .get_mdata_value(info.name, info.type_tag, key)
+                .map_err(AppError::from)
+                .and_then(move |value| {
+                    Ok(Value {
+                        content: info.decrypt(&value.content)?,
+                        entry_version: value.entry_version,
+                    })
+                })
+                .into_box()
+        })
+    }
+
+    /// List all entries of the mutable data, decrypting them if `info` is
+    /// private. Deleted entries are listed with empty content.
+    pub fn list_mdata_entries(
+        &self,
+        info: MDataInfo,
+    ) -> Box<NativeFuture<BTreeMap<Vec<u8>, Value>>> {
+        self.exec(move |client, _| {
+            client
+                .list_mdata_entries(info.name, info.type_tag)
+                .map_err(AppError::from)
+                .and_then(move |entries| {
+                    Ok(mdata_info::decrypt_entries_with_deleted(&info, &entries)?)
+                })
+        })
+    }
+
+    /// Apply the entry actions to the mutable data, encrypting them first if
+    /// `info` is private.
+    pub fn mutate_mdata_entries(
+        &self,
+        info: MDataInfo,
+        actions: BTreeMap<Vec<u8>, EntryAction>,
+    ) -> Box<NativeFuture<()>> {
+        self.exec(move |client, _| {
+            let actions = fry!(mdata_info::encrypt_entry_actions(&info, &actions));
+
+            client
+                .mutate_mdata_entries(info.name, info.type_tag, actions)
+                .map_err(AppError::from)
+                .into_box()
+        })
+    }
+
+    /// Export all the data reachable from the app's access container into an
+    /// archive at `path`, encrypted with `key`. `progress` is called with the
+    /// number of entries exported so far and the total number of entries.
+    pub fn backup<P, F>(
+        &self,
+        path: P,
+        key: shared_secretbox::Key,
+        progress: F,
+    ) -> Box<NativeFuture<()>>
+    where
+        P: AsRef<Path>,
+        F: FnMut(u64, u64) + Send + 'static,
+    {
+        let path = path.as_ref().to_path_buf();
+
+        Box::new(
+            self.exec(move |client, context| {
+                backup::export(client, context, key, progress)
+            }).and_then(move |archive| {
+                    let mut file = File::create(path)?;
+                    file.write_all(&archive)?;
+                    Ok(())
+                }),
+        )
+    }
+
+    /// Restore the archive at `path` created by `backup` into the containers
+    /// of this app.
+    pub fn restore<P, F>(
+        &self,
+        path: P,
+        key: shared_secretbox::Key,
+        progress: F,
+    ) -> Box<NativeFuture<()>>
+    where
+        P: AsRef<Path>,
+        F: FnMut(u64, u64) + Send + 'static,
+    {
+        let mut archive = Vec::new();
+        let res = File::open(path).and_then(|mut file| file.read_to_end(&mut archive));
+        if let Err(err) = res {
+            return Box::new(future::err(AppError::from(err)));
+        }
+
+        self.exec(move |client, context| {
+            backup::restore(client, context, &archive, &key, progress)
+        })
+    }
+}
+
+/// Encode the IPC request to be sent to the authenticator. Returns the
+/// generated request id together with the encoded request.
+pub fn encode_req(req: IpcReq) -> Result<(u32, String), AppError> {
+    let req_id = ipc::gen_req_id();
+    let encoded = ipc::encode_msg(&IpcMsg::Req { req_id, req }, SAFE_AUTH_SCHEME)?;
+    Ok((req_id, encoded))
+}
+
+/// Decode IPC message received from the authenticator.
+pub fn decode_msg(encoded: &str) -> Result<IpcMsg, AppError> {
+    Ok(ipc::decode_msg(encoded)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use routing::{Action, MutableData, PermissionSet, User};
+    use safe_core::DIR_TAG;
+    use test_utils::create_app;
+
+    // Store and fetch immutable data through the native API.
+    #[test]
+    fn idata_round_trip() {
+        let app = create_app();
+        let key = shared_secretbox::gen_key();
+
+        let name = unwrap!(app.put_idata(vec![1, 2, 3], Some(key.clone())).wait());
+        let value = unwrap!(app.get_idata(name, Some(key)).wait());
+        assert_eq!(value, vec![1, 2, 3]);
+
+        let info = unwrap!(app.account_info().wait());
+        assert!(info.mutations_done > 0);
+    }
+
+    // Mutate and read private mutable data through the native API.
+    #[test]
+    fn private_mdata() {
+        let app = create_app();
+        let info = unwrap!(MDataInfo::random_private(DIR_TAG));
+        let info2 = info.clone();
+
+        unwrap!(
+            app.exec(move |client, _| {
+                let app_key = unwrap!(client.public_signing_key());
+                let owners = btree_set![unwrap!(client.owner_key())];
+                let perms = btree_map![
+                    User::Key(app_key) => PermissionSet::new().allow(Action::Insert)
+                ];
+                let mdata = unwrap!(MutableData::new(
+                    info2.name,
+                    info2.type_tag,
+                    perms,
+                    BTreeMap::new(),
+                    owners,
+                ));
+
+                client.put_mdata(mdata).map_err(AppError::from)
+            }).wait()
+        );
+
+        let actions = btree_map![
+            b"key".to_vec() => EntryAction::Ins(Value {
+                content: b"value".to_vec(),
+                entry_version: 0,
+            })
+        ];
+        unwrap!(app.mutate_mdata_entries(info.clone(), actions).wait());
+
+        let value = unwrap!(app.get_mdata_value(info.clone(), b"key".to_vec()).wait());
+        assert_eq!(value.content, b"value".to_vec());
+
+        let entries = unwrap!(app.list_mdata_entries(info).wait());
+        assert_eq!(entries.len(), 1);
+        assert!(entries.contains_key(&b"key".to_vec()));
+    }
+
+    // Encode and decode IPC requests.
+    #[test]
+    fn ipc_round_trip() {
+        let (req_id, encoded) = unwrap!(encode_req(IpcReq::Unregistered));
+        assert!(encoded.starts_with(SAFE_AUTH_SCHEME));
+
+        match unwrap!(decode_msg(&encoded)) {
+            IpcMsg::Req {
+                req_id: decoded_id,
+                req: IpcReq::Unregistered,
+            } => assert_eq!(decoded_id, req_id),
+            x => panic!("Unexpected {:?}", x),
+        }
+    }
+}
@@ -16,23 +16,32 @@
 // relating to use of the SAFE Network Software.
 
 //! This module implements storage (cache) for objects that have to be passed
-//! across FFI boundaries.
+//! across FFI boundaries. Each kind of object is kept in its own store holding
+//! up to `capacity` objects. When a store is full, its least recently used
+//! objects which are not pinned are evicted and the eviction callback (if any)
+//! is notified of their handles.
 
 use super::errors::AppError;
 use AppContext;
 use ffi::cipher_opt::CipherOpt;
+use ffi::mutable_data::entries::MDataEntriesIter;
 use ffi::nfs::FileContext;
+use ffi_utils::ReprC;
 use lru_cache::LruCache;
 use routing::{EntryAction, PermissionSet, User, Value};
 use rust_sodium::crypto::{box_, sign};
 use safe_core::{MDataInfo, SelfEncryptionStorage};
-use safe_core::crypto::shared_box;
+#[cfg(feature = "unstable-data-types")]
+use safe_core::appendable_data::AppendableData;
+use safe_core::crypto::{shared_box, shared_sign};
 use self_encryption::{SelfEncryptor, SequentialEncryptor};
 use std::cell::{Cell, RefCell, RefMut};
-use std::collections::{BTreeMap, BTreeSet};
+use std::collections::{BTreeMap, BTreeSet, HashSet};
 use std::u64;
+use std::usize;
 
-const DEFAULT_CAPACITY: usize = 1000;
+/// Default maximum number of objects of each kind kept in the cache.
+pub const DEFAULT_CAPACITY: usize = 1000;
 
 /// Object handle associated with objects. In normal C API one would expect rust
 /// code to pass pointers to opaque object to C. C code would then need to pass
@@ -58,6 +67,8 @@ pub type MDataInfoHandle = ObjectHandle;
 /// Disambiguating `ObjectHandle`
 pub type MDataEntriesHandle = ObjectHandle;
 /// Disambiguating `ObjectHandle`
+pub type MDataEntriesIteratorHandle = ObjectHandle;
+/// Disambiguating `ObjectHandle`
 pub type MDataKeysHandle = ObjectHandle;
 /// Disambiguating `ObjectHandle`
 pub type MDataValuesHandle = ObjectHandle;
@@ -74,16 +85,74 @@ pub type SelfEncryptorWriterHandle = ObjectHandle;
 /// Disambiguating `ObjectHandle`
 pub type SignKeyHandle = ObjectHandle;
 /// Disambiguating `ObjectHandle`
+pub type SignSecKeyHandle = ObjectHandle;
+/// Disambiguating `ObjectHandle`
 pub type FileContextHandle = ObjectHandle;
+/// Disambiguating `ObjectHandle`
+#[cfg(feature = "unstable-data-types")]
+pub type ADataHandle = ObjectHandle;
+
+/// Numbers of the objects of each kind currently held in the object cache.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct ObjectCacheStats {
+    /// Cipher options
+    pub cipher_opt: usize,
+    /// Public encryption keys
+    pub encrypt_key: usize,
+    /// Secret encryption keys
+    pub secret_key: usize,
+    /// `MDataInfo`s
+    pub mdata_info: usize,
+    /// MutableData entries
+    pub mdata_entries: usize,
+    /// MutableData entries iterators
+    pub mdata_entries_iter: usize,
+    /// MutableData keys
+    pub mdata_keys: usize,
+    /// MutableData values
+    pub mdata_values: usize,
+    /// MutableData entry actions
+    pub mdata_entry_actions: usize,
+    /// MutableData permissions
+    pub mdata_permissions: usize,
+    /// MutableData permission sets
+    pub mdata_permission_set: usize,
+    /// Self Encryptor readers
+    pub se_reader: usize,
+    /// Self Encryptor writers
+    pub se_writer: usize,
+    /// Public sign keys
+    pub sign_key: usize,
+    /// Secret sign keys
+    pub sign_sec_key: usize,
+    /// File contexts
+    pub file: usize,
+    /// Pinned objects of any kind
+    pub pinned: usize,
+}
+
+impl ReprC for ObjectCacheStats {
+    type C = *const ObjectCacheStats;
+    type Error = AppError;
+
+    unsafe fn clone_from_repr_c(repr_c: Self::C) -> Result<Self, Self::Error> {
+        Ok(*repr_c)
+    }
+}
 
 /// Contains session object cache
 pub struct ObjectCache {
     handle_gen: HandleGenerator,
+    capacity: Cell<usize>,
+    pinned: RefCell<HashSet<ObjectHandle>>,
+    on_evict: RefCell<Option<Box<Fn(ObjectHandle)>>>,
     cipher_opt: Store<CipherOpt>,
     encrypt_key: Store<box_::PublicKey>,
     secret_key: Store<shared_box::SecretKey>,
     mdata_info: Store<MDataInfo>,
     mdata_entries: Store<BTreeMap<Vec<u8>, Value>>,
+    mdata_entries_iter: Store<MDataEntriesIter>,
     mdata_keys: Store<BTreeSet<Vec<u8>>>,
     mdata_values: Store<Vec<Value>>,
     mdata_entry_actions: Store<BTreeMap<Vec<u8>, EntryAction>>,
@@ -92,7 +161,10 @@ pub struct ObjectCache {
     se_reader: Store<SelfEncryptor<SelfEncryptionStorage<AppContext>>>,
     se_writer: Store<SequentialEncryptor<SelfEncryptionStorage<AppContext>>>,
     sign_key: Store<sign::PublicKey>,
+    sign_sec_key: Store<shared_sign::SecretKey>,
     file: Store<FileContext>,
+    #[cfg(feature = "unstable-data-types")]
+    adata: Store<AppendableData>,
 }
 
 impl ObjectCache {
@@ -100,11 +172,15 @@ impl ObjectCache {
     pub fn new() -> Self {
         ObjectCache {
             handle_gen: HandleGenerator::new(),
+            capacity: Cell::new(DEFAULT_CAPACITY),
+            pinned: RefCell::new(HashSet::new()),
+            on_evict: RefCell::new(None),
             cipher_opt: Store::new(),
             encrypt_key: Store::new(),
             secret_key: Store::new(),
             mdata_info: Store::new(),
             mdata_entries: Store::new(),
+            mdata_entries_iter: Store::new(),
             mdata_keys: Store::new(),
             mdata_values: Store::new(),
             mdata_entry_actions: Store::new(),
@@ -113,27 +189,123 @@ impl ObjectCache {
             se_reader: Store::new(),
             se_writer: Store::new(),
             sign_key: Store::new(),
+            sign_sec_key: Store::new(),
             file: Store::new(),
+            #[cfg(feature = "unstable-data-types")]
+            adata: Store::new(),
         }
     }
 
     /// Reset the object cache by removing all objects stored in it.
     pub fn reset(&self) {
         self.handle_gen.reset();
-        self.cipher_opt.clear();
-        self.encrypt_key.clear();
-        self.secret_key.clear();
-        self.mdata_info.clear();
-        self.mdata_entries.clear();
-        self.mdata_keys.clear();
-        self.mdata_values.clear();
-        self.mdata_entry_actions.clear();
-        self.mdata_permissions.clear();
-        self.mdata_permission_set.clear();
-        self.se_reader.clear();
-        self.se_writer.clear();
-        self.sign_key.clear();
-        self.file.clear();
+        self.pinned.borrow_mut().clear();
+        for store in self.stores() {
+            store.clear();
+        }
+    }
+
+    /// Set the maximum number of objects of each kind kept in the cache,
+    /// evicting the excess objects right away.
+    pub fn set_capacity(&self, capacity: usize) {
+        self.capacity.set(capacity);
+
+        let evicted: Vec<_> = {
+            let pinned = self.pinned.borrow();
+            self.stores()
+                .into_iter()
+                .flat_map(|store| store.evict(capacity, &pinned, None))
+                .collect()
+        };
+        self.notify_evicted(evicted);
+    }
+
+    /// Set the callback notified of the handles of the evicted objects.
+    pub fn set_eviction_callback<F: Fn(ObjectHandle) + 'static>(&self, f: F) {
+        *self.on_evict.borrow_mut() = Some(Box::new(f));
+    }
+
+    /// Protect the object from being evicted. Pinned objects are kept even if
+    /// that makes their store exceed its capacity.
+    pub fn pin(&self, handle: ObjectHandle) -> Result<(), AppError> {
+        if self.stores().into_iter().any(|store| store.contains(handle)) {
+            let _ = self.pinned.borrow_mut().insert(handle);
+            Ok(())
+        } else {
+            Err(AppError::InvalidObjectHandle)
+        }
+    }
+
+    /// Allow the object to be evicted again.
+    pub fn unpin(&self, handle: ObjectHandle) -> Result<(), AppError> {
+        if self.pinned.borrow_mut().remove(&handle) {
+            Ok(())
+        } else {
+            Err(AppError::InvalidObjectHandle)
+        }
+    }
+
+    /// Count the objects currently held, e.g. to find handles which are
+    /// never freed.
+    pub fn stats(&self) -> ObjectCacheStats {
+        ObjectCacheStats {
+            cipher_opt: self.cipher_opt.len(),
+            encrypt_key: self.encrypt_key.len(),
+            secret_key: self.secret_key.len(),
+            mdata_info: self.mdata_info.len(),
+            mdata_entries: self.mdata_entries.len(),
+            mdata_entries_iter: self.mdata_entries_iter.len(),
+            mdata_keys: self.mdata_keys.len(),
+            mdata_values: self.mdata_values.len(),
+            mdata_entry_actions: self.mdata_entry_actions.len(),
+            mdata_permissions: self.mdata_permissions.len(),
+            mdata_permission_set: self.mdata_permission_set.len(),
+            se_reader: self.se_reader.len(),
+            se_writer: self.se_writer.len(),
+            sign_key: self.sign_key.len(),
+            sign_sec_key: self.sign_sec_key.len(),
+            file: self.file.len(),
+            pinned: self.pinned.borrow().len(),
+        }
+    }
+
+    fn stores(&self) -> Vec<&AnyStore> {
+        #[cfg_attr(not(feature = "unstable-data-types"), allow(unused_mut))]
+        let mut stores: Vec<&AnyStore> = vec![
+            &self.cipher_opt,
+            &self.encrypt_key,
+            &self.secret_key,
+            &self.mdata_info,
+            &self.mdata_entries,
+            &self.mdata_entries_iter,
+            &self.mdata_keys,
+            &self.mdata_values,
+            &self.mdata_entry_actions,
+            &self.mdata_permissions,
+            &self.mdata_permission_set,
+            &self.se_reader,
+            &self.se_writer,
+            &self.sign_key,
+            &self.sign_sec_key,
+            &self.file,
+        ];
+        #[cfg(feature = "unstable-data-types")]
+        stores.push(&self.adata);
+        stores
+    }
+
+    fn notify_evicted(&self, evicted: Vec<ObjectHandle>) {
+        if evicted.is_empty() {
+            return;
+        }
+
+        debug!("Evicted {} objects from the object cache", evicted.len());
+
+        if let Some(ref on_evict) = *self.on_evict.borrow() {
+            for handle in evicted {
+                on_evict(handle);
+            }
+        }
     }
 }
 
@@ -150,6 +322,13 @@ macro_rules! impl_cache {
             pub fn $insert(&self, value: $ty) -> $handle {
                 let handle = self.handle_gen.gen();
                 self.$name.insert(handle, value);
+
+                let evicted = {
+                    let pinned = self.pinned.borrow();
+                    self.$name.evict(self.capacity.get(), &pinned, Some(handle))
+                };
+                self.notify_evicted(evicted);
+
                 handle
             }
 
@@ -160,6 +339,7 @@ macro_rules! impl_cache {
 
             /// Remove object from the object cache and return it.
             pub fn $remove(&self, handle: $handle) -> Result<$ty, AppError> {
+                let _ = self.pinned.borrow_mut().remove(&handle);
                 self.$name.remove(handle).ok_or(AppError::$error)
             }
         }
@@ -209,6 +389,13 @@ impl_cache!(mdata_entries,
             get_mdata_entries,
             insert_mdata_entries,
             remove_mdata_entries);
+impl_cache!(mdata_entries_iter,
+            MDataEntriesIter,
+            MDataEntriesIteratorHandle,
+            InvalidMDataEntriesIteratorHandle,
+            get_mdata_entries_iter,
+            insert_mdata_entries_iter,
+            remove_mdata_entries_iter);
 impl_cache!(mdata_keys,
             BTreeSet<Vec<u8>>,
             MDataKeysHandle,
@@ -265,6 +452,13 @@ impl_cache!(sign_key,
             get_sign_key,
             insert_sign_key,
             remove_sign_key);
+impl_cache!(sign_sec_key,
+            shared_sign::SecretKey,
+            SignSecKeyHandle,
+            InvalidSignSecKeyHandle,
+            get_sign_sec_key,
+            insert_sign_sec_key,
+            remove_sign_sec_key);
 impl_cache!(file,
             FileContext,
             FileContextHandle,
@@ -272,6 +466,14 @@ impl_cache!(file,
             get_file,
             insert_file,
             remove_file);
+#[cfg(feature = "unstable-data-types")]
+impl_cache!(adata,
+            AppendableData,
+            ADataHandle,
+            InvalidADataHandle,
+            get_adata,
+            insert_adata,
+            remove_adata);
 
 impl Default for ObjectCache {
     fn default() -> Self {
@@ -304,7 +506,9 @@ struct Store<V> {
 
 impl<V> Store<V> {
     fn new() -> Self {
-        Store { inner: RefCell::new(LruCache::new(DEFAULT_CAPACITY)) }
+        // The objects are evicted by `evict`, so that the pinned ones can be
+        // skipped.
+        Store { inner: RefCell::new(LruCache::new(usize::MAX)) }
     }
 
     fn get(&self, handle: ObjectHandle) -> Option<RefMut<V>> {
@@ -324,6 +528,60 @@ impl<V> Store<V> {
     fn remove(&self, handle: ObjectHandle) -> Option<V> {
         self.inner.borrow_mut().remove(&handle)
     }
+}
+
+// Operations common to the stores of all kinds of objects.
+trait AnyStore {
+    fn len(&self) -> usize;
+
+    fn contains(&self, handle: ObjectHandle) -> bool;
+
+    // Remove the least recently used objects which are neither pinned nor
+    // `keep` until at most `capacity` are left, returning their handles.
+    fn evict(
+        &self,
+        capacity: usize,
+        pinned: &HashSet<ObjectHandle>,
+        keep: Option<ObjectHandle>,
+    ) -> Vec<ObjectHandle>;
+
+    fn clear(&self);
+}
+
+impl<V> AnyStore for Store<V> {
+    fn len(&self) -> usize {
+        self.inner.borrow().len()
+    }
+
+    fn contains(&self, handle: ObjectHandle) -> bool {
+        self.inner.borrow_mut().contains_key(&handle)
+    }
+
+    fn evict(
+        &self,
+        capacity: usize,
+        pinned: &HashSet<ObjectHandle>,
+        keep: Option<ObjectHandle>,
+    ) -> Vec<ObjectHandle> {
+        let mut inner = self.inner.borrow_mut();
+        if inner.len() <= capacity {
+            return Vec::new();
+        }
+
+        let excess = inner.len() - capacity;
+        let evicted: Vec<_> = inner
+            .iter()
+            .map(|(handle, _)| *handle)
+            .filter(|handle| !pinned.contains(handle) && Some(*handle) != keep)
+            .take(excess)
+            .collect();
+
+        for handle in &evicted {
+            let _ = inner.remove(handle);
+        }
+
+        evicted
+    }
 
     fn clear(&self) {
         self.inner.borrow_mut().clear()
@@ -347,4 +605,65 @@ mod tests {
         object_cache.reset();
         assert!(object_cache.get_sign_key(handle).is_err());
     }
+
+    // Test counting the objects held.
+    #[test]
+    fn stats() {
+        let object_cache = ObjectCache::new();
+        assert_eq!(object_cache.stats(), ObjectCacheStats::default());
+
+        let handle = object_cache.insert_sign_key(sign::gen_keypair().0);
+        let _ = object_cache.insert_sign_key(sign::gen_keypair().0);
+        let _ = object_cache.insert_mdata_keys(BTreeSet::new());
+        unwrap!(object_cache.pin(handle));
+
+        let stats = object_cache.stats();
+        assert_eq!(stats.sign_key, 2);
+        assert_eq!(stats.mdata_keys, 1);
+        assert_eq!(stats.pinned, 1);
+        assert_eq!(stats.cipher_opt, 0);
+
+        let _ = unwrap!(object_cache.remove_sign_key(handle));
+        let stats = object_cache.stats();
+        assert_eq!(stats.sign_key, 1);
+        assert_eq!(stats.pinned, 0);
+    }
+
+    // Test evicting the least recently used objects:
+    // 1. Objects beyond the capacity are evicted and reported to the callback.
+    // 2. Recently used and pinned objects are kept.
+    // 3. Lowering the capacity evicts the excess objects right away.
+    #[test]
+    fn eviction() {
+        use std::rc::Rc;
+
+        let object_cache = ObjectCache::new();
+        let evicted = Rc::new(RefCell::new(Vec::new()));
+        let evicted2 = Rc::clone(&evicted);
+        object_cache.set_eviction_callback(move |handle| evicted2.borrow_mut().push(handle));
+        object_cache.set_capacity(2);
+
+        let insert = || object_cache.insert_sign_key(sign::gen_keypair().0);
+        let handle0 = insert();
+        let handle1 = insert();
+        unwrap!(object_cache.pin(handle0));
+        assert!(object_cache.pin(12_345).is_err());
+
+        // `handle0` is pinned, so `handle1` is evicted instead.
+        let handle2 = insert();
+        assert_eq!(*evicted.borrow(), vec![handle1]);
+        assert!(object_cache.get_sign_key(handle0).is_ok());
+        assert!(object_cache.get_sign_key(handle1).is_err());
+
+        // `handle0` is used more recently than `handle2` once unpinned.
+        unwrap!(object_cache.unpin(handle0));
+        assert!(object_cache.unpin(handle0).is_err());
+        assert!(object_cache.get_sign_key(handle0).is_ok());
+        let handle3 = insert();
+        assert_eq!(*evicted.borrow(), vec![handle1, handle2]);
+
+        object_cache.set_capacity(1);
+        assert_eq!(*evicted.borrow(), vec![handle1, handle2, handle0]);
+        assert!(object_cache.get_sign_key(handle3).is_ok());
+    }
 }
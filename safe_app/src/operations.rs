@@ -0,0 +1,216 @@
+// Copyright 2017 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under (1) the MaidSafe.net Commercial License,
+// version 1.0 or later, or (2) The General Public License (GPL), version 3, depending on which
+// licence you accepted on initial access to the Software (the "Licences").
+//
+// By contributing code to the SAFE Network Software, or to this project generally, you agree to be
+// bound by the terms of the MaidSafe Contributor Agreement.  This, along with the Licenses can be
+// found in the root directory of this project at LICENSE, COPYING and CONTRIBUTOR.
+//
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.
+//
+// Please review the Licences for the specific language governing permissions and limitations
+// relating to use of the SAFE Network Software.
+
+//! Registry of operations whose results are collected by polling rather than
+//! delivered through callbacks, and of in-flight operations which can be
+//! cancelled.
+
+use errors::AppError;
+use ffi_utils::ErrorCode;
+use futures::sync::oneshot;
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+/// Identifier of an operation started through the polling API.
+pub type OperationId = u64;
+
+/// Outcome of a completed operation.
+#[derive(Debug)]
+pub struct OperationResult {
+    /// Error code of the operation (0 on success).
+    pub error_code: i32,
+    /// Serialised result of the operation (empty on failure).
+    pub payload: Vec<u8>,
+}
+
+/// Keeps track of in-flight and completed polled operations. Completed results
+/// stay in the registry until they are taken out by the host.
+pub struct Operations {
+    inner: Mutex<Inner>,
+}
+
+struct Inner {
+    last_id: OperationId,
+    ops: HashMap<OperationId, Option<OperationResult>>,
+    cancellable: HashMap<OperationId, oneshot::Sender<()>>,
+}
+
+impl Operations {
+    /// Create empty registry.
+    pub fn new() -> Self {
+        Operations {
+            inner: Mutex::new(Inner {
+                last_id: 0,
+                ops: HashMap::new(),
+                cancellable: HashMap::new(),
+            }),
+        }
+    }
+
+    /// Register new in-flight operation and return its id.
+    pub fn register(&self) -> OperationId {
+        let mut inner = unwrap!(self.inner.lock());
+        let id = inner.next_id();
+        let _ = inner.ops.insert(id, None);
+        id
+    }
+
+    /// Register new cancellable operation. Returns its id and a receiver which
+    /// resolves once the operation is cancelled with `cancel`.
+    pub fn register_cancellable(&self) -> (OperationId, oneshot::Receiver<()>) {
+        let (tx, rx) = oneshot::channel();
+        let mut inner = unwrap!(self.inner.lock());
+        let id = inner.next_id();
+        let _ = inner.cancellable.insert(id, tx);
+        (id, rx)
+    }
+
+    /// Signal the cancellable operation to stop. Returns `false` if no such
+    /// operation is in flight (e.g. because it has already completed).
+    pub fn cancel(&self, id: OperationId) -> bool {
+        let mut inner = unwrap!(self.inner.lock());
+        match inner.cancellable.remove(&id) {
+            Some(tx) => {
+                let _ = tx.send(());
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Forget the cancellable operation once it has completed.
+    pub fn finish_cancellable(&self, id: OperationId) {
+        let mut inner = unwrap!(self.inner.lock());
+        let _ = inner.cancellable.remove(&id);
+    }
+
+    /// Record the result of the operation.
+    pub fn complete(&self, id: OperationId, result: Result<Vec<u8>, AppError>) {
+        let result = match result {
+            Ok(payload) => OperationResult {
+                error_code: 0,
+                payload: payload,
+            },
+            Err(err) => {
+                debug!("Polled operation {} failed: {}", id, err);
+                OperationResult {
+                    error_code: err.error_code(),
+                    payload: Vec::new(),
+                }
+            }
+        };
+
+        let mut inner = unwrap!(self.inner.lock());
+        if let Some(entry) = inner.ops.get_mut(&id) {
+            *entry = Some(result);
+        }
+    }
+
+    /// Forget the operation, regardless of whether it has completed or not.
+    pub fn remove(&self, id: OperationId) -> bool {
+        let mut inner = unwrap!(self.inner.lock());
+        inner.ops.remove(&id).is_some()
+    }
+
+    /// Returns whether the operation has completed, or `None` if no such
+    /// operation is known.
+    pub fn is_complete(&self, id: OperationId) -> Option<bool> {
+        let inner = unwrap!(self.inner.lock());
+        inner.ops.get(&id).map(Option::is_some)
+    }
+
+    /// Take the result of a completed operation out of the registry.
+    pub fn take_result(&self, id: OperationId) -> Result<OperationResult, AppError> {
+        let mut inner = unwrap!(self.inner.lock());
+        match inner.ops.remove(&id) {
+            Some(Some(result)) => Ok(result),
+            Some(None) => {
+                let _ = inner.ops.insert(id, None);
+                Err(AppError::OperationInProgress)
+            }
+            None => Err(AppError::InvalidOperationId),
+        }
+    }
+}
+
+impl Inner {
+    fn next_id(&mut self) -> OperationId {
+        self.last_id = self.last_id.wrapping_add(1);
+        self.last_id
+    }
+}
+
+impl Default for Operations {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::Future;
+
+    #[test]
+    fn lifecycle() {
+        let ops = Operations::new();
+        let id0 = ops.register();
+        let id1 = ops.register();
+        assert!(id0 != id1);
+
+        assert_eq!(ops.is_complete(id0), Some(false));
+        match ops.take_result(id0) {
+            Err(AppError::OperationInProgress) => (),
+            x => panic!("Unexpected {:?}", x),
+        }
+
+        ops.complete(id0, Ok(vec![1, 2, 3]));
+        ops.complete(id1, Err(AppError::NoSuchContainer));
+        assert_eq!(ops.is_complete(id0), Some(true));
+
+        let res = unwrap!(ops.take_result(id0));
+        assert_eq!(res.error_code, 0);
+        assert_eq!(res.payload, vec![1, 2, 3]);
+        assert_eq!(ops.is_complete(id0), None);
+
+        let res = unwrap!(ops.take_result(id1));
+        assert_eq!(res.error_code, AppError::NoSuchContainer.error_code());
+        assert!(res.payload.is_empty());
+
+        let id2 = ops.register();
+        assert!(ops.remove(id2));
+        ops.complete(id2, Ok(vec![]));
+        assert_eq!(ops.is_complete(id2), None);
+    }
+
+    #[test]
+    fn cancellation() {
+        let ops = Operations::new();
+        let (id0, rx0) = ops.register_cancellable();
+        let (id1, rx1) = ops.register_cancellable();
+        assert!(id0 != id1);
+
+        assert!(ops.cancel(id0));
+        assert!(!ops.cancel(id0));
+        assert_eq!(unwrap!(rx0.wait()), ());
+
+        // Finished operations can no longer be cancelled.
+        ops.finish_cancellable(id1);
+        assert!(!ops.cancel(id1));
+        assert!(rx1.wait().is_err());
+    }
+}
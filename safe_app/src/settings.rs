@@ -0,0 +1,312 @@
+// Copyright 2016 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under (1) the MaidSafe.net Commercial License,
+// version 1.0 or later, or (2) The General Public License (GPL), version 3, depending on which
+// licence you accepted on initial access to the Software (the "Licences").
+//
+// By contributing code to the SAFE Network Software, or to this project generally, you agree to be
+// bound by the terms of the MaidSafe Contributor Agreement.  This, along with the Licenses can be
+// found in the root directory of this project at LICENSE, COPYING and CONTRIBUTOR.
+//
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.
+//
+// Please review the Licences for the specific language governing permissions and limitations
+// relating to use of the SAFE Network Software.
+
+//! Typed settings store kept in the app's own container. Settings are stored
+//! as serialised values under `settings/`-prefixed keys, so every device the
+//! user runs the app on sees the same preferences. Changes are collected
+//! locally and committed with `save`, merging with modifications made by other
+//! devices in the meantime.
+
+use {AppContext, AppFuture};
+use errors::AppError;
+use futures::Future;
+use maidsafe_utilities::serialisation::{deserialise, serialise};
+use safe_core::{Client, FutureExt, MDataInfo, mdata_info};
+use safe_core::structures::{MergeStrategy, edit};
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use std::collections::BTreeMap;
+use std::rc::Rc;
+
+/// Prefix of the container keys holding the settings.
+pub const SETTINGS_PREFIX: &'static str = "settings/";
+
+// Stored values of the settings together with their entry versions.
+type Entries = BTreeMap<String, (Vec<u8>, u64)>;
+
+/// Settings of the app, as loaded from its own container plus the local
+/// changes not saved yet.
+#[derive(Debug)]
+pub struct Settings {
+    info: MDataInfo,
+    entries: Entries,
+    pending: BTreeMap<String, Option<Vec<u8>>>,
+}
+
+impl Settings {
+    /// Load the settings from the app's own container.
+    pub fn load(client: &Client<AppContext>, context: &AppContext) -> Box<AppFuture<Self>> {
+        let client = client.clone();
+
+        context
+            .get_own_container(&client)
+            .and_then(move |info| {
+                let entries = fetch(&client, &info);
+                entries.map(move |entries| {
+                    Settings {
+                        info,
+                        entries,
+                        pending: BTreeMap::new(),
+                    }
+                })
+            })
+            .into_box()
+    }
+
+    /// Get the value of the setting, including unsaved changes.
+    pub fn get<T: DeserializeOwned>(&self, key: &str) -> Result<Option<T>, AppError> {
+        let value = match self.pending.get(key) {
+            Some(&Some(ref value)) => value,
+            Some(&None) => return Ok(None),
+            None => {
+                match self.entries.get(key) {
+                    Some(&(ref value, _)) => value,
+                    None => return Ok(None),
+                }
+            }
+        };
+
+        Ok(Some(deserialise(value)?))
+    }
+
+    /// Set the value of the setting. The change is kept locally until `save`.
+    pub fn set<T: Serialize>(&mut self, key: &str, value: &T) -> Result<(), AppError> {
+        let _ = self.pending.insert(key.to_string(), Some(serialise(value)?));
+        Ok(())
+    }
+
+    /// Remove the setting. The change is kept locally until `save`.
+    pub fn remove(&mut self, key: &str) {
+        let _ = self.pending.insert(key.to_string(), None);
+    }
+
+    /// Returns `true` if there are changes not saved yet.
+    pub fn is_dirty(&self) -> bool {
+        !self.pending.is_empty()
+    }
+
+    /// Check whether the settings were modified (e.g. by another device) since
+    /// they were loaded. Entry mutations do not bump the version of the
+    /// `MutableData` itself, so the entry versions are compared instead.
+    pub fn has_changed(&self, client: &Client<AppContext>) -> Box<AppFuture<bool>> {
+        let loaded = versions(&self.entries);
+
+        fetch(client, &self.info)
+            .map(move |entries| versions(&entries) != loaded)
+            .into_box()
+    }
+
+    /// Commit the local changes and return the settings reloaded from the
+    /// network. Settings modified by another device since they were loaded
+    /// are resolved with `strategy`, which receives the key without the
+    /// `settings/` prefix.
+    pub fn save<M>(self, client: &Client<AppContext>, strategy: M) -> Box<AppFuture<Self>>
+    where
+        M: MergeStrategy + 'static,
+    {
+        let client = client.clone();
+        let info = self.info.clone();
+        let strategy = Prefixed(Rc::new(strategy));
+        let strategy2 = strategy.clone();
+
+        let Settings { entries, pending, .. } = self;
+
+        let apply = move |current: &mut BTreeMap<Vec<u8>, Vec<u8>>| for (key, local) in pending {
+            let full_key = setting_key(&key);
+            let base = entries.get(&key).map(|&(ref value, _)| value.clone());
+            let remote = current.get(&full_key).cloned();
+
+            // Only consult the strategy if another device modified the
+            // setting since it was loaded.
+            let value = if base == remote {
+                local
+            } else {
+                strategy2.merge(
+                    &full_key,
+                    base.as_ref().map(|v| &v[..]),
+                    local.as_ref().map(|v| &v[..]),
+                    remote.as_ref().map(|v| &v[..]),
+                )
+            };
+
+            let _ = match value {
+                Some(value) => current.insert(full_key, value),
+                None => current.remove(&full_key),
+            };
+        };
+
+        edit(&client, info.clone(), apply, strategy)
+            .map_err(AppError::from)
+            .and_then(move |()| {
+                let entries = fetch(&client, &info);
+                entries.map(move |entries| {
+                    Settings {
+                        info,
+                        entries,
+                        pending: BTreeMap::new(),
+                    }
+                })
+            })
+            .into_box()
+    }
+}
+
+// Passes the keys to the wrapped strategy with the `settings/` prefix removed.
+struct Prefixed<M>(Rc<M>);
+
+impl<M> Clone for Prefixed<M> {
+    fn clone(&self) -> Self {
+        Prefixed(Rc::clone(&self.0))
+    }
+}
+
+impl<M: MergeStrategy> MergeStrategy for Prefixed<M> {
+    fn merge(
+        &self,
+        key: &[u8],
+        base: Option<&[u8]>,
+        local: Option<&[u8]>,
+        remote: Option<&[u8]>,
+    ) -> Option<Vec<u8>> {
+        let key = if key.starts_with(SETTINGS_PREFIX.as_bytes()) {
+            &key[SETTINGS_PREFIX.len()..]
+        } else {
+            key
+        };
+        self.0.merge(key, base, local, remote)
+    }
+}
+
+fn setting_key(key: &str) -> Vec<u8> {
+    format!("{}{}", SETTINGS_PREFIX, key).into_bytes()
+}
+
+fn versions(entries: &Entries) -> BTreeMap<String, u64> {
+    entries
+        .iter()
+        .map(|(key, &(_, version))| (key.clone(), version))
+        .collect()
+}
+
+// Fetch the non-deleted settings stored in the container.
+fn fetch(client: &Client<AppContext>, info: &MDataInfo) -> Box<AppFuture<Entries>> {
+    let info = info.clone();
+
+    client
+        .list_mdata_entries(info.name, info.type_tag)
+        .and_then(move |entries| {
+            let entries = entries
+                .into_iter()
+                .filter(|&(_, ref value)| !value.content.is_empty())
+                .collect();
+            mdata_info::decrypt_entries(&info, &entries)
+        })
+        .map_err(AppError::from)
+        .map(|entries| {
+            entries
+                .into_iter()
+                .filter_map(|(key, value)| {
+                    let key = match String::from_utf8(key) {
+                        Ok(key) => key,
+                        Err(_) => return None,
+                    };
+                    if key.starts_with(SETTINGS_PREFIX) {
+                        let name = key[SETTINGS_PREFIX.len()..].to_string();
+                        Some((name, (value.content, value.entry_version)))
+                    } else {
+                        None
+                    }
+                })
+                .collect()
+        })
+        .into_box()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::Future;
+    use maidsafe_utilities::serialisation::{deserialise, serialise};
+    use safe_core::structures::{LastWriterWins, MergeWith};
+    use std::collections::HashMap;
+    use test_utils::{create_app_with_access, run};
+
+    // Settings saved on one device are seen by another one, and concurrent
+    // modifications of the same setting are resolved by the merge strategy.
+    #[test]
+    fn sync_between_devices() {
+        let app = create_app_with_access(HashMap::new());
+
+        let (first, second) = run(&app, |client, context| {
+            let client2 = client.clone();
+            let context2 = context.clone();
+
+            Settings::load(client, context).and_then(move |first| {
+                Settings::load(&client2, &context2).map(move |second| (first, second))
+            })
+        });
+
+        let mut first = first;
+        unwrap!(first.set("volume", &5u32));
+        unwrap!(first.set("theme", &"dark".to_string()));
+        assert_eq!(unwrap!(first.get::<u32>("volume")), Some(5));
+        assert!(first.is_dirty());
+
+        let first = run(&app, move |client, _| first.save(client, LastWriterWins));
+        assert!(!first.is_dirty());
+        assert_eq!(unwrap!(first.get::<u32>("volume")), Some(5));
+
+        // The second device notices the change.
+        let (changed, mut second) = run(&app, move |client, _| {
+            let changed = second.has_changed(client);
+            changed.map(move |changed| (changed, second))
+        });
+        assert!(changed);
+        assert_eq!(unwrap!(second.get::<u32>("volume")), None);
+
+        // Conflicting change is merged by adding both values.
+        unwrap!(second.set("volume", &3u32));
+
+        let merge = MergeWith(|key: &[u8],
+                               _base: Option<&[u8]>,
+                               local: Option<&[u8]>,
+                               remote: Option<&[u8]>| {
+            assert_eq!(key, &b"volume"[..]);
+            let local: u32 = unwrap!(deserialise(unwrap!(local)));
+            let remote: u32 = unwrap!(deserialise(unwrap!(remote)));
+            Some(unwrap!(serialise(&(local + remote))))
+        });
+
+        let second = run(&app, move |client, _| second.save(client, merge));
+        assert_eq!(unwrap!(second.get::<u32>("volume")), Some(8));
+        assert_eq!(unwrap!(second.get::<String>("theme")), Some("dark".to_string()));
+
+        let (changed, first) = run(&app, move |client, _| {
+            let changed = first.has_changed(client);
+            changed.map(move |changed| (changed, first))
+        });
+        assert!(changed);
+        assert_eq!(unwrap!(first.get::<String>("theme")), Some("dark".to_string()));
+
+        // Removal of a setting loaded from the network.
+        let mut first = first;
+        first.remove("theme");
+        let first = run(&app, move |client, _| first.save(client, LastWriterWins));
+        assert_eq!(unwrap!(first.get::<String>("theme")), None);
+        assert_eq!(unwrap!(first.get::<u32>("volume")), Some(8));
+    }
+}
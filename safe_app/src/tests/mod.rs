@@ -33,6 +33,45 @@ use std::collections::HashMap;
 use test_utils::{create_app_with_access, run};
 use test_utils::gen_app_exchange_info;
 
+// Test the operations over the limit are rejected, or blocked until the ones
+// in flight complete.
+#[test]
+fn request_limit() {
+    use backpressure::OverflowPolicy;
+    use errors::AppError;
+    use futures::sync::oneshot;
+    use safe_core::FutureExt;
+    use test_utils::create_app;
+
+    let app = create_app();
+    app.set_request_limit(Some(1), OverflowPolicy::Reject);
+
+    let (done_tx, done_rx) = oneshot::channel::<()>();
+    let (finished_tx, finished_rx) = oneshot::channel::<()>();
+    unwrap!(app.send(move |_, _| {
+        done_rx
+            .then(move |_| {
+                let _ = finished_tx.send(());
+                Ok::<_, ()>(())
+            })
+            .into_box()
+            .into()
+    }));
+    assert_eq!(app.requests_in_flight(), 1);
+
+    match app.send(|_, _| None) {
+        Err(AppError::TooManyRequests) => (),
+        res => panic!("Unexpected {:?}", res),
+    }
+
+    unwrap!(done_tx.send(()));
+    unwrap!(finished_rx.wait());
+
+    // The slot is released just after the operation completes, so wait for it.
+    app.set_request_limit(Some(1), OverflowPolicy::Block);
+    unwrap!(app.send(|_, _| None));
+}
+
 // Test refreshing access info by fetching it from the network.
 #[test]
 fn refresh_access_info() {
@@ -248,3 +287,54 @@ fn app_container_creation() {
 
     assert_eq!(num_containers(&app), 1); // should only contain app container
 }
+
+// Test that an app learns about the revocation of its key when its mutations
+// are denied.
+#[test]
+fn app_revocation_detection() {
+    use errors::AppError;
+    use routing::{ClientError, ImmutableData};
+    use safe_core::{CoreError, NetworkEvent};
+    use std::sync::mpsc;
+    use std::time::Duration;
+
+    let auth = authenticator::create_account_and_login();
+
+    let app_info = gen_app_exchange_info();
+    let app_id = app_info.id.clone();
+    let auth_granted = unwrap!(authenticator::register_app(
+        &auth,
+        &AuthReq {
+            app: app_info,
+            app_container: false,
+            containers: HashMap::new(),
+        },
+    ));
+
+    let (tx, rx) = mpsc::channel();
+    let app = unwrap!(App::registered(app_id.clone(), auth_granted, move |event| {
+        if let Ok(NetworkEvent::AppRevoked) = event {
+            let _ = tx.send(());
+        }
+    }));
+
+    let authorised = run(&app, |client, _| client.is_app_authorised().map_err(AppError::from));
+    assert!(authorised);
+
+    revoke(&auth, &app_id);
+
+    let authorised = run(&app, |client, _| client.is_app_authorised().map_err(AppError::from));
+    assert!(!authorised);
+
+    run(&app, |client, _| {
+        client.put_idata(ImmutableData::new(vec![1; 10])).then(|res| {
+            match res {
+                Err(CoreError::RoutingClientError(ClientError::AccessDenied)) => (),
+                res => panic!("Unexpected {:?}", res),
+            }
+            Ok::<_, AppError>(())
+        })
+    });
+
+    unwrap!(rx.recv_timeout(Duration::from_secs(10)));
+}
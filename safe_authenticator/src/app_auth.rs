@@ -21,7 +21,7 @@ use super::{AuthError, AuthFuture};
 use AccessContainerEntry;
 use access_container;
 use app_container;
-use config::{self, AppInfo, Apps};
+use config::{self, AppEvent, AppEventKind, AppInfo, Apps};
 use futures::Future;
 use ipc::update_container_perms;
 use routing::ClientError;
@@ -257,11 +257,13 @@ fn authenticate_new_app(
     let c4 = client.clone();
     let c5 = client.clone();
     let c6 = client.clone();
+    let c7 = client.clone();
 
     let sign_pk = app.keys.sign_pk;
     let app_keys = app.keys.clone();
     let app_keys_auth = app.keys.clone();
     let app_id = app.info.id.clone();
+    let event = AppEvent::new(AppEventKind::Authorised, app.info.clone(), permissions.clone());
 
     client
         .list_auth_keys_and_version()
@@ -289,6 +291,7 @@ fn authenticate_new_app(
         .and_then(move |(perms, app)| {
             update_access_container(&c5, &app, perms)
         })
+        .and_then(move |()| config::record_app_event(&c7, event))
         .and_then(move |()| {
             let access_container = c6.access_container()?;
 
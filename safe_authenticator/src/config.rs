@@ -20,13 +20,15 @@ use futures::Future;
 use futures::future::{self, Either, Loop};
 use maidsafe_utilities::serialisation::{deserialise, serialise};
 use routing::{ClientError, EntryActions, EntryError};
-use safe_core::{Client, CoreError, FutureExt};
+use safe_core::{Client, CoreError, DIR_TAG, FutureExt, MDataInfo};
 use safe_core::ipc::IpcError;
-use safe_core::ipc::req::AppExchangeInfo;
+use safe_core::ipc::req::{AppExchangeInfo, Permission};
 use safe_core::ipc::resp::AppKeys;
 use serde::Serialize;
+use safe_core::structures::AppendLog;
 use serde::de::DeserializeOwned;
-use std::collections::{HashMap, VecDeque};
+use std::collections::{BTreeMap, BTreeSet, HashMap, VecDeque};
+use std::time::{SystemTime, UNIX_EPOCH};
 use tiny_keccak::sha3_256;
 
 /// App data stored in the authenticator configuration.
@@ -50,11 +52,64 @@ pub const KEY_APPS: &'static [u8] = b"apps";
 /// Config file key under which the revocation queue is stored.
 pub const KEY_APP_REVOCATION_QUEUE: &'static [u8] = b"revocation-queue";
 
+/// Config file key under which the root of the append log holding the history
+/// of the apps access is stored.
+pub const KEY_APP_HISTORY: &'static [u8] = b"app-history";
+
+/// Kind of a change of an app's access.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub enum AppEventKind {
+    /// The app was authorised
+    Authorised,
+    /// The app was granted access to additional containers
+    ContainersGranted,
+    /// The app was revoked
+    Revoked,
+}
+
+/// Record of a change of an app's access, kept in the app history.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct AppEvent {
+    /// Kind of the change
+    pub kind: AppEventKind,
+    /// Application info (id, name, vendor, etc.)
+    pub info: AppExchangeInfo,
+    /// Time of the change, in seconds since the UNIX epoch
+    pub timestamp: u64,
+    /// Containers and the permissions granted by the change (empty for
+    /// revocations)
+    pub containers: HashMap<String, BTreeSet<Permission>>,
+}
+
+impl AppEvent {
+    /// Create a new event happening now.
+    pub fn new(
+        kind: AppEventKind,
+        info: AppExchangeInfo,
+        containers: HashMap<String, BTreeSet<Permission>>,
+    ) -> Self {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|duration| duration.as_secs())
+            .unwrap_or(0);
+
+        AppEvent {
+            kind,
+            info,
+            timestamp,
+            containers,
+        }
+    }
+}
+
 /// Maps from a SHA-3 hash of an app ID to app info
 pub type Apps = HashMap<[u8; 32], AppInfo>;
 /// Contains a queue of revocations that are currently running or have failed
 /// String refers to `app_id`.
 pub type RevocationQueue = VecDeque<String>;
+/// Changes of the apps access, oldest first.
+pub type AppHistory = Vec<AppEvent>;
 
 /// Bump the current version to obtain new version.
 pub fn next_version(version: Option<u64>) -> u64 {
@@ -159,6 +214,85 @@ pub fn remove_from_app_revocation_queue(
     )
 }
 
+/// Get the history of the apps access.
+/// If no change was recorded yet, returns an empty history.
+pub fn get_app_history(client: &Client<()>) -> Box<AuthFuture<AppHistory>> {
+    let client = client.clone();
+
+    get_entry(&client, KEY_APP_HISTORY)
+        .and_then(move |(_, root): (_, Option<MDataInfo>)| match root {
+            Some(root) => {
+                AppendLog::new(root, BTreeMap::new())
+                    .read_from(&client, 0)
+                    .map_err(AuthError::from)
+                    .and_then(|items| -> Result<AppHistory, AuthError> {
+                        items
+                            .iter()
+                            .map(|item| deserialise(item).map_err(AuthError::from))
+                            .collect()
+                    })
+                    .into_box()
+            }
+            None => ok!(AppHistory::new()),
+        })
+        .into_box()
+}
+
+/// Append `event` to the history of the apps access, one log item per event.
+/// The history is informative only, so failures are logged rather than
+/// returned: the change it records has already taken place.
+pub fn record_app_event(client: &Client<()>, event: AppEvent) -> Box<AuthFuture<()>> {
+    let client = client.clone();
+    let client2 = client.clone();
+    let item = fry!(serialise(&event));
+
+    get_entry(&client, KEY_APP_HISTORY)
+        .and_then(move |(version, root): (_, Option<MDataInfo>)| match root {
+            Some(root) => ok!(root),
+            None => create_app_history(&client, version),
+        })
+        .and_then(move |root| {
+            AppendLog::new(root, BTreeMap::new())
+                .append(&client2, item)
+                .map_err(AuthError::from)
+        })
+        .map(|_| ())
+        .or_else(|error| {
+            warn!("Failed to record the app event in the history: {:?}", error);
+            Ok(())
+        })
+        .into_box()
+}
+
+// Create new log for the app history and store its root in the config. If
+// another authenticator instance stored its root first, that one is returned
+// and the new log is left unused.
+fn create_app_history(client: &Client<()>, version: Option<u64>) -> Box<AuthFuture<MDataInfo>> {
+    let root = fry!(MDataInfo::random_private(DIR_TAG));
+    let client2 = client.clone();
+
+    AppendLog::create(client, root.clone(), BTreeMap::new())
+        .map_err(AuthError::from)
+        .and_then(move |_| {
+            mutate_entry(
+                &client2,
+                KEY_APP_HISTORY,
+                None,
+                next_version(version),
+                move |stored| if stored.is_none() {
+                    *stored = Some(root.clone());
+                    true
+                } else {
+                    false
+                },
+            )
+        })
+        .and_then(|(_, root)| {
+            root.ok_or_else(|| AuthError::Unexpected("Missing app history".to_owned()))
+        })
+        .into_box()
+}
+
 fn get_entry<T>(client: &Client<()>, key: &[u8]) -> Box<AuthFuture<(Option<u64>, T)>>
 where
     T: Default + DeserializeOwned + Serialize + 'static,
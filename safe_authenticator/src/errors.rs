@@ -19,7 +19,7 @@
 
 pub use self::codes::*;
 use config_file_handler::Error as ConfigFileHandlerError;
-use ffi_utils::ErrorCode;
+use ffi_utils::{ErrorCode, error_catalog_to_json, error_code_name};
 use futures::sync::mpsc::SendError;
 use maidsafe_utilities::serialisation::SerialisationError;
 use routing::ClientError;
@@ -35,64 +35,85 @@ use std::string::FromUtf8Error;
 use std::sync::mpsc::RecvError;
 
 mod codes {
-    // Core errors
-    pub const ERR_ENCODE_DECODE_ERROR: i32 = -1;
-    pub const ERR_ASYMMETRIC_DECIPHER_FAILURE: i32 = -2;
-    pub const ERR_SYMMETRIC_DECIPHER_FAILURE: i32 = -3;
-    pub const ERR_RECEIVED_UNEXPECTED_DATA: i32 = -4;
-    pub const ERR_RECEIVED_UNEXPECTED_EVENT: i32 = -5;
-    pub const ERR_VERSION_CACHE_MISS: i32 = -6;
-    pub const ERR_ROOT_DIRECTORY_EXISTS: i32 = -7;
-    pub const ERR_RANDOM_DATA_GENERATION_FAILURE: i32 = -8;
-    pub const ERR_OPERATION_FORBIDDEN: i32 = -9;
-    pub const ERR_ROUTING_ERROR: i32 = -10;
-    pub const ERR_ROUTING_INTERFACE_ERROR: i32 = -11;
-    pub const ERR_UNSUPPORTED_SALT_SIZE_FOR_PW_HASH: i32 = -12;
-    pub const ERR_UNSUCCESSFUL_PW_HASH: i32 = -13;
-    pub const ERR_OPERATION_ABORTED: i32 = -14;
-    pub const ERR_MPID_MESSAGING_ERROR: i32 = -15;
-    pub const ERR_SELF_ENCRYPTION: i32 = -16;
-    pub const ERR_REQUEST_TIMEOUT: i32 = -17;
-
-    // routing Client errors
-    pub const ERR_ACCESS_DENIED: i32 = -100;
-    pub const ERR_NO_SUCH_ACCOUNT: i32 = -101;
-    pub const ERR_ACCOUNT_EXISTS: i32 = -102;
-    pub const ERR_NO_SUCH_DATA: i32 = -103;
-    pub const ERR_DATA_EXISTS: i32 = -104;
-    pub const ERR_DATA_TOO_LARGE: i32 = -105;
-    pub const ERR_NO_SUCH_ENTRY: i32 = -106;
-    pub const ERR_TOO_MANY_ENTRIES: i32 = -108;
-    pub const ERR_NO_SUCH_KEY: i32 = -109;
-    pub const ERR_INVALID_OWNERS: i32 = -110;
-    pub const ERR_INVALID_SUCCESSOR: i32 = -111;
-    pub const ERR_INVALID_OPERATION: i32 = -112;
-    pub const ERR_LOW_BALANCE: i32 = -113;
-    pub const ERR_NETWORK_FULL: i32 = -114;
-    pub const ERR_NETWORK_OTHER: i32 = -115;
-    pub const ERR_INVALID_INVITATION: i32 = -116;
-    pub const ERR_INVITATION_ALREADY_CLAIMED: i32 = -117;
-    pub const ERR_INVALID_ENTRY_ACTIONS: i32 = -118;
-
-    // IPC errors.
-    pub const ERR_AUTH_DENIED: i32 = -200;
-    pub const ERR_CONTAINERS_DENIED: i32 = -201;
-    pub const ERR_INVALID_MSG: i32 = -202;
-    pub const ERR_ALREADY_AUTHORISED: i32 = -203;
-    pub const ERR_UNKNOWN_APP: i32 = -204;
-    pub const ERR_STRING_ERROR: i32 = -205;
-    pub const ERR_SHARE_MDATA_DENIED: i32 = -206;
-    pub const ERR_INVALID_OWNER: i32 = -207;
-
-    // NFS errors.
-    pub const ERR_FILE_EXISTS: i32 = -300;
-    pub const ERR_FILE_NOT_FOUND: i32 = -301;
-    pub const ERR_INVALID_RANGE: i32 = -302;
-
-    // Authenticator errors
-    pub const ERR_IO_ERROR: i32 = -1013;
-    pub const ERR_ACCOUNT_CONTAINERS_CREATION: i32 = -1014;
-    pub const ERR_UNEXPECTED: i32 = -2000;
+    error_codes! {
+        /// Catalog of all error codes returned by the authenticator, with their
+        /// symbolic names and descriptions.
+        ERROR_CATALOG;
+        /// Returns the symbolic name of the error code as a static NUL-terminated
+        /// string, or null if the code is unknown.
+        error_code_c_name;
+
+        // Core errors
+        ERR_ENCODE_DECODE_ERROR = -1, "Serialisation error";
+        ERR_ASYMMETRIC_DECIPHER_FAILURE = -2, "Asymmetric decryption failure";
+        ERR_SYMMETRIC_DECIPHER_FAILURE = -3, "Symmetric decryption failure";
+        ERR_RECEIVED_UNEXPECTED_DATA = -4, "Received unexpected data";
+        ERR_RECEIVED_UNEXPECTED_EVENT = -5, "Received unexpected event";
+        ERR_VERSION_CACHE_MISS = -6, "Version not found in the cache";
+        ERR_ROOT_DIRECTORY_EXISTS = -7, "Root directory already exists";
+        ERR_RANDOM_DATA_GENERATION_FAILURE = -8, "Failed to generate random data";
+        ERR_OPERATION_FORBIDDEN = -9, "Forbidden operation";
+        ERR_ROUTING_ERROR = -10, "Routing error";
+        ERR_ROUTING_INTERFACE_ERROR = -11, "Routing interface error";
+        ERR_UNSUPPORTED_SALT_SIZE_FOR_PW_HASH = -12, "Unsupported salt size for password hashing";
+        ERR_UNSUCCESSFUL_PW_HASH = -13, "Password hashing failed";
+        ERR_OPERATION_ABORTED = -14, "Operation aborted";
+        ERR_MPID_MESSAGING_ERROR = -15, "MPID messaging error";
+        ERR_SELF_ENCRYPTION = -16, "Self-encryption error";
+        ERR_REQUEST_TIMEOUT = -17, "Request has timed out";
+
+        // routing Client errors
+        ERR_ACCESS_DENIED = -100, "Access denied";
+        ERR_NO_SUCH_ACCOUNT = -101, "Requested account not found";
+        ERR_ACCOUNT_EXISTS = -102, "Account already exists";
+        ERR_NO_SUCH_DATA = -103, "Requested data not found";
+        ERR_DATA_EXISTS = -104, "Data already exists";
+        ERR_DATA_TOO_LARGE = -105, "Data exceeds size limit";
+        ERR_NO_SUCH_ENTRY = -106, "Requested entry not found";
+        ERR_TOO_MANY_ENTRIES = -108, "Exceeded the maximum number of entries";
+        ERR_NO_SUCH_KEY = -109, "Key does not exist";
+        ERR_INVALID_OWNERS = -110, "Invalid owners";
+        ERR_INVALID_SUCCESSOR = -111, "Invalid version successor";
+        ERR_INVALID_OPERATION = -112, "Invalid operation";
+        ERR_LOW_BALANCE = -113, "Insufficient account balance";
+        ERR_NETWORK_FULL = -114, "Network is full";
+        ERR_NETWORK_OTHER = -115, "Other network error";
+        ERR_INVALID_INVITATION = -116, "Invalid invitation";
+        ERR_INVITATION_ALREADY_CLAIMED = -117, "Invitation already claimed";
+        ERR_INVALID_ENTRY_ACTIONS = -118, "Some entry actions are not valid";
+
+        // IPC errors.
+        ERR_AUTH_DENIED = -200, "Authentication denied";
+        ERR_CONTAINERS_DENIED = -201, "Containers denied";
+        ERR_INVALID_MSG = -202, "Invalid IPC message";
+        ERR_ALREADY_AUTHORISED = -203, "App is already authorised";
+        ERR_UNKNOWN_APP = -204, "App is not registered";
+        ERR_STRING_ERROR = -205, "String conversion error";
+        ERR_SHARE_MDATA_DENIED = -206, "Shared access to MutableData denied";
+        ERR_INVALID_OWNER = -207, "Requested shared access to non-owned MutableData";
+
+        // NFS errors.
+        ERR_FILE_EXISTS = -300, "File already exists";
+        ERR_FILE_NOT_FOUND = -301, "File not found";
+        ERR_INVALID_RANGE = -302, "Invalid byte range";
+        ERR_DIR_NOT_FOUND = -303, "Directory not found";
+
+        // Authenticator errors
+        ERR_IO_ERROR = -1013, "I/O error";
+        ERR_ACCOUNT_CONTAINERS_CREATION = -1014, "Failed to create standard account containers";
+        ERR_UNEXPECTED = -2000, "Unexpected (probably a logic error)";
+    }
+}
+
+/// Returns the `ERROR_CATALOG` rendered as JSON.
+pub fn error_catalog_json() -> String {
+    error_catalog_to_json(ERROR_CATALOG)
+}
+
+/// Returns the symbolic name of the error `code`, or `None` if the code is not
+/// in the `ERROR_CATALOG`.
+pub fn error_name(code: i32) -> Option<&'static str> {
+    error_code_name(ERROR_CATALOG, code)
 }
 
 /// Authenticator errors
@@ -249,6 +270,7 @@ impl ErrorCode for AuthError {
                     NfsError::FileExists => ERR_FILE_EXISTS,
                     NfsError::FileNotFound => ERR_FILE_NOT_FOUND,
                     NfsError::InvalidRange => ERR_INVALID_RANGE,
+                    NfsError::DirNotFound => ERR_DIR_NOT_FOUND,
                     NfsError::EncodeDecodeError(_) => ERR_ENCODE_DECODE_ERROR,
                     NfsError::SelfEncryption(_) => ERR_SELF_ENCRYPTION,
                     NfsError::Unexpected(_) => ERR_UNEXPECTED,
@@ -302,7 +324,134 @@ fn core_error_code(err: &CoreError) -> i32 {
         CoreError::OperationAborted => ERR_OPERATION_ABORTED,
         CoreError::MpidMessagingError(_) => ERR_MPID_MESSAGING_ERROR,
         CoreError::SelfEncryption(_) => ERR_SELF_ENCRYPTION,
-        CoreError::RequestTimeout => ERR_REQUEST_TIMEOUT,
+        CoreError::RequestTimeout(_) => ERR_REQUEST_TIMEOUT,
         CoreError::Unexpected(_) => ERR_UNEXPECTED,
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ffi_utils::StringError;
+    use maidsafe_utilities::serialisation::deserialise;
+    use std::collections::{BTreeMap, HashSet};
+    use std::ffi::CStr;
+    use std::io::ErrorKind;
+
+    // Every error code and name must appear in the catalog exactly once.
+    #[test]
+    fn error_catalog_is_unique() {
+        let names: HashSet<_> = ERROR_CATALOG.iter().map(|&(name, _, _)| name).collect();
+        let codes: HashSet<_> = ERROR_CATALOG.iter().map(|&(_, code, _)| code).collect();
+
+        assert_eq!(names.len(), ERROR_CATALOG.len());
+        assert_eq!(codes.len(), ERROR_CATALOG.len());
+        assert_eq!(error_name(ERR_AUTH_DENIED), Some("ERR_AUTH_DENIED"));
+        assert_eq!(error_name(1), None);
+
+        let name = unsafe { CStr::from_ptr(error_code_c_name(ERR_AUTH_DENIED)) };
+        assert_eq!(unwrap!(name.to_str()), "ERR_AUTH_DENIED");
+        assert!(error_code_c_name(1).is_null());
+    }
+
+    // Every error code the errors are mapped to must be in the catalog. The
+    // codes of the variants whose payload can't be easily built are checked
+    // directly.
+    #[test]
+    fn error_catalog_is_complete() {
+        let mut errors: Vec<_> = core_errors().into_iter().map(AuthError::CoreError).collect();
+        errors.extend(nfs_errors().into_iter().map(AuthError::NfsError));
+        errors.extend(ipc_errors().into_iter().map(AuthError::IpcError));
+        errors.extend(vec![
+            AuthError::EncodeDecodeError,
+            AuthError::IoError(IoError::new(ErrorKind::Other, "")),
+            AuthError::AccountContainersCreation(String::new()),
+            AuthError::Unexpected(String::new()),
+        ]);
+
+        let mut codes: Vec<_> = errors.iter().map(|error| error.error_code()).collect();
+        codes.extend(vec![
+            ERR_ROUTING_ERROR,
+            ERR_ROUTING_INTERFACE_ERROR,
+            ERR_MPID_MESSAGING_ERROR,
+            ERR_SELF_ENCRYPTION,
+        ]);
+
+        for code in codes {
+            assert!(error_name(code).is_some(), "Error code {} not in the catalog", code);
+        }
+    }
+
+    // Errors of every variant of `CoreError`, except those whose payload
+    // can't be easily built in tests.
+    fn core_errors() -> Vec<CoreError> {
+        let client_errors = vec![
+            ClientError::AccessDenied,
+            ClientError::NoSuchAccount,
+            ClientError::AccountExists,
+            ClientError::NoSuchData,
+            ClientError::DataExists,
+            ClientError::DataTooLarge,
+            ClientError::NoSuchEntry,
+            ClientError::InvalidEntryActions(BTreeMap::new()),
+            ClientError::TooManyEntries,
+            ClientError::NoSuchKey,
+            ClientError::InvalidOwners,
+            ClientError::InvalidSuccessor(0),
+            ClientError::InvalidOperation,
+            ClientError::LowBalance,
+            ClientError::NetworkFull,
+            ClientError::NetworkOther(String::new()),
+            ClientError::InvalidInvitation,
+            ClientError::InvitationAlreadyClaimed,
+        ];
+
+        let mut errors = vec![
+            CoreError::EncodeDecodeError(unwrap!(deserialise::<u8>(&[]).err())),
+            CoreError::AsymmetricDecipherFailure,
+            CoreError::SymmetricDecipherFailure,
+            CoreError::ReceivedUnexpectedData,
+            CoreError::ReceivedUnexpectedEvent,
+            CoreError::VersionCacheMiss,
+            CoreError::RootDirectoryExists,
+            CoreError::RandomDataGenerationFailure,
+            CoreError::OperationForbidden,
+            CoreError::Unexpected(String::new()),
+            CoreError::UnsupportedSaltSizeForPwHash,
+            CoreError::UnsuccessfulPwHash,
+            CoreError::OperationAborted,
+            CoreError::RequestTimeout(None),
+        ];
+        errors.extend(client_errors.into_iter().map(CoreError::RoutingClientError));
+        errors
+    }
+
+    fn nfs_errors() -> Vec<NfsError> {
+        let mut errors = vec![
+            NfsError::FileExists,
+            NfsError::FileNotFound,
+            NfsError::DirNotFound,
+            NfsError::InvalidRange,
+            NfsError::Unexpected(String::new()),
+            NfsError::EncodeDecodeError(unwrap!(deserialise::<u8>(&[]).err())),
+        ];
+        errors.extend(core_errors().into_iter().map(NfsError::CoreError));
+        errors
+    }
+
+    fn ipc_errors() -> Vec<IpcError> {
+        vec![
+            IpcError::AuthDenied,
+            IpcError::ContainersDenied,
+            IpcError::InvalidMsg,
+            IpcError::EncodeDecodeError,
+            IpcError::StringError(StringError::Utf8(String::new())),
+            IpcError::AlreadyAuthorised,
+            IpcError::UnknownApp,
+            IpcError::ShareMDataDenied,
+            IpcError::InvalidOwner(Vec::new()),
+            IpcError::Unexpected(String::new()),
+        ]
+    }
+
+}
@@ -20,7 +20,7 @@ use AuthError;
 use Authenticator;
 use app_auth::{AppState, app_state};
 use app_container;
-use config;
+use config::{self, AppEventKind};
 use ffi_utils::{FFI_RESULT_OK, FfiResult, OpaqueCtx, SafePtr, catch_unwind_cb, from_c_str,
                 vec_into_raw_parts};
 use futures::Future;
@@ -65,6 +65,36 @@ impl Drop for RegisteredApp {
     }
 }
 
+/// Change of an app's access recorded in the app history
+#[repr(C)]
+pub struct AppHistoryEntry {
+    /// Kind of the change
+    pub kind: AppEventKind,
+    /// Application info
+    pub app_info: FfiAppExchangeInfo,
+    /// Time of the change, in seconds since the UNIX epoch
+    pub timestamp: u64,
+    /// List of containers the change granted access to
+    pub containers: *const ContainerPermissions,
+    /// Length of the containers array
+    pub containers_len: usize,
+    /// Capacity of the containers array. Internal data required
+    /// for the Rust allocator.
+    pub containers_cap: usize,
+}
+
+impl Drop for AppHistoryEntry {
+    fn drop(&mut self) {
+        unsafe {
+            let _ = Vec::from_raw_parts(
+                self.containers as *mut ContainerPermissions,
+                self.containers_len,
+                self.containers_cap,
+            );
+        }
+    }
+}
+
 /// Removes a revoked app from the authenticator config.
 ///
 /// Callback parameters: user data, error code
@@ -261,6 +291,58 @@ pub unsafe extern "C" fn auth_registered_apps(
     })
 }
 
+/// Get the history of the authorisations, container grants and revocations
+/// of apps, oldest first. Unlike `auth_registered_apps` and
+/// `auth_revoked_apps`, it includes apps which were removed since.
+///
+/// Callback parameters: user data, error code, app history vector, vector size
+#[no_mangle]
+pub unsafe extern "C" fn auth_app_history(
+    auth: *const Authenticator,
+    user_data: *mut c_void,
+    o_cb: extern "C" fn(user_data: *mut c_void,
+                        result: FfiResult,
+                        app_history_ptr: *const AppHistoryEntry,
+                        app_history_len: usize),
+) {
+    let user_data = OpaqueCtx(user_data);
+
+    catch_unwind_cb(user_data.0, o_cb, || -> Result<_, AuthError> {
+        (*auth).send(move |client| {
+            config::get_app_history(client)
+                .and_then(move |history| {
+                    let mut entries = Vec::with_capacity(history.len());
+
+                    for event in history {
+                        let app_info = event.info.into_repr_c()?;
+                        let containers = containers_into_vec(event.containers.into_iter())?;
+                        let (containers_ptr, len, cap) = vec_into_raw_parts(containers);
+
+                        entries.push(AppHistoryEntry {
+                            kind: event.kind,
+                            app_info,
+                            timestamp: event.timestamp,
+                            containers: containers_ptr,
+                            containers_len: len,
+                            containers_cap: cap,
+                        });
+                    }
+
+                    o_cb(user_data.0, FFI_RESULT_OK, entries.as_safe_ptr(), entries.len());
+
+                    Ok(())
+                })
+                .map_err(move |e| {
+                    call_result_cb!(Err::<(), _>(e), user_data, o_cb);
+                })
+                .into_box()
+                .into()
+        })?;
+
+        Ok(())
+    })
+}
+
 /// Return a list of apps having access to an arbitrary MD object.
 /// `md_name` and `md_type_tag` together correspond to a single MD.
 ///
@@ -18,11 +18,12 @@
 use {AuthError, Authenticator};
 use access_container;
 use app_auth;
-use config;
+use config::{self, AppEvent, AppEventKind};
 use ffi_utils::{FFI_RESULT_OK, FfiResult, OpaqueCtx, ReprC, catch_unwind_cb, from_c_str};
 use futures::{Future, Stream, stream};
 use ipc::{decode_ipc_msg, decode_share_mdata_req, encode_response, update_container_perms};
-use revocation::{flush_app_revocation_queue, revoke_app};
+use revocation::{RevocationStep, flush_app_revocation_queue, revoke_app,
+                 revoke_app_with_progress};
 use routing::{ClientError, User};
 use safe_core::{Client, CoreError, FutureExt};
 use safe_core::ffi::ipc::req::{AuthReq as FfiAuthReq, ContainersReq as FfiContainersReq,
@@ -31,7 +32,7 @@ use safe_core::ffi::ipc::resp::MetadataResponse as FfiUserMetadata;
 use safe_core::ipc::{IpcError, IpcMsg, decode_msg};
 use safe_core::ipc::req::{AuthReq, ContainersReq, IpcReq, ShareMDataReq};
 use safe_core::ipc::resp::IpcResp;
-use std::ffi::CStr;
+use std::ffi::{CStr, CString};
 use std::os::raw::{c_char, c_void};
 
 /// Decodes a given encoded IPC message without requiring an authorised account
@@ -296,6 +297,50 @@ pub unsafe extern "C" fn auth_revoke_app(
     });
 }
 
+/// Revoke app access, calling `o_progress_cb` with the id of the app being
+/// revoked and the completed step as the revocation progresses, so the UI
+/// can show the revocation status. Apps which were already in the
+/// revocation queue are revoked first.
+#[no_mangle]
+pub unsafe extern "C" fn auth_revoke_app_with_progress(
+    auth: *const Authenticator,
+    app_id: *const c_char,
+    user_data: *mut c_void,
+    o_progress_cb: extern "C" fn(user_data: *mut c_void,
+                                 app_id: *const c_char,
+                                 step: RevocationStep),
+    o_cb: extern "C" fn(*mut c_void, FfiResult, *const c_char),
+) {
+    let user_data = OpaqueCtx(user_data);
+
+    catch_unwind_cb(user_data.0, o_cb, || -> Result<_, AuthError> {
+        let app_id = from_c_str(app_id)?;
+
+        (*auth).send(move |client| {
+            let progress = move |app_id: &str, step| {
+                if let Ok(app_id) = CString::new(app_id) {
+                    o_progress_cb(user_data.0, app_id.as_ptr(), step);
+                }
+            };
+
+            revoke_app_with_progress(client, &app_id, progress)
+                .and_then(move |_| {
+                    let resp =
+                        encode_response(&IpcMsg::Revoked { app_id: app_id.clone() }, &app_id)?;
+                    o_cb(user_data.0, FFI_RESULT_OK, resp.as_ptr());
+                    Ok(())
+                })
+                .map_err(move |e| {
+                    call_result_cb!(Err::<(), _>(e), user_data, o_cb);
+                })
+                .into_box()
+                .into()
+        })?;
+
+        Ok(())
+    });
+}
+
 /// Flush the revocation queue.
 #[no_mangle]
 pub unsafe extern "C" fn auth_flush_app_revocation_queue(
@@ -480,11 +525,17 @@ pub unsafe extern "C" fn encode_containers_resp(
             let permissions = cont_req.containers.clone();
             let app_id = cont_req.app.id.clone();
             let app_id2 = app_id.clone();
+            let event = AppEvent::new(
+                AppEventKind::ContainersGranted,
+                cont_req.app.clone(),
+                cont_req.containers.clone(),
+            );
 
             (*auth).send(move |client| {
                 let c2 = client.clone();
                 let c3 = client.clone();
                 let c4 = client.clone();
+                let c5 = client.clone();
 
                 config::get_app(client, &app_id)
                     .and_then(move |app| {
@@ -525,6 +576,7 @@ pub unsafe extern "C" fn encode_containers_resp(
                     .and_then(move |(version, app_id, app_keys, perms)| {
                         access_container::put_entry(&c4, &app_id, &app_keys, &perms, version)
                     })
+                    .and_then(move |_| config::record_app_event(&c5, event))
                     .and_then(move |_| {
                         let resp = encode_response(
                             &IpcMsg::Resp {
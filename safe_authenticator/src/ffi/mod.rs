@@ -24,11 +24,13 @@ pub mod ipc;
 
 use Authenticator;
 use config_file_handler;
-use errors::AuthError;
+use errors::{AuthError, error_catalog_json, error_code_c_name};
 use ffi_utils::{FFI_RESULT_OK, FfiResult, OpaqueCtx, catch_unwind_cb, from_c_str};
 use futures::Future;
-use safe_core::FutureExt;
+use safe_core::{FutureExt, config};
 use safe_core::ffi::AccountInfo as FfiAccountInfo;
+use safe_core::mnemonic;
+use safe_core::ipc::uri_scheme;
 use std::ffi::{CStr, CString, OsStr};
 use std::os::raw::{c_char, c_void};
 
@@ -126,6 +128,118 @@ pub unsafe extern "C" fn login(
     })
 }
 
+/// Create a registered client whose secrets and keys are all derived from a 24-word
+/// mnemonic phrase, as produced by `auth_generate_mnemonic`. The phrase alone is then
+/// enough to log back in with `login_from_mnemonic`.
+///
+/// Callback parameters: user data, error code, authenticator
+#[no_mangle]
+pub unsafe extern "C" fn create_acc_from_mnemonic(
+    mnemonic: *const c_char,
+    network_cb_user_data: *mut c_void,
+    user_data: *mut c_void,
+    o_network_obs_cb: extern "C" fn(user_data: *mut c_void, err_code: i32, event: i32),
+    o_cb: extern "C" fn(user_data: *mut c_void,
+                        result: FfiResult,
+                        authenticator: *mut Authenticator),
+) {
+    let user_data = OpaqueCtx(user_data);
+    let network_cb_user_data = OpaqueCtx(network_cb_user_data);
+
+    catch_unwind_cb(user_data, o_cb, || -> Result<_, AuthError> {
+        trace!("Authenticator - create a client account from a mnemonic.");
+
+        let mnemonic = from_c_str(mnemonic)?;
+
+        let authenticator = Authenticator::create_acc_from_seed(mnemonic, move |net_event| {
+            let ud = network_cb_user_data.0;
+            match net_event {
+                Ok(event) => o_network_obs_cb(ud, 0, event.into()),
+                Err(()) => o_network_obs_cb(ud, -1, 0),
+            }
+        })?;
+
+        o_cb(
+            user_data.0,
+            FFI_RESULT_OK,
+            Box::into_raw(Box::new(authenticator)),
+        );
+
+        Ok(())
+    })
+}
+
+/// Log into an account created with `create_acc_from_mnemonic`.
+///
+/// Callback parameters: user data, error code, authenticator
+#[no_mangle]
+pub unsafe extern "C" fn login_from_mnemonic(
+    mnemonic: *const c_char,
+    user_data: *mut c_void,
+    network_cb_user_data: *mut c_void,
+    o_network_obs_cb: unsafe extern "C" fn(user_data: *mut c_void, err_code: i32, event: i32),
+    o_cb: extern "C" fn(user_data: *mut c_void,
+                        result: FfiResult,
+                        authenticator: *mut Authenticator),
+) {
+    let user_data = OpaqueCtx(user_data);
+    let network_cb_user_data = OpaqueCtx(network_cb_user_data);
+
+    catch_unwind_cb(user_data, o_cb, || -> Result<_, AuthError> {
+        trace!("Authenticator - log in a client from a mnemonic.");
+
+        let mnemonic = from_c_str(mnemonic)?;
+
+        let authenticator = Authenticator::login_from_seed(mnemonic, move |net_event| {
+            match net_event {
+                Ok(event) => o_network_obs_cb(network_cb_user_data.0, 0, event.into()),
+                Err(()) => o_network_obs_cb(network_cb_user_data.0, -1, 0),
+            }
+        })?;
+
+        o_cb(
+            user_data.0,
+            FFI_RESULT_OK,
+            Box::into_raw(Box::new(authenticator)),
+        );
+
+        Ok(())
+    })
+}
+
+/// Generates a new random 24-word mnemonic phrase, suitable for `create_acc_from_mnemonic`
+/// and for writing down as a backup of the account.
+///
+/// Callback parameters: user data, error code, mnemonic
+#[no_mangle]
+pub unsafe extern "C" fn auth_generate_mnemonic(
+    user_data: *mut c_void,
+    o_cb: extern "C" fn(user_data: *mut c_void, result: FfiResult, mnemonic: *const c_char),
+) {
+    catch_unwind_cb(user_data, o_cb, || -> Result<_, AuthError> {
+        let mnemonic = CString::new(mnemonic::generate()?)?;
+        o_cb(user_data, FFI_RESULT_OK, mnemonic.as_ptr());
+        Ok(())
+    });
+}
+
+/// Checks whether the given phrase is a valid mnemonic: 24 known words with a
+/// matching checksum. Useful to catch typos when the user restores a backup.
+///
+/// Callback parameters: user data, error code, is valid
+#[no_mangle]
+pub unsafe extern "C" fn auth_validate_mnemonic(
+    mnemonic: *const c_char,
+    user_data: *mut c_void,
+    o_cb: extern "C" fn(user_data: *mut c_void, result: FfiResult, valid: bool),
+) {
+    catch_unwind_cb(user_data, o_cb, || -> Result<_, AuthError> {
+        let mnemonic = from_c_str(mnemonic)?;
+        o_cb(user_data, FFI_RESULT_OK, mnemonic::validate(&mnemonic));
+        Ok(())
+    });
+}
+
 /// Try to restore a failed connection with the network.
 ///
 /// Callback parameters: user data, error code
@@ -181,6 +295,35 @@ pub unsafe extern "C" fn auth_account_info(
     })
 }
 
+/// Change the account password. Once this succeeds, only the new password can
+/// be used with `login`; if it fails, the old one keeps working. The account
+/// locator can't be changed.
+///
+/// Callback parameters: user data, error code
+#[no_mangle]
+pub unsafe extern "C" fn auth_change_password(
+    auth: *const Authenticator,
+    new_account_password: *const c_char,
+    user_data: *mut c_void,
+    o_cb: extern "C" fn(user_data: *mut c_void, result: FfiResult),
+) {
+    catch_unwind_cb(user_data, o_cb, || -> Result<_, AuthError> {
+        let user_data = OpaqueCtx(user_data);
+        let new_password = from_c_str(new_account_password)?;
+
+        (*auth).send(move |client| {
+            client
+                .change_password(&new_password)
+                .map(move |()| o_cb(user_data.0, FFI_RESULT_OK))
+                .map_err(move |e| {
+                    call_result_cb!(Err::<(), _>(AuthError::from(e)), user_data, o_cb);
+                })
+                .into_box()
+                .into()
+        })
+    })
+}
+
 /// Returns the expected name for the application executable without an extension
 #[no_mangle]
 pub unsafe extern "C" fn auth_exe_file_stem(
@@ -208,6 +351,7 @@ pub unsafe extern "C" fn auth_exe_file_stem(
 }
 
 /// Sets the additional path in `config_file_handler` to to search for files
+/// and re-reads the `safe_core.config` configuration file.
 #[no_mangle]
 pub unsafe extern "C" fn auth_set_additional_search_path(
     new_path: *const c_char,
@@ -217,11 +361,71 @@ pub unsafe extern "C" fn auth_set_additional_search_path(
     catch_unwind_cb(user_data, o_cb, || -> Result<_, AuthError> {
         let new_path = CStr::from_ptr(new_path).to_str()?;
         config_file_handler::set_additional_search_path(OsStr::new(new_path));
+        if let Err(err) = config::reload() {
+            warn!("Couldn't reload the configuration: {:?}", err);
+        }
+        o_cb(user_data, FFI_RESULT_OK);
+        Ok(())
+    });
+}
+
+/// Register the current executable as the handler of the `safe-auth:` URI
+/// scheme. `name` is the human readable name of the authenticator.
+#[no_mangle]
+pub unsafe extern "C" fn auth_register_uri_scheme(
+    name: *const c_char,
+    user_data: *mut c_void,
+    o_cb: extern "C" fn(user_data: *mut c_void, result: FfiResult),
+) {
+    catch_unwind_cb(user_data, o_cb, || -> Result<_, AuthError> {
+        let name = CStr::from_ptr(name).to_str()?;
+        uri_scheme::register(uri_scheme::SAFE_AUTH_SCHEME, name)?;
+        o_cb(user_data, FFI_RESULT_OK);
+        Ok(())
+    });
+}
+
+/// Remove the registration of the current executable as the handler of the
+/// `safe-auth:` URI scheme.
+#[no_mangle]
+pub unsafe extern "C" fn auth_deregister_uri_scheme(
+    user_data: *mut c_void,
+    o_cb: extern "C" fn(user_data: *mut c_void, result: FfiResult),
+) {
+    catch_unwind_cb(user_data, o_cb, || -> Result<_, AuthError> {
+        uri_scheme::deregister(uri_scheme::SAFE_AUTH_SCHEME)?;
         o_cb(user_data, FFI_RESULT_OK);
         Ok(())
     });
 }
 
+/// Returns the catalog of all error codes as a JSON array of objects with
+/// `name`, `code` and `description` fields.
+///
+/// Callback parameters: user data, error code, catalog JSON
+#[no_mangle]
+pub unsafe extern "C" fn auth_error_catalog(
+    user_data: *mut c_void,
+    o_cb: extern "C" fn(user_data: *mut c_void,
+                        result: FfiResult,
+                        catalog_json: *const c_char),
+) {
+    catch_unwind_cb(user_data, o_cb, || -> Result<_, AuthError> {
+        let catalog_json = CString::new(error_catalog_json())?;
+        o_cb(user_data, FFI_RESULT_OK, catalog_json.as_ptr());
+        Ok(())
+    });
+}
+
+/// Returns the symbolic name (e.g. `ERR_NO_SUCH_ENTRY`) of the error code, as
+/// listed in the catalog returned by `auth_error_catalog`, or null if the code is
+/// unknown. The name is a static NUL-terminated string, so the caller must NOT
+/// free it.
+#[no_mangle]
+pub extern "C" fn auth_error_code_name(code: i32) -> *const c_char {
+    error_code_c_name(code)
+}
+
 /// Discard and clean up the previously allocated authenticator instance.
 /// Use this only if the authenticator is obtained from one of the auth
 /// functions in this crate (`create_acc` or `login`).
@@ -285,6 +489,115 @@ mod tests {
         }
     }
 
+    // Test generating and validating a mnemonic, then creating an account from it and
+    // logging in with it.
+    #[test]
+    fn create_account_and_login_from_mnemonic() {
+        use ffi_utils::test_utils::{send_via_user_data, sender_as_user_data};
+        use std::sync::mpsc;
+
+        let mnemonic: String = unsafe { unwrap!(call_1(|ud, cb| auth_generate_mnemonic(ud, cb))) };
+
+        let validate = |phrase: &str| -> bool {
+            let phrase = unwrap!(CString::new(phrase));
+            let (tx, rx) = mpsc::channel::<bool>();
+            unsafe {
+                auth_validate_mnemonic(phrase.as_ptr(), sender_as_user_data(&tx), validate_cb);
+            }
+            unwrap!(rx.recv())
+        };
+
+        assert!(validate(&mnemonic));
+        assert!(!validate("not a valid mnemonic"));
+
+        let mnemonic = unwrap!(CString::new(mnemonic));
+
+        {
+            let auth_h: *mut Authenticator = unsafe {
+                unwrap!(call_1(|ud, cb| {
+                    create_acc_from_mnemonic(mnemonic.as_ptr(), ud, ud, net_event_cb, cb)
+                }))
+            };
+            assert!(!auth_h.is_null());
+            unsafe { auth_free(auth_h) };
+        }
+
+        {
+            let auth_h: *mut Authenticator = unsafe {
+                unwrap!(call_1(|ud, cb| {
+                    login_from_mnemonic(mnemonic.as_ptr(), ud, ud, net_event_cb, cb)
+                }))
+            };
+            assert!(!auth_h.is_null());
+            unsafe { auth_free(auth_h) };
+        }
+
+        extern "C" fn validate_cb(user_data: *mut c_void, res: FfiResult, valid: bool) {
+            assert_eq!(res.error_code, 0);
+            unsafe {
+                send_via_user_data(user_data, valid);
+            }
+        }
+    }
+
+    // Test changing the password and logging in with the new one.
+    #[test]
+    fn change_password() {
+        use ffi_utils::test_utils::call_0;
+
+        let acc_locator = unwrap!(CString::new(unwrap!(utils::generate_random_string(10))));
+        let acc_password = unwrap!(CString::new(unwrap!(utils::generate_random_string(10))));
+        let invitation = unwrap!(CString::new(unwrap!(utils::generate_random_string(10))));
+        let new_password = unwrap!(CString::new(unwrap!(utils::generate_random_string(10))));
+
+        unsafe {
+            let auth_h: *mut Authenticator = unwrap!(call_1(|ud, cb| {
+                create_acc(
+                    acc_locator.as_ptr(),
+                    acc_password.as_ptr(),
+                    invitation.as_ptr(),
+                    ud,
+                    ud,
+                    net_event_cb,
+                    cb,
+                )
+            }));
+
+            unwrap!(call_0(
+                |ud, cb| auth_change_password(auth_h, new_password.as_ptr(), ud, cb),
+            ));
+            auth_free(auth_h);
+        }
+
+        let res: Result<*mut Authenticator, _> = unsafe {
+            call_1(|ud, cb| {
+                login(
+                    acc_locator.as_ptr(),
+                    acc_password.as_ptr(),
+                    ud,
+                    ud,
+                    net_event_cb,
+                    cb,
+                )
+            })
+        };
+        assert!(res.is_err());
+
+        unsafe {
+            let auth_h: *mut Authenticator = unwrap!(call_1(|ud, cb| {
+                login(
+                    acc_locator.as_ptr(),
+                    new_password.as_ptr(),
+                    ud,
+                    ud,
+                    net_event_cb,
+                    cb,
+                )
+            }));
+            auth_free(auth_h);
+        }
+    }
+
     // Test disconnection and reconnection with the authenticator.
     #[cfg(all(test, feature = "use-mock-routing"))]
     #[test]
@@ -87,7 +87,8 @@ pub mod test_utils;
 #[cfg(test)]
 mod tests;
 
-pub use self::errors::AuthError;
+pub use self::errors::{AuthError, ERROR_CATALOG, error_catalog_json, error_name};
+pub use self::revocation::RevocationStep;
 use futures::Future;
 use futures::stream::Stream;
 use futures::sync::mpsc;
@@ -158,6 +159,26 @@ impl Authenticator {
         )
     }
 
+    /// Create a new account whose secrets and keys are all derived from a 24-word mnemonic
+    /// phrase (see `safe_core::mnemonic`).
+    pub fn create_acc_from_seed<S, NetObs>(
+        mnemonic: S,
+        network_observer: NetObs,
+    ) -> Result<Self, AuthError>
+    where
+        NetObs: FnMut(Result<NetworkEvent, ()>) + Send + 'static,
+        S: Into<String>,
+    {
+        let mnemonic = mnemonic.into();
+
+        Self::create_acc_impl(
+            move |el_h, core_tx, net_tx| {
+                Client::registered_from_seed(&mnemonic, el_h, core_tx, net_tx)
+            },
+            network_observer,
+        )
+    }
+
     /// Create a new account
     fn create_acc_impl<F: 'static + Send, NetObs>(
         create_client_fn: F,
@@ -238,6 +259,23 @@ impl Authenticator {
         )
     }
 
+    /// Log in to an account created with `create_acc_from_seed`
+    pub fn login_from_seed<S, NetObs>(
+        mnemonic: S,
+        network_observer: NetObs,
+    ) -> Result<Self, AuthError>
+    where
+        S: Into<String>,
+        NetObs: FnMut(Result<NetworkEvent, ()>) + Send + 'static,
+    {
+        let mnemonic = mnemonic.into();
+
+        Self::login_impl(
+            move |el_h, core_tx, net_tx| Client::login_from_seed(&mnemonic, el_h, core_tx, net_tx),
+            network_observer,
+        )
+    }
+
     /// Log in to an existing account
     pub fn login_impl<F: Send + 'static, NetObs>(
         create_client_fn: F,
@@ -17,7 +17,7 @@
 
 use super::{AuthError, AuthFuture};
 use access_container::{self, AUTHENTICATOR_ENTRY};
-use config::{self, AppInfo, RevocationQueue};
+use config::{self, AppEvent, AppEventKind, AppInfo, RevocationQueue};
 use futures::Future;
 use futures::future::{self, Either, Loop};
 use routing::{ClientError, EntryActions, User};
@@ -25,10 +25,45 @@ use rust_sodium::crypto::sign;
 use safe_core::{Client, CoreError, FutureExt, MDataInfo};
 use safe_core::ipc::IpcError;
 use safe_core::recovery;
+use std::cell::RefCell;
 use std::collections::HashMap;
+use std::rc::Rc;
+
+/// Stage of an app revocation, reported as the revocation progresses.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum RevocationStep {
+    /// The app's auth key was deleted, so the app can't mutate data anymore
+    AuthKeyDeleted,
+    /// The app's permissions were removed from the containers it had access to
+    PermissionsRevoked,
+    /// The containers the app could read were re-encrypted with new keys
+    ContainersReencrypted,
+    /// The app's entry was removed from the access container, so the app is
+    /// fully revoked
+    Done,
+}
+
+type Progress = Rc<RefCell<FnMut(&str, RevocationStep)>>;
 
 /// Revokes app access using a revocation queue
 pub fn revoke_app(client: &Client<()>, app_id: &str) -> Box<AuthFuture<()>> {
+    revoke_app_with_progress(client, app_id, no_progress)
+}
+
+/// Revokes app access using a revocation queue, calling `progress` with the
+/// app id and the completed step as the revocation progresses. Apps which
+/// were already in the queue are revoked first, and their progress is
+/// reported too.
+pub fn revoke_app_with_progress<F>(
+    client: &Client<()>,
+    app_id: &str,
+    progress: F,
+) -> Box<AuthFuture<()>>
+where
+    F: FnMut(&str, RevocationStep) + 'static,
+{
+    let progress: Progress = Rc::new(RefCell::new(progress));
     let app_id = app_id.to_string();
     let client = client.clone();
     let c2 = client.clone();
@@ -43,7 +78,7 @@ pub fn revoke_app(client: &Client<()>, app_id: &str) -> Box<AuthFuture<()>> {
             )
         })
         .and_then(move |(version, queue)| {
-            flush_app_revocation_queue_impl(&c2, queue, version + 1)
+            flush_app_revocation_queue_impl(&c2, queue, version + 1, progress)
         })
         .into_box()
 }
@@ -54,7 +89,8 @@ pub fn flush_app_revocation_queue(client: &Client<()>) -> Box<AuthFuture<()>> {
 
     config::get_app_revocation_queue(&client)
         .and_then(move |(version, queue)| if let Some(version) = version {
-            flush_app_revocation_queue_impl(&client, queue, version + 1)
+            let progress: Progress = Rc::new(RefCell::new(no_progress));
+            flush_app_revocation_queue_impl(&client, queue, version + 1, progress)
         } else {
             future::ok(()).into_box()
         })
@@ -65,6 +101,7 @@ fn flush_app_revocation_queue_impl(
     client: &Client<()>,
     queue: RevocationQueue,
     version: u64,
+    progress: Progress,
 ) -> Box<AuthFuture<()>> {
     let client = client.clone();
 
@@ -73,7 +110,7 @@ fn flush_app_revocation_queue_impl(
         let c3 = client.clone();
 
         if let Some(app_id) = queue.front().cloned() {
-            let f = revoke_single_app(&c2, &app_id)
+            let f = revoke_single_app(&c2, &app_id, Rc::clone(&progress))
                 .and_then(move |_| {
                     config::remove_from_app_revocation_queue(&c3, queue, version, app_id)
                 })
@@ -88,13 +125,22 @@ fn flush_app_revocation_queue_impl(
 }
 
 /// Revoke access for a single app
-fn revoke_single_app(client: &Client<()>, app_id: &str) -> Box<AuthFuture<()>> {
+fn revoke_single_app(
+    client: &Client<()>,
+    app_id: &str,
+    progress: Progress,
+) -> Box<AuthFuture<()>> {
+    let app_id2 = app_id.to_string();
+    let p2 = Rc::clone(&progress);
+    let p3 = Rc::clone(&progress);
+    let p4 = Rc::clone(&progress);
     let c2 = client.clone();
     let c4 = client.clone();
     let c5 = client.clone();
     let c6 = client.clone();
     let c7 = client.clone();
     let c8 = client.clone();
+    let c9 = client.clone();
 
     // 1. Put the provided app_id into the revocation queue
     // 2. Delete the app key from MaidManagers
@@ -107,7 +153,10 @@ fn revoke_single_app(client: &Client<()>, app_id: &str) -> Box<AuthFuture<()>> {
     // 5. Remove the revoked app from the access container
     config::get_app(client, app_id)
         .and_then(move |app| {
-            delete_app_auth_key(&c2, app.keys.sign_pk).map(move |_| app)
+            delete_app_auth_key(&c2, app.keys.sign_pk).map(move |_| {
+                report(&p2, &app.info.id, RevocationStep::AuthKeyDeleted);
+                app
+            })
         })
         .and_then(move |app| {
             access_container::fetch_entry(&c4, &app.info.id, app.keys.clone())
@@ -122,8 +171,10 @@ fn revoke_single_app(client: &Client<()>, app_id: &str) -> Box<AuthFuture<()>> {
                 })
         })
         .and_then(move |(app, ac_entry_version, containers)| {
-            revoke_container_perms(&c5, &containers, app.keys.sign_pk)
-                .map(move |_| (app, ac_entry_version, containers))
+            revoke_container_perms(&c5, &containers, app.keys.sign_pk).map(move |_| {
+                report(&p3, &app.info.id, RevocationStep::PermissionsRevoked);
+                (app, ac_entry_version, containers)
+            })
         })
         .and_then(move |(app, ac_entry_version, containers)| {
             refresh_from_access_container_root(&c6, containers).map(move |refreshed_containers| {
@@ -131,15 +182,31 @@ fn revoke_single_app(client: &Client<()>, app_id: &str) -> Box<AuthFuture<()>> {
             })
         })
         .and_then(move |(app, ac_entry_version, containers)| {
-            reencrypt_containers_and_update_access_container(&c7, containers, &app)
-                .map(move |_| (app, ac_entry_version))
+            reencrypt_containers_and_update_access_container(&c7, containers, &app).map(
+                move |_| {
+                    report(&p4, &app.info.id, RevocationStep::ContainersReencrypted);
+                    (app, ac_entry_version)
+                },
+            )
         })
         .and_then(move |(app, version)| {
             access_container::delete_entry(&c8, &app.info.id, &app.keys, version + 1)
+                .map(move |_| app)
+        })
+        .and_then(move |app| {
+            let event = AppEvent::new(AppEventKind::Revoked, app.info, HashMap::new());
+            config::record_app_event(&c9, event)
         })
+        .map(move |_| report(&progress, &app_id2, RevocationStep::Done))
         .into_box()
 }
 
+fn report(progress: &Progress, app_id: &str, step: RevocationStep) {
+    (&mut *progress.borrow_mut())(app_id, step)
+}
+
+fn no_progress(_app_id: &str, _step: RevocationStep) {}
+
 /// Delete the app's auth key from the Maid Manager - this prevents the app from
 /// performing any more mutations.
 fn delete_app_auth_key(client: &Client<()>, key: sign::PublicKey) -> Box<AuthFuture<()>> {
@@ -29,10 +29,11 @@ use safe_core::utils::symmetric_encrypt;
 use std::collections::HashMap;
 
 /// Default Directories to be created at registration
-pub static DEFAULT_PRIVATE_DIRS: [&'static str; 5] = [
+pub static DEFAULT_PRIVATE_DIRS: [&'static str; 6] = [
     "_documents",
     "_downloads",
     "_music",
+    "_pictures",
     "_videos",
     "_publicNames",
 ];
@@ -20,11 +20,13 @@ use Authenticator;
 use app_container;
 use errors::AuthError;
 use futures::Future;
-use revocation;
+use revocation::{self, RevocationStep};
 use routing::{AccountInfo, User};
 use safe_core::{CoreError, MDataInfo};
 use safe_core::ipc::AuthReq;
 use safe_core::nfs::NfsError;
+use std::cell::RefCell;
+use std::rc::Rc;
 use test_utils::{access_container, create_account_and_login, create_authenticator, create_file,
                  fetch_file, rand_app, register_app, revoke, run, try_access_container};
 
@@ -931,6 +933,77 @@ fn flushing_empty_app_revocation_queue_does_not_mutate_network() {
     assert_eq!(account_info_2, account_info_3);
 }
 
+// Test that the progress of an app revocation is reported step by step.
+#[test]
+fn app_revocation_progress() {
+    let authenticator = create_account_and_login();
+
+    let auth_req = AuthReq {
+        app: rand_app(),
+        app_container: false,
+        containers: create_containers_req(),
+    };
+    let app_id = auth_req.app.id.clone();
+    let _ = unwrap!(register_app(&authenticator, &auth_req));
+
+    let app_id2 = app_id.clone();
+    let steps = run(&authenticator, move |client| {
+        let steps = Rc::new(RefCell::new(Vec::new()));
+        let steps2 = Rc::clone(&steps);
+
+        revocation::revoke_app_with_progress(client, &app_id2, move |app_id, step| {
+            steps2.borrow_mut().push((app_id.to_string(), step));
+        }).map(move |_| steps.borrow().clone())
+    });
+
+    assert_eq!(
+        steps,
+        vec![
+            (app_id.clone(), RevocationStep::AuthKeyDeleted),
+            (app_id.clone(), RevocationStep::PermissionsRevoked),
+            (app_id.clone(), RevocationStep::ContainersReencrypted),
+            (app_id, RevocationStep::Done),
+        ]
+    );
+}
+
+// Test that authorisations and revocations are recorded in the app history.
+#[test]
+fn app_history() {
+    use config::{self, AppEventKind};
+
+    let authenticator = create_account_and_login();
+
+    let auth_req = AuthReq {
+        app: rand_app(),
+        app_container: false,
+        containers: create_containers_req(),
+    };
+    let app_id = auth_req.app.id.clone();
+
+    let _ = unwrap!(register_app(&authenticator, &auth_req));
+    revoke(&authenticator, &app_id);
+    let _ = unwrap!(register_app(&authenticator, &auth_req));
+
+    let history = run(&authenticator, |client| config::get_app_history(client));
+
+    let kinds: Vec<_> = history.iter().map(|event| event.kind).collect();
+    assert_eq!(
+        kinds,
+        vec![
+            AppEventKind::Authorised,
+            AppEventKind::Revoked,
+            AppEventKind::Authorised,
+        ]
+    );
+
+    for event in &history {
+        assert_eq!(event.info, auth_req.app);
+    }
+    assert_eq!(history[0].containers, auth_req.containers);
+    assert!(history[1].containers.is_empty());
+}
+
 fn count_mdata_entries(authenticator: &Authenticator, info: MDataInfo) -> usize {
     run(authenticator, move |client| {
         client
@@ -0,0 +1,221 @@
+// Copyright 2016 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under (1) the MaidSafe.net Commercial License,
+// version 1.0 or later, or (2) The General Public License (GPL), version 3, depending on which
+// licence you accepted on initial access to the Software (the "Licences").
+//
+// By contributing code to the SAFE Network Software, or to this project generally, you agree to be
+// bound by the terms of the MaidSafe Contributor Agreement.  This, along with the Licenses can be
+// found in the root directory of this project at LICENSE, COPYING and CONTRIBUTOR.
+//
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.
+//
+// Please review the Licences for the specific language governing permissions and limitations
+// relating to use of the SAFE Network Software.
+
+//! Experimental append-only data type.
+//!
+//! `AppendableData` is public data with a fixed set of owners, to which
+//! anyone passing its filter can append entries. Only the owners can change
+//! the filter or clear the entries. The network doesn't support this type yet,
+//! so it's only available against the mock vault.
+
+use routing::{ClientError, XorName};
+use rust_sodium::crypto::sign;
+use std::collections::BTreeSet;
+
+/// Maximum number of entries in a single `AppendableData`.
+pub const MAX_APPENDABLE_DATA_ENTRIES: usize = 1000;
+
+/// Filter deciding which keys are allowed to append.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub enum Filter {
+    /// Everyone except the listed keys may append.
+    BlackList(BTreeSet<sign::PublicKey>),
+    /// Only the listed keys may append.
+    WhiteList(BTreeSet<sign::PublicKey>),
+}
+
+impl Filter {
+    /// Returns true if `key` passes this filter.
+    pub fn allows(&self, key: &sign::PublicKey) -> bool {
+        match *self {
+            Filter::BlackList(ref keys) => !keys.contains(key),
+            Filter::WhiteList(ref keys) => keys.contains(key),
+        }
+    }
+}
+
+impl Default for Filter {
+    fn default() -> Self {
+        Filter::BlackList(BTreeSet::new())
+    }
+}
+
+/// Single entry appended to `AppendableData`.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct AppendedEntry {
+    /// Key of the appender.
+    pub author: sign::PublicKey,
+    /// Content of the entry.
+    pub content: Vec<u8>,
+}
+
+/// Append-only public data.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct AppendableData {
+    name: XorName,
+    tag: u64,
+    owners: BTreeSet<sign::PublicKey>,
+    filter: Filter,
+    entries: Vec<AppendedEntry>,
+}
+
+impl AppendableData {
+    /// Create new empty `AppendableData`.
+    pub fn new(
+        name: XorName,
+        tag: u64,
+        owners: BTreeSet<sign::PublicKey>,
+        filter: Filter,
+    ) -> Result<Self, ClientError> {
+        if owners.is_empty() {
+            return Err(ClientError::InvalidOwners);
+        }
+
+        Ok(AppendableData {
+            name,
+            tag,
+            owners,
+            filter,
+            entries: Vec::new(),
+        })
+    }
+
+    /// Returns the name.
+    pub fn name(&self) -> &XorName {
+        &self.name
+    }
+
+    /// Returns the type tag.
+    pub fn tag(&self) -> u64 {
+        self.tag
+    }
+
+    /// Returns the owner keys.
+    pub fn owners(&self) -> &BTreeSet<sign::PublicKey> {
+        &self.owners
+    }
+
+    /// Returns the filter.
+    pub fn filter(&self) -> &Filter {
+        &self.filter
+    }
+
+    /// Returns the appended entries, oldest first.
+    pub fn entries(&self) -> &[AppendedEntry] {
+        &self.entries
+    }
+
+    /// Append `content` on behalf of `requester`. Owners can always append,
+    /// everyone else has to pass the filter.
+    pub fn append(
+        &mut self,
+        content: Vec<u8>,
+        requester: sign::PublicKey,
+    ) -> Result<(), ClientError> {
+        if !self.is_owner(&requester) && !self.filter.allows(&requester) {
+            return Err(ClientError::AccessDenied);
+        }
+        if self.entries.len() >= MAX_APPENDABLE_DATA_ENTRIES {
+            return Err(ClientError::TooManyEntries);
+        }
+
+        self.entries.push(AppendedEntry {
+            author: requester,
+            content,
+        });
+        Ok(())
+    }
+
+    /// Replace the filter. Only the owners can do this.
+    pub fn set_filter(
+        &mut self,
+        filter: Filter,
+        requester: sign::PublicKey,
+    ) -> Result<(), ClientError> {
+        self.check_owner(&requester)?;
+        self.filter = filter;
+        Ok(())
+    }
+
+    /// Remove all entries. Only the owners can do this.
+    pub fn clear(&mut self, requester: sign::PublicKey) -> Result<(), ClientError> {
+        self.check_owner(&requester)?;
+        self.entries.clear();
+        Ok(())
+    }
+
+    fn is_owner(&self, key: &sign::PublicKey) -> bool {
+        self.owners.contains(key)
+    }
+
+    fn check_owner(&self, key: &sign::PublicKey) -> Result<(), ClientError> {
+        if self.is_owner(key) {
+            Ok(())
+        } else {
+            Err(ClientError::AccessDenied)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand;
+
+    #[test]
+    fn filters() {
+        let (owner, _) = sign::gen_keypair();
+        let (friend, _) = sign::gen_keypair();
+        let (stranger, _) = sign::gen_keypair();
+
+        let mut data = unwrap!(AppendableData::new(
+            rand::random(),
+            10000,
+            btree_set![owner],
+            Filter::default(),
+        ));
+
+        unwrap!(data.append(vec![1], owner));
+        unwrap!(data.append(vec![2], stranger));
+        assert_eq!(data.entries().len(), 2);
+        assert_eq!(data.entries()[1].author, stranger);
+
+        // Only owners can change the filter.
+        let filter = Filter::WhiteList(btree_set![friend]);
+        match data.set_filter(filter.clone(), stranger) {
+            Err(ClientError::AccessDenied) => (),
+            x => panic!("Unexpected {:?}", x),
+        }
+        unwrap!(data.set_filter(filter, owner));
+
+        unwrap!(data.append(vec![3], friend));
+        unwrap!(data.append(vec![4], owner));
+        match data.append(vec![5], stranger) {
+            Err(ClientError::AccessDenied) => (),
+            x => panic!("Unexpected {:?}", x),
+        }
+        assert_eq!(data.entries().len(), 4);
+
+        // Only owners can clear the entries.
+        match data.clear(friend) {
+            Err(ClientError::AccessDenied) => (),
+            x => panic!("Unexpected {:?}", x),
+        }
+        unwrap!(data.clear(owner));
+        assert!(data.entries().is_empty());
+    }
+}
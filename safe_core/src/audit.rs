@@ -0,0 +1,286 @@
+// Copyright 2017 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under (1) the MaidSafe.net Commercial License,
+// version 1.0 or later, or (2) The General Public License (GPL), version 3, depending on which
+// licence you accepted on initial access to the Software (the "Licences").
+//
+// By contributing code to the SAFE Network Software, or to this project generally, you agree to be
+// bound by the terms of the MaidSafe Contributor Agreement.  This, along with the Licenses can be
+// found in the root directory of this project at LICENSE, COPYING and CONTRIBUTOR.
+//
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.
+//
+// Please review the Licences for the specific language governing permissions and limitations
+// relating to use of the SAFE Network Software.
+
+//! Local audit trail of the mutation requests signed by the client.
+//!
+//! When enabled with `Client::set_audit_log`, every mutation request sent to
+//! the network is recorded in an encrypted, append-only file. Each record also
+//! holds the hash of the previous one, so records removed from the middle of
+//! the log or reordered are detected when the log is read back.
+
+use chrono::{DateTime, Utc};
+use errors::CoreError;
+use maidsafe_utilities::serialisation::{deserialise, serialise};
+use metrics::Operation;
+use routing::{MessageId, XorName};
+use rust_sodium::crypto::secretbox;
+use std::cell::Cell;
+use std::fs::{File, OpenOptions};
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+use tiny_keccak::sha3_256;
+use utils::{symmetric_decrypt, symmetric_encrypt};
+
+/// Target of the audited request.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub enum Target {
+    /// `ImmutableData` with the given name
+    ImmutableData(XorName),
+    /// `MutableData` with the given name and type tag
+    MutableData(XorName, u64),
+    /// The account itself (e.g. its authorised keys)
+    Account,
+}
+
+impl Target {
+    /// Name of the targeted data, if any.
+    pub fn name(&self) -> Option<&XorName> {
+        match *self {
+            Target::ImmutableData(ref name) |
+            Target::MutableData(ref name, _) => Some(name),
+            Target::Account => None,
+        }
+    }
+}
+
+/// Single record of the audit log.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct AuditEntry {
+    /// Type of the request
+    pub operation: Operation,
+    /// Target of the request
+    pub target: Target,
+    /// Time the request was sent at
+    pub timestamp: DateTime<Utc>,
+    /// Id of the request message
+    pub msg_id: MessageId,
+}
+
+/// Criteria for querying the audit log. Entries have to match all the
+/// criteria which are set.
+#[derive(Clone, Debug, Default)]
+pub struct AuditQuery {
+    /// Only entries of this operation
+    pub operation: Option<Operation>,
+    /// Only entries targeting data with this name
+    pub name: Option<XorName>,
+    /// Only entries recorded at or after this time
+    pub since: Option<DateTime<Utc>>,
+    /// Only entries recorded before this time
+    pub until: Option<DateTime<Utc>>,
+}
+
+impl AuditQuery {
+    /// Returns true if the entry matches this query.
+    pub fn matches(&self, entry: &AuditEntry) -> bool {
+        let operation = self.operation.map_or(true, |op| op == entry.operation);
+        let name = self.name.as_ref().map_or(
+            true,
+            |name| entry.target.name() == Some(name),
+        );
+        let since = self.since.map_or(true, |since| entry.timestamp >= since);
+        let until = self.until.map_or(true, |until| entry.timestamp < until);
+
+        operation && name && since && until
+    }
+}
+
+#[derive(Deserialize, Serialize)]
+struct Record {
+    entry: AuditEntry,
+    prev_hash: [u8; 32],
+}
+
+/// Encrypted append-only log of the mutation requests.
+pub struct AuditLog {
+    path: PathBuf,
+    key: secretbox::Key,
+    last_hash: Cell<[u8; 32]>,
+}
+
+impl AuditLog {
+    /// Open the log at `path`, creating it if it doesn't exist. Fails if the
+    /// existing log can't be decrypted with `key` or has been tampered with.
+    pub fn open<P: AsRef<Path>>(path: P, key: secretbox::Key) -> Result<Self, CoreError> {
+        let log = AuditLog {
+            path: path.as_ref().to_path_buf(),
+            key,
+            last_hash: Cell::new([0; 32]),
+        };
+
+        if log.path.exists() {
+            let (_, last_hash) = log.read()?;
+            log.last_hash.set(last_hash);
+        }
+
+        Ok(log)
+    }
+
+    /// Append the entry to the log.
+    pub fn record(&self, entry: AuditEntry) -> Result<(), CoreError> {
+        let record = Record {
+            entry,
+            prev_hash: self.last_hash.get(),
+        };
+        let cipher_text = symmetric_encrypt(&serialise(&record)?, &self.key, None)?;
+
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .map_err(io_error)?;
+        let mut buffer = encode_len(cipher_text.len()).to_vec();
+        buffer.extend_from_slice(&cipher_text);
+        file.write_all(&buffer).map_err(io_error)?;
+
+        self.last_hash.set(sha3_256(&cipher_text));
+        Ok(())
+    }
+
+    /// Read all entries of the log, oldest first.
+    pub fn entries(&self) -> Result<Vec<AuditEntry>, CoreError> {
+        self.read().map(|(entries, _)| entries)
+    }
+
+    /// Read the entries matching the query, oldest first.
+    pub fn query(&self, query: &AuditQuery) -> Result<Vec<AuditEntry>, CoreError> {
+        Ok(
+            self.entries()?
+                .into_iter()
+                .filter(|entry| query.matches(entry))
+                .collect(),
+        )
+    }
+
+    // Read and verify the whole log, returning its entries and the hash of
+    // the last record.
+    fn read(&self) -> Result<(Vec<AuditEntry>, [u8; 32]), CoreError> {
+        let mut content = Vec::new();
+        let _ = File::open(&self.path)
+            .and_then(|mut file| file.read_to_end(&mut content))
+            .map_err(io_error)?;
+
+        let mut entries = Vec::new();
+        let mut prev_hash = [0; 32];
+        let mut rest = &content[..];
+
+        while !rest.is_empty() {
+            if rest.len() < 4 {
+                return Err(corrupted());
+            }
+            let len = decode_len(&rest[..4]);
+            if rest.len() < 4 + len {
+                return Err(corrupted());
+            }
+            let cipher_text = &rest[4..4 + len];
+            rest = &rest[4 + len..];
+
+            let record: Record = deserialise(&symmetric_decrypt(cipher_text, &self.key)?)?;
+            if record.prev_hash != prev_hash {
+                return Err(corrupted());
+            }
+
+            prev_hash = sha3_256(cipher_text);
+            entries.push(record.entry);
+        }
+
+        Ok((entries, prev_hash))
+    }
+}
+
+fn encode_len(len: usize) -> [u8; 4] {
+    let len = len as u32;
+    [(len >> 24) as u8, (len >> 16) as u8, (len >> 8) as u8, len as u8]
+}
+
+fn decode_len(bytes: &[u8]) -> usize {
+    bytes.iter().fold(0, |len, byte| (len << 8) | *byte as usize)
+}
+
+fn io_error(error: io::Error) -> CoreError {
+    CoreError::Unexpected(format!("Audit log I/O error: {:?}", error))
+}
+
+fn corrupted() -> CoreError {
+    CoreError::Unexpected("Audit log is corrupted".to_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand;
+    use std::env;
+    use std::fs;
+
+    fn entry(operation: Operation, target: Target) -> AuditEntry {
+        AuditEntry {
+            operation,
+            target,
+            timestamp: Utc::now(),
+            msg_id: MessageId::new(),
+        }
+    }
+
+    // Record entries, read them back from a reopened log and query them.
+    #[test]
+    fn record_and_query() {
+        let path = env::temp_dir().join(format!("audit-{}", rand::random::<u64>()));
+        let key = secretbox::gen_key();
+
+        let name: XorName = rand::random();
+        let put = entry(Operation::PutIData, Target::ImmutableData(name));
+        let mutate = entry(Operation::MutateMDataEntries, Target::MutableData(name, 15_000));
+        let auth = entry(Operation::InsAuthKey, Target::Account);
+
+        {
+            let log = unwrap!(AuditLog::open(&path, key.clone()));
+            unwrap!(log.record(put.clone()));
+            unwrap!(log.record(mutate.clone()));
+        }
+
+        let log = unwrap!(AuditLog::open(&path, key.clone()));
+        unwrap!(log.record(auth.clone()));
+        assert_eq!(unwrap!(log.entries()), vec![put.clone(), mutate.clone(), auth]);
+
+        let query = AuditQuery {
+            name: Some(name),
+            ..Default::default()
+        };
+        assert_eq!(unwrap!(log.query(&query)), vec![put, mutate.clone()]);
+
+        let query = AuditQuery {
+            operation: Some(Operation::MutateMDataEntries),
+            ..Default::default()
+        };
+        assert_eq!(unwrap!(log.query(&query)), vec![mutate]);
+
+        // Wrong key can't open the log.
+        assert!(AuditLog::open(&path, secretbox::gen_key()).is_err());
+
+        // Removing a record from the middle breaks the chain.
+        let mut content = Vec::new();
+        let _ = unwrap!(unwrap!(File::open(&path)).read_to_end(&mut content));
+        let first_len = 4 + decode_len(&content[..4]);
+        let second_len = 4 + decode_len(&content[first_len..first_len + 4]);
+        let mut tampered = content[..first_len].to_vec();
+        tampered.extend_from_slice(&content[first_len + second_len..]);
+        unwrap!(unwrap!(File::create(&path)).write_all(&tampered));
+        assert!(AuditLog::open(&path, key).is_err());
+
+        unwrap!(fs::remove_file(&path));
+    }
+}
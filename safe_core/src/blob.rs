@@ -0,0 +1,397 @@
+// Copyright 2017 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under (1) the MaidSafe.net Commercial License,
+// version 1.0 or later, or (2) The General Public License (GPL), version 3, depending on which
+// licence you accepted on initial access to the Software (the "Licences").
+//
+// By contributing code to the SAFE Network Software, or to this project generally, you agree to be
+// bound by the terms of the MaidSafe Contributor Agreement.  This, along with the Licenses can be
+// found in the root directory of this project at LICENSE, COPYING and CONTRIBUTOR.
+//
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.
+//
+// Please review the Licences for the specific language governing permissions and limitations
+// relating to use of the SAFE Network Software.
+
+//! Content of arbitrary size stored as a sequence of independently
+//! self-encrypted segments. The segments are listed in a `BlobManifest`
+//! together with hashes of their plain text, which allows resuming interrupted
+//! uploads, reading arbitrary ranges without fetching the whole content, and
+//! verifying the integrity of each segment.
+//!
+//! Small content which doesn't need any of that can be stored with `put_blob`,
+//! which skips self-encryption for content up to `INLINE_MAX_SIZE`.
+
+use client::Client;
+use crypto::shared_secretbox;
+use errors::CoreError;
+use event_loop::CoreFuture;
+use futures::{Future, future};
+use futures::future::Loop;
+use immutable_data;
+use maidsafe_utilities::serialisation::{deserialise, serialise};
+use routing::{ImmutableData, XorName};
+use std::cmp;
+use tiny_keccak::sha3_256;
+use utils::{self, FutureExt};
+
+/// Size of a single segment. Only the last segment of a blob can be smaller.
+pub const SEGMENT_SIZE: usize = 1024 * 1024;
+
+/// Maximum size of content stored by `put_blob` in a single `ImmutableData`.
+/// Self-encrypting such content would cost more chunks than the content itself.
+pub const INLINE_MAX_SIZE: usize = 3 * 1024;
+
+/// Address of content stored with `put_blob`.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub enum BlobAddress {
+    /// Content stored directly in the `ImmutableData` with this name.
+    Inline(XorName),
+    /// Content self-encrypted, with the data map stored in the
+    /// `ImmutableData` with this name.
+    SelfEncrypted(XorName),
+}
+
+/// Store the content in the network, encrypted with `encryption_key` if given.
+/// Content up to `INLINE_MAX_SIZE` bytes is stored in a single `ImmutableData`,
+/// larger content is self-encrypted.
+pub fn put_blob<T: 'static>(
+    client: &Client<T>,
+    content: &[u8],
+    encryption_key: Option<shared_secretbox::Key>,
+) -> Box<CoreFuture<BlobAddress>> {
+    if content.len() > INLINE_MAX_SIZE {
+        return put_content(client, content, encryption_key)
+            .map(BlobAddress::SelfEncrypted)
+            .into_box();
+    }
+
+    let value = match encryption_key {
+        Some(key) => fry!(utils::symmetric_encrypt(content, &key, None)),
+        None => content.to_vec(),
+    };
+    let data = ImmutableData::new(value);
+    let name = *data.name();
+
+    client
+        .put_idata(data)
+        .map(move |_| BlobAddress::Inline(name))
+        .into_box()
+}
+
+/// Fetch content stored with `put_blob`.
+pub fn get_blob<T: 'static>(
+    client: &Client<T>,
+    address: &BlobAddress,
+    decryption_key: Option<shared_secretbox::Key>,
+) -> Box<CoreFuture<Vec<u8>>> {
+    match *address {
+        BlobAddress::Inline(name) => {
+            client
+                .get_idata(name)
+                .and_then(move |data| match decryption_key {
+                    Some(key) => utils::symmetric_decrypt(data.value(), &key),
+                    None => Ok(data.value().clone()),
+                })
+                .into_box()
+        }
+        BlobAddress::SelfEncrypted(name) => {
+            immutable_data::get_value(client, &name, decryption_key)
+        }
+    }
+}
+
+/// Segment of a blob.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct BlobSegment {
+    /// Name of the `ImmutableData` holding the segment's data map
+    pub name: XorName,
+    /// Size of the segment
+    pub size: u64,
+    /// SHA3-256 hash of the segment's plain text
+    pub hash: [u8; 32],
+}
+
+/// List of segments a blob consists of.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Serialize, Deserialize)]
+pub struct BlobManifest {
+    segments: Vec<BlobSegment>,
+}
+
+impl BlobManifest {
+    /// Create manifest of an empty blob.
+    pub fn new() -> Self {
+        BlobManifest { segments: Vec::new() }
+    }
+
+    /// Segments of the blob.
+    pub fn segments(&self) -> &[BlobSegment] {
+        &self.segments
+    }
+
+    /// Size of the blob.
+    pub fn size(&self) -> u64 {
+        self.segments.iter().map(|segment| segment.size).sum()
+    }
+
+    /// Store the manifest itself in the network and return the name it can be
+    /// fetched by.
+    pub fn put<T: 'static>(
+        &self,
+        client: &Client<T>,
+        encryption_key: Option<shared_secretbox::Key>,
+    ) -> Box<CoreFuture<XorName>> {
+        let content = fry!(serialise(self));
+        put_content(client, &content, encryption_key)
+    }
+
+    /// Fetch manifest stored with `put`.
+    pub fn get<T: 'static>(
+        client: &Client<T>,
+        name: XorName,
+        decryption_key: Option<shared_secretbox::Key>,
+    ) -> Box<CoreFuture<Self>> {
+        immutable_data::get_value(client, &name, decryption_key)
+            .and_then(|content| Ok(deserialise(&content)?))
+            .into_box()
+    }
+
+    /// Read `len` bytes starting at `offset`, fetching only the segments
+    /// covering the range. The range is truncated to the size of the blob.
+    pub fn read<T: 'static>(
+        &self,
+        client: &Client<T>,
+        offset: u64,
+        len: u64,
+        decryption_key: Option<shared_secretbox::Key>,
+    ) -> Box<CoreFuture<Vec<u8>>> {
+        let end = cmp::min(offset.saturating_add(len), self.size());
+        let mut reads = Vec::new();
+        let mut segment_start = 0;
+
+        for segment in &self.segments {
+            let segment_end = segment_start + segment.size;
+
+            if segment_end > offset && segment_start < end {
+                let from = (cmp::max(offset, segment_start) - segment_start) as usize;
+                let to = (cmp::min(end, segment_end) - segment_start) as usize;
+
+                reads.push(
+                    read_segment(client, segment, decryption_key.clone())
+                        .map(move |content| content[from..to].to_vec()),
+                );
+            }
+
+            segment_start = segment_end;
+        }
+
+        future::join_all(reads)
+            .map(|parts| {
+                parts.into_iter().fold(Vec::new(), |mut content, part| {
+                    content.extend_from_slice(&part);
+                    content
+                })
+            })
+            .into_box()
+    }
+}
+
+/// Uploader of blob content. Content is buffered until a full segment is
+/// available and then uploaded. The manifest of the already uploaded segments
+/// can be saved at any point and passed to `resume` to continue an interrupted
+/// upload.
+#[derive(Debug)]
+pub struct BlobWriter {
+    manifest: BlobManifest,
+    buffer: Vec<u8>,
+    encryption_key: Option<shared_secretbox::Key>,
+}
+
+impl BlobWriter {
+    /// Start uploading new blob.
+    pub fn new(encryption_key: Option<shared_secretbox::Key>) -> Self {
+        Self::resume(BlobManifest::new(), encryption_key)
+    }
+
+    /// Continue uploading the blob after the segments in `manifest`. The
+    /// content written next should start at `manifest.size()`.
+    pub fn resume(manifest: BlobManifest, encryption_key: Option<shared_secretbox::Key>) -> Self {
+        BlobWriter {
+            manifest,
+            buffer: Vec::new(),
+            encryption_key,
+        }
+    }
+
+    /// Manifest of the segments uploaded so far. Doesn't include the content
+    /// which is still buffered.
+    pub fn manifest(&self) -> &BlobManifest {
+        &self.manifest
+    }
+
+    /// Write the content, uploading all full segments.
+    pub fn write<T: 'static>(mut self, client: &Client<T>, data: &[u8]) -> Box<CoreFuture<Self>> {
+        self.buffer.extend_from_slice(data);
+        self.flush(client, false)
+    }
+
+    /// Upload the remaining content and return the complete manifest.
+    pub fn close<T: 'static>(self, client: &Client<T>) -> Box<CoreFuture<BlobManifest>> {
+        self.flush(client, true)
+            .map(|writer| writer.manifest)
+            .into_box()
+    }
+
+    // Upload the buffered segments one by one, so that the manifest always
+    // reflects a prefix of the content.
+    fn flush<T: 'static>(self, client: &Client<T>, all: bool) -> Box<CoreFuture<Self>> {
+        let client = client.clone();
+
+        future::loop_fn(self, move |mut writer| {
+            let len = cmp::min(writer.buffer.len(), SEGMENT_SIZE);
+            if len == 0 || (len < SEGMENT_SIZE && !all) {
+                return ok!(Loop::Break(writer));
+            }
+
+            let content: Vec<_> = writer.buffer.drain(..len).collect();
+            let hash = sha3_256(&content);
+
+            put_content(&client, &content, writer.encryption_key.clone())
+                .map(move |name| {
+                    writer.manifest.segments.push(BlobSegment {
+                        name,
+                        size: content.len() as u64,
+                        hash,
+                    });
+                    Loop::Continue(writer)
+                })
+                .into_box()
+        }).into_box()
+    }
+}
+
+fn put_content<T: 'static>(
+    client: &Client<T>,
+    content: &[u8],
+    encryption_key: Option<shared_secretbox::Key>,
+) -> Box<CoreFuture<XorName>> {
+    let client2 = client.clone();
+
+    immutable_data::create(client, content, encryption_key)
+        .and_then(move |data| {
+            let name = *data.name();
+            client2.put_idata(data).map(move |_| name)
+        })
+        .into_box()
+}
+
+fn read_segment<T: 'static>(
+    client: &Client<T>,
+    segment: &BlobSegment,
+    decryption_key: Option<shared_secretbox::Key>,
+) -> Box<CoreFuture<Vec<u8>>> {
+    let hash = segment.hash;
+
+    immutable_data::get_value(client, &segment.name, decryption_key)
+        .and_then(move |content| if sha3_256(&content) == hash {
+            Ok(content)
+        } else {
+            Err(CoreError::ReceivedUnexpectedData)
+        })
+        .into_box()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{self, Rng};
+    use utils::test_utils::random_client;
+
+    // Small content is stored inline and larger content self-encrypted, and
+    // both are read back the same way.
+    #[test]
+    fn put_and_get_blob() {
+        let small = vec![1u8; INLINE_MAX_SIZE];
+        let large = vec![2u8; INLINE_MAX_SIZE + 1];
+        let key = shared_secretbox::gen_key();
+
+        random_client(move |client| {
+            let client2 = client.clone();
+            let client3 = client.clone();
+            let key2 = key.clone();
+            let key3 = key.clone();
+
+            put_blob(client, &small, Some(key.clone()))
+                .join(put_blob(client, &large, Some(key)))
+                .and_then(move |(small_address, large_address)| {
+                    match (small_address, large_address) {
+                        (BlobAddress::Inline(_), BlobAddress::SelfEncrypted(_)) => (),
+                        addresses => panic!("Unexpected {:?}", addresses),
+                    }
+
+                    get_blob(&client2, &small_address, Some(key2))
+                        .join(get_blob(&client3, &large_address, Some(key3)))
+                        .map(move |(small2, large2)| {
+                            assert_eq!(small2, small);
+                            assert_eq!(large2, large);
+                        })
+                })
+        });
+    }
+
+    // Interrupted upload is resumed, and ranges spanning several segments are
+    // read back.
+    #[test]
+    fn resumable_upload_and_random_access() {
+        let mut rng = rand::thread_rng();
+        let content: Vec<u8> = rng.gen_iter().take(SEGMENT_SIZE * 2 + 100).collect();
+        let content2 = content.clone();
+
+        let key = shared_secretbox::gen_key();
+        let key2 = key.clone();
+
+        // Upload the first segment and a bit more, then "crash".
+        let partial = random_client(move |client| {
+            BlobWriter::new(Some(key2))
+                .write(client, &content2[..SEGMENT_SIZE + 10])
+                .map(|writer| writer.manifest().clone())
+        });
+        assert_eq!(partial.segments().len(), 1);
+        assert_eq!(partial.size(), SEGMENT_SIZE as u64);
+
+        random_client(move |client| {
+            let client2 = client.clone();
+            let client3 = client.clone();
+            let client4 = client.clone();
+            let client5 = client.clone();
+            let key2 = key.clone();
+            let key3 = key.clone();
+            let offset = partial.size() as usize;
+
+            let write = BlobWriter::resume(partial, Some(key)).write(client, &content[offset..]);
+
+            write
+                .and_then(move |writer| writer.close(&client2))
+                .and_then(move |manifest| {
+                    assert_eq!(manifest.segments().len(), 3);
+                    assert_eq!(manifest.size(), content.len() as u64);
+                    manifest.put(&client3, Some(key2)).map(move |name| (name, content))
+                })
+                .and_then(move |(name, content)| {
+                    let get = BlobManifest::get(&client4, name, Some(key3.clone()));
+                    get.map(move |manifest| (manifest, key3, content))
+                })
+                .and_then(move |(manifest, key, content)| {
+                    let start = SEGMENT_SIZE as u64 - 5;
+                    manifest
+                        .read(&client5, start, SEGMENT_SIZE as u64 + 10, Some(key))
+                        .map(move |read| {
+                            let start = start as usize;
+                            assert_eq!(&read[..], &content[start..start + SEGMENT_SIZE + 10]);
+                        })
+                })
+        });
+    }
+}
@@ -17,6 +17,7 @@
 
 use DIR_TAG;
 use client::MDataInfo;
+use config::{self, Kdf};
 use crypto::{shared_box, shared_secretbox, shared_sign};
 use errors::CoreError;
 use maidsafe_utilities::serialisation::{deserialise, serialise};
@@ -24,6 +25,16 @@ use routing::{FullId, XOR_NAME_LEN, XorName};
 use rust_sodium::crypto::{box_, pwhash, secretbox, sign};
 use rust_sodium::crypto::sign::Seed;
 use tiny_keccak::sha3_256;
+use utils;
+
+/// Version of the account backup format produced by `Account::export`.
+const ACCOUNT_BACKUP_VERSION: u64 = 1;
+/// Current version of the serialised `Account` layout. Changing the layout
+/// requires bumping it and registering a migration from the previous version
+/// in `MIGRATIONS`.
+pub const ACCOUNT_VERSION: u64 = 1;
+/// Version of the header produced by `Account::encrypt`.
+const ENCRYPTED_ACCOUNT_VERSION: u64 = 1;
 
 /// Representing the User Account information on the network
 #[derive(Debug, PartialEq, Deserialize, Serialize)]
@@ -52,23 +63,113 @@ impl Account {
     }
 
     /// Symmetric encryption of Account using User's credentials.
-    /// Credentials are passed through key-derivation-function first
+    /// Credentials are passed through the key-derivation-function selected by
+    /// the current `CoreConfig` first.
     pub fn encrypt(&self, password: &[u8], pin: &[u8]) -> Result<Vec<u8>, CoreError> {
-        let serialised_self = serialise(self)?;
-        let (key, nonce) = Self::generate_crypto_keys(password, pin)?;
+        self.encrypt_with_kdf(password, pin, config::get().get_kdf())
+    }
+
+    /// Symmetric encryption of Account using User's credentials, passed
+    /// through the given key-derivation-function first. The function and its
+    /// parameters are stored alongside the ciphertext.
+    pub fn encrypt_with_kdf(
+        &self,
+        password: &[u8],
+        pin: &[u8],
+        kdf: Kdf,
+    ) -> Result<Vec<u8>, CoreError> {
+        let serialised_self = self.serialise_versioned()?;
+        let (key, nonce) = Self::generate_crypto_keys_with(password, pin, kdf)?;
+
+        let encrypted = EncryptedAccount {
+            version: ENCRYPTED_ACCOUNT_VERSION,
+            kdf,
+            ciphertext: secretbox::seal(&serialised_self, &nonce, &key),
+        };
 
-        Ok(secretbox::seal(&serialised_self, &nonce, &key))
+        Ok(serialise(&encrypted)?)
     }
 
     /// Symmetric decryption of Account using User's credentials.
-    /// Credentials are passed through key-derivation-function first
+    /// Credentials are passed through the key-derivation-function recorded
+    /// when encrypting. Packets encrypted before the function was recorded are
+    /// decrypted using the interactive scrypt parameters.
     pub fn decrypt(encrypted_self: &[u8], password: &[u8], pin: &[u8]) -> Result<Self, CoreError> {
+        if let Ok(encrypted) = deserialise::<EncryptedAccount>(encrypted_self) {
+            if encrypted.version != ENCRYPTED_ACCOUNT_VERSION {
+                return Err(CoreError::Unexpected(format!(
+                    "Unsupported encrypted account version: {}",
+                    encrypted.version
+                )));
+            }
+
+            let (key, nonce) = Self::generate_crypto_keys_with(password, pin, encrypted.kdf)?;
+            let decrypted_self = secretbox::open(&encrypted.ciphertext, &nonce, &key)
+                .map_err(|_| CoreError::SymmetricDecipherFailure)?;
+
+            return Self::deserialise_versioned(&decrypted_self);
+        }
+
         let (key, nonce) = Self::generate_crypto_keys(password, pin)?;
         let decrypted_self = secretbox::open(encrypted_self, &nonce, &key).map_err(|_| {
             CoreError::SymmetricDecipherFailure
         })?;
 
-        Ok(deserialise(&decrypted_self)?)
+        Self::deserialise_versioned(&decrypted_self)
+    }
+
+    /// Serialise the Account into a versioned backup, encrypted with a key derived from
+    /// `password` and a random salt.
+    pub fn export(&self, password: &[u8]) -> Result<Vec<u8>, CoreError> {
+        let salt = utils::generate_secret_vector(pwhash::SALTBYTES)?;
+        let key = Self::generate_backup_key(password, &salt)?;
+        let nonce = secretbox::gen_nonce();
+
+        let backup = AccountBackup {
+            version: ACCOUNT_BACKUP_VERSION,
+            salt,
+            nonce,
+            ciphertext: secretbox::seal(&self.serialise_versioned()?, &nonce, &key),
+        };
+
+        Ok(serialise(&backup)?)
+    }
+
+    /// Restore the Account from a backup produced by `export`.
+    pub fn import(backup: &[u8], password: &[u8]) -> Result<Self, CoreError> {
+        let backup: AccountBackup = deserialise(backup)?;
+        if backup.version != ACCOUNT_BACKUP_VERSION {
+            return Err(CoreError::Unexpected(format!(
+                "Unsupported account backup version: {}",
+                backup.version
+            )));
+        }
+
+        let key = Self::generate_backup_key(password, &backup.salt)?;
+        let decrypted_self = secretbox::open(&backup.ciphertext, &backup.nonce, &key)
+            .map_err(|_| CoreError::SymmetricDecipherFailure)?;
+
+        Self::deserialise_versioned(&decrypted_self)
+    }
+
+    /// Serialise the Account tagged with `ACCOUNT_VERSION`.
+    fn serialise_versioned(&self) -> Result<Vec<u8>, CoreError> {
+        Ok(serialise(&VersionedAccount {
+            version: ACCOUNT_VERSION,
+            content: serialise(self)?,
+        })?)
+    }
+
+    /// Deserialise the Account serialised by any earlier version, migrating it
+    /// to the current layout. Content without a version tag is treated as
+    /// version 0.
+    fn deserialise_versioned(serialised: &[u8]) -> Result<Self, CoreError> {
+        let (version, content) = match deserialise::<VersionedAccount>(serialised) {
+            Ok(versioned) => (versioned.version, versioned.content),
+            Err(_) => (0, serialised.to_vec()),
+        };
+
+        Ok(deserialise(&migrate(version, content)?)?)
     }
 
     /// Generate User's Identity for the network using supplied credentials in
@@ -83,9 +184,17 @@ impl Account {
     fn generate_crypto_keys(
         password: &[u8],
         pin: &[u8],
+    ) -> Result<(secretbox::Key, secretbox::Nonce), CoreError> {
+        Self::generate_crypto_keys_with(password, pin, Kdf::scrypt_interactive())
+    }
+
+    fn generate_crypto_keys_with(
+        password: &[u8],
+        pin: &[u8],
+        kdf: Kdf,
     ) -> Result<(secretbox::Key, secretbox::Nonce), CoreError> {
         let mut output = [0; secretbox::KEYBYTES + secretbox::NONCEBYTES];
-        Self::derive_key(&mut output[..], password, pin)?;
+        Self::derive_key_with(&mut output[..], password, pin, kdf)?;
 
         // OK to unwrap here, as we guaranteed the slices have the correct length.
         let key = unwrap!(secretbox::Key::from_slice(&output[..secretbox::KEYBYTES]));
@@ -94,7 +203,23 @@ impl Account {
         Ok((key, nonce))
     }
 
+    fn generate_backup_key(password: &[u8], salt: &[u8]) -> Result<secretbox::Key, CoreError> {
+        let mut output = [0; secretbox::KEYBYTES];
+        Self::derive_key(&mut output[..], password, salt)?;
+
+        Ok(secretbox::Key(output))
+    }
+
     fn derive_key(output: &mut [u8], input: &[u8], user_salt: &[u8]) -> Result<(), CoreError> {
+        Self::derive_key_with(output, input, user_salt, Kdf::scrypt_interactive())
+    }
+
+    fn derive_key_with(
+        output: &mut [u8],
+        input: &[u8],
+        user_salt: &[u8],
+        kdf: Kdf,
+    ) -> Result<(), CoreError> {
         let mut salt = pwhash::Salt([0; pwhash::SALTBYTES]);
         {
             let pwhash::Salt(ref mut salt_bytes) = salt;
@@ -108,15 +233,72 @@ impl Account {
             }
         }
 
-        pwhash::derive_key(
-            output,
-            input,
-            &salt,
-            pwhash::OPSLIMIT_INTERACTIVE,
-            pwhash::MEMLIMIT_INTERACTIVE,
-        ).map(|_| ())
-            .map_err(|_| CoreError::UnsuccessfulPwHash)
+        match kdf {
+            Kdf::Scrypt {
+                ops_limit,
+                mem_limit,
+            } => {
+                pwhash::derive_key(
+                    output,
+                    input,
+                    &salt,
+                    pwhash::OpsLimit(ops_limit as usize),
+                    pwhash::MemLimit(mem_limit as usize),
+                ).map(|_| ())
+                    .map_err(|_| CoreError::UnsuccessfulPwHash)
+            }
+        }
+    }
+}
+
+/// Encrypted Account together with what's needed to decrypt it given the password.
+#[derive(Deserialize, Serialize)]
+struct AccountBackup {
+    version: u64,
+    salt: Vec<u8>,
+    nonce: secretbox::Nonce,
+    ciphertext: Vec<u8>,
+}
+
+/// Serialised Account tagged with the version of its layout.
+#[derive(Deserialize, Serialize)]
+struct VersionedAccount {
+    version: u64,
+    content: Vec<u8>,
+}
+
+// Migrations of the serialised Account, the one at index `n` converting the
+// layout of version `n` to that of version `n + 1`.
+const MIGRATIONS: &'static [fn(Vec<u8>) -> Result<Vec<u8>, CoreError>] = &[migrate_v0_to_v1];
+
+// Upgrade the serialised Account of the given version to `ACCOUNT_VERSION`.
+fn migrate(version: u64, mut content: Vec<u8>) -> Result<Vec<u8>, CoreError> {
+    if version > ACCOUNT_VERSION {
+        return Err(CoreError::Unexpected(
+            format!("Unsupported account version: {}", version),
+        ));
+    }
+
+    for migration in &MIGRATIONS[version as usize..] {
+        content = migration(content)?;
     }
+
+    Ok(content)
+}
+
+// Version 0 is the layout serialised before the version tag was introduced.
+// Adding the tag didn't change the layout itself.
+fn migrate_v0_to_v1(content: Vec<u8>) -> Result<Vec<u8>, CoreError> {
+    Ok(content)
+}
+
+/// Encrypted Account together with the key-derivation-function its key was
+/// derived with.
+#[derive(Deserialize, Serialize)]
+struct EncryptedAccount {
+    version: u64,
+    kdf: Kdf,
+    ciphertext: Vec<u8>,
 }
 
 /// Client signing and encryption keypairs
@@ -172,6 +354,7 @@ impl Into<FullId> for ClientKeys {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use errors::CoreError;
     use maidsafe_utilities::serialisation::{deserialise, serialise};
     use std::u32;
 
@@ -249,6 +432,94 @@ mod tests {
         assert_eq!(decoded, account);
     }
 
+    // Test every version up to the current one has a migration registered.
+    #[test]
+    fn migrations_registered() {
+        assert_eq!(MIGRATIONS.len() as u64, ACCOUNT_VERSION);
+    }
+
+    // Test accounts serialised by earlier versions still load.
+    #[test]
+    fn versioned_serialisation() {
+        let account = unwrap!(Account::new(ClientKeys::new(None)));
+
+        let serialised = unwrap!(account.serialise_versioned());
+        assert_eq!(unwrap!(Account::deserialise_versioned(&serialised)), account);
+
+        // Fixture of the layout without the version tag.
+        let untagged = unwrap!(serialise(&account));
+        assert_eq!(unwrap!(Account::deserialise_versioned(&untagged)), account);
+
+        let future = unwrap!(serialise(&VersionedAccount {
+            version: ACCOUNT_VERSION + 1,
+            content: untagged,
+        }));
+        match Account::deserialise_versioned(&future) {
+            Err(CoreError::Unexpected(_)) => (),
+            x => panic!("Unexpected {:?}", x),
+        }
+
+        // Account packet encrypted by the version without the version tag or
+        // the KDF header. The keys are filled with constant bytes, so the
+        // decrypted content can be checked.
+        let legacy = include_bytes!("../../test_data/encrypted_account_v0");
+        let account = unwrap!(Account::decrypt(legacy, b"impossible to guess", b"1000"));
+
+        assert_eq!(account.maid_keys.sign_pk, sign::PublicKey([1; 32]));
+        assert_eq!(
+            account.maid_keys.sign_sk,
+            shared_sign::SecretKey::from_raw(&[2; 64])
+        );
+        assert_eq!(account.maid_keys.enc_pk, box_::PublicKey([3; 32]));
+        assert_eq!(
+            account.maid_keys.enc_sk,
+            shared_box::SecretKey::from_raw(&[4; 32])
+        );
+        assert_eq!(
+            account.maid_keys.enc_key,
+            shared_secretbox::Key::from_raw(&[5; 32])
+        );
+
+        let access_container = MDataInfo::new_private(
+            XorName([6; 32]),
+            DIR_TAG,
+            (
+                shared_secretbox::Key::from_raw(&[7; 32]),
+                secretbox::Nonce([8; 24]),
+            ),
+        );
+        let config_root = MDataInfo::new_private(
+            XorName([9; 32]),
+            DIR_TAG,
+            (
+                shared_secretbox::Key::from_raw(&[10; 32]),
+                secretbox::Nonce([11; 24]),
+            ),
+        );
+        assert_eq!(account.access_container, access_container);
+        assert_eq!(account.config_root, config_root);
+        assert!(account.root_dirs_created);
+    }
+
+    // Test exporting and importing account backups.
+    #[test]
+    fn backup() {
+        let account = unwrap!(Account::new(ClientKeys::new(None)));
+        let password = b"impossible to guess";
+
+        let backup1 = unwrap!(account.export(password));
+        let backup2 = unwrap!(account.export(password));
+        assert_ne!(backup1, backup2);
+
+        assert_eq!(unwrap!(Account::import(&backup1, password)), account);
+        assert_eq!(unwrap!(Account::import(&backup2, password)), account);
+
+        match Account::import(&backup1, b"wrong password") {
+            Err(CoreError::SymmetricDecipherFailure) => (),
+            x => panic!("Unexpected {:?}", x),
+        }
+    }
+
     // Test encryption and decryption of accounts.
     #[test]
     fn encryption() {
@@ -265,4 +536,31 @@ mod tests {
         let decrypted = unwrap!(Account::decrypt(&encrypted, password, pin));
         assert_eq!(account, decrypted);
     }
+
+    // Test accounts encrypted with a custom work factor, or before the
+    // key-derivation-function was stored with the packet, still decrypt.
+    #[test]
+    fn encryption_kdf() {
+        let account = unwrap!(Account::new(ClientKeys::new(None)));
+
+        let password = b"impossible to guess";
+        let pin = b"1000";
+
+        let kdf = Kdf::Scrypt {
+            ops_limit: 2 * pwhash::OPSLIMIT_INTERACTIVE.0 as u64,
+            mem_limit: 2 * pwhash::MEMLIMIT_INTERACTIVE.0 as u64,
+        };
+        let encrypted = unwrap!(account.encrypt_with_kdf(password, pin, kdf));
+        assert_ne!(encrypted, unwrap!(account.encrypt(password, pin)));
+        assert_eq!(unwrap!(Account::decrypt(&encrypted, password, pin)), account);
+
+        match Account::decrypt(&encrypted, b"wrong password", pin) {
+            Err(CoreError::SymmetricDecipherFailure) => (),
+            x => panic!("Unexpected {:?}", x),
+        }
+
+        let (key, nonce) = unwrap!(Account::generate_crypto_keys(password, pin));
+        let legacy = secretbox::seal(&unwrap!(serialise(&account)), &nonce, &key);
+        assert_eq!(unwrap!(Account::decrypt(&legacy, password, pin)), account);
+    }
 }
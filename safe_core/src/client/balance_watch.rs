@@ -0,0 +1,111 @@
+// Copyright 2017 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under (1) the MaidSafe.net Commercial License,
+// version 1.0 or later, or (2) The General Public License (GPL), version 3, depending on which
+// licence you accepted on initial access to the Software (the "Licences").
+//
+// By contributing code to the SAFE Network Software, or to this project generally, you agree to be
+// bound by the terms of the MaidSafe Contributor Agreement.  This, along with the Licenses can be
+// found in the root directory of this project at LICENSE, COPYING and CONTRIBUTOR.
+//
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.
+//
+// Please review the Licences for the specific language governing permissions and limitations
+// relating to use of the SAFE Network Software.
+
+// Number of mutations after which the balance is refreshed even if it's still
+// far from the threshold.
+const REFRESH_INTERVAL: u64 = 100;
+
+// Watches the mutation balance of the account, so a warning can be raised
+// before the mutations start failing. The balance is fetched from the network
+// only occasionally, and estimated from the number of mutations done in
+// between. It's refreshed more eagerly as the estimate gets closer to the
+// threshold.
+#[derive(Debug)]
+pub struct BalanceWatch {
+    threshold: u64,
+    estimate: Option<u64>,
+    since_refresh: u64,
+    warned: bool,
+}
+
+impl BalanceWatch {
+    pub fn new(threshold: u64) -> Self {
+        BalanceWatch {
+            threshold,
+            estimate: None,
+            since_refresh: 0,
+            warned: false,
+        }
+    }
+
+    // Record a successful mutation. Returns `true` if the balance should be
+    // refreshed from the network.
+    pub fn mutation_done(&mut self) -> bool {
+        self.since_refresh += 1;
+
+        match self.estimate {
+            Some(ref mut estimate) => {
+                *estimate = estimate.saturating_sub(1);
+                *estimate <= self.threshold.saturating_mul(2) ||
+                    self.since_refresh >= REFRESH_INTERVAL
+            }
+            None => true,
+        }
+    }
+
+    // Update the balance with the value fetched from the network. Returns the
+    // number of available mutations if the warning should be raised. The
+    // warning is raised only once until the balance gets back above the
+    // threshold.
+    pub fn refreshed(&mut self, mutations_available: u64) -> Option<u64> {
+        self.estimate = Some(mutations_available);
+        self.since_refresh = 0;
+
+        if mutations_available >= self.threshold {
+            self.warned = false;
+            None
+        } else if self.warned {
+            None
+        } else {
+            self.warned = true;
+            Some(mutations_available)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // The balance is refreshed more often near the threshold, and the warning
+    // is raised once per crossing.
+    #[test]
+    fn watermark() {
+        let mut watch = BalanceWatch::new(10);
+
+        // Unknown balance is fetched right away.
+        assert!(watch.mutation_done());
+        assert_eq!(watch.refreshed(1000), None);
+
+        // Far from the threshold, refreshes are only periodic.
+        for _ in 0..REFRESH_INTERVAL - 1 {
+            assert!(!watch.mutation_done());
+        }
+        assert!(watch.mutation_done());
+
+        // Near the threshold, every mutation triggers a refresh.
+        assert_eq!(watch.refreshed(21), None);
+        assert!(watch.mutation_done());
+
+        assert_eq!(watch.refreshed(9), Some(9));
+        assert_eq!(watch.refreshed(8), None);
+
+        // Topping up the balance re-arms the warning.
+        assert_eq!(watch.refreshed(500), None);
+        assert_eq!(watch.refreshed(5), Some(5));
+    }
+}
@@ -0,0 +1,210 @@
+// Copyright 2017 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under (1) the MaidSafe.net Commercial License,
+// version 1.0 or later, or (2) The General Public License (GPL), version 3, depending on which
+// licence you accepted on initial access to the Software (the "Licences").
+//
+// By contributing code to the SAFE Network Software, or to this project generally, you agree to be
+// bound by the terms of the MaidSafe Contributor Agreement.  This, along with the Licenses can be
+// found in the root directory of this project at LICENSE, COPYING and CONTRIBUTOR.
+//
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.
+//
+// Please review the Licences for the specific language governing permissions and limitations
+// relating to use of the SAFE Network Software.
+
+use super::Client;
+use errors::CoreError;
+use event_loop::CoreFuture;
+use futures::Future;
+use futures::future;
+use routing::{ImmutableData, MutableData, Value, XorName};
+use std::collections::BTreeMap;
+use utils::FutureExt;
+
+/// Response to a request in a batch.
+#[derive(Debug)]
+pub enum BatchResponse {
+    /// Response to `Batch::get_idata`.
+    IData(ImmutableData),
+    /// Response to `Batch::get_mdata_shell`.
+    MDataShell(MutableData),
+    /// Response to `Batch::get_mdata_version`.
+    MDataVersion(u64),
+    /// Response to `Batch::list_mdata_entries`.
+    MDataEntries(BTreeMap<Vec<u8>, Value>),
+    /// Response to `Batch::get_mdata_value`.
+    MDataValue(Value),
+}
+
+/// Requests pipelined by `Client::batch`. Every request is sent as soon as
+/// it's added, without waiting for the responses to the previous ones, so
+/// the batch takes about one round trip instead of one per request.
+pub struct Batch<T> {
+    client: Client<T>,
+    requests: Vec<Box<CoreFuture<Result<BatchResponse, CoreError>>>>,
+}
+
+impl<T: 'static> Batch<T> {
+    /// Create an empty batch.
+    pub fn new(client: &Client<T>) -> Self {
+        Batch {
+            client: client.clone(),
+            requests: Vec::new(),
+        }
+    }
+
+    /// Get `ImmutableData` (see `Client::get_idata`).
+    pub fn get_idata(&mut self, name: XorName) -> &mut Self {
+        let fut = self.client.get_idata(name).map(BatchResponse::IData);
+        self.push(fut)
+    }
+
+    /// Get a `MutableData` shell (see `Client::get_mdata_shell`).
+    pub fn get_mdata_shell(&mut self, name: XorName, tag: u64) -> &mut Self {
+        let fut = self.client.get_mdata_shell(name, tag).map(
+            BatchResponse::MDataShell,
+        );
+        self.push(fut)
+    }
+
+    /// Get the version of `MutableData` (see `Client::get_mdata_version`).
+    pub fn get_mdata_version(&mut self, name: XorName, tag: u64) -> &mut Self {
+        let fut = self.client.get_mdata_version(name, tag).map(
+            BatchResponse::MDataVersion,
+        );
+        self.push(fut)
+    }
+
+    /// List the entries of `MutableData` (see `Client::list_mdata_entries`).
+    pub fn list_mdata_entries(&mut self, name: XorName, tag: u64) -> &mut Self {
+        let fut = self.client.list_mdata_entries(name, tag).map(
+            BatchResponse::MDataEntries,
+        );
+        self.push(fut)
+    }
+
+    /// Get a single entry of `MutableData` (see `Client::get_mdata_value`).
+    pub fn get_mdata_value(&mut self, name: XorName, tag: u64, key: Vec<u8>) -> &mut Self {
+        let fut = self.client.get_mdata_value(name, tag, key).map(
+            BatchResponse::MDataValue,
+        );
+        self.push(fut)
+    }
+
+    /// Number of requests in the batch.
+    pub fn len(&self) -> usize {
+        self.requests.len()
+    }
+
+    /// Returns `true` if there are no requests in the batch.
+    pub fn is_empty(&self) -> bool {
+        self.requests.is_empty()
+    }
+
+    /// Wait for the responses to all the requests, returned in the order the
+    /// requests were added. A failed request doesn't fail the whole batch.
+    pub fn responses(self) -> Box<CoreFuture<Vec<Result<BatchResponse, CoreError>>>> {
+        future::join_all(self.requests).into_box()
+    }
+
+    fn push<F>(&mut self, fut: F) -> &mut Self
+    where
+        F: Future<Item = BatchResponse, Error = CoreError> + 'static,
+    {
+        self.requests.push(fut.then(Ok).into_box());
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::Future;
+    use routing::EntryActions;
+    use utils::test_utils::random_client;
+
+    // Test a batch of requests to existing and missing data.
+    #[test]
+    fn batch() {
+        random_client(|client| {
+            let client2 = client.clone();
+            let client3 = client.clone();
+
+            let idata = ImmutableData::new(vec![1, 2, 3]);
+            let idata_name = *idata.name();
+            let missing_name = new_name();
+
+            let tag = 15_001;
+            let mdata_name = new_name();
+            let owners = btree_set![unwrap!(client.owner_key())];
+            let entries = btree_map![
+                b"key".to_vec() => Value { content: b"value".to_vec(), entry_version: 0 }
+            ];
+            let mdata = unwrap!(MutableData::new(
+                mdata_name,
+                tag,
+                Default::default(),
+                entries,
+                owners,
+            ));
+
+            client
+                .put_idata(idata.clone())
+                .join(client.put_mdata(mdata))
+                .then(move |res| {
+                    unwrap!(res);
+                    let actions = EntryActions::new().ins(b"key2".to_vec(), vec![2], 0);
+                    client2.mutate_mdata_entries(mdata_name, tag, actions.into())
+                })
+                .then(move |res| {
+                    unwrap!(res);
+
+                    client3.batch(|batch| {
+                        let _ = batch
+                            .get_idata(idata_name)
+                            .get_idata(missing_name)
+                            .get_mdata_version(mdata_name, tag)
+                            .get_mdata_value(mdata_name, tag, b"key".to_vec())
+                            .list_mdata_entries(mdata_name, tag);
+                    })
+                })
+                .map(move |responses| {
+                    assert_eq!(responses.len(), 5);
+                    let mut responses = responses.into_iter();
+
+                    match responses.next() {
+                        Some(Ok(BatchResponse::IData(data))) => assert_eq!(data, idata),
+                        x => panic!("Unexpected {:?}", x),
+                    }
+                    match responses.next() {
+                        Some(Err(_)) => (),
+                        x => panic!("Unexpected {:?}", x),
+                    }
+                    match responses.next() {
+                        Some(Ok(BatchResponse::MDataVersion(version))) => assert_eq!(version, 0),
+                        x => panic!("Unexpected {:?}", x),
+                    }
+                    match responses.next() {
+                        Some(Ok(BatchResponse::MDataValue(value))) => {
+                            assert_eq!(value.content, b"value".to_vec())
+                        }
+                        x => panic!("Unexpected {:?}", x),
+                    }
+                    match responses.next() {
+                        Some(Ok(BatchResponse::MDataEntries(entries))) => {
+                            assert_eq!(entries.len(), 2)
+                        }
+                        x => panic!("Unexpected {:?}", x),
+                    }
+                })
+                .map_err(|err| panic!("{:?}", err))
+        });
+    }
+
+    fn new_name() -> XorName {
+        ::rand::random()
+    }
+}
@@ -0,0 +1,51 @@
+// Copyright 2017 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under (1) the MaidSafe.net Commercial License,
+// version 1.0 or later, or (2) The General Public License (GPL), version 3, depending on which
+// licence you accepted on initial access to the Software (the "Licences").
+//
+// By contributing code to the SAFE Network Software, or to this project generally, you agree to be
+// bound by the terms of the MaidSafe Contributor Agreement.  This, along with the Licenses can be
+// found in the root directory of this project at LICENSE, COPYING and CONTRIBUTOR.
+//
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.
+//
+// Please review the Licences for the specific language governing permissions and limitations
+// relating to use of the SAFE Network Software.
+
+use routing::XorName;
+use std::collections::VecDeque;
+
+/// Paged listing of the entries of a `MutableData`, started with
+/// `Client::list_mdata_entry_pages`. The keys are listed once, when the listing
+/// starts, and every page fetched with `Client::next_mdata_entry_page` then
+/// only fetches the values of its own keys.
+#[derive(Clone, Debug, PartialEq)]
+pub struct MDataEntryPages {
+    /// Name of the data.
+    pub name: XorName,
+    /// Type tag of the data.
+    pub tag: u64,
+    /// Keys of the entries not fetched yet, in order.
+    pub keys: VecDeque<Vec<u8>>,
+}
+
+impl MDataEntryPages {
+    /// Whether all the pages have been fetched.
+    pub fn is_done(&self) -> bool {
+        self.keys.is_empty()
+    }
+
+    /// Key the next page starts at, unless all the pages have been fetched.
+    pub fn next_key(&self) -> Option<&Vec<u8>> {
+        self.keys.front()
+    }
+
+    /// Remove and return the keys of the next page of up to `limit` entries.
+    pub fn take_page(&mut self, limit: usize) -> Vec<Vec<u8>> {
+        let len = limit.min(self.keys.len());
+        self.keys.drain(..len).collect()
+    }
+}
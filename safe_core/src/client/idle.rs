@@ -0,0 +1,141 @@
+// Copyright 2017 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under (1) the MaidSafe.net Commercial License,
+// version 1.0 or later, or (2) The General Public License (GPL), version 3, depending on which
+// licence you accepted on initial access to the Software (the "Licences").
+//
+// By contributing code to the SAFE Network Software, or to this project generally, you agree to be
+// bound by the terms of the MaidSafe Contributor Agreement.  This, along with the Licenses can be
+// found in the root directory of this project at LICENSE, COPYING and CONTRIBUTOR.
+//
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.
+//
+// Please review the Licences for the specific language governing permissions and limitations
+// relating to use of the SAFE Network Software.
+
+use std::cmp;
+use std::time::{Duration, Instant};
+
+/// Idle handling of the client connection.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct IdleConfig {
+    /// Interval of the keep-alive pings (account info requests) sent while
+    /// the client is otherwise idle. `None` disables the keep-alive.
+    pub keep_alive: Option<Duration>,
+    /// Time without any request after which the connection is parked, to
+    /// free the resources held by it. The connection is transparently
+    /// resumed on the next request. `None` disables parking.
+    pub idle_timeout: Option<Duration>,
+}
+
+impl IdleConfig {
+    /// How often the idle state has to be checked, or `None` if there is
+    /// nothing to check.
+    pub fn check_interval(&self) -> Option<Duration> {
+        match (self.keep_alive, self.idle_timeout) {
+            (Some(keep_alive), Some(idle_timeout)) => Some(cmp::min(keep_alive, idle_timeout)),
+            (interval, None) | (None, interval) => interval,
+        }
+    }
+}
+
+// What the idle check decided to do.
+#[derive(Debug, PartialEq, Eq)]
+pub enum IdleAction {
+    None,
+    Ping,
+    Park,
+}
+
+#[derive(Debug)]
+pub struct IdleState {
+    pub config: IdleConfig,
+    pub parked: bool,
+    // Incremented on every config change, to stop the checks of the
+    // previous config.
+    pub generation: u64,
+    // Last request made by the user of the client.
+    last_activity: Instant,
+    // Last request sent to the network, including the keep-alive pings.
+    last_request: Instant,
+}
+
+impl IdleState {
+    pub fn new() -> Self {
+        let now = Instant::now();
+        IdleState {
+            config: IdleConfig::default(),
+            parked: false,
+            generation: 0,
+            last_activity: now,
+            last_request: now,
+        }
+    }
+
+    // Record a request made by the user.
+    pub fn activity(&mut self, now: Instant) {
+        self.last_activity = now;
+        self.last_request = now;
+    }
+
+    // Decide what to do at `now`. `busy` is true if there are requests still
+    // waiting for the response.
+    pub fn check(&mut self, now: Instant, busy: bool) -> IdleAction {
+        if self.parked || busy {
+            return IdleAction::None;
+        }
+
+        if let Some(idle_timeout) = self.config.idle_timeout {
+            if now.duration_since(self.last_activity) >= idle_timeout {
+                self.parked = true;
+                return IdleAction::Park;
+            }
+        }
+
+        if let Some(keep_alive) = self.config.keep_alive {
+            if now.duration_since(self.last_request) >= keep_alive {
+                self.last_request = now;
+                return IdleAction::Ping;
+            }
+        }
+
+        IdleAction::None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Idle client is pinged periodically and then parked.
+    #[test]
+    fn ping_then_park() {
+        let mut state = IdleState::new();
+        state.config = IdleConfig {
+            keep_alive: Some(Duration::from_secs(10)),
+            idle_timeout: Some(Duration::from_secs(25)),
+        };
+        assert_eq!(state.config.check_interval(), Some(Duration::from_secs(10)));
+
+        let start = state.last_activity;
+        let at = |secs| start + Duration::from_secs(secs);
+
+        assert_eq!(state.check(at(5), false), IdleAction::None);
+        assert_eq!(state.check(at(10), false), IdleAction::Ping);
+        assert_eq!(state.check(at(15), false), IdleAction::None);
+        assert_eq!(state.check(at(20), false), IdleAction::Ping);
+
+        // Pending requests postpone the parking.
+        assert_eq!(state.check(at(25), true), IdleAction::None);
+        assert_eq!(state.check(at(25), false), IdleAction::Park);
+        assert_eq!(state.check(at(40), false), IdleAction::None);
+
+        // User activity resets the timers.
+        state.parked = false;
+        state.activity(at(40));
+        assert_eq!(state.check(at(45), false), IdleAction::None);
+        assert_eq!(state.check(at(50), false), IdleAction::Ping);
+    }
+}
@@ -201,6 +201,28 @@ pub fn decrypt_entries(
     Ok(output)
 }
 
+/// Decrypt entries using the `MDataInfo`, keeping the deleted ones. The
+/// content of the deleted entries is empty, so only their keys are decrypted.
+pub fn decrypt_entries_with_deleted(
+    info: &MDataInfo,
+    entries: &BTreeMap<Vec<u8>, Value>,
+) -> Result<BTreeMap<Vec<u8>, Value>, CoreError> {
+    let mut output = BTreeMap::new();
+
+    for (key, value) in entries {
+        let decrypted_key = info.decrypt(key)?;
+        let decrypted_value = if value.content.is_empty() {
+            value.clone()
+        } else {
+            decrypt_value(info, value)?
+        };
+
+        let _ = output.insert(decrypted_key, decrypted_value);
+    }
+
+    Ok(output)
+}
+
 /// Decrypt all keys using the `MDataInfo`.
 pub fn decrypt_keys(
     info: &MDataInfo,
@@ -0,0 +1,183 @@
+// Copyright 2017 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under (1) the MaidSafe.net Commercial License,
+// version 1.0 or later, or (2) The General Public License (GPL), version 3, depending on which
+// licence you accepted on initial access to the Software (the "Licences").
+//
+// By contributing code to the SAFE Network Software, or to this project generally, you agree to be
+// bound by the terms of the MaidSafe Contributor Agreement.  This, along with the Licenses can be
+// found in the root directory of this project at LICENSE, COPYING and CONTRIBUTOR.
+//
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.
+//
+// Please review the Licences for the specific language governing permissions and limitations
+// relating to use of the SAFE Network Software.
+
+//! BIP39-style mnemonic phrases encoding 256 bits of entropy as 24 words from the standard
+//! English word list, usable as a human-friendly backup of an account seed.
+
+use errors::CoreError;
+use rust_sodium::crypto::hash::sha256;
+use std::fmt::Write;
+use tiny_keccak::sha3_512;
+use utils;
+
+/// Number of words in a mnemonic phrase.
+pub const WORD_COUNT: usize = 24;
+/// Number of entropy bytes encoded by a mnemonic phrase.
+pub const ENTROPY_LEN: usize = 32;
+
+const BITS_PER_WORD: usize = 11;
+
+lazy_static! {
+    static ref WORDS: Vec<&'static str> = include_str!("mnemonic_words.txt").lines().collect();
+}
+
+/// Generates a new mnemonic phrase from fresh random entropy.
+pub fn generate() -> Result<String, CoreError> {
+    let entropy = utils::generate_secret_vector(ENTROPY_LEN)?;
+    from_entropy(&entropy)
+}
+
+/// Encodes `entropy` (exactly `ENTROPY_LEN` bytes) as a mnemonic phrase.
+pub fn from_entropy(entropy: &[u8]) -> Result<String, CoreError> {
+    if entropy.len() != ENTROPY_LEN {
+        return Err(CoreError::Unexpected(
+            format!("Mnemonic entropy must be {} bytes", ENTROPY_LEN),
+        ));
+    }
+
+    let mut bytes = entropy.to_vec();
+    bytes.push(checksum(entropy));
+
+    let words: Vec<&str> = (0..WORD_COUNT)
+        .map(|i| {
+            let index = (0..BITS_PER_WORD).fold(0, |index, j| {
+                let bit = i * BITS_PER_WORD + j;
+                (index << 1) | ((bytes[bit / 8] >> (7 - bit % 8)) & 1) as usize
+            });
+            WORDS[index]
+        })
+        .collect();
+
+    Ok(words.join(" "))
+}
+
+/// Decodes a mnemonic phrase back into its entropy, verifying the words and the checksum.
+pub fn to_entropy(mnemonic: &str) -> Result<Vec<u8>, CoreError> {
+    let words: Vec<&str> = mnemonic.split_whitespace().collect();
+    if words.len() != WORD_COUNT {
+        return Err(CoreError::Unexpected(
+            format!("Mnemonic must have {} words", WORD_COUNT),
+        ));
+    }
+
+    let mut bytes = [0u8; ENTROPY_LEN + 1];
+
+    for (i, word) in words.iter().enumerate() {
+        let index = WORDS.binary_search(word).map_err(|_| {
+            CoreError::Unexpected(format!("Unknown mnemonic word: {}", word))
+        })?;
+
+        for j in 0..BITS_PER_WORD {
+            if index & (1 << (BITS_PER_WORD - 1 - j)) != 0 {
+                let bit = i * BITS_PER_WORD + j;
+                bytes[bit / 8] |= 1 << (7 - bit % 8);
+            }
+        }
+    }
+
+    if checksum(&bytes[..ENTROPY_LEN]) != bytes[ENTROPY_LEN] {
+        return Err(CoreError::Unexpected(
+            "Invalid mnemonic checksum".to_string(),
+        ));
+    }
+
+    Ok(bytes[..ENTROPY_LEN].to_vec())
+}
+
+/// Returns `true` if `mnemonic` is a well-formed phrase with a valid checksum.
+pub fn validate(mnemonic: &str) -> bool {
+    to_entropy(mnemonic).is_ok()
+}
+
+/// Derives the account seed (as accepted by `Client::registered_with_seed`) from a mnemonic
+/// phrase.
+pub fn to_seed(mnemonic: &str) -> Result<String, CoreError> {
+    let entropy = to_entropy(mnemonic)?;
+    let mut seed = String::with_capacity(128);
+
+    for byte in sha3_512(&entropy).iter() {
+        let _ = write!(seed, "{:02x}", byte);
+    }
+
+    Ok(seed)
+}
+
+fn checksum(entropy: &[u8]) -> u8 {
+    sha256::hash(entropy).0[0]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Check the encoding against the reference BIP39 vectors.
+    #[test]
+    fn reference_vectors() {
+        let vectors = [
+            (
+                [0u8; ENTROPY_LEN],
+                "abandon abandon abandon abandon abandon abandon abandon abandon abandon \
+                 abandon abandon abandon abandon abandon abandon abandon abandon abandon \
+                 abandon abandon abandon abandon abandon art",
+            ),
+            (
+                [0x7f; ENTROPY_LEN],
+                "legal winner thank year wave sausage worth useful legal winner thank year \
+                 wave sausage worth useful legal winner thank year wave sausage worth title",
+            ),
+            (
+                [0x80; ENTROPY_LEN],
+                "letter advice cage absurd amount doctor acoustic avoid letter advice cage \
+                 absurd amount doctor acoustic avoid letter advice cage absurd amount doctor \
+                 acoustic bless",
+            ),
+            (
+                [0xff; ENTROPY_LEN],
+                "zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo \
+                 zoo zoo zoo zoo vote",
+            ),
+        ];
+
+        for &(ref entropy, phrase) in &vectors {
+            assert_eq!(unwrap!(from_entropy(entropy)), phrase);
+            assert_eq!(unwrap!(to_entropy(phrase)), entropy.to_vec());
+        }
+    }
+
+    // Generated phrases round-trip, and corrupted ones are rejected.
+    #[test]
+    fn generate_and_validate() {
+        assert_eq!(WORDS.len(), 2048);
+
+        let mnemonic = unwrap!(generate());
+        assert_eq!(mnemonic.split_whitespace().count(), WORD_COUNT);
+        assert!(validate(&mnemonic));
+        assert_eq!(unwrap!(to_seed(&mnemonic)), unwrap!(to_seed(&mnemonic)));
+
+        // Wrong checksum.
+        let zeros = vec!["abandon"; WORD_COUNT].join(" ");
+        assert!(!validate(&zeros));
+
+        // Unknown word.
+        let first = unwrap!(mnemonic.split_whitespace().next());
+        let unknown = mnemonic.replacen(first, "xyz", 1);
+        assert!(!validate(&unknown));
+
+        // Wrong number of words.
+        assert!(!validate("abandon abandon art"));
+    }
+}
@@ -75,9 +75,10 @@ impl Account {
         &self.auth_keys
     }
 
-    pub fn increment_mutations_counter(&mut self) {
+    // Record a mutation costing `cost` units of the balance.
+    pub fn charge_mutation(&mut self, cost: u64) {
         self.account_info.mutations_done += 1;
-        self.account_info.mutations_available -= 1;
+        self.account_info.mutations_available -= cost;
         self.version += 1;
     }
 
@@ -0,0 +1,134 @@
+// Copyright 2016 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under (1) the MaidSafe.net Commercial License,
+// version 1.0 or later, or (2) The General Public License (GPL), version 3, depending on which
+// licence you accepted on initial access to the Software (the "Licences").
+//
+// By contributing code to the SAFE Network Software, or to this project generally, you agree to be
+// bound by the terms of the MaidSafe Contributor Agreement.  This, along with the Licenses can be
+// found in the root directory of this project at LICENSE, COPYING and CONTRIBUTOR.
+//
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.
+//
+// Please review the Licences for the specific language governing permissions and limitations
+// relating to use of the SAFE Network Software.
+
+use super::latency::to_millis;
+use maidsafe_utilities::thread;
+use routing::Event;
+use std;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::Sender;
+use std::time::{Duration, Instant};
+
+const CHURN_THREAD_NAME: &'static str = "Mock routing churn";
+
+/// Churn of the mock network, as seen by the client.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct ChurnConfig {
+    /// Interval at which the network sends `Event::RestartRequired`, asking
+    /// the client to reconnect.
+    pub restart_interval: Option<Duration>,
+    /// Period and length of the windows during which the network doesn't
+    /// respond to any request. Each window closes a period.
+    pub unresponsive: Option<(Duration, Duration)>,
+}
+
+// Churn simulated for a single mock routing client. Stops when dropped.
+pub struct Churn {
+    config: ChurnConfig,
+    started: Instant,
+    stop: Arc<AtomicBool>,
+}
+
+impl Churn {
+    pub fn start(config: ChurnConfig, sender: Sender<Event>) -> Self {
+        let stop = Arc::new(AtomicBool::new(false));
+
+        if let Some(interval) = config.restart_interval {
+            let stop = Arc::clone(&stop);
+            let _ = thread::named(CHURN_THREAD_NAME, move || loop {
+                std::thread::sleep(interval);
+                if stop.load(Ordering::Relaxed) || sender.send(Event::RestartRequired).is_err() {
+                    break;
+                }
+            });
+        }
+
+        Churn {
+            config,
+            started: Instant::now(),
+            stop,
+        }
+    }
+
+    // Whether the network is currently in an unresponsive window.
+    pub fn unresponsive(&self) -> bool {
+        let (period, length) = match self.config.unresponsive {
+            Some((period, length)) => (to_millis(&period), to_millis(&length)),
+            None => return false,
+        };
+        if period == 0 {
+            return false;
+        }
+
+        let elapsed = to_millis(&self.started.elapsed());
+        elapsed % period + length >= period
+    }
+}
+
+impl Drop for Churn {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::mpsc;
+
+    #[test]
+    fn unresponsive_window() {
+        let (tx, _rx) = mpsc::channel();
+
+        let churn = Churn::start(
+            ChurnConfig {
+                restart_interval: None,
+                unresponsive: Some((Duration::from_millis(200), Duration::from_millis(100))),
+            },
+            tx.clone(),
+        );
+        assert!(!churn.unresponsive());
+        std::thread::sleep(Duration::from_millis(150));
+        assert!(churn.unresponsive());
+
+        let churn = Churn::start(ChurnConfig::default(), tx);
+        assert!(!churn.unresponsive());
+    }
+
+    #[test]
+    fn restart_required() {
+        let (tx, rx) = mpsc::channel();
+
+        let churn = Churn::start(
+            ChurnConfig {
+                restart_interval: Some(Duration::from_millis(50)),
+                unresponsive: None,
+            },
+            tx,
+        );
+
+        for _ in 0..2 {
+            match unwrap!(rx.recv_timeout(Duration::from_secs(10))) {
+                Event::RestartRequired => (),
+                event => panic!("Unexpected event {:?}", event),
+            }
+        }
+
+        drop(churn);
+    }
+}
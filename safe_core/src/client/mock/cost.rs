@@ -0,0 +1,72 @@
+// Copyright 2016 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under (1) the MaidSafe.net Commercial License,
+// version 1.0 or later, or (2) The General Public License (GPL), version 3, depending on which
+// licence you accepted on initial access to the Software (the "Licences").
+//
+// By contributing code to the SAFE Network Software, or to this project generally, you agree to be
+// bound by the terms of the MaidSafe Contributor Agreement.  This, along with the Licenses can be
+// found in the root directory of this project at LICENSE, COPYING and CONTRIBUTOR.
+//
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.
+//
+// Please review the Licences for the specific language governing permissions and limitations
+// relating to use of the SAFE Network Software.
+
+/// Cost of the mutations charged to the account by the mock vault, in units of
+/// `AccountInfo::mutations_available`. Once the account can't afford a
+/// mutation, it fails with `ClientError::LowBalance`. The default model
+/// charges one unit per mutation.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct CostModel {
+    /// Cost of putting `ImmutableData`, regardless of its size.
+    pub idata_put: u64,
+    /// Additional cost of putting `ImmutableData`, per started KiB of its size.
+    pub idata_put_per_kib: u64,
+    /// Cost of putting new `MutableData`.
+    pub mdata_put: u64,
+    /// Cost of mutating existing `MutableData` (its entries, permissions or
+    /// owners).
+    pub mdata_mutation: u64,
+}
+
+impl CostModel {
+    /// Cost of putting `ImmutableData` of the given size in bytes.
+    pub fn idata_put_cost(&self, size: usize) -> u64 {
+        let kibs = (size as u64 + 1023) / 1024;
+        self.idata_put + self.idata_put_per_kib * kibs
+    }
+}
+
+impl Default for CostModel {
+    fn default() -> Self {
+        CostModel {
+            idata_put: 1,
+            idata_put_per_kib: 0,
+            mdata_put: 1,
+            mdata_mutation: 1,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn idata_put_cost() {
+        let model = CostModel {
+            idata_put: 2,
+            idata_put_per_kib: 3,
+            ..CostModel::default()
+        };
+
+        assert_eq!(model.idata_put_cost(0), 2);
+        assert_eq!(model.idata_put_cost(1), 5);
+        assert_eq!(model.idata_put_cost(1024), 5);
+        assert_eq!(model.idata_put_cost(1025), 8);
+        assert_eq!(CostModel::default().idata_put_cost(1_000_000), 1);
+    }
+}
@@ -0,0 +1,134 @@
+// Copyright 2016 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under (1) the MaidSafe.net Commercial License,
+// version 1.0 or later, or (2) The General Public License (GPL), version 3, depending on which
+// licence you accepted on initial access to the Software (the "Licences").
+//
+// By contributing code to the SAFE Network Software, or to this project generally, you agree to be
+// bound by the terms of the MaidSafe Contributor Agreement.  This, along with the Licenses can be
+// found in the root directory of this project at LICENSE, COPYING and CONTRIBUTOR.
+//
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.
+//
+// Please review the Licences for the specific language governing permissions and limitations
+// relating to use of the SAFE Network Software.
+
+use super::latency::RequestKind;
+use super::rng;
+use routing::{ClientError, Response};
+
+/// Fault injected into the responses of the mock network.
+#[derive(Clone, Debug)]
+pub enum FaultKind {
+    /// The response is never delivered, as if it was lost by the network.
+    DropResponse,
+    /// The response is delivered the given number of milliseconds late.
+    DelayBy(u64),
+    /// The response fails with the given error. Note the request itself
+    /// still takes effect, as when the network loses only the success.
+    Error(ClientError),
+}
+
+/// Fault with the probability it's injected with, optionally limited to some
+/// kinds of requests.
+#[derive(Clone, Debug)]
+pub struct Fault {
+    kind: FaultKind,
+    probability: f64,
+    requests: Option<Vec<RequestKind>>,
+}
+
+impl Fault {
+    /// Create the fault injected with `probability` (0.0 to 1.0) into the
+    /// responses to the given kinds of requests, or all of them if `None`.
+    pub fn new(kind: FaultKind, probability: f64, requests: Option<Vec<RequestKind>>) -> Self {
+        Fault {
+            kind: kind,
+            probability: probability,
+            requests: requests,
+        }
+    }
+
+    // Decide whether to inject the fault into the response to the given kind
+    // of request.
+    fn triggers(&self, kind: RequestKind) -> bool {
+        if let Some(ref requests) = self.requests {
+            if !requests.contains(&kind) {
+                return false;
+            }
+        }
+
+        rng::random::<f64>() < self.probability
+    }
+}
+
+/// Apply the faults to the response to the given kind of request. Returns the
+/// response to deliver along with the extra delay, or `None` if the response
+/// is to be dropped.
+pub fn apply(
+    faults: &[Fault],
+    kind: RequestKind,
+    mut response: Response,
+) -> Option<(Response, u64)> {
+    let mut delay_ms = 0;
+
+    for fault in faults.iter().filter(|fault| fault.triggers(kind)) {
+        match fault.kind {
+            FaultKind::DropResponse => return None,
+            FaultKind::DelayBy(ms) => delay_ms += ms,
+            FaultKind::Error(ref err) => response = with_error(response, err.clone()),
+        }
+    }
+
+    Some((response, delay_ms))
+}
+
+// Replace the result of the response with the error.
+fn with_error(response: Response, err: ClientError) -> Response {
+    match response {
+        Response::GetAccountInfo { msg_id, .. } => {
+            Response::GetAccountInfo { res: Err(err), msg_id }
+        }
+        Response::PutIData { msg_id, .. } => Response::PutIData { res: Err(err), msg_id },
+        Response::GetIData { msg_id, .. } => Response::GetIData { res: Err(err), msg_id },
+        Response::PutMData { msg_id, .. } => Response::PutMData { res: Err(err), msg_id },
+        Response::GetMData { msg_id, .. } => Response::GetMData { res: Err(err), msg_id },
+        Response::GetMDataVersion { msg_id, .. } => {
+            Response::GetMDataVersion { res: Err(err), msg_id }
+        }
+        Response::GetMDataShell { msg_id, .. } => Response::GetMDataShell { res: Err(err), msg_id },
+        Response::ListMDataEntries { msg_id, .. } => {
+            Response::ListMDataEntries { res: Err(err), msg_id }
+        }
+        Response::ListMDataKeys { msg_id, .. } => Response::ListMDataKeys { res: Err(err), msg_id },
+        Response::ListMDataValues { msg_id, .. } => {
+            Response::ListMDataValues { res: Err(err), msg_id }
+        }
+        Response::GetMDataValue { msg_id, .. } => Response::GetMDataValue { res: Err(err), msg_id },
+        Response::MutateMDataEntries { msg_id, .. } => {
+            Response::MutateMDataEntries { res: Err(err), msg_id }
+        }
+        Response::ListMDataPermissions { msg_id, .. } => {
+            Response::ListMDataPermissions { res: Err(err), msg_id }
+        }
+        Response::ListMDataUserPermissions { msg_id, .. } => {
+            Response::ListMDataUserPermissions { res: Err(err), msg_id }
+        }
+        Response::SetMDataUserPermissions { msg_id, .. } => {
+            Response::SetMDataUserPermissions { res: Err(err), msg_id }
+        }
+        Response::DelMDataUserPermissions { msg_id, .. } => {
+            Response::DelMDataUserPermissions { res: Err(err), msg_id }
+        }
+        Response::ChangeMDataOwner { msg_id, .. } => {
+            Response::ChangeMDataOwner { res: Err(err), msg_id }
+        }
+        Response::ListAuthKeysAndVersion { msg_id, .. } => {
+            Response::ListAuthKeysAndVersion { res: Err(err), msg_id }
+        }
+        Response::InsAuthKey { msg_id, .. } => Response::InsAuthKey { res: Err(err), msg_id },
+        Response::DelAuthKey { msg_id, .. } => Response::DelAuthKey { res: Err(err), msg_id },
+    }
+}
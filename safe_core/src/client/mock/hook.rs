@@ -0,0 +1,254 @@
+// Copyright 2016 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under (1) the MaidSafe.net Commercial License,
+// version 1.0 or later, or (2) The General Public License (GPL), version 3, depending on which
+// licence you accepted on initial access to the Software (the "Licences").
+//
+// By contributing code to the SAFE Network Software, or to this project generally, you agree to be
+// bound by the terms of the MaidSafe Contributor Agreement.  This, along with the Licenses can be
+// found in the root directory of this project at LICENSE, COPYING and CONTRIBUTOR.
+//
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.
+//
+// Please review the Licences for the specific language governing permissions and limitations
+// relating to use of the SAFE Network Software.
+
+//! Request hooks scripted as ordered expectations, for building multi-step
+//! scenarios against the mock routing.
+
+use super::latency::RequestKind;
+use super::routing::Routing;
+use routing::{ClientError, Request, Response};
+use std::cell::RefCell;
+use std::collections::{HashMap, VecDeque};
+use std::rc::Rc;
+
+/// Builder of a request hook answering the requests according to ordered
+/// expectations. The expectations for each kind of request are consumed in the
+/// order they were added. Requests without a pending expectation are handled
+/// by the mock network as usual and captured as unmatched.
+#[derive(Default)]
+pub struct RequestHookBuilder {
+    expectations: HashMap<RequestKind, VecDeque<Expectation>>,
+}
+
+impl RequestHookBuilder {
+    /// Create a builder with no expectations.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Let the next `times` requests of the kind be handled as usual.
+    pub fn pass(self, kind: RequestKind, times: usize) -> Self {
+        self.expect(kind, times, Action::Pass)
+    }
+
+    /// Fail the next `times` requests of the kind with the error, without
+    /// handling them.
+    pub fn fail(self, kind: RequestKind, error: ClientError, times: usize) -> Self {
+        self.expect(kind, times, Action::Fail(error))
+    }
+
+    /// Answer the next `times` requests of the kind with the response returned
+    /// by `f`, without handling them.
+    pub fn respond<F>(self, kind: RequestKind, times: usize, f: F) -> Self
+    where
+        F: FnMut(&Request) -> Response + 'static,
+    {
+        self.expect(kind, times, Action::Respond(Box::new(f)))
+    }
+
+    /// Install the hook into the mock routing, replacing any previous one.
+    /// Returns the log of the requests seen by the hook.
+    pub fn install(self, routing: &mut Routing) -> RequestLog {
+        let state = Rc::new(RefCell::new(State {
+            expectations: self.expectations,
+            counts: HashMap::new(),
+            unmatched: Vec::new(),
+        }));
+        let hook_state = Rc::clone(&state);
+
+        routing.set_request_hook(move |request| hook_state.borrow_mut().handle(request));
+        RequestLog { state }
+    }
+
+    fn expect(mut self, kind: RequestKind, times: usize, action: Action) -> Self {
+        if times > 0 {
+            self.expectations
+                .entry(kind)
+                .or_insert_with(VecDeque::new)
+                .push_back(Expectation {
+                    action,
+                    remaining: times,
+                });
+        }
+        self
+    }
+}
+
+/// Log of the requests seen by a hook installed by `RequestHookBuilder`.
+#[derive(Clone)]
+pub struct RequestLog {
+    state: Rc<RefCell<State>>,
+}
+
+impl RequestLog {
+    /// Number of requests of the kind seen so far.
+    pub fn count(&self, kind: RequestKind) -> usize {
+        self.state.borrow().counts.get(&kind).cloned().unwrap_or(0)
+    }
+
+    /// Requests which had no pending expectation, in the order they were sent.
+    pub fn unmatched(&self) -> Vec<Request> {
+        self.state.borrow().unmatched.clone()
+    }
+
+    /// Panics if some of the expectations haven't been met yet.
+    pub fn assert_done(&self) {
+        let state = self.state.borrow();
+        let pending: Vec<_> = state
+            .expectations
+            .iter()
+            .map(|(kind, queue)| {
+                (kind, queue.iter().map(|exp| exp.remaining).sum::<usize>())
+            })
+            .filter(|&(_, remaining)| remaining > 0)
+            .collect();
+
+        assert!(pending.is_empty(), "Pending expectations: {:?}", pending);
+    }
+}
+
+struct State {
+    expectations: HashMap<RequestKind, VecDeque<Expectation>>,
+    counts: HashMap<RequestKind, usize>,
+    unmatched: Vec<Request>,
+}
+
+impl State {
+    fn handle(&mut self, request: &Request) -> Option<Response> {
+        let kind = match request_kind(request) {
+            Some(kind) => kind,
+            None => return None,
+        };
+        *self.counts.entry(kind).or_insert(0) += 1;
+
+        let (response, done) = match self.expectations.get_mut(&kind).and_then(
+            |queue| queue.front_mut(),
+        ) {
+            Some(expectation) => {
+                expectation.remaining -= 1;
+                (
+                    expectation.action.respond(request),
+                    expectation.remaining == 0,
+                )
+            }
+            None => {
+                self.unmatched.push(request.clone());
+                return None;
+            }
+        };
+
+        if done {
+            if let Some(queue) = self.expectations.get_mut(&kind) {
+                let _ = queue.pop_front();
+            }
+        }
+
+        response
+    }
+}
+
+struct Expectation {
+    action: Action,
+    remaining: usize,
+}
+
+enum Action {
+    Pass,
+    Fail(ClientError),
+    Respond(Box<FnMut(&Request) -> Response>),
+}
+
+impl Action {
+    fn respond(&mut self, request: &Request) -> Option<Response> {
+        match *self {
+            Action::Pass => None,
+            Action::Fail(ref error) => error_response(request, error.clone()),
+            Action::Respond(ref mut f) => Some(f(request)),
+        }
+    }
+}
+
+fn request_kind(request: &Request) -> Option<RequestKind> {
+    let kind = match *request {
+        Request::Refresh(..) => return None,
+        Request::GetAccountInfo(..) => RequestKind::GetAccountInfo,
+        Request::PutIData { .. } => RequestKind::PutIData,
+        Request::GetIData { .. } => RequestKind::GetIData,
+        Request::PutMData { .. } => RequestKind::PutMData,
+        Request::GetMDataVersion { .. } => RequestKind::GetMDataVersion,
+        Request::GetMDataShell { .. } => RequestKind::GetMDataShell,
+        Request::GetMData { .. } => RequestKind::GetMData,
+        Request::ListMDataEntries { .. } |
+        Request::ListMDataKeys { .. } |
+        Request::ListMDataValues { .. } |
+        Request::GetMDataValue { .. } => RequestKind::GetMDataEntries,
+        Request::MutateMDataEntries { .. } => RequestKind::SetMDataEntries,
+        Request::ListMDataPermissions { .. } |
+        Request::ListMDataUserPermissions { .. } => RequestKind::GetMDataPermissions,
+        Request::SetMDataUserPermissions { .. } |
+        Request::DelMDataUserPermissions { .. } => RequestKind::SetMDataPermissions,
+        Request::ChangeMDataOwner { .. } => RequestKind::ChangeMDataOwner,
+        Request::ListAuthKeysAndVersion(..) => RequestKind::ListAuthKeysAndVersion,
+        Request::InsAuthKey { .. } => RequestKind::InsAuthKey,
+        Request::DelAuthKey { .. } => RequestKind::DelAuthKey,
+    };
+
+    Some(kind)
+}
+
+// Response to the request failing with the error.
+fn error_response(request: &Request, err: ClientError) -> Option<Response> {
+    let msg_id = *request.message_id();
+
+    let response = match *request {
+        Request::Refresh(..) => return None,
+        Request::GetAccountInfo(..) => Response::GetAccountInfo { res: Err(err), msg_id },
+        Request::PutIData { .. } => Response::PutIData { res: Err(err), msg_id },
+        Request::GetIData { .. } => Response::GetIData { res: Err(err), msg_id },
+        Request::PutMData { .. } => Response::PutMData { res: Err(err), msg_id },
+        Request::GetMDataVersion { .. } => Response::GetMDataVersion { res: Err(err), msg_id },
+        Request::GetMDataShell { .. } => Response::GetMDataShell { res: Err(err), msg_id },
+        Request::GetMData { .. } => Response::GetMData { res: Err(err), msg_id },
+        Request::ListMDataEntries { .. } => Response::ListMDataEntries { res: Err(err), msg_id },
+        Request::ListMDataKeys { .. } => Response::ListMDataKeys { res: Err(err), msg_id },
+        Request::ListMDataValues { .. } => Response::ListMDataValues { res: Err(err), msg_id },
+        Request::GetMDataValue { .. } => Response::GetMDataValue { res: Err(err), msg_id },
+        Request::MutateMDataEntries { .. } => {
+            Response::MutateMDataEntries { res: Err(err), msg_id }
+        }
+        Request::ListMDataPermissions { .. } => {
+            Response::ListMDataPermissions { res: Err(err), msg_id }
+        }
+        Request::ListMDataUserPermissions { .. } => {
+            Response::ListMDataUserPermissions { res: Err(err), msg_id }
+        }
+        Request::SetMDataUserPermissions { .. } => {
+            Response::SetMDataUserPermissions { res: Err(err), msg_id }
+        }
+        Request::DelMDataUserPermissions { .. } => {
+            Response::DelMDataUserPermissions { res: Err(err), msg_id }
+        }
+        Request::ChangeMDataOwner { .. } => Response::ChangeMDataOwner { res: Err(err), msg_id },
+        Request::ListAuthKeysAndVersion(..) => {
+            Response::ListAuthKeysAndVersion { res: Err(err), msg_id }
+        }
+        Request::InsAuthKey { .. } => Response::InsAuthKey { res: Err(err), msg_id },
+        Request::DelAuthKey { .. } => Response::DelAuthKey { res: Err(err), msg_id },
+    };
+
+    Some(response)
+}
@@ -0,0 +1,96 @@
+// Copyright 2016 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under (1) the MaidSafe.net Commercial License,
+// version 1.0 or later, or (2) The General Public License (GPL), version 3, depending on which
+// licence you accepted on initial access to the Software (the "Licences").
+//
+// By contributing code to the SAFE Network Software, or to this project generally, you agree to be
+// bound by the terms of the MaidSafe Contributor Agreement.  This, along with the Licenses can be
+// found in the root directory of this project at LICENSE, COPYING and CONTRIBUTOR.
+//
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.
+//
+// Please review the Licences for the specific language governing permissions and limitations
+// relating to use of the SAFE Network Software.
+
+//! Direct inspection of the contents of the mock vault, so tests can verify
+//! the side effects of the client without going back through the client
+//! itself. Note the entries of private `MutableData` are stored encrypted.
+
+pub use super::DataId;
+use super::routing::lock_vault;
+use super::vault::Data;
+use routing::{AccountInfo, MutableData, XorName};
+use std::collections::{BTreeMap, BTreeSet};
+
+/// Snapshot of the contents of the mock vault.
+#[derive(Clone, Debug, Default)]
+pub struct VaultSnapshot {
+    /// Account info of every account, keyed by the name of its client manager.
+    pub accounts: BTreeMap<XorName, AccountInfo>,
+    /// Identifiers of all the stored data.
+    pub data: BTreeSet<DataId>,
+    /// Number of entries (including the deleted ones) of every stored
+    /// `MutableData`, keyed by its name and type tag.
+    pub mdata_entries: BTreeMap<(XorName, u64), usize>,
+}
+
+/// Take a snapshot of the current contents of the mock vault.
+pub fn vault_snapshot() -> VaultSnapshot {
+    let vault = lock_vault(false);
+    let mut snapshot = VaultSnapshot::default();
+
+    for (name, account) in vault.accounts() {
+        let _ = snapshot.accounts.insert(*name, *account.account_info());
+    }
+
+    for (id, data) in vault.data() {
+        let _ = snapshot.data.insert(*id);
+
+        if let Data::Mutable(ref data) = *data {
+            let _ = snapshot.mdata_entries.insert(
+                (*data.name(), data.tag()),
+                data.entries().len(),
+            );
+        }
+    }
+
+    snapshot
+}
+
+/// Get the `MutableData` with the given name and type tag from the mock vault.
+pub fn get_mdata(name: &XorName, tag: u64) -> Option<MutableData> {
+    match lock_vault(false).get_data(&DataId::mutable(*name, tag)) {
+        Some(Data::Mutable(data)) => Some(data),
+        _ => None,
+    }
+}
+
+/// Assert the entry of the `MutableData` stored in the mock vault has the
+/// expected content, with `None` meaning the entry is missing or deleted.
+/// Panics if the `MutableData` itself doesn't exist.
+pub fn assert_mdata_entry(name: &XorName, tag: u64, key: &[u8], expected: Option<&[u8]>) {
+    let data = match get_mdata(name, tag) {
+        Some(data) => data,
+        None => panic!("MutableData {:?} (tag {}) not found in the mock vault", name, tag),
+    };
+
+    let actual = data.get(key).map(|value| &value.content[..]).and_then(
+        |content| if content.is_empty() {
+            None
+        } else {
+            Some(content)
+        },
+    );
+
+    assert_eq!(
+        actual,
+        expected,
+        "Unexpected content of the entry {:?} of MutableData {:?} (tag {})",
+        key,
+        name,
+        tag
+    );
+}
@@ -0,0 +1,134 @@
+// Copyright 2016 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under (1) the MaidSafe.net Commercial License,
+// version 1.0 or later, or (2) The General Public License (GPL), version 3, depending on which
+// licence you accepted on initial access to the Software (the "Licences").
+//
+// By contributing code to the SAFE Network Software, or to this project generally, you agree to be
+// bound by the terms of the MaidSafe Contributor Agreement.  This, along with the Licenses can be
+// found in the root directory of this project at LICENSE, COPYING and CONTRIBUTOR.
+//
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.
+//
+// Please review the Licences for the specific language governing permissions and limitations
+// relating to use of the SAFE Network Software.
+
+use super::rng;
+use rand::Rng;
+use std::collections::HashMap;
+use std::time::Duration;
+
+/// Kind of the request sent to the mock network, used to look up the latency
+/// of its response.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum RequestKind {
+    /// `get_account_info`
+    GetAccountInfo,
+    /// `put_idata`
+    PutIData,
+    /// `get_idata`
+    GetIData,
+    /// `put_mdata`
+    PutMData,
+    /// `get_mdata_version`
+    GetMDataVersion,
+    /// `get_mdata_shell`
+    GetMDataShell,
+    /// `get_mdata`
+    GetMData,
+    /// `list_mdata_entries`, `list_mdata_keys`, `list_mdata_values` and
+    /// `get_mdata_value`
+    GetMDataEntries,
+    /// `mutate_mdata_entries`
+    SetMDataEntries,
+    /// `list_mdata_permissions` and `list_mdata_user_permissions`
+    GetMDataPermissions,
+    /// `set_mdata_user_permissions` and `del_mdata_user_permissions`
+    SetMDataPermissions,
+    /// `change_mdata_owner`
+    ChangeMDataOwner,
+    /// `list_auth_keys_and_version`
+    ListAuthKeysAndVersion,
+    /// `ins_auth_key`
+    InsAuthKey,
+    /// `del_auth_key`
+    DelAuthKey,
+}
+
+/// Latency of the responses of the mock network. Each response is delayed by
+/// the latency of its kind of request (or the default one) plus a random
+/// jitter, so responses to consecutive requests can arrive out of order.
+#[derive(Clone, Debug, Default)]
+pub struct LatencyProfile {
+    /// Latency of the kinds of requests without one set explicitly.
+    pub default: Duration,
+    /// Latency of the individual kinds of requests.
+    pub requests: HashMap<RequestKind, Duration>,
+    /// Maximum random latency added to every response.
+    pub jitter: Duration,
+}
+
+impl LatencyProfile {
+    /// Create a profile with the same latency for all requests.
+    pub fn uniform(latency: Duration) -> Self {
+        LatencyProfile {
+            default: latency,
+            requests: HashMap::new(),
+            jitter: Duration::new(0, 0),
+        }
+    }
+
+    /// Set the latency of the given kind of request.
+    pub fn with_request(mut self, kind: RequestKind, latency: Duration) -> Self {
+        let _ = self.requests.insert(kind, latency);
+        self
+    }
+
+    /// Set the maximum random latency added to every response.
+    pub fn with_jitter(mut self, jitter: Duration) -> Self {
+        self.jitter = jitter;
+        self
+    }
+
+    /// Latency of the response to the given kind of request, in milliseconds,
+    /// including the jitter.
+    pub fn delay_ms(&self, kind: RequestKind) -> u64 {
+        let base = self.requests.get(&kind).unwrap_or(&self.default);
+        let jitter = to_millis(&self.jitter);
+        let jitter = if jitter > 0 {
+            rng::with_rng(|rng| rng.gen_range(0, jitter + 1))
+        } else {
+            0
+        };
+
+        to_millis(base) + jitter
+    }
+}
+
+pub fn to_millis(duration: &Duration) -> u64 {
+    duration.as_secs() * 1000 + u64::from(duration.subsec_nanos()) / 1_000_000
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn delays() {
+        let profile = LatencyProfile::uniform(Duration::from_millis(100))
+            .with_request(RequestKind::PutIData, Duration::from_millis(500))
+            .with_jitter(Duration::from_millis(50));
+
+        for _ in 0..10 {
+            let delay = profile.delay_ms(RequestKind::GetIData);
+            assert!(delay >= 100 && delay <= 150);
+
+            let delay = profile.delay_ms(RequestKind::PutIData);
+            assert!(delay >= 500 && delay <= 550);
+        }
+
+        assert_eq!(LatencyProfile::default().delay_ms(RequestKind::GetMData), 0);
+    }
+}
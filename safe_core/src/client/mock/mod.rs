@@ -16,13 +16,30 @@
 // relating to use of the SAFE Network Software.
 
 mod account;
+mod churn;
+mod cost;
+mod fault;
+#[cfg(any(test, feature = "testing"))]
+mod hook;
+#[cfg(any(test, feature = "testing"))]
+pub mod inspect;
+mod latency;
+pub mod rng;
 mod routing;
 #[cfg(test)]
 mod tests;
+mod trace;
 mod vault;
 
 pub use self::account::{Account, DEFAULT_MAX_MUTATIONS};
+pub use self::churn::ChurnConfig;
+pub use self::cost::CostModel;
+pub use self::fault::FaultKind;
+#[cfg(any(test, feature = "testing"))]
+pub use self::hook::{RequestHookBuilder, RequestLog};
+pub use self::latency::{LatencyProfile, RequestKind};
 pub use self::routing::{RequestHookFn, Routing};
+pub use self::trace::{Trace, TraceEntry};
 use routing::XorName;
 
 /// Identifier of immutable data
@@ -45,6 +62,18 @@ impl MutableDataId {
     }
 }
 
+/// Identifier of appendable data
+#[cfg(feature = "unstable-data-types")]
+#[derive(Clone, Copy, Debug, Deserialize, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize)]
+pub struct AppendableDataId(pub XorName, pub u64);
+
+#[cfg(feature = "unstable-data-types")]
+impl AppendableDataId {
+    pub fn name(&self) -> &XorName {
+        &self.0
+    }
+}
+
 /// Identifier for a data (immutable or mutable)
 #[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd, Deserialize, Serialize)]
 pub enum DataId {
@@ -52,6 +81,9 @@ pub enum DataId {
     Immutable(ImmutableDataId),
     /// Identifier of mutable data.
     Mutable(MutableDataId),
+    /// Identifier of appendable data.
+    #[cfg(feature = "unstable-data-types")]
+    Appendable(AppendableDataId),
 }
 
 impl DataId {
@@ -65,11 +97,19 @@ impl DataId {
         DataId::Mutable(MutableDataId(name, tag))
     }
 
+    /// Create `DataId` for appendable data.
+    #[cfg(feature = "unstable-data-types")]
+    pub fn appendable(name: XorName, tag: u64) -> Self {
+        DataId::Appendable(AppendableDataId(name, tag))
+    }
+
     /// Get name of this identifier.
     pub fn name(&self) -> &XorName {
         match *self {
             DataId::Immutable(ref id) => id.name(),
             DataId::Mutable(ref id) => id.name(),
+            #[cfg(feature = "unstable-data-types")]
+            DataId::Appendable(ref id) => id.name(),
         }
     }
 }
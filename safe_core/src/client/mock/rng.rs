@@ -0,0 +1,128 @@
+// Copyright 2016 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under (1) the MaidSafe.net Commercial License,
+// version 1.0 or later, or (2) The General Public License (GPL), version 3, depending on which
+// licence you accepted on initial access to the Software (the "Licences").
+//
+// By contributing code to the SAFE Network Software, or to this project generally, you agree to be
+// bound by the terms of the MaidSafe Contributor Agreement.  This, along with the Licenses can be
+// found in the root directory of this project at LICENSE, COPYING and CONTRIBUTOR.
+//
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.
+//
+// Please review the Licences for the specific language governing permissions and limitations
+// relating to use of the SAFE Network Software.
+
+//! Source of randomness of the mock network. Every random decision of the mock
+//! routing, as well as the random data generated by `utils` when built with
+//! mock routing, comes from a per-thread generator. The generators of all the
+//! threads are derived from a single master seed and the index of the thread,
+//! in the order the threads first use them. The master seed can be set through
+//! the `SAFE_MOCK_SEED` environment variable. When the variable is not set, a
+//! random seed is picked and printed once, so a failed test can be replayed by
+//! rerunning it with that seed.
+//!
+//! Note the key pairs are still generated by `rust_sodium` and are therefore
+//! not reproducible, and neither is the secret material (salts, mnemonic
+//! entropy), which is always drawn from the OS generator.
+
+use rand::{self, Rand, Rng, SeedableRng, XorShiftRng};
+use std::cell::RefCell;
+use std::env;
+use std::sync::atomic::{ATOMIC_USIZE_INIT, AtomicUsize, Ordering};
+
+/// Name of the environment variable holding the seed.
+pub const SEED_ENV_VAR: &'static str = "SAFE_MOCK_SEED";
+
+lazy_static! {
+    static ref MASTER_SEED: u64 = {
+        let seed = env_seed().unwrap_or_else(rand::random);
+        // Printed to stderr rather than logged, so that it's shown along with
+        // the output of the failed test, without polluting the stdout of the
+        // apps.
+        eprintln!("Mock network RNG seed: {} (set {} to replay)", seed, SEED_ENV_VAR);
+        seed
+    };
+}
+
+static NEXT_THREAD_INDEX: AtomicUsize = ATOMIC_USIZE_INIT;
+
+thread_local! {
+    static RNG: RefCell<XorShiftRng> = RefCell::new(initial_rng());
+}
+
+/// Seed from the `SAFE_MOCK_SEED` environment variable, if set and valid.
+pub fn env_seed() -> Option<u64> {
+    env::var(SEED_ENV_VAR).ok().and_then(
+        |seed| seed.trim().parse().ok(),
+    )
+}
+
+/// Reseed the generator of the current thread.
+pub fn reseed(seed: u64) {
+    RNG.with(|rng| *rng.borrow_mut() = seeded(seed))
+}
+
+/// Run `f` with the generator of the current thread.
+pub fn with_rng<F, R>(f: F) -> R
+where
+    F: FnOnce(&mut XorShiftRng) -> R,
+{
+    RNG.with(|rng| f(&mut *rng.borrow_mut()))
+}
+
+/// Generate a random value using the generator of the current thread.
+pub fn random<T: Rand>() -> T {
+    with_rng(|rng| rng.gen())
+}
+
+fn initial_rng() -> XorShiftRng {
+    let index = NEXT_THREAD_INDEX.fetch_add(1, Ordering::SeqCst);
+    seeded(thread_seed(*MASTER_SEED, index as u64))
+}
+
+// Derive the seed of the thread with the given index from the master seed, so
+// that the threads don't share their sequences.
+fn thread_seed(master: u64, index: u64) -> u64 {
+    // SplitMix64 step.
+    let mut z = master.wrapping_add(index.wrapping_mul(0x9e37_79b9_7f4a_7c15));
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+fn seeded(seed: u64) -> XorShiftRng {
+    // `XorShiftRng` must not be seeded with all zeros, so the upper half of its
+    // seed is fixed.
+    XorShiftRng::from_seed(
+        [seed as u32, (seed >> 32) as u32, 0x9e37_79b9, 0x7f4a_7c15],
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reproducible() {
+        reseed(42);
+        let first: Vec<u64> = (0..8).map(|_| random()).collect();
+
+        reseed(42);
+        let second: Vec<u64> = (0..8).map(|_| random()).collect();
+        assert_eq!(first, second);
+
+        reseed(43);
+        let third: Vec<u64> = (0..8).map(|_| random()).collect();
+        assert_ne!(first, third);
+    }
+
+    #[test]
+    fn thread_seeds_differ() {
+        assert_eq!(thread_seed(42, 0), thread_seed(42, 0));
+        assert_ne!(thread_seed(42, 0), thread_seed(42, 1));
+        assert_ne!(thread_seed(42, 0), thread_seed(43, 0));
+    }
+}
@@ -16,15 +16,24 @@
 // relating to use of the SAFE Network Software.
 
 use super::DataId;
+use super::churn::{Churn, ChurnConfig};
+use super::cost::CostModel;
+use super::fault::{self, Fault, FaultKind};
+use super::latency::{LatencyProfile, RequestKind};
+use super::rng;
+use super::trace::{Recorder, Replayer, Trace};
 use super::vault::{self, Data, Vault, VaultGuard};
+#[cfg(feature = "unstable-data-types")]
+use appendable_data::{AppendableData, Filter};
+use maidsafe_utilities::serialisation::deserialise;
 use maidsafe_utilities::thread;
-use rand;
-use routing::{Authority, BootstrapConfig, ClientError, EntryAction, Event, FullId, ImmutableData,
-              InterfaceError, MessageId, MutableData, PermissionSet, Request, Response,
-              RoutingError, TYPE_TAG_SESSION_PACKET, User, XorName};
+use routing::{ACC_LOGIN_ENTRY_KEY, AccountPacket, Authority, BootstrapConfig, ClientError,
+              EntryAction, Event, FullId, ImmutableData, InterfaceError, MessageId, MutableData,
+              PermissionSet, Request, Response, RoutingError, TYPE_TAG_SESSION_PACKET, User,
+              Value, XorName};
 use rust_sodium::crypto::sign;
 use std;
-use std::cell::Cell;
+use std::cell::{Cell, RefCell};
 use std::collections::{BTreeMap, BTreeSet};
 use std::sync::Mutex;
 use std::sync::mpsc::Sender;
@@ -38,32 +47,19 @@ pub type RequestHookFn = FnMut(&Request) -> Option<Response> + 'static;
 const CONNECT_THREAD_NAME: &'static str = "Mock routing connect";
 const DELAY_THREAD_NAME: &'static str = "Mock routing delay";
 
-const DEFAULT_DELAY_MS: u64 = 0;
-const CONNECT_DELAY_MS: u64 = DEFAULT_DELAY_MS;
+const CONNECT_DELAY_MS: u64 = 0;
 
-const GET_ACCOUNT_INFO_DELAY_MS: u64 = DEFAULT_DELAY_MS;
-const PUT_IDATA_DELAY_MS: u64 = DEFAULT_DELAY_MS;
-const GET_IDATA_DELAY_MS: u64 = DEFAULT_DELAY_MS;
-
-const PUT_MDATA_DELAY_MS: u64 = DEFAULT_DELAY_MS;
-const GET_MDATA_VERSION_DELAY_MS: u64 = DEFAULT_DELAY_MS;
-const GET_MDATA_SHELL_DELAY_MS: u64 = DEFAULT_DELAY_MS;
-const GET_MDATA_DELAY_MS: u64 = DEFAULT_DELAY_MS;
-const GET_MDATA_ENTRIES_DELAY_MS: u64 = DEFAULT_DELAY_MS;
-const SET_MDATA_ENTRIES_DELAY_MS: u64 = DEFAULT_DELAY_MS;
-const GET_MDATA_PERMISSIONS_DELAY_MS: u64 = DEFAULT_DELAY_MS;
-const SET_MDATA_PERMISSIONS_DELAY_MS: u64 = DEFAULT_DELAY_MS;
-const CHANGE_MDATA_OWNER_DELAY_MS: u64 = DEFAULT_DELAY_MS;
-
-const LIST_AUTH_KEYS_AND_VERSION_DELAY_MS: u64 = DEFAULT_DELAY_MS;
-const INS_AUTH_KEY_DELAY_MS: u64 = DEFAULT_DELAY_MS;
-const DEL_AUTH_KEY_DELAY_MS: u64 = DEFAULT_DELAY_MS;
+thread_local! {
+    // Churn simulated for the mock routing clients created on this thread. It
+    // is kept here so it survives the restarts of the client.
+    static CHURN: Cell<Option<ChurnConfig>> = Cell::new(None);
+}
 
 lazy_static! {
     static ref VAULT: Mutex<Vault> = Mutex::new(Vault::new());
 }
 
-fn lock_vault(write: bool) -> VaultGuard<'static> {
+pub fn lock_vault(write: bool) -> VaultGuard<'static> {
     vault::lock(&VAULT, write)
 }
 
@@ -76,6 +72,14 @@ pub struct Routing {
     max_ops_countdown: Option<Cell<u64>>,
     timeout_simulation: bool,
     request_hook: Option<Box<RequestHookFn>>,
+    invitation_required: bool,
+    latency: LatencyProfile,
+    faults: Vec<Fault>,
+    recorder: RefCell<Option<Recorder>>,
+    replayer: Option<Replayer>,
+    contention: f64,
+    cost_model: CostModel,
+    churn: Option<Churn>,
 }
 
 impl Routing {
@@ -95,9 +99,13 @@ impl Routing {
             let _ = cloned_sender.send(Event::Connected);
         });
 
+        let churn = CHURN.with(|churn| churn.get()).map(
+            |config| Churn::start(config, sender.clone()),
+        );
+
         let client_auth = Authority::Client {
             client_id: *FullId::new().public_id(),
-            proxy_node_name: rand::random(),
+            proxy_node_name: rng::random(),
         };
 
         Ok(Routing {
@@ -107,6 +115,14 @@ impl Routing {
             max_ops_countdown: None,
             timeout_simulation: false,
             request_hook: None,
+            invitation_required: false,
+            latency: LatencyProfile::default(),
+            faults: Vec::new(),
+            recorder: RefCell::new(None),
+            replayer: None,
+            contention: 0.0,
+            cost_model: CostModel::default(),
+            churn: churn,
         })
     }
 
@@ -116,6 +132,12 @@ impl Routing {
         dst: Authority<XorName>,
         msg_id: MessageId,
     ) -> Result<(), InterfaceError> {
+        let override_response = self.intercept(&Request::GetAccountInfo(msg_id));
+        if let Some(response) = override_response {
+            self.send_response(RequestKind::GetAccountInfo, dst, self.client_auth, response);
+            return Ok(());
+        }
+
         if self.simulate_network_errors() {
             return Ok(());
         }
@@ -136,7 +158,7 @@ impl Routing {
         };
 
         self.send_response(
-            GET_ACCOUNT_INFO_DELAY_MS,
+            RequestKind::GetAccountInfo,
             dst,
             self.client_auth,
             Response::GetAccountInfo {
@@ -157,17 +179,14 @@ impl Routing {
     ) -> Result<(), InterfaceError> {
         let data_name = *data.name();
         let nae_auth = Authority::NaeManager(data_name);
+        let cost = self.cost_model.idata_put_cost(data.value().len());
 
-        let override_response = if let Some(ref mut hook) = self.request_hook {
-            hook(&Request::PutIData {
-                data: data.clone(),
-                msg_id,
-            })
-        } else {
-            None
-        };
+        let override_response = self.intercept(&Request::PutIData {
+            data: data.clone(),
+            msg_id,
+        });
         if let Some(response) = override_response {
-            self.send_response(PUT_IDATA_DELAY_MS, nae_auth, self.client_auth, response);
+            self.send_response(RequestKind::PutIData, nae_auth, self.client_auth, response);
             return Ok(());
         }
 
@@ -179,7 +198,7 @@ impl Routing {
 
         let res = {
             self.verify_network_limits(msg_id, "put_idata")
-                .and_then(|_| vault.authorise_mutation(&dst, self.client_key()))
+                .and_then(|_| vault.authorise_mutation(&dst, self.client_key(), cost))
                 .and_then(|_| {
                     match vault.get_data(&DataId::immutable(*data.name())) {
                         // Immutable data is de-duplicated so always allowed
@@ -191,11 +210,11 @@ impl Routing {
                         }
                     }
                 })
-                .map(|_| vault.commit_mutation(&dst))
+                .map(|_| vault.commit_mutation(&dst, cost))
         };
 
         self.send_response(
-            PUT_IDATA_DELAY_MS,
+            RequestKind::PutIData,
             nae_auth,
             self.client_auth,
             Response::PutIData { res, msg_id },
@@ -212,13 +231,9 @@ impl Routing {
     ) -> Result<(), InterfaceError> {
         let nae_auth = Authority::NaeManager(name);
 
-        let override_response = if let Some(ref mut hook) = self.request_hook {
-            hook(&Request::GetIData { name, msg_id })
-        } else {
-            None
-        };
+        let override_response = self.intercept(&Request::GetIData { name, msg_id });
         if let Some(response) = override_response {
-            self.send_response(GET_IDATA_DELAY_MS, nae_auth, self.client_auth, response);
+            self.send_response(RequestKind::GetIData, nae_auth, self.client_auth, response);
             return Ok(());
         }
 
@@ -240,7 +255,7 @@ impl Routing {
         };
 
         self.send_response(
-            GET_IDATA_DELAY_MS,
+            RequestKind::GetIData,
             nae_auth,
             self.client_auth,
             Response::GetIData { res, msg_id },
@@ -259,17 +274,13 @@ impl Routing {
         let data_name = DataId::mutable(*data.name(), data.tag());
         let nae_auth = Authority::NaeManager(*data_name.name());
 
-        let override_response = if let Some(ref mut hook) = self.request_hook {
-            hook(&Request::PutMData {
-                data: data.clone(),
-                msg_id,
-                requester,
-            })
-        } else {
-            None
-        };
+        let override_response = self.intercept(&Request::PutMData {
+            data: data.clone(),
+            msg_id,
+            requester,
+        });
         if let Some(response) = override_response {
-            self.send_response(PUT_MDATA_DELAY_MS, nae_auth, self.client_auth, response);
+            self.send_response(RequestKind::PutMData, nae_auth, self.client_auth, response);
             return Ok(());
         }
 
@@ -290,6 +301,8 @@ impl Routing {
 
             if vault.contains_data(&data_name) {
                 Err(ClientError::AccountExists)
+            } else if let Err(err) = self.claim_invitation(&mut vault, &data, requester) {
+                Err(err)
             } else {
                 vault.insert_account(dst_name);
                 vault.insert_data(data_name, Data::Mutable(data));
@@ -297,8 +310,9 @@ impl Routing {
             }
         } else {
             // Put normal data.
+            let cost = self.cost_model.mdata_put;
             vault
-                .authorise_mutation(&dst, self.client_key())
+                .authorise_mutation(&dst, self.client_key(), cost)
                 .and_then(|_| Self::verify_owner(&dst, data.owners()))
                 .and_then(|_| if vault.contains_data(&data_name) {
                     Err(ClientError::DataExists)
@@ -306,11 +320,11 @@ impl Routing {
                     vault.insert_data(data_name, Data::Mutable(data));
                     Ok(())
                 })
-                .map(|_| vault.commit_mutation(&dst))
+                .map(|_| vault.commit_mutation(&dst, cost))
         };
 
         self.send_response(
-            PUT_MDATA_DELAY_MS,
+            RequestKind::PutMData,
             nae_auth,
             self.client_auth,
             Response::PutMData { res, msg_id },
@@ -331,7 +345,7 @@ impl Routing {
                         tag,
                         Request::GetMDataVersion { name, tag, msg_id },
                         "get_mdata_version",
-                        GET_MDATA_VERSION_DELAY_MS,
+                        RequestKind::GetMDataVersion,
                         |data| Ok(data.version()),
                         |res| Response::GetMDataVersion { res, msg_id })
     }
@@ -349,7 +363,7 @@ impl Routing {
                         tag,
                         Request::GetMData { name, tag, msg_id },
                         "get_mdata",
-                        GET_MDATA_DELAY_MS,
+                        RequestKind::GetMData,
                         Ok,
                         |res| Response::GetMData { res, msg_id })
     }
@@ -367,7 +381,7 @@ impl Routing {
                         tag,
                         Request::GetMDataShell { name, tag, msg_id },
                         "get_mdata_shell",
-                        GET_MDATA_SHELL_DELAY_MS,
+                        RequestKind::GetMDataShell,
                         |data| Ok(data.shell()),
                         |res| Response::GetMDataShell { res, msg_id })
     }
@@ -385,7 +399,7 @@ impl Routing {
                         tag,
                         Request::ListMDataEntries { name, tag, msg_id },
                         "list_mdata_entries",
-                        GET_MDATA_ENTRIES_DELAY_MS,
+                        RequestKind::GetMDataEntries,
                         |data| Ok(data.entries().clone()),
                         |res| Response::ListMDataEntries { res, msg_id })
     }
@@ -403,7 +417,7 @@ impl Routing {
                         tag,
                         Request::ListMDataKeys { name, tag, msg_id },
                         "list_mdata_keys",
-                        GET_MDATA_ENTRIES_DELAY_MS,
+                        RequestKind::GetMDataEntries,
                         |data| {
                             let keys = data.keys().into_iter().cloned().collect();
                             Ok(keys)
@@ -424,7 +438,7 @@ impl Routing {
                         tag,
                         Request::ListMDataValues { name, tag, msg_id },
                         "list_mdata_values",
-                        GET_MDATA_ENTRIES_DELAY_MS,
+                        RequestKind::GetMDataEntries,
                         |data| {
                             let values = data.values().into_iter().cloned().collect();
                             Ok(values)
@@ -451,7 +465,7 @@ impl Routing {
                             msg_id,
                         },
                         "get_mdata_value",
-                        GET_MDATA_ENTRIES_DELAY_MS,
+                        RequestKind::GetMDataEntries,
                         |data| data.get(&key).cloned().ok_or(ClientError::NoSuchEntry),
                         |res| Response::GetMDataValue { res, msg_id })
     }
@@ -466,6 +480,28 @@ impl Routing {
         msg_id: MessageId,
         requester: sign::PublicKey,
     ) -> Result<(), InterfaceError> {
+        if self.contended() {
+            let keys: Vec<_> = actions.keys().cloned().collect();
+            race_mutation(name, tag, |data, owner| {
+                let actions = keys.into_iter()
+                    .map(|key| {
+                        let action = match data.get(&key) {
+                            Some(value) => EntryAction::Update(Value {
+                                content: value.content.clone(),
+                                entry_version: value.entry_version + 1,
+                            }),
+                            None => EntryAction::Ins(Value {
+                                content: Vec::new(),
+                                entry_version: 0,
+                            }),
+                        };
+                        (key, action)
+                    })
+                    .collect();
+                data.mutate_entries(actions, owner)
+            });
+        }
+
         let actions2 = actions.clone();
 
         self.mutate_mdata(dst,
@@ -480,7 +516,7 @@ impl Routing {
                           },
                           requester,
                           "mutate_mdata_entries",
-                          SET_MDATA_ENTRIES_DELAY_MS,
+                          RequestKind::SetMDataEntries,
                           |data| data.mutate_entries(actions2, requester),
                           |res| Response::MutateMDataEntries { res, msg_id })
     }
@@ -498,7 +534,7 @@ impl Routing {
                         tag,
                         Request::ListMDataPermissions { name, tag, msg_id },
                         "list_mdata_permissions",
-                        GET_MDATA_PERMISSIONS_DELAY_MS,
+                        RequestKind::GetMDataPermissions,
                         |data| Ok(data.permissions().clone()),
                         |res| Response::ListMDataPermissions { res, msg_id })
     }
@@ -522,7 +558,7 @@ impl Routing {
                             msg_id,
                         },
                         "list_mdata_user_permissions",
-                        GET_MDATA_PERMISSIONS_DELAY_MS,
+                        RequestKind::GetMDataPermissions,
                         |data| data.user_permissions(&user).map(|p| *p),
                         |res| Response::ListMDataUserPermissions { res, msg_id })
     }
@@ -540,6 +576,10 @@ impl Routing {
         msg_id: MessageId,
         requester: sign::PublicKey,
     ) -> Result<(), InterfaceError> {
+        if self.contended() {
+            race_mutation(name, tag, race_version);
+        }
+
         self.mutate_mdata(dst,
                           name,
                           tag,
@@ -554,7 +594,7 @@ impl Routing {
                           },
                           requester,
                           "set_mdata_user_permissions",
-                          SET_MDATA_PERMISSIONS_DELAY_MS,
+                          RequestKind::SetMDataPermissions,
                           |data| data.set_user_permissions(user, permissions, version, requester),
                           |res| Response::SetMDataUserPermissions { res, msg_id })
     }
@@ -570,6 +610,10 @@ impl Routing {
         msg_id: MessageId,
         requester: sign::PublicKey,
     ) -> Result<(), InterfaceError> {
+        if self.contended() {
+            race_mutation(name, tag, race_version);
+        }
+
         self.mutate_mdata(dst,
                           name,
                           tag,
@@ -583,7 +627,7 @@ impl Routing {
                           },
                           requester,
                           "del_mdata_user_permissions",
-                          SET_MDATA_PERMISSIONS_DELAY_MS,
+                          RequestKind::SetMDataPermissions,
                           |data| data.del_user_permissions(&user, version, requester),
                           |res| Response::DelMDataUserPermissions { res, msg_id })
     }
@@ -604,7 +648,7 @@ impl Routing {
             Some(_) | None => {
                 // `new_owners` must have exactly 1 element.
                 self.send_response(
-                    CHANGE_MDATA_OWNER_DELAY_MS,
+                    RequestKind::ChangeMDataOwner,
                     dst,
                     self.client_auth,
                     Response::ChangeMDataOwner {
@@ -619,6 +663,10 @@ impl Routing {
         let requester = *self.client_key();
         let requester_name = XorName(sha3_256(&requester[..]));
 
+        if self.contended() {
+            race_mutation(name, tag, race_version);
+        }
+
         self.mutate_mdata(dst,
                           name,
                           tag,
@@ -631,7 +679,7 @@ impl Routing {
                           },
                           requester,
                           "change_mdata_owner",
-                          CHANGE_MDATA_OWNER_DELAY_MS,
+                          RequestKind::ChangeMDataOwner,
                           |data| {
             let dst_name = match dst {
                 Authority::ClientManager(name) => name,
@@ -660,14 +708,10 @@ impl Routing {
         dst: Authority<XorName>,
         msg_id: MessageId,
     ) -> Result<(), InterfaceError> {
-        let override_response = if let Some(ref mut hook) = self.request_hook {
-            hook(&Request::ListAuthKeysAndVersion(msg_id))
-        } else {
-            None
-        };
+        let override_response = self.intercept(&Request::ListAuthKeysAndVersion(msg_id));
         if let Some(response) = override_response {
             self.send_response(
-                LIST_AUTH_KEYS_AND_VERSION_DELAY_MS,
+                RequestKind::ListAuthKeysAndVersion,
                 dst,
                 self.client_auth,
                 response,
@@ -697,7 +741,7 @@ impl Routing {
             };
 
         self.send_response(
-            LIST_AUTH_KEYS_AND_VERSION_DELAY_MS,
+            RequestKind::ListAuthKeysAndVersion,
             dst,
             self.client_auth,
             Response::ListAuthKeysAndVersion { res, msg_id },
@@ -713,17 +757,13 @@ impl Routing {
         version: u64,
         msg_id: MessageId,
     ) -> Result<(), InterfaceError> {
-        let override_response = if let Some(ref mut hook) = self.request_hook {
-            hook(&Request::InsAuthKey {
-                key,
-                version,
-                msg_id,
-            })
-        } else {
-            None
-        };
+        let override_response = self.intercept(&Request::InsAuthKey {
+            key,
+            version,
+            msg_id,
+        });
         if let Some(response) = override_response {
-            self.send_response(INS_AUTH_KEY_DELAY_MS, dst, self.client_auth, response);
+            self.send_response(RequestKind::InsAuthKey, dst, self.client_auth, response);
             return Ok(());
         }
 
@@ -749,7 +789,7 @@ impl Routing {
 
 
         self.send_response(
-            INS_AUTH_KEY_DELAY_MS,
+            RequestKind::InsAuthKey,
             dst,
             self.client_auth,
             Response::InsAuthKey { res, msg_id },
@@ -765,17 +805,13 @@ impl Routing {
         version: u64,
         msg_id: MessageId,
     ) -> Result<(), InterfaceError> {
-        let override_response = if let Some(ref mut hook) = self.request_hook {
-            hook(&Request::DelAuthKey {
-                key,
-                version,
-                msg_id,
-            })
-        } else {
-            None
-        };
+        let override_response = self.intercept(&Request::DelAuthKey {
+            key,
+            version,
+            msg_id,
+        });
         if let Some(response) = override_response {
-            self.send_response(DEL_AUTH_KEY_DELAY_MS, dst, self.client_auth, response);
+            self.send_response(RequestKind::DelAuthKey, dst, self.client_auth, response);
             return Ok(());
         }
 
@@ -800,7 +836,7 @@ impl Routing {
         };
 
         self.send_response(
-            DEL_AUTH_KEY_DELAY_MS,
+            RequestKind::DelAuthKey,
             dst,
             self.client_auth,
             Response::DelAuthKey { res, msg_id },
@@ -810,18 +846,30 @@ impl Routing {
 
     fn send_response(
         &self,
-        delay_ms: u64,
+        kind: RequestKind,
         src: Authority<XorName>,
         dst: Authority<XorName>,
         response: Response,
     ) {
+        let (response, fault_delay_ms) = match fault::apply(&self.faults, kind, response) {
+            Some(res) => res,
+            None => {
+                trace!("Mock routing: dropping response to {:?}", kind);
+                return;
+            }
+        };
+
+        if let Some(ref mut recorder) = *self.recorder.borrow_mut() {
+            recorder.response(&response);
+        }
+
         let event = Event::Response {
             response: response,
             src: src,
             dst: dst,
         };
 
-        self.send_event(delay_ms, event)
+        self.send_event(self.latency.delay_ms(kind) + fault_delay_ms, event)
     }
 
     fn send_event(&self, delay_ms: u64, event: Event) {
@@ -838,6 +886,29 @@ impl Routing {
         }
     }
 
+    // Decide whether a mutation request loses the race to another client.
+    fn contended(&self) -> bool {
+        self.contention > 0.0 && rng::random::<f64>() < self.contention
+    }
+
+    // Record the request and return the response overriding its regular
+    // handling, if any.
+    fn intercept(&mut self, request: &Request) -> Option<Response> {
+        if let Some(ref mut recorder) = *self.recorder.borrow_mut() {
+            recorder.request(request);
+        }
+
+        if let Some(ref mut hook) = self.request_hook {
+            if let Some(response) = hook(request) {
+                return Some(response);
+            }
+        }
+
+        self.replayer.as_mut().and_then(
+            |replayer| replayer.response(request),
+        )
+    }
+
     fn client_name(&self) -> XorName {
         match self.client_auth {
             Authority::Client { ref client_id, .. } => *client_id.name(),
@@ -852,7 +923,7 @@ impl Routing {
         tag: u64,
         request: Request,
         log_label: &str,
-        delay_ms: u64,
+        kind: RequestKind,
         f: F,
         g: G,
     ) -> Result<(), InterfaceError>
@@ -866,7 +937,7 @@ impl Routing {
             request,
             None,
             log_label,
-            delay_ms,
+            kind,
             false,
             |data, vault| {
                 vault.authorise_read(&dst, &name)?;
@@ -884,7 +955,7 @@ impl Routing {
         request: Request,
         requester: sign::PublicKey,
         log_label: &str,
-        delay_ms: u64,
+        kind: RequestKind,
         f: F,
         g: G,
     ) -> Result<(), InterfaceError>
@@ -893,12 +964,13 @@ impl Routing {
         G: FnOnce(Result<R, ClientError>) -> Response,
     {
         let client_key = *self.client_key();
+        let cost = self.cost_model.mdata_mutation;
         let mutate = |mut data: MutableData, vault: &mut Vault| {
-            vault.authorise_mutation(&dst, &client_key)?;
+            vault.authorise_mutation(&dst, &client_key, cost)?;
 
             let output = f(&mut data)?;
             vault.insert_data(DataId::mutable(name, tag), Data::Mutable(data));
-            vault.commit_mutation(&dst);
+            vault.commit_mutation(&dst, cost);
 
             Ok(output)
         };
@@ -910,7 +982,7 @@ impl Routing {
             request,
             Some(requester),
             log_label,
-            delay_ms,
+            kind,
             true,
             mutate,
             g,
@@ -924,7 +996,7 @@ impl Routing {
         request: Request,
         requester: Option<sign::PublicKey>,
         log_label: &str,
-        delay_ms: u64,
+        kind: RequestKind,
         write: bool,
         f: F,
         g: G,
@@ -936,13 +1008,9 @@ impl Routing {
         let nae_auth = Authority::NaeManager(name);
         let msg_id = *request.message_id();
 
-        let override_response = if let Some(ref mut hook) = self.request_hook {
-            hook(&request)
-        } else {
-            None
-        };
+        let override_response = self.intercept(&request);
         if let Some(response) = override_response {
-            self.send_response(delay_ms, nae_auth, self.client_auth, response);
+            self.send_response(kind, nae_auth, self.client_auth, response);
             return Ok(());
         };
 
@@ -968,7 +1036,7 @@ impl Routing {
             }
         };
 
-        self.send_response(delay_ms, nae_auth, self.client_auth, g(res));
+        self.send_response(kind, nae_auth, self.client_auth, g(res));
         Ok(())
     }
 
@@ -993,6 +1061,30 @@ impl Routing {
         }
     }
 
+    // Claim the invitation the account packet was created with, if
+    // invitations are required.
+    fn claim_invitation(
+        &self,
+        vault: &mut Vault,
+        data: &MutableData,
+        requester: sign::PublicKey,
+    ) -> Result<(), ClientError> {
+        if !self.invitation_required {
+            return Ok(());
+        }
+
+        let packet = data.get(ACC_LOGIN_ENTRY_KEY).and_then(|value| {
+            deserialise(&value.content).ok()
+        });
+
+        match packet {
+            Some(AccountPacket::WithInvitation { invitation_string, .. }) => {
+                vault.claim_invitation(&invitation_string, requester)
+            }
+            _ => Err(ClientError::InvalidInvitation),
+        }
+    }
+
     fn verify_requester(&self, requester: Option<sign::PublicKey>) -> Result<(), ClientError> {
         let requester = match requester {
             Some(key) => key,
@@ -1048,7 +1140,7 @@ impl Routing {
             return true;
         }
 
-        false
+        self.churn.as_ref().map_or(false, Churn::unresponsive)
     }
 
     fn client_key(&self) -> &sign::PublicKey {
@@ -1056,6 +1148,105 @@ impl Routing {
     }
 }
 
+#[cfg(feature = "unstable-data-types")]
+impl Routing {
+    // `AppendableData` requests aren't part of the routing protocol, so they
+    // are served synchronously instead of through the event channel.
+
+    /// Put `AppendableData` into the mock vault.
+    pub fn put_adata(
+        &mut self,
+        dst: Authority<XorName>,
+        data: AppendableData,
+    ) -> Result<(), ClientError> {
+        let data_id = DataId::appendable(*data.name(), data.tag());
+        let mut vault = lock_vault(true);
+
+        let cost = self.cost_model.mdata_put;
+        vault.authorise_mutation(&dst, self.client_key(), cost)?;
+        Self::verify_owner(&dst, data.owners())?;
+
+        if vault.contains_data(&data_id) {
+            return Err(ClientError::DataExists);
+        }
+
+        vault.insert_data(data_id, Data::Appendable(data));
+        vault.commit_mutation(&dst, cost);
+        Ok(())
+    }
+
+    /// Fetch `AppendableData` from the mock vault.
+    pub fn get_adata(&self, name: XorName, tag: u64) -> Result<AppendableData, ClientError> {
+        let vault = lock_vault(false);
+        match vault.get_data(&DataId::appendable(name, tag)) {
+            Some(Data::Appendable(data)) => Ok(data),
+            _ => Err(ClientError::NoSuchData),
+        }
+    }
+
+    /// Append an entry to `AppendableData`.
+    pub fn append_adata(
+        &mut self,
+        dst: Authority<XorName>,
+        name: XorName,
+        tag: u64,
+        content: Vec<u8>,
+        requester: sign::PublicKey,
+    ) -> Result<(), ClientError> {
+        self.mutate_adata(dst, name, tag, |data| data.append(content, requester))
+    }
+
+    /// Replace the filter of `AppendableData`.
+    pub fn set_adata_filter(
+        &mut self,
+        dst: Authority<XorName>,
+        name: XorName,
+        tag: u64,
+        filter: Filter,
+        requester: sign::PublicKey,
+    ) -> Result<(), ClientError> {
+        self.mutate_adata(dst, name, tag, |data| data.set_filter(filter, requester))
+    }
+
+    /// Remove all entries from `AppendableData`.
+    pub fn clear_adata(
+        &mut self,
+        dst: Authority<XorName>,
+        name: XorName,
+        tag: u64,
+        requester: sign::PublicKey,
+    ) -> Result<(), ClientError> {
+        self.mutate_adata(dst, name, tag, |data| data.clear(requester))
+    }
+
+    fn mutate_adata<F>(
+        &mut self,
+        dst: Authority<XorName>,
+        name: XorName,
+        tag: u64,
+        f: F,
+    ) -> Result<(), ClientError>
+    where
+        F: FnOnce(&mut AppendableData) -> Result<(), ClientError>,
+    {
+        let data_id = DataId::appendable(name, tag);
+        let mut vault = lock_vault(true);
+
+        let cost = self.cost_model.mdata_mutation;
+        vault.authorise_mutation(&dst, self.client_key(), cost)?;
+
+        let mut data = match vault.get_data(&data_id) {
+            Some(Data::Appendable(data)) => data,
+            _ => return Err(ClientError::NoSuchData),
+        };
+        f(&mut data)?;
+
+        vault.insert_data(data_id, Data::Appendable(data));
+        vault.commit_mutation(&dst, cost);
+        Ok(())
+    }
+}
+
 #[cfg(any(feature = "testing", test))]
 impl Routing {
     /// Set hook function to override response results for test purposes.
@@ -1083,10 +1274,90 @@ impl Routing {
         let _ = std::thread::spawn(move || unwrap!(sender.send(Event::Terminate)));
     }
 
+    /// Simulate churn of the network: periodic requests to restart and windows
+    /// during which no request is responded to. The churn applies to the mock
+    /// routing clients subsequently created on this thread too, so it survives
+    /// the restarts of the client. `ChurnConfig::default()` stops it.
+    pub fn simulate_churn(&mut self, config: ChurnConfig) {
+        CHURN.with(|churn| churn.set(Some(config)));
+        self.churn = Some(Churn::start(config, self.sender.clone()));
+    }
+
     /// Simulates network timeouts
     pub fn set_simulate_timeout(&mut self, enable: bool) {
         self.timeout_simulation = enable;
     }
+
+    /// Delay the responses according to the given latency profile, to
+    /// simulate the timing of the real network.
+    pub fn set_latency_profile(&mut self, profile: LatencyProfile) {
+        self.latency = profile;
+    }
+
+    /// Inject the fault into the responses to all requests, with the given
+    /// probability (0.0 to 1.0).
+    pub fn inject_fault(&mut self, kind: FaultKind, probability: f64) {
+        self.faults.push(Fault::new(kind, probability, None));
+    }
+
+    /// Inject the fault into the responses to the given kinds of requests
+    /// only, with the given probability (0.0 to 1.0).
+    pub fn inject_fault_for(
+        &mut self,
+        kind: FaultKind,
+        probability: f64,
+        requests: &[RequestKind],
+    ) {
+        self.faults.push(Fault::new(kind, probability, Some(requests.to_vec())));
+    }
+
+    /// Remove all the injected faults.
+    pub fn clear_faults(&mut self) {
+        self.faults.clear();
+    }
+
+    /// Start recording the requests and responses, discarding any previous
+    /// recording.
+    pub fn start_recording(&mut self) {
+        *self.recorder.borrow_mut() = Some(Recorder::new());
+    }
+
+    /// Stop recording and return the recorded trace.
+    pub fn stop_recording(&mut self) -> Option<Trace> {
+        self.recorder.borrow_mut().take().map(Recorder::into_trace)
+    }
+
+    /// Answer the requests matching the ones in the trace with their recorded
+    /// responses, in the order they were recorded. The other requests are
+    /// handled as usual.
+    pub fn replay(&mut self, trace: Trace) {
+        self.replayer = Some(Replayer::new(trace));
+    }
+
+    /// Stop answering the requests with the recorded responses.
+    pub fn stop_replay(&mut self) {
+        self.replayer = None;
+    }
+
+    /// Simulate other clients racing this one to mutate the same data: with
+    /// the given probability (0.0 to 1.0), a conflicting mutation is applied
+    /// right before each `MutableData` mutation request, which then fails
+    /// with the errors the real network returns on such conflicts.
+    pub fn set_contention(&mut self, probability: f64) {
+        self.contention = probability;
+    }
+
+    /// Charge the mutations according to the given cost model, so running out
+    /// of balance can be simulated.
+    pub fn set_cost_model(&mut self, model: CostModel) {
+        self.cost_model = model;
+    }
+
+    /// Require a valid unclaimed invitation for creating accounts. The
+    /// invitation is claimed when the account is created.
+    pub fn set_invitation_required(&mut self, required: bool) {
+        self.invitation_required = required;
+    }
 }
 
 impl Drop for Routing {
@@ -1094,3 +1365,35 @@ impl Drop for Routing {
         let _ = self.sender.send(Event::Terminate);
     }
 }
+
+// Apply a mutation made by a racing client (one of the owners) to the data.
+fn race_mutation<F>(name: XorName, tag: u64, race: F)
+where
+    F: FnOnce(&mut MutableData, sign::PublicKey) -> Result<(), ClientError>,
+{
+    let mut vault = lock_vault(true);
+    let data_id = DataId::mutable(name, tag);
+
+    if let Some(Data::Mutable(mut data)) = vault.get_data(&data_id) {
+        let owner = match data.owners().iter().next() {
+            Some(owner) => *owner,
+            None => return,
+        };
+
+        match race(&mut data, owner) {
+            Ok(()) => vault.insert_data(data_id, Data::Mutable(data)),
+            Err(err) => trace!("Mock routing: racing mutation failed: {:?}", err),
+        }
+    }
+}
+
+// Racing mutation bumping the version of the data, by re-setting the
+// permissions of one of its users.
+fn race_version(data: &mut MutableData, owner: sign::PublicKey) -> Result<(), ClientError> {
+    let (user, permissions) = match data.permissions().iter().next() {
+        Some((user, permissions)) => (*user, *permissions),
+        None => return Err(ClientError::NoSuchKey),
+    };
+    let version = data.version() + 1;
+    data.set_user_permissions(user, permissions, version, owner)
+}
@@ -16,14 +16,22 @@
 // relating to use of the SAFE Network Software.
 
 use super::DEFAULT_MAX_MUTATIONS;
+use super::cost::CostModel;
+use super::fault::FaultKind;
+use super::hook::RequestHookBuilder;
+use super::inspect;
+use super::latency::{LatencyProfile, RequestKind};
+use super::trace::Trace;
 use super::routing::Routing;
+use super::vault::INVITE_TOKEN_TYPE_TAG;
+use maidsafe_utilities::serialisation::serialise;
 use rand;
-use routing::{AccountInfo, Action, Authority, ClientError, EntryAction, EntryActions, Event,
-              FullId, ImmutableData, MessageId, MutableData, PermissionSet, Request, Response,
-              TYPE_TAG_SESSION_PACKET, User, Value, XorName};
+use routing::{ACC_LOGIN_ENTRY_KEY, AccountInfo, AccountPacket, Action, Authority, ClientError,
+              EntryAction, EntryActions, Event, FullId, ImmutableData, MessageId, MutableData,
+              PermissionSet, Request, Response, TYPE_TAG_SESSION_PACKET, User, Value, XorName};
 use rust_sodium::crypto::sign;
 use std::sync::mpsc::{self, Receiver};
-use std::time::Duration;
+use std::time::{Duration, Instant};
 use tiny_keccak::sha3_256;
 use utils;
 
@@ -1150,6 +1158,124 @@ fn balance_check() {
     assert!(mdata.serialised_size() > 0);
 }
 
+// Mutations are charged according to the cost model and fail once the account
+// can't afford them.
+#[test]
+fn cost_model() {
+    let (mut routing, routing_rx, full_id) = setup();
+    let owner_key = *full_id.public_id().signing_public_key();
+    let client_mgr = create_account(&mut routing, &routing_rx, owner_key);
+
+    routing.set_cost_model(CostModel {
+        idata_put: 1,
+        idata_put_per_kib: 10,
+        mdata_put: DEFAULT_MAX_MUTATIONS,
+        mdata_mutation: 1,
+    });
+
+    // 3 KiB of ImmutableData costs 1 + 3 * 10.
+    let data = ImmutableData::new(unwrap!(utils::generate_random_vector(3 * 1024)));
+    let msg_id = MessageId::new();
+    unwrap!(routing.put_idata(client_mgr, data, msg_id));
+    expect_success!(routing_rx, msg_id, Response::PutIData);
+
+    let info = account_info(&mut routing, &routing_rx, client_mgr);
+    assert_eq!(info.mutations_done, 1);
+    assert_eq!(info.mutations_available, DEFAULT_MAX_MUTATIONS - 31);
+
+    // Putting MutableData is now too expensive.
+    let data = unwrap!(MutableData::new(
+        rand::random(),
+        1000,
+        Default::default(),
+        Default::default(),
+        btree_set!(owner_key),
+    ));
+    let msg_id = MessageId::new();
+    unwrap!(routing.put_mdata(client_mgr, data, msg_id, owner_key));
+    expect_failure!(
+        routing_rx,
+        msg_id,
+        Response::PutMData,
+        ClientError::LowBalance
+    );
+
+    let info = account_info(&mut routing, &routing_rx, client_mgr);
+    assert_eq!(info.mutations_done, 1);
+    assert_eq!(info.mutations_available, DEFAULT_MAX_MUTATIONS - 31);
+}
+
+// Test the requests are answered according to the ordered expectations of the
+// hook, with the other requests captured as unmatched.
+#[test]
+fn request_hook_builder() {
+    let (mut routing, routing_rx, full_id) = setup();
+    let owner_key = *full_id.public_id().signing_public_key();
+    let client_mgr = create_account(&mut routing, &routing_rx, owner_key);
+
+    let log = RequestHookBuilder::new()
+        .fail(
+            RequestKind::PutMData,
+            ClientError::NetworkOther("first".to_owned()),
+            1,
+        )
+        .pass(RequestKind::PutMData, 1)
+        .respond(RequestKind::GetMDataVersion, 1, |request| {
+            Response::GetMDataVersion {
+                res: Ok(42),
+                msg_id: *request.message_id(),
+            }
+        })
+        .install(&mut routing);
+
+    let name = rand::random();
+    let tag = 1000u64;
+    let data = unwrap!(MutableData::new(
+        name,
+        tag,
+        Default::default(),
+        Default::default(),
+        btree_set!(owner_key),
+    ));
+
+    // The first put fails, the second one succeeds.
+    let msg_id = MessageId::new();
+    unwrap!(routing.put_mdata(client_mgr, data.clone(), msg_id, owner_key));
+    expect_failure!(
+        routing_rx,
+        msg_id,
+        Response::PutMData,
+        ClientError::NetworkOther(_)
+    );
+
+    let msg_id = MessageId::new();
+    unwrap!(routing.put_mdata(client_mgr, data, msg_id, owner_key));
+    expect_success!(routing_rx, msg_id, Response::PutMData);
+
+    // The canned response is returned only once.
+    let nae_mgr = Authority::NaeManager(name);
+    let msg_id = MessageId::new();
+    unwrap!(routing.get_mdata_version(nae_mgr, name, tag, msg_id));
+    let version = expect_success!(routing_rx, msg_id, Response::GetMDataVersion);
+    assert_eq!(version, 42);
+
+    let msg_id = MessageId::new();
+    unwrap!(routing.get_mdata_version(nae_mgr, name, tag, msg_id));
+    let version = expect_success!(routing_rx, msg_id, Response::GetMDataVersion);
+    assert_eq!(version, 0);
+
+    log.assert_done();
+    assert_eq!(log.count(RequestKind::PutMData), 2);
+    assert_eq!(log.count(RequestKind::GetMDataVersion), 2);
+
+    let unmatched = log.unmatched();
+    assert_eq!(unmatched.len(), 1);
+    match unmatched[0] {
+        Request::GetMDataVersion { msg_id: unmatched_id, .. } => assert_eq!(unmatched_id, msg_id),
+        ref request => panic!("Unexpected {:?}", request),
+    }
+}
+
 // Test routing request hooks.
 #[test]
 fn request_hooks() {
@@ -1243,6 +1369,310 @@ fn request_hooks() {
     expect_success!(routing_rx, msg_id, Response::MutateMDataEntries);
 }
 
+// Test account creation with invitations.
+#[test]
+fn invitation_claims() {
+    let (mut routing, routing_rx, full_id) = setup();
+
+    let owner_key = *full_id.public_id().signing_public_key();
+    let client_mgr = create_account(&mut routing, &routing_rx, owner_key);
+
+    // Put the invitation token to the network.
+    let invitation = unwrap!(utils::generate_random_string(10));
+    let name = XorName(sha3_256(invitation.as_bytes()));
+    let permissions = btree_map![
+        User::Anyone => PermissionSet::new().allow(Action::Insert)
+    ];
+    let data = unwrap!(MutableData::new(name,
+                                        INVITE_TOKEN_TYPE_TAG,
+                                        permissions,
+                                        Default::default(),
+                                        btree_set!(owner_key)));
+
+    let msg_id = MessageId::new();
+    unwrap!(routing.put_mdata(client_mgr, data, msg_id, owner_key));
+    expect_success!(routing_rx, msg_id, Response::PutMData);
+
+    routing.set_invitation_required(true);
+
+    // Creating an account without an invitation fails.
+    let (dst, data, key) = account_packet(None);
+    let msg_id = MessageId::new();
+    unwrap!(routing.put_mdata(dst, data, msg_id, key));
+    expect_failure!(routing_rx,
+                    msg_id,
+                    Response::PutMData,
+                    ClientError::InvalidInvitation);
+
+    // Creating an account with a non-existing invitation fails.
+    let (dst, data, key) = account_packet(Some("invalid invitation"));
+    let msg_id = MessageId::new();
+    unwrap!(routing.put_mdata(dst, data, msg_id, key));
+    expect_failure!(routing_rx,
+                    msg_id,
+                    Response::PutMData,
+                    ClientError::InvalidInvitation);
+
+    // Creating an account with a valid invitation succeeds.
+    let (dst, data, key) = account_packet(Some(&invitation));
+    let msg_id = MessageId::new();
+    unwrap!(routing.put_mdata(dst, data, msg_id, key));
+    expect_success!(routing_rx, msg_id, Response::PutMData);
+
+    // The invitation is now marked as claimed.
+    let msg_id = MessageId::new();
+    unwrap!(routing.list_mdata_keys(Authority::NaeManager(name),
+                                    name,
+                                    INVITE_TOKEN_TYPE_TAG,
+                                    msg_id));
+    let keys = expect_success!(routing_rx, msg_id, Response::ListMDataKeys);
+    assert_eq!(keys.len(), 1);
+
+    // Claiming the same invitation again fails.
+    let (dst, data, key) = account_packet(Some(&invitation));
+    let msg_id = MessageId::new();
+    unwrap!(routing.put_mdata(dst, data, msg_id, key));
+    expect_failure!(routing_rx,
+                    msg_id,
+                    Response::PutMData,
+                    ClientError::InvitationAlreadyClaimed);
+}
+
+// Test the responses are delayed according to the latency profile.
+#[test]
+fn latency_profile() {
+    let (mut routing, routing_rx, full_id) = setup();
+    let owner_key = *full_id.public_id().signing_public_key();
+    let client_mgr = create_account(&mut routing, &routing_rx, owner_key);
+
+    routing.set_latency_profile(LatencyProfile::uniform(Duration::from_millis(10))
+        .with_request(RequestKind::PutIData, Duration::from_millis(500)));
+
+    let data = ImmutableData::new(unwrap!(utils::generate_random_vector(10)));
+    let start = Instant::now();
+
+    // The slow put completes after the get sent after it.
+    let put_msg_id = MessageId::new();
+    unwrap!(routing.put_idata(client_mgr, data, put_msg_id));
+    let _ = account_info(&mut routing, &routing_rx, client_mgr);
+    expect_success!(routing_rx, put_msg_id, Response::PutIData);
+
+    assert!(start.elapsed() >= Duration::from_millis(500));
+}
+
+// Test the faults injected into the responses.
+#[test]
+fn fault_injection() {
+    let (mut routing, routing_rx, full_id) = setup();
+    let owner_key = *full_id.public_id().signing_public_key();
+    let client_mgr = create_account(&mut routing, &routing_rx, owner_key);
+
+    let data = ImmutableData::new(unwrap!(utils::generate_random_vector(10)));
+
+    // Error injected into the puts only.
+    routing.inject_fault_for(
+        FaultKind::Error(ClientError::NetworkOther("Injected".to_owned())),
+        1.0,
+        &[RequestKind::PutIData],
+    );
+
+    let msg_id = MessageId::new();
+    unwrap!(routing.put_idata(client_mgr, data.clone(), msg_id));
+    expect_failure!(routing_rx,
+                    msg_id,
+                    Response::PutIData,
+                    ClientError::NetworkOther(_));
+    let _ = account_info(&mut routing, &routing_rx, client_mgr);
+
+    // Dropped responses.
+    routing.clear_faults();
+    routing.inject_fault(FaultKind::DropResponse, 1.0);
+
+    let msg_id = MessageId::new();
+    unwrap!(routing.put_idata(client_mgr, data.clone(), msg_id));
+    assert!(routing_rx.recv_timeout(Duration::from_millis(500)).is_err());
+
+    // Faults with zero probability are never injected.
+    routing.clear_faults();
+    routing.inject_fault(FaultKind::DropResponse, 0.0);
+
+    let msg_id = MessageId::new();
+    unwrap!(routing.put_idata(client_mgr, data, msg_id));
+    expect_success!(routing_rx, msg_id, Response::PutIData);
+}
+
+// Test recording the traffic and replaying it.
+#[test]
+fn record_and_replay() {
+    let (mut routing, routing_rx, full_id) = setup();
+    let owner_key = *full_id.public_id().signing_public_key();
+    let client_mgr = create_account(&mut routing, &routing_rx, owner_key);
+
+    let data = ImmutableData::new(unwrap!(utils::generate_random_vector(10)));
+    let nae_mgr = Authority::NaeManager(*data.name());
+
+    // Record a failed get of the data.
+    routing.start_recording();
+
+    let msg_id = MessageId::new();
+    unwrap!(routing.get_idata(nae_mgr, *data.name(), msg_id));
+    expect_failure!(routing_rx, msg_id, Response::GetIData, ClientError::NoSuchData);
+
+    let trace = unwrap!(routing.stop_recording());
+    assert_eq!(trace.entries.len(), 1);
+    assert_eq!(trace.entries[0].msg_id, msg_id);
+    assert!(trace.entries[0].response.is_some());
+
+    let trace = unwrap!(Trace::from_bytes(&unwrap!(trace.to_bytes())));
+
+    // Put the data; the replayed get still fails as recorded.
+    let msg_id = MessageId::new();
+    unwrap!(routing.put_idata(client_mgr, data.clone(), msg_id));
+    expect_success!(routing_rx, msg_id, Response::PutIData);
+
+    routing.replay(trace);
+
+    let msg_id = MessageId::new();
+    unwrap!(routing.get_idata(nae_mgr, *data.name(), msg_id));
+    expect_failure!(routing_rx, msg_id, Response::GetIData, ClientError::NoSuchData);
+
+    // The recorded responses are used up, so the next get succeeds.
+    let msg_id = MessageId::new();
+    unwrap!(routing.get_idata(nae_mgr, *data.name(), msg_id));
+    let got = expect_success!(routing_rx, msg_id, Response::GetIData);
+    assert_eq!(got, data);
+}
+
+// Test the mutations lose the race to the simulated competing clients.
+#[test]
+fn contention() {
+    let (mut routing, routing_rx, full_id) = setup();
+    let owner_key = *full_id.public_id().signing_public_key();
+    let client_mgr = create_account(&mut routing, &routing_rx, owner_key);
+
+    let name = rand::random();
+    let tag = 1000u64;
+    let key = b"key0".to_vec();
+    let perms = PermissionSet::new().allow(Action::Insert);
+
+    let data = unwrap!(MutableData::new(
+        name,
+        tag,
+        btree_map![User::Anyone => perms],
+        btree_map![key.clone() => Value { content: vec![0], entry_version: 0 }],
+        btree_set!(owner_key),
+    ));
+
+    let msg_id = MessageId::new();
+    unwrap!(routing.put_mdata(client_mgr, data, msg_id, owner_key));
+    expect_success!(routing_rx, msg_id, Response::PutMData);
+
+    routing.set_contention(1.0);
+
+    // The entry is updated by the racing client first.
+    let actions = EntryActions::new().update(key.clone(), vec![1], 1).into();
+    let msg_id = MessageId::new();
+    unwrap!(routing.mutate_mdata_entries(client_mgr, name, tag, actions, msg_id, owner_key));
+    expect_failure!(routing_rx,
+                    msg_id,
+                    Response::MutateMDataEntries,
+                    ClientError::InvalidEntryActions(_));
+
+    // So is the version of the data.
+    let msg_id = MessageId::new();
+    unwrap!(routing.set_mdata_user_permissions(
+        client_mgr,
+        name,
+        tag,
+        User::Anyone,
+        perms,
+        1,
+        msg_id,
+        owner_key,
+    ));
+    expect_failure!(routing_rx,
+                    msg_id,
+                    Response::SetMDataUserPermissions,
+                    ClientError::InvalidSuccessor(_));
+
+    // Retrying with the refreshed versions succeeds without contention.
+    routing.set_contention(0.0);
+
+    let actions = EntryActions::new().update(key, vec![1], 2).into();
+    let msg_id = MessageId::new();
+    unwrap!(routing.mutate_mdata_entries(client_mgr, name, tag, actions, msg_id, owner_key));
+    expect_success!(routing_rx, msg_id, Response::MutateMDataEntries);
+
+    let msg_id = MessageId::new();
+    unwrap!(routing.set_mdata_user_permissions(
+        client_mgr,
+        name,
+        tag,
+        User::Anyone,
+        perms,
+        2,
+        msg_id,
+        owner_key,
+    ));
+    expect_success!(routing_rx, msg_id, Response::SetMDataUserPermissions);
+}
+
+// The side effects of the requests are visible through the vault inspection API.
+#[test]
+fn vault_inspection() {
+    let (mut routing, routing_rx, full_id) = setup();
+
+    let owner_key = *full_id.public_id().signing_public_key();
+    let client_mgr = create_account(&mut routing, &routing_rx, owner_key);
+
+    let name = rand::random();
+    let tag = 1000u64;
+    let entries = btree_map![
+        b"key0".to_vec() => Value { content: b"value0".to_vec(), entry_version: 0 },
+        b"key1".to_vec() => Value { content: b"value1".to_vec(), entry_version: 0 }
+    ];
+    let data = unwrap!(MutableData::new(
+        name,
+        tag,
+        Default::default(),
+        entries,
+        btree_set!(owner_key),
+    ));
+
+    let msg_id = MessageId::new();
+    unwrap!(routing.put_mdata(client_mgr, data, msg_id, owner_key));
+    expect_success!(routing_rx, msg_id, Response::PutMData);
+
+    let snapshot = inspect::vault_snapshot();
+    assert!(snapshot.data.contains(&inspect::DataId::mutable(name, tag)));
+    assert_eq!(snapshot.mdata_entries[&(name, tag)], 2);
+
+    let account_name = match client_mgr {
+        Authority::ClientManager(name) => name,
+        _ => unreachable!(),
+    };
+    assert_eq!(
+        snapshot.accounts[&account_name].mutations_done,
+        account_info(&mut routing, &routing_rx, client_mgr).mutations_done
+    );
+
+    // Delete an entry and check the vault reflects it.
+    let actions = EntryActions::new().del(b"key0".to_vec(), 1).into();
+    let msg_id = MessageId::new();
+    unwrap!(routing.mutate_mdata_entries(
+        client_mgr,
+        name,
+        tag,
+        actions,
+        msg_id,
+        owner_key,
+    ));
+    expect_success!(routing_rx, msg_id, Response::MutateMDataEntries);
+
+    inspect::assert_mdata_entry(&name, tag, b"key0", None);
+    inspect::assert_mdata_entry(&name, tag, b"key1", Some(&b"value1"[..]));
+}
+
 fn setup() -> (Routing, Receiver<Event>, FullId) {
     let full_id = FullId::new();
     let (routing_tx, routing_rx) = mpsc::channel();
@@ -1294,3 +1724,34 @@ fn account_info(
     unwrap!(routing.get_account_info(dst, msg_id));
     expect_success!(routing_rx, msg_id, Response::GetAccountInfo)
 }
+
+// Construct an account packet for a new random owner, optionally tied to the
+// given invitation.
+fn account_packet(
+    invitation: Option<&str>,
+) -> (Authority<XorName>, MutableData, sign::PublicKey) {
+    let (owner_key, _) = sign::gen_keypair();
+    let account_name = XorName(sha3_256(&owner_key[..]));
+
+    let packet = match invitation {
+        Some(invitation) => AccountPacket::WithInvitation {
+            invitation_string: invitation.to_owned(),
+            acc_pkt: Vec::new(),
+        },
+        None => AccountPacket::AccPkt(Vec::new()),
+    };
+    let entries = btree_map![
+        ACC_LOGIN_ENTRY_KEY.to_owned() => Value {
+            content: unwrap!(serialise(&packet)),
+            entry_version: 0,
+        }
+    ];
+
+    let data = unwrap!(MutableData::new(account_name,
+                                        TYPE_TAG_SESSION_PACKET,
+                                        Default::default(),
+                                        entries,
+                                        btree_set![owner_key]));
+
+    (Authority::ClientManager(account_name), data, owner_key)
+}
@@ -0,0 +1,171 @@
+// Copyright 2016 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under (1) the MaidSafe.net Commercial License,
+// version 1.0 or later, or (2) The General Public License (GPL), version 3, depending on which
+// licence you accepted on initial access to the Software (the "Licences").
+//
+// By contributing code to the SAFE Network Software, or to this project generally, you agree to be
+// bound by the terms of the MaidSafe Contributor Agreement.  This, along with the Licenses can be
+// found in the root directory of this project at LICENSE, COPYING and CONTRIBUTOR.
+//
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.
+//
+// Please review the Licences for the specific language governing permissions and limitations
+// relating to use of the SAFE Network Software.
+
+use maidsafe_utilities::serialisation::{SerialisationError, deserialise, serialise};
+use routing::{MessageId, Request, Response};
+use std::collections::{HashMap, VecDeque};
+use std::time::Instant;
+
+/// Request sent to the mock network and the response delivered for it.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct TraceEntry {
+    /// Id of the request message.
+    pub msg_id: MessageId,
+    /// The request.
+    pub request: Request,
+    /// Milliseconds since the start of the recording the request was sent at.
+    pub request_ms: u64,
+    /// The response, or `None` if none was delivered.
+    pub response: Option<Response>,
+    /// Milliseconds since the start of the recording the response was
+    /// delivered at.
+    pub response_ms: Option<u64>,
+}
+
+/// Trace of the traffic of the mock network.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct Trace {
+    /// Recorded requests in the order they were sent.
+    pub entries: Vec<TraceEntry>,
+}
+
+impl Trace {
+    /// Serialise the trace, e.g. to store it in a file.
+    pub fn to_bytes(&self) -> Result<Vec<u8>, SerialisationError> {
+        serialise(self)
+    }
+
+    /// Deserialise a trace previously serialised with `to_bytes`.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, SerialisationError> {
+        deserialise(bytes)
+    }
+}
+
+// Records the traffic into a trace.
+pub struct Recorder {
+    start: Instant,
+    trace: Trace,
+}
+
+impl Recorder {
+    pub fn new() -> Self {
+        Recorder {
+            start: Instant::now(),
+            trace: Trace::default(),
+        }
+    }
+
+    pub fn request(&mut self, request: &Request) {
+        let request_ms = self.elapsed_ms();
+        self.trace.entries.push(TraceEntry {
+            msg_id: *request.message_id(),
+            request: request.clone(),
+            request_ms: request_ms,
+            response: None,
+            response_ms: None,
+        });
+    }
+
+    pub fn response(&mut self, response: &Response) {
+        let response_ms = self.elapsed_ms();
+        let msg_id = *response.message_id();
+
+        // Message ids might be reused, so pick the latest request still
+        // waiting for the response.
+        let entry = self.trace.entries.iter_mut().rev().find(|entry| {
+            entry.msg_id == msg_id && entry.response.is_none()
+        });
+        if let Some(entry) = entry {
+            entry.response = Some(response.clone());
+            entry.response_ms = Some(response_ms);
+        }
+    }
+
+    pub fn into_trace(self) -> Trace {
+        self.trace
+    }
+
+    fn elapsed_ms(&self) -> u64 {
+        let elapsed = self.start.elapsed();
+        elapsed.as_secs() * 1000 + u64::from(elapsed.subsec_nanos()) / 1_000_000
+    }
+}
+
+// Feeds the responses of a recorded trace back as canned responses to the
+// matching requests, in the order they were recorded.
+pub struct Replayer {
+    responses: HashMap<Vec<u8>, VecDeque<Response>>,
+}
+
+impl Replayer {
+    pub fn new(trace: Trace) -> Self {
+        let mut responses = HashMap::new();
+
+        for entry in trace.entries {
+            if let Some(response) = entry.response {
+                responses
+                    .entry(request_key(&entry.request))
+                    .or_insert_with(VecDeque::new)
+                    .push_back(response);
+            }
+        }
+
+        Replayer { responses: responses }
+    }
+
+    // Canned response to the request, rewritten to its message id.
+    pub fn response(&mut self, request: &Request) -> Option<Response> {
+        let response = self.responses
+            .get_mut(&request_key(request))
+            .and_then(|responses| responses.pop_front());
+
+        response.and_then(|response| {
+            with_message_id(&response, *request.message_id())
+        })
+    }
+}
+
+// Message ids are different on every run, so requests are matched by their
+// serialised form with the message id blanked out.
+fn request_key(request: &Request) -> Vec<u8> {
+    let mut key = unwrap!(serialise(request));
+    let id = unwrap!(serialise(request.message_id()));
+    let blank = vec![0; id.len()];
+    replace_bytes(&mut key, &id, &blank);
+    key
+}
+
+fn with_message_id(response: &Response, msg_id: MessageId) -> Option<Response> {
+    let mut bytes = unwrap!(serialise(response));
+    let old_id = unwrap!(serialise(response.message_id()));
+    let new_id = unwrap!(serialise(&msg_id));
+    replace_bytes(&mut bytes, &old_id, &new_id);
+    deserialise(&bytes).ok()
+}
+
+// Replace the first occurrence of `from` in `bytes` with `to` of the same
+// length.
+fn replace_bytes(bytes: &mut [u8], from: &[u8], to: &[u8]) {
+    if from.is_empty() || bytes.len() < from.len() {
+        return;
+    }
+
+    let pos = (0..bytes.len() - from.len() + 1).find(|&pos| &bytes[pos..pos + from.len()] == from);
+    if let Some(pos) = pos {
+        bytes[pos..pos + to.len()].copy_from_slice(to);
+    }
+}
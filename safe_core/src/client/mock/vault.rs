@@ -17,14 +17,18 @@
 
 use super::Account;
 use super::DataId;
+#[cfg(feature = "unstable-data-types")]
+use appendable_data::AppendableData;
 use fs2::FileExt;
 use maidsafe_utilities::serialisation::{deserialise, serialise};
-use routing::{Authority, ClientError, ImmutableData, MutableData, XorName};
+use routing::{Authority, ClientError, EntryActions, ImmutableData, MutableData, XorName};
 use rust_sodium::crypto::sign;
 use std::collections::HashMap;
+#[cfg(any(test, feature = "testing"))]
+use std::collections::hash_map;
 use std::env;
 use std::fs::{File, OpenOptions};
-use std::io::{Read, Write};
+use std::io::{Read, Seek, SeekFrom, Write};
 use std::ops::{Deref, DerefMut};
 use std::path::PathBuf;
 use std::sync::{Mutex, MutexGuard};
@@ -33,6 +37,14 @@ use std::time::SystemTime;
 use tiny_keccak::sha3_256;
 
 const FILE_NAME: &'static str = "MockVault";
+// Environment variable overriding the path of the file the vault is stored in.
+const PATH_ENV_VAR: &'static str = "SAFE_MOCK_VAULT_PATH";
+
+/// Type tag of the `MutableData` representing an invitation token. Its name is
+/// the SHA3 hash of the invitation string.
+pub const INVITE_TOKEN_TYPE_TAG: u64 = 8;
+// Key of the entry marking the invitation as claimed.
+const INVITE_CLAIMED_KEY: &'static [u8] = b"claimed";
 
 pub struct Vault {
     cache: Cache,
@@ -40,18 +52,31 @@ pub struct Vault {
 }
 
 impl Vault {
+    // Create the vault, stored in memory if `SAFE_MEMORY_STORE` is set, or
+    // else in the file at `SAFE_MOCK_VAULT_PATH` (defaults to a file in the
+    // temp directory), so it survives restarts of the process.
     pub fn new() -> Self {
-        let store: Box<Store> = match env::var("SAFE_MEMORY_STORE") {
-            Ok(_) => {
-                trace!("Mock vault: using memory store");
-                Box::new(MemoryStore)
-            }
-            Err(_) => {
-                trace!("Mock vault: using file store");
-                Box::new(FileStore::new())
-            }
+        if env::var("SAFE_MEMORY_STORE").is_ok() {
+            trace!("Mock vault: using memory store");
+            return Self::with_store(Box::new(MemoryStore));
+        }
+
+        let path = match env::var_os(PATH_ENV_VAR) {
+            Some(path) => PathBuf::from(path),
+            None => env::temp_dir().join(FILE_NAME),
         };
+        Self::with_path(path)
+    }
+
+    // Create the vault stored in the file at `path`, loading any data already
+    // stored there.
+    pub fn with_path<P: Into<PathBuf>>(path: P) -> Self {
+        let path = path.into();
+        trace!("Mock vault: using file store at {:?}", path);
+        Self::with_store(Box::new(FileStore::new(path)))
+    }
 
+    fn with_store(store: Box<Store>) -> Self {
         Vault {
             cache: Cache {
                 client_manager: HashMap::new(),
@@ -91,11 +116,12 @@ impl Vault {
         }
     }
 
-    // Authorise mutation operation.
+    // Authorise mutation operation costing `cost` units of the balance.
     pub fn authorise_mutation(
         &self,
         dst: &Authority<XorName>,
         sign_pk: &sign::PublicKey,
+        cost: u64,
     ) -> Result<(), ClientError> {
         let dst_name = match *dst {
             Authority::ClientManager(name) => name,
@@ -120,19 +146,50 @@ impl Vault {
             return Err(ClientError::AccessDenied);
         }
 
-        if account.account_info().mutations_available == 0 {
+        if account.account_info().mutations_available < cost {
             return Err(ClientError::LowBalance);
         }
 
         Ok(())
     }
 
-    // Commit a mutation.
-    pub fn commit_mutation(&mut self, dst: &Authority<XorName>) {
+    // Commit a mutation, charging `cost` to the account.
+    pub fn commit_mutation(&mut self, dst: &Authority<XorName>, cost: u64) {
         {
             let account = unwrap!(self.get_account_mut(&dst.name()));
-            account.increment_mutations_counter();
+            account.charge_mutation(cost);
+        }
+    }
+
+    // Claim the invitation on behalf of the account being created by
+    // `claimant`, so the same invitation can't be used again.
+    pub fn claim_invitation(
+        &mut self,
+        invitation: &str,
+        claimant: sign::PublicKey,
+    ) -> Result<(), ClientError> {
+        let name = XorName(sha3_256(invitation.as_bytes()));
+        let data_id = DataId::mutable(name, INVITE_TOKEN_TYPE_TAG);
+
+        let mut data = match self.get_data(&data_id) {
+            Some(Data::Mutable(data)) => data,
+            _ => return Err(ClientError::InvalidInvitation),
+        };
+
+        if data.get(INVITE_CLAIMED_KEY).is_some() {
+            return Err(ClientError::InvitationAlreadyClaimed);
         }
+
+        let actions = EntryActions::new()
+            .ins(INVITE_CLAIMED_KEY.to_vec(), claimant[..].to_vec(), 0)
+            .into();
+        data.mutate_entries(actions, claimant).map_err(|err| {
+            debug!("Failed to claim invitation: {:?}", err);
+            ClientError::InvalidInvitation
+        })?;
+
+        self.insert_data(data_id, Data::Mutable(data));
+        Ok(())
     }
 
     // Check if data with the given name is in the storage.
@@ -149,6 +206,18 @@ impl Vault {
     pub fn insert_data(&mut self, name: DataId, data: Data) {
         let _ = self.cache.nae_manager.insert(name, data);
     }
+
+    // All the accounts in the storage, keyed by the name of their client manager.
+    #[cfg(any(test, feature = "testing"))]
+    pub fn accounts(&self) -> hash_map::Iter<XorName, Account> {
+        self.cache.client_manager.iter()
+    }
+
+    // All the data in the storage.
+    #[cfg(any(test, feature = "testing"))]
+    pub fn data(&self) -> hash_map::Iter<DataId, Data> {
+        self.cache.nae_manager.iter()
+    }
 }
 
 pub struct VaultGuard<'a>(MutexGuard<'a, Vault>);
@@ -193,6 +262,8 @@ struct Cache {
 pub enum Data {
     Immutable(ImmutableData),
     Mutable(MutableData),
+    #[cfg(feature = "unstable-data-types")]
+    Appendable(AppendableData),
 }
 
 trait Store: Send {
@@ -211,22 +282,20 @@ impl Store for MemoryStore {
 }
 
 struct FileStore {
+    path: PathBuf,
     // `bool` element indicates whether the store is being written to.
     file: Option<(File, bool)>,
     sync_time: Option<SystemTime>,
 }
 
 impl FileStore {
-    fn new() -> Self {
+    fn new(path: PathBuf) -> Self {
         FileStore {
+            path: path,
             file: None,
             sync_time: None,
         }
     }
-
-    fn path() -> PathBuf {
-        env::temp_dir().join(FILE_NAME)
-    }
 }
 
 impl Store for FileStore {
@@ -238,7 +307,7 @@ impl Store for FileStore {
                 .write(true)
                 .create(true)
                 .truncate(false)
-                .open(Self::path())
+                .open(&self.path)
         );
 
         if writing {
@@ -293,6 +362,7 @@ impl Store for FileStore {
             if writing {
                 let raw_data = unwrap!(serialise(&cache));
                 unwrap!(file.set_len(0));
+                unwrap!(file.seek(SeekFrom::Start(0)));
                 unwrap!(file.write_all(&raw_data));
                 unwrap!(file.sync_all());
 
@@ -305,3 +375,38 @@ impl Store for FileStore {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{self, Rng};
+    use std::fs;
+
+    // Test the data stored in the file survives restarts of the vault.
+    #[test]
+    fn file_persistence() {
+        let path = env::temp_dir().join(format!("MockVault-{}", rand::thread_rng().gen::<u64>()));
+        let name = rand::random();
+        let data = ImmutableData::new(vec![1, 2, 3]);
+        let data_id = DataId::immutable(*data.name());
+
+        {
+            let vault = Mutex::new(Vault::with_path(path.clone()));
+            let mut vault = lock(&vault, true);
+            vault.insert_account(name);
+            vault.insert_data(data_id, Data::Immutable(data.clone()));
+        }
+
+        {
+            let vault = Mutex::new(Vault::with_path(path.clone()));
+            let vault = lock(&vault, false);
+            assert!(vault.get_account(&name).is_some());
+            match vault.get_data(&data_id) {
+                Some(Data::Immutable(stored)) => assert_eq!(stored, data),
+                _ => panic!("Data not persisted"),
+            }
+        }
+
+        unwrap!(fs::remove_file(&path));
+    }
+}
@@ -17,55 +17,104 @@
 
 /// `MDataInfo` utilities.
 pub mod mdata_info;
+/// Mnemonic phrases for seed backup.
+pub mod mnemonic;
 /// Operations with recovery.
 pub mod recovery;
 
 mod account;
+mod balance_watch;
+mod batch;
+mod entry_pages;
+mod idle;
 #[cfg(feature = "use-mock-routing")]
 mod mock;
+mod network_events;
+mod pending;
+mod rate_limit;
+mod retry;
+mod scheduler;
 mod routing_event_loop;
+mod snapshot;
+mod watch;
 
 use self::account::Account;
+use audit::{AuditEntry, AuditLog, Target};
+use chrono::Utc;
+use config;
+use self::balance_watch::BalanceWatch;
+pub use self::batch::{Batch, BatchResponse};
+pub use self::entry_pages::MDataEntryPages;
+use self::idle::{IdleAction, IdleState};
+pub use self::idle::IdleConfig;
+#[cfg(feature = "unstable-data-types")]
+use appendable_data::{AppendableData, Filter};
 pub use self::account::ClientKeys;
 pub use self::mdata_info::MDataInfo;
+use self::network_events::NetworkEvents;
+use self::pending::PendingRequests;
+use self::rate_limit::TokenBucket;
+pub use self::rate_limit::RateLimit;
+pub use self::retry::RetryPolicy;
+use self::scheduler::Scheduler;
+pub use self::pending::{PendingRequest, RequestState};
+pub use self::scheduler::Priority;
+pub use self::snapshot::MDataSnapshot;
+pub use self::watch::MDataDiff;
 #[cfg(feature = "use-mock-routing")]
 use self::mock::Routing;
 #[cfg(feature = "use-mock-routing")]
 pub use self::mock::Routing as MockRouting;
-use crypto::{shared_box, shared_secretbox, shared_sign};
+#[cfg(feature = "use-mock-routing")]
+pub use self::mock::{ChurnConfig as MockChurnConfig, CostModel as MockCostModel,
+                     FaultKind as MockFaultKind, LatencyProfile as MockLatencyProfile,
+                     RequestKind as MockRequestKind, Trace as MockTrace,
+                     TraceEntry as MockTraceEntry};
+#[cfg(all(feature = "use-mock-routing", any(test, feature = "testing")))]
+pub use self::mock::{RequestHookBuilder as MockRequestHookBuilder,
+                     RequestLog as MockRequestLog};
+#[cfg(all(feature = "use-mock-routing", any(test, feature = "testing")))]
+pub use self::mock::inspect as mock_vault;
+#[cfg(feature = "use-mock-routing")]
+pub use self::mock::rng as mock_rng;
+use crypto::{hkdf, shared_box, shared_secretbox, shared_sign};
 use errors::CoreError;
-use event::{CoreEvent, NetworkEvent, NetworkTx};
+use event::{CoreEvent, NetworkEvent, NetworkEventStream, NetworkTx};
 use event_loop::{CoreFuture, CoreMsgTx};
-use futures::{Complete, Future};
+use futures::{Complete, Future, Stream, stream};
 use futures::future::{self, Either, FutureResult, Loop, Then};
 use futures::sync::oneshot;
 use ipc::BootstrapConfig;
 use lru_cache::LruCache;
 use maidsafe_utilities::serialisation::{deserialise, serialise};
 use maidsafe_utilities::thread::{self, Joiner};
-use routing::{ACC_LOGIN_ENTRY_KEY, AccountInfo, AccountPacket, Authority, EntryAction, Event,
-              FullId, ImmutableData, InterfaceError, MessageId, MutableData, PermissionSet,
-              Response, TYPE_TAG_SESSION_PACKET, User, Value, XorName};
+use metrics::{Metrics, NoopMetrics, Operation, SessionMetrics, SessionStats};
+use routing::{ACC_LOGIN_ENTRY_KEY, AccountInfo, AccountPacket, Authority, ClientError,
+              EntryAction, EntryActions, Event, FullId, ImmutableData, InterfaceError, MessageId,
+              MutableData, PermissionSet, Response, TYPE_TAG_SESSION_PACKET, User, Value,
+              XorName};
 #[cfg(not(feature = "use-mock-routing"))]
 use routing::Client as Routing;
 use rust_sodium::crypto::box_;
 use rust_sodium::crypto::sign::{self, Seed};
+use self_encryption_storage::ChunkCache;
 use std::cell::{Ref, RefCell, RefMut};
+use structures::owned_data;
 use std::collections::{BTreeMap, BTreeSet, HashMap};
 use std::fmt;
 use std::io;
 use std::rc::Rc;
 use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
-use std::time::Duration;
+use std::time::{Duration, Instant};
 use tiny_keccak::sha3_256;
 use tokio_core::reactor::{Handle, Timeout};
 use utils::{self, FutureExt};
 
-const CONNECTION_TIMEOUT_SECS: u64 = 40;
-const REQUEST_TIMEOUT_SECS: u64 = 180;
 const SEED_SUBPARTS: usize = 4;
-const IMMUT_DATA_CACHE_SIZE: usize = 300;
-const RETRY_DELAY_MS: u64 = 800;
+// Type tag of the session packet stored by `import_account`. The network treats a put of
+// `TYPE_TAG_SESSION_PACKET` as the creation of an account, so the restored account packet is
+// stored as ordinary data of the account it belongs to instead.
+const IMPORTED_SESSION_PACKET_TAG: u64 = 15_008;
 
 macro_rules! match_event {
     ($r:ident, $event:path) => {
@@ -81,7 +130,7 @@ macro_rules! match_event {
 
 macro_rules! wait_for_response {
     ($rx:expr, $res:path, $msg_id:expr) => {
-        match $rx.recv_timeout(Duration::from_secs(REQUEST_TIMEOUT_SECS)) {
+        match $rx.recv_timeout(config::get().get_request_timeout()) {
             Ok(Event::Response {
                 response: $res { res, msg_id: res_msg_id },
                 ..
@@ -115,15 +164,29 @@ pub struct Client<T> {
 
 struct Inner<T> {
     el_handle: Handle,
-    routing: Routing,
+    routing: Option<Routing>,
     hooks: HashMap<MessageId, Complete<CoreEvent>>,
+    pending: PendingRequests,
     cache: LruCache<XorName, ImmutableData>,
+    mdata_shell_cache: Option<LruCache<(XorName, u64), MutableData>>,
     client_type: ClientType,
     timeout: Duration,
     joiner: Joiner,
     session_packet_version: u64,
     core_tx: CoreMsgTx<T>,
     net_tx: NetworkTx,
+    net_events: NetworkEvents,
+    metrics: Rc<Metrics>,
+    session_metrics: SessionMetrics,
+    rate_limiter: Option<TokenBucket>,
+    retry_policy: Option<RetryPolicy>,
+    balance_watch: Option<BalanceWatch>,
+    audit_log: Option<Rc<AuditLog>>,
+    chunk_cache: Option<Rc<ChunkCache>>,
+    idle: IdleState,
+    reconnect_delay: Option<Duration>,
+    scheduler: Scheduler,
+    owned_data_index: bool,
 }
 
 impl<T> Clone for Client<T> {
@@ -145,19 +208,34 @@ impl<T: 'static> Client<T> {
         trace!("Creating unregistered client.");
 
         let (routing, routing_rx) = setup_routing(None, config.clone())?;
+        let (net_events, net_tx) = NetworkEvents::start(&el_handle, net_tx);
         let joiner = spawn_routing_thread(routing_rx, core_tx.clone(), net_tx.clone());
 
         Ok(Self::new(Inner {
             el_handle: el_handle,
-            routing: routing,
+            routing: Some(routing),
             hooks: HashMap::with_capacity(10),
-            cache: LruCache::new(IMMUT_DATA_CACHE_SIZE),
+            pending: PendingRequests::new(),
+            cache: LruCache::new(config::get().get_immut_data_cache_size()),
+            mdata_shell_cache: None,
             client_type: ClientType::unreg(config),
-            timeout: Duration::from_secs(REQUEST_TIMEOUT_SECS),
+            timeout: config::get().get_request_timeout(),
             joiner: joiner,
             session_packet_version: 0,
             net_tx: net_tx,
+            net_events: net_events,
             core_tx: core_tx,
+            metrics: Rc::new(NoopMetrics),
+            session_metrics: SessionMetrics::new(),
+            rate_limiter: None,
+            retry_policy: None,
+            balance_watch: None,
+            audit_log: None,
+            chunk_cache: None,
+            idle: IdleState::new(),
+            reconnect_delay: None,
+            scheduler: Scheduler::new(),
+            owned_data_index: false,
         }))
     }
 
@@ -187,19 +265,36 @@ impl<T: 'static> Client<T> {
         let arr = Self::divide_seed(seed)?;
 
         let id_seed = Seed(sha3_256(arr[SEED_SUBPARTS - 2]));
+        let acc = Account::new(ClientKeys::new(Some(&id_seed)))?;
 
         Self::registered_impl(
             arr[0],
             arr[1],
             "",
+            TYPE_TAG_SESSION_PACKET,
             el_handle,
             core_tx,
             net_tx,
-            Some(&id_seed),
+            acc,
             |routing| routing,
         )
     }
 
+    /// Same as `registered_with_seed`, but the seed is derived from a 24-word mnemonic phrase
+    /// (see the `mnemonic` module), so it can be backed up and restored in a standard way.
+    pub fn registered_from_seed(
+        mnemonic: &str,
+        el_handle: Handle,
+        core_tx: CoreMsgTx<T>,
+        net_tx: NetworkTx,
+    ) -> Result<Client<T>, CoreError>
+    where
+        T: 'static,
+    {
+        let seed = mnemonic::to_seed(mnemonic)?;
+        Self::registered_with_seed(&seed, el_handle, core_tx, net_tx)
+    }
+
     /// This is a Gateway function to the Maidsafe network. This will help
     /// create a fresh acc for the user in the SAFE-network.
     pub fn registered(
@@ -213,26 +308,69 @@ impl<T: 'static> Client<T> {
     where
         T: 'static,
     {
+        let acc = Account::new(ClientKeys::new(None))?;
+
         Self::registered_impl(acc_locator.as_bytes(),
                               acc_password.as_bytes(),
                               invitation,
+                              TYPE_TAG_SESSION_PACKET,
                               el_handle,
                               core_tx,
                               net_tx,
-                              None,
+                              acc,
                               |routing| routing)
     }
 
+    /// This is a Gateway function to the Maidsafe network. It restores an account from a
+    /// backup made with `export_account` (protected by `backup_password`) under a fresh pair
+    /// of credentials. The restored account keeps its keys, access container and config
+    /// root, so all data and app authorisations are available again. The credentials the
+    /// backup was made from are not affected.
+    ///
+    /// The account must still exist on the network: only a new session packet is stored
+    /// (and paid for) under the new credentials, the account itself isn't re-created.
+    pub fn import_account(
+        backup: &[u8],
+        backup_password: &str,
+        acc_locator: &str,
+        acc_password: &str,
+        el_handle: Handle,
+        core_tx: CoreMsgTx<T>,
+        net_tx: NetworkTx,
+    ) -> Result<Client<T>, CoreError>
+    where
+        T: 'static,
+    {
+        let acc = Account::import(backup, backup_password.as_bytes())?;
+
+        // No account is created, so there's no invitation to claim either.
+        Self::registered_impl(
+            acc_locator.as_bytes(),
+            acc_password.as_bytes(),
+            "",
+            IMPORTED_SESSION_PACKET_TAG,
+            el_handle,
+            core_tx,
+            net_tx,
+            acc,
+            |routing| routing,
+        )
+    }
+
     /// This is a Gateway function to the Maidsafe network. This will help
     /// create a fresh acc for the user in the SAFE-network.
+    ///
+    /// The session packet is put with `acc_tag`: `TYPE_TAG_SESSION_PACKET` creates the account,
+    /// while `IMPORTED_SESSION_PACKET_TAG` stores it as data of the (existing) account.
     fn registered_impl<F>(
         acc_locator: &[u8],
         acc_password: &[u8],
         invitation: &str,
+        acc_tag: u64,
         el_handle: Handle,
         core_tx: CoreMsgTx<T>,
         net_tx: NetworkTx,
-        id_seed: Option<&Seed>,
+        acc: Account,
         routing_wrapper_fn: F,
     ) -> Result<Client<T>, CoreError>
     where
@@ -246,15 +384,12 @@ impl<T: 'static> Client<T> {
         let acc_loc = Account::generate_network_id(&keyword, &pin)?;
         let user_cred = UserCred::new(password, pin);
 
-        let maid_keys = ClientKeys::new(id_seed);
-        let pub_key = maid_keys.sign_pk;
-        let full_id = Some(maid_keys.clone().into());
+        let pub_key = acc.maid_keys.sign_pk;
+        let full_id = Some(acc.maid_keys.clone().into());
 
         let (mut routing, routing_rx) = setup_routing(full_id, None)?;
         routing = routing_wrapper_fn(routing);
 
-        let acc = Account::new(maid_keys)?;
-
         let acc_ciphertext = acc.encrypt(&user_cred.password, &user_cred.pin)?;
         let acc_data =
             btree_map![
@@ -273,7 +408,7 @@ impl<T: 'static> Client<T> {
 
         let acc_md = MutableData::new(
             acc_loc,
-            TYPE_TAG_SESSION_PACKET,
+            acc_tag,
             BTreeMap::new(),
             acc_data,
             btree_set![pub_key],
@@ -295,19 +430,34 @@ impl<T: 'static> Client<T> {
             })?;
 
         // Create the client
+        let (net_events, net_tx) = NetworkEvents::start(&el_handle, net_tx);
         let joiner = spawn_routing_thread(routing_rx, core_tx.clone(), net_tx.clone());
 
         Ok(Self::new(Inner {
             el_handle: el_handle,
-            routing: routing,
+            routing: Some(routing),
             hooks: HashMap::with_capacity(10),
-            cache: LruCache::new(IMMUT_DATA_CACHE_SIZE),
-            client_type: ClientType::reg(acc, acc_loc, user_cred, cm_addr),
-            timeout: Duration::from_secs(REQUEST_TIMEOUT_SECS),
+            pending: PendingRequests::new(),
+            cache: LruCache::new(config::get().get_immut_data_cache_size()),
+            mdata_shell_cache: None,
+            client_type: ClientType::reg(acc, acc_loc, acc_tag, user_cred, cm_addr),
+            timeout: config::get().get_request_timeout(),
             joiner: joiner,
             session_packet_version: 0,
             net_tx: net_tx,
+            net_events: net_events,
             core_tx: core_tx,
+            metrics: Rc::new(NoopMetrics),
+            session_metrics: SessionMetrics::new(),
+            rate_limiter: None,
+            retry_policy: None,
+            balance_watch: None,
+            audit_log: None,
+            chunk_cache: None,
+            idle: IdleState::new(),
+            reconnect_delay: None,
+            scheduler: Scheduler::new(),
+            owned_data_index: false,
         }))
     }
 
@@ -332,6 +482,20 @@ impl<T: 'static> Client<T> {
         )
     }
 
+    /// Login to an account created with `registered_from_seed`.
+    pub fn login_from_seed(
+        mnemonic: &str,
+        el_handle: Handle,
+        core_tx: CoreMsgTx<T>,
+        net_tx: NetworkTx,
+    ) -> Result<Client<T>, CoreError>
+    where
+        T: 'static,
+    {
+        let seed = mnemonic::to_seed(mnemonic)?;
+        Self::login_with_seed(&seed, el_handle, core_tx, net_tx)
+    }
+
     /// This is a Gateway function to the Maidsafe network. This will help
     /// login to an already existing account of the user in the SAFE-network.
     pub fn login(
@@ -373,29 +537,35 @@ impl<T: 'static> Client<T> {
 
         let dst = Authority::NaeManager(acc_loc);
 
-        let (acc_content, acc_version) = {
+        let (acc_content, acc_version, acc_tag) = {
             trace!("Creating throw-away routing getter for account packet.");
             let (mut routing, routing_rx) = setup_routing(None, None)?;
             routing = routing_wrapper_fn(routing);
 
-            let msg_id = MessageId::new();
-            let val = routing
-                .get_mdata_value(
-                    dst,
-                    acc_loc,
-                    TYPE_TAG_SESSION_PACKET,
-                    ACC_LOGIN_ENTRY_KEY.to_owned(),
-                    msg_id,
-                )
-                .map_err(CoreError::from)
-                .and_then(|_| {
-                    wait_for_response!(routing_rx, Response::GetMDataValue, msg_id)
-                })
-                .map_err(|e| {
-                    warn!("Could not fetch account from the Network: {:?}", e);
-                    e
-                })?;
-            (val.content, val.entry_version)
+            let mut get_value = |tag| {
+                let msg_id = MessageId::new();
+                routing
+                    .get_mdata_value(dst, acc_loc, tag, ACC_LOGIN_ENTRY_KEY.to_owned(), msg_id)
+                    .map_err(CoreError::from)
+                    .and_then(|_| {
+                        wait_for_response!(routing_rx, Response::GetMDataValue, msg_id)
+                    })
+            };
+
+            // Credentials an account was imported under have their session
+            // packet stored with a different tag.
+            let (val, tag) = match get_value(TYPE_TAG_SESSION_PACKET) {
+                Err(CoreError::RoutingClientError(ClientError::NoSuchAccount)) |
+                Err(CoreError::RoutingClientError(ClientError::NoSuchData)) => {
+                    (get_value(IMPORTED_SESSION_PACKET_TAG), IMPORTED_SESSION_PACKET_TAG)
+                }
+                res => (res, TYPE_TAG_SESSION_PACKET),
+            };
+            let val = val.map_err(|e| {
+                warn!("Could not fetch account from the Network: {:?}", e);
+                e
+            })?;
+            (val.content, val.entry_version, tag)
         };
 
         let acc = match deserialise::<AccountPacket>(&acc_content)? {
@@ -415,19 +585,34 @@ impl<T: 'static> Client<T> {
         let (mut routing, routing_rx) = setup_routing(Some(id_packet), None)?;
         routing = routing_wrapper_fn(routing);
 
+        let (net_events, net_tx) = NetworkEvents::start(&el_handle, net_tx);
         let joiner = spawn_routing_thread(routing_rx, core_tx.clone(), net_tx.clone());
 
         Ok(Self::new(Inner {
             el_handle: el_handle,
-            routing: routing,
+            routing: Some(routing),
             hooks: HashMap::with_capacity(10),
-            cache: LruCache::new(IMMUT_DATA_CACHE_SIZE),
-            client_type: ClientType::reg(acc, acc_loc, user_cred, cm_addr),
-            timeout: Duration::from_secs(REQUEST_TIMEOUT_SECS),
+            pending: PendingRequests::new(),
+            cache: LruCache::new(config::get().get_immut_data_cache_size()),
+            mdata_shell_cache: None,
+            client_type: ClientType::reg(acc, acc_loc, acc_tag, user_cred, cm_addr),
+            timeout: config::get().get_request_timeout(),
             joiner: joiner,
             session_packet_version: acc_version,
             net_tx: net_tx,
+            net_events: net_events,
             core_tx: core_tx,
+            metrics: Rc::new(NoopMetrics),
+            session_metrics: SessionMetrics::new(),
+            rate_limiter: None,
+            retry_policy: None,
+            balance_watch: None,
+            audit_log: None,
+            chunk_cache: None,
+            idle: IdleState::new(),
+            reconnect_delay: None,
+            scheduler: Scheduler::new(),
+            owned_data_index: false,
         }))
     }
 
@@ -469,19 +654,34 @@ impl<T: 'static> Client<T> {
         let (mut routing, routing_rx) =
             setup_routing(Some(keys.clone().into()), Some(config.clone()))?;
         routing = routing_wrapper_fn(routing);
+        let (net_events, net_tx) = NetworkEvents::start(&el_handle, net_tx);
         let joiner = spawn_routing_thread(routing_rx, core_tx.clone(), net_tx.clone());
 
         Ok(Self::new(Inner {
             el_handle: el_handle,
-            routing: routing,
+            routing: Some(routing),
             hooks: HashMap::with_capacity(10),
-            cache: LruCache::new(IMMUT_DATA_CACHE_SIZE),
+            pending: PendingRequests::new(),
+            cache: LruCache::new(config::get().get_immut_data_cache_size()),
+            mdata_shell_cache: None,
             client_type: ClientType::from_keys(keys, owner, config),
-            timeout: Duration::from_secs(REQUEST_TIMEOUT_SECS),
+            timeout: config::get().get_request_timeout(),
             joiner: joiner,
             session_packet_version: 0,
             net_tx: net_tx,
+            net_events: net_events,
             core_tx: core_tx,
+            metrics: Rc::new(NoopMetrics),
+            session_metrics: SessionMetrics::new(),
+            rate_limiter: None,
+            retry_policy: None,
+            balance_watch: None,
+            audit_log: None,
+            chunk_cache: None,
+            idle: IdleState::new(),
+            reconnect_delay: None,
+            scheduler: Scheduler::new(),
+            owned_data_index: false,
         }))
     }
 
@@ -521,6 +721,90 @@ impl<T: 'static> Client<T> {
         self.inner_mut().timeout = duration;
     }
 
+    /// Issue the requests made by `f` with the given timeout, restoring the
+    /// previous timeout afterwards. Requests not responded to in time fail
+    /// with `CoreError::RequestTimeout` carrying their message id.
+    pub fn with_timeout<F, R>(&self, duration: Duration, f: F) -> R
+    where
+        F: FnOnce(&Self) -> R,
+    {
+        let previous = self.inner().timeout;
+        self.set_timeout(duration);
+        let result = f(self);
+        self.set_timeout(previous);
+        result
+    }
+
+    /// Limit the rate the mutation requests are sent at, to stay under the
+    /// rate limits of the network. Requests over the limit are delayed rather
+    /// than sent and rejected. `None` removes the limit.
+    pub fn set_rate_limit(&self, limit: Option<RateLimit>) {
+        self.inner_mut().rate_limiter = limit.map(TokenBucket::new);
+    }
+
+    /// Set the maximum number of `ImmutableData` kept in the cache.
+    pub fn set_idata_cache_capacity(&self, capacity: usize) {
+        self.inner_mut().cache.set_capacity(capacity);
+    }
+
+    /// Cache up to `capacity` `MutableData` shells fetched with
+    /// `get_mdata_shell`, so fetching them again doesn't hit the network. A
+    /// cached shell is evicted when this client mutates the data, but changes
+    /// made by other clients aren't noticed. `None` disables the cache.
+    pub fn set_mdata_shell_cache(&self, capacity: Option<usize>) {
+        self.inner_mut().mdata_shell_cache = capacity.map(LruCache::new);
+    }
+
+    /// Retry the requests failed with transient errors according to the given
+    /// policy (see `RetryPolicy` for which requests are retried). `None`
+    /// disables the retries.
+    pub fn set_retry_policy(&self, policy: Option<RetryPolicy>) {
+        self.inner_mut().retry_policy = policy;
+    }
+
+    /// Record every mutation request sent by this client in the given audit
+    /// log. `None` stops the recording.
+    pub fn set_audit_log(&self, audit_log: Option<AuditLog>) {
+        self.inner_mut().audit_log = audit_log.map(Rc::new);
+    }
+
+    /// Returns the audit log the mutation requests are recorded in, if any.
+    pub fn audit_log(&self) -> Option<Rc<AuditLog>> {
+        self.inner().audit_log.clone()
+    }
+
+    /// Use the given index of uploaded chunks when writing files, so chunks
+    /// which are already in the network are not uploaded again. `None`
+    /// disables it.
+    pub fn set_chunk_cache(&self, chunk_cache: Option<ChunkCache>) {
+        self.inner_mut().chunk_cache = chunk_cache.map(Rc::new);
+    }
+
+    /// Returns the index of uploaded chunks, if any.
+    pub fn chunk_cache(&self) -> Option<Rc<ChunkCache>> {
+        self.inner().chunk_cache.clone()
+    }
+
+    /// Record the `MutableData` put by this client in the owned-data index of
+    /// the account (see `structures::owned_data`). Each recording costs an
+    /// extra mutation, so it's disabled by default.
+    pub fn set_owned_data_index(&self, enabled: bool) {
+        self.inner_mut().owned_data_index = enabled;
+    }
+
+    /// Raise `NetworkEvent::LowBalance` through the network observer when the
+    /// number of mutations available to the account drops below `threshold`.
+    /// The balance is refreshed in the background after mutations. `None`
+    /// disables the warning.
+    pub fn set_low_balance_threshold(&self, threshold: Option<u64>) {
+        self.inner_mut().balance_watch = threshold.map(BalanceWatch::new);
+    }
+
+    /// Requests sent to the network and not yet completed, oldest first.
+    pub fn pending_requests(&self) -> Vec<PendingRequest> {
+        self.inner().pending.list()
+    }
+
     /// Restart the routing client and reconnect to the network.
     pub fn restart_routing(&self) -> Result<(), CoreError> {
         let opt_id = match self.inner().client_type {
@@ -538,16 +822,170 @@ impl<T: 'static> Client<T> {
         );
 
         self.inner_mut().hooks.clear();
-        self.inner_mut().routing = routing;
+        self.inner_mut().pending.clear();
+        self.inner_mut().routing = Some(routing);
         self.inner_mut().joiner = joiner;
+        self.inner_mut().idle.parked = false;
 
         self.inner().net_tx.unbounded_send(NetworkEvent::Connected)?;
 
         Ok(())
     }
 
+    /// Limit the number of requests in flight to `max`, queueing the others
+    /// by their priority. `None` removes the limit.
+    pub fn set_max_requests_in_flight(&self, max: Option<usize>) {
+        self.inner().scheduler.set_max_in_flight(max);
+    }
+
+    /// Set the priority of the requests issued from now on.
+    pub fn set_priority(&self, priority: Priority) {
+        self.inner().scheduler.set_priority(priority);
+    }
+
+    /// Issue the requests made by `f` with the given priority, restoring the
+    /// previous priority afterwards.
+    pub fn with_priority<F, R>(&self, priority: Priority, f: F) -> R
+    where
+        F: FnOnce(&Self) -> R,
+    {
+        let previous = self.inner().scheduler.priority();
+        self.set_priority(priority);
+        let result = f(self);
+        self.set_priority(previous);
+        result
+    }
+
+    /// Stream of the network events of the client occurring from now on. They
+    /// are delivered to the network observer the client was created with too.
+    pub fn network_events(&self) -> NetworkEventStream {
+        self.inner().net_events.subscribe()
+    }
+
+    /// Reconnect automatically, after the given delay, when the network asks
+    /// the client to restart. The disconnection and the reconnection are
+    /// reported to the network observer. Requests sent in the meantime
+    /// reconnect immediately. `None` disables the reconnection.
+    pub fn set_auto_reconnect(&self, delay: Option<Duration>) {
+        self.inner_mut().reconnect_delay = delay;
+    }
+
+    #[doc(hidden)]
+    pub fn restart_required(&self) {
+        let delay = match self.inner().reconnect_delay {
+            Some(delay) => delay,
+            None => {
+                debug!("Restart required, but the automatic reconnection is disabled.");
+                return;
+            }
+        };
+
+        trace!("Restart required, reconnecting in {:?}.", delay);
+        // Park the connection, so the next request reconnects right away. The
+        // routing client is dropped outside of the borrow.
+        let routing = {
+            let mut inner = self.inner_mut();
+            inner.idle.parked = true;
+            inner.routing.take()
+        };
+        drop(routing);
+
+        let client = self.clone();
+        let fut = self.delay(delay)
+            .and_then(move |()| if client.inner().idle.parked {
+                client.restart_routing()
+            } else {
+                Ok(())
+            })
+            .map_err(|error| debug!("Reconnection failed: {:?}", error));
+        self.inner().el_handle.spawn(fut);
+    }
+
+    /// Configure the keep-alive pings and parking of the idle connection.
+    /// Parking is reported to the network observer as `Disconnected`, and
+    /// resuming on the next request as `Connected`.
+    pub fn set_idle_config(&self, config: IdleConfig) {
+        let generation = {
+            let mut inner = self.inner_mut();
+            inner.idle.config = config;
+            inner.idle.generation += 1;
+            inner.idle.generation
+        };
+        let interval = match config.check_interval() {
+            Some(interval) => interval,
+            None => return,
+        };
+
+        let inner = Rc::downgrade(&self.inner);
+        let el_handle = self.inner().el_handle.clone();
+
+        let fut = future::loop_fn((), move |()| {
+            let inner = inner.clone();
+            let timeout = match Timeout::new(interval, &el_handle) {
+                Ok(timeout) => timeout,
+                Err(error) => {
+                    debug!("Timeout create error: {:?}", error);
+                    return future::ok::<_, io::Error>(Loop::Break(())).into_box();
+                }
+            };
+
+            timeout
+                .map(move |()| {
+                    let client = match inner.upgrade() {
+                        Some(inner) => Client { inner },
+                        None => return Loop::Break(()),
+                    };
+                    if client.inner().idle.generation != generation {
+                        return Loop::Break(());
+                    }
+
+                    client.check_idle();
+                    Loop::Continue(())
+                })
+                .into_box()
+        }).map_err(|error| debug!("Idle check failed: {:?}", error));
+
+        self.inner().el_handle.spawn(fut);
+    }
+
+    /// Returns the totals of the network operations performed during this
+    /// session. Unlike the metrics sink, these are always collected.
+    pub fn stats(&self) -> SessionStats {
+        self.inner().session_metrics.stats()
+    }
+
+    /// Install the sink the metrics of the network operations are reported to.
+    pub fn set_metrics<M: Metrics + 'static>(&self, metrics: M) {
+        self.inner_mut().metrics = Rc::new(metrics);
+    }
+
+    /// Returns a future which resolves once the given duration elapses.
+    pub fn delay(&self, duration: Duration) -> Box<CoreFuture<()>> {
+        let timeout = match Timeout::new(duration, &self.inner().el_handle) {
+            Ok(timeout) => timeout,
+            Err(err) => {
+                return err!(CoreError::Unexpected(
+                    format!("Timeout create error: {:?}", err),
+                ))
+            }
+        };
+
+        timeout
+            .map_err(|err| {
+                CoreError::Unexpected(format!("Timeout fire error {:?}", err))
+            })
+            .into_box()
+    }
+
     #[doc(hidden)]
     pub fn fire_hook(&self, id: &MessageId, event: CoreEvent) {
+        // The request rejected due to rate limiting stays pending until resent.
+        if let CoreEvent::RateLimitExceeded = event {
+            self.inner_mut().pending.set_state(id, RequestState::RateLimited);
+        } else {
+            self.inner_mut().pending.remove(id);
+        }
+
         // Using in `if` keeps borrow alive. Do not try to combine the 2 lines into one.
         let opt = self.inner_mut().hooks.remove(id);
         if let Some(hook) = opt {
@@ -587,7 +1025,7 @@ impl<T: 'static> Client<T> {
         }
 
         let inner = Rc::downgrade(&self.inner);
-        self.send(move |routing, msg_id| {
+        let fut = self.send(Operation::GetIData, move |routing, msg_id| {
             routing.get_idata(Authority::NaeManager(name), name, msg_id)
         }).and_then(|event| match_event!(event, CoreEvent::GetIData))
             .map(move |data| {
@@ -597,7 +1035,8 @@ impl<T: 'static> Client<T> {
                 }
                 data
             })
-            .into_box()
+            .into_box();
+        self.measure(Operation::GetIData, fut, |data| data.value().len() as u64)
     }
 
     // TODO All these return the same future from all branches. So convert to impl
@@ -607,7 +1046,9 @@ impl<T: 'static> Client<T> {
     pub fn put_idata(&self, data: ImmutableData) -> Box<CoreFuture<()>> {
         trace!("PutIData for {:?}", data);
 
-        self.send_mutation(move |routing, dst, msg_id| {
+        let bytes = data.value().len() as u64;
+        let target = Target::ImmutableData(*data.name());
+        self.send_mutation(Operation::PutIData, target, bytes, move |routing, dst, msg_id| {
             routing.put_idata(dst, data.clone(), msg_id)
         })
     }
@@ -617,9 +1058,56 @@ impl<T: 'static> Client<T> {
         trace!("PutMData for {:?}", data);
 
         let requester = fry!(self.public_signing_key());
-        self.send_mutation(move |routing, dst, msg_id| {
+        let bytes = mdata_size(&data);
+        let name = *data.name();
+        let tag = data.tag();
+        let client = self.clone();
+
+        let target = Target::MutableData(name, tag);
+        let op = Operation::PutMData;
+        let fut = self.send_mutation(op, target, bytes, move |routing, dst, msg_id| {
             routing.put_mdata(dst, data.clone(), msg_id, requester)
-        })
+        });
+
+        // Session packets and the index itself aren't user data.
+        let internal = tag == TYPE_TAG_SESSION_PACKET || tag == IMPORTED_SESSION_PACKET_TAG ||
+            tag == owned_data::OWNED_DATA_TAG;
+        if internal || !self.inner().owned_data_index {
+            return fut;
+        }
+
+        // Recording the data in the owned-data index is best effort - the put
+        // itself has already succeeded.
+        fut.and_then(move |()| {
+            owned_data::record(&client, name, tag).or_else(move |error| {
+                warn!("Failed to record {:?} in the owned data index: {:?}", name, error);
+                Ok(())
+            })
+        }).into_box()
+    }
+
+    /// Put new private `MutableData` owned by this client onto the network.
+    /// The entries are given in plain text and are encrypted with freshly
+    /// generated keys. The returned `MDataInfo` holds the keys needed to read
+    /// the data and should be kept, e.g. in a container of the user.
+    pub fn create_private_mdata(
+        &self,
+        type_tag: u64,
+        permissions: BTreeMap<User, PermissionSet>,
+        entries: BTreeMap<Vec<u8>, Value>,
+    ) -> Box<CoreFuture<MDataInfo>> {
+        let info = fry!(MDataInfo::random_private(type_tag));
+        let entries = fry!(mdata_info::encrypt_entries(&info, &entries));
+        let owner_key = fry!(self.owner_key());
+        let data = fry!(MutableData::new(
+            info.name,
+            info.type_tag,
+            permissions,
+            entries,
+            btree_set![owner_key],
+        ));
+
+        self.put_mdata(data).map(move |_| info).into_box()
     }
 
     /// Mutates `MutableData` entries in bulk.
@@ -632,39 +1120,189 @@ impl<T: 'static> Client<T> {
         trace!("PutMData for {:?}", name);
 
         let requester = fry!(self.public_signing_key());
-        self.send_mutation(move |routing, dst, msg_id| {
+        let bytes = actions_size(&actions);
+        let target = Target::MutableData(name, tag);
+        let op = Operation::MutateMDataEntries;
+        self.send_mutation(op, target, bytes, move |routing, dst, msg_id| {
             routing.mutate_mdata_entries(dst, name, tag, actions.clone(), msg_id, requester)
         })
     }
 
+    /// Mutates entries of the `MutableData` with the actions `f` computes from its current
+    /// entries. When the mutation conflicts with a concurrent one (e.g. an entry version is no
+    /// longer the successor of the current one), the entries are fetched again and `f`
+    /// re-applied to them, up to `CoreConfig::get_transact_attempts` times in total.
+    pub fn mutate_mdata_transact<F>(&self, name: XorName, tag: u64, f: F) -> Box<CoreFuture<()>>
+    where
+        F: Fn(&BTreeMap<Vec<u8>, Value>) -> EntryActions + 'static,
+    {
+        trace!("Transactionally mutating MData entries of {:?}", name);
+
+        let client = self.clone();
+        let f = Rc::new(f);
+
+        future::loop_fn(1, move |attempt| {
+            let client2 = client.clone();
+            let f = Rc::clone(&f);
+
+            client
+                .list_mdata_entries(name, tag)
+                .and_then(move |entries| {
+                    client2.mutate_mdata_entries(name, tag, f(&entries).into())
+                })
+                .then(move |res| match res {
+                    Ok(()) => Ok(Loop::Break(())),
+                    Err(CoreError::RoutingClientError(ClientError::InvalidEntryActions(_))) |
+                    Err(CoreError::RoutingClientError(ClientError::InvalidSuccessor(_)))
+                        if attempt < config::get().get_transact_attempts() => {
+                        debug!("MData {:?} modified concurrently, retrying.", name);
+                        Ok(Loop::Continue(attempt + 1))
+                    }
+                    Err(error) => Err(error),
+                })
+        }).into_box()
+    }
+
+    /// Fetches the entries of `MutableData` together with the version of its shell, for
+    /// changes to be committed with `commit_snapshot`.
+    pub fn get_mdata_snapshot(&self, name: XorName, tag: u64) -> Box<CoreFuture<MDataSnapshot>> {
+        self.get_mdata(name, tag)
+            .map(|data| MDataSnapshot::new(&data))
+            .into_box()
+    }
+
+    /// Mutates entries of the `MutableData` the snapshot was taken of, unless it has changed
+    /// since. The versions of the actions are set from the snapshot (see
+    /// `MDataSnapshot::rebase_actions`), so the mutation is rejected with `InvalidEntryActions`
+    /// if any of the touched entries was modified. If the shell was modified, the mutation is
+    /// not attempted and `InvalidSuccessor` with the current shell version is returned.
+    pub fn commit_snapshot(
+        &self,
+        snapshot: &MDataSnapshot,
+        actions: BTreeMap<Vec<u8>, EntryAction>,
+    ) -> Box<CoreFuture<()>> {
+        trace!("Committing MData snapshot of {:?}", snapshot.name);
+
+        let name = snapshot.name;
+        let tag = snapshot.tag;
+        let version = snapshot.version;
+        let actions = snapshot.rebase_actions(actions);
+        let client = self.clone();
+
+        self.get_mdata_version(name, tag)
+            .and_then(move |current_version| if current_version == version {
+                client.mutate_mdata_entries(name, tag, actions)
+            } else {
+                err!(CoreError::RoutingClientError(
+                    ClientError::InvalidSuccessor(current_version),
+                ))
+            })
+            .into_box()
+    }
+
+    /// Poll the entries of the `MutableData` every `interval` and stream the changes since the
+    /// previous poll. Polls which find no changes yield nothing. Mutating the entries doesn't
+    /// bump the version of the shell, so the entries are listed on every poll rather than only
+    /// when the shell version changes. The stream ends with the first failed poll; dropping
+    /// it stops the polling.
+    pub fn watch_mdata(
+        &self,
+        name: XorName,
+        tag: u64,
+        interval: Duration,
+    ) -> Box<Stream<Item = MDataDiff, Error = CoreError>> {
+        trace!("Watching MData {:?}", name);
+
+        let client = self.clone();
+
+        let stream = self.list_mdata_entries(name, tag)
+            .map(move |entries| {
+                stream::unfold(entries, move |last| {
+                    let client = client.clone();
+
+                    let poll = future::loop_fn(last, move |last| {
+                        let client2 = client.clone();
+
+                        client
+                            .delay(interval)
+                            .and_then(move |()| client2.list_mdata_entries(name, tag))
+                            .map(move |current| {
+                                let diff = MDataDiff::between(&last, &current);
+                                if diff.is_empty() {
+                                    Loop::Continue(current)
+                                } else {
+                                    Loop::Break((diff, current))
+                                }
+                            })
+                    });
+
+                    Some(poll)
+                })
+            })
+            .flatten_stream();
+
+        Box::new(stream)
+    }
+
     /// Get entire `MutableData` from the network.
     pub fn get_mdata(&self, name: XorName, tag: u64) -> Box<CoreFuture<MutableData>> {
         trace!("GetMData for {:?}", name);
 
-        self.send(move |routing, msg_id| {
+        let fut = self.send(Operation::GetMData, move |routing, msg_id| {
             routing.get_mdata(Authority::NaeManager(name), name, tag, msg_id)
         }).and_then(|event| match_event!(event, CoreEvent::GetMData))
-            .into_box()
+            .into_box();
+        self.measure(Operation::GetMData, fut, mdata_size)
     }
 
     /// Get a shell (bare bones) version of `MutableData` from the network.
     pub fn get_mdata_shell(&self, name: XorName, tag: u64) -> Box<CoreFuture<MutableData>> {
         trace!("GetMDataShell for {:?}", name);
 
-        self.send(move |routing, msg_id| {
+        if let Some(shell) = self.inner_mut().mdata_shell_cache.as_mut().and_then(
+            |cache| cache.get_mut(&(name, tag)),
+        )
+        {
+            trace!("MutableData shell found in cache.");
+            return future::ok(shell.clone()).into_box();
+        }
+
+        let inner = Rc::downgrade(&self.inner);
+        let fut = self.send(Operation::GetMDataShell, move |routing, msg_id| {
             routing.get_mdata_shell(Authority::NaeManager(name), name, tag, msg_id)
         }).and_then(|event| match_event!(event, CoreEvent::GetMDataShell))
-            .into_box()
+            .map(move |shell| {
+                if let Some(inner) = inner.upgrade() {
+                    if let Some(ref mut cache) = inner.borrow_mut().mdata_shell_cache {
+                        let _ = cache.insert((name, tag), shell.clone());
+                    }
+                }
+                shell
+            })
+            .into_box();
+        self.measure(Operation::GetMDataShell, fut, |_| 0)
+    }
+
+    /// Pipeline the requests added to the batch by `f`, and wait for the
+    /// responses to all of them.
+    pub fn batch<F>(&self, f: F) -> Box<CoreFuture<Vec<Result<BatchResponse, CoreError>>>>
+    where
+        F: FnOnce(&mut Batch<T>),
+    {
+        let mut batch = Batch::new(self);
+        f(&mut batch);
+        batch.responses()
     }
 
     /// Get a current version of `MutableData` from the network.
     pub fn get_mdata_version(&self, name: XorName, tag: u64) -> Box<CoreFuture<u64>> {
         trace!("GetMDataVersion for {:?}", name);
 
-        self.send(move |routing, msg_id| {
+        let fut = self.send(Operation::GetMDataVersion, move |routing, msg_id| {
             routing.get_mdata_version(Authority::NaeManager(name), name, tag, msg_id)
         }).and_then(|event| match_event!(event, CoreEvent::GetMDataVersion))
-            .into_box()
+            .into_box();
+        self.measure(Operation::GetMDataVersion, fut, |_| 0)
     }
 
     /// Returns a complete list of entries in `MutableData`.
@@ -675,19 +1313,117 @@ impl<T: 'static> Client<T> {
     ) -> Box<CoreFuture<BTreeMap<Vec<u8>, Value>>> {
         trace!("ListMDataEntries for {:?}", name);
 
-        self.send(move |routing, msg_id| {
+        let fut = self.send(Operation::ListMDataEntries, move |routing, msg_id| {
             routing.list_mdata_entries(Authority::NaeManager(name), name, tag, msg_id)
         }).and_then(|event| match_event!(event, CoreEvent::ListMDataEntries))
-            .into_box()
+            .into_box();
+        self.measure(Operation::ListMDataEntries, fut, entries_size)
     }
 
     /// Returns a list of keys in `MutableData` stored on the network
     pub fn list_mdata_keys(&self, name: XorName, tag: u64) -> Box<CoreFuture<BTreeSet<Vec<u8>>>> {
         trace!("ListMDataKeys for {:?}", name);
 
-        self.send(move |routing, msg_id| {
+        let fut = self.send(Operation::ListMDataKeys, move |routing, msg_id| {
             routing.list_mdata_keys(Authority::NaeManager(name), name, tag, msg_id)
         }).and_then(|event| match_event!(event, CoreEvent::ListMDataKeys))
+            .into_box();
+        self.measure(Operation::ListMDataKeys, fut, keys_size)
+    }
+
+    /// Returns up to `limit` entries of `MutableData`, in the order of their
+    /// keys, starting at `start_key` (or the first key if `None`), along with
+    /// the key the next page starts at (`None` if this is the last page).
+    /// `limit` must be greater than zero. Deleted entries are skipped, so a
+    /// page can hold fewer than `limit` entries even if it's not the last one.
+    ///
+    /// The network can't return a range of entries, so the paging is done on
+    /// the client side: every call lists all the keys and then fetches the
+    /// values of the keys in the page. To go through all the pages, use
+    /// `list_mdata_entry_pages` instead, which lists the keys only once.
+    pub fn list_mdata_entries_range(
+        &self,
+        name: XorName,
+        tag: u64,
+        start_key: Option<Vec<u8>>,
+        limit: usize,
+    ) -> Box<CoreFuture<(BTreeMap<Vec<u8>, Value>, Option<Vec<u8>>)>> {
+        trace!("ListMDataEntriesRange for {:?}", name);
+
+        if limit == 0 {
+            return err!(zero_page_limit());
+        }
+
+        let client = self.clone();
+
+        self.list_mdata_entry_pages(name, tag, start_key)
+            .and_then(move |pages| client.next_mdata_entry_page(pages, limit))
+            .map(|(entries, pages)| (entries, pages.next_key().cloned()))
+            .into_box()
+    }
+
+    /// Start listing the entries of `MutableData` page by page, from
+    /// `start_key` (or the first key if `None`). All the keys are listed once
+    /// here, the pages are then fetched with `next_mdata_entry_page`.
+    pub fn list_mdata_entry_pages(
+        &self,
+        name: XorName,
+        tag: u64,
+        start_key: Option<Vec<u8>>,
+    ) -> Box<CoreFuture<MDataEntryPages>> {
+        self.list_mdata_keys(name, tag)
+            .map(move |keys| {
+                let keys = keys.into_iter()
+                    .filter(|key| {
+                        start_key.as_ref().map_or(true, |start_key| key >= start_key)
+                    })
+                    .collect();
+
+                MDataEntryPages { name, tag, keys }
+            })
+            .into_box()
+    }
+
+    /// Fetch the next page of up to `limit` entries, in the order of their
+    /// keys, returning it together with the remaining pages. Deleted entries,
+    /// including those deleted since the keys were listed, are skipped, so a
+    /// page can hold fewer than `limit` entries even if it's not the last one.
+    /// `limit` must be greater than zero.
+    pub fn next_mdata_entry_page(
+        &self,
+        mut pages: MDataEntryPages,
+        limit: usize,
+    ) -> Box<CoreFuture<(BTreeMap<Vec<u8>, Value>, MDataEntryPages)>> {
+        if limit == 0 {
+            return err!(zero_page_limit());
+        }
+
+        let name = pages.name;
+        let tag = pages.tag;
+
+        let values: Vec<_> = pages
+            .take_page(limit)
+            .into_iter()
+            .map(|key| {
+                self.get_mdata_value(name, tag, key.clone()).then(
+                    move |res| match res {
+                        Ok(value) => Ok(if value.content.is_empty() {
+                            None
+                        } else {
+                            Some((key, value))
+                        }),
+                        // Deleted since the keys were listed.
+                        Err(CoreError::RoutingClientError(ClientError::NoSuchEntry)) => Ok(None),
+                        Err(error) => Err(error),
+                    },
+                )
+            })
+            .collect();
+
+        future::join_all(values)
+            .map(move |entries| {
+                (entries.into_iter().filter_map(|entry| entry).collect(), pages)
+            })
             .into_box()
     }
 
@@ -695,20 +1431,22 @@ impl<T: 'static> Client<T> {
     pub fn list_mdata_values(&self, name: XorName, tag: u64) -> Box<CoreFuture<Vec<Value>>> {
         trace!("ListMDataValues for {:?}", name);
 
-        self.send(move |routing, msg_id| {
+        let fut = self.send(Operation::ListMDataValues, move |routing, msg_id| {
             routing.list_mdata_values(Authority::NaeManager(name), name, tag, msg_id)
         }).and_then(|event| match_event!(event, CoreEvent::ListMDataValues))
-            .into_box()
+            .into_box();
+        self.measure(Operation::ListMDataValues, fut, values_size)
     }
 
     /// Get a single entry from `MutableData`
     pub fn get_mdata_value(&self, name: XorName, tag: u64, key: Vec<u8>) -> Box<CoreFuture<Value>> {
         trace!("GetMDataValue for {:?}", name);
 
-        self.send(move |routing, msg_id| {
+        let fut = self.send(Operation::GetMDataValue, move |routing, msg_id| {
             routing.get_mdata_value(Authority::NaeManager(name), name, tag, key.clone(), msg_id)
         }).and_then(|event| match_event!(event, CoreEvent::GetMDataValue))
-            .into_box()
+            .into_box();
+        self.measure(Operation::GetMDataValue, fut, |value| value.content.len() as u64)
     }
 
     /// Get data from the network.
@@ -716,9 +1454,11 @@ impl<T: 'static> Client<T> {
         trace!("Account info GET issued.");
 
         let dst = fry!(self.cm_addr());
-        self.send(move |routing, msg_id| routing.get_account_info(dst, msg_id))
-            .and_then(|event| match_event!(event, CoreEvent::GetAccountInfo))
-            .into_box()
+        let fut = self.send(Operation::GetAccountInfo, move |routing, msg_id| {
+            routing.get_account_info(dst, msg_id)
+        }).and_then(|event| match_event!(event, CoreEvent::GetAccountInfo))
+            .into_box();
+        self.measure(Operation::GetAccountInfo, fut, |_| 0)
     }
 
     /// Returns a list of permissions in `MutableData` stored on the network
@@ -729,10 +1469,11 @@ impl<T: 'static> Client<T> {
     ) -> Box<CoreFuture<BTreeMap<User, PermissionSet>>> {
         trace!("ListMDataPermissions for {:?}", name);
 
-        self.send(move |routing, msg_id| {
+        let fut = self.send(Operation::ListMDataPermissions, move |routing, msg_id| {
             routing.list_mdata_permissions(Authority::NaeManager(name), name, tag, msg_id)
         }).and_then(|event| match_event!(event, CoreEvent::ListMDataPermissions))
-            .into_box()
+            .into_box();
+        self.measure(Operation::ListMDataPermissions, fut, |_| 0)
     }
 
     /// Returns a list of permissions for a particular User in MutableData
@@ -744,13 +1485,14 @@ impl<T: 'static> Client<T> {
     ) -> Box<CoreFuture<PermissionSet>> {
         trace!("ListMDataUserPermissions for {:?}", name);
 
-        self.send(move |routing, msg_id| {
+        let fut = self.send(Operation::ListMDataUserPermissions, move |routing, msg_id| {
             let dst = Authority::NaeManager(name);
             routing.list_mdata_user_permissions(dst, name, tag, user, msg_id)
         }).and_then(|event| {
                 match_event!(event, CoreEvent::ListMDataUserPermissions)
             })
-            .into_box()
+            .into_box();
+        self.measure(Operation::ListMDataUserPermissions, fut, |_| 0)
     }
 
     /// Updates or inserts a permission set for a given user
@@ -765,7 +1507,9 @@ impl<T: 'static> Client<T> {
         trace!("SetMDataUserPermissions for {:?}", name);
 
         let requester = fry!(self.public_signing_key());
-        self.send_mutation(move |routing, dst, msg_id| {
+        let target = Target::MutableData(name, tag);
+        let op = Operation::SetMDataUserPermissions;
+        self.send_mutation(op, target, 0, move |routing, dst, msg_id| {
             routing.set_mdata_user_permissions(
                 dst,
                 name,
@@ -790,7 +1534,9 @@ impl<T: 'static> Client<T> {
         trace!("DelMDataUserPermissions for {:?}", name);
 
         let requester = fry!(self.public_signing_key());
-        self.send_mutation(move |routing, dst, msg_id| {
+        let target = Target::MutableData(name, tag);
+        let op = Operation::DelMDataUserPermissions;
+        self.send_mutation(op, target, 0, move |routing, dst, msg_id| {
             routing.del_mdata_user_permissions(dst, name, tag, user, version, msg_id, requester)
         })
     }
@@ -805,7 +1551,8 @@ impl<T: 'static> Client<T> {
     ) -> Box<CoreFuture<()>> {
         trace!("ChangeMDataOwner for {:?}", name);
 
-        self.send_mutation(move |routing, dst, msg_id| {
+        let target = Target::MutableData(name, tag);
+        self.send_mutation(Operation::ChangeMDataOwner, target, 0, move |routing, dst, msg_id| {
             routing.change_mdata_owner(dst, name, tag, btree_set![new_owner], version, msg_id)
         })
     }
@@ -815,11 +1562,26 @@ impl<T: 'static> Client<T> {
         trace!("ListAuthKeysAndVersion");
 
         let dst = fry!(self.cm_addr());
-        self.send(move |routing, msg_id| {
+        let fut = self.send(Operation::ListAuthKeysAndVersion, move |routing, msg_id| {
             routing.list_auth_keys_and_version(dst, msg_id)
         }).and_then(|event| {
                 match_event!(event, CoreEvent::ListAuthKeysAndVersion)
             })
+            .into_box();
+        self.measure(Operation::ListAuthKeysAndVersion, fut, |_| 0)
+    }
+
+    /// Checks whether the key of this app is still among the keys authorised
+    /// by the owner of the account. The owner itself is always authorised.
+    pub fn is_app_authorised(&self) -> Box<CoreFuture<bool>> {
+        let sign_pk = match self.inner().client_type {
+            ClientType::FromKeys { ref keys, .. } => keys.sign_pk,
+            ClientType::Registered { .. } => return ok!(true),
+            ClientType::Unregistered { .. } => return err!(CoreError::OperationForbidden),
+        };
+
+        self.list_auth_keys_and_version()
+            .map(move |(keys, _)| keys.contains(&sign_pk))
             .into_box()
     }
 
@@ -827,7 +1589,7 @@ impl<T: 'static> Client<T> {
     pub fn ins_auth_key(&self, key: sign::PublicKey, version: u64) -> Box<CoreFuture<()>> {
         trace!("InsAuthKey ({:?})", key);
 
-        self.send_mutation(move |routing, dst, msg_id| {
+        self.send_mutation(Operation::InsAuthKey, Target::Account, 0, move |routing, dst, msg_id| {
             routing.ins_auth_key(dst, key, version, msg_id)
         })
     }
@@ -836,7 +1598,7 @@ impl<T: 'static> Client<T> {
     pub fn del_auth_key(&self, key: sign::PublicKey, version: u64) -> Box<CoreFuture<()>> {
         trace!("DelAuthKey ({:?})", key);
 
-        self.send_mutation(move |routing, dst, msg_id| {
+        self.send_mutation(Operation::DelAuthKey, Target::Account, 0, move |routing, dst, msg_id| {
             routing.del_auth_key(dst, key, version, msg_id)
         })
     }
@@ -945,6 +1707,24 @@ impl<T: 'static> Client<T> {
         self.inner().client_type.secret_symmetric_key()
     }
 
+    /// Derives a symmetric key for the given `context` and `index` from the
+    /// secret signing key using HKDF. The same key is derived every time for
+    /// the same account (or app), so apps can partition their encryption
+    /// domains without storing the keys.
+    pub fn derive_subkey(
+        &self,
+        context: &[u8],
+        index: u64,
+    ) -> Result<shared_secretbox::Key, CoreError> {
+        let sk = self.secret_signing_key()?;
+
+        let mut info = context.to_vec();
+        info.extend((0..8).rev().map(|i| (index >> (i * 8)) as u8));
+
+        let key = hkdf::derive(&sk.0, &info);
+        Ok(shared_secretbox::Key::from_raw(&key))
+    }
+
     /// Returns the public and secret signing keys.
     pub fn signing_keypair(&self) -> Result<(sign::PublicKey, shared_sign::SecretKey), CoreError> {
         let inner = self.inner();
@@ -1001,27 +1781,173 @@ impl<T: 'static> Client<T> {
         };
 
         let data_name = fry!(self.inner().client_type.acc_loc());
+        let tag = fry!(self.inner().client_type.acc_tag());
+
+        self.mutate_mdata_entries(data_name, tag, update)
+    }
 
-        self.mutate_mdata_entries(data_name, TYPE_TAG_SESSION_PACKET, update)
+    /// Exports the account (its keys, access container and config root) as a versioned blob
+    /// encrypted with `password`, which can be restored with `import_account`.
+    pub fn export_account(&self, password: &str) -> Result<Vec<u8>, CoreError> {
+        self.inner().client_type.acc()?.export(password.as_bytes())
+    }
+
+    /// Changes the account password.
+    ///
+    /// The session packet is fetched, decrypted with the current credentials and re-encrypted
+    /// in place under the new password, so the account itself (its balance and the keys of
+    /// the authorised apps) is left untouched. The locator can't be changed: it determines
+    /// the location of the session packet, and the network only accepts a session packet at
+    /// a new location when a new account is being created.
+    pub fn change_password(&self, new_password: &str) -> Box<CoreFuture<()>> {
+        trace!("Changing account password.");
+
+        let (acc_loc, acc_tag, old_password, pin) = {
+            let inner = self.inner();
+            let cred = fry!(inner.client_type.user_cred());
+            (
+                fry!(inner.client_type.acc_loc()),
+                fry!(inner.client_type.acc_tag()),
+                cred.password.clone(),
+                cred.pin.clone(),
+            )
+        };
+        let new_cred = UserCred::new(utils::derive_password(new_password.as_bytes()), pin.clone());
+
+        let client = self.clone();
+        let client2 = self.clone();
+
+        self.get_mdata_value(acc_loc, acc_tag, ACC_LOGIN_ENTRY_KEY.to_owned())
+            .and_then(move |value| -> Result<_, CoreError> {
+                let acc = match deserialise::<AccountPacket>(&value.content)? {
+                    AccountPacket::AccPkt(acc_content) |
+                    AccountPacket::WithInvitation { acc_pkt: acc_content, .. } => {
+                        Account::decrypt(&acc_content, &old_password, &pin)?
+                    }
+                };
+                let acc_ciphertext = acc.encrypt(&new_cred.password, &new_cred.pin)?;
+                let content = serialise(&AccountPacket::AccPkt(acc_ciphertext))?;
+
+                Ok((content, value.entry_version + 1, new_cred))
+            })
+            .and_then(move |(content, entry_version, new_cred)| {
+                let actions = btree_map![
+                    ACC_LOGIN_ENTRY_KEY.to_owned() => EntryAction::Update(Value {
+                        content,
+                        entry_version,
+                    })
+                ];
+                client
+                    .mutate_mdata_entries(acc_loc, acc_tag, actions)
+                    .map(move |()| (entry_version, new_cred))
+            })
+            .and_then(move |(entry_version, new_cred)| -> Result<_, CoreError> {
+                let mut inner = client2.inner_mut();
+                inner.client_type.set_user_cred(new_cred)?;
+                inner.session_packet_version = entry_version;
+                Ok(())
+            })
+            .into_box()
     }
 
     /// Sends a request and returns a future that resolves to the response.
-    fn send<F>(&self, req: F) -> Box<CoreFuture<CoreEvent>>
+    fn send<F>(&self, op: Operation, req: F) -> Box<CoreFuture<CoreEvent>>
     where
         F: Fn(&mut Routing, MessageId) -> Result<(), InterfaceError> + 'static,
     {
-        let inner = Rc::downgrade(&self.inner);
-        let func = move |_| if let Some(inner) = inner.upgrade() {
-            let msg_id = MessageId::new();
-            if let Err(error) = req(&mut inner.borrow_mut().routing, msg_id) {
-                return future::err(CoreError::from(error)).into_box();
+        self.send_retrying(op, true, req)
+    }
+
+    /// Sends a request, retrying it according to the retry policy if it's
+    /// `idempotent`.
+    fn send_retrying<F>(
+        &self,
+        op: Operation,
+        idempotent: bool,
+        req: F,
+    ) -> Box<CoreFuture<CoreEvent>>
+    where
+        F: Fn(&mut Routing, MessageId) -> Result<(), InterfaceError> + 'static,
+    {
+        fry!(self.mark_active());
+
+        let policy = match self.inner().retry_policy {
+            Some(policy) if idempotent => policy,
+            _ => return self.send_request(op, 1, req),
+        };
+
+        let req = Rc::new(req);
+        let client = self.clone();
+        // The retries are sent with the priority and timeout of the original
+        // request.
+        let priority = self.inner().scheduler.priority();
+        let duration = self.inner().timeout;
+
+        future::loop_fn(1, move |attempt| {
+            let client2 = client.clone();
+            let req = Rc::clone(&req);
+
+            client
+                .with_priority(priority, |client| {
+                    client.with_timeout(duration, |client| {
+                        client.send_request(op, attempt, move |routing, msg_id| {
+                            req(routing, msg_id)
+                        })
+                    })
+                })
+                .then(move |res| {
+                    let transient = match res {
+                        Ok(ref event) => {
+                            retry::event_error(event).map_or(false, retry::is_transient)
+                        }
+                        Err(ref error) => retry::is_transient(error),
+                    };
+
+                    if transient && attempt < policy.max_attempts {
+                        debug!("Retrying the request failed with a transient error: {:?}", res);
+                        client2
+                            .delay(policy.backoff(attempt))
+                            .map(move |()| Loop::Continue(attempt + 1))
+                            .into_box()
+                    } else {
+                        future::result(res.map(Loop::Break)).into_box()
+                    }
+                })
+        }).into_box()
+    }
+
+    /// Sends a request without counting it as an activity of the user.
+    fn send_request<F>(&self, op: Operation, attempt: u32, req: F) -> Box<CoreFuture<CoreEvent>>
+    where
+        F: Fn(&mut Routing, MessageId) -> Result<(), InterfaceError> + 'static,
+    {
+        let inner = Rc::downgrade(&self.inner);
+        let duration = self.inner().timeout;
+        let func = move |_| if let Some(inner) = inner.upgrade() {
+            let msg_id = MessageId::new();
+            let res = match inner.borrow_mut().routing {
+                Some(ref mut routing) => req(routing, msg_id).map_err(CoreError::from),
+                None => Err(CoreError::OperationAborted),
+            };
+            if let Err(error) = res {
+                return future::err(error).into_box();
             }
 
             let (hook, rx) = oneshot::channel();
             let _ = inner.borrow_mut().hooks.insert(msg_id, hook);
 
+            let now = Instant::now();
+            inner.borrow_mut().pending.insert(PendingRequest {
+                msg_id,
+                operation: op,
+                attempt,
+                sent_at: now,
+                deadline: now + duration,
+                state: RequestState::AwaitingResponse,
+            });
+
             let rx = rx.map_err(|_| CoreError::OperationAborted);
-            let rx = setup_timeout_and_retry_delay(&inner, msg_id, rx);
+            let rx = setup_timeout_and_retry_delay(&inner, msg_id, duration, rx);
             let rx = rx.map(|event| if let CoreEvent::RateLimitExceeded = event {
                 Loop::Continue(())
             } else {
@@ -1032,19 +1958,231 @@ impl<T: 'static> Client<T> {
             future::err(CoreError::OperationAborted).into_box()
         };
 
-        future::loop_fn((), func).into_box()
+        // Wait for a free slot before sending the request, then hold it until
+        // the request is finished or dropped.
+        let priority = self.inner().scheduler.priority();
+        let slot = self.inner().scheduler.acquire(priority);
+
+        slot.map_err(|_| CoreError::OperationAborted)
+            .and_then(move |slot| {
+                future::loop_fn((), func).then(move |res| {
+                    drop(slot);
+                    res
+                })
+            })
+            .into_box()
     }
 
-    /// Sends a mutation request.
-    fn send_mutation<F>(&self, req: F) -> Box<CoreFuture<()>>
+    /// Sends a mutation request, reporting `bytes` as its size to the metrics
+    /// and recording it in the audit log.
+    fn send_mutation<F>(
+        &self,
+        op: Operation,
+        target: Target,
+        bytes: u64,
+        req: F,
+    ) -> Box<CoreFuture<()>>
     where
         F: Fn(&mut Routing, Authority<XorName>, MessageId) -> Result<(), InterfaceError> + 'static,
     {
         let dst = fry!(self.cm_addr());
 
-        self.send(move |routing, msg_id| req(routing, dst, msg_id))
+        let wait = self.inner_mut().rate_limiter.as_mut().map(|bucket| {
+            bucket.reserve(Instant::now())
+        });
+        let pacing = match wait {
+            Some(wait) if wait > Duration::from_secs(0) => self.delay(wait),
+            _ => future::ok(()).into_box(),
+        };
+
+        let audit_log = self.inner().audit_log.clone();
+        let duration = self.inner().timeout;
+        let priority = self.inner().scheduler.priority();
+        let client = self.clone();
+        let fut = pacing
+            .and_then(move |()| {
+                // Sent with the timeout and priority in effect when the
+                // mutation was issued.
+                client.with_timeout(duration, |client| {
+                    client.with_priority(priority, |client| {
+                        let versioned = retry::is_versioned(op);
+                        client.send_retrying(op, versioned, move |routing, msg_id| {
+                            req(routing, dst, msg_id)?;
+
+                            if let Some(ref audit_log) = audit_log {
+                                let entry = AuditEntry {
+                                    operation: op,
+                                    target,
+                                    timestamp: Utc::now(),
+                                    msg_id,
+                                };
+                                if let Err(error) = audit_log.record(entry) {
+                                    warn!(
+                                        "Failed to record {:?} in the audit log: {:?}",
+                                        op,
+                                        error
+                                    );
+                                }
+                            }
+
+                            Ok(())
+                        })
+                    })
+                })
+            })
             .and_then(|event| match_event!(event, CoreEvent::Mutation))
-            .into_box()
+            .into_box();
+        let client = self.clone();
+        let client2 = self.clone();
+        let mut fut = fut.map(move |()| client.watch_balance())
+            .map_err(move |error| {
+                if let CoreError::RoutingClientError(ClientError::AccessDenied) = error {
+                    client2.check_revoked();
+                }
+                error
+            })
+            .into_box();
+
+        // Evict the cached shell both when the mutation is sent and when it
+        // completes, so a fetch racing it can't leave a stale shell behind.
+        if let Target::MutableData(name, tag) = target {
+            self.evict_mdata_shell(name, tag);
+            let client = self.clone();
+            fut = fut.then(move |res| {
+                client.evict_mdata_shell(name, tag);
+                res
+            }).into_box();
+        }
+
+        self.measure(op, fut, move |_| bytes)
+    }
+
+    fn evict_mdata_shell(&self, name: XorName, tag: u64) {
+        if let Some(ref mut cache) = self.inner_mut().mdata_shell_cache {
+            let _ = cache.remove(&(name, tag));
+        }
+    }
+
+    /// Records an activity of the user, resuming the parked connection.
+    fn mark_active(&self) -> Result<(), CoreError> {
+        let parked = {
+            let mut inner = self.inner_mut();
+            inner.idle.activity(Instant::now());
+            inner.idle.parked
+        };
+
+        if parked {
+            trace!("Resuming the parked connection.");
+            self.restart_routing()
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Pings or parks the connection if it's been idle long enough.
+    fn check_idle(&self) {
+        let action = {
+            let mut inner = self.inner_mut();
+            let busy = !inner.hooks.is_empty();
+            inner.idle.check(Instant::now(), busy)
+        };
+
+        match action {
+            IdleAction::None => (),
+            IdleAction::Ping => {
+                let dst = match self.cm_addr() {
+                    Ok(dst) => dst,
+                    Err(_) => return,
+                };
+                let fut = self.send_request(Operation::GetAccountInfo, 1, move |routing, msg_id| {
+                    routing.get_account_info(dst, msg_id)
+                }).map(|_| ())
+                    .map_err(|error| debug!("Keep-alive ping failed: {:?}", error));
+                self.inner().el_handle.spawn(fut);
+            }
+            IdleAction::Park => {
+                trace!("Parking the idle connection.");
+                // Drop the routing client outside of the borrow.
+                let routing = self.inner_mut().routing.take();
+                drop(routing);
+            }
+        }
+    }
+
+    /// Refreshes the account balance in the background if due, and raises the
+    /// low balance warning if it's below the threshold.
+    fn watch_balance(&self) {
+        let refresh = match self.inner_mut().balance_watch {
+            Some(ref mut watch) => watch.mutation_done(),
+            None => false,
+        };
+        if !refresh {
+            return;
+        }
+
+        let client = self.clone();
+        let fut = self.get_account_info()
+            .map(move |info| {
+                let warning = client.inner_mut().balance_watch.as_mut().and_then(|watch| {
+                    watch.refreshed(info.mutations_available)
+                });
+
+                if let Some(mutations_available) = warning {
+                    let event = NetworkEvent::LowBalance(mutations_available);
+                    if let Err(error) = client.inner().net_tx.unbounded_send(event) {
+                        debug!("Couldn't send NetworkEvent::LowBalance: {:?}", error);
+                    }
+                }
+            })
+            .map_err(|error| debug!("Failed to refresh the account balance: {:?}", error));
+
+        self.inner().el_handle.spawn(fut);
+    }
+
+    /// Raises `NetworkEvent::AppRevoked` if the key of this app is no longer
+    /// authorised, after a mutation has been denied.
+    fn check_revoked(&self) {
+        match self.inner().client_type {
+            ClientType::FromKeys { .. } => (),
+            _ => return,
+        }
+
+        let net_tx = self.inner().net_tx.clone();
+        let fut = self.is_app_authorised()
+            .map(move |authorised| if !authorised {
+                if let Err(error) = net_tx.unbounded_send(NetworkEvent::AppRevoked) {
+                    debug!("Couldn't send NetworkEvent::AppRevoked: {:?}", error);
+                }
+            })
+            .map_err(|error| debug!("Failed to check the authorisation of the app: {:?}", error));
+
+        self.inner().el_handle.spawn(fut);
+    }
+
+    /// Reports the request to the metrics sink and the session statistics.
+    fn measure<U, F>(&self, op: Operation, fut: Box<CoreFuture<U>>, bytes: F) -> Box<CoreFuture<U>>
+    where
+        U: 'static,
+        F: FnOnce(&U) -> u64 + 'static,
+    {
+        let metrics = Rc::clone(&self.inner().metrics);
+        let session_metrics = self.inner().session_metrics.clone();
+        let start = Instant::now();
+        metrics.request_started(op);
+        session_metrics.request_started(op);
+
+        fut.then(move |res| {
+            let latency = start.elapsed();
+            {
+                let (bytes, result) = match res {
+                    Ok(ref value) => (bytes(value), Ok(())),
+                    Err(ref error) => (0, Err(error)),
+                };
+                metrics.request_completed(op, bytes, latency, result);
+                session_metrics.request_completed(op, bytes, latency, result);
+            }
+            res
+        }).into_box()
     }
 
     fn inner(&self) -> Ref<Inner<T>> {
@@ -1061,9 +2199,100 @@ impl<T: 'static> Client<T> {
 }
 
 
+/// Operations on the experimental `AppendableData` type. These are served by
+/// the mock vault only.
+#[cfg(feature = "unstable-data-types")]
+impl<T: 'static> Client<T> {
+    /// Put `AppendableData` onto the network.
+    pub fn put_adata(&self, data: AppendableData) -> Box<CoreFuture<()>> {
+        trace!("PutAData for {:?}", data);
+
+        let dst = fry!(self.cm_addr());
+        let res = fry!(self.with_routing(move |routing| routing.put_adata(dst, data)));
+        future::result(res.map_err(CoreError::RoutingClientError)).into_box()
+    }
+
+    /// Get `AppendableData` from the network.
+    pub fn get_adata(&self, name: XorName, tag: u64) -> Box<CoreFuture<AppendableData>> {
+        trace!("GetAData for {:?}", name);
+
+        let res = fry!(self.with_routing(move |routing| routing.get_adata(name, tag)));
+        future::result(res.map_err(CoreError::RoutingClientError)).into_box()
+    }
+
+    /// Append an entry to `AppendableData`.
+    pub fn append_adata(&self, name: XorName, tag: u64, content: Vec<u8>) -> Box<CoreFuture<()>> {
+        trace!("AppendAData for {:?}", name);
+
+        let requester = fry!(self.public_signing_key());
+        let dst = fry!(self.cm_addr());
+        let res = fry!(self.with_routing(move |routing| {
+            routing.append_adata(dst, name, tag, content, requester)
+        }));
+        future::result(res.map_err(CoreError::RoutingClientError)).into_box()
+    }
+
+    /// Replace the filter of `AppendableData`.
+    pub fn set_adata_filter(&self, name: XorName, tag: u64, filter: Filter) -> Box<CoreFuture<()>> {
+        trace!("SetADataFilter for {:?}", name);
+
+        let requester = fry!(self.public_signing_key());
+        let dst = fry!(self.cm_addr());
+        let res = fry!(self.with_routing(move |routing| {
+            routing.set_adata_filter(dst, name, tag, filter, requester)
+        }));
+        future::result(res.map_err(CoreError::RoutingClientError)).into_box()
+    }
+
+    /// Remove all entries from `AppendableData`.
+    pub fn clear_adata(&self, name: XorName, tag: u64) -> Box<CoreFuture<()>> {
+        trace!("ClearAData for {:?}", name);
+
+        let requester = fry!(self.public_signing_key());
+        let dst = fry!(self.cm_addr());
+        let res = fry!(self.with_routing(move |routing| {
+            routing.clear_adata(dst, name, tag, requester)
+        }));
+        future::result(res.map_err(CoreError::RoutingClientError)).into_box()
+    }
+
+    // Calls `f` with the routing client, resuming the parked connection first.
+    fn with_routing<F, R>(&self, f: F) -> Result<R, CoreError>
+    where
+        F: FnOnce(&mut Routing) -> R,
+    {
+        self.mark_active()?;
+        let mut inner = self.inner_mut();
+        let routing = inner.routing.as_mut().ok_or(CoreError::OperationAborted)?;
+        Ok(f(routing))
+    }
+}
+
 #[cfg(any(all(test, feature = "use-mock-routing"),
             all(feature = "testing", feature = "use-mock-routing")))]
 impl<T: 'static> Client<T> {
+    /// Create a new account with random credentials in the mock network, so
+    /// tests don't need to go through the authenticator. Invitations aren't
+    /// checked by the mock network.
+    pub fn registered_test_account(
+        el_handle: Handle,
+        core_tx: CoreMsgTx<T>,
+        net_tx: NetworkTx,
+    ) -> Result<Client<T>, CoreError> {
+        let acc_locator = utils::generate_random_string(10)?;
+        let acc_password = utils::generate_random_string(10)?;
+        let invitation = utils::generate_random_string(10)?;
+
+        Self::registered(
+            &acc_locator,
+            &acc_password,
+            &invitation,
+            el_handle,
+            core_tx,
+            net_tx,
+        )
+    }
+
     /// Allows customising the mock Routing client before registering a new account
     pub fn registered_with_hook<F>(
         acc_locator: &str,
@@ -1078,14 +2307,17 @@ impl<T: 'static> Client<T> {
         T: 'static,
         F: Fn(Routing) -> Routing,
     {
+        let acc = Account::new(ClientKeys::new(None))?;
+
         Self::registered_impl(
             acc_locator.as_bytes(),
             acc_password.as_bytes(),
             invitation,
+            TYPE_TAG_SESSION_PACKET,
             el_handle,
             core_tx,
             net_tx,
-            None,
+            acc,
             routing_wrapper_fn,
         )
     }
@@ -1115,21 +2347,30 @@ impl<T: 'static> Client<T> {
 
     #[doc(hidden)]
     pub fn set_network_limits(&self, max_ops_count: Option<u64>) {
-        self.inner.borrow_mut().routing.set_network_limits(
-            max_ops_count,
-        );
+        if let Some(ref mut routing) = self.inner.borrow_mut().routing {
+            routing.set_network_limits(max_ops_count);
+        }
     }
 
     #[doc(hidden)]
     pub fn simulate_network_disconnect(&self) {
-        self.inner.borrow_mut().routing.simulate_disconnect();
+        if let Some(ref routing) = self.inner.borrow().routing {
+            routing.simulate_disconnect();
+        }
     }
 
     #[doc(hidden)]
     pub fn set_simulate_timeout(&self, enabled: bool) {
-        self.inner.borrow_mut().routing.set_simulate_timeout(
-            enabled,
-        );
+        if let Some(ref mut routing) = self.inner.borrow_mut().routing {
+            routing.set_simulate_timeout(enabled);
+        }
+    }
+
+    #[doc(hidden)]
+    pub fn simulate_churn(&self, config: MockChurnConfig) {
+        if let Some(ref mut routing) = self.inner.borrow_mut().routing {
+            routing.simulate_churn(config);
+        }
     }
 }
 
@@ -1142,6 +2383,7 @@ impl<T> fmt::Debug for Client<T> {
 fn setup_timeout_and_retry_delay<T, F>(
     inner: &Rc<RefCell<Inner<T>>>,
     msg_id: MessageId,
+    duration: Duration,
     future: F,
 ) -> Box<CoreFuture<CoreEvent>>
 where
@@ -1153,8 +2395,14 @@ where
     let future = future.and_then(move |event| {
         if let CoreEvent::RateLimitExceeded = event {
             if let Some(inner) = inner_weak.upgrade() {
-                let delay = Duration::from_millis(RETRY_DELAY_MS);
-                let fut = timeout(delay, &inner.borrow().el_handle).or_else(move |_| Ok(event));
+                let delay = config::get().get_retry_delay();
+                let fut = timeout(delay, &inner.borrow().el_handle).or_else(move |_| {
+                    // Resent under a new message id.
+                    if let Some(inner) = inner_weak.upgrade() {
+                        inner.borrow_mut().pending.remove(&msg_id);
+                    }
+                    Ok(event)
+                });
                 return Either::A(fut);
             }
         }
@@ -1163,14 +2411,21 @@ where
     });
 
     // Fail if no response received within the timeout.
-    let duration = inner.borrow().timeout;
     let inner_weak = Rc::downgrade(inner);
     let timeout = timeout(duration, &inner.borrow().el_handle).then(move |result| {
         if let Some(inner) = inner_weak.upgrade() {
-            let _ = inner.borrow_mut().hooks.remove(&msg_id);
+            let mut inner = inner.borrow_mut();
+            let _ = inner.hooks.remove(&msg_id);
+            inner.pending.remove(&msg_id);
         }
 
-        result
+        match result {
+            Err(CoreError::RequestTimeout(None)) => {
+                debug!("Request {:?} timed out.", msg_id);
+                Err(CoreError::RequestTimeout(Some(msg_id)))
+            }
+            result => result,
+        }
     });
 
     future
@@ -1182,6 +2437,42 @@ where
         .into_box()
 }
 
+// Empty pages would never get past the start key.
+fn zero_page_limit() -> CoreError {
+    CoreError::Unexpected("The limit of entries per page must be greater than zero".to_string())
+}
+
+fn entries_size(entries: &BTreeMap<Vec<u8>, Value>) -> u64 {
+    entries
+        .iter()
+        .map(|(key, value)| (key.len() + value.content.len()) as u64)
+        .sum()
+}
+
+fn keys_size(keys: &BTreeSet<Vec<u8>>) -> u64 {
+    keys.iter().map(|key| key.len() as u64).sum()
+}
+
+#[cfg_attr(feature = "cargo-clippy", allow(ptr_arg))]
+fn values_size(values: &Vec<Value>) -> u64 {
+    values.iter().map(|value| value.content.len() as u64).sum()
+}
+
+fn mdata_size(data: &MutableData) -> u64 {
+    entries_size(data.entries())
+}
+
+fn actions_size(actions: &BTreeMap<Vec<u8>, EntryAction>) -> u64 {
+    actions
+        .iter()
+        .map(|(key, action)| match *action {
+            EntryAction::Ins(ref value) |
+            EntryAction::Update(ref value) => (key.len() + value.content.len()) as u64,
+            EntryAction::Del(_) => key.len() as u64,
+        })
+        .sum()
+}
+
 // Create a future that resolves into `CoreError::RequestTimeout` after the given time interval.
 fn timeout(duration: Duration, handle: &Handle) -> TimeoutFuture {
     let timeout = match Timeout::new(duration, handle) {
@@ -1195,7 +2486,7 @@ fn timeout(duration: Duration, handle: &Handle) -> TimeoutFuture {
 
     fn map_result(result: io::Result<()>) -> Result<CoreEvent, CoreError> {
         match result {
-            Ok(()) => Err(CoreError::RequestTimeout),
+            Ok(()) => Err(CoreError::RequestTimeout(None)),
             Err(err) => Err(CoreError::Unexpected(
                 format!("Timeout fire error {:?}", err),
             )),
@@ -1239,6 +2530,7 @@ enum ClientType {
     Registered {
         acc: Account,
         acc_loc: XorName,
+        acc_tag: u64,
         user_cred: UserCred,
         cm_addr: Authority<XorName>,
     },
@@ -1266,12 +2558,14 @@ impl ClientType {
     fn reg(
         acc: Account,
         acc_loc: XorName,
+        acc_tag: u64,
         user_cred: UserCred,
         cm_addr: Authority<XorName>,
     ) -> Self {
         ClientType::Registered {
             acc,
             acc_loc,
+            acc_tag,
             user_cred,
             cm_addr,
         }
@@ -1313,6 +2607,14 @@ impl ClientType {
         }
     }
 
+    fn acc_tag(&self) -> Result<u64, CoreError> {
+        match *self {
+            ClientType::Registered { acc_tag, .. } => Ok(acc_tag),
+            ClientType::FromKeys { .. } |
+            ClientType::Unregistered { .. } => Err(CoreError::OperationForbidden),
+        }
+    }
+
     fn user_cred(&self) -> Result<&UserCred, CoreError> {
         match *self {
             ClientType::Registered { ref user_cred, .. } => Ok(user_cred),
@@ -1321,6 +2623,17 @@ impl ClientType {
         }
     }
 
+    fn set_user_cred(&mut self, new_cred: UserCred) -> Result<(), CoreError> {
+        match *self {
+            ClientType::Registered { ref mut user_cred, .. } => {
+                *user_cred = new_cred;
+                Ok(())
+            }
+            ClientType::FromKeys { .. } |
+            ClientType::Unregistered { .. } => Err(CoreError::OperationForbidden),
+        }
+    }
+
     fn cm_addr(&self) -> Result<&Authority<XorName>, CoreError> {
         match *self {
             ClientType::FromKeys { ref cm_addr, .. } |
@@ -1382,16 +2695,17 @@ fn setup_routing(
     full_id: Option<FullId>,
     config: Option<BootstrapConfig>,
 ) -> Result<(Routing, Receiver<Event>), CoreError> {
+    let core_config = config::get();
     let (routing_tx, routing_rx) = mpsc::channel();
     let routing = Routing::new(
         routing_tx,
         full_id,
         config,
-        Duration::from_secs(REQUEST_TIMEOUT_SECS),
+        core_config.get_request_timeout(),
     )?;
 
     trace!("Waiting to get connected to the Network...");
-    match routing_rx.recv_timeout(Duration::from_secs(CONNECTION_TIMEOUT_SECS)) {
+    match routing_rx.recv_timeout(core_config.get_connection_timeout()) {
         Ok(Event::Connected) => (),
         Ok(Event::Terminate) => {
             // TODO: Consider adding a separate error type for this
@@ -1400,7 +2714,7 @@ fn setup_routing(
             ));
         }
         Err(RecvTimeoutError::Timeout) => {
-            return Err(CoreError::RequestTimeout);
+            return Err(CoreError::RequestTimeout(None));
         }
         x => {
             warn!("Could not connect to the Network. Unexpected: {:?}", x);
@@ -1440,91 +2754,859 @@ mod tests {
     use utils;
     use utils::test_utils::{finish, random_client, setup_client};
 
-    // Test logging in using a seeded account.
+    // Mutations over the rate limit are delayed instead of sent at once.
     #[test]
-    fn seeded_login() {
-        let invalid_seed = String::from("123");
-        {
-            let el = unwrap!(Core::new());
-            let (core_tx, _): (CoreMsgTx<()>, _) = mpsc::unbounded();
-            let (net_tx, _) = mpsc::unbounded();
-
-            match Client::registered_with_seed(&invalid_seed, el.handle(), core_tx, net_tx) {
-                Err(CoreError::Unexpected(_)) => (),
-                _ => panic!("Expected a failure"),
-            }
-        }
-        {
-            let el = unwrap!(Core::new());
-            let (core_tx, _): (CoreMsgTx<()>, _) = mpsc::unbounded();
-            let (net_tx, _) = mpsc::unbounded();
+    fn rate_limited_mutations() {
+        let elapsed = random_client(|client| {
+            client.set_rate_limit(Some(RateLimit {
+                requests_per_sec: 10,
+                burst: 1,
+            }));
+
+            let start = Instant::now();
+            let puts: Vec<_> = (0..3u8)
+                .map(|i| client.put_idata(ImmutableData::new(vec![i; 10])))
+                .collect();
+
+            future::join_all(puts).map(move |_| start.elapsed())
+        });
 
-            match Client::login_with_seed(&invalid_seed, el.handle(), core_tx, net_tx) {
-                Err(CoreError::Unexpected(_)) => (),
-                _ => panic!("Expected a failure"),
-            }
-        }
+        assert!(elapsed >= Duration::from_millis(200));
+    }
 
-        let seed = unwrap!(utils::generate_random_string(30));
+    // Entries of private `MutableData` are stored encrypted and can be read
+    // back using the returned `MDataInfo`.
+    #[test]
+    fn private_mdata() {
+        random_client(|client| {
+            let client2 = client.clone();
+            let entries = btree_map![
+                b"key".to_vec() => Value { content: b"value".to_vec(), entry_version: 0 }
+            ];
 
-        setup_client(
-            |el_h, core_tx, net_tx| {
-                match Client::login_with_seed(
-                    &seed,
-                    el_h.clone(),
-                    core_tx.clone(),
-                    net_tx.clone(),
-                ) {
-                    Err(CoreError::RoutingClientError(ClientError::NoSuchAccount)) => (),
-                    x => panic!("Unexpected Login outcome: {:?}", x),
-                }
-                Client::registered_with_seed(&seed, el_h, core_tx, net_tx)
-            },
-            |_| finish(),
-        );
+            client
+                .create_private_mdata(DIR_TAG, BTreeMap::new(), entries)
+                .and_then(move |info| {
+                    client2
+                        .list_mdata_entries(info.name, info.type_tag)
+                        .map(move |entries| (info, entries))
+                })
+                .map(|(info, entries)| {
+                    assert_eq!(entries.len(), 1);
+                    assert!(!entries.contains_key(&b"key".to_vec()));
 
-        setup_client(|el_h, core_tx, net_tx| Client::login_with_seed(&seed, el_h, core_tx, net_tx),
-                     |_| finish());
+                    let entries = unwrap!(mdata_info::decrypt_entries(&info, &entries));
+                    assert_eq!(entries[&b"key".to_vec()].content, b"value".to_vec());
+                })
+        });
     }
 
-    // Tests for unregistered clients.
-    // 1. Have a registered client PUT something on the network.
-    // 2. Try to set the access container as unregistered - this should fail.
-    // 3. Try to set the config root directory as unregistered - this should fail.
+    // Non-owners can append to `AppendableData` but can't change its filter.
+    #[cfg(feature = "unstable-data-types")]
     #[test]
-    fn unregistered_client() {
-        let orig_data = ImmutableData::new(unwrap!(utils::generate_random_vector(30)));
+    fn appendable_data() {
+        use appendable_data::{AppendableData, Filter};
 
-        // Registered Client PUTs something onto the network
-        {
-            let orig_data = orig_data.clone();
-            random_client(|client| client.put_idata(orig_data));
-        }
+        let name: XorName = rand::random();
+        let tag = 15_001;
 
-        // Unregistered Client should be able to retrieve the data
-        setup_client(|el_h, core_tx, net_tx| Client::unregistered(el_h, core_tx, net_tx, None),
-                     move |client| {
+        random_client(move |client| {
+            let owner = unwrap!(client.owner_key());
+            let data = unwrap!(AppendableData::new(
+                name,
+                tag,
+                btree_set![owner],
+                Filter::default(),
+            ));
+
+            let client2 = client.clone();
+            client
+                .put_adata(data)
+                .then(move |res| {
+                    unwrap!(res);
+                    client2.append_adata(name, tag, b"owner".to_vec())
+                })
+                .map_err(|err| panic!("{:?}", err))
+        });
+
+        random_client(move |client| {
             let client2 = client.clone();
             let client3 = client.clone();
 
             client
-                .get_idata(*orig_data.name())
+                .append_adata(name, tag, b"guest".to_vec())
                 .then(move |res| {
-                          let data = unwrap!(res);
-                          assert_eq!(data, orig_data);
-                          let dir = unwrap!(MDataInfo::random_private(DIR_TAG));
-                          client2.set_access_container(dir)
-                      })
+                    unwrap!(res);
+                    client2.set_adata_filter(name, tag, Filter::WhiteList(Default::default()))
+                })
                 .then(move |res| {
-                    let e = match res {
-                        Ok(_) => {
-                            panic!("Unregistered client should not be allowed to set user root dir")
-                        }
-                        Err(e) => e,
-                    };
-                    match e {
-                        CoreError::OperationForbidden => (),
-                        _ => panic!("Unexpected {:?}", e),
+                    match res {
+                        Err(CoreError::RoutingClientError(ClientError::AccessDenied)) => (),
+                        x => panic!("Unexpected {:?}", x),
+                    }
+                    client3.get_adata(name, tag)
+                })
+                .map(|data| {
+                    let contents: Vec<_> = data.entries()
+                        .iter()
+                        .map(|entry| entry.content.clone())
+                        .collect();
+                    assert_eq!(contents, vec![b"owner".to_vec(), b"guest".to_vec()]);
+                })
+                .map_err(|err| panic!("{:?}", err))
+        });
+    }
+
+    // Mutation requests are recorded in the audit log.
+    #[test]
+    fn audit_trail() {
+        use audit::{AuditLog, Target};
+        use rand::{self, Rng};
+        use rust_sodium::crypto::secretbox;
+        use std::env;
+        use std::fs;
+
+        let path = env::temp_dir().join(format!("audit-{}", rand::thread_rng().gen::<u64>()));
+        let key = secretbox::gen_key();
+        let data = ImmutableData::new(vec![1; 10]);
+        let name = *data.name();
+
+        let log_path = path.clone();
+        let log_key = key.clone();
+        random_client(move |client| {
+            client.set_audit_log(Some(unwrap!(AuditLog::open(log_path, log_key))));
+            client.put_idata(data)
+        });
+
+        let entries = unwrap!(unwrap!(AuditLog::open(&path, key)).entries());
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].operation, Operation::PutIData);
+        assert_eq!(entries[0].target, Target::ImmutableData(name));
+
+        unwrap!(fs::remove_file(&path));
+    }
+
+    // Test the cached MutableData shells are used and evicted on mutation.
+    #[cfg(feature = "use-mock-routing")]
+    #[test]
+    fn mdata_shell_cache() {
+        use routing::{MutableData, PermissionSet, User};
+
+        random_client(|client| {
+            let client2 = client.clone();
+            let client3 = client.clone();
+            let client4 = client.clone();
+            let client5 = client.clone();
+
+            let name = rand::random();
+            let tag = 15_001;
+            let owners = btree_set![unwrap!(client.owner_key())];
+            let data = unwrap!(MutableData::new(
+                name,
+                tag,
+                Default::default(),
+                Default::default(),
+                owners,
+            ));
+
+            client.set_mdata_shell_cache(Some(10));
+            client
+                .put_mdata(data)
+                .then(move |res| {
+                    unwrap!(res);
+                    client2.get_mdata_shell(name, tag)
+                })
+                .then(move |res| {
+                    assert_eq!(unwrap!(res).version(), 0);
+                    let perms = PermissionSet::new();
+                    client3.set_mdata_user_permissions(name, tag, User::Anyone, perms, 1)
+                })
+                .then(move |res| {
+                    unwrap!(res);
+                    client4.get_mdata_shell(name, tag)
+                })
+                .then(move |res| {
+                    // The shell cached before the mutation was evicted.
+                    assert_eq!(unwrap!(res).version(), 1);
+
+                    // Served from the cache without hitting the network.
+                    client5.set_network_limits(Some(0));
+                    client5.get_mdata_shell(name, tag)
+                })
+                .then(|res| {
+                    assert_eq!(unwrap!(res).version(), 1);
+                    finish()
+                })
+        });
+    }
+
+    // Test concurrent transactions on the same entry don't lose updates.
+    #[test]
+    fn mdata_transact() {
+        use routing::MutableData;
+
+        random_client(|client| {
+            let client2 = client.clone();
+            let client3 = client.clone();
+            let client4 = client.clone();
+
+            let name = rand::random();
+            let tag = 15_001;
+            let key = b"counter".to_vec();
+            let owners = btree_set![unwrap!(client.owner_key())];
+            let data = unwrap!(MutableData::new(
+                name,
+                tag,
+                Default::default(),
+                btree_map![key.clone() => Value { content: vec![0], entry_version: 0 }],
+                owners,
+            ));
+
+            let increment = |key: Vec<u8>| {
+                move |entries: &BTreeMap<Vec<u8>, Value>| {
+                    let value = unwrap!(entries.get(&key));
+                    EntryActions::new().update(
+                        key.clone(),
+                        vec![value.content[0] + 1],
+                        value.entry_version + 1,
+                    )
+                }
+            };
+            let increment1 = increment(key.clone());
+            let increment2 = increment(key.clone());
+
+            client
+                .put_mdata(data)
+                .then(move |res| {
+                    unwrap!(res);
+                    let f0 = client2.mutate_mdata_transact(name, tag, increment1);
+                    let f1 = client2.mutate_mdata_transact(name, tag, increment2);
+                    f0.join(f1)
+                })
+                .then(move |res| {
+                    unwrap!(res);
+                    client3.get_mdata_value(name, tag, key)
+                })
+                .then(move |res| {
+                    let value = unwrap!(res);
+                    assert_eq!(value.content, vec![2]);
+                    assert_eq!(value.entry_version, 2);
+
+                    // Actions which can never succeed are retried only so many times.
+                    client4.mutate_mdata_transact(name, tag, |_| {
+                        EntryActions::new().update(b"counter".to_vec(), vec![9], 0)
+                    })
+                })
+                .then(|res| {
+                    match res {
+                        Err(CoreError::RoutingClientError(
+                            ClientError::InvalidEntryActions(_))) => (),
+                        x => panic!("Unexpected {:?}", x),
+                    }
+                    finish()
+                })
+        });
+    }
+
+    // Test committing changes against a snapshot:
+    // 1. Changes to entries untouched since the snapshot are committed.
+    // 2. Changes to entries modified since the snapshot are rejected.
+    // 3. Any changes are rejected once the shell was modified.
+    #[cfg(feature = "use-mock-routing")]
+    #[test]
+    fn mdata_snapshot() {
+        use routing::{MutableData, PermissionSet, User};
+
+        random_client(|client| {
+            let client2 = client.clone();
+            let client3 = client.clone();
+            let client4 = client.clone();
+            let client5 = client.clone();
+            let client6 = client.clone();
+            let client7 = client.clone();
+
+            let name = rand::random();
+            let tag = 15_001;
+            let owners = btree_set![unwrap!(client.owner_key())];
+            let entries = btree_map![
+                b"a".to_vec() => Value { content: vec![1], entry_version: 0 },
+                b"b".to_vec() => Value { content: vec![1], entry_version: 0 }
+            ];
+            let data = unwrap!(MutableData::new(
+                name,
+                tag,
+                Default::default(),
+                entries,
+                owners,
+            ));
+
+            let set = |key: &str, content: u8| {
+                btree_map![key.as_bytes().to_vec() => EntryAction::Update(Value {
+                    content: vec![content],
+                    entry_version: 0,
+                })]
+            };
+            let set_a2 = set("a", 2);
+            let set_b2 = set("b", 2);
+            let set_a3 = set("a", 3);
+            let ins_c = btree_map![b"c".to_vec() => EntryAction::Ins(Value {
+                content: vec![1],
+                entry_version: 0,
+            })];
+
+            client
+                .put_mdata(data)
+                .then(move |res| {
+                    unwrap!(res);
+                    client2.get_mdata_snapshot(name, tag)
+                })
+                .then(move |res| {
+                    let snapshot = unwrap!(res);
+                    assert_eq!(snapshot.get(b"a"), Some(&[1][..]));
+                    assert_eq!(snapshot.get(b"c"), None);
+
+                    let snapshot2 = snapshot.clone();
+                    let snapshot3 = snapshot.clone();
+                    client3
+                        .commit_snapshot(&snapshot, set_a2)
+                        .then(move |res| {
+                            unwrap!(res);
+                            // "b" hasn't been touched since the snapshot.
+                            client4.commit_snapshot(&snapshot2, set_b2)
+                        })
+                        .then(move |res| {
+                            unwrap!(res);
+                            // "a" has.
+                            client5.commit_snapshot(&snapshot3, set_a3)
+                        })
+                })
+                .then(move |res| {
+                    match res {
+                        Err(CoreError::RoutingClientError(
+                            ClientError::InvalidEntryActions(_))) => (),
+                        x => panic!("Unexpected {:?}", x),
+                    }
+                    client6.get_mdata_snapshot(name, tag)
+                })
+                .then(move |res| {
+                    let snapshot = unwrap!(res);
+                    assert_eq!(snapshot.get(b"a"), Some(&[2][..]));
+                    assert_eq!(snapshot.get(b"b"), Some(&[2][..]));
+
+                    let client8 = client7.clone();
+                    let perms = PermissionSet::new();
+                    client7
+                        .set_mdata_user_permissions(name, tag, User::Anyone, perms, 1)
+                        .then(move |res| {
+                            unwrap!(res);
+                            client8.commit_snapshot(&snapshot, ins_c)
+                        })
+                })
+                .then(|res| {
+                    match res {
+                        Err(CoreError::RoutingClientError(ClientError::InvalidSuccessor(1))) => (),
+                        x => panic!("Unexpected {:?}", x),
+                    }
+                    finish()
+                })
+        });
+    }
+
+    // Test deriving subkeys:
+    // 1. The same context and index always give the same key.
+    // 2. Different contexts, indices or accounts give different keys.
+    #[test]
+    fn derive_subkey() {
+        let (key0, key1) = random_client(|client| {
+            let key0 = unwrap!(client.derive_subkey(b"inbox", 0));
+            assert_eq!(key0, unwrap!(client.derive_subkey(b"inbox", 0)));
+
+            let key1 = unwrap!(client.derive_subkey(b"inbox", 1));
+            assert_ne!(key0, key1);
+            assert_ne!(key0, unwrap!(client.derive_subkey(b"outbox", 0)));
+
+            finish().map(move |_| (key0, key1))
+        });
+
+        let (key3, key4) = random_client(|client| {
+            let key3 = unwrap!(client.derive_subkey(b"inbox", 0));
+            let key4 = unwrap!(client.derive_subkey(b"inbox", 1));
+            finish().map(move |_| (key3, key4))
+        });
+
+        assert_ne!(key0, key3);
+        assert_ne!(key1, key4);
+    }
+
+    // Test watching MutableData for changes:
+    // 1. Changes made after the watch started are reported as one diff.
+    // 2. Polls without changes yield nothing.
+    #[test]
+    fn watch_mdata() {
+        use futures::Stream;
+        use routing::MutableData;
+
+        random_client(|client| {
+            let client2 = client.clone();
+            let client3 = client.clone();
+
+            let name = rand::random();
+            let tag = 15_001;
+            let owners = btree_set![unwrap!(client.owner_key())];
+            let entries = btree_map![
+                b"a".to_vec() => Value { content: vec![1], entry_version: 0 },
+                b"b".to_vec() => Value { content: vec![1], entry_version: 0 }
+            ];
+            let data = unwrap!(MutableData::new(
+                name,
+                tag,
+                Default::default(),
+                entries,
+                owners,
+            ));
+
+            client
+                .put_mdata(data)
+                .then(move |res| {
+                    unwrap!(res);
+
+                    let diffs = client2
+                        .watch_mdata(name, tag, Duration::from_millis(10))
+                        .into_future()
+                        .map_err(|(error, _)| error);
+
+                    let actions = EntryActions::new()
+                        .ins(b"c".to_vec(), vec![1], 0)
+                        .update(b"a".to_vec(), vec![2], 1)
+                        .del(b"b".to_vec(), 1);
+                    let mutation = client2
+                        .delay(Duration::from_millis(50))
+                        .and_then(move |()| {
+                            client3.mutate_mdata_entries(name, tag, actions.into())
+                        });
+
+                    diffs.join(mutation)
+                })
+                .then(|res| {
+                    let ((diff, _), ()) = unwrap!(res);
+                    let diff = unwrap!(diff);
+
+                    assert_eq!(
+                        diff.added,
+                        btree_map![b"c".to_vec() => Value { content: vec![1], entry_version: 0 }]
+                    );
+                    assert_eq!(
+                        diff.updated,
+                        btree_map![b"a".to_vec() => Value { content: vec![2], entry_version: 1 }]
+                    );
+                    assert_eq!(diff.deleted, btree_set![b"b".to_vec()]);
+                    finish()
+                })
+        });
+    }
+
+    // Test listing the entries of MutableData page by page.
+    #[test]
+    fn mdata_entries_pages() {
+        use routing::MutableData;
+
+        random_client(|client| {
+            let client2 = client.clone();
+
+            let name = rand::random();
+            let tag = 15_001;
+            let entries: BTreeMap<_, _> = (0..5u8)
+                .map(|i| {
+                    (vec![i], Value {
+                        content: vec![i],
+                        entry_version: 0,
+                    })
+                })
+                .collect();
+            let owners = btree_set![unwrap!(client.owner_key())];
+            let data = unwrap!(MutableData::new(
+                name,
+                tag,
+                Default::default(),
+                entries.clone(),
+                owners,
+            ));
+
+            client
+                .put_mdata(data)
+                .and_then(move |()| {
+                    future::loop_fn((None, BTreeMap::new(), 0), move |(start, mut all, pages)| {
+                        client2.list_mdata_entries_range(name, tag, start, 2).map(
+                            move |(page, next)| {
+                                assert!(page.len() <= 2);
+                                all.extend(page);
+                                match next {
+                                    Some(next) => Loop::Continue((Some(next), all, pages + 1)),
+                                    None => Loop::Break((all, pages + 1)),
+                                }
+                            },
+                        )
+                    })
+                })
+                .map(move |(all, pages)| {
+                    assert_eq!(pages, 3);
+                    assert_eq!(all, entries);
+                })
+        });
+
+        // Deleted entries are skipped and the keys are listed only once.
+        random_client(|client| {
+            let client2 = client.clone();
+            let client3 = client.clone();
+
+            let name = rand::random();
+            let tag = 15_001;
+            let entries: BTreeMap<_, _> = (0..5u8)
+                .map(|i| {
+                    // Every other entry is deleted.
+                    let content = if i % 2 == 0 { vec![i] } else { Vec::new() };
+                    (vec![i], Value {
+                        content,
+                        entry_version: 1,
+                    })
+                })
+                .collect();
+            let owners = btree_set![unwrap!(client.owner_key())];
+            let data = unwrap!(MutableData::new(
+                name,
+                tag,
+                Default::default(),
+                entries,
+                owners,
+            ));
+
+            client
+                .put_mdata(data)
+                .and_then(move |()| client2.list_mdata_entry_pages(name, tag, Some(vec![1])))
+                .and_then(move |pages| {
+                    assert_eq!(pages.next_key(), Some(&vec![1]));
+                    future::loop_fn((pages, BTreeMap::new()), move |(pages, mut all)| {
+                        client3.next_mdata_entry_page(pages, 2).map(move |(page, pages)| {
+                            all.extend(page);
+                            if pages.is_done() {
+                                Loop::Break(all)
+                            } else {
+                                Loop::Continue((pages, all))
+                            }
+                        })
+                    })
+                })
+                .map(move |all| {
+                    let keys: Vec<_> = all.keys().cloned().collect();
+                    assert_eq!(keys, vec![vec![2], vec![4]]);
+                })
+        });
+
+        // Empty pages would never get past the start key.
+        random_client(|client| {
+            client
+                .list_mdata_entries_range(rand::random(), 15_001, None, 0)
+                .then(|res| -> Result<_, CoreError> {
+                    match res {
+                        Err(CoreError::Unexpected(_)) => Ok(()),
+                        res => panic!("Unexpected {:?}", res),
+                    }
+                })
+        });
+    }
+
+    // Test logging in using a seeded account.
+    #[test]
+    fn seeded_login() {
+        let invalid_seed = String::from("123");
+        {
+            let el = unwrap!(Core::new());
+            let (core_tx, _): (CoreMsgTx<()>, _) = mpsc::unbounded();
+            let (net_tx, _) = mpsc::unbounded();
+
+            match Client::registered_with_seed(&invalid_seed, el.handle(), core_tx, net_tx) {
+                Err(CoreError::Unexpected(_)) => (),
+                _ => panic!("Expected a failure"),
+            }
+        }
+        {
+            let el = unwrap!(Core::new());
+            let (core_tx, _): (CoreMsgTx<()>, _) = mpsc::unbounded();
+            let (net_tx, _) = mpsc::unbounded();
+
+            match Client::login_with_seed(&invalid_seed, el.handle(), core_tx, net_tx) {
+                Err(CoreError::Unexpected(_)) => (),
+                _ => panic!("Expected a failure"),
+            }
+        }
+
+        let seed = unwrap!(utils::generate_random_string(30));
+
+        setup_client(
+            |el_h, core_tx, net_tx| {
+                match Client::login_with_seed(
+                    &seed,
+                    el_h.clone(),
+                    core_tx.clone(),
+                    net_tx.clone(),
+                ) {
+                    Err(CoreError::RoutingClientError(ClientError::NoSuchAccount)) => (),
+                    x => panic!("Unexpected Login outcome: {:?}", x),
+                }
+                Client::registered_with_seed(&seed, el_h, core_tx, net_tx)
+            },
+            |_| finish(),
+        );
+
+        setup_client(|el_h, core_tx, net_tx| Client::login_with_seed(&seed, el_h, core_tx, net_tx),
+                     |_| finish());
+    }
+
+    // Test the test account is registered and can put data right away.
+    #[cfg(feature = "use-mock-routing")]
+    #[test]
+    fn registered_test_account() {
+        setup_client(Client::registered_test_account, |client| {
+            assert!(client.owner_key().is_ok());
+            client.put_idata(ImmutableData::new(vec![1, 2, 3]))
+        });
+    }
+
+    // Test exporting an account and restoring it under new credentials.
+    #[test]
+    fn export_and_import_account() {
+        let backup_password = unwrap!(utils::generate_random_string(10));
+
+        let (app_key, _) = sign::gen_keypair();
+
+        let (backup, access_container, config_root, info) = {
+            let backup_password = backup_password.clone();
+            random_client(move |client| {
+                let client2 = client.clone();
+                let client3 = client.clone();
+                let client4 = client.clone();
+
+                client
+                    .list_auth_keys_and_version()
+                    .and_then(move |(_, version)| client2.ins_auth_key(app_key, version + 1))
+                    .and_then(move |()| client3.get_account_info())
+                    .and_then(move |info| -> Result<_, CoreError> {
+                        let backup = client4.export_account(&backup_password)?;
+                        Ok((
+                            backup,
+                            client4.access_container()?,
+                            client4.config_root_dir()?,
+                            info,
+                        ))
+                    })
+            })
+        };
+
+        // A wrong password is rejected.
+        {
+            let el = unwrap!(Core::new());
+            let (core_tx, _): (CoreMsgTx<()>, _) = mpsc::unbounded();
+            let (net_tx, _) = mpsc::unbounded();
+
+            match Client::import_account(&backup, "wrong", "a", "b", el.handle(), core_tx, net_tx) {
+                Err(CoreError::SymmetricDecipherFailure) => (),
+                _ => panic!("Expected a failure"),
+            }
+        }
+
+        let acc_locator = unwrap!(utils::generate_random_string(10));
+        let acc_password = unwrap!(utils::generate_random_string(10));
+
+        setup_client(
+            |el_h, core_tx, net_tx| {
+                Client::import_account(
+                    &backup,
+                    &backup_password,
+                    &acc_locator,
+                    &acc_password,
+                    el_h,
+                    core_tx,
+                    net_tx,
+                )
+            },
+            |_| finish(),
+        );
+
+        setup_client(
+            |el_h, core_tx, net_tx| {
+                Client::login(&acc_locator, &acc_password, el_h, core_tx, net_tx)
+            },
+            move |client| {
+                assert_eq!(unwrap!(client.access_container()), access_container);
+                assert_eq!(unwrap!(client.config_root_dir()), config_root);
+
+                // The account wasn't re-created: the authorised keys are kept and
+                // the import was paid from the existing balance.
+                client
+                    .list_auth_keys_and_version()
+                    .join(client.get_account_info())
+                    .map(move |((keys, _), new_info)| {
+                        assert!(keys.contains(&app_key));
+                        assert!(new_info.mutations_done > info.mutations_done);
+                        assert!(new_info.mutations_available < info.mutations_available);
+                    })
+            },
+        );
+
+        // The account packet can be updated under the imported credentials.
+        setup_client(
+            |el_h, core_tx, net_tx| {
+                Client::login(&acc_locator, &acc_password, el_h, core_tx, net_tx)
+            },
+            |client| client.update_account_packet(),
+        );
+    }
+
+    // Test creating and logging into an account from a mnemonic phrase.
+    #[test]
+    fn mnemonic_login() {
+        {
+            let el = unwrap!(Core::new());
+            let (core_tx, _): (CoreMsgTx<()>, _) = mpsc::unbounded();
+            let (net_tx, _) = mpsc::unbounded();
+
+            match Client::registered_from_seed("not a mnemonic", el.handle(), core_tx, net_tx) {
+                Err(CoreError::Unexpected(_)) => (),
+                _ => panic!("Expected a failure"),
+            }
+        }
+
+        let phrase = unwrap!(mnemonic::generate());
+
+        setup_client(
+            |el_h, core_tx, net_tx| Client::registered_from_seed(&phrase, el_h, core_tx, net_tx),
+            |_| finish(),
+        );
+
+        // The mnemonic only stands for the seed, so logging in with either reaches the account.
+        let seed = unwrap!(mnemonic::to_seed(&phrase));
+        setup_client(
+            |el_h, core_tx, net_tx| Client::login_with_seed(&seed, el_h, core_tx, net_tx),
+            |_| finish(),
+        );
+        setup_client(
+            |el_h, core_tx, net_tx| Client::login_from_seed(&phrase, el_h, core_tx, net_tx),
+            |_| finish(),
+        );
+    }
+
+    // Test changing the account password:
+    // 1. Change the password twice, which rewrites the session packet in place.
+    // 2. Check the account packet can still be updated after each change.
+    // 3. Check the authorised keys and the balance of the account survive.
+    // 4. Only the latest password can be used to log in.
+    #[test]
+    fn change_password() {
+        let locator = unwrap!(utils::generate_random_string(10));
+        let password = unwrap!(utils::generate_random_string(10));
+        let invitation = unwrap!(utils::generate_random_string(10));
+        let new_password = unwrap!(utils::generate_random_string(10));
+        let newest_password = unwrap!(utils::generate_random_string(10));
+
+        {
+            let new_password = new_password.clone();
+            let newest_password = newest_password.clone();
+
+            setup_client(
+                |el_h, core_tx, net_tx| {
+                    Client::registered(&locator, &password, &invitation, el_h, core_tx, net_tx)
+                },
+                move |client| {
+                    let client2 = client.clone();
+                    let client3 = client.clone();
+                    let client4 = client.clone();
+                    let client5 = client.clone();
+                    let client6 = client.clone();
+                    let client7 = client.clone();
+                    let client8 = client.clone();
+
+                    let (app_key, _) = sign::gen_keypair();
+
+                    client
+                        .list_auth_keys_and_version()
+                        .and_then(move |(_, version)| client2.ins_auth_key(app_key, version + 1))
+                        .and_then(move |()| client3.get_account_info())
+                        .and_then(move |info| {
+                            client4.change_password(&new_password).map(move |()| info)
+                        })
+                        .and_then(move |info| client5.update_account_packet().map(move |()| info))
+                        .and_then(move |info| {
+                            client6.change_password(&newest_password).map(move |()| info)
+                        })
+                        .and_then(move |info| client7.update_account_packet().map(move |()| info))
+                        .and_then(move |info| {
+                            client8
+                                .list_auth_keys_and_version()
+                                .join(client8.get_account_info())
+                                .map(move |((keys, _), new_info)| {
+                                    assert!(keys.contains(&app_key));
+                                    // A re-created account would have its
+                                    // full balance again.
+                                    assert!(new_info.mutations_done > info.mutations_done);
+                                    assert!(
+                                        new_info.mutations_available <= info.mutations_available
+                                    );
+                                })
+                        })
+                },
+            );
+        }
+
+        let login = |locator: &str, password: &str| {
+            let el = unwrap!(Core::new());
+            let (core_tx, _): (CoreMsgTx<()>, _) = mpsc::unbounded();
+            let (net_tx, _) = mpsc::unbounded();
+            Client::login(locator, password, el.handle(), core_tx, net_tx).map(|_| ())
+        };
+
+        assert!(login(&locator, &password).is_err());
+        assert!(login(&locator, &new_password).is_err());
+        unwrap!(login(&locator, &newest_password));
+    }
+
+    // Tests for unregistered clients.
+    // 1. Have a registered client PUT something on the network.
+    // 2. Try to set the access container as unregistered - this should fail.
+    // 3. Try to set the config root directory as unregistered - this should fail.
+    #[test]
+    fn unregistered_client() {
+        let orig_data = ImmutableData::new(unwrap!(utils::generate_random_vector(30)));
+
+        // Registered Client PUTs something onto the network
+        {
+            let orig_data = orig_data.clone();
+            random_client(|client| client.put_idata(orig_data));
+        }
+
+        // Unregistered Client should be able to retrieve the data
+        setup_client(|el_h, core_tx, net_tx| Client::unregistered(el_h, core_tx, net_tx, None),
+                     move |client| {
+            let client2 = client.clone();
+            let client3 = client.clone();
+
+            client
+                .get_idata(*orig_data.name())
+                .then(move |res| {
+                          let data = unwrap!(res);
+                          assert_eq!(data, orig_data);
+                          let dir = unwrap!(MDataInfo::random_private(DIR_TAG));
+                          client2.set_access_container(dir)
+                      })
+                .then(move |res| {
+                    let e = match res {
+                        Ok(_) => {
+                            panic!("Unregistered client should not be allowed to set user root dir")
+                        }
+                        Err(e) => e,
+                    };
+                    match e {
+                        CoreError::OperationForbidden => (),
+                        _ => panic!("Unexpected {:?}", e),
                     }
 
                     let dir = unwrap!(MDataInfo::random_private(DIR_TAG));
@@ -1659,6 +3741,88 @@ mod tests {
                      });
     }
 
+    // Low balance warning is raised after a mutation once the balance is below
+    // the threshold.
+    #[test]
+    fn low_balance_warning() {
+        use event::NetworkEvent;
+        use utils::test_utils::random_client_with_net_obs;
+        use futures;
+        use maidsafe_utilities::thread;
+        use std::sync::mpsc;
+        use std::u64;
+
+        let (tx, rx) = mpsc::channel();
+        let (hook, keep_alive) = futures::oneshot();
+
+        let _joiner = thread::named("Network Observer", move || {
+            match unwrap!(rx.recv()) {
+                NetworkEvent::LowBalance(available) => assert!(available > 0),
+                x => panic!("Unexpected network event: {:?}", x),
+            }
+            let _ = hook.send(());
+        });
+
+        random_client_with_net_obs(
+            move |net_event| unwrap!(tx.send(net_event)),
+            move |client| {
+                client.set_low_balance_threshold(Some(u64::MAX));
+                client
+                    .put_idata(ImmutableData::new(vec![1; 10]))
+                    .map_err(|err| panic!("{:?}", err))
+                    .and_then(|()| keep_alive.map_err(|_| ()))
+            },
+        );
+    }
+
+    // Test parking the idle connection and resuming it on the next request.
+    #[cfg(feature = "use-mock-routing")]
+    #[test]
+    fn idle_connection() {
+        use event::NetworkEvent;
+        use utils::test_utils::random_client_with_net_obs;
+        use maidsafe_utilities::thread;
+        use std::sync::mpsc;
+
+        let (tx, rx) = mpsc::channel();
+        let (parked_tx, parked_rx) = mpsc::channel();
+
+        let _joiner = thread::named("Network Observer", move || {
+            match unwrap!(rx.recv()) {
+                NetworkEvent::Disconnected => (),
+                x => panic!("Unexpected network event: {:?}", x),
+            }
+            unwrap!(parked_tx.send(()));
+            match unwrap!(rx.recv()) {
+                NetworkEvent::Connected => (),
+                x => panic!("Unexpected network event: {:?}", x),
+            }
+        });
+
+        random_client_with_net_obs(
+            move |net_event| unwrap!(tx.send(net_event)),
+            move |client| {
+                let client2 = client.clone();
+
+                client.set_idle_config(IdleConfig {
+                    keep_alive: None,
+                    idle_timeout: Some(Duration::from_millis(100)),
+                });
+
+                let el_handle = client.inner().el_handle.clone();
+                let wait = Timeout::new(Duration::from_millis(500), &el_handle);
+                unwrap!(wait)
+                    .map_err(|err| panic!("{:?}", err))
+                    .and_then(move |()| {
+                        unwrap!(parked_rx.recv());
+                        client2.get_account_info()
+                    })
+                    .map_err(|err| panic!("{:?}", err))
+                    .map(|_| ())
+            },
+        );
+    }
+
     // Test restarting routing after a network disconnect.
     #[cfg(feature = "use-mock-routing")]
     #[test]
@@ -1694,6 +3858,228 @@ mod tests {
         );
     }
 
+    // Test the waiting requests of higher priority are sent first.
+    #[test]
+    fn priority_scheduling() {
+        use std::cell::RefCell;
+
+        random_client(|client| {
+            let order = Rc::new(RefCell::new(Vec::new()));
+            client.set_max_requests_in_flight(Some(1));
+
+            let requests: Vec<_> = vec![
+                ("first", Priority::Low),
+                ("second", Priority::Low),
+                ("third", Priority::High),
+            ].into_iter()
+                .map(|(label, priority)| {
+                    let order = Rc::clone(&order);
+                    client.with_priority(priority, |client| {
+                        client.get_account_info().map(
+                            move |_| order.borrow_mut().push(label),
+                        )
+                    })
+                })
+                .collect();
+
+            // The mutation waits for the slot with the priority it was issued
+            // with, even though it is only queued once polled.
+            let order2 = Rc::clone(&order);
+            let mutation = client.with_priority(Priority::High, |client| {
+                client.put_idata(ImmutableData::new(vec![1; 10])).map(
+                    move |_| order2.borrow_mut().push("mutation"),
+                )
+            });
+
+            future::join_all(requests).join(mutation).map(move |_| {
+                assert_eq!(
+                    *order.borrow(),
+                    vec!["first", "third", "mutation", "second"]
+                );
+            })
+        });
+    }
+
+    // Test the requests waiting for a slot are not sent to the network.
+    #[test]
+    fn max_requests_in_flight() {
+        random_client(|client| {
+            let client2 = client.clone();
+            client.set_max_requests_in_flight(Some(1));
+
+            let first = client.get_account_info();
+            let second = client.get_account_info();
+            let check = future::lazy(move || {
+                // Both requests have been polled by now, but only the first
+                // one holds a slot.
+                assert_eq!(client2.pending_requests().len(), 1);
+                Ok(())
+            });
+
+            first.join3(second, check).map(|_| ())
+        });
+    }
+
+    // Test dropped requests don't hold their slots.
+    #[test]
+    fn dropped_requests_free_slots() {
+        random_client(|client| {
+            client.set_max_requests_in_flight(Some(1));
+
+            // Holding the slot, but never polled.
+            drop(client.get_account_info());
+
+            // Waiting for the slot.
+            let first = client.get_account_info();
+            drop(client.get_account_info());
+
+            let client2 = client.clone();
+            first.and_then(move |_| client2.get_account_info()).map(
+                |_| (),
+            )
+        });
+    }
+
+    // Test the network events are delivered to all the subscribed streams.
+    #[cfg(feature = "use-mock-routing")]
+    #[test]
+    fn network_events() {
+        use event::NetworkEvent;
+
+        random_client(|client| {
+            let events = client.network_events().take(2).collect();
+            let events2 = client.network_events().take(2).collect();
+
+            client.simulate_network_disconnect();
+            unwrap!(client.restart_routing());
+
+            events.join(events2).map(|(events, events2)| {
+                for events in &[events, events2] {
+                    assert_eq!(events.len(), 2);
+                    assert!(events.iter().any(|event| match *event {
+                        NetworkEvent::Disconnected => true,
+                        _ => false,
+                    }));
+                    assert!(events.iter().any(|event| match *event {
+                        NetworkEvent::Connected => true,
+                        _ => false,
+                    }));
+                }
+            })
+        });
+    }
+
+    // Test reconnecting automatically when the network requests a restart, and
+    // the requests sent during the unresponsive windows timing out.
+    #[cfg(feature = "use-mock-routing")]
+    #[test]
+    fn churn() {
+        use MockChurnConfig;
+        use event::NetworkEvent;
+        use utils::test_utils::random_client_with_net_obs;
+        use futures;
+        use maidsafe_utilities::thread;
+        use std::sync::mpsc;
+
+        let (tx, rx) = mpsc::channel();
+        let (hook, reconnected) = futures::oneshot();
+
+        let _joiner = thread::named("Network Observer", move || {
+            match unwrap!(rx.recv()) {
+                NetworkEvent::Disconnected => (),
+                x => panic!("Unexpected network event: {:?}", x),
+            }
+            match unwrap!(rx.recv()) {
+                NetworkEvent::Connected => (),
+                x => panic!("Unexpected network event: {:?}", x),
+            }
+            let _ = hook.send(());
+        });
+
+        random_client_with_net_obs(
+            move |net_event| unwrap!(tx.send(net_event)),
+            move |client| {
+                let client2 = client.clone();
+                let client3 = client.clone();
+
+                client.set_auto_reconnect(Some(Duration::from_millis(100)));
+                client.simulate_churn(MockChurnConfig {
+                    restart_interval: Some(Duration::from_millis(200)),
+                    unresponsive: None,
+                });
+
+                reconnected
+                    .map_err(|err| panic!("{:?}", err))
+                    .and_then(move |()| {
+                        // The network stops responding altogether.
+                        client2.simulate_churn(MockChurnConfig {
+                            restart_interval: None,
+                            unresponsive: Some((
+                                Duration::from_secs(60),
+                                Duration::from_secs(60),
+                            )),
+                        });
+                        client2.set_timeout(Duration::from_millis(250));
+                        client2.get_account_info()
+                    })
+                    .then(move |res| {
+                        match res {
+                            Err(CoreError::RequestTimeout(_)) => (),
+                            res => panic!("Unexpected {:?}", res),
+                        }
+
+                        client3.simulate_churn(Default::default());
+                        client3.get_account_info()
+                    })
+                    .map_err(|err| panic!("{:?}", err))
+                    .map(|_| ())
+            },
+        );
+    }
+
+    // Test the requests timed out are retried according to the retry policy.
+    #[cfg(feature = "use-mock-routing")]
+    #[test]
+    fn retry() {
+        use std::time::Duration;
+
+        random_client(|client| {
+            let client2 = client.clone();
+            let client3 = client.clone();
+
+            let data = ImmutableData::new(unwrap!(utils::generate_random_vector(4)));
+            let name = *data.name();
+
+            client
+                .put_idata(data.clone())
+                .then(move |res| {
+                    unwrap!(res);
+
+                    client2.set_simulate_timeout(true);
+                    client2.set_timeout(Duration::from_millis(250));
+                    client2.set_retry_policy(Some(RetryPolicy {
+                        max_attempts: 3,
+                        initial_backoff: Duration::from_millis(100),
+                        max_backoff: Duration::from_secs(1),
+                    }));
+
+                    // The network recovers before the third attempt.
+                    let client4 = client2.clone();
+                    let recover = client2
+                        .delay(Duration::from_millis(400))
+                        .map(move |()| client4.set_simulate_timeout(false))
+                        .map_err(|err| panic!("{:?}", err));
+                    client2.inner().el_handle.spawn(recover);
+
+                    client3.get_idata(name)
+                })
+                .then(move |res| {
+                    assert_eq!(unwrap!(res), data);
+                    finish()
+                })
+        });
+    }
+
     // Test that a `RequestTimeout` error is returned on network timeout.
     #[cfg(feature = "use-mock-routing")]
     #[test]
@@ -1711,7 +4097,7 @@ mod tests {
                 .get_idata(rand::random())
                 .then(|result| match result {
                     Ok(_) => panic!("Unexpected success"),
-                    Err(CoreError::RequestTimeout) => Ok::<_, CoreError>(()),
+                    Err(CoreError::RequestTimeout(_)) => Ok::<_, CoreError>(()),
                     Err(err) => panic!("Unexpected {:?}", err),
                 })
                 .then(move |result| {
@@ -1724,9 +4110,87 @@ mod tests {
                 })
                 .then(|result| match result {
                     Ok(_) => panic!("Unexpected success"),
-                    Err(CoreError::RequestTimeout) => Ok::<_, CoreError>(()),
+                    Err(CoreError::RequestTimeout(Some(_))) => Ok::<_, CoreError>(()),
                     Err(err) => panic!("Unexpected {:?}", err),
                 })
         })
     }
+
+    // Test requests issued with a custom timeout time out on their own
+    // deadline, while the client-wide timeout is left intact.
+    #[cfg(feature = "use-mock-routing")]
+    #[test]
+    fn per_request_timeout() {
+        use std::time::Duration;
+
+        random_client(|client| {
+            let client2 = client.clone();
+            let client3 = client.clone();
+
+            client.set_simulate_timeout(true);
+
+            client
+                .with_timeout(Duration::from_millis(250), |client| {
+                    client.get_idata(rand::random())
+                })
+                .then(|result| match result {
+                    Ok(_) => panic!("Unexpected success"),
+                    Err(CoreError::RequestTimeout(Some(_))) => Ok::<_, CoreError>(()),
+                    Err(err) => panic!("Unexpected {:?}", err),
+                })
+                .then(move |result| {
+                    unwrap!(result);
+                    assert_eq!(
+                        client2.inner().timeout,
+                        config::get().get_request_timeout()
+                    );
+
+                    let data = unwrap!(utils::generate_random_vector(4));
+                    let data = ImmutableData::new(data);
+
+                    client3.with_timeout(Duration::from_millis(250), |client| {
+                        client.put_idata(data)
+                    })
+                })
+                .then(|result| match result {
+                    Ok(_) => panic!("Unexpected success"),
+                    Err(CoreError::RequestTimeout(Some(_))) => Ok::<_, CoreError>(()),
+                    Err(err) => panic!("Unexpected {:?}", err),
+                })
+        })
+    }
+
+    // Test the requests in flight are registered until they time out.
+    #[cfg(feature = "use-mock-routing")]
+    #[test]
+    fn pending_requests() {
+        use std::time::Duration;
+
+        random_client(|client| {
+            let client2 = client.clone();
+
+            client.set_simulate_timeout(true);
+            client.set_timeout(Duration::from_millis(250));
+
+            let fut = client.get_idata(rand::random());
+
+            let pending = client.pending_requests();
+            assert_eq!(pending.len(), 1);
+            assert_eq!(pending[0].operation, Operation::GetIData);
+            assert_eq!(pending[0].attempt, 1);
+            assert_eq!(pending[0].state, RequestState::AwaitingResponse);
+            assert!(pending[0].deadline > pending[0].sent_at);
+
+            fut.then(move |result| {
+                match result {
+                    Err(CoreError::RequestTimeout(Some(msg_id))) => {
+                        assert_eq!(msg_id, pending[0].msg_id)
+                    }
+                    result => panic!("Unexpected {:?}", result),
+                }
+                assert!(client2.pending_requests().is_empty());
+                Ok::<_, CoreError>(())
+            })
+        })
+    }
 }
@@ -0,0 +1,61 @@
+// Copyright 2017 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under (1) the MaidSafe.net Commercial License,
+// version 1.0 or later, or (2) The General Public License (GPL), version 3, depending on which
+// licence you accepted on initial access to the Software (the "Licences").
+//
+// By contributing code to the SAFE Network Software, or to this project generally, you agree to be
+// bound by the terms of the MaidSafe Contributor Agreement.  This, along with the Licenses can be
+// found in the root directory of this project at LICENSE, COPYING and CONTRIBUTOR.
+//
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.
+//
+// Please review the Licences for the specific language governing permissions and limitations
+// relating to use of the SAFE Network Software.
+
+use event::{NetworkEvent, NetworkEventStream, NetworkTx};
+use futures::Stream;
+use futures::sync::mpsc;
+use std::cell::RefCell;
+use std::rc::Rc;
+use tokio_core::reactor::Handle;
+
+// Fans the network events out to the network observer of the client and to
+// the streams subscribed through `Client::network_events`.
+#[derive(Clone)]
+pub struct NetworkEvents {
+    subscribers: Rc<RefCell<Vec<NetworkTx>>>,
+}
+
+impl NetworkEvents {
+    // Start forwarding the events sent through the returned sender to the
+    // `observer` and the subscribers, in the event loop.
+    pub fn start(el_handle: &Handle, observer: NetworkTx) -> (Self, NetworkTx) {
+        let (tx, rx) = mpsc::unbounded();
+        let subscribers = Rc::new(RefCell::new(Vec::<NetworkTx>::new()));
+        let subscribers2 = Rc::clone(&subscribers);
+
+        let fut = rx.for_each(move |event| {
+            // Subscribers which dropped their stream are forgotten.
+            subscribers2.borrow_mut().retain(
+                |subscriber| subscriber.unbounded_send(event).is_ok(),
+            );
+            if let Err(error) = observer.unbounded_send(event) {
+                trace!("Couldn't send {:?} to the network observer: {:?}", event, error);
+            }
+            Ok(())
+        });
+        el_handle.spawn(fut);
+
+        (NetworkEvents { subscribers }, tx)
+    }
+
+    // Stream of the network events occurring from now on.
+    pub fn subscribe(&self) -> NetworkEventStream {
+        let (tx, rx) = mpsc::unbounded();
+        self.subscribers.borrow_mut().push(tx);
+        rx
+    }
+}
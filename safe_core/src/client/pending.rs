@@ -0,0 +1,125 @@
+// Copyright 2017 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under (1) the MaidSafe.net Commercial License,
+// version 1.0 or later, or (2) The General Public License (GPL), version 3, depending on which
+// licence you accepted on initial access to the Software (the "Licences").
+//
+// By contributing code to the SAFE Network Software, or to this project generally, you agree to be
+// bound by the terms of the MaidSafe Contributor Agreement.  This, along with the Licenses can be
+// found in the root directory of this project at LICENSE, COPYING and CONTRIBUTOR.
+//
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.
+//
+// Please review the Licences for the specific language governing permissions and limitations
+// relating to use of the SAFE Network Software.
+
+use metrics::Operation;
+use routing::MessageId;
+use std::collections::HashMap;
+use std::time::Instant;
+
+/// State of a request sent to the network.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RequestState {
+    /// Waiting for the response.
+    AwaitingResponse,
+    /// Rejected due to rate limiting, waiting to be resent under a new message
+    /// id.
+    RateLimited,
+}
+
+/// Request sent to the network and not yet completed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PendingRequest {
+    /// Id of the message carrying the request.
+    pub msg_id: MessageId,
+    /// Kind of the request.
+    pub operation: Operation,
+    /// Number of the attempt to send the request (see `RetryPolicy`),
+    /// starting at 1.
+    pub attempt: u32,
+    /// When the message was sent.
+    pub sent_at: Instant,
+    /// When the request times out unless responded to.
+    pub deadline: Instant,
+    /// What the request is waiting for.
+    pub state: RequestState,
+}
+
+// Registry of the requests in flight, keyed by the id of their message.
+#[derive(Default)]
+pub struct PendingRequests {
+    requests: HashMap<MessageId, PendingRequest>,
+}
+
+impl PendingRequests {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn insert(&mut self, request: PendingRequest) {
+        let _ = self.requests.insert(request.msg_id, request);
+    }
+
+    pub fn set_state(&mut self, msg_id: &MessageId, state: RequestState) {
+        if let Some(request) = self.requests.get_mut(msg_id) {
+            request.state = state;
+        }
+    }
+
+    pub fn remove(&mut self, msg_id: &MessageId) {
+        let _ = self.requests.remove(msg_id);
+    }
+
+    pub fn clear(&mut self) {
+        self.requests.clear();
+    }
+
+    // The requests in flight, oldest first.
+    pub fn list(&self) -> Vec<PendingRequest> {
+        let mut requests: Vec<_> = self.requests.values().cloned().collect();
+        requests.sort_by_key(|request| request.sent_at);
+        requests
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    // Test the requests are listed oldest first and removed once completed.
+    #[test]
+    fn registry() {
+        let now = Instant::now();
+        let request = |operation, sent_at| {
+            PendingRequest {
+                msg_id: MessageId::new(),
+                operation,
+                attempt: 1,
+                sent_at,
+                deadline: sent_at + Duration::from_secs(1),
+                state: RequestState::AwaitingResponse,
+            }
+        };
+        let newer = request(Operation::PutIData, now + Duration::from_millis(10));
+        let older = request(Operation::GetIData, now);
+
+        let mut pending = PendingRequests::new();
+        pending.insert(newer);
+        pending.insert(older);
+        assert_eq!(pending.list(), vec![older, newer]);
+
+        pending.set_state(&newer.msg_id, RequestState::RateLimited);
+        assert_eq!(pending.list()[1].state, RequestState::RateLimited);
+
+        pending.remove(&older.msg_id);
+        let remaining = pending.list();
+        assert_eq!(remaining.len(), 1);
+        assert_eq!(remaining[0].msg_id, newer.msg_id);
+        pending.clear();
+        assert!(pending.list().is_empty());
+    }
+}
@@ -0,0 +1,105 @@
+// Copyright 2017 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under (1) the MaidSafe.net Commercial License,
+// version 1.0 or later, or (2) The General Public License (GPL), version 3, depending on which
+// licence you accepted on initial access to the Software (the "Licences").
+//
+// By contributing code to the SAFE Network Software, or to this project generally, you agree to be
+// bound by the terms of the MaidSafe Contributor Agreement.  This, along with the Licenses can be
+// found in the root directory of this project at LICENSE, COPYING and CONTRIBUTOR.
+//
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.
+//
+// Please review the Licences for the specific language governing permissions and limitations
+// relating to use of the SAFE Network Software.
+
+use std::cmp;
+use std::time::{Duration, Instant};
+
+/// Limit on the rate of mutation requests sent by the client.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RateLimit {
+    /// Sustained number of requests per second.
+    pub requests_per_sec: u32,
+    /// Number of requests which can be sent at once after a period of
+    /// inactivity.
+    pub burst: u32,
+}
+
+// Token bucket pacing the requests. Every request takes one token, and the
+// tokens are refilled at the configured rate up to the burst size. If there
+// are no tokens left, the request is reserved a slot in the future, so
+// concurrent requests are spread out evenly instead of all waiting for the
+// same refill.
+#[derive(Debug)]
+pub struct TokenBucket {
+    rate: f64,
+    capacity: f64,
+    tokens: f64,
+    last_refill: Instant,
+}
+
+impl TokenBucket {
+    pub fn new(limit: RateLimit) -> Self {
+        let capacity = f64::from(cmp::max(limit.burst, 1));
+
+        TokenBucket {
+            rate: f64::from(cmp::max(limit.requests_per_sec, 1)),
+            capacity,
+            tokens: capacity,
+            last_refill: Instant::now(),
+        }
+    }
+
+    // Take a token and return how long the request has to wait before it can
+    // be sent.
+    pub fn reserve(&mut self, now: Instant) -> Duration {
+        let elapsed = duration_to_secs(now.duration_since(self.last_refill));
+        self.last_refill = now;
+        self.tokens = (self.tokens + elapsed * self.rate).min(self.capacity);
+        self.tokens -= 1.0;
+
+        if self.tokens >= 0.0 {
+            Duration::from_secs(0)
+        } else {
+            secs_to_duration(-self.tokens / self.rate)
+        }
+    }
+}
+
+fn duration_to_secs(duration: Duration) -> f64 {
+    duration.as_secs() as f64 + f64::from(duration.subsec_nanos()) / 1e9
+}
+
+fn secs_to_duration(secs: f64) -> Duration {
+    let whole = secs.trunc();
+    Duration::new(whole as u64, ((secs - whole) * 1e9).round() as u32)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Requests over the burst size are spaced out at the configured rate.
+    #[test]
+    fn pacing() {
+        let mut bucket = TokenBucket::new(RateLimit {
+            requests_per_sec: 10,
+            burst: 2,
+        });
+        let start = bucket.last_refill;
+
+        assert_eq!(bucket.reserve(start), Duration::from_secs(0));
+        assert_eq!(bucket.reserve(start), Duration::from_secs(0));
+        assert_eq!(bucket.reserve(start), Duration::from_millis(100));
+        assert_eq!(bucket.reserve(start), Duration::from_millis(200));
+
+        // After a second of inactivity the bucket is full again, but not more.
+        let later = start + Duration::from_secs(2);
+        assert_eq!(bucket.reserve(later), Duration::from_secs(0));
+        assert_eq!(bucket.reserve(later), Duration::from_secs(0));
+        assert_eq!(bucket.reserve(later), Duration::from_millis(100));
+    }
+}
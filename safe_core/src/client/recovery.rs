@@ -72,11 +72,11 @@ pub fn mutate_mdata_entries<T: 'static>(
                         ))
                     }
                 }
-                CoreError::RequestTimeout => {
+                CoreError::RequestTimeout(msg_id) => {
                     if attempts < MAX_ATTEMPTS {
                         Ok(Loop::Continue((attempts + 1, actions)))
                     } else {
-                        Err(CoreError::RequestTimeout)
+                        Err(CoreError::RequestTimeout(msg_id))
                     }
                 }
                 error => Err(error),
@@ -108,11 +108,11 @@ pub fn set_mdata_user_permissions<T: 'static>(
                         Err(error)
                     }
                 }
-                CoreError::RequestTimeout => {
+                CoreError::RequestTimeout(msg_id) => {
                     if attempts < MAX_ATTEMPTS {
                         Ok(Loop::Continue((attempts + 1, version)))
                     } else {
-                        Err(CoreError::RequestTimeout)
+                        Err(CoreError::RequestTimeout(msg_id))
                     }
                 }
                 error => Err(error),
@@ -144,11 +144,11 @@ pub fn del_mdata_user_permissions<T: 'static>(
                         Err(error)
                     }
                 }
-                CoreError::RequestTimeout => {
+                CoreError::RequestTimeout(msg_id) => {
                     if attempts < MAX_ATTEMPTS {
                         Ok(Loop::Continue((attempts + 1, version)))
                     } else {
-                        Err(CoreError::RequestTimeout)
+                        Err(CoreError::RequestTimeout(msg_id))
                     }
                 }
                 error => Err(error),
@@ -329,11 +329,11 @@ pub fn ins_auth_key<T: 'static>(
                         Err(error)
                     }
                 }
-                CoreError::RequestTimeout => {
+                CoreError::RequestTimeout(msg_id) => {
                     if attempts < MAX_ATTEMPTS {
                         Ok(Loop::Continue((attempts + 1, version)))
                     } else {
-                        Err(CoreError::RequestTimeout)
+                        Err(CoreError::RequestTimeout(msg_id))
                     }
                 }
                 error => Err(error),
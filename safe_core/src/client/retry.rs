@@ -0,0 +1,109 @@
+// Copyright 2017 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under (1) the MaidSafe.net Commercial License,
+// version 1.0 or later, or (2) The General Public License (GPL), version 3, depending on which
+// licence you accepted on initial access to the Software (the "Licences").
+//
+// By contributing code to the SAFE Network Software, or to this project generally, you agree to be
+// bound by the terms of the MaidSafe Contributor Agreement.  This, along with the Licenses can be
+// found in the root directory of this project at LICENSE, COPYING and CONTRIBUTOR.
+//
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.
+//
+// Please review the Licences for the specific language governing permissions and limitations
+// relating to use of the SAFE Network Software.
+
+use errors::CoreError;
+use event::CoreEvent;
+use metrics::Operation;
+use routing::ClientError;
+use std::cmp;
+use std::time::Duration;
+
+/// Policy of retrying the requests failed with transient errors (timeouts and
+/// `NetworkOther`). Only the requests which can't take effect twice are
+/// retried: reads, and mutations checked against the version of the data.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RetryPolicy {
+    /// Maximum number of attempts, including the first one.
+    pub max_attempts: u32,
+    /// Delay before the first retry. It's doubled before every further one.
+    pub initial_backoff: Duration,
+    /// Maximum delay between the retries.
+    pub max_backoff: Duration,
+}
+
+impl RetryPolicy {
+    /// Delay before retrying the given failed attempt (counting from 1).
+    pub fn backoff(&self, attempt: u32) -> Duration {
+        let factor = 1u32.checked_shl(attempt.saturating_sub(1)).unwrap_or(u32::max_value());
+        let backoff = self.initial_backoff.checked_mul(factor).unwrap_or(
+            self.max_backoff,
+        );
+        cmp::min(backoff, self.max_backoff)
+    }
+}
+
+// Is the mutation checked against the version of the data, so retrying it
+// after it actually succeeded fails rather than applies it twice?
+pub fn is_versioned(op: Operation) -> bool {
+    match op {
+        Operation::SetMDataUserPermissions |
+        Operation::DelMDataUserPermissions |
+        Operation::ChangeMDataOwner |
+        Operation::InsAuthKey |
+        Operation::DelAuthKey => true,
+        _ => false,
+    }
+}
+
+// Is the request worth retrying after failing with the given error?
+pub fn is_transient(error: &CoreError) -> bool {
+    match *error {
+        CoreError::RequestTimeout(_) |
+        CoreError::RoutingClientError(ClientError::NetworkOther(_)) => true,
+        _ => false,
+    }
+}
+
+// Error carried by the response event, if any.
+pub fn event_error(event: &CoreEvent) -> Option<&CoreError> {
+    match *event {
+        CoreEvent::GetAccountInfo(Err(ref error)) |
+        CoreEvent::Mutation(Err(ref error)) |
+        CoreEvent::GetIData(Err(ref error)) |
+        CoreEvent::GetMDataVersion(Err(ref error)) |
+        CoreEvent::ListMDataEntries(Err(ref error)) |
+        CoreEvent::ListMDataKeys(Err(ref error)) |
+        CoreEvent::ListMDataValues(Err(ref error)) |
+        CoreEvent::GetMDataValue(Err(ref error)) |
+        CoreEvent::ListMDataPermissions(Err(ref error)) |
+        CoreEvent::ListMDataUserPermissions(Err(ref error)) |
+        CoreEvent::ListAuthKeysAndVersion(Err(ref error)) |
+        CoreEvent::GetMDataShell(Err(ref error)) |
+        CoreEvent::GetMData(Err(ref error)) => Some(error),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn backoff() {
+        let policy = RetryPolicy {
+            max_attempts: 10,
+            initial_backoff: Duration::from_millis(100),
+            max_backoff: Duration::from_millis(500),
+        };
+
+        assert_eq!(policy.backoff(1), Duration::from_millis(100));
+        assert_eq!(policy.backoff(2), Duration::from_millis(200));
+        assert_eq!(policy.backoff(3), Duration::from_millis(400));
+        assert_eq!(policy.backoff(4), Duration::from_millis(500));
+        assert_eq!(policy.backoff(40), Duration::from_millis(500));
+    }
+}
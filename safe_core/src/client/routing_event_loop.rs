@@ -38,6 +38,15 @@ where
                     break;
                 }
             }
+            Event::RestartRequired => {
+                let msg = CoreMsg::new(|client, _| {
+                    client.restart_required();
+                    None
+                });
+                if core_tx.unbounded_send(msg).is_err() {
+                    break;
+                }
+            }
             Event::Terminate => {
                 if let Err(e) = net_tx.unbounded_send(NetworkEvent::Disconnected) {
                     trace!("Couldn't send NetworkEvent::Disconnected: {:?}", e);
@@ -0,0 +1,228 @@
+// Copyright 2017 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under (1) the MaidSafe.net Commercial License,
+// version 1.0 or later, or (2) The General Public License (GPL), version 3, depending on which
+// licence you accepted on initial access to the Software (the "Licences").
+//
+// By contributing code to the SAFE Network Software, or to this project generally, you agree to be
+// bound by the terms of the MaidSafe Contributor Agreement.  This, along with the Licenses can be
+// found in the root directory of this project at LICENSE, COPYING and CONTRIBUTOR.
+//
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.
+//
+// Please review the Licences for the specific language governing permissions and limitations
+// relating to use of the SAFE Network Software.
+
+use futures::{Future, future};
+use futures::sync::oneshot;
+use std::cell::RefCell;
+use std::collections::{BTreeMap, VecDeque};
+use std::rc::Rc;
+
+/// Priority of the requests sent to the network. When the number of requests
+/// in flight is limited, the waiting requests of higher priority are sent
+/// first.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Priority {
+    /// Requests the user is waiting for, e.g. reads triggered by the UI.
+    High,
+    /// The default priority.
+    Normal,
+    /// Background work, e.g. uploads.
+    Low,
+}
+
+impl Default for Priority {
+    fn default() -> Self {
+        Priority::Normal
+    }
+}
+
+// Limits the number of requests in flight, queueing the others by priority.
+#[derive(Clone, Default)]
+pub struct Scheduler(Rc<RefCell<State>>);
+
+#[derive(Default)]
+struct State {
+    // Priority of the requests issued from now on.
+    priority: Priority,
+    max_in_flight: Option<usize>,
+    in_flight: usize,
+    waiting: BTreeMap<Priority, VecDeque<oneshot::Sender<Slot>>>,
+}
+
+impl Scheduler {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // Priority of the requests issued from now on.
+    pub fn priority(&self) -> Priority {
+        self.0.borrow().priority
+    }
+
+    pub fn set_priority(&self, priority: Priority) {
+        self.0.borrow_mut().priority = priority;
+    }
+
+    // Change the limit of requests in flight (`None` for no limit), letting
+    // the waiting requests through if it was raised.
+    pub fn set_max_in_flight(&self, max_in_flight: Option<usize>) {
+        self.0.borrow_mut().max_in_flight = max_in_flight;
+        self.pump();
+    }
+
+    // Take a slot for a request of the given priority. The returned future
+    // resolves once the slot is granted, and the slot is given back when it's
+    // dropped. Dropping the future before that gives up the place in the
+    // queue.
+    pub fn acquire(
+        &self,
+        priority: Priority,
+    ) -> Box<Future<Item = Slot, Error = oneshot::Canceled>> {
+        {
+            let mut state = self.0.borrow_mut();
+            if state.has_capacity() && state.waiting.is_empty() {
+                state.in_flight += 1;
+                return Box::new(future::ok(Slot(self.clone())));
+            }
+        }
+
+        let (tx, rx) = oneshot::channel();
+        self.0
+            .borrow_mut()
+            .waiting
+            .entry(priority)
+            .or_insert_with(VecDeque::new)
+            .push_back(tx);
+        self.pump();
+        Box::new(rx)
+    }
+
+    // Give back the slot of a finished request.
+    fn release(&self) {
+        {
+            let mut state = self.0.borrow_mut();
+            state.in_flight = state.in_flight.saturating_sub(1);
+        }
+        self.pump();
+    }
+
+    // Grant the free slots to the waiting requests, in the order of priority.
+    fn pump(&self) {
+        loop {
+            let tx = {
+                let mut state = self.0.borrow_mut();
+                if !state.has_capacity() {
+                    return;
+                }
+                match state.pop_waiting() {
+                    Some(tx) => {
+                        state.in_flight += 1;
+                        tx
+                    }
+                    None => return,
+                }
+            };
+
+            // The request could have been dropped in the meantime, in which
+            // case the returned slot is given back right away.
+            let _ = tx.send(Slot(self.clone()));
+        }
+    }
+}
+
+impl State {
+    fn pop_waiting(&mut self) -> Option<oneshot::Sender<Slot>> {
+        let priority = match self.waiting.keys().next() {
+            Some(priority) => *priority,
+            None => return None,
+        };
+
+        let (tx, empty) = {
+            let queue = unwrap!(self.waiting.get_mut(&priority));
+            (queue.pop_front(), queue.is_empty())
+        };
+        if empty {
+            let _ = self.waiting.remove(&priority);
+        }
+        tx
+    }
+
+    fn has_capacity(&self) -> bool {
+        self.max_in_flight.map_or(true, |max| self.in_flight < max)
+    }
+}
+
+// Slot of a request in flight, given back to the scheduler when dropped, so
+// requests which are cancelled or time out don't hold it.
+pub struct Slot(Scheduler);
+
+impl Drop for Slot {
+    fn drop(&mut self) {
+        self.0.release();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Waiting requests are granted the slots in the order of priority, and in
+    // the order they were issued within the same priority.
+    #[test]
+    fn priority_order() {
+        let scheduler = Scheduler::new();
+        scheduler.set_max_in_flight(Some(1));
+
+        let first = unwrap!(scheduler.acquire(Priority::Low).wait());
+        let low = scheduler.acquire(Priority::Low);
+        let high0 = scheduler.acquire(Priority::High);
+        let high1 = scheduler.acquire(Priority::High);
+
+        drop(first);
+        let slot = unwrap!(high0.wait());
+
+        // A dropped request doesn't hold the slot.
+        drop(high1);
+        drop(slot);
+        let slot = unwrap!(low.wait());
+
+        drop(slot);
+        let _slot = unwrap!(scheduler.acquire(Priority::Normal).wait());
+    }
+
+    // Raising the limit lets the waiting requests through.
+    #[test]
+    fn raise_limit() {
+        let scheduler = Scheduler::new();
+        scheduler.set_max_in_flight(Some(0));
+
+        let first = scheduler.acquire(Priority::Normal);
+        let second = scheduler.acquire(Priority::Normal);
+
+        scheduler.set_max_in_flight(None);
+        let _first = unwrap!(first.wait());
+        let _second = unwrap!(second.wait());
+    }
+
+    // A slot granted to a request which is dropped before taking it is given
+    // back.
+    #[test]
+    fn granted_slot_of_dropped_request() {
+        let scheduler = Scheduler::new();
+        scheduler.set_max_in_flight(Some(1));
+
+        let first = unwrap!(scheduler.acquire(Priority::Normal).wait());
+        let second = scheduler.acquire(Priority::Normal);
+        let third = scheduler.acquire(Priority::Normal);
+
+        // The slot is granted to `second`, which is dropped without taking it.
+        drop(first);
+        drop(second);
+
+        let _third = unwrap!(third.wait());
+    }
+}
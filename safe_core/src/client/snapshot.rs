@@ -0,0 +1,96 @@
+// Copyright 2017 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under (1) the MaidSafe.net Commercial License,
+// version 1.0 or later, or (2) The General Public License (GPL), version 3, depending on which
+// licence you accepted on initial access to the Software (the "Licences").
+//
+// By contributing code to the SAFE Network Software, or to this project generally, you agree to be
+// bound by the terms of the MaidSafe Contributor Agreement.  This, along with the Licenses can be
+// found in the root directory of this project at LICENSE, COPYING and CONTRIBUTOR.
+//
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.
+//
+// Please review the Licences for the specific language governing permissions and limitations
+// relating to use of the SAFE Network Software.
+
+use routing::{EntryAction, MutableData, Value, XorName};
+use std::collections::BTreeMap;
+
+/// Entries of a `MutableData` together with the version of its shell, as
+/// fetched at one point in time. Changes prepared against a snapshot can be
+/// committed with `Client::commit_snapshot`, which fails if the data changed
+/// in the meantime.
+#[derive(Clone, Debug, PartialEq)]
+pub struct MDataSnapshot {
+    /// Name of the data.
+    pub name: XorName,
+    /// Type tag of the data.
+    pub tag: u64,
+    /// Version of the data shell (permissions and owners).
+    pub version: u64,
+    /// All entries, including the deleted ones (which have empty content).
+    pub entries: BTreeMap<Vec<u8>, Value>,
+}
+
+impl MDataSnapshot {
+    /// Take the snapshot of the given `MutableData`.
+    pub fn new(data: &MutableData) -> Self {
+        MDataSnapshot {
+            name: *data.name(),
+            tag: data.tag(),
+            version: data.version(),
+            entries: data.entries().clone(),
+        }
+    }
+
+    /// Content of the entry, unless it's missing or deleted.
+    pub fn get(&self, key: &[u8]) -> Option<&[u8]> {
+        self.entries.get(key).and_then(|value| if value.content.is_empty() {
+            None
+        } else {
+            Some(&value.content[..])
+        })
+    }
+
+    /// Set the versions of the entry actions to succeed the versions in this
+    /// snapshot, so the mutation fails if any touched entry has changed since.
+    /// Inserting a deleted entry becomes its update and updating a missing one
+    /// its insert. Inserting an existing entry or deleting a missing one is
+    /// left to be rejected by the network.
+    pub fn rebase_actions(
+        &self,
+        actions: BTreeMap<Vec<u8>, EntryAction>,
+    ) -> BTreeMap<Vec<u8>, EntryAction> {
+        actions
+            .into_iter()
+            .map(|(key, action)| {
+                let current_version = self.entries.get(&key).map(|value| {
+                    (value.entry_version, value.content.is_empty())
+                });
+
+                let action = match (action, current_version) {
+                    (EntryAction::Ins(value), Some((version, true))) |
+                    (EntryAction::Update(value), Some((version, _))) => {
+                        EntryAction::Update(Value {
+                            content: value.content,
+                            entry_version: version + 1,
+                        })
+                    }
+                    (EntryAction::Del(_), Some((version, _))) => EntryAction::Del(version + 1),
+                    (EntryAction::Ins(value), _) |
+                    (EntryAction::Update(value), None) => {
+                        EntryAction::Ins(Value {
+                            content: value.content,
+                            entry_version: 0,
+                        })
+                    }
+                    (EntryAction::Del(_), None) => EntryAction::Del(0),
+                };
+
+                (key, action)
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,120 @@
+// Copyright 2017 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under (1) the MaidSafe.net Commercial License,
+// version 1.0 or later, or (2) The General Public License (GPL), version 3, depending on which
+// licence you accepted on initial access to the Software (the "Licences").
+//
+// By contributing code to the SAFE Network Software, or to this project generally, you agree to be
+// bound by the terms of the MaidSafe Contributor Agreement.  This, along with the Licenses can be
+// found in the root directory of this project at LICENSE, COPYING and CONTRIBUTOR.
+//
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.
+//
+// Please review the Licences for the specific language governing permissions and limitations
+// relating to use of the SAFE Network Software.
+
+use routing::Value;
+use std::collections::{BTreeMap, BTreeSet};
+
+/// Changes of the entries of a `MutableData` between two points in time, as
+/// reported by `Client::watch_mdata`.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct MDataDiff {
+    /// Entries which were inserted (or re-inserted after deletion).
+    pub added: BTreeMap<Vec<u8>, Value>,
+    /// Entries whose version changed.
+    pub updated: BTreeMap<Vec<u8>, Value>,
+    /// Keys of the entries which were deleted.
+    pub deleted: BTreeSet<Vec<u8>>,
+}
+
+impl MDataDiff {
+    /// Compute the changes from the `old` entries to the `new` ones. Deleted
+    /// entries (which have empty content) are treated as missing.
+    pub fn between(old: &BTreeMap<Vec<u8>, Value>, new: &BTreeMap<Vec<u8>, Value>) -> Self {
+        let mut diff = MDataDiff::default();
+
+        for (key, value) in new {
+            let old_value = old.get(key).and_then(|value| if value.content.is_empty() {
+                None
+            } else {
+                Some(value)
+            });
+
+            match old_value {
+                None if value.content.is_empty() => (),
+                None => {
+                    let _ = diff.added.insert(key.clone(), value.clone());
+                }
+                Some(_) if value.content.is_empty() => {
+                    let _ = diff.deleted.insert(key.clone());
+                }
+                Some(old_value) if old_value.entry_version != value.entry_version => {
+                    let _ = diff.updated.insert(key.clone(), value.clone());
+                }
+                Some(_) => (),
+            }
+        }
+
+        for (key, value) in old {
+            if !value.content.is_empty() && !new.contains_key(key) {
+                let _ = diff.deleted.insert(key.clone());
+            }
+        }
+
+        diff
+    }
+
+    /// Whether there are no changes.
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.updated.is_empty() && self.deleted.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn value(content: &[u8], entry_version: u64) -> Value {
+        Value {
+            content: content.to_vec(),
+            entry_version,
+        }
+    }
+
+    #[test]
+    fn between() {
+        let old = btree_map![
+            b"same".to_vec() => value(b"1", 0),
+            b"updated".to_vec() => value(b"1", 0),
+            b"deleted".to_vec() => value(b"1", 0),
+            b"removed".to_vec() => value(b"1", 0),
+            b"reinserted".to_vec() => value(b"", 1)
+        ];
+        let new = btree_map![
+            b"same".to_vec() => value(b"1", 0),
+            b"updated".to_vec() => value(b"2", 1),
+            b"deleted".to_vec() => value(b"", 1),
+            b"reinserted".to_vec() => value(b"2", 2),
+            b"added".to_vec() => value(b"1", 0)
+        ];
+
+        let diff = MDataDiff::between(&old, &new);
+        assert_eq!(
+            diff.added,
+            btree_map![
+                b"added".to_vec() => value(b"1", 0),
+                b"reinserted".to_vec() => value(b"2", 2)
+            ]
+        );
+        assert_eq!(diff.updated, btree_map![b"updated".to_vec() => value(b"2", 1)]);
+        assert_eq!(
+            diff.deleted,
+            btree_set![b"deleted".to_vec(), b"removed".to_vec()]
+        );
+
+        assert!(MDataDiff::between(&new, &new).is_empty());
+    }
+}
@@ -0,0 +1,333 @@
+// Copyright 2017 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under (1) the MaidSafe.net Commercial License,
+// version 1.0 or later, or (2) The General Public License (GPL), version 3, depending on which
+// licence you accepted on initial access to the Software (the "Licences").
+//
+// By contributing code to the SAFE Network Software, or to this project generally, you agree to be
+// bound by the terms of the MaidSafe Contributor Agreement.  This, along with the Licenses can be
+// found in the root directory of this project at LICENSE, COPYING and CONTRIBUTOR.
+//
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.
+//
+// Please review the Licences for the specific language governing permissions and limitations
+// relating to use of the SAFE Network Software.
+
+//! Process-wide configuration of safe_core: network timeouts, caching,
+//! retries, logging and how the account packet is encrypted. The KDF
+//! parameters are stored in the header of every packet encrypted with them, so
+//! raising the cost later doesn't prevent existing accounts from logging in.
+//!
+//! The defaults can be overridden by the optional JSON file `safe_core.config`,
+//! looked up next to the executable and in the other `config_file_handler`
+//! search paths (including the one set by `set_additional_search_path`). The
+//! file is read on first use of the configuration; call `reload` after
+//! changing the search path. All its fields are optional, e.g.
+//!
+//! ```json
+//! {
+//!     "request_timeout_secs": 60,
+//!     "immut_data_cache_size": 1000,
+//!     "log_filter": "warn,safe_core::client=debug"
+//! }
+//! ```
+
+use config_file_handler::{self, FileHandler};
+use errors::CoreError;
+use logging::LogConfig;
+use rust_sodium::crypto::pwhash;
+use std::io;
+use std::sync::Mutex;
+use std::time::Duration;
+
+/// Name of the configuration file.
+pub const CONFIG_FILE_NAME: &'static str = "safe_core.config";
+/// Default time to wait for connecting to the network.
+pub const DEFAULT_CONNECTION_TIMEOUT_SECS: u64 = 40;
+/// Default time to wait for the response to a request.
+pub const DEFAULT_REQUEST_TIMEOUT_SECS: u64 = 180;
+/// Default number of `ImmutableData` chunks cached in memory.
+pub const DEFAULT_IMMUT_DATA_CACHE_SIZE: usize = 300;
+/// Default delay before resending a request rejected due to rate limiting.
+pub const DEFAULT_RETRY_DELAY_MS: u64 = 800;
+/// Default number of attempts of `Client::mutate_mdata_transact`.
+pub const DEFAULT_TRANSACT_ATTEMPTS: usize = 5;
+
+lazy_static! {
+    static ref CONFIG: Mutex<CoreConfig> = Mutex::new(load().unwrap_or_else(|err| {
+        warn!("Couldn't read {}: {:?}", CONFIG_FILE_NAME, err);
+        CoreConfig::default()
+    }));
+}
+
+/// Key derivation function turning the user's credentials into the key the
+/// account packet is encrypted with, together with its work factor.
+///
+/// Only scrypt is offered, as it's the only password hash exposed by the
+/// `rust_sodium` version in use. New functions (e.g. Argon2id) can be added as
+/// further variants without affecting the packets encrypted so far.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Kdf {
+    /// scrypt (`crypto_pwhash_scryptsalsa208sha256`).
+    Scrypt {
+        /// Maximum number of computations to perform.
+        ops_limit: u64,
+        /// Maximum amount of memory to use, in bytes.
+        mem_limit: u64,
+    },
+}
+
+impl Kdf {
+    /// scrypt with the parameters recommended for interactive logins. This is
+    /// what accounts were encrypted with before the KDF became configurable.
+    pub fn scrypt_interactive() -> Self {
+        Kdf::Scrypt {
+            ops_limit: pwhash::OPSLIMIT_INTERACTIVE.0 as u64,
+            mem_limit: pwhash::MEMLIMIT_INTERACTIVE.0 as u64,
+        }
+    }
+
+    /// scrypt with the parameters recommended for highly sensitive data. Note
+    /// that these require about 1 GiB of memory and several seconds to log in.
+    pub fn scrypt_sensitive() -> Self {
+        Kdf::Scrypt {
+            ops_limit: pwhash::OPSLIMIT_SENSITIVE.0 as u64,
+            mem_limit: pwhash::MEMLIMIT_SENSITIVE.0 as u64,
+        }
+    }
+}
+
+impl Default for Kdf {
+    fn default() -> Self {
+        Self::scrypt_interactive()
+    }
+}
+
+/// Configuration of safe_core.
+#[derive(Clone, Debug, PartialEq)]
+pub struct CoreConfig {
+    connection_timeout: Duration,
+    request_timeout: Duration,
+    immut_data_cache_size: usize,
+    retry_delay: Duration,
+    transact_attempts: usize,
+    log_filter: Option<String>,
+    kdf: Kdf,
+}
+
+impl Default for CoreConfig {
+    fn default() -> Self {
+        CoreConfig {
+            connection_timeout: Duration::from_secs(DEFAULT_CONNECTION_TIMEOUT_SECS),
+            request_timeout: Duration::from_secs(DEFAULT_REQUEST_TIMEOUT_SECS),
+            immut_data_cache_size: DEFAULT_IMMUT_DATA_CACHE_SIZE,
+            retry_delay: Duration::from_millis(DEFAULT_RETRY_DELAY_MS),
+            transact_attempts: DEFAULT_TRANSACT_ATTEMPTS,
+            log_filter: None,
+            kdf: Kdf::default(),
+        }
+    }
+}
+
+impl CoreConfig {
+    /// Create the default configuration.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the time to wait for connecting to the network.
+    pub fn connection_timeout(mut self, timeout: Duration) -> Self {
+        self.connection_timeout = timeout;
+        self
+    }
+
+    /// Set the time to wait for the response to a request.
+    pub fn request_timeout(mut self, timeout: Duration) -> Self {
+        self.request_timeout = timeout;
+        self
+    }
+
+    /// Set the number of `ImmutableData` chunks newly created clients cache.
+    pub fn immut_data_cache_size(mut self, size: usize) -> Self {
+        self.immut_data_cache_size = size;
+        self
+    }
+
+    /// Set the delay before resending a request rejected due to rate limiting.
+    pub fn retry_delay(mut self, delay: Duration) -> Self {
+        self.retry_delay = delay;
+        self
+    }
+
+    /// Set the number of attempts of `Client::mutate_mdata_transact`.
+    pub fn transact_attempts(mut self, attempts: usize) -> Self {
+        self.transact_attempts = attempts;
+        self
+    }
+
+    /// Set the filter `log_config` applies (see `LogConfig::filter`).
+    pub fn log_filter<S: Into<String>>(mut self, filter: S) -> Self {
+        self.log_filter = Some(filter.into());
+        self
+    }
+
+    /// Set the key derivation function newly encrypted account packets use.
+    pub fn kdf(mut self, kdf: Kdf) -> Self {
+        self.kdf = kdf;
+        self
+    }
+
+    /// Time to wait for connecting to the network.
+    pub fn get_connection_timeout(&self) -> Duration {
+        self.connection_timeout
+    }
+
+    /// Time to wait for the response to a request.
+    pub fn get_request_timeout(&self) -> Duration {
+        self.request_timeout
+    }
+
+    /// Number of `ImmutableData` chunks newly created clients cache.
+    pub fn get_immut_data_cache_size(&self) -> usize {
+        self.immut_data_cache_size
+    }
+
+    /// Delay before resending a request rejected due to rate limiting.
+    pub fn get_retry_delay(&self) -> Duration {
+        self.retry_delay
+    }
+
+    /// Number of attempts of `Client::mutate_mdata_transact`.
+    pub fn get_transact_attempts(&self) -> usize {
+        self.transact_attempts
+    }
+
+    /// Key derivation function newly encrypted account packets use.
+    pub fn get_kdf(&self) -> Kdf {
+        self.kdf
+    }
+
+    /// Logger configuration with the configured filter applied, to be passed
+    /// to `logging::init`.
+    pub fn log_config(&self) -> Result<LogConfig, CoreError> {
+        match self.log_filter {
+            Some(ref filter) => LogConfig::new().filter(filter),
+            None => Ok(LogConfig::new()),
+        }
+    }
+
+    fn apply(mut self, file: ConfigFile) -> Self {
+        if let Some(secs) = file.connection_timeout_secs {
+            self.connection_timeout = Duration::from_secs(secs);
+        }
+        if let Some(secs) = file.request_timeout_secs {
+            self.request_timeout = Duration::from_secs(secs);
+        }
+        if let Some(size) = file.immut_data_cache_size {
+            self.immut_data_cache_size = size;
+        }
+        if let Some(ms) = file.retry_delay_ms {
+            self.retry_delay = Duration::from_millis(ms);
+        }
+        if let Some(attempts) = file.transact_attempts {
+            self.transact_attempts = attempts;
+        }
+        if file.log_filter.is_some() {
+            self.log_filter = file.log_filter;
+        }
+        if let Some(kdf) = file.kdf {
+            self.kdf = kdf;
+        }
+        self
+    }
+}
+
+/// Contents of the configuration file. Missing fields keep their defaults.
+#[derive(Debug, Default, Deserialize, Serialize)]
+struct ConfigFile {
+    connection_timeout_secs: Option<u64>,
+    request_timeout_secs: Option<u64>,
+    immut_data_cache_size: Option<usize>,
+    retry_delay_ms: Option<u64>,
+    transact_attempts: Option<usize>,
+    log_filter: Option<String>,
+    kdf: Option<Kdf>,
+}
+
+/// Read the configuration from the configuration file, defaulting the missing
+/// fields.
+pub fn load() -> Result<CoreConfig, CoreError> {
+    let res = FileHandler::<ConfigFile>::new(&CONFIG_FILE_NAME, false)
+        .and_then(|handler| handler.read_file());
+    let file = match res {
+        Ok(file) => file,
+        // The file is optional.
+        Err(config_file_handler::Error::Io(ref err)) if err.kind() == io::ErrorKind::NotFound => {
+            debug!("No {} found, using the defaults", CONFIG_FILE_NAME);
+            ConfigFile::default()
+        }
+        Err(err) => return Err(CoreError::Unexpected(format!("{}", err))),
+    };
+
+    Ok(CoreConfig::default().apply(file))
+}
+
+/// Re-read the configuration file, replacing the current configuration.
+pub fn reload() -> Result<(), CoreError> {
+    set(load()?);
+    Ok(())
+}
+
+/// Replace the configuration. Affects only the operations started afterwards.
+pub fn set(config: CoreConfig) {
+    if let Ok(mut current) = CONFIG.lock() {
+        *current = config;
+    }
+}
+
+/// Get the current configuration.
+pub fn get() -> CoreConfig {
+    match CONFIG.lock() {
+        Ok(config) => config.clone(),
+        Err(_) => CoreConfig::default(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Test the fields present in the configuration file override the
+    // defaults.
+    #[test]
+    fn file_overrides() {
+        let file = ConfigFile {
+            request_timeout_secs: Some(60),
+            transact_attempts: Some(2),
+            log_filter: Some("warn,safe_core::client=debug".to_string()),
+            ..ConfigFile::default()
+        };
+        let config = CoreConfig::default().apply(file);
+
+        assert_eq!(config.get_request_timeout(), Duration::from_secs(60));
+        assert_eq!(config.get_transact_attempts(), 2);
+        assert_eq!(
+            config.get_connection_timeout(),
+            Duration::from_secs(DEFAULT_CONNECTION_TIMEOUT_SECS)
+        );
+        assert_eq!(
+            config.get_immut_data_cache_size(),
+            DEFAULT_IMMUT_DATA_CACHE_SIZE
+        );
+        assert_eq!(config.get_kdf(), Kdf::default());
+        let _ = unwrap!(config.log_config());
+
+        let config = CoreConfig::default().apply(ConfigFile {
+            log_filter: Some("nonsense=level".to_string()),
+            ..ConfigFile::default()
+        });
+        assert!(config.log_config().is_err());
+    }
+}
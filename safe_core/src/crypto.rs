@@ -216,3 +216,42 @@ pub mod shared_sign {
         }
     }
 }
+
+/// Key derivation utilities.
+pub mod hkdf {
+    use rust_sodium::crypto::auth::hmacsha256;
+
+    /// Length of the derived keys.
+    pub const KEY_LEN: usize = hmacsha256::TAGBYTES;
+
+    /// Derive a key from the input keying material `ikm` and the
+    /// context-specific `info` using HKDF (RFC 5869) with SHA-256 and no salt.
+    /// A single block of output keying material is produced.
+    pub fn derive(ikm: &[u8], info: &[u8]) -> [u8; KEY_LEN] {
+        let salt = hmacsha256::Key([0; hmacsha256::KEYBYTES]);
+        let hmacsha256::Tag(prk) = hmacsha256::authenticate(ikm, &salt);
+
+        let mut input = info.to_vec();
+        input.push(1);
+
+        let hmacsha256::Tag(okm) = hmacsha256::authenticate(&input, &hmacsha256::Key(prk));
+        okm
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        // Test case 3 from RFC 5869 (no salt, no info), truncated to one block.
+        #[test]
+        fn rfc_vector() {
+            let okm = derive(&[0x0b; 22], &[]);
+            let expected = [
+                0x8d, 0xa4, 0xe7, 0x75, 0xa5, 0x63, 0xc1, 0x8f, 0x71, 0x5f, 0x80, 0x2a, 0x06,
+                0x3c, 0x5a, 0x31, 0xb8, 0xa1, 0x1f, 0x5c, 0x5e, 0xe1, 0x87, 0x9e, 0xc3, 0x45,
+                0x4e, 0x5f, 0x3c, 0x73, 0x8d, 0x2d,
+            ];
+            assert_eq!(okm, expected);
+        }
+    }
+}
@@ -17,7 +17,7 @@
 
 use futures::sync::mpsc::SendError;
 use maidsafe_utilities::serialisation::SerialisationError;
-use routing::{ClientError, InterfaceError, RoutingError};
+use routing::{ClientError, InterfaceError, MessageId, RoutingError};
 use routing::messaging;
 use self_encryption::SelfEncryptionError;
 use self_encryption_storage::SelfEncryptionStorageError;
@@ -66,8 +66,9 @@ pub enum CoreError {
     MpidMessagingError(messaging::Error),
     /// Error while self-encrypting data
     SelfEncryption(SelfEncryptionError<SelfEncryptionStorageError>),
-    /// The request has timed out
-    RequestTimeout,
+    /// The request with the given id (`None` when connecting to the network)
+    /// has timed out
+    RequestTimeout(Option<MessageId>),
 }
 
 impl<'a> From<&'a str> for CoreError {
@@ -178,7 +179,9 @@ impl Debug for CoreError {
             CoreError::SelfEncryption(ref error) => {
                 write!(formatter, "CoreError::SelfEncryption -> {:?}", error)
             }
-            CoreError::RequestTimeout => write!(formatter, "CoreError::RequestTimeout"),
+            CoreError::RequestTimeout(ref msg_id) => {
+                write!(formatter, "CoreError::RequestTimeout -> {:?}", msg_id)
+            }
         }
     }
 }
@@ -243,7 +246,10 @@ impl Display for CoreError {
             CoreError::SelfEncryption(ref error) => {
                 write!(formatter, "Self-encryption error: {}", error)
             }
-            CoreError::RequestTimeout => write!(formatter, "CoreError::RequestTimeout"),
+            CoreError::RequestTimeout(Some(ref msg_id)) => {
+                write!(formatter, "Request {:?} has timed out", msg_id)
+            }
+            CoreError::RequestTimeout(None) => write!(formatter, "Request has timed out"),
         }
     }
 }
@@ -271,7 +277,7 @@ impl Error for CoreError {
             CoreError::OperationAborted => "Operation aborted",
             CoreError::MpidMessagingError(_) => "Mpid messaging error",
             CoreError::SelfEncryption(ref error) => error.description(),
-            CoreError::RequestTimeout => "Request has timed out",
+            CoreError::RequestTimeout(_) => "Request has timed out",
         }
     }
 
@@ -60,7 +60,7 @@ pub enum CoreEvent {
 }
 
 /// Netowork Events that Client Modules need to deal with
-#[derive(Debug)]
+#[derive(Clone, Copy, Debug)]
 pub enum NetworkEvent {
     /// The core engine is connected to atleast one peer
     Connected,
@@ -68,6 +68,12 @@ pub enum NetworkEvent {
     /// circumstances this would indicate that client connection to proxy node
     /// has been lost)
     Disconnected,
+    /// Number of mutations available to the account dropped below the
+    /// configured threshold
+    LowBalance(u64),
+    /// The key of the app has been revoked by the owner of the account, so
+    /// its mutations are no longer accepted
+    AppRevoked,
 }
 
 impl Into<i32> for NetworkEvent {
@@ -75,6 +81,8 @@ impl Into<i32> for NetworkEvent {
         match self {
             NetworkEvent::Connected => NETWORK_EVENT_START_RANGE,
             NetworkEvent::Disconnected => NETWORK_EVENT_START_RANGE - 1,
+            NetworkEvent::LowBalance(_) => NETWORK_EVENT_START_RANGE - 2,
+            NetworkEvent::AppRevoked => NETWORK_EVENT_START_RANGE - 3,
         }
     }
 }
@@ -83,3 +91,6 @@ impl Into<i32> for NetworkEvent {
 pub type NetworkRx = mpsc::UnboundedReceiver<NetworkEvent>;
 /// `NetworkEvent` transmitter.
 pub type NetworkTx = mpsc::UnboundedSender<NetworkEvent>;
+/// Stream of the `NetworkEvent`s of a client, returned by
+/// `Client::network_events`.
+pub type NetworkEventStream = mpsc::UnboundedReceiver<NetworkEvent>;
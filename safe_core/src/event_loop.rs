@@ -18,9 +18,13 @@
 use client::Client;
 use errors::CoreError;
 use futures::Future;
+use futures::future::{self, Either, Loop};
 use futures::stream::Stream;
 use futures::sync::mpsc;
-use tokio_core::reactor::Core;
+use std::cell::Cell;
+use std::rc::Rc;
+use std::time::{Duration, Instant};
+use tokio_core::reactor::{Core, Timeout};
 
 /// Transmitter of messages to be run in the core event loop.
 pub type CoreMsgTx<T> = mpsc::UnboundedSender<CoreMsg<T>>;
@@ -30,9 +34,20 @@ pub type CoreMsgRx<T> = mpsc::UnboundedReceiver<CoreMsg<T>>;
 /// The final future which the event loop will run.
 pub type TailFuture = Box<Future<Item = (), Error = ()>>;
 type TailFutureFn<T> = FnMut(&Client<T>, &T) -> Option<TailFuture> + Send + 'static;
+type DrainedFn = FnMut() + Send + 'static;
+
+// How often the event loop checks whether the in-flight work has completed
+// while draining.
+const DRAIN_POLL_INTERVAL_MS: u64 = 10;
 
 /// The message format that core event loop understands.
-pub struct CoreMsg<T>(Option<Box<TailFutureFn<T>>>);
+pub struct CoreMsg<T>(Action<T>);
+
+enum Action<T> {
+    Run(Box<TailFutureFn<T>>),
+    Drain(Duration, Box<DrainedFn>),
+    Terminate,
+}
 
 /// Future trait returned from core operations.
 pub type CoreFuture<T> = Future<Item = T, Error = CoreError>;
@@ -46,7 +61,7 @@ impl<T> CoreMsg<T> {
         F: FnOnce(&Client<T>, &T) -> Option<TailFuture> + Send + 'static,
     {
         let mut f = Some(f);
-        CoreMsg(Some(
+        CoreMsg(Action::Run(
             Box::new(move |client, context| -> Option<TailFuture> {
                 let f = unwrap!(f.take());
                 f(client, context)
@@ -57,7 +72,25 @@ impl<T> CoreMsg<T> {
     /// Construct a new message which when processed by the event loop will
     /// terminate the event loop. This will be the graceful exit condition.
     pub fn build_terminator() -> Self {
-        CoreMsg(None)
+        CoreMsg(Action::Terminate)
+    }
+
+    /// Construct a new message which when processed by the event loop will
+    /// stop accepting further messages, wait up to `timeout` for the
+    /// in-flight requests and futures to complete and then terminate the
+    /// event loop. `on_drained` is called on the event loop thread right
+    /// before it exits.
+    pub fn build_drainer<F>(timeout: Duration, on_drained: F) -> Self
+    where
+        F: FnOnce() + Send + 'static,
+    {
+        let mut on_drained = Some(on_drained);
+        CoreMsg(Action::Drain(
+            timeout,
+            Box::new(move || if let Some(f) = on_drained.take() {
+                f()
+            }),
+        ))
     }
 }
 
@@ -65,19 +98,73 @@ impl<T> CoreMsg<T> {
 /// Hence must typically be called inside a spawned thread.
 pub fn run<T>(mut el: Core, client: &Client<T>, context: &T, el_rx: CoreMsgRx<T>) {
     let el_h = el.handle();
+    let in_flight = Rc::new(Cell::new(0));
+    let drain = Rc::new(Cell::new(None));
 
-    let keep_alive = el_rx.for_each(|core_msg| {
-        if let Some(mut f) = core_msg.0 {
-            if let Some(tail) = f(client, context) {
-                el_h.spawn(tail);
+    {
+        let keep_alive = el_rx.for_each(|core_msg| match core_msg.0 {
+            Action::Run(mut f) => {
+                if let Some(tail) = f(client, context) {
+                    in_flight.set(in_flight.get() + 1);
+                    let in_flight = Rc::clone(&in_flight);
+                    el_h.spawn(tail.then(move |res| {
+                        in_flight.set(in_flight.get() - 1);
+                        res
+                    }));
+                }
+                Ok(())
+            }
+            Action::Drain(timeout, on_drained) => {
+                drain.set(Some((timeout, on_drained)));
+                Err(())
             }
-            Ok(())
-        } else {
             // Err(io::Error::new(ErrorKind::Other, "Graceful Termination"))
-            Err(())
+            Action::Terminate => Err(()),
+        });
+
+        let _ = el.run(keep_alive);
+    }
+
+    if let Some((timeout, mut on_drained)) = drain.take() {
+        run_until_drained(&mut el, client, &in_flight, timeout);
+        on_drained();
+    }
+
+    debug!("Exiting Core Event Loop");
+}
+
+// Keep running the event loop until all the in-flight work has completed or
+// the `timeout` has elapsed.
+fn run_until_drained<T>(
+    el: &mut Core,
+    client: &Client<T>,
+    in_flight: &Rc<Cell<usize>>,
+    timeout: Duration,
+) {
+    let deadline = Instant::now() + timeout;
+    let el_h = el.handle();
+
+    let fut = future::loop_fn((), |()| {
+        if in_flight.get() == 0 && client.pending_requests().is_empty() {
+            return Either::A(future::ok(Loop::Break(())));
+        }
+        if Instant::now() >= deadline {
+            warn!(
+                "Shutting down with {} futures and {} requests still in flight.",
+                in_flight.get(),
+                client.pending_requests().len()
+            );
+            return Either::A(future::ok(Loop::Break(())));
+        }
+
+        let poll = Duration::from_millis(DRAIN_POLL_INTERVAL_MS);
+        match Timeout::new(poll, &el_h) {
+            Ok(timeout) => Either::B(timeout.map(|()| Loop::Continue(()))),
+            Err(err) => Either::A(future::err(err)),
         }
     });
 
-    let _ = el.run(keep_alive);
-    debug!("Exiting Core Event Loop");
+    if let Err(err) = el.run(fut) {
+        debug!("Error while draining the event loop: {:?}", err);
+    }
 }
@@ -17,14 +17,21 @@
 
 use client::Client;
 use crypto::shared_secretbox;
+use errors::CoreError;
 use event_loop::CoreFuture;
-use futures::Future;
+use futures::{Future, Stream, stream};
 use maidsafe_utilities::serialisation::{deserialise, serialise};
-use routing::{ImmutableData, XorName};
+use routing::{ImmutableData, XOR_NAME_LEN, XorName};
 use self_encryption::{DataMap, SelfEncryptor};
 use self_encryption_storage::SelfEncryptionStorage;
+use std::cmp;
+use std::collections::BTreeSet;
+use std::rc::Rc;
 use utils::{self, FutureExt};
 
+/// Default size of the pieces the value is yielded in by `get_value_stream`.
+pub const STREAM_PIECE_SIZE: u64 = 1024 * 1024;
+
 #[derive(Serialize, Deserialize)]
 enum DataTypeEncoding {
     Serialised(Vec<u8>),
@@ -73,20 +80,7 @@ pub fn extract_value<T: 'static>(
     data: &ImmutableData,
     decryption_key: Option<shared_secretbox::Key>,
 ) -> Box<CoreFuture<Vec<u8>>> {
-    let client = client.clone();
-
-    unpack(client.clone(), data)
-        .and_then(move |value| {
-            let data_map = if let Some(key) = decryption_key {
-                let plain_text = utils::symmetric_decrypt(&value, &key)?;
-                deserialise(&plain_text)?
-            } else {
-                deserialise(&value)?
-            };
-
-            let storage = SelfEncryptionStorage::new(client);
-            Ok(SelfEncryptor::new(storage, data_map)?)
-        })
+    value_encryptor(client.clone(), data, decryption_key)
         .and_then(|self_encryptor| {
             let length = self_encryptor.len();
             self_encryptor.read(0, length).map_err(From::from)
@@ -109,6 +103,113 @@ pub fn get_value<T: 'static>(
         .into_box()
 }
 
+/// Get immutable data created via `create()` from the network and stream its
+/// value in pieces of at most `piece_size` bytes, decrypting it in the process
+/// (if keys provided). Only the chunks backing the piece being read are
+/// fetched, so large values don't have to be held in memory whole.
+pub fn get_value_stream<T: 'static>(
+    client: &Client<T>,
+    name: &XorName,
+    decryption_key: Option<shared_secretbox::Key>,
+    piece_size: u64,
+) -> Box<Stream<Item = Vec<u8>, Error = CoreError>> {
+    let client2 = client.clone();
+    let piece_size = cmp::max(piece_size, 1);
+
+    let stream = client
+        .get_idata(*name)
+        .and_then(move |data| value_encryptor(client2, &data, decryption_key))
+        .map(move |self_encryptor| {
+            let length = self_encryptor.len();
+            let self_encryptor = Rc::new(self_encryptor);
+
+            stream::unfold(0, move |position| if position < length {
+                let len = cmp::min(piece_size, length - position);
+                let piece = self_encryptor
+                    .read(position, len)
+                    .map_err(CoreError::from)
+                    .map(move |piece| (piece, position + len));
+                Some(piece)
+            } else {
+                None
+            })
+        })
+        .flatten_stream();
+
+    Box::new(stream)
+}
+
+/// Get the names of all the chunks the value of immutable data created via
+/// `create()` is stored in, including `name` itself. The chunks of the value
+/// are found by decrypting (if keys provided) its `DataMap`; the value itself
+/// is not fetched.
+pub fn chunk_names<T: 'static>(
+    client: &Client<T>,
+    name: &XorName,
+    decryption_key: Option<shared_secretbox::Key>,
+) -> Box<CoreFuture<BTreeSet<XorName>>> {
+    let client2 = client.clone();
+    let mut names = BTreeSet::new();
+    let _ = names.insert(*name);
+
+    client
+        .get_idata(*name)
+        .and_then(move |data| unpack_chunk_names(client2, &data, names))
+        .and_then(move |(value, mut names)| {
+            let data_map: DataMap = if let Some(key) = decryption_key {
+                let plain_text = utils::symmetric_decrypt(&value, &key)?;
+                deserialise(&plain_text)?
+            } else {
+                deserialise(&value)?
+            };
+
+            names.extend(data_map_chunk_names(&data_map));
+            Ok(names)
+        })
+        .into_box()
+}
+
+/// Get the names of the chunks the content described by `data_map` is stored
+/// in.
+pub fn data_map_chunk_names(data_map: &DataMap) -> Vec<XorName> {
+    match *data_map {
+        DataMap::Chunks(ref chunks) => {
+            chunks
+                .iter()
+                .filter(|chunk| chunk.hash.len() == XOR_NAME_LEN)
+                .map(|chunk| {
+                    let mut name = [0u8; XOR_NAME_LEN];
+                    name.copy_from_slice(&chunk.hash);
+                    XorName(name)
+                })
+                .collect()
+        }
+        DataMap::Content(_) |
+        DataMap::None => Vec::new(),
+    }
+}
+
+// Self encryptor reading the value of immutable data created via `create()`.
+fn value_encryptor<T: 'static>(
+    client: Client<T>,
+    data: &ImmutableData,
+    decryption_key: Option<shared_secretbox::Key>,
+) -> Box<CoreFuture<SelfEncryptor<SelfEncryptionStorage<T>>>> {
+    unpack(client.clone(), data)
+        .and_then(move |value| {
+            let data_map = if let Some(key) = decryption_key {
+                let plain_text = utils::symmetric_decrypt(&value, &key)?;
+                deserialise(&plain_text)?
+            } else {
+                deserialise(&value)?
+            };
+
+            let storage = SelfEncryptionStorage::new(client);
+            Ok(SelfEncryptor::new(storage, data_map)?)
+        })
+        .into_box()
+}
+
 // TODO: consider rewriting these two function to not use recursion.
 
 fn pack<T: 'static>(client: Client<T>, value: Vec<u8>) -> Box<CoreFuture<ImmutableData>> {
@@ -151,10 +252,37 @@ fn unpack<T: 'static>(client: Client<T>, data: &ImmutableData) -> Box<CoreFuture
     }
 }
 
+// Same as `unpack`, additionally collecting the names of the chunks the packed
+// data is stored in.
+fn unpack_chunk_names<T: 'static>(
+    client: Client<T>,
+    data: &ImmutableData,
+    mut names: BTreeSet<XorName>,
+) -> Box<CoreFuture<(Vec<u8>, BTreeSet<XorName>)>> {
+    match fry!(deserialise(data.value())) {
+        DataTypeEncoding::Serialised(value) => ok!((value, names)),
+        DataTypeEncoding::DataMap(data_map) => {
+            names.extend(data_map_chunk_names(&data_map));
+
+            let storage = SelfEncryptionStorage::new(client.clone());
+            let self_encryptor = fry!(SelfEncryptor::new(storage, data_map));
+            let length = self_encryptor.len();
+            self_encryptor
+                .read(0, length)
+                .map_err(From::from)
+                .and_then(move |serialised_data| {
+                    let data = fry!(deserialise(&serialised_data));
+                    unpack_chunk_names(client, &data, names)
+                })
+                .into_box()
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use futures::Future;
+    use futures::{Future, Stream};
     use utils;
     use utils::test_utils::{finish, random_client};
 
@@ -183,6 +311,38 @@ mod tests {
         create_and_retrieve(10 * 1024 * 1024)
     }
 
+    // Test streaming the value of a 2mb idata in pieces.
+    #[test]
+    fn stream_value() {
+        let value = unwrap!(utils::generate_random_vector(2 * 1024 * 1024));
+        let key = shared_secretbox::gen_key();
+
+        random_client(move |client| {
+            let client2 = client.clone();
+            let client3 = client.clone();
+
+            create(client, &value.clone(), Some(key.clone()))
+                .then(move |res| {
+                    let data = unwrap!(res);
+                    let data_name = *data.name();
+                    client2.put_idata(data).map(move |_| data_name)
+                })
+                .then(move |res| {
+                    let data_name = unwrap!(res);
+                    get_value_stream(&client3, &data_name, Some(key), 300 * 1024).collect()
+                })
+                .then(move |res| {
+                    let pieces = unwrap!(res);
+                    assert_eq!(pieces.len(), 7);
+                    assert!(pieces.iter().all(|piece| piece.len() <= 300 * 1024));
+
+                    let value_after: Vec<u8> = pieces.into_iter().flat_map(|p| p).collect();
+                    assert_eq!(value_after, value);
+                    finish()
+                })
+        })
+    }
+
     fn create_and_retrieve(size: usize) {
         let value = unwrap!(utils::generate_random_vector(size));
 
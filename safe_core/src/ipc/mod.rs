@@ -19,6 +19,8 @@
 pub mod req;
 /// Response module
 pub mod resp;
+/// URI scheme handler registration
+pub mod uri_scheme;
 
 mod errors;
 
@@ -0,0 +1,268 @@
+// Copyright 2017 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under (1) the MaidSafe.net Commercial License,
+// version 1.0 or later, or (2) The General Public License (GPL), version 3, depending on which
+// licence you accepted on initial access to the Software (the "Licences").
+//
+// By contributing code to the SAFE Network Software, or to this project generally, you agree to be
+// bound by the terms of the MaidSafe Contributor Agreement.  This, along with the Licenses can be
+// found in the root directory of this project at LICENSE, COPYING and CONTRIBUTOR.
+//
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.
+//
+// Please review the Licences for the specific language governing permissions and limitations
+// relating to use of the SAFE Network Software.
+
+//! Registration of URI scheme handlers (e.g. `safe-auth:`) for the current
+//! executable. Each supported platform has its own implementation; on other
+//! platforms registration fails with `IpcError::Unexpected`.
+
+use super::errors::IpcError;
+use std::env;
+use std::io;
+use std::path::PathBuf;
+use std::process::Command;
+
+/// URI scheme handled by the authenticator.
+pub const SAFE_AUTH_SCHEME: &'static str = "safe-auth";
+
+/// Register the current executable as the handler of the given URI scheme
+/// (without the trailing colon). `name` is a human readable name of the
+/// application, shown by the OS in its handler selection dialogs.
+pub fn register(scheme: &str, name: &str) -> Result<(), IpcError> {
+    validate_scheme(scheme)?;
+    let exe = env::current_exe().map_err(io_error)?;
+    imp::register(scheme, name, exe)
+}
+
+/// Remove registration of the current executable as the handler of the
+/// given URI scheme.
+pub fn deregister(scheme: &str) -> Result<(), IpcError> {
+    validate_scheme(scheme)?;
+    let exe = env::current_exe().map_err(io_error)?;
+    imp::deregister(scheme, exe)
+}
+
+// RFC 3986: scheme = ALPHA *( ALPHA / DIGIT / "+" / "-" / "." )
+fn validate_scheme(scheme: &str) -> Result<(), IpcError> {
+    let mut chars = scheme.chars();
+    let valid = chars.next().map_or(false, is_alpha) &&
+        chars.all(|c| is_alpha(c) || is_digit(c) || c == '+' || c == '-' || c == '.');
+
+    if valid {
+        Ok(())
+    } else {
+        Err(IpcError::Unexpected(format!("Invalid URI scheme: {:?}", scheme)))
+    }
+}
+
+fn is_alpha(c: char) -> bool {
+    match c {
+        'a'...'z' | 'A'...'Z' => true,
+        _ => false,
+    }
+}
+
+fn is_digit(c: char) -> bool {
+    match c {
+        '0'...'9' => true,
+        _ => false,
+    }
+}
+
+fn io_error(err: io::Error) -> IpcError {
+    IpcError::Unexpected(format!("{}", err))
+}
+
+#[cfg(any(target_os = "linux", target_os = "windows", target_os = "macos"))]
+fn run(command: &mut Command) -> Result<(), IpcError> {
+    let status = command.status().map_err(io_error)?;
+    if status.success() {
+        Ok(())
+    } else {
+        Err(IpcError::Unexpected(
+            format!("{:?} failed with {}", command, status),
+        ))
+    }
+}
+
+#[cfg(target_os = "linux")]
+mod imp {
+    use super::{PathBuf, io_error, run};
+    use super::super::errors::IpcError;
+    use std::env;
+    use std::fs::{self, File};
+    use std::io::{ErrorKind, Write};
+    use std::process::Command;
+
+    pub fn register(scheme: &str, name: &str, exe: PathBuf) -> Result<(), IpcError> {
+        let dir = applications_dir()?;
+        fs::create_dir_all(&dir).map_err(io_error)?;
+
+        let entry = format!(
+            "[Desktop Entry]\n\
+             Type=Application\n\
+             Name={}\n\
+             Exec=\"{}\" %u\n\
+             NoDisplay=true\n\
+             MimeType=x-scheme-handler/{};\n",
+            name,
+            exe.display(),
+            scheme
+        );
+
+        let file_name = desktop_file_name(scheme);
+        let mut file = File::create(dir.join(&file_name)).map_err(io_error)?;
+        file.write_all(entry.as_bytes()).map_err(io_error)?;
+
+        run(Command::new("xdg-mime").args(
+            &[
+                "default",
+                &file_name,
+                &format!("x-scheme-handler/{}", scheme),
+            ],
+        ))
+    }
+
+    pub fn deregister(scheme: &str, _exe: PathBuf) -> Result<(), IpcError> {
+        let dir = applications_dir()?;
+        match fs::remove_file(dir.join(desktop_file_name(scheme))) {
+            Ok(()) => (),
+            Err(ref err) if err.kind() == ErrorKind::NotFound => return Ok(()),
+            Err(err) => return Err(io_error(err)),
+        }
+
+        // Refreshing the cache is best-effort, the tool is not always present.
+        let _ = Command::new("update-desktop-database").arg(dir).status();
+        Ok(())
+    }
+
+    fn desktop_file_name(scheme: &str) -> String {
+        format!("{}-handler.desktop", scheme)
+    }
+
+    fn applications_dir() -> Result<PathBuf, IpcError> {
+        let data_home = match env::var_os("XDG_DATA_HOME") {
+            Some(dir) => PathBuf::from(dir),
+            None => {
+                let home = env::var_os("HOME").ok_or_else(|| {
+                    IpcError::Unexpected("HOME is not set".to_string())
+                })?;
+                PathBuf::from(home).join(".local").join("share")
+            }
+        };
+        Ok(data_home.join("applications"))
+    }
+}
+
+#[cfg(target_os = "windows")]
+mod imp {
+    use super::{PathBuf, run};
+    use super::super::errors::IpcError;
+    use std::process::Command;
+
+    pub fn register(scheme: &str, name: &str, exe: PathBuf) -> Result<(), IpcError> {
+        let key = class_key(scheme);
+        let command = format!("\"{}\" \"%1\"", exe.display());
+
+        run(Command::new("reg").args(
+            &["add", &key, "/ve", "/d", &format!("URL:{}", name), "/f"],
+        ))?;
+        run(Command::new("reg").args(
+            &["add", &key, "/v", "URL Protocol", "/d", "", "/f"],
+        ))?;
+        run(Command::new("reg").args(
+            &[
+                "add",
+                &format!("{}\\shell\\open\\command", key),
+                "/ve",
+                "/d",
+                &command,
+                "/f",
+            ],
+        ))
+    }
+
+    pub fn deregister(scheme: &str, _exe: PathBuf) -> Result<(), IpcError> {
+        run(Command::new("reg").args(&["delete", &class_key(scheme), "/f"]))
+    }
+
+    // Per-user registration, doesn't require elevated privileges.
+    fn class_key(scheme: &str) -> String {
+        format!("HKCU\\Software\\Classes\\{}", scheme)
+    }
+}
+
+#[cfg(target_os = "macos")]
+mod imp {
+    use super::{PathBuf, run};
+    use super::super::errors::IpcError;
+    use std::process::Command;
+
+    const LSREGISTER: &'static str = "/System/Library/Frameworks/CoreServices.framework/Frameworks/\
+                              LaunchServices.framework/Support/lsregister";
+
+    // On macOS the handled schemes are declared by `CFBundleURLTypes` in the
+    // bundle's `Info.plist`, so registration means (un)registering the
+    // enclosing app bundle with Launch Services.
+    pub fn register(_scheme: &str, _name: &str, exe: PathBuf) -> Result<(), IpcError> {
+        let bundle = app_bundle(exe)?;
+        run(Command::new(LSREGISTER).arg("-f").arg(bundle))
+    }
+
+    pub fn deregister(_scheme: &str, exe: PathBuf) -> Result<(), IpcError> {
+        let bundle = app_bundle(exe)?;
+        run(Command::new(LSREGISTER).arg("-u").arg(bundle))
+    }
+
+    fn app_bundle(exe: PathBuf) -> Result<PathBuf, IpcError> {
+        let mut path = exe.as_path();
+        while let Some(parent) = path.parent() {
+            if parent.extension().map_or(false, |ext| ext == "app") {
+                return Ok(parent.to_path_buf());
+            }
+            path = parent;
+        }
+
+        Err(IpcError::Unexpected(
+            format!("{} is not inside an app bundle", exe.display()),
+        ))
+    }
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "windows", target_os = "macos")))]
+mod imp {
+    use super::PathBuf;
+    use super::super::errors::IpcError;
+
+    pub fn register(_scheme: &str, _name: &str, _exe: PathBuf) -> Result<(), IpcError> {
+        Err(IpcError::Unexpected(
+            "URI scheme registration is not supported on this platform"
+                .to_string(),
+        ))
+    }
+
+    pub fn deregister(_scheme: &str, _exe: PathBuf) -> Result<(), IpcError> {
+        Err(IpcError::Unexpected(
+            "URI scheme registration is not supported on this platform"
+                .to_string(),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scheme_validation() {
+        assert!(validate_scheme(SAFE_AUTH_SCHEME).is_ok());
+        assert!(validate_scheme("safe-app.net+x1").is_ok());
+        assert!(validate_scheme("").is_err());
+        assert!(validate_scheme("1safe").is_err());
+        assert!(validate_scheme("safe auth").is_err());
+        assert!(validate_scheme("safe:auth").is_err());
+    }
+}
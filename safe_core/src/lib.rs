@@ -44,11 +44,11 @@
 
 extern crate base64;
 extern crate chrono;
+extern crate config_file_handler;
 extern crate ffi_utils;
 #[cfg(feature = "use-mock-routing")]
 extern crate fs2;
 extern crate futures;
-#[cfg(feature = "use-mock-routing")]
 #[macro_use]
 extern crate lazy_static;
 #[macro_use]
@@ -78,30 +78,56 @@ pub use ffi::nfs::*;
 /// Utility functions
 #[macro_use]
 pub mod utils;
+/// Local audit trail of the mutation requests
+pub mod audit;
+/// Process-wide configuration
+pub mod config;
+/// Experimental append-only data type
+#[cfg(feature = "unstable-data-types")]
+pub mod appendable_data;
 /// Event loop handling
 pub mod event_loop;
 /// Utilities for handling `ImmutableData`
 pub mod immutable_data;
+/// Large content stored as a manifest of self-encrypted segments
+pub mod blob;
 /// Inter-Process Communication utilities
 pub mod ipc;
+/// Structured logging with per-module levels and in-memory history
+pub mod logging;
+/// Metrics of the network operations
+pub mod metrics;
+/// Copying `MutableData` to a new location
+pub mod migration;
 /// NFS utilities
 pub mod nfs;
 /// Implements the Self Encryption storage trait
 pub mod self_encryption_storage;
 /// Cryptographic utilities
 pub mod crypto;
+/// High-level data structures built on top of `MutableData`
+pub mod structures;
+/// Addressing data by `safe://` URLs
+pub mod xor_url;
 
 mod client;
 mod errors;
 mod event;
 
-pub use self::client::{Client, ClientKeys, MDataInfo, mdata_info, recovery};
+pub use self::client::{Batch, BatchResponse, Client, ClientKeys, IdleConfig, MDataDiff,
+                       MDataEntryPages, MDataInfo, MDataSnapshot, PendingRequest, Priority,
+                       RateLimit, RequestState, RetryPolicy, mdata_info, mnemonic, recovery};
 #[cfg(feature = "use-mock-routing")]
-pub use self::client::MockRouting;
+pub use self::client::{MockChurnConfig, MockCostModel, MockFaultKind, MockLatencyProfile,
+                       MockRequestKind, MockRouting, MockTrace, MockTraceEntry, mock_rng};
+#[cfg(all(feature = "use-mock-routing", any(test, feature = "testing")))]
+pub use self::client::{MockRequestHookBuilder, MockRequestLog, mock_vault};
+pub use self::config::{CoreConfig, Kdf};
 pub use self::errors::CoreError;
-pub use self::event::{CoreEvent, NetworkEvent, NetworkRx, NetworkTx};
+pub use self::event::{CoreEvent, NetworkEvent, NetworkEventStream, NetworkRx, NetworkTx};
 pub use self::event_loop::{CoreFuture, CoreMsg, CoreMsgRx, CoreMsgTx};
-pub use self::self_encryption_storage::{SelfEncryptionStorage, SelfEncryptionStorageError};
+pub use self::self_encryption_storage::{ChunkCache, SelfEncryptionStorage,
+                                        SelfEncryptionStorageError};
 pub use self::utils::FutureExt;
 pub use ffi::*;
 
@@ -0,0 +1,318 @@
+// Copyright 2017 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under (1) the MaidSafe.net Commercial License,
+// version 1.0 or later, or (2) The General Public License (GPL), version 3, depending on which
+// licence you accepted on initial access to the Software (the "Licences").
+//
+// By contributing code to the SAFE Network Software, or to this project generally, you agree to be
+// bound by the terms of the MaidSafe Contributor Agreement.  This, along with the Licenses can be
+// found in the root directory of this project at LICENSE, COPYING and CONTRIBUTOR.
+//
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.
+//
+// Please review the Licences for the specific language governing permissions and limitations
+// relating to use of the SAFE Network Software.
+
+//! Structured logging. Records can be written to stderr either as plain text
+//! or as JSON objects (one per line), filtered by per-module levels. The most
+//! recent records are additionally kept in memory, so they can be retrieved
+//! and attached to bug reports on platforms where log files are not easily
+//! accessible.
+
+use chrono::Utc;
+use errors::CoreError;
+use log::{self, Log, LogLevelFilter, LogMetadata, LogRecord};
+use std::collections::VecDeque;
+use std::io::{self, Write};
+use std::str::FromStr;
+use std::sync::Mutex;
+
+/// Default number of records kept in memory.
+pub const DEFAULT_HISTORY_SIZE: usize = 1000;
+
+lazy_static! {
+    static ref HISTORY: Mutex<VecDeque<String>> = Mutex::new(VecDeque::new());
+}
+
+/// Output format of the log records.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LogFormat {
+    /// Human readable single line.
+    Plain,
+    /// JSON object with `time`, `level`, `target`, `file`, `line` and `msg`.
+    Json,
+}
+
+/// Configuration of the logger.
+#[derive(Clone, Debug)]
+pub struct LogConfig {
+    level: LogLevelFilter,
+    modules: Vec<(String, LogLevelFilter)>,
+    format: LogFormat,
+    stderr: bool,
+    history_size: usize,
+}
+
+impl Default for LogConfig {
+    fn default() -> Self {
+        LogConfig {
+            level: LogLevelFilter::Info,
+            modules: Vec::new(),
+            format: LogFormat::Plain,
+            stderr: true,
+            history_size: DEFAULT_HISTORY_SIZE,
+        }
+    }
+}
+
+impl LogConfig {
+    /// Create configuration logging at `Info` level in plain format.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the level of the modules without a specific level.
+    pub fn level(mut self, level: LogLevelFilter) -> Self {
+        self.level = level;
+        self
+    }
+
+    /// Set the level of the given module and its submodules.
+    pub fn module<S: Into<String>>(mut self, module: S, level: LogLevelFilter) -> Self {
+        self.modules.push((module.into(), level));
+        self
+    }
+
+    /// Set the output format.
+    pub fn format(mut self, format: LogFormat) -> Self {
+        self.format = format;
+        self
+    }
+
+    /// Enable or disable writing the records to stderr.
+    pub fn stderr(mut self, enabled: bool) -> Self {
+        self.stderr = enabled;
+        self
+    }
+
+    /// Set the number of records kept in memory (0 disables the history).
+    pub fn history_size(mut self, size: usize) -> Self {
+        self.history_size = size;
+        self
+    }
+
+    /// Apply a filter of comma-separated directives, each being either a
+    /// level or `module=level` (e.g. `"warn,safe_core::client=debug"`).
+    pub fn filter(mut self, spec: &str) -> Result<Self, CoreError> {
+        for directive in spec.split(',').map(str::trim).filter(|d| !d.is_empty()) {
+            let mut parts = directive.splitn(2, '=');
+            let first = parts.next().unwrap_or("");
+
+            match parts.next() {
+                Some(level) => {
+                    let level = parse_level(level)?;
+                    self.modules.push((first.trim().to_string(), level));
+                }
+                None => self.level = parse_level(first)?,
+            }
+        }
+
+        Ok(self)
+    }
+
+    /// Level the records from `target` are logged at. The most specific
+    /// module matching `target` wins.
+    pub fn level_for(&self, target: &str) -> LogLevelFilter {
+        self.modules
+            .iter()
+            .filter(|&&(ref module, _)| {
+                target == module || target.starts_with(&format!("{}::", module))
+            })
+            .max_by_key(|&&(ref module, _)| module.len())
+            .map_or(self.level, |&(_, level)| level)
+    }
+
+    fn max_level(&self) -> LogLevelFilter {
+        self.modules.iter().fold(
+            self.level,
+            |max, &(_, level)| if level > max { level } else { max },
+        )
+    }
+}
+
+/// Install the logger. Fails if a logger has already been installed.
+pub fn init(config: LogConfig) -> Result<(), CoreError> {
+    log::set_logger(move |max_level| {
+        max_level.set(config.max_level());
+        Box::new(Logger { config })
+    }).map_err(|err| CoreError::Unexpected(format!("{}", err)))
+}
+
+/// Get the records kept in memory, oldest first.
+pub fn history() -> Vec<String> {
+    match HISTORY.lock() {
+        Ok(history) => history.iter().cloned().collect(),
+        Err(_) => Vec::new(),
+    }
+}
+
+/// Remove all the records kept in memory.
+pub fn clear_history() {
+    if let Ok(mut history) = HISTORY.lock() {
+        history.clear();
+    }
+}
+
+struct Logger {
+    config: LogConfig,
+}
+
+impl Log for Logger {
+    fn enabled(&self, metadata: &LogMetadata) -> bool {
+        metadata.level() <= self.config.level_for(metadata.target())
+    }
+
+    fn log(&self, record: &LogRecord) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+
+        let line = format_record(self.config.format, record);
+
+        if self.config.stderr {
+            let _ = writeln!(io::stderr(), "{}", line);
+        }
+
+        push_history(line, self.config.history_size);
+    }
+}
+
+fn parse_level(level: &str) -> Result<LogLevelFilter, CoreError> {
+    LogLevelFilter::from_str(level.trim()).map_err(|_| {
+        CoreError::Unexpected(format!("Invalid log level: {}", level))
+    })
+}
+
+fn format_record(format: LogFormat, record: &LogRecord) -> String {
+    let time = Utc::now().to_rfc3339();
+    let location = record.location();
+    let message = format!("{}", record.args());
+
+    match format {
+        LogFormat::Plain => {
+            format!(
+                "{} {:<5} [{}:{}] {}",
+                time,
+                record.level(),
+                location.file(),
+                location.line(),
+                message
+            )
+        }
+        LogFormat::Json => {
+            format!(
+                "{{\"time\":\"{}\",\"level\":\"{}\",\"target\":\"{}\",\"file\":\"{}\",\
+                 \"line\":{},\"msg\":\"{}\"}}",
+                time,
+                record.level(),
+                json_escape(record.target()),
+                json_escape(location.file()),
+                location.line(),
+                json_escape(&message)
+            )
+        }
+    }
+}
+
+fn json_escape(input: &str) -> String {
+    let mut output = String::with_capacity(input.len());
+
+    for c in input.chars() {
+        match c {
+            '"' => output.push_str("\\\""),
+            '\\' => output.push_str("\\\\"),
+            '\n' => output.push_str("\\n"),
+            '\r' => output.push_str("\\r"),
+            '\t' => output.push_str("\\t"),
+            c if (c as u32) < 0x20 => output.push_str(&format!("\\u{:04x}", c as u32)),
+            c => output.push(c),
+        }
+    }
+
+    output
+}
+
+fn push_history(line: String, size: usize) {
+    if size == 0 {
+        return;
+    }
+
+    if let Ok(mut history) = HISTORY.lock() {
+        while history.len() >= size {
+            let _ = history.pop_front();
+        }
+        history.push_back(line);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Parse filter directives and resolve per-module levels.
+    #[test]
+    fn module_levels() {
+        let config = unwrap!(LogConfig::new().filter(
+            "warn, safe_core::client=debug,safe_core::client::mock=off",
+        ));
+
+        assert_eq!(config.level_for("safe_app"), LogLevelFilter::Warn);
+        assert_eq!(config.level_for("safe_core"), LogLevelFilter::Warn);
+        assert_eq!(
+            config.level_for("safe_core::client"),
+            LogLevelFilter::Debug
+        );
+        assert_eq!(
+            config.level_for("safe_core::client::routing"),
+            LogLevelFilter::Debug
+        );
+        assert_eq!(
+            config.level_for("safe_core::client::mock::vault"),
+            LogLevelFilter::Off
+        );
+        assert_eq!(
+            config.level_for("safe_core::clientele"),
+            LogLevelFilter::Warn
+        );
+        assert_eq!(config.max_level(), LogLevelFilter::Debug);
+
+        assert!(LogConfig::new().filter("safe_core=loud").is_err());
+    }
+
+    // Escape strings embedded in JSON records.
+    #[test]
+    fn escaping() {
+        assert_eq!(json_escape("plain"), "plain");
+        assert_eq!(
+            json_escape("say \"hi\"\n\\ \u{1}"),
+            "say \\\"hi\\\"\\n\\\\ \\u0001"
+        );
+    }
+
+    // Keep only the most recent records.
+    #[test]
+    fn bounded_history() {
+        clear_history();
+
+        for i in 0..5 {
+            push_history(format!("record {}", i), 3);
+        }
+
+        assert_eq!(history(), vec!["record 2", "record 3", "record 4"]);
+
+        clear_history();
+        assert!(history().is_empty());
+    }
+}
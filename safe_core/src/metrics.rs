@@ -0,0 +1,379 @@
+// Copyright 2017 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under (1) the MaidSafe.net Commercial License,
+// version 1.0 or later, or (2) The General Public License (GPL), version 3, depending on which
+// licence you accepted on initial access to the Software (the "Licences").
+//
+// By contributing code to the SAFE Network Software, or to this project generally, you agree to be
+// bound by the terms of the MaidSafe Contributor Agreement.  This, along with the Licenses can be
+// found in the root directory of this project at LICENSE, COPYING and CONTRIBUTOR.
+//
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.
+//
+// Please review the Licences for the specific language governing permissions and limitations
+// relating to use of the SAFE Network Software.
+
+//! Metrics of the network operations performed by the client. The client
+//! reports the start and the completion of every request to the installed
+//! `Metrics` sink, which by default ignores them. Regardless of the sink, the
+//! client keeps the totals of its session, available as `SessionStats`.
+
+use errors::CoreError;
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::rc::Rc;
+use std::time::Duration;
+
+/// Type of the network operation.
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize)]
+pub enum Operation {
+    /// Get `ImmutableData`
+    GetIData,
+    /// Put `ImmutableData`
+    PutIData,
+    /// Put `MutableData`
+    PutMData,
+    /// Get entire `MutableData`
+    GetMData,
+    /// Get `MutableData` shell
+    GetMDataShell,
+    /// Get `MutableData` version
+    GetMDataVersion,
+    /// List `MutableData` entries
+    ListMDataEntries,
+    /// List `MutableData` keys
+    ListMDataKeys,
+    /// List `MutableData` values
+    ListMDataValues,
+    /// Get single `MutableData` value
+    GetMDataValue,
+    /// Mutate `MutableData` entries
+    MutateMDataEntries,
+    /// List `MutableData` permissions
+    ListMDataPermissions,
+    /// List `MutableData` permissions of a single user
+    ListMDataUserPermissions,
+    /// Set `MutableData` permissions of a user
+    SetMDataUserPermissions,
+    /// Delete `MutableData` permissions of a user
+    DelMDataUserPermissions,
+    /// Change `MutableData` owner
+    ChangeMDataOwner,
+    /// Get account info
+    GetAccountInfo,
+    /// List authorised keys
+    ListAuthKeysAndVersion,
+    /// Insert authorised key
+    InsAuthKey,
+    /// Delete authorised key
+    DelAuthKey,
+}
+
+impl Operation {
+    /// All the operations, in the order of their numeric codes.
+    pub fn all() -> &'static [Operation] {
+        use self::Operation::*;
+
+        static ALL: [Operation; 20] = [
+            GetIData,
+            PutIData,
+            PutMData,
+            GetMData,
+            GetMDataShell,
+            GetMDataVersion,
+            ListMDataEntries,
+            ListMDataKeys,
+            ListMDataValues,
+            GetMDataValue,
+            MutateMDataEntries,
+            ListMDataPermissions,
+            ListMDataUserPermissions,
+            SetMDataUserPermissions,
+            DelMDataUserPermissions,
+            ChangeMDataOwner,
+            GetAccountInfo,
+            ListAuthKeysAndVersion,
+            InsAuthKey,
+            DelAuthKey,
+        ];
+
+        &ALL
+    }
+
+    /// Stable numeric code of the operation, as used across FFI.
+    pub fn code(&self) -> u32 {
+        Self::all().iter().position(|op| op == self).unwrap_or(0) as u32
+    }
+}
+
+/// Sink for the client operation metrics.
+pub trait Metrics {
+    /// Called when the request is sent.
+    fn request_started(&self, _op: Operation) {}
+
+    /// Called when the response is received or the request fails. `bytes` is
+    /// the size of the data sent (for mutations) or received (for reads).
+    fn request_completed(
+        &self,
+        _op: Operation,
+        _bytes: u64,
+        _latency: Duration,
+        _result: Result<(), &CoreError>,
+    ) {
+    }
+}
+
+/// Metrics sink ignoring everything. Used by default.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct NoopMetrics;
+
+impl Metrics for NoopMetrics {}
+
+/// Aggregated statistics of a single operation type.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct OperationStats {
+    /// Number of requests sent.
+    pub started: u64,
+    /// Number of requests which succeeded.
+    pub succeeded: u64,
+    /// Number of requests which failed.
+    pub failed: u64,
+    /// Total number of bytes sent or received.
+    pub bytes: u64,
+    /// Total latency of the completed requests.
+    pub latency: Duration,
+}
+
+/// Metrics sink aggregating the statistics per operation type. Clones share
+/// the same statistics, so one clone can be installed into the client while
+/// another is used to read the statistics.
+#[derive(Clone, Debug, Default)]
+pub struct AggregateMetrics {
+    stats: Rc<RefCell<BTreeMap<Operation, OperationStats>>>,
+}
+
+impl AggregateMetrics {
+    /// Create new aggregating sink with empty statistics.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Get the statistics of all operations performed so far.
+    pub fn stats(&self) -> BTreeMap<Operation, OperationStats> {
+        self.stats.borrow().clone()
+    }
+
+    /// Reset all the statistics.
+    pub fn reset(&self) {
+        self.stats.borrow_mut().clear();
+    }
+}
+
+impl Metrics for AggregateMetrics {
+    fn request_started(&self, op: Operation) {
+        self.stats.borrow_mut().entry(op).or_insert_with(Default::default).started += 1;
+    }
+
+    fn request_completed(
+        &self,
+        op: Operation,
+        bytes: u64,
+        latency: Duration,
+        result: Result<(), &CoreError>,
+    ) {
+        let mut stats = self.stats.borrow_mut();
+        let stats = stats.entry(op).or_insert_with(Default::default);
+
+        if result.is_ok() {
+            stats.succeeded += 1;
+        } else {
+            stats.failed += 1;
+        }
+        stats.bytes += bytes;
+        stats.latency += latency;
+    }
+}
+
+/// Totals of the operations performed by a client during its session.
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+pub struct SessionStats {
+    /// Number of read requests (gets and listings) sent.
+    pub gets: u64,
+    /// Number of put requests sent.
+    pub puts: u64,
+    /// Number of other mutation requests sent.
+    pub mutations: u64,
+    /// Total number of bytes sent or received.
+    pub bytes: u64,
+    /// Number of completed requests, whether they succeeded or failed.
+    pub completed: u64,
+    /// Number of failed requests per kind of the error.
+    pub errors: BTreeMap<String, u64>,
+    /// Total latency of the completed requests.
+    pub latency: Duration,
+}
+
+impl SessionStats {
+    /// Average latency of the completed requests.
+    pub fn average_latency(&self) -> Duration {
+        if self.completed == 0 {
+            Duration::from_secs(0)
+        } else {
+            self.latency / self.completed as u32
+        }
+    }
+}
+
+/// Metrics sink keeping the `SessionStats`. Clones share the same statistics.
+#[derive(Clone, Debug, Default)]
+pub struct SessionMetrics {
+    stats: Rc<RefCell<SessionStats>>,
+}
+
+impl SessionMetrics {
+    /// Create new sink with empty statistics.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Get the statistics of the session so far.
+    pub fn stats(&self) -> SessionStats {
+        self.stats.borrow().clone()
+    }
+}
+
+impl Metrics for SessionMetrics {
+    fn request_started(&self, op: Operation) {
+        use self::Operation::*;
+
+        let mut stats = self.stats.borrow_mut();
+        match op {
+            PutIData | PutMData => stats.puts += 1,
+            MutateMDataEntries |
+            SetMDataUserPermissions |
+            DelMDataUserPermissions |
+            ChangeMDataOwner |
+            InsAuthKey |
+            DelAuthKey => stats.mutations += 1,
+            _ => stats.gets += 1,
+        }
+    }
+
+    fn request_completed(
+        &self,
+        _op: Operation,
+        bytes: u64,
+        latency: Duration,
+        result: Result<(), &CoreError>,
+    ) {
+        let mut stats = self.stats.borrow_mut();
+
+        if let Err(error) = result {
+            *stats.errors.entry(error_kind(error)).or_insert(0) += 1;
+        }
+        stats.bytes += bytes;
+        stats.completed += 1;
+        stats.latency += latency;
+    }
+}
+
+// Name of the error variant (of the routing error for routing client errors),
+// without its payload.
+fn error_kind(error: &CoreError) -> String {
+    let description = match *error {
+        CoreError::RoutingClientError(ref error) => format!("{:?}", error),
+        ref error => format!("{:?}", error),
+    };
+
+    description
+        .split(|c: char| !c.is_alphanumeric())
+        .next()
+        .unwrap_or("")
+        .to_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::Future;
+    use routing::ImmutableData;
+    use utils::test_utils::random_client;
+
+    // Operations performed by the client are aggregated per type.
+    #[test]
+    fn aggregate() {
+        let stats = random_client(|client| {
+            let metrics = AggregateMetrics::new();
+            client.set_metrics(metrics.clone());
+
+            let data = ImmutableData::new(vec![1; 100]);
+            let name = *data.name();
+            let client2 = client.clone();
+
+            client
+                .put_idata(data)
+                .then(move |res| {
+                    unwrap!(res);
+                    client2.get_idata(name)
+                })
+                .map(move |_| metrics.stats())
+        });
+
+        let put = unwrap!(stats.get(&Operation::PutIData));
+        assert_eq!(put.started, 1);
+        assert_eq!(put.succeeded, 1);
+        assert_eq!(put.failed, 0);
+        assert_eq!(put.bytes, 100);
+
+        let get = unwrap!(stats.get(&Operation::GetIData));
+        assert_eq!(get.succeeded, 1);
+        assert_eq!(get.bytes, 100);
+
+        assert!(stats.get(&Operation::PutMData).is_none());
+
+        assert_eq!(Operation::GetIData.code(), 0);
+        assert_eq!(Operation::DelAuthKey.code(), 19);
+    }
+
+    // Session totals are kept by the client without installing any sink.
+    #[test]
+    fn session_stats() {
+        use rand;
+        use routing::ClientError;
+
+        let stats = random_client(|client| {
+            let data = ImmutableData::new(vec![1; 100]);
+            let name = *data.name();
+            let client2 = client.clone();
+            let client3 = client.clone();
+            let client4 = client.clone();
+
+            client
+                .put_idata(data)
+                .then(move |res| {
+                    unwrap!(res);
+                    client2.get_idata(name)
+                })
+                .then(move |res| {
+                    let _ = unwrap!(res);
+                    client3.get_idata(rand::random())
+                })
+                .then(move |res| {
+                    match res {
+                        Err(CoreError::RoutingClientError(ClientError::NoSuchData)) => (),
+                        x => panic!("Unexpected {:?}", x),
+                    }
+                    Ok::<_, CoreError>(client4.stats())
+                })
+        });
+
+        assert_eq!(stats.puts, 1);
+        assert_eq!(stats.gets, 2);
+        assert_eq!(stats.mutations, 0);
+        assert_eq!(stats.bytes, 200);
+        assert_eq!(stats.completed, 3);
+        assert_eq!(stats.errors, btree_map!["NoSuchData".to_owned() => 1]);
+    }
+}
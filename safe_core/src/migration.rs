@@ -0,0 +1,283 @@
+// Copyright 2017 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under (1) the MaidSafe.net Commercial License,
+// version 1.0 or later, or (2) The General Public License (GPL), version 3, depending on which
+// licence you accepted on initial access to the Software (the "Licences").
+//
+// By contributing code to the SAFE Network Software, or to this project generally, you agree to be
+// bound by the terms of the MaidSafe Contributor Agreement.  This, along with the Licenses can be
+// found in the root directory of this project at LICENSE, COPYING and CONTRIBUTOR.
+//
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.
+//
+// Please review the Licences for the specific language governing permissions and limitations
+// relating to use of the SAFE Network Software.
+
+//! Copying `MutableData` to a new location, e.g. for schema upgrades.
+
+use client::{Client, MDataInfo, mdata_info};
+use errors::CoreError;
+use event_loop::CoreFuture;
+use futures::Future;
+use routing::{EntryAction, MutableData, PermissionSet, User, Value};
+use rust_sodium::crypto::sign;
+use std::collections::BTreeMap;
+use std::rc::Rc;
+use utils::FutureExt;
+
+/// Migration of a `MutableData` to a new name, type tag, encryption or owner.
+/// The source permissions are carried over, optionally mapped by a function,
+/// and the entries are re-encrypted for the target. The copy is verified
+/// against the source before the ownership is transferred or the source is
+/// cleared.
+pub struct Migration {
+    source: MDataInfo,
+    target: MDataInfo,
+    owner: Option<sign::PublicKey>,
+    map_permissions: Rc<Fn(User, PermissionSet) -> Option<(User, PermissionSet)>>,
+    clear_source: bool,
+}
+
+impl Migration {
+    /// Create migration from `source` to `target`. The target must not exist.
+    pub fn new(source: MDataInfo, target: MDataInfo) -> Self {
+        Migration {
+            source,
+            target,
+            owner: None,
+            map_permissions: Rc::new(|user, permissions| Some((user, permissions))),
+            clear_source: false,
+        }
+    }
+
+    /// Transfer the ownership of the target to the given key once the copy is
+    /// verified.
+    pub fn owner(mut self, owner: sign::PublicKey) -> Self {
+        self.owner = Some(owner);
+        self
+    }
+
+    /// Map every permission set of the source to the one to be set on the
+    /// target. Returning `None` drops the permissions of that user.
+    pub fn map_permissions<F>(mut self, f: F) -> Self
+    where
+        F: Fn(User, PermissionSet) -> Option<(User, PermissionSet)> + 'static,
+    {
+        self.map_permissions = Rc::new(f);
+        self
+    }
+
+    /// Delete all source entries once the copy is verified. The network
+    /// doesn't support deleting `MutableData` itself, so its shell remains.
+    pub fn clear_source(mut self, clear: bool) -> Self {
+        self.clear_source = clear;
+        self
+    }
+
+    /// Run the migration.
+    pub fn run<T: 'static>(self, client: &Client<T>) -> Box<CoreFuture<()>> {
+        let Migration {
+            source,
+            target,
+            owner,
+            map_permissions,
+            clear_source,
+        } = self;
+
+        let owners = btree_set![fry!(client.owner_key())];
+        let client2 = client.clone();
+        let client3 = client.clone();
+        let client4 = client.clone();
+        let client5 = client.clone();
+        let target2 = target.clone();
+        let target3 = target.clone();
+
+        client
+            .get_mdata_shell(source.name, source.type_tag)
+            .join(client.list_mdata_entries(source.name, source.type_tag))
+            .and_then(move |(shell, entries)| {
+                let entries = fry!(mdata_info::decrypt_entries(&source, &live_entries(&entries)));
+                let live = contents(&entries);
+
+                let permissions = shell
+                    .permissions()
+                    .iter()
+                    .filter_map(|(user, set)| map_permissions(*user, *set))
+                    .collect();
+                let target_entries: BTreeMap<_, _> = live.iter()
+                    .map(|(key, content)| {
+                        (
+                            key.clone(),
+                            Value {
+                                content: content.clone(),
+                                entry_version: 0,
+                            },
+                        )
+                    })
+                    .collect();
+                let target_entries = fry!(mdata_info::encrypt_entries(&target, &target_entries));
+                let data = fry!(MutableData::new(
+                    target.name,
+                    target.type_tag,
+                    permissions,
+                    target_entries,
+                    owners,
+                ));
+
+                client2
+                    .put_mdata(data)
+                    .map(move |_| (source, entries, live))
+                    .into_box()
+            })
+            .and_then(move |(source, entries, live)| {
+                verify(&client3, &target2, live).map(move |_| (source, entries))
+            })
+            .and_then(move |(source, entries)| match owner {
+                Some(owner) => {
+                    client4
+                        .change_mdata_owner(target3.name, target3.type_tag, owner, 1)
+                        .map(move |_| (source, entries))
+                        .into_box()
+                }
+                None => ok!((source, entries)),
+            })
+            .and_then(move |(source, entries)| if clear_source {
+                clear(&client5, &source, &entries)
+            } else {
+                ok!(())
+            })
+            .into_box()
+    }
+}
+
+// Drop the deleted entries. Their content is empty, so they can't be decrypted.
+fn live_entries(entries: &BTreeMap<Vec<u8>, Value>) -> BTreeMap<Vec<u8>, Value> {
+    entries
+        .iter()
+        .filter(|&(_, value)| !value.content.is_empty())
+        .map(|(key, value)| (key.clone(), value.clone()))
+        .collect()
+}
+
+fn contents(entries: &BTreeMap<Vec<u8>, Value>) -> BTreeMap<Vec<u8>, Vec<u8>> {
+    entries
+        .iter()
+        .map(|(key, value)| (key.clone(), value.content.clone()))
+        .collect()
+}
+
+// Check the target holds exactly the expected entries.
+fn verify<T: 'static>(
+    client: &Client<T>,
+    target: &MDataInfo,
+    expected: BTreeMap<Vec<u8>, Vec<u8>>,
+) -> Box<CoreFuture<()>> {
+    let target = target.clone();
+
+    client
+        .list_mdata_entries(target.name, target.type_tag)
+        .and_then(move |entries| {
+            let entries = mdata_info::decrypt_entries(&target, &live_entries(&entries))?;
+            if contents(&entries) == expected {
+                Ok(())
+            } else {
+                Err(CoreError::Unexpected(
+                    "Migrated entries don't match the source".to_string(),
+                ))
+            }
+        })
+        .into_box()
+}
+
+fn clear<T: 'static>(
+    client: &Client<T>,
+    source: &MDataInfo,
+    entries: &BTreeMap<Vec<u8>, Value>,
+) -> Box<CoreFuture<()>> {
+    let actions: BTreeMap<_, _> = entries
+        .iter()
+        .map(|(key, value)| {
+            (key.clone(), EntryAction::Del(value.entry_version + 1))
+        })
+        .collect();
+
+    if actions.is_empty() {
+        return ok!(());
+    }
+
+    let actions = fry!(mdata_info::encrypt_entry_actions(source, &actions));
+    client.mutate_mdata_entries(source.name, source.type_tag, actions)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use DIR_TAG;
+    use routing::Action;
+    use utils::test_utils::random_client;
+
+    // Migrate private data to public one with a different type tag, dropping
+    // some permissions and clearing the source.
+    #[test]
+    fn migrate_and_clear() {
+        random_client(|client| {
+            let client2 = client.clone();
+            let client3 = client.clone();
+            let client4 = client.clone();
+
+            let source = unwrap!(MDataInfo::random_private(DIR_TAG));
+            let target = unwrap!(MDataInfo::random_public(DIR_TAG + 1));
+            let source2 = source.clone();
+            let target2 = target.clone();
+
+            let (user_pk, _) = sign::gen_keypair();
+            let perms = btree_map![
+                User::Anyone => PermissionSet::new().allow(Action::Insert),
+                User::Key(user_pk) => PermissionSet::new().allow(Action::Update)
+            ];
+            let entries = btree_map![
+                b"a".to_vec() => Value { content: b"1".to_vec(), entry_version: 0 },
+                b"b".to_vec() => Value { content: b"2".to_vec(), entry_version: 0 }
+            ];
+            let entries = unwrap!(mdata_info::encrypt_entries(&source, &entries));
+            let owners = btree_set![unwrap!(client.owner_key())];
+            let data = unwrap!(MutableData::new(
+                source.name,
+                source.type_tag,
+                perms,
+                entries,
+                owners,
+            ));
+
+            client
+                .put_mdata(data)
+                .and_then(move |_| {
+                    Migration::new(source, target)
+                        .map_permissions(|user, permissions| match user {
+                            User::Anyone => None,
+                            user => Some((user, permissions)),
+                        })
+                        .clear_source(true)
+                        .run(&client2)
+                })
+                .and_then(move |_| {
+                    client3
+                        .get_mdata_shell(target2.name, target2.type_tag)
+                        .join(client3.list_mdata_entries(target2.name, target2.type_tag))
+                })
+                .and_then(move |(shell, entries)| {
+                    assert_eq!(shell.permissions().len(), 1);
+                    assert!(shell.permissions().contains_key(&User::Key(user_pk)));
+                    assert_eq!(entries.len(), 2);
+                    assert_eq!(entries[&b"a".to_vec()].content, b"1".to_vec());
+
+                    client4.list_mdata_entries(source2.name, source2.type_tag)
+                })
+                .map(|entries| {
+                    assert!(entries.values().all(|value| value.content.is_empty()));
+                })
+        });
+    }
+}
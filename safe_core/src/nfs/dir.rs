@@ -15,14 +15,19 @@
 // Please review the Licences for the specific language governing permissions and limitations
 // relating to use of the SAFE Network Software.
 
-use client::{Client, MDataInfo};
+use client::{Client, MDataInfo, mdata_info};
 use errors::CoreError;
-use futures::Future;
+use futures::{Future, future};
+use maidsafe_utilities::serialisation::{deserialise, serialise};
 use nfs::{NfsError, NfsFuture};
-use routing::{ClientError, MutableData, PermissionSet, User, Value};
+use routing::{ClientError, EntryAction, EntryActions, MutableData, PermissionSet, User, Value};
 use std::collections::BTreeMap;
 use utils::FutureExt;
 
+/// Prefix of the plaintext keys of the entries linking sub-directories. The
+/// value of such entry is the serialised `MDataInfo` of the sub-directory.
+pub const DIRS_KEY_PREFIX: &'static [u8] = b"\0dirs/";
+
 /// Create a new directory based on the provided `MDataInfo`
 pub fn create_dir<T: 'static>(
     client: &Client<T>,
@@ -48,3 +53,314 @@ pub fn create_dir<T: 'static>(
         .map_err(NfsError::from)
         .into_box()
 }
+
+/// Link `child` into `parent` as a sub-directory called `name`.
+pub fn insert_dir<T: 'static>(
+    client: &Client<T>,
+    parent: &MDataInfo,
+    name: &str,
+    child: &MDataInfo,
+) -> Box<NfsFuture<()>> {
+    let key = fry!(parent.enc_entry_key(&dir_entry_key(name)));
+    let content = fry!(parent.enc_entry_value(&fry!(serialise(child))));
+    let actions = EntryActions::new().ins(key, content, 0);
+
+    client
+        .mutate_mdata_entries(parent.name, parent.type_tag, actions.into())
+        .map_err(NfsError::from)
+        .into_box()
+}
+
+/// Get the sub-directory called `name` of `parent`.
+pub fn fetch_dir<T: 'static>(
+    client: &Client<T>,
+    parent: &MDataInfo,
+    name: &str,
+) -> Box<NfsFuture<MDataInfo>> {
+    fetch_link(client, parent, name)
+        .map(|(child, _)| child)
+        .into_box()
+}
+
+/// List the sub-directories of the directory by their names.
+pub fn list_dirs<T: 'static>(
+    client: &Client<T>,
+    dir: &MDataInfo,
+) -> Box<NfsFuture<BTreeMap<String, MDataInfo>>> {
+    list_entries(client, dir)
+        .and_then(|entries| -> Result<_, NfsError> {
+            let mut dirs = BTreeMap::new();
+            for (key, value) in entries {
+                if !key.starts_with(DIRS_KEY_PREFIX) {
+                    continue;
+                }
+                let name = String::from_utf8(key[DIRS_KEY_PREFIX.len()..].to_vec())
+                    .map_err(|err| {
+                        NfsError::Unexpected(format!("Invalid directory name: {:?}", err))
+                    })?;
+                let _ = dirs.insert(name, deserialise(&value.content)?);
+            }
+            Ok(dirs)
+        })
+        .into_box()
+}
+
+/// Copy the directory together with all its sub-directories. Every copied
+/// directory gets a new random location (and encryption key, if it's
+/// private) and the permissions of its original, and all its entries are
+/// written in a single request. Files share the content with the originals,
+/// as the content is immutable. Returns the copy of `src`, which is not
+/// linked anywhere yet (see `insert_dir`).
+///
+/// Sub-directories are copied before their parent, so if the copy fails
+/// half-way, no partial copy is reachable from the returned directory.
+pub fn copy_recursive<T: 'static>(
+    client: &Client<T>,
+    src: &MDataInfo,
+) -> Box<NfsFuture<MDataInfo>> {
+    let client2 = client.clone();
+    let client3 = client.clone();
+    let dst = fry!(new_dir_info(src));
+
+    client
+        .get_mdata_shell(src.name, src.type_tag)
+        .map_err(NfsError::from)
+        .join(list_entries(client, src))
+        .and_then(move |(shell, entries)| {
+            let mut contents = BTreeMap::new();
+            let mut children = Vec::new();
+
+            for (key, value) in entries {
+                if key.starts_with(DIRS_KEY_PREFIX) {
+                    let child: MDataInfo = fry!(deserialise(&value.content));
+                    children.push(copy_recursive(&client2, &child).map(move |copy| (key, copy)));
+                } else {
+                    let _ = contents.insert(key, value);
+                }
+            }
+
+            future::join_all(children)
+                .map(move |children| (shell, contents, children))
+                .into_box()
+        })
+        .and_then(move |(shell, mut contents, children)| {
+            for (key, child) in children {
+                let content = fry!(serialise(&child));
+                let _ = contents.insert(
+                    key,
+                    Value {
+                        content,
+                        entry_version: 0,
+                    },
+                );
+            }
+
+            let contents = fry!(mdata_info::encrypt_entries(&dst, &contents));
+            create_dir(&client3, &dst, contents, shell.permissions().clone())
+                .map(move |()| dst)
+                .into_box()
+        })
+        .into_box()
+}
+
+/// Move the sub-directory `name` of `src_parent` into `dst_parent` under
+/// `new_name`. Only the links in the parents are rewritten, the contents of
+/// the directory stay where they are.
+///
+/// The new link is inserted before the old one is removed, so if the move
+/// fails half-way, the directory is reachable from both parents rather than
+/// from neither. Moving a directory into itself or one of its descendants is
+/// rejected, as it would make the tree cyclic.
+pub fn move_to<T: 'static>(
+    client: &Client<T>,
+    src_parent: &MDataInfo,
+    name: &str,
+    dst_parent: &MDataInfo,
+    new_name: &str,
+) -> Box<NfsFuture<()>> {
+    let client2 = client.clone();
+    let client3 = client.clone();
+    let client4 = client.clone();
+    let src_parent = src_parent.clone();
+    let dst_parent = dst_parent.clone();
+    let new_name = new_name.to_string();
+    let key = fry!(src_parent.enc_entry_key(&dir_entry_key(name)));
+
+    fetch_link(client, &src_parent, name)
+        .and_then(move |(child, version)| {
+            contains_dir(&client4, &child, &dst_parent).map(move |cyclic| {
+                (child, version, dst_parent, cyclic)
+            })
+        })
+        .and_then(move |(child, version, dst_parent, cyclic)| {
+            if cyclic {
+                return err!(NfsError::Unexpected(
+                    "Can't move a directory into itself or its descendant".to_string(),
+                ));
+            }
+            insert_dir(&client2, &dst_parent, &new_name, &child)
+                .map(move |()| version)
+                .into_box()
+        })
+        .and_then(move |version| {
+            let actions = EntryActions::new().del(key, version + 1);
+            client3
+                .mutate_mdata_entries(src_parent.name, src_parent.type_tag, actions.into())
+                .map_err(NfsError::from)
+        })
+        .into_box()
+}
+
+// Whether `target` is `dir` itself or any directory below it.
+fn contains_dir<T: 'static>(
+    client: &Client<T>,
+    dir: &MDataInfo,
+    target: &MDataInfo,
+) -> Box<NfsFuture<bool>> {
+    if dir.name == target.name && dir.type_tag == target.type_tag {
+        return ok!(true);
+    }
+
+    let client = client.clone();
+    let target = target.clone();
+
+    list_dirs(&client, dir)
+        .and_then(move |children| {
+            let checks: Vec<_> = children
+                .values()
+                .map(|child| contains_dir(&client, child, &target))
+                .collect();
+            future::join_all(checks).map(|found| found.into_iter().any(|found| found))
+        })
+        .into_box()
+}
+
+/// Delete the sub-directory `name` of `parent` together with everything in
+/// it. All entries of every directory are deleted in a single request per
+/// directory.
+///
+/// Sub-directories are emptied before their parent and the link in `parent`
+/// is removed last, so if the deletion fails half-way, the remaining part of
+/// the tree stays reachable and calling this function again finishes the job.
+pub fn delete_recursive<T: 'static>(
+    client: &Client<T>,
+    parent: &MDataInfo,
+    name: &str,
+) -> Box<NfsFuture<()>> {
+    let client2 = client.clone();
+    let client3 = client.clone();
+    let parent = parent.clone();
+    let key = fry!(parent.enc_entry_key(&dir_entry_key(name)));
+
+    fetch_link(client, &parent, name)
+        .and_then(move |(child, version)| clear_dir(&client2, &child).map(move |()| version))
+        .and_then(move |version| {
+            let actions = EntryActions::new().del(key, version + 1);
+            client3
+                .mutate_mdata_entries(parent.name, parent.type_tag, actions.into())
+                .map_err(NfsError::from)
+        })
+        .into_box()
+}
+
+// Recursively delete all entries of the directory.
+fn clear_dir<T: 'static>(client: &Client<T>, dir: &MDataInfo) -> Box<NfsFuture<()>> {
+    let client2 = client.clone();
+    let client3 = client.clone();
+    let dir = dir.clone();
+    let dir2 = dir.clone();
+
+    client
+        .list_mdata_entries(dir.name, dir.type_tag)
+        .map_err(NfsError::from)
+        .and_then(move |entries| {
+            let mut actions = BTreeMap::new();
+            let mut children = Vec::new();
+
+            for (key, value) in entries {
+                if value.content.is_empty() {
+                    continue;
+                }
+                if fry!(dir.decrypt(&key)).starts_with(DIRS_KEY_PREFIX) {
+                    let child: MDataInfo = fry!(deserialise(&fry!(dir.decrypt(&value.content))));
+                    children.push(clear_dir(&client2, &child));
+                }
+                let _ = actions.insert(key, EntryAction::Del(value.entry_version + 1));
+            }
+
+            future::join_all(children)
+                .map(move |_| actions)
+                .into_box()
+        })
+        .and_then(move |actions| {
+            if actions.is_empty() {
+                return ok!(());
+            }
+            client3
+                .mutate_mdata_entries(dir2.name, dir2.type_tag, actions)
+                .map_err(NfsError::from)
+                .into_box()
+        })
+        .into_box()
+}
+
+// Get the sub-directory called `name` of `parent` and the version of its link.
+fn fetch_link<T: 'static>(
+    client: &Client<T>,
+    parent: &MDataInfo,
+    name: &str,
+) -> Box<NfsFuture<(MDataInfo, u64)>> {
+    let parent = parent.clone();
+    let key = fry!(parent.enc_entry_key(&dir_entry_key(name)));
+
+    client
+        .get_mdata_value(parent.name, parent.type_tag, key)
+        .then(move |res| -> Result<_, NfsError> {
+            match res {
+                Ok(ref value) if value.content.is_empty() => Err(NfsError::DirNotFound),
+                Ok(value) => {
+                    let child = deserialise(&parent.decrypt(&value.content)?)?;
+                    Ok((child, value.entry_version))
+                }
+                Err(CoreError::RoutingClientError(ClientError::NoSuchEntry)) => {
+                    Err(NfsError::DirNotFound)
+                }
+                Err(err) => Err(NfsError::from(err)),
+            }
+        })
+        .into_box()
+}
+
+// List the entries of the directory which are not deleted, decrypted.
+fn list_entries<T: 'static>(
+    client: &Client<T>,
+    dir: &MDataInfo,
+) -> Box<NfsFuture<BTreeMap<Vec<u8>, Value>>> {
+    let dir = dir.clone();
+
+    client
+        .list_mdata_entries(dir.name, dir.type_tag)
+        .and_then(move |entries| {
+            let entries = entries
+                .into_iter()
+                .filter(|&(_, ref value)| !value.content.is_empty())
+                .collect();
+            mdata_info::decrypt_entries(&dir, &entries)
+        })
+        .map_err(NfsError::from)
+        .into_box()
+}
+
+fn new_dir_info(dir: &MDataInfo) -> Result<MDataInfo, CoreError> {
+    if dir.enc_info.is_some() {
+        MDataInfo::random_private(dir.type_tag)
+    } else {
+        MDataInfo::random_public(dir.type_tag)
+    }
+}
+
+fn dir_entry_key(name: &str) -> Vec<u8> {
+    let mut key = DIRS_KEY_PREFIX.to_vec();
+    key.extend_from_slice(name.as_bytes());
+    key
+}
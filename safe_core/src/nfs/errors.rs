@@ -30,6 +30,8 @@ pub enum NfsError {
     FileExists,
     /// File not found
     FileNotFound,
+    /// Sub-directory not found
+    DirNotFound,
     /// Invalid byte range specified
     InvalidRange,
     /// Unexpected error
@@ -72,6 +74,7 @@ impl fmt::Display for NfsError {
                 write!(f, "File already exists with the same name in a directory")
             }
             NfsError::FileNotFound => write!(f, "File not found"),
+            NfsError::DirNotFound => write!(f, "Directory not found"),
 
             NfsError::InvalidRange => write!(f, "Invalid byte range specified"),
             NfsError::Unexpected(ref error) => write!(f, "Unexpected error - {:?}", error),
@@ -99,6 +102,7 @@ impl fmt::Debug for NfsError {
             NfsError::CoreError(ref error) => write!(f, "NfsError::CoreError -> {:?}", error),
             NfsError::FileExists => write!(f, "NfsError::FileExists"),
             NfsError::FileNotFound => write!(f, "NfsError::FileNotFound"),
+            NfsError::DirNotFound => write!(f, "NfsError::DirNotFound"),
             NfsError::InvalidRange => write!(f, "NfsError::InvalidRange"),
             NfsError::Unexpected(ref error) => write!(f, "NfsError::Unexpected -> {:?}", error),
             NfsError::EncodeDecodeError(ref error) => {
@@ -18,13 +18,27 @@
 use client::{Client, MDataInfo};
 use crypto::shared_secretbox;
 use errors::CoreError;
-use futures::{Future, IntoFuture};
+use futures::{Future, IntoFuture, future};
+use futures::future::Loop;
 use maidsafe_utilities::serialisation::{deserialise, serialise};
-use nfs::{File, Mode, NfsError, NfsFuture, Reader, Writer};
-use routing::{ClientError, EntryActions};
+use nfs::{DIRS_KEY_PREFIX, File, Mode, NfsError, NfsFuture, Reader, Writer};
+use routing::{ClientError, EntryActions, Value};
 use self_encryption_storage::SelfEncryptionStorage;
 use utils::FutureExt;
 
+/// Prefix of the plaintext keys of the entries holding the previous versions
+/// of files. File names come from C strings, so they can't clash with it.
+pub const VERSIONS_KEY_PREFIX: &'static [u8] = b"\0versions/";
+
+/// Maximum number of previous versions kept for a file. `update` drops the
+/// oldest ones beyond it, so the versions entry doesn't outgrow the limits of
+/// the `MutableData`.
+pub const MAX_KEPT_VERSIONS: usize = 10;
+
+/// Prefix of the plaintext keys of the entries holding the progress of
+/// unfinished uploads (see `NfsFileWriter`).
+pub const UPLOADS_KEY_PREFIX: &'static [u8] = b"\0uploads/";
+
 /// Insert the file into the directory.
 pub fn insert<S, T>(
     client: Client<T>,
@@ -126,6 +140,9 @@ where
 /// Updates the file.
 /// If `version` is 0, the current version is first retrieved from the network,
 /// and that version incremented by one is then used as the actual version.
+/// The replaced file is kept in the versions entry of the file, in the same
+/// mutation, so it can later be retrieved with `fetch_version`. Only the last
+/// `MAX_KEPT_VERSIONS` replaced files are kept.
 pub fn update<S, T>(
     client: Client<T>,
     parent: MDataInfo,
@@ -140,33 +157,171 @@ where
     let name = name.as_ref();
     trace!("Updating file with name '{}'", name);
 
-    let client2 = client.clone();
+    let key = fry!(parent.enc_entry_key(name.as_bytes()));
+    let versions_key = fry!(parent.enc_entry_key(&versions_entry_key(name)));
+    let encoded = fry!(serialise(&file));
+    let content = fry!(parent.enc_entry_value(&encoded));
 
-    serialise(&file)
-        .map_err(From::from)
-        .and_then(|encoded| {
-            let key = parent.enc_entry_key(name.as_bytes())?;
-            let content = parent.enc_entry_value(&encoded)?;
+    let current = get_optional_value(&client, &parent, key.clone());
+    let versions = get_optional_value(&client, &parent, versions_key.clone());
+
+    current
+        .join(versions)
+        .map_err(convert_error)
+        .and_then(move |(current, versions)| {
+            let current = current.ok_or(NfsError::FileNotFound)?;
+            let version = if version != 0 {
+                version
+            } else {
+                current.entry_version + 1
+            };
+            let mut actions = EntryActions::new().update(key, content, version);
+
+            // Deleted files have no content to keep.
+            if !current.content.is_empty() {
+                let (mut previous, versions_version) = match versions {
+                    Some(value) => (
+                        decode_versions(&parent, &value)?,
+                        Some(value.entry_version),
+                    ),
+                    None => (Vec::new(), None),
+                };
+                let old_file = deserialise(&parent.decrypt(&current.content)?)?;
+                previous.push((current.entry_version, old_file));
+                if previous.len() > MAX_KEPT_VERSIONS {
+                    let excess = previous.len() - MAX_KEPT_VERSIONS;
+                    let _ = previous.drain(..excess);
+                }
 
-            Ok((key, content))
+                let encoded = parent.enc_entry_value(&serialise(&previous)?)?;
+                actions = match versions_version {
+                    Some(entry_version) => {
+                        actions.update(versions_key, encoded, entry_version + 1)
+                    }
+                    None => actions.ins(versions_key, encoded, 0),
+                };
+            }
+
+            Ok((actions, parent))
         })
-        .into_future()
-        .and_then(move |(key, content)| if version != 0 {
-            ok!((key, content, version, parent))
-        } else {
+        .and_then(move |(actions, parent)| {
             client
-                .get_mdata_value(parent.name, parent.type_tag, key.clone())
-                .map(move |value| (key, content, value.entry_version + 1, parent))
-                .into_box()
+                .mutate_mdata_entries(parent.name, parent.type_tag, actions.into())
+                .map_err(convert_error)
         })
-        .and_then(move |(key, content, version, parent)| {
-            client2.mutate_mdata_entries(
-                parent.name,
-                parent.type_tag,
-                EntryActions::new().update(key, content, version).into(),
-            )
+        .into_box()
+}
+
+/// Gets the given version of the file from the directory. The current version
+/// is the one returned by `fetch`; up to `MAX_KEPT_VERSIONS` earlier ones are
+/// available if the file was replaced using `update`.
+pub fn fetch_version<S, T>(
+    client: &Client<T>,
+    parent: &MDataInfo,
+    name: S,
+    version: u64,
+) -> Box<NfsFuture<File>>
+where
+    S: AsRef<str>,
+    T: 'static,
+{
+    versions(client, parent, name)
+        .and_then(move |versions| {
+            versions
+                .into_iter()
+                .find(|&(file_version, _)| file_version == version)
+                .map(|(_, file)| file)
+                .ok_or(NfsError::FileNotFound)
         })
+        .into_box()
+}
+
+/// Lists the versions of the file which can be retrieved with
+/// `fetch_version`, in ascending order. The last one is the current version,
+/// unless the file has been deleted.
+pub fn list_versions<S, T>(
+    client: &Client<T>,
+    parent: &MDataInfo,
+    name: S,
+) -> Box<NfsFuture<Vec<u64>>>
+where
+    S: AsRef<str>,
+    T: 'static,
+{
+    versions(client, parent, name)
+        .map(|versions| versions.into_iter().map(|(version, _)| version).collect())
+        .into_box()
+}
+
+// All the known versions of the file, oldest first.
+fn versions<S, T>(
+    client: &Client<T>,
+    parent: &MDataInfo,
+    name: S,
+) -> Box<NfsFuture<Vec<(u64, File)>>>
+where
+    S: AsRef<str>,
+    T: 'static,
+{
+    let name = name.as_ref();
+    let key = fry!(parent.enc_entry_key(name.as_bytes()));
+    let versions_key = fry!(parent.enc_entry_key(&versions_entry_key(name)));
+    let parent = parent.clone();
+
+    let current = get_optional_value(client, &parent, key);
+    let versions = get_optional_value(client, &parent, versions_key);
+
+    current
+        .join(versions)
         .map_err(convert_error)
+        .and_then(move |(current, versions)| {
+            let mut result = match versions {
+                Some(value) => decode_versions(&parent, &value)?,
+                None => Vec::new(),
+            };
+
+            if let Some(value) = current {
+                if !value.content.is_empty() {
+                    let file = deserialise(&parent.decrypt(&value.content)?)?;
+                    result.push((value.entry_version, file));
+                }
+            }
+
+            if result.is_empty() {
+                Err(NfsError::FileNotFound)
+            } else {
+                Ok(result)
+            }
+        })
+        .into_box()
+}
+
+fn versions_entry_key(name: &str) -> Vec<u8> {
+    let mut key = VERSIONS_KEY_PREFIX.to_vec();
+    key.extend_from_slice(name.as_bytes());
+    key
+}
+
+fn decode_versions(parent: &MDataInfo, value: &Value) -> Result<Vec<(u64, File)>, NfsError> {
+    if value.content.is_empty() {
+        Ok(Vec::new())
+    } else {
+        Ok(deserialise(&parent.decrypt(&value.content)?)?)
+    }
+}
+
+fn get_optional_value<T: 'static>(
+    client: &Client<T>,
+    parent: &MDataInfo,
+    key: Vec<u8>,
+) -> Box<Future<Item = Option<Value>, Error = CoreError>> {
+    client
+        .get_mdata_value(parent.name, parent.type_tag, key)
+        .then(|res| match res {
+            Ok(value) => Ok(Some(value)),
+            Err(CoreError::RoutingClientError(ClientError::NoSuchEntry)) => Ok(None),
+            Err(err) => Err(err),
+        })
         .into_box()
 }
 
@@ -194,6 +349,120 @@ where
     )
 }
 
+/// Criteria for `search`. A file matches when it satisfies all the criteria
+/// which are set.
+#[derive(Clone, Debug, Default)]
+pub struct SearchQuery {
+    /// Case-insensitive substring of the file name
+    pub name: Option<String>,
+    /// Byte sequence contained in the user metadata (e.g. MIME type stored there)
+    pub user_metadata: Option<Vec<u8>>,
+    /// Whether to match the previous versions of the files as well
+    pub include_versions: bool,
+    /// Whether to match the files of unfinished uploads as well
+    pub include_uploads: bool,
+}
+
+impl SearchQuery {
+    /// Check whether the file with the given name matches the query.
+    pub fn matches(&self, name: &str, file: &File) -> bool {
+        if let Some(ref pattern) = self.name {
+            if !name.to_lowercase().contains(&pattern.to_lowercase()) {
+                return false;
+            }
+        }
+        if let Some(ref pattern) = self.user_metadata {
+            let metadata = file.user_metadata();
+            if !pattern.is_empty() && !metadata.windows(pattern.len()).any(|w| w == &pattern[..]) {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+/// Search the given directories for files matching the query, using only the
+/// file metadata. The directories are scanned one by one and `on_match` is
+/// called with the directory index, file name and file as soon as matches in
+/// a directory are found. Returns the total number of matches. With
+/// `include_versions` set, every matching previous version of a file counts
+/// as a separate match. The same goes for unfinished uploads with
+/// `include_uploads` set.
+pub fn search<T, F>(
+    client: Client<T>,
+    dirs: Vec<MDataInfo>,
+    query: SearchQuery,
+    on_match: F,
+) -> Box<NfsFuture<u64>>
+where
+    T: 'static,
+    F: FnMut(usize, &str, &File) + 'static,
+{
+    future::loop_fn(
+        (0, on_match, 0),
+        move |(index, mut on_match, count)| {
+            let dir = match dirs.get(index) {
+                Some(dir) => dir.clone(),
+                None => return ok!(Loop::Break(count)),
+            };
+            let query = query.clone();
+
+            client
+                .list_mdata_entries(dir.name, dir.type_tag)
+                .map_err(NfsError::from)
+                .and_then(move |entries| {
+                    let mut count = count;
+
+                    for (key, value) in entries {
+                        // Skip deleted entries.
+                        if value.content.is_empty() {
+                            continue;
+                        }
+
+                        let key = dir.decrypt(&key)?;
+                        // Sub-directory links hold no files.
+                        if key.starts_with(DIRS_KEY_PREFIX) {
+                            continue;
+                        }
+                        let (name, files): (Vec<u8>, Vec<File>) =
+                            if key.starts_with(VERSIONS_KEY_PREFIX) {
+                                if !query.include_versions {
+                                    continue;
+                                }
+                                let files = decode_versions(&dir, &value)?
+                                    .into_iter()
+                                    .map(|(_, file)| file)
+                                    .collect();
+                                (key[VERSIONS_KEY_PREFIX.len()..].to_vec(), files)
+                            } else if key.starts_with(UPLOADS_KEY_PREFIX) {
+                                if !query.include_uploads {
+                                    continue;
+                                }
+                                let file = deserialise(&dir.decrypt(&value.content)?)?;
+                                (key[UPLOADS_KEY_PREFIX.len()..].to_vec(), vec![file])
+                            } else {
+                                (key, vec![deserialise(&dir.decrypt(&value.content)?)?])
+                            };
+
+                        let name = String::from_utf8(name).map_err(|err| {
+                            NfsError::Unexpected(format!("Invalid file name: {:?}", err))
+                        })?;
+
+                        for file in files {
+                            if query.matches(&name, &file) {
+                                on_match(index, &name, &file);
+                                count += 1;
+                            }
+                        }
+                    }
+
+                    Ok(Loop::Continue((index + 1, on_match, count)))
+                })
+                .into_box()
+        },
+    ).into_box()
+}
+
 // This is different from `impl From<CoreError> for NfsError`, because it maps
 // `NoSuchEntry` to `FileNotFound`.
 // TODO:  consider performing such conversion directly in the mentioned `impl From`.
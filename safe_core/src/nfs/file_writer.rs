@@ -0,0 +1,223 @@
+// Copyright 2017 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under (1) the MaidSafe.net Commercial License,
+// version 1.0 or later, or (2) The General Public License (GPL), version 3, depending on which
+// licence you accepted on initial access to the Software (the "Licences").
+//
+// By contributing code to the SAFE Network Software, or to this project generally, you agree to be
+// bound by the terms of the MaidSafe Contributor Agreement.  This, along with the Licenses can be
+// found in the root directory of this project at LICENSE, COPYING and CONTRIBUTOR.
+//
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.
+//
+// Please review the Licences for the specific language governing permissions and limitations
+// relating to use of the SAFE Network Software.
+
+use client::{Client, MDataInfo};
+use errors::CoreError;
+use futures::Future;
+use maidsafe_utilities::serialisation::{deserialise, serialise};
+use nfs::{File, Mode, NfsError, NfsFuture, Writer};
+use nfs::file_helper::{self, UPLOADS_KEY_PREFIX};
+use routing::{ClientError, EntryActions};
+use std::cell::RefCell;
+use std::rc::Rc;
+use utils::FutureExt;
+
+/// Writer which stores its progress in the parent directory after every
+/// chunk, so an interrupted upload can be resumed (even after a restart) by
+/// opening a writer for the same file name again.
+pub struct NfsFileWriter<T> {
+    client: Client<T>,
+    parent: MDataInfo,
+    // Encrypted key of the entry holding the unfinished upload.
+    key: Vec<u8>,
+    state: Rc<RefCell<State<T>>>,
+}
+
+struct State<T> {
+    // `None` while a chunk is being written.
+    writer: Option<Writer<T>>,
+    // The file as of the last stored chunk.
+    file: File,
+    // Version of the upload entry, if the entry exists.
+    entry_version: Option<u64>,
+    // Whether the upload entry currently holds a file.
+    stored: bool,
+}
+
+impl<T: 'static> NfsFileWriter<T> {
+    /// Open a writer appending to `file`, which is stored under `name` in
+    /// `parent`. If an unfinished upload of a file with the same name exists,
+    /// it is resumed instead and `file` is ignored. Use `size` to find out how
+    /// much data is already stored.
+    pub fn open<S: AsRef<str>>(
+        client: Client<T>,
+        parent: MDataInfo,
+        name: S,
+        file: File,
+    ) -> Box<NfsFuture<NfsFileWriter<T>>> {
+        let key = fry!(parent.enc_entry_key(&upload_entry_key(name.as_ref())));
+        let parent2 = parent.clone();
+        let client2 = client.clone();
+
+        client
+            .get_mdata_value(parent.name, parent.type_tag, key.clone())
+            .then(move |res| -> Result<_, NfsError> {
+                match res {
+                    Ok(value) => {
+                        if value.content.is_empty() {
+                            Ok((file, Some(value.entry_version), false))
+                        } else {
+                            let file = deserialise(&parent.decrypt(&value.content)?)?;
+                            Ok((file, Some(value.entry_version), true))
+                        }
+                    }
+                    Err(CoreError::RoutingClientError(ClientError::NoSuchEntry)) => {
+                        Ok((file, None, false))
+                    }
+                    Err(err) => Err(NfsError::from(err)),
+                }
+            })
+            .and_then(move |(file, entry_version, stored)| {
+                open_writer(&client2, &parent2, &file).map(move |writer| {
+                    NfsFileWriter {
+                        client: client2,
+                        parent: parent2,
+                        key,
+                        state: Rc::new(RefCell::new(State {
+                            writer: Some(writer),
+                            file,
+                            entry_version,
+                            stored,
+                        })),
+                    }
+                })
+            })
+            .into_box()
+    }
+
+    /// Size of the data stored so far. A resumed upload continues from here.
+    pub fn size(&self) -> u64 {
+        self.state.borrow().file.size()
+    }
+
+    /// Write the chunk and store the progress, so the upload can be resumed
+    /// from the end of this chunk. Chunks must be written one at a time. If
+    /// writing fails, the writer has to be opened again to resume the upload
+    /// from the last stored chunk.
+    pub fn write_chunk(&self, data: &[u8]) -> Box<NfsFuture<()>> {
+        let writer = match self.state.borrow_mut().writer.take() {
+            Some(writer) => writer,
+            None => {
+                return err!(NfsError::Unexpected(
+                    "Another chunk is being written".to_string(),
+                ))
+            }
+        };
+
+        let client = self.client.clone();
+        let parent = self.parent.clone();
+        let key = self.key.clone();
+        let state = Rc::clone(&self.state);
+
+        let fut = writer.write(data);
+        fut.and_then(move |_| writer.close())
+            .and_then(move |file| {
+                let content = fry!(parent.enc_entry_value(&fry!(serialise(&file))));
+                let entry_version = state.borrow().entry_version;
+                let (actions, entry_version) = match entry_version {
+                    Some(version) => (
+                        EntryActions::new().update(key, content, version + 1),
+                        version + 1,
+                    ),
+                    None => (EntryActions::new().ins(key, content, 0), 0),
+                };
+
+                client
+                    .mutate_mdata_entries(parent.name, parent.type_tag, actions.into())
+                    .map_err(NfsError::from)
+                    .and_then(move |_| {
+                        let writer = open_writer(&client, &parent, &file);
+                        writer.map(move |writer| (writer, file))
+                    })
+                    .map(move |(writer, file)| {
+                        let mut state = state.borrow_mut();
+                        state.writer = Some(writer);
+                        state.file = file;
+                        state.entry_version = Some(entry_version);
+                        state.stored = true;
+                    })
+                    .into_box()
+            })
+            .into_box()
+    }
+
+    /// Finish the upload and remove its stored progress. Returns the final
+    /// `File`, which still has to be inserted into (or updated in) the parent
+    /// directory.
+    pub fn close(self) -> Box<NfsFuture<File>> {
+        let writer = match self.state.borrow_mut().writer.take() {
+            Some(writer) => writer,
+            None => {
+                return err!(NfsError::Unexpected(
+                    "Another chunk is being written".to_string(),
+                ))
+            }
+        };
+
+        let entry_version = {
+            let state = self.state.borrow();
+            if state.stored {
+                state.entry_version
+            } else {
+                None
+            }
+        };
+        let client = self.client;
+        let parent = self.parent;
+        let key = self.key;
+
+        writer
+            .close()
+            .and_then(move |file| match entry_version {
+                Some(version) => {
+                    let actions = EntryActions::new().del(key, version + 1);
+                    client
+                        .mutate_mdata_entries(parent.name, parent.type_tag, actions.into())
+                        .map_err(NfsError::from)
+                        .map(move |_| file)
+                        .into_box()
+                }
+                None => ok!(file),
+            })
+            .into_box()
+    }
+}
+
+fn open_writer<T: 'static>(
+    client: &Client<T>,
+    parent: &MDataInfo,
+    file: &File,
+) -> Box<NfsFuture<Writer<T>>> {
+    // A file without any content has no data-map to append to yet.
+    let mode = if file.size() == 0 {
+        Mode::Overwrite
+    } else {
+        Mode::Append
+    };
+    file_helper::write(
+        client.clone(),
+        file.clone(),
+        mode,
+        parent.enc_key().cloned(),
+    )
+}
+
+fn upload_entry_key(name: &str) -> Vec<u8> {
+    let mut key = UPLOADS_KEY_PREFIX.to_vec();
+    key.extend_from_slice(name.as_bytes());
+    key
+}
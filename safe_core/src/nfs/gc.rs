@@ -0,0 +1,121 @@
+// Copyright 2016 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under (1) the MaidSafe.net Commercial License,
+// version 1.0 or later, or (2) The General Public License (GPL), version 3, depending on which
+// licence you accepted on initial access to the Software (the "Licences").
+//
+// By contributing code to the SAFE Network Software, or to this project generally, you agree to be
+// bound by the terms of the MaidSafe Contributor Agreement.  This, along with the Licenses can be
+// found in the root directory of this project at LICENSE, COPYING and CONTRIBUTOR.
+//
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.
+//
+// Please review the Licences for the specific language governing permissions and limitations
+// relating to use of the SAFE Network Software.
+
+//! Analysis of the immutable chunks referenced by NFS directories, to find
+//! chunks which are no longer needed.
+
+use client::{Client, MDataInfo};
+use crypto::shared_secretbox;
+use futures::{Future, future};
+use immutable_data;
+use nfs::{File, NfsError, NfsFuture, data_map};
+use nfs::file_helper::{self, SearchQuery};
+use routing::XorName;
+use std::cell::RefCell;
+use std::collections::{BTreeMap, BTreeSet};
+use std::mem;
+use std::rc::Rc;
+use utils::FutureExt;
+
+/// Result of `find_unreferenced_chunks`.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct GcReport {
+    /// Names of all the chunks referenced by the files in the directories.
+    pub referenced: BTreeSet<XorName>,
+    /// Chunks from the inventory which are not referenced, with their sizes.
+    pub orphaned: BTreeMap<XorName, u64>,
+    /// Total size of the orphaned chunks.
+    pub reclaimable: u64,
+}
+
+/// Walk the files of the given directories, including their previous
+/// versions and unfinished uploads, and collect the names of the chunks their
+/// data maps and contents are stored in. The chunks of `inventory` (chunk name
+/// to its size) which are not referenced by any file are reported as orphaned.
+/// Nothing is deleted.
+pub fn find_unreferenced_chunks<T: 'static>(
+    client: Client<T>,
+    dirs: Vec<MDataInfo>,
+    inventory: BTreeMap<XorName, u64>,
+) -> Box<NfsFuture<GcReport>> {
+    let files = Rc::new(RefCell::new(Vec::new()));
+    let files2 = Rc::clone(&files);
+    let client2 = client.clone();
+
+    let search = file_helper::search(
+        client,
+        dirs.clone(),
+        SearchQuery {
+            include_versions: true,
+            include_uploads: true,
+            ..SearchQuery::default()
+        },
+        move |index, _name, file| files2.borrow_mut().push((index, file.clone())),
+    );
+
+    search
+        .and_then(move |_| {
+            let files = mem::replace(&mut *files.borrow_mut(), Vec::new());
+            let futures: Vec<_> = files
+                .into_iter()
+                .map(|(index, file)| {
+                    file_chunk_names(&client2, &file, dirs[index].enc_key().cloned())
+                })
+                .collect();
+
+            future::join_all(futures)
+        })
+        .map(move |names| {
+            let mut report = GcReport::default();
+
+            for name in names.into_iter().flat_map(|names| names) {
+                let _ = report.referenced.insert(name);
+            }
+
+            for (name, size) in inventory {
+                if !report.referenced.contains(&name) {
+                    let _ = report.orphaned.insert(name, size);
+                    report.reclaimable += size;
+                }
+            }
+
+            report
+        })
+        .into_box()
+}
+
+// Names of the chunks the data map of the file and its content are stored in.
+fn file_chunk_names<T: 'static>(
+    client: &Client<T>,
+    file: &File,
+    encryption_key: Option<shared_secretbox::Key>,
+) -> Box<NfsFuture<BTreeSet<XorName>>> {
+    let data_map_name = *file.data_map_name();
+
+    let stored = immutable_data::chunk_names(client, &data_map_name, encryption_key.clone())
+        .map_err(NfsError::from);
+    let content = data_map::get(client, &data_map_name, encryption_key)
+        .map(|data_map| immutable_data::data_map_chunk_names(&data_map));
+
+    stored
+        .join(content)
+        .map(|(mut names, content)| {
+            names.extend(content);
+            names
+        })
+        .into_box()
+}
@@ -22,14 +22,19 @@ mod errors;
 mod data_map;
 mod dir;
 mod file;
+mod file_writer;
+mod gc;
 mod reader;
 #[cfg(test)]
 mod tests;
 mod writer;
 
-pub use self::dir::create_dir;
+pub use self::dir::{DIRS_KEY_PREFIX, copy_recursive, create_dir, delete_recursive, fetch_dir,
+                    insert_dir, list_dirs, move_to};
 pub use self::errors::NfsError;
 pub use self::file::File;
+pub use self::file_writer::NfsFileWriter;
+pub use self::gc::{GcReport, find_unreferenced_chunks};
 pub use self::reader::Reader;
 pub use self::writer::{Mode, Writer};
 use futures::Future;
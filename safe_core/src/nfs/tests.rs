@@ -21,7 +21,9 @@ use crypto::shared_secretbox;
 use errors::CoreError;
 use futures::Future;
 use futures::future::{self, Loop};
-use nfs::{File, Mode, NfsError, NfsFuture, create_dir, file_helper};
+use nfs::{File, Mode, NfsError, NfsFileWriter, NfsFuture, copy_recursive, create_dir,
+          delete_recursive, fetch_dir, file_helper, find_unreferenced_chunks, insert_dir,
+          list_dirs, move_to};
 use nfs::reader::Reader;
 use nfs::writer::Writer;
 use rand::{self, Rng};
@@ -608,3 +610,481 @@ fn encryption() {
             })
     })
 }
+
+// Search directories by file name and user metadata.
+#[test]
+fn file_search() {
+    random_client(|client| {
+        let c2 = client.clone();
+        let c3 = client.clone();
+        let c4 = client.clone();
+
+        let dir0 = unwrap!(MDataInfo::random_private(DIR_TAG));
+        let dir1 = unwrap!(MDataInfo::random_private(DIR_TAG));
+        let dirs = vec![dir0.clone(), dir1.clone()];
+        let create0 = create_dir(client, &dir0, btree_map![], btree_map![]);
+        let create1 = create_dir(client, &dir1, btree_map![], btree_map![]);
+
+        create0
+            .join(create1)
+            .then(move |res| {
+                assert!(res.is_ok());
+
+                let inserts = vec![
+                    file_helper::insert(
+                        c2.clone(),
+                        dir0,
+                        "Holiday.jpg",
+                        &File::new(b"image/jpeg".to_vec()),
+                    ),
+                    file_helper::insert(
+                        c2.clone(),
+                        dir1.clone(),
+                        "notes.txt",
+                        &File::new(b"text/plain".to_vec()),
+                    ),
+                    file_helper::insert(
+                        c2,
+                        dir1,
+                        "holiday-plan.txt",
+                        &File::new(b"text/plain".to_vec()),
+                    ),
+                ];
+                future::join_all(inserts)
+            })
+            .then(move |res| {
+                assert!(res.is_ok());
+
+                let query = file_helper::SearchQuery {
+                    name: Some("HOLIDAY".to_string()),
+                    user_metadata: None,
+                    include_versions: false,
+                    include_uploads: false,
+                };
+                let mut found = Vec::new();
+                file_helper::search(c3, dirs.clone(), query, move |index, name, _| {
+                    found.push((index, name.to_string()));
+                    assert!(found.len() <= 2);
+                }).map(move |count| (count, dirs))
+            })
+            .then(move |res| {
+                let (count, dirs) = unwrap!(res);
+                assert_eq!(count, 2);
+
+                let query = file_helper::SearchQuery {
+                    name: Some(".txt".to_string()),
+                    user_metadata: Some(b"text/".to_vec()),
+                    include_versions: false,
+                    include_uploads: false,
+                };
+                file_helper::search(c4, dirs, query, |index, _, file| {
+                    assert_eq!(index, 1);
+                    assert_eq!(file.user_metadata(), b"text/plain");
+                })
+            })
+            .map(|count| assert_eq!(count, 2))
+    })
+}
+
+// Find the chunks which are not referenced by any file.
+#[test]
+fn unreferenced_chunks() {
+    random_client(|client| {
+        let c2 = client.clone();
+        let c3 = client.clone();
+        let c4 = client.clone();
+        let stray = rand::random();
+
+        create_test_file(client)
+            .then(move |res| {
+                let (dir, file) = unwrap!(res);
+                let inventory = btree_map![stray => 100];
+
+                find_unreferenced_chunks(c2, vec![dir.clone()], inventory)
+                    .map(move |report| (dir, file, report))
+            })
+            .then(move |res| {
+                let (dir, file, report) = unwrap!(res);
+
+                // The data map and the content chunks are all referenced.
+                assert!(report.referenced.contains(file.data_map_name()));
+                assert!(report.referenced.len() > 1);
+                assert_eq!(report.orphaned, btree_map![stray => 100]);
+                assert_eq!(report.reclaimable, 100);
+
+                file_helper::delete(&c3, &dir, "hello.txt", 1)
+                    .map(move |_| (dir, report.referenced))
+            })
+            .then(move |res| {
+                let (dir, referenced) = unwrap!(res);
+                let inventory = referenced.iter().map(|name| (*name, 1)).collect();
+
+                find_unreferenced_chunks(c4, vec![dir], inventory)
+                    .map(move |report| (referenced, report))
+            })
+            .map(|(referenced, report)| {
+                assert!(report.referenced.is_empty());
+                assert_eq!(report.orphaned.len(), referenced.len());
+                assert_eq!(report.reclaimable, referenced.len() as u64);
+            })
+    });
+}
+
+// Only the last `MAX_KEPT_VERSIONS` replaced versions of a file are kept.
+#[test]
+fn file_versions_pruned() {
+    random_client(|client| {
+        let c2 = client.clone();
+        let c3 = client.clone();
+        let updates = file_helper::MAX_KEPT_VERSIONS as u64 + 2;
+
+        create_test_file(client)
+            .then(move |res| {
+                let (dir, file) = unwrap!(res);
+
+                future::loop_fn(1, move |version| {
+                    let dir = dir.clone();
+                    file_helper::update(c2.clone(), dir.clone(), "hello.txt", &file, version)
+                        .map(move |()| if version < updates {
+                            Loop::Continue(version + 1)
+                        } else {
+                            Loop::Break(dir)
+                        })
+                })
+            })
+            .then(move |res| {
+                let dir = unwrap!(res);
+                file_helper::list_versions(&c3, &dir, "hello.txt")
+            })
+            .map(move |versions| {
+                let oldest = updates - file_helper::MAX_KEPT_VERSIONS as u64;
+                assert_eq!(versions, (oldest..updates + 1).collect::<Vec<_>>());
+            })
+    });
+}
+
+// Replaced versions of a file stay available.
+// 1. Update the metadata of a file twice.
+// 2. All three versions are listed and can be fetched.
+// 3. The previous versions are not matched by a regular search.
+#[test]
+fn file_versions() {
+    random_client(|client| {
+        let c2 = client.clone();
+        let c3 = client.clone();
+        let c4 = client.clone();
+        let c5 = client.clone();
+        let c6 = client.clone();
+        let c7 = client.clone();
+
+        create_test_file(client)
+            .then(move |res| {
+                let (dir, mut file) = unwrap!(res);
+
+                file.set_user_metadata(vec![1u8; 10]);
+                file_helper::update(c2, dir.clone(), "hello.txt", &file, 1)
+                    .map(move |()| (dir, file))
+            })
+            .then(move |res| {
+                let (dir, mut file) = unwrap!(res);
+
+                file.set_user_metadata(vec![2u8; 10]);
+                file_helper::update(c3, dir.clone(), "hello.txt", &file, 0).map(move |()| dir)
+            })
+            .then(move |res| {
+                let dir = unwrap!(res);
+                file_helper::list_versions(&c4, &dir, "hello.txt").map(move |versions| {
+                    (dir, versions)
+                })
+            })
+            .then(move |res| {
+                let (dir, versions) = unwrap!(res);
+                assert_eq!(versions, vec![0, 1, 2]);
+
+                let original = file_helper::fetch_version(&c5, &dir, "hello.txt", 0);
+                let first = file_helper::fetch_version(&c5, &dir, "hello.txt", 1);
+                let missing = file_helper::fetch_version(&c5, &dir, "hello.txt", 3)
+                    .then(|res| match res {
+                        Err(NfsError::FileNotFound) => Ok(()),
+                        x => panic!("Unexpected {:?}", x),
+                    });
+
+                original.join3(first, missing).map(move |(original, first, ())| {
+                    (dir, original, first)
+                })
+            })
+            .then(move |res| {
+                let (dir, original, first) = unwrap!(res);
+                assert!(original.user_metadata().is_empty());
+                assert_eq!(first.user_metadata(), &[1u8; 10][..]);
+
+                let query = file_helper::SearchQuery {
+                    name: Some("hello".to_string()),
+                    user_metadata: None,
+                    include_versions: false,
+                    include_uploads: false,
+                };
+                file_helper::search(c6, vec![dir.clone()], query, |_, _, file| {
+                    assert_eq!(file.user_metadata(), &[2u8; 10][..]);
+                }).map(move |count| (dir, count))
+            })
+            .then(move |res| {
+                let (dir, count) = unwrap!(res);
+                assert_eq!(count, 1);
+
+                let query = file_helper::SearchQuery {
+                    name: Some("hello".to_string()),
+                    user_metadata: None,
+                    include_versions: true,
+                    include_uploads: false,
+                };
+                file_helper::search(c7, vec![dir], query, |_, _, _| ())
+            })
+            .map(|count| assert_eq!(count, 3))
+    });
+}
+
+// An interrupted upload can be resumed.
+// 1. Write a chunk and drop the writer without closing it.
+// 2. Open a writer for the same file name again - it resumes after the chunk
+//    and the unfinished upload is only matched by a search which includes
+//    uploads.
+// 3. Write another chunk and close the writer - the file has the contents of
+//    both chunks and the unfinished upload is gone.
+#[test]
+fn resumable_upload() {
+    random_client(|client| {
+        let c2 = client.clone();
+        let c3 = client.clone();
+        let c4 = client.clone();
+        let c5 = client.clone();
+        let c6 = client.clone();
+
+        create_test_file(client)
+            .then(move |res| {
+                let (dir, _) = unwrap!(res);
+                NfsFileWriter::open(c2, dir.clone(), "upload.bin", File::new(Vec::new()))
+                    .map(move |writer| (dir, writer))
+            })
+            .then(move |res| {
+                let (dir, writer) = unwrap!(res);
+                assert_eq!(writer.size(), 0);
+
+                // Dropping the writer simulates an interrupted upload.
+                writer.write_chunk(&[1u8; 10]).map(move |()| dir)
+            })
+            .then(move |res| {
+                let dir = unwrap!(res);
+                NfsFileWriter::open(c3, dir.clone(), "upload.bin", File::new(Vec::new()))
+                    .map(move |writer| (dir, writer))
+            })
+            .then(move |res| {
+                let (dir, writer) = unwrap!(res);
+                assert_eq!(writer.size(), 10);
+
+                let query = file_helper::SearchQuery {
+                    name: Some("upload".to_string()),
+                    include_uploads: true,
+                    ..file_helper::SearchQuery::default()
+                };
+                file_helper::search(c4, vec![dir.clone()], query, |_, name, file| {
+                    assert_eq!(name, "upload.bin");
+                    assert_eq!(file.size(), 10);
+                }).map(move |count| (dir, writer, count))
+            })
+            .then(move |res| {
+                let (dir, writer, count) = unwrap!(res);
+                assert_eq!(count, 1);
+
+                let fut = writer.write_chunk(&[2u8; 10]);
+                fut.and_then(move |()| writer.close())
+                    .map(move |file| (dir, file))
+            })
+            .then(move |res| {
+                let (dir, file) = unwrap!(res);
+                assert_eq!(file.size(), 20);
+
+                file_helper::read(c5, &file, dir.enc_key().cloned())
+                    .and_then(|reader| {
+                        let size = reader.size();
+                        reader.read(0, size)
+                    })
+                    .map(move |content| (dir, content))
+            })
+            .then(move |res| {
+                let (dir, content) = unwrap!(res);
+                let mut expected = vec![1u8; 10];
+                expected.extend_from_slice(&[2u8; 10]);
+                assert_eq!(content, expected);
+
+                let query = file_helper::SearchQuery {
+                    include_uploads: true,
+                    ..file_helper::SearchQuery::default()
+                };
+                file_helper::search(c6, vec![dir], query, |_, name, _| {
+                    assert_eq!(name, "hello.txt");
+                })
+            })
+            .map(|count| assert_eq!(count, 1))
+    });
+}
+
+// Create a private directory holding a single empty file called `file_name`.
+fn create_dir_with_file(client: &Client<()>, file_name: &str) -> Box<NfsFuture<MDataInfo>> {
+    let c2 = client.clone();
+    let dir = unwrap!(MDataInfo::random_private(DIR_TAG));
+    let dir2 = dir.clone();
+    let file_name = file_name.to_string();
+
+    create_dir(client, &dir, btree_map![], btree_map![])
+        .and_then(move |()| file_helper::insert(c2, dir2, file_name, &File::new(Vec::new())))
+        .map(move |()| dir)
+        .into_box()
+}
+
+// Test the recursive directory operations.
+// 1. Build a tree: root -> "sub" (with "a.txt") -> "deep" (with "b.txt").
+// 2. Copy "sub" and link the copy into root - the copy holds the same files
+//    and sub-directories, but lives elsewhere.
+// 3. Move the copy from root into "sub".
+// 4. Delete "sub" recursively - it's gone from root along with its contents,
+//    and the files of root are untouched.
+#[test]
+fn recursive_dir_ops() {
+    random_client(|client| {
+        let c2 = client.clone();
+        let c3 = client.clone();
+        let c4 = client.clone();
+        let c5 = client.clone();
+        let c6 = client.clone();
+        let c7 = client.clone();
+        let c8 = client.clone();
+        let c9 = client.clone();
+
+        create_test_file(client)
+            .join3(
+                create_dir_with_file(client, "a.txt"),
+                create_dir_with_file(client, "b.txt"),
+            )
+            .then(move |res| {
+                let ((root, _), sub, deep) = unwrap!(res);
+                let link_deep = insert_dir(&c2, &sub, "deep", &deep);
+                let link_sub = insert_dir(&c2, &root, "sub", &sub);
+                link_deep.join(link_sub).map(move |_| (root, sub))
+            })
+            .then(move |res| {
+                let (root, sub) = unwrap!(res);
+                copy_recursive(&c3, &sub).map(move |copy| (root, sub, copy))
+            })
+            .then(move |res| {
+                let (root, sub, copy) = unwrap!(res);
+                assert_ne!(copy.name, sub.name);
+
+                let client = c4.clone();
+                insert_dir(&c4, &root, "copy", &copy)
+                    .and_then(move |()| list_dirs(&client, &copy).map(move |dirs| (copy, dirs)))
+                    .map(move |(copy, dirs)| (root, sub, copy, dirs))
+            })
+            .then(move |res| {
+                let (root, sub, copy, dirs) = unwrap!(res);
+                assert_eq!(dirs.keys().collect::<Vec<_>>(), vec!["deep"]);
+
+                let deep_copy = dirs["deep"].clone();
+                let fetch_a = file_helper::fetch(c5.clone(), copy, "a.txt");
+                let fetch_b = file_helper::fetch(c5, deep_copy, "b.txt");
+                fetch_a.join(fetch_b).map(move |_| (root, sub))
+            })
+            .then(move |res| {
+                let (root, sub) = unwrap!(res);
+                let client = c6.clone();
+                let sub2 = sub.clone();
+                move_to(&c6, &root, "copy", &sub, "moved")
+                    .and_then(move |()| list_dirs(&client, &sub2))
+                    .map(move |dirs| (root, sub, dirs))
+            })
+            .then(move |res| {
+                let (root, sub, dirs) = unwrap!(res);
+                assert_eq!(dirs.keys().collect::<Vec<_>>(), vec!["deep", "moved"]);
+
+                list_dirs(&c7, &root).map(move |dirs| (root, sub, dirs))
+            })
+            .then(move |res| {
+                let (root, sub, dirs) = unwrap!(res);
+                assert_eq!(dirs.keys().collect::<Vec<_>>(), vec!["sub"]);
+
+                delete_recursive(&c8, &root, "sub").map(move |()| (root, sub))
+            })
+            .then(move |res| {
+                let (root, sub) = unwrap!(res);
+                let fetch_sub = fetch_dir(&c9, &root, "sub").then(|res| match res {
+                    Err(NfsError::DirNotFound) => Ok(()),
+                    x => panic!("Unexpected {:?}", x),
+                });
+                let fetch_a = file_helper::fetch(c9.clone(), sub, "a.txt").then(|res| {
+                    assert!(res.is_err());
+                    Ok(())
+                });
+                let query = file_helper::SearchQuery::default();
+                let search = file_helper::search(c9, vec![root], query, |_, name, _| {
+                    assert_eq!(name, "hello.txt");
+                });
+
+                fetch_sub.join3(fetch_a, search)
+            })
+            .map(|((), (), count)| assert_eq!(count, 1))
+    });
+}
+
+// Moving a directory into itself or into one of its descendants must fail
+// and leave the tree as it was.
+#[test]
+fn move_into_descendant_fails() {
+    random_client(|client| {
+        let c2 = client.clone();
+        let c3 = client.clone();
+        let c4 = client.clone();
+        let c5 = client.clone();
+
+        create_test_file(client)
+            .join3(
+                create_dir_with_file(client, "a.txt"),
+                create_dir_with_file(client, "b.txt"),
+            )
+            .then(move |res| {
+                let ((root, _), sub, deep) = unwrap!(res);
+                let link_deep = insert_dir(&c2, &sub, "deep", &deep);
+                let link_sub = insert_dir(&c2, &root, "sub", &sub);
+                link_deep.join(link_sub).map(move |_| (root, sub, deep))
+            })
+            .then(move |res| {
+                let (root, sub, deep) = unwrap!(res);
+                let into_self = move_to(&c3, &root, "sub", &sub, "moved").then(|res| {
+                    match res {
+                        Err(NfsError::Unexpected(_)) => Ok(()),
+                        x => panic!("Unexpected {:?}", x),
+                    }
+                });
+                let into_child = move_to(&c3, &root, "sub", &deep, "moved").then(|res| {
+                    match res {
+                        Err(NfsError::Unexpected(_)) => Ok(()),
+                        x => panic!("Unexpected {:?}", x),
+                    }
+                });
+                into_self
+                    .join(into_child)
+                    .map(move |_| (root, sub, deep))
+            })
+            .then(move |res: Result<_, NfsError>| {
+                let (root, sub, deep) = unwrap!(res);
+                let root_dirs = list_dirs(&c4, &root);
+                let sub_dirs = list_dirs(&c4, &sub);
+                let deep_dirs = list_dirs(&c5, &deep);
+                root_dirs.join3(sub_dirs, deep_dirs)
+            })
+            .map(|(root_dirs, sub_dirs, deep_dirs)| {
+                assert_eq!(root_dirs.keys().collect::<Vec<_>>(), vec!["sub"]);
+                assert_eq!(sub_dirs.keys().collect::<Vec<_>>(), vec!["deep"]);
+                assert!(deep_dirs.is_empty());
+            })
+    });
+}
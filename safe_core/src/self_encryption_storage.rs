@@ -16,22 +16,33 @@
 // relating to use of the SAFE Network Software.
 
 use super::{Client, CoreError, FutureExt};
-use futures::{self, Future};
+use futures::{self, Future, future};
+use lru_cache::LruCache;
 use routing::{ImmutableData, XOR_NAME_LEN, XorName};
 use self_encryption::{Storage, StorageError};
+use std::cell::RefCell;
 use std::error::Error;
 use std::fmt::{self, Display, Formatter};
+use std::fs::{self, File};
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
 
 /// Network storage is the concrete type which self-encryption crate will use
 /// to put or get data from the network
 pub struct SelfEncryptionStorage<T> {
     client: Client<T>,
+    chunk_cache: Option<Rc<ChunkCache>>,
 }
 
-impl<T> SelfEncryptionStorage<T> {
+impl<T: 'static> SelfEncryptionStorage<T> {
     /// Create a new SelfEncryptionStorage instance
     pub fn new(client: Client<T>) -> Self {
-        SelfEncryptionStorage { client: client }
+        let chunk_cache = client.chunk_cache();
+        SelfEncryptionStorage {
+            client: client,
+            chunk_cache: chunk_cache,
+        }
     }
 }
 
@@ -53,9 +64,20 @@ impl<T: 'static> Storage for SelfEncryptionStorage<T> {
             XorName(temp)
         };
 
+        if let Some(content) = self.chunk_cache.as_ref().and_then(|cache| cache.get(&name)) {
+            trace!("Chunk found in the chunk cache.");
+            return future::ok(content).into_box();
+        }
+
+        let chunk_cache = self.chunk_cache.clone();
         self.client
             .get_idata(name)
-            .map(|data| data.value().clone())
+            .map(move |data| {
+                if let Some(cache) = chunk_cache {
+                    cache.insert(*data.name(), data.value());
+                }
+                data.value().clone()
+            })
             .map_err(From::from)
             .into_box()
     }
@@ -63,8 +85,181 @@ impl<T: 'static> Storage for SelfEncryptionStorage<T> {
     fn put(&mut self, _: Vec<u8>, data: Vec<u8>) -> Box<Future<Item = (), Error = Self::Error>> {
         trace!("Self encrypt invoked PutIData.");
         let data = ImmutableData::new(data);
-        self.client.put_idata(data).map_err(From::from).into_box()
+        let name = *data.name();
+
+        let chunk_cache = match self.chunk_cache {
+            Some(ref cache) if cache.contains(&name) => {
+                trace!("Chunk already uploaded, skipping the PutIData.");
+                return future::ok(()).into_box();
+            }
+            ref chunk_cache => chunk_cache.clone(),
+        };
+
+        self.client
+            .put_idata(data.clone())
+            .map(move |_| if let Some(cache) = chunk_cache {
+                cache.insert(name, data.value());
+            })
+            .map_err(From::from)
+            .into_box()
+    }
+}
+
+/// Index of the chunks known to be stored in the network, used to skip
+/// uploading unchanged chunks again when a modified file is written.
+/// Optionally, the content of the chunks is also kept in a local directory, so
+/// reads of the cached chunks don't hit the network. At most `capacity` chunks
+/// are kept, the least recently used ones are evicted first.
+pub struct ChunkCache {
+    known: RefCell<LruCache<XorName, ()>>,
+    dir: Option<PathBuf>,
+}
+
+impl ChunkCache {
+    /// Create an index of at most `capacity` chunks uploaded or fetched in this
+    /// session.
+    pub fn new(capacity: usize) -> Self {
+        ChunkCache {
+            known: RefCell::new(LruCache::new(capacity)),
+            dir: None,
+        }
+    }
+
+    /// Create an index which also stores the content of at most `capacity`
+    /// chunks in `dir`, creating the directory if needed. The chunks stored
+    /// there by earlier sessions are known too, the oldest of them are removed
+    /// if there's more than `capacity` of them.
+    pub fn with_dir<P: AsRef<Path>>(dir: P, capacity: usize) -> Result<Self, CoreError> {
+        let to_core_error = |error: io::Error| {
+            CoreError::Unexpected(format!("Chunk cache I/O error: {:?}", error))
+        };
+
+        fs::create_dir_all(&dir).map_err(&to_core_error)?;
+
+        let mut stored = Vec::new();
+        for entry in fs::read_dir(&dir).map_err(&to_core_error)? {
+            let entry = entry.map_err(&to_core_error)?;
+            let name = match entry.file_name().to_str().and_then(parse_name) {
+                Some(name) => name,
+                None => continue,
+            };
+            let modified = entry
+                .metadata()
+                .and_then(|metadata| metadata.modified())
+                .map_err(&to_core_error)?;
+            stored.push((modified, name));
+        }
+        stored.sort();
+
+        let cache = ChunkCache {
+            known: RefCell::new(LruCache::new(capacity)),
+            dir: Some(dir.as_ref().to_path_buf()),
+        };
+        for (_, name) in stored {
+            if capacity == 0 {
+                cache.remove_file(&name);
+            } else {
+                cache.remember(name);
+            }
+        }
+
+        Ok(cache)
+    }
+
+    /// Whether the chunk with the given name is known to be in the network.
+    /// If the chunks are stored locally, the chunk is only known if its stored
+    /// content matches the name.
+    pub fn contains(&self, name: &XorName) -> bool {
+        if self.dir.is_some() {
+            self.get(name).is_some()
+        } else {
+            self.known.borrow_mut().get_mut(name).is_some()
+        }
+    }
+
+    // Content of the chunk from the local directory. Content which doesn't
+    // match the name is ignored.
+    fn get(&self, name: &XorName) -> Option<Vec<u8>> {
+        if self.known.borrow_mut().get_mut(name).is_none() {
+            return None;
+        }
+        let path = match self.path(name) {
+            Some(path) => path,
+            None => return None,
+        };
+        let mut content = Vec::new();
+        if File::open(path)
+            .and_then(|mut file| file.read_to_end(&mut content))
+            .is_err()
+        {
+            return None;
+        }
+
+        if ImmutableData::new(content.clone()).name() == name {
+            Some(content)
+        } else {
+            None
+        }
+    }
+
+    // Storing the content locally is best effort, the chunk is in the network
+    // anyway.
+    fn insert(&self, name: XorName, content: &[u8]) {
+        if self.known.borrow().capacity() == 0 {
+            return;
+        }
+        if let Some(path) = self.path(&name) {
+            if let Err(error) = File::create(path).and_then(|mut file| file.write_all(content)) {
+                warn!("Failed to store chunk {:?} in the chunk cache: {:?}", name, error);
+            }
+        }
+        self.remember(name);
+    }
+
+    // Add the chunk to the index, evicting the least recently used chunk if
+    // the index is full.
+    fn remember(&self, name: XorName) {
+        let mut known = self.known.borrow_mut();
+        if known.get_mut(&name).is_some() {
+            return;
+        }
+
+        if known.len() >= known.capacity() {
+            if let Some((evicted, ())) = known.remove_lru() {
+                self.remove_file(&evicted);
+            }
+        }
+        let _ = known.insert(name, ());
+    }
+
+    fn remove_file(&self, name: &XorName) {
+        if let Some(path) = self.path(name) {
+            if let Err(error) = fs::remove_file(path) {
+                warn!("Failed to remove chunk {:?} from the chunk cache: {:?}", name, error);
+            }
+        }
+    }
+
+    fn path(&self, name: &XorName) -> Option<PathBuf> {
+        self.dir.as_ref().map(|dir| {
+            let file_name: String = name.0.iter().map(|byte| format!("{:02x}", byte)).collect();
+            dir.join(file_name)
+        })
+    }
+}
+
+// Parse the chunk name from the name of the file it's stored in.
+fn parse_name(file_name: &str) -> Option<XorName> {
+    if file_name.len() != 2 * XOR_NAME_LEN || !file_name.chars().all(|c| c.is_digit(16)) {
+        return None;
+    }
+
+    let mut name = [0; XOR_NAME_LEN];
+    for (i, byte) in name.iter_mut().enumerate() {
+        // OK to unwrap here, as we checked all the characters are hex digits.
+        *byte = unwrap!(u8::from_str_radix(&file_name[2 * i..2 * i + 2], 16));
     }
+    Some(XorName(name))
 }
 
 /// Errors arising from storage object being used by self-encryptors.
@@ -94,3 +289,84 @@ impl From<CoreError> for SelfEncryptionStorageError {
 }
 
 impl StorageError for SelfEncryptionStorageError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand;
+    use std::env;
+    use utils::test_utils::random_client;
+
+    // Chunks known to be in the network are not uploaded again and are read
+    // back from the local directory.
+    #[test]
+    fn chunk_cache() {
+        let dir = env::temp_dir().join(format!("chunks-{}", rand::random::<u64>()));
+        let dir2 = dir.clone();
+
+        random_client(move |client| {
+            client.set_chunk_cache(Some(unwrap!(ChunkCache::with_dir(&dir2, 16))));
+
+            let client2 = client.clone();
+            let mut storage = SelfEncryptionStorage::new(client.clone());
+            let chunk = vec![1u8; 64];
+            let name = *ImmutableData::new(chunk.clone()).name();
+
+            storage.put(Vec::new(), chunk.clone()).and_then(move |_| {
+                let stats = client2.stats();
+                assert!(unwrap!(client2.chunk_cache()).contains(&name));
+
+                storage
+                    .put(Vec::new(), chunk.clone())
+                    .and_then(move |_| storage.get(&name.0))
+                    .map(move |content| {
+                        assert_eq!(content, chunk);
+
+                        let new_stats = client2.stats();
+                        assert_eq!(new_stats.puts, stats.puts);
+                        assert_eq!(new_stats.gets, stats.gets);
+                    })
+            })
+        });
+
+        unwrap!(fs::remove_dir_all(&dir));
+    }
+
+    // Only chunks whose stored content matches the name are known, and the
+    // least recently used chunks are evicted from the full cache.
+    #[test]
+    fn chunk_cache_eviction() {
+        let dir = env::temp_dir().join(format!("chunks-{}", rand::random::<u64>()));
+        let cache = unwrap!(ChunkCache::with_dir(&dir, 2));
+
+        let chunks: Vec<_> = (0..3u8).map(|i| vec![i; 64]).collect();
+        let names: Vec<_> = chunks
+            .iter()
+            .map(|chunk| *ImmutableData::new(chunk.clone()).name())
+            .collect();
+
+        cache.insert(names[0], &chunks[0]);
+        cache.insert(names[1], &chunks[1]);
+        assert!(cache.contains(&names[0]));
+        assert!(cache.contains(&names[1]));
+
+        // The second chunk is now the least recently used one.
+        assert!(cache.contains(&names[0]));
+        cache.insert(names[2], &chunks[2]);
+        assert!(cache.contains(&names[0]));
+        assert!(!cache.contains(&names[1]));
+        assert!(cache.contains(&names[2]));
+        assert!(!unwrap!(cache.path(&names[1])).exists());
+
+        // Corrupt the stored content of a chunk.
+        unwrap!(unwrap!(File::create(unwrap!(cache.path(&names[0])))).write_all(&chunks[2]));
+        assert!(!cache.contains(&names[0]));
+
+        // The chunks stored by earlier sessions are known too.
+        let cache = unwrap!(ChunkCache::with_dir(&dir, 2));
+        assert!(cache.contains(&names[2]));
+        assert!(!cache.contains(&names[1]));
+
+        unwrap!(fs::remove_dir_all(&dir));
+    }
+}
@@ -0,0 +1,407 @@
+// Copyright 2017 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under (1) the MaidSafe.net Commercial License,
+// version 1.0 or later, or (2) The General Public License (GPL), version 3, depending on which
+// licence you accepted on initial access to the Software (the "Licences").
+//
+// By contributing code to the SAFE Network Software, or to this project generally, you agree to be
+// bound by the terms of the MaidSafe Contributor Agreement.  This, along with the Licenses can be
+// found in the root directory of this project at LICENSE, COPYING and CONTRIBUTOR.
+//
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.
+//
+// Please review the Licences for the specific language governing permissions and limitations
+// relating to use of the SAFE Network Software.
+
+//! Append-only log stored in a chain of `MutableData` segments.
+//!
+//! Each segment holds up to `SEGMENT_LEN` items, a counter of the items it
+//! holds and, once full, a link to the next segment. Appending updates the
+//! counter and inserts the item in a single mutation, so concurrent appends
+//! are serialised by the entry versions: the loser of a race gets
+//! `InvalidEntryActions` and retries with the fresh counter.
+
+use client::{Client, MDataInfo, mdata_info};
+use errors::CoreError;
+use event_loop::CoreFuture;
+use futures::{Future, future};
+use futures::future::Loop;
+use maidsafe_utilities::serialisation::{deserialise, serialise};
+use routing::{ClientError, EntryAction, MutableData, PermissionSet, User, Value};
+use std::cmp;
+use std::collections::BTreeMap;
+use std::ops::Range;
+use utils::FutureExt;
+
+/// Maximum number of items stored in a single segment.
+pub const SEGMENT_LEN: u64 = 96;
+
+const LEN_KEY: &'static [u8] = b"len";
+const NEXT_KEY: &'static [u8] = b"next";
+/// Maximum number of attempts to append an item.
+const MAX_ATTEMPTS: usize = 10;
+
+/// Append-only log of binary items.
+#[derive(Clone, Debug)]
+pub struct AppendLog {
+    root: MDataInfo,
+    perms: BTreeMap<User, PermissionSet>,
+}
+
+struct Segment {
+    info: MDataInfo,
+    len: u64,
+    len_version: u64,
+}
+
+impl AppendLog {
+    /// Open existing log rooted at the given `MutableData`. `perms` are used
+    /// when new segments have to be created.
+    pub fn new(root: MDataInfo, perms: BTreeMap<User, PermissionSet>) -> Self {
+        AppendLog { root, perms }
+    }
+
+    /// Open existing log rooted at the given `MutableData`, using the
+    /// permissions of the root for the new segments.
+    pub fn open<T: 'static>(client: &Client<T>, root: MDataInfo) -> Box<CoreFuture<Self>> {
+        client
+            .list_mdata_permissions(root.name, root.type_tag)
+            .map(move |perms| AppendLog::new(root, perms))
+            .into_box()
+    }
+
+    /// `MDataInfo` of the first segment of this log.
+    pub fn root(&self) -> &MDataInfo {
+        &self.root
+    }
+
+    /// Create new empty log.
+    pub fn create<T: 'static>(
+        client: &Client<T>,
+        root: MDataInfo,
+        perms: BTreeMap<User, PermissionSet>,
+    ) -> Box<CoreFuture<Self>> {
+        let log = AppendLog::new(root.clone(), perms);
+        put_segment(client, &root, &log.perms)
+            .map(move |_| log)
+            .into_box()
+    }
+
+    /// Append the item to the end of the log and return its index.
+    pub fn append<T: 'static>(&self, client: &Client<T>, item: Vec<u8>) -> Box<CoreFuture<u64>> {
+        let log = self.clone();
+        let client = client.clone();
+
+        future::loop_fn(0, move |attempts| {
+            let log2 = log.clone();
+            let client2 = client.clone();
+            let item = item.clone();
+
+            log.last_segment(&client)
+                .and_then(move |(segment, offset)| if segment.len < SEGMENT_LEN {
+                    let index = offset + segment.len;
+                    let actions = btree_map![
+                        LEN_KEY.to_vec() => EntryAction::Update(Value {
+                            content: encode_len(segment.len + 1),
+                            entry_version: segment.len_version + 1,
+                        }),
+                        item_key(segment.len) => EntryAction::Ins(Value {
+                            content: item,
+                            entry_version: 0,
+                        })
+                    ];
+
+                    mutate(&client2, &segment.info, actions)
+                        .map(move |_| Loop::Break(index))
+                        .into_box()
+                } else {
+                    log2.link_segment(&client2, &segment.info)
+                        .map(|_| Loop::Continue(()))
+                        .into_box()
+                })
+                .then(move |res| match res {
+                    Ok(Loop::Break(index)) => Ok(Loop::Break(index)),
+                    Ok(Loop::Continue(())) => Ok(Loop::Continue(attempts)),
+                    Err(CoreError::RoutingClientError(ClientError::InvalidEntryActions(_)))
+                        if attempts < MAX_ATTEMPTS => Ok(Loop::Continue(attempts + 1)),
+                    Err(error) => Err(error),
+                })
+        }).into_box()
+    }
+
+    /// Number of items in the log.
+    pub fn len<T: 'static>(&self, client: &Client<T>) -> Box<CoreFuture<u64>> {
+        self.last_segment(client)
+            .map(|(segment, offset)| offset + segment.len)
+            .into_box()
+    }
+
+    /// Read the items in the given range of indices. The range is truncated to
+    /// the current length of the log.
+    pub fn read<T: 'static>(
+        &self,
+        client: &Client<T>,
+        range: Range<u64>,
+    ) -> Box<CoreFuture<Vec<Vec<u8>>>> {
+        let client = client.clone();
+        let state = (self.root.clone(), 0, Vec::new());
+
+        future::loop_fn(state, move |(info, offset, mut items)| {
+            let range = range.clone();
+            let end = offset + SEGMENT_LEN;
+
+            if range.end <= offset || range.start >= range.end {
+                return ok!(Loop::Break(items));
+            }
+
+            client
+                .list_mdata_entries(info.name, info.type_tag)
+                .and_then(move |entries| {
+                    let entries = mdata_info::decrypt_entries(&info, &entries)?;
+
+                    for index in cmp::max(range.start, offset)..cmp::min(range.end, end) {
+                        match entries.get(&item_key(index - offset)) {
+                            Some(value) => items.push(value.content.clone()),
+                            None => return Ok(Loop::Break(items)),
+                        }
+                    }
+
+                    match entries.get(NEXT_KEY) {
+                        Some(value) => {
+                            Ok(Loop::Continue((deserialise(&value.content)?, end, items)))
+                        }
+                        None => Ok(Loop::Break(items)),
+                    }
+                })
+                .into_box()
+        }).into_box()
+    }
+
+    /// Read all the items starting at the given index.
+    pub fn read_from<T: 'static>(
+        &self,
+        client: &Client<T>,
+        index: u64,
+    ) -> Box<CoreFuture<Vec<Vec<u8>>>> {
+        self.read(client, index..u64::max_value())
+    }
+
+    // Walk the chain of segments and return the last one together with the
+    // index of its first item.
+    fn last_segment<T: 'static>(&self, client: &Client<T>) -> Box<CoreFuture<(Segment, u64)>> {
+        let client = client.clone();
+
+        future::loop_fn((self.root.clone(), 0), move |(info, offset)| {
+            let keys = fry!(encrypt_keys(&info, &[LEN_KEY, NEXT_KEY]));
+            let client2 = client.clone();
+
+            client
+                .get_mdata_value(info.name, info.type_tag, keys[0].clone())
+                .and_then(move |len| {
+                    let len_version = len.entry_version;
+                    let len = decode_len(&info.decrypt(&len.content)?)?;
+
+                    Ok(Segment {
+                        info,
+                        len,
+                        len_version,
+                    })
+                })
+                .and_then(move |segment| {
+                    let info = segment.info.clone();
+
+                    client2
+                        .get_mdata_value(info.name, info.type_tag, keys[1].clone())
+                        .then(move |res| match res {
+                            Ok(next) => {
+                                let next = info.decrypt(&next.content)?;
+                                Ok(Loop::Continue((deserialise(&next)?, offset + SEGMENT_LEN)))
+                            }
+                            Err(CoreError::RoutingClientError(ClientError::NoSuchEntry)) => {
+                                Ok(Loop::Break((segment, offset)))
+                            }
+                            Err(error) => Err(error),
+                        })
+                })
+                .into_box()
+        }).into_box()
+    }
+
+    // Create new segment and link it from the given full segment. If another
+    // writer links its own segment first, the new one is left unused.
+    fn link_segment<T: 'static>(
+        &self,
+        client: &Client<T>,
+        info: &MDataInfo,
+    ) -> Box<CoreFuture<()>> {
+        let next = fry!(if self.root.enc_info.is_some() {
+            MDataInfo::random_private(self.root.type_tag)
+        } else {
+            MDataInfo::random_public(self.root.type_tag)
+        });
+        let content = fry!(serialise(&next));
+        let actions = btree_map![
+            NEXT_KEY.to_vec() => EntryAction::Ins(Value {
+                content,
+                entry_version: 0,
+            })
+        ];
+
+        let client2 = client.clone();
+        let info = info.clone();
+
+        put_segment(client, &next, &self.perms)
+            .and_then(move |_| mutate(&client2, &info, actions))
+            .into_box()
+    }
+}
+
+fn put_segment<T: 'static>(
+    client: &Client<T>,
+    info: &MDataInfo,
+    perms: &BTreeMap<User, PermissionSet>,
+) -> Box<CoreFuture<()>> {
+    let owners = btree_set![fry!(client.owner_key())];
+    let entries = btree_map![
+        LEN_KEY.to_vec() => Value {
+            content: encode_len(0),
+            entry_version: 0,
+        }
+    ];
+    let entries = fry!(mdata_info::encrypt_entries(info, &entries));
+    let data = fry!(MutableData::new(
+        info.name,
+        info.type_tag,
+        perms.clone(),
+        entries,
+        owners,
+    ));
+
+    client.put_mdata(data)
+}
+
+fn mutate<T: 'static>(
+    client: &Client<T>,
+    info: &MDataInfo,
+    actions: BTreeMap<Vec<u8>, EntryAction>,
+) -> Box<CoreFuture<()>> {
+    let actions = fry!(mdata_info::encrypt_entry_actions(info, &actions));
+    client.mutate_mdata_entries(info.name, info.type_tag, actions)
+}
+
+fn encrypt_keys(info: &MDataInfo, keys: &[&[u8]]) -> Result<Vec<Vec<u8>>, CoreError> {
+    keys.iter().map(|key| info.enc_entry_key(key)).collect()
+}
+
+fn item_key(index: u64) -> Vec<u8> {
+    (0..8).rev().map(|i| (index >> (i * 8)) as u8).collect()
+}
+
+fn encode_len(len: u64) -> Vec<u8> {
+    item_key(len)
+}
+
+fn decode_len(bytes: &[u8]) -> Result<u64, CoreError> {
+    if bytes.len() != 8 {
+        return Err(CoreError::ReceivedUnexpectedData);
+    }
+    Ok(bytes.iter().fold(0, |acc, byte| (acc << 8) | u64::from(*byte)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use DIR_TAG;
+    use routing::Action;
+    use utils::test_utils::random_client;
+
+    #[test]
+    fn len_encoding() {
+        for len in &[0, 1, 255, 256, SEGMENT_LEN, u64::max_value()] {
+            assert_eq!(unwrap!(decode_len(&encode_len(*len))), *len);
+        }
+        assert!(decode_len(&[1, 2, 3]).is_err());
+    }
+
+    // Append enough items to span multiple segments and read them back.
+    #[test]
+    fn append_and_read() {
+        random_client(|client| {
+            let client2 = client.clone();
+            let client3 = client.clone();
+            let client4 = client.clone();
+
+            let perms = btree_map![
+                User::Key(unwrap!(client.public_signing_key())) =>
+                    PermissionSet::new().allow(Action::Insert).allow(Action::Update)
+            ];
+            let root = unwrap!(MDataInfo::random_private(DIR_TAG));
+            let count = SEGMENT_LEN + 4;
+
+            AppendLog::create(client, root, perms)
+                .and_then(move |log| {
+                    future::loop_fn(0, move |index| {
+                        let log = log.clone();
+                        if index == count {
+                            return ok!(Loop::Break(log));
+                        }
+
+                        log.append(&client2, vec![index as u8])
+                            .map(move |appended| {
+                                assert_eq!(appended, index);
+                                Loop::Continue(index + 1)
+                            })
+                            .into_box()
+                    })
+                })
+                .and_then(move |log| {
+                    log.len(&client3).map(move |len| {
+                        assert_eq!(len, count);
+                        log
+                    })
+                })
+                .and_then(move |log| {
+                    log.read(&client4, SEGMENT_LEN - 2..count + 10)
+                        .map(move |items| {
+                            let expected: Vec<_> = (SEGMENT_LEN - 2..count)
+                                .map(|index| vec![index as u8])
+                                .collect();
+                            assert_eq!(items, expected);
+                        })
+                })
+        });
+    }
+
+    // Opened log picks up the permissions of the root and reads the tail.
+    #[test]
+    fn open_and_read_from() {
+        random_client(|client| {
+            let client2 = client.clone();
+            let client3 = client.clone();
+            let client4 = client.clone();
+
+            let perms = btree_map![
+                User::Key(unwrap!(client.public_signing_key())) =>
+                    PermissionSet::new().allow(Action::Insert).allow(Action::Update)
+            ];
+            let root = unwrap!(MDataInfo::random_public(DIR_TAG));
+            let root2 = root.clone();
+
+            AppendLog::create(client, root, perms.clone())
+                .and_then(move |log| {
+                    let appends = (0..3u8).map(|i| log.append(&client2, vec![i]));
+                    future::join_all(appends.collect::<Vec<_>>())
+                })
+                .and_then(move |_| AppendLog::open(&client3, root2))
+                .and_then(move |log| {
+                    assert_eq!(log.perms, perms);
+                    log.read_from(&client4, 1)
+                })
+                .map(|items| {
+                    assert_eq!(items.len(), 2);
+                })
+        });
+    }
+}
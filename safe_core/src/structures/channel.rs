@@ -0,0 +1,205 @@
+// Copyright 2017 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under (1) the MaidSafe.net Commercial License,
+// version 1.0 or later, or (2) The General Public License (GPL), version 3, depending on which
+// licence you accepted on initial access to the Software (the "Licences").
+//
+// By contributing code to the SAFE Network Software, or to this project generally, you agree to be
+// bound by the terms of the MaidSafe Contributor Agreement.  This, along with the Licenses can be
+// found in the root directory of this project at LICENSE, COPYING and CONTRIBUTOR.
+//
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.
+//
+// Please review the Licences for the specific language governing permissions and limitations
+// relating to use of the SAFE Network Software.
+
+//! Inbox-like messaging channel. Writers append messages encrypted to the
+//! recipient's public encryption key into a shared `AppendLog`; recipients
+//! poll the log for messages appended after their cursor.
+
+use client::{Client, MDataInfo};
+use crypto::shared_box;
+use event_loop::CoreFuture;
+use futures::{Future, future};
+use futures::future::Loop;
+use maidsafe_utilities::serialisation::{deserialise, serialise};
+use routing::{Action, PermissionSet, User};
+use rust_sodium::crypto::{box_, sealedbox};
+use std::collections::BTreeMap;
+use std::time::Duration;
+use std::u64;
+use structures::AppendLog;
+use utils::FutureExt;
+
+/// Messaging channel which anyone can write to.
+#[derive(Clone, Debug)]
+pub struct Channel {
+    log: AppendLog,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Envelope {
+    recipient: box_::PublicKey,
+    cipher_text: Vec<u8>,
+}
+
+impl Channel {
+    /// Open existing channel.
+    pub fn new(info: MDataInfo) -> Self {
+        Channel { log: AppendLog::new(info, channel_perms()) }
+    }
+
+    /// `MDataInfo` the channel is rooted at.
+    pub fn info(&self) -> &MDataInfo {
+        self.log.root()
+    }
+
+    /// Create new channel. `info` should be public, as the messages are
+    /// encrypted individually.
+    pub fn create<T: 'static>(client: &Client<T>, info: MDataInfo) -> Box<CoreFuture<Self>> {
+        AppendLog::create(client, info, channel_perms())
+            .map(|log| Channel { log })
+            .into_box()
+    }
+
+    /// Send message to the owner of the given public encryption key. Returns
+    /// the position of the message in the channel.
+    pub fn send<T: 'static>(
+        &self,
+        client: &Client<T>,
+        recipient: &box_::PublicKey,
+        message: &[u8],
+    ) -> Box<CoreFuture<u64>> {
+        let envelope = Envelope {
+            recipient: *recipient,
+            cipher_text: sealedbox::seal(message, recipient),
+        };
+
+        self.log.append(client, fry!(serialise(&envelope)))
+    }
+
+    /// Fetch the messages addressed to the owner of the given key pair which
+    /// were sent at or after `cursor`. Returns the messages together with the
+    /// cursor to use for the next poll.
+    pub fn poll<T: 'static>(
+        &self,
+        client: &Client<T>,
+        pk: box_::PublicKey,
+        sk: shared_box::SecretKey,
+        cursor: u64,
+    ) -> Box<CoreFuture<(Vec<Vec<u8>>, u64)>> {
+        self.log
+            .read(client, cursor..u64::MAX)
+            .map(move |items| {
+                let next_cursor = cursor + items.len() as u64;
+                let messages = items
+                    .into_iter()
+                    .filter_map(|item| open_envelope(&item, &pk, &sk))
+                    .collect();
+
+                (messages, next_cursor)
+            })
+            .into_box()
+    }
+
+    /// Like `poll`, but if there are no new messages, keeps polling every
+    /// `interval` until some arrive or `max_polls` polls have been made.
+    pub fn wait<T: 'static>(
+        &self,
+        client: &Client<T>,
+        pk: box_::PublicKey,
+        sk: shared_box::SecretKey,
+        cursor: u64,
+        interval: Duration,
+        max_polls: usize,
+    ) -> Box<CoreFuture<(Vec<Vec<u8>>, u64)>> {
+        let channel = self.clone();
+        let client = client.clone();
+
+        future::loop_fn((1, cursor), move |(polls, cursor)| {
+            let client2 = client.clone();
+
+            channel
+                .poll(&client, pk, sk.clone(), cursor)
+                .and_then(move |(messages, cursor)| {
+                    if messages.is_empty() && polls < max_polls {
+                        client2
+                            .delay(interval)
+                            .map(move |_| Loop::Continue((polls + 1, cursor)))
+                            .into_box()
+                    } else {
+                        ok!(Loop::Break((messages, cursor)))
+                    }
+                })
+        }).into_box()
+    }
+}
+
+fn channel_perms() -> BTreeMap<User, PermissionSet> {
+    btree_map![
+        User::Anyone => PermissionSet::new().allow(Action::Insert).allow(Action::Update)
+    ]
+}
+
+// Returns `None` for messages addressed to someone else or which can't be
+// decrypted.
+fn open_envelope(item: &[u8], pk: &box_::PublicKey, sk: &box_::SecretKey) -> Option<Vec<u8>> {
+    match deserialise::<Envelope>(item) {
+        Ok(ref envelope) if envelope.recipient == *pk => {
+            sealedbox::open(&envelope.cipher_text, pk, sk).ok()
+        }
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use DIR_TAG;
+    use utils::test_utils::random_client;
+
+    // Messages can be read only by their recipient.
+    #[test]
+    fn send_and_poll() {
+        random_client(|client| {
+            let client2 = client.clone();
+            let client3 = client.clone();
+            let client4 = client.clone();
+
+            let (pk, sk) = unwrap!(client.encryption_keypair());
+            let (other_pk, _) = box_::gen_keypair();
+            let info = unwrap!(MDataInfo::random_public(DIR_TAG));
+
+            Channel::create(client, info)
+                .and_then(move |channel| {
+                    let sends = vec![
+                        channel.send(&client2, &pk, b"first"),
+                        channel.send(&client2, &other_pk, b"private"),
+                        channel.send(&client2, &pk, b"second"),
+                    ];
+                    future::join_all(sends).map(move |_| channel)
+                })
+                .and_then(move |channel| {
+                    let sk2 = sk.clone();
+                    channel.poll(&client3, pk, sk, 0).map(move |(messages, cursor)| {
+                        assert_eq!(messages.len(), 2);
+                        assert!(messages.contains(&b"first".to_vec()));
+                        assert!(messages.contains(&b"second".to_vec()));
+                        assert_eq!(cursor, 3);
+                        (channel, sk2, cursor)
+                    })
+                })
+                .and_then(move |(channel, sk, cursor)| {
+                    let interval = Duration::from_millis(10);
+                    channel.wait(&client4, pk, sk, cursor, interval, 3).map(
+                        move |(messages, next_cursor)| {
+                            assert!(messages.is_empty());
+                            assert_eq!(next_cursor, cursor);
+                        },
+                    )
+                })
+        });
+    }
+}
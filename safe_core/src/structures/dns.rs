@@ -0,0 +1,236 @@
+// Copyright 2017 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under (1) the MaidSafe.net Commercial License,
+// version 1.0 or later, or (2) The General Public License (GPL), version 3, depending on which
+// licence you accepted on initial access to the Software (the "Licences").
+//
+// By contributing code to the SAFE Network Software, or to this project generally, you agree to be
+// bound by the terms of the MaidSafe Contributor Agreement.  This, along with the Licenses can be
+// found in the root directory of this project at LICENSE, COPYING and CONTRIBUTOR.
+//
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.
+//
+// Please review the Licences for the specific language governing permissions and limitations
+// relating to use of the SAFE Network Software.
+
+//! Public names (DNS). A public name is a public `MutableData` whose name is
+//! derived from the human readable name, so anyone who knows it can find the
+//! `MutableData`. Its entries map service names (e.g. `www` or `blog`) to the
+//! NFS directories serving them, which is what website publishing is built
+//! on.
+//!
+//! The `MDataInfo` of a service directory is published in plain, so the
+//! directory should be public.
+
+use client::{Client, MDataInfo};
+use errors::CoreError;
+use event_loop::CoreFuture;
+use futures::Future;
+use maidsafe_utilities::serialisation::{deserialise, serialise};
+use routing::{Action, ClientError, EntryActions, MutableData, PermissionSet, User, Value, XorName};
+use std::collections::BTreeMap;
+use tiny_keccak::sha3_256;
+use utils::FutureExt;
+
+/// Type tag of the public name `MutableData`.
+pub const DNS_TAG: u64 = 15_007;
+
+/// Name of the `MutableData` of the given public name.
+pub fn public_name_address(public_name: &str) -> XorName {
+    XorName(sha3_256(public_name.as_bytes()))
+}
+
+/// Register the public name, owned by the client's account, with the given
+/// services. Fails if the name is already taken.
+pub fn register<T: 'static>(
+    client: &Client<T>,
+    public_name: &str,
+    services: BTreeMap<String, MDataInfo>,
+) -> Box<CoreFuture<()>> {
+    let owner_key = fry!(client.owner_key());
+    // The registering key might belong to an app rather than the owner, so it
+    // needs permissions to manage the services later.
+    let perms = btree_map![
+        User::Key(fry!(client.public_signing_key())) => PermissionSet::new()
+            .allow(Action::Insert)
+            .allow(Action::Update)
+            .allow(Action::Delete)
+    ];
+    let mut entries = BTreeMap::new();
+
+    for (service, dir) in services {
+        let _ = entries.insert(
+            service.into_bytes(),
+            Value {
+                content: fry!(serialise(&dir)),
+                entry_version: 0,
+            },
+        );
+    }
+
+    let data = fry!(MutableData::new(
+        public_name_address(public_name),
+        DNS_TAG,
+        perms,
+        entries,
+        btree_set![owner_key],
+    ));
+
+    client.put_mdata(data)
+}
+
+/// Map the service of the public name to the directory, replacing the
+/// existing mapping if any. Only the owner of the name can do this.
+pub fn add_service<T: 'static>(
+    client: &Client<T>,
+    public_name: &str,
+    service: &str,
+    dir: &MDataInfo,
+) -> Box<CoreFuture<()>> {
+    let name = public_name_address(public_name);
+    let key = service.as_bytes().to_vec();
+    let content = fry!(serialise(dir));
+    let client2 = client.clone();
+
+    client
+        .get_mdata_value(name, DNS_TAG, key.clone())
+        .then(move |res| {
+            let actions = match res {
+                Ok(value) => EntryActions::new().update(key, content, value.entry_version + 1),
+                Err(CoreError::RoutingClientError(ClientError::NoSuchEntry)) => {
+                    EntryActions::new().ins(key, content, 0)
+                }
+                Err(err) => return err!(err),
+            };
+            client2.mutate_mdata_entries(name, DNS_TAG, actions.into())
+        })
+        .into_box()
+}
+
+/// Remove the service of the public name. Only the owner of the name can do
+/// this.
+pub fn remove_service<T: 'static>(
+    client: &Client<T>,
+    public_name: &str,
+    service: &str,
+) -> Box<CoreFuture<()>> {
+    let name = public_name_address(public_name);
+    let key = service.as_bytes().to_vec();
+    let client2 = client.clone();
+
+    client
+        .get_mdata_value(name, DNS_TAG, key.clone())
+        .and_then(move |value| {
+            if value.content.is_empty() {
+                return err!(CoreError::RoutingClientError(ClientError::NoSuchEntry));
+            }
+            let actions = EntryActions::new().del(key, value.entry_version + 1);
+            client2.mutate_mdata_entries(name, DNS_TAG, actions.into())
+        })
+        .into_box()
+}
+
+/// Get the directory serving the service of the public name.
+pub fn lookup<T: 'static>(
+    client: &Client<T>,
+    public_name: &str,
+    service: &str,
+) -> Box<CoreFuture<MDataInfo>> {
+    client
+        .get_mdata_value(
+            public_name_address(public_name),
+            DNS_TAG,
+            service.as_bytes().to_vec(),
+        )
+        .and_then(|value| {
+            if value.content.is_empty() {
+                Err(CoreError::RoutingClientError(ClientError::NoSuchEntry))
+            } else {
+                Ok(deserialise(&value.content)?)
+            }
+        })
+        .into_box()
+}
+
+/// List the services of the public name.
+pub fn list_services<T: 'static>(
+    client: &Client<T>,
+    public_name: &str,
+) -> Box<CoreFuture<Vec<String>>> {
+    client
+        .list_mdata_entries(public_name_address(public_name), DNS_TAG)
+        .map(|entries| {
+            entries
+                .into_iter()
+                .filter(|&(_, ref value)| !value.content.is_empty())
+                .filter_map(|(key, _)| String::from_utf8(key).ok())
+                .collect()
+        })
+        .into_box()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use DIR_TAG;
+    use utils;
+    use utils::test_utils::random_client;
+
+    // Register a public name, map services to directories and look them up.
+    #[test]
+    fn register_and_lookup() {
+        random_client(|client| {
+            let client2 = client.clone();
+            let client3 = client.clone();
+            let client4 = client.clone();
+            let client5 = client.clone();
+            let client6 = client.clone();
+
+            let name = unwrap!(utils::generate_random_string(10));
+            let name2 = name.clone();
+            let name3 = name.clone();
+            let name4 = name.clone();
+            let name5 = name.clone();
+
+            let www = unwrap!(MDataInfo::random_public(DIR_TAG));
+            let blog = unwrap!(MDataInfo::random_public(DIR_TAG));
+            let blog2 = blog.clone();
+
+            register(client, &name, btree_map!["www".to_string() => www.clone()])
+                .and_then(move |_| {
+                    register(&client2, &name2, Default::default()).then(|res| {
+                        match res {
+                            Err(CoreError::RoutingClientError(ClientError::DataExists)) => (),
+                            res => panic!("Unexpected {:?}", res),
+                        }
+                        Ok::<_, CoreError>(())
+                    })
+                })
+                .and_then(move |_| add_service(&client3, &name3, "blog", &blog))
+                .and_then(move |_| {
+                    let www_dir = lookup(&client4, &name4, "www");
+                    let blog_dir = lookup(&client4, &name4, "blog");
+                    www_dir.join(blog_dir).map(move |(www_dir, blog_dir)| {
+                        assert_eq!(www_dir, www);
+                        assert_eq!(blog_dir, blog2);
+                    })
+                })
+                .and_then(move |_| remove_service(&client5, &name5, "www"))
+                .and_then(move |_| {
+                    let services = list_services(&client6, &name);
+                    let www_dir = lookup(&client6, &name, "www").then(|res| {
+                        match res {
+                            Err(CoreError::RoutingClientError(ClientError::NoSuchEntry)) => (),
+                            res => panic!("Unexpected {:?}", res),
+                        }
+                        Ok::<_, CoreError>(())
+                    });
+                    services.join(www_dir).map(|(services, _)| {
+                        assert_eq!(services, vec!["blog".to_string()]);
+                    })
+                })
+        });
+    }
+}
@@ -0,0 +1,418 @@
+// Copyright 2017 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under (1) the MaidSafe.net Commercial License,
+// version 1.0 or later, or (2) The General Public License (GPL), version 3, depending on which
+// licence you accepted on initial access to the Software (the "Licences").
+//
+// By contributing code to the SAFE Network Software, or to this project generally, you agree to be
+// bound by the terms of the MaidSafe Contributor Agreement.  This, along with the Licenses can be
+// found in the root directory of this project at LICENSE, COPYING and CONTRIBUTOR.
+//
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.
+//
+// Please review the Licences for the specific language governing permissions and limitations
+// relating to use of the SAFE Network Software.
+
+//! Container shared among a group of users. The data itself lives in an
+//! encrypted `MutableData`. Its `MDataInfo` (including the encryption key) is
+//! sealed to every member's public encryption key and stored in a separate,
+//! public members `MutableData` keyed by the member's public signing key.
+//! Removing a member rotates the encryption key.
+
+use client::{Client, MDataInfo, recovery};
+use errors::CoreError;
+use event_loop::CoreFuture;
+use futures::{Future, future};
+use maidsafe_utilities::serialisation::{deserialise, serialise};
+use rand;
+use routing::{Action, ClientError, EntryAction, EntryActions, MutableData, PermissionSet, User,
+              Value, XorName};
+use rust_sodium::crypto::{box_, sealedbox, sign};
+use std::collections::BTreeMap;
+use utils::FutureExt;
+
+/// Member of a shared container.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct Member {
+    /// Public signing key the member's mutations are authorised by
+    pub sign_pk: sign::PublicKey,
+    /// Public encryption key the container key is sealed to
+    pub enc_pk: box_::PublicKey,
+}
+
+#[derive(Serialize, Deserialize)]
+struct MemberEntry {
+    enc_pk: box_::PublicKey,
+    sealed_info: Vec<u8>,
+}
+
+/// Container shared among a group of users.
+#[derive(Clone, Debug)]
+pub struct SharedContainer {
+    data: MDataInfo,
+    members: XorName,
+}
+
+impl SharedContainer {
+    /// Construct from already known `MDataInfo` of the shared data and the
+    /// name of the members `MutableData`.
+    pub fn new(data: MDataInfo, members: XorName) -> Self {
+        SharedContainer { data, members }
+    }
+
+    /// Create new shared container with the client as its owner and only member.
+    pub fn create<T: 'static>(client: &Client<T>, type_tag: u64) -> Box<CoreFuture<Self>> {
+        let data = fry!(MDataInfo::random_private(type_tag));
+        let members: XorName = rand::random();
+        let owners = btree_set![fry!(client.owner_key())];
+
+        let owner = Member {
+            sign_pk: fry!(client.public_signing_key()),
+            enc_pk: fry!(client.public_encryption_key()),
+        };
+        let entry = MemberEntry {
+            enc_pk: owner.enc_pk,
+            sealed_info: fry!(seal_info(&data, &owner.enc_pk)),
+        };
+        let entries = btree_map![
+            owner.sign_pk.0.to_vec() => Value {
+                content: fry!(serialise(&entry)),
+                entry_version: 0,
+            }
+        ];
+
+        // The creator might be an app, so it needs explicit permissions to
+        // modify the data and to manage the members.
+        let perms = btree_map![
+            User::Key(owner.sign_pk) => member_permissions().allow(Action::ManagePermissions)
+        ];
+
+        let data_md = fry!(MutableData::new(
+            data.name,
+            data.type_tag,
+            perms.clone(),
+            Default::default(),
+            owners.clone(),
+        ));
+        let members_md = fry!(MutableData::new(
+            members,
+            type_tag,
+            perms,
+            entries,
+            owners,
+        ));
+
+        let container = SharedContainer { data, members };
+
+        client
+            .put_mdata(data_md)
+            .join(client.put_mdata(members_md))
+            .map(move |_| container)
+            .into_box()
+    }
+
+    /// Open shared container the client is a member of, given the name of its
+    /// members `MutableData`.
+    pub fn open<T: 'static>(
+        client: &Client<T>,
+        members: XorName,
+        type_tag: u64,
+    ) -> Box<CoreFuture<Self>> {
+        let sign_pk = fry!(client.public_signing_key());
+        let (enc_pk, enc_sk) = fry!(client.encryption_keypair());
+
+        client
+            .get_mdata_value(members, type_tag, sign_pk.0.to_vec())
+            .and_then(move |value| {
+                if value.content.is_empty() {
+                    return Err(CoreError::RoutingClientError(ClientError::NoSuchEntry));
+                }
+                let entry: MemberEntry = deserialise(&value.content)?;
+                let info = sealedbox::open(&entry.sealed_info, &enc_pk, &enc_sk)
+                    .map_err(|_| CoreError::AsymmetricDecipherFailure)?;
+                let data = deserialise(&info)?;
+
+                Ok(SharedContainer { data, members })
+            })
+            .into_box()
+    }
+
+    /// `MDataInfo` of the shared data.
+    pub fn data(&self) -> &MDataInfo {
+        &self.data
+    }
+
+    /// Name of the members `MutableData`. Together with the type tag, this is
+    /// what members need to open the container.
+    pub fn members_name(&self) -> XorName {
+        self.members
+    }
+
+    /// List current members.
+    pub fn members<T: 'static>(&self, client: &Client<T>) -> Box<CoreFuture<Vec<Member>>> {
+        client
+            .list_mdata_entries(self.members, self.data.type_tag)
+            .and_then(|entries| {
+                let mut members = Vec::new();
+
+                for (key, value) in entries {
+                    if value.content.is_empty() || key.len() != sign::PUBLICKEYBYTES {
+                        continue;
+                    }
+                    let mut sign_pk = [0; sign::PUBLICKEYBYTES];
+                    sign_pk.copy_from_slice(&key);
+                    let entry: MemberEntry = deserialise(&value.content)?;
+
+                    members.push(Member {
+                        sign_pk: sign::PublicKey(sign_pk),
+                        enc_pk: entry.enc_pk,
+                    });
+                }
+
+                Ok(members)
+            })
+            .into_box()
+    }
+
+    /// Add new member, granting them permission to modify the shared data.
+    /// Only the owner can manage the members.
+    pub fn add_member<T: 'static>(&self, client: &Client<T>, member: Member) -> Box<CoreFuture<()>> {
+        let entry = MemberEntry {
+            enc_pk: member.enc_pk,
+            sealed_info: fry!(seal_info(&self.data, &member.enc_pk)),
+        };
+        let actions = btree_map![
+            member.sign_pk.0.to_vec() => EntryAction::Ins(Value {
+                content: fry!(serialise(&entry)),
+                entry_version: 0,
+            })
+        ];
+
+        let (name, type_tag) = (self.data.name, self.data.type_tag);
+        let client2 = client.clone();
+        let client3 = client.clone();
+
+        client
+            .mutate_mdata_entries(self.members, type_tag, actions)
+            .and_then(move |_| client2.get_mdata_version(name, type_tag))
+            .and_then(move |version| {
+                recovery::set_mdata_user_permissions(
+                    &client3,
+                    name,
+                    type_tag,
+                    User::Key(member.sign_pk),
+                    member_permissions(),
+                    version + 1,
+                )
+            })
+            .into_box()
+    }
+
+    /// Remove member, revoking their permissions and rotating the encryption
+    /// key of the shared data so that future changes remain hidden from them.
+    /// Returns the container with the new key.
+    pub fn remove_member<T: 'static>(
+        &self,
+        client: &Client<T>,
+        sign_pk: sign::PublicKey,
+    ) -> Box<CoreFuture<Self>> {
+        let mut container = self.clone();
+        container.data.start_new_enc_info();
+
+        let client2 = client.clone();
+        let client3 = client.clone();
+        let client4 = client.clone();
+        let data = self.data.clone();
+
+        client
+            .get_mdata_version(data.name, data.type_tag)
+            .and_then(move |version| {
+                recovery::del_mdata_user_permissions(
+                    &client2,
+                    data.name,
+                    data.type_tag,
+                    User::Key(sign_pk),
+                    version + 1,
+                )
+            })
+            .and_then(move |_| reencrypt(&client3, container))
+            .and_then(move |container| {
+                container.reseal(&client4, sign_pk).map(
+                    move |_| container,
+                )
+            })
+            .into_box()
+    }
+
+    // Replace the sealed infos of the remaining members with the current one
+    // and delete the removed member's entry.
+    fn reseal<T: 'static>(
+        &self,
+        client: &Client<T>,
+        removed: sign::PublicKey,
+    ) -> Box<CoreFuture<()>> {
+        let data = self.data.clone();
+        let members = self.members;
+        let client2 = client.clone();
+
+        client
+            .list_mdata_entries(members, data.type_tag)
+            .and_then(move |entries| {
+                let mut actions = EntryActions::new();
+
+                for (key, value) in entries {
+                    if value.content.is_empty() {
+                        continue;
+                    }
+                    if key == removed.0.to_vec() {
+                        actions = actions.del(key, value.entry_version + 1);
+                        continue;
+                    }
+
+                    let entry: MemberEntry = deserialise(&value.content)?;
+                    let entry = MemberEntry {
+                        enc_pk: entry.enc_pk,
+                        sealed_info: seal_info(&data, &entry.enc_pk)?,
+                    };
+                    actions = actions.update(key, serialise(&entry)?, value.entry_version + 1);
+                }
+
+                Ok((data, actions))
+            })
+            .and_then(move |(data, actions)| {
+                client2.mutate_mdata_entries(members, data.type_tag, actions.into())
+            })
+            .into_box()
+    }
+}
+
+// Re-encrypt all entries of the shared data with the new encryption info.
+fn reencrypt<T: 'static>(
+    client: &Client<T>,
+    mut container: SharedContainer,
+) -> Box<CoreFuture<SharedContainer>> {
+    let client2 = client.clone();
+
+    client
+        .list_mdata_entries(container.data.name, container.data.type_tag)
+        .and_then(move |entries| {
+            let actions = fry!(reencrypt_actions(&container.data, entries));
+            let (name, type_tag) = (container.data.name, container.data.type_tag);
+            container.data.commit_new_enc_info();
+
+            client2
+                .mutate_mdata_entries(name, type_tag, actions.into())
+                .map(move |_| container)
+                .into_box()
+        })
+        .into_box()
+}
+
+fn reencrypt_actions(
+    data: &MDataInfo,
+    entries: BTreeMap<Vec<u8>, Value>,
+) -> Result<EntryActions, CoreError> {
+    let mut actions = EntryActions::new();
+
+    for (old_key, value) in entries {
+        if value.content.is_empty() {
+            continue;
+        }
+
+        let plain_key = data.decrypt(&old_key)?;
+        let plain_content = data.decrypt(&value.content)?;
+        let new_key = data.enc_entry_key(&plain_key)?;
+        let new_content = data.enc_entry_value(&plain_content)?;
+
+        actions = actions.del(old_key, value.entry_version + 1).ins(
+            new_key,
+            new_content,
+            0,
+        );
+    }
+
+    Ok(actions)
+}
+
+fn seal_info(info: &MDataInfo, enc_pk: &box_::PublicKey) -> Result<Vec<u8>, CoreError> {
+    Ok(sealedbox::seal(&serialise(info)?, enc_pk))
+}
+
+fn member_permissions() -> PermissionSet {
+    PermissionSet::new()
+        .allow(Action::Insert)
+        .allow(Action::Update)
+        .allow(Action::Delete)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use DIR_TAG;
+    use utils::test_utils::random_client;
+
+    // Removing a member rotates the key, keeping the existing entries readable
+    // for the remaining members.
+    #[test]
+    fn add_and_remove_member() {
+        random_client(|client| {
+            let client2 = client.clone();
+            let client3 = client.clone();
+            let client4 = client.clone();
+            let client5 = client.clone();
+            let client6 = client.clone();
+            let client7 = client.clone();
+
+            let (sign_pk, _) = sign::gen_keypair();
+            let (enc_pk, _) = box_::gen_keypair();
+            let member = Member { sign_pk, enc_pk };
+
+            SharedContainer::create(client, DIR_TAG)
+                .and_then(move |container| {
+                    let data = container.data().clone();
+                    let actions = btree_map![
+                        unwrap!(data.enc_entry_key(b"key")) => EntryAction::Ins(Value {
+                            content: unwrap!(data.enc_entry_value(b"value")),
+                            entry_version: 0,
+                        })
+                    ];
+                    client2
+                        .mutate_mdata_entries(data.name, data.type_tag, actions)
+                        .map(move |_| container)
+                })
+                .and_then(move |container| {
+                    container.add_member(&client3, member).map(
+                        move |_| container,
+                    )
+                })
+                .and_then(move |container| {
+                    container.members(&client4).map(move |members| {
+                        assert_eq!(members.len(), 2);
+                        assert!(members.contains(&member));
+                        container
+                    })
+                })
+                .and_then(move |container| container.remove_member(&client5, sign_pk))
+                .and_then(move |container| {
+                    let members = container.members_name();
+                    let type_tag = container.data().type_tag;
+                    let old_key = container.data().enc_key().cloned();
+
+                    SharedContainer::open(&client6, members, type_tag).and_then(
+                        move |opened| {
+                            assert!(opened.data().enc_key() != old_key.as_ref());
+                            let key = unwrap!(opened.data().enc_entry_key(b"key"));
+                            client7
+                                .get_mdata_value(opened.data().name, type_tag, key)
+                                .map(move |value| {
+                                    let content = unwrap!(opened.data().decrypt(&value.content));
+                                    assert_eq!(content, b"value".to_vec());
+                                })
+                        },
+                    )
+                })
+        });
+    }
+}
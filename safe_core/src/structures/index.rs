@@ -0,0 +1,480 @@
+// Copyright 2017 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under (1) the MaidSafe.net Commercial License,
+// version 1.0 or later, or (2) The General Public License (GPL), version 3, depending on which
+// licence you accepted on initial access to the Software (the "Licences").
+//
+// By contributing code to the SAFE Network Software, or to this project generally, you agree to be
+// bound by the terms of the MaidSafe Contributor Agreement.  This, along with the Licenses can be
+// found in the root directory of this project at LICENSE, COPYING and CONTRIBUTOR.
+//
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.
+//
+// Please review the Licences for the specific language governing permissions and limitations
+// relating to use of the SAFE Network Software.
+
+//! Secondary indexes over a primary `MutableData`. Every index is a separate
+//! `MutableData` whose entries map the index keys, extracted from the primary
+//! entries by a user supplied function, to the primary keys. The indexes are
+//! updated together with every write to the primary data, and can be
+//! rebuilt from the primary data if they diverge.
+
+use client::{Client, MDataInfo, mdata_info};
+use errors::CoreError;
+use event_loop::CoreFuture;
+use futures::{Future, future};
+use routing::{ClientError, EntryAction, MutableData, Value};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::{self, Debug, Formatter};
+use std::rc::Rc;
+use utils::FutureExt;
+
+// Content of every live index entry. Empty content marks a deleted entry, so
+// the primary key can't be stored in the value.
+const INDEX_MARKER: &'static [u8] = &[1];
+
+/// Secondary index.
+#[derive(Clone)]
+pub struct Index {
+    info: MDataInfo,
+    extract: Rc<Fn(&[u8], &[u8]) -> Vec<Vec<u8>>>,
+}
+
+impl Index {
+    /// Create index stored in the `MutableData` described by `info`. `extract`
+    /// returns the index keys for the given primary key and value.
+    pub fn new<F>(info: MDataInfo, extract: F) -> Self
+    where
+        F: Fn(&[u8], &[u8]) -> Vec<Vec<u8>> + 'static,
+    {
+        Index {
+            info,
+            extract: Rc::new(extract),
+        }
+    }
+
+    /// `MDataInfo` of the index.
+    pub fn info(&self) -> &MDataInfo {
+        &self.info
+    }
+
+    fn entry_keys(&self, key: &[u8], value: Option<&[u8]>) -> BTreeSet<Vec<u8>> {
+        match value {
+            Some(value) => {
+                (self.extract)(key, value)
+                    .into_iter()
+                    .map(|index_key| encode_entry_key(&index_key, key))
+                    .collect()
+            }
+            None => BTreeSet::new(),
+        }
+    }
+}
+
+impl Debug for Index {
+    fn fmt(&self, formatter: &mut Formatter) -> fmt::Result {
+        write!(formatter, "Index {{ info: {:?} }}", self.info)
+    }
+}
+
+/// Primary `MutableData` with secondary indexes kept consistent with it.
+#[derive(Clone, Debug)]
+pub struct IndexedData {
+    primary: MDataInfo,
+    indexes: Vec<Index>,
+}
+
+impl IndexedData {
+    /// Wrap existing primary data and indexes.
+    pub fn new(primary: MDataInfo, indexes: Vec<Index>) -> Self {
+        IndexedData { primary, indexes }
+    }
+
+    /// Put new empty primary data and indexes to the network.
+    pub fn create<T: 'static>(
+        client: &Client<T>,
+        primary: MDataInfo,
+        indexes: Vec<Index>,
+    ) -> Box<CoreFuture<Self>> {
+        let owners = btree_set![fry!(client.owner_key())];
+        let mut puts = Vec::with_capacity(indexes.len() + 1);
+
+        for info in Some(&primary).into_iter().chain(
+            indexes.iter().map(|index| &index.info),
+        )
+        {
+            let data = fry!(MutableData::new(
+                info.name,
+                info.type_tag,
+                Default::default(),
+                Default::default(),
+                owners.clone(),
+            ));
+            puts.push(client.put_mdata(data));
+        }
+
+        future::join_all(puts)
+            .map(move |_| IndexedData::new(primary, indexes))
+            .into_box()
+    }
+
+    /// `MDataInfo` of the primary data.
+    pub fn primary(&self) -> &MDataInfo {
+        &self.primary
+    }
+
+    /// Insert or update the primary entry and update the indexes accordingly.
+    pub fn put<T: 'static>(
+        &self,
+        client: &Client<T>,
+        key: Vec<u8>,
+        value: Vec<u8>,
+    ) -> Box<CoreFuture<()>> {
+        self.write(client, key, Some(value))
+    }
+
+    /// Delete the primary entry and its index entries.
+    pub fn delete<T: 'static>(&self, client: &Client<T>, key: Vec<u8>) -> Box<CoreFuture<()>> {
+        self.write(client, key, None)
+    }
+
+    /// Return the primary keys indexed under `index_key` by the index at the
+    /// given position.
+    pub fn lookup<T: 'static>(
+        &self,
+        client: &Client<T>,
+        index: usize,
+        index_key: &[u8],
+    ) -> Box<CoreFuture<Vec<Vec<u8>>>> {
+        let info = match self.indexes.get(index) {
+            Some(index) => index.info.clone(),
+            None => return err!(CoreError::Unexpected(format!("No index {}", index))),
+        };
+        let index_key = index_key.to_vec();
+
+        fetch(client, &info)
+            .map(move |entries| {
+                entries
+                    .into_iter()
+                    .filter(|&(_, ref value)| !value.content.is_empty())
+                    .filter_map(|(key, _)| decode_entry_key(&key))
+                    .filter(|&(ref key, _)| *key == index_key)
+                    .map(|(_, primary_key)| primary_key)
+                    .collect()
+            })
+            .into_box()
+    }
+
+    /// Rebuild the indexes from the primary data, adding missing and deleting
+    /// stale index entries. Returns the number of repaired entries.
+    pub fn repair<T: 'static>(&self, client: &Client<T>) -> Box<CoreFuture<usize>> {
+        let indexes = self.indexes.clone();
+        let client2 = client.clone();
+
+        fetch(client, &self.primary)
+            .and_then(move |primary| {
+                let fs = indexes.into_iter().map(move |index| {
+                    let mut expected = BTreeSet::new();
+                    for (key, value) in &primary {
+                        if !value.content.is_empty() {
+                            expected.extend(index.entry_keys(key, Some(&value.content)));
+                        }
+                    }
+
+                    let client3 = client2.clone();
+
+                    let existing = fetch(&client2, &index.info);
+                    existing.and_then(move |existing| {
+                        let stale = existing
+                            .iter()
+                            .filter(|&(key, value)| {
+                                !value.content.is_empty() && !expected.contains(key)
+                            })
+                            .map(|(key, _)| key.clone())
+                            .collect();
+                        let actions = sync_actions(&existing, expected, stale);
+                        let count = actions.len();
+
+                        mutate(&client3, &index.info, actions).map(move |_| count)
+                    })
+                });
+
+                future::join_all(fs)
+            })
+            .map(|counts| counts.into_iter().sum())
+            .into_box()
+    }
+
+    fn write<T: 'static>(
+        &self,
+        client: &Client<T>,
+        key: Vec<u8>,
+        value: Option<Vec<u8>>,
+    ) -> Box<CoreFuture<()>> {
+        let primary = self.primary.clone();
+        let indexes = self.indexes.clone();
+        let client2 = client.clone();
+        let client3 = client.clone();
+
+        get_value(client, &primary, &key)
+            .and_then(move |current| {
+                let action = match (current.as_ref(), value.clone()) {
+                    (Some(current), Some(content)) => {
+                        EntryAction::Update(Value {
+                            content,
+                            entry_version: current.entry_version + 1,
+                        })
+                    }
+                    (None, Some(content)) => {
+                        EntryAction::Ins(Value {
+                            content,
+                            entry_version: 0,
+                        })
+                    }
+                    (Some(current), None) if !current.content.is_empty() => {
+                        EntryAction::Del(current.entry_version + 1)
+                    }
+                    _ => return ok!(()),
+                };
+                let old_value = current.and_then(|value| if value.content.is_empty() {
+                    None
+                } else {
+                    Some(value.content)
+                });
+
+                let client4 = client3.clone();
+                mutate(&client2, &primary, btree_map![key.clone() => action])
+                    .and_then(move |_| {
+                        let fs = indexes.into_iter().map(move |index| {
+                            let old = index.entry_keys(&key, old_value.as_ref().map(|v| &v[..]));
+                            let new = index.entry_keys(&key, value.as_ref().map(|v| &v[..]));
+                            update_index(&client4, index.info, &old, new)
+                        });
+                        future::join_all(fs).map(|_| ())
+                    })
+                    .into_box()
+            })
+            .into_box()
+    }
+}
+
+// Bring the index entries of a single primary entry from `old` to `new`.
+fn update_index<T: 'static>(
+    client: &Client<T>,
+    info: MDataInfo,
+    old: &BTreeSet<Vec<u8>>,
+    new: BTreeSet<Vec<u8>>,
+) -> Box<CoreFuture<()>> {
+    let removed: BTreeSet<_> = old.difference(&new).cloned().collect();
+    let affected: Vec<_> = removed.iter().chain(new.iter()).cloned().collect();
+    let client2 = client.clone();
+
+    let mut fs = Vec::with_capacity(affected.len());
+    for key in affected {
+        let value = get_value(client, &info, &key);
+        fs.push(value.map(move |value| (key, value)));
+    }
+
+    future::join_all(fs)
+        .and_then(move |values| {
+            let existing = values
+                .into_iter()
+                .filter_map(|(key, value)| value.map(|value| (key, value)))
+                .collect();
+            let actions = sync_actions(&existing, new, removed);
+            mutate(&client2, &info, actions)
+        })
+        .into_box()
+}
+
+// Entry actions making `add` present and `remove` absent, given the existing
+// entries (including the deleted ones).
+fn sync_actions(
+    existing: &BTreeMap<Vec<u8>, Value>,
+    add: BTreeSet<Vec<u8>>,
+    remove: BTreeSet<Vec<u8>>,
+) -> BTreeMap<Vec<u8>, EntryAction> {
+    let mut actions = BTreeMap::new();
+
+    for key in add {
+        let action = match existing.get(&key) {
+            Some(value) if value.content.is_empty() => {
+                EntryAction::Update(Value {
+                    content: INDEX_MARKER.to_vec(),
+                    entry_version: value.entry_version + 1,
+                })
+            }
+            Some(_) => continue,
+            None => {
+                EntryAction::Ins(Value {
+                    content: INDEX_MARKER.to_vec(),
+                    entry_version: 0,
+                })
+            }
+        };
+        let _ = actions.insert(key, action);
+    }
+
+    for key in remove {
+        if let Some(value) = existing.get(&key) {
+            if !value.content.is_empty() {
+                let _ = actions.insert(key, EntryAction::Del(value.entry_version + 1));
+            }
+        }
+    }
+
+    actions
+}
+
+fn fetch<T: 'static>(
+    client: &Client<T>,
+    info: &MDataInfo,
+) -> Box<CoreFuture<BTreeMap<Vec<u8>, Value>>> {
+    let info = info.clone();
+
+    client
+        .list_mdata_entries(info.name, info.type_tag)
+        .and_then(move |entries| mdata_info::decrypt_entries_with_deleted(&info, &entries))
+        .into_box()
+}
+
+// Fetch the decrypted entry, or `None` if it doesn't exist.
+fn get_value<T: 'static>(
+    client: &Client<T>,
+    info: &MDataInfo,
+    key: &[u8],
+) -> Box<CoreFuture<Option<Value>>> {
+    let info = info.clone();
+    let enc_key = fry!(info.enc_entry_key(key));
+
+    client
+        .get_mdata_value(info.name, info.type_tag, enc_key)
+        .then(move |res| match res {
+            Ok(value) => {
+                let content = if value.content.is_empty() {
+                    value.content
+                } else {
+                    info.decrypt(&value.content)?
+                };
+                Ok(Some(Value {
+                    content,
+                    entry_version: value.entry_version,
+                }))
+            }
+            Err(CoreError::RoutingClientError(ClientError::NoSuchEntry)) => Ok(None),
+            Err(error) => Err(error),
+        })
+        .into_box()
+}
+
+fn mutate<T: 'static>(
+    client: &Client<T>,
+    info: &MDataInfo,
+    actions: BTreeMap<Vec<u8>, EntryAction>,
+) -> Box<CoreFuture<()>> {
+    if actions.is_empty() {
+        return ok!(());
+    }
+
+    let actions = fry!(mdata_info::encrypt_entry_actions(info, &actions));
+    client.mutate_mdata_entries(info.name, info.type_tag, actions)
+}
+
+fn encode_entry_key(index_key: &[u8], primary_key: &[u8]) -> Vec<u8> {
+    let len = index_key.len() as u32;
+    let mut key: Vec<u8> = (0..4).rev().map(|i| (len >> (i * 8)) as u8).collect();
+    key.extend_from_slice(index_key);
+    key.extend_from_slice(primary_key);
+    key
+}
+
+fn decode_entry_key(key: &[u8]) -> Option<(Vec<u8>, Vec<u8>)> {
+    if key.len() < 4 {
+        return None;
+    }
+    let len = key[..4].iter().fold(0, |acc, byte| (acc << 8) | *byte as usize);
+    if key.len() < 4 + len {
+        return None;
+    }
+    Some((key[4..4 + len].to_vec(), key[4 + len..].to_vec()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use DIR_TAG;
+    use utils::test_utils::random_client;
+
+    // Index the entries by the first byte of their value.
+    fn by_first_byte(_key: &[u8], value: &[u8]) -> Vec<Vec<u8>> {
+        value.iter().take(1).map(|byte| vec![*byte]).collect()
+    }
+
+    // Writes keep the index consistent, and a diverged index gets repaired.
+    #[test]
+    fn put_delete_repair() {
+        random_client(|client| {
+            let client2 = client.clone();
+            let client3 = client.clone();
+            let client4 = client.clone();
+            let client5 = client.clone();
+            let client6 = client.clone();
+            let client7 = client.clone();
+
+            let primary = unwrap!(MDataInfo::random_private(DIR_TAG));
+            let index = Index::new(unwrap!(MDataInfo::random_private(DIR_TAG)), by_first_byte);
+
+            IndexedData::create(client, primary, vec![index])
+                .and_then(move |data| {
+                    let puts = vec![
+                        data.put(&client2, b"k1".to_vec(), b"apple".to_vec()),
+                        data.put(&client2, b"k2".to_vec(), b"avocado".to_vec()),
+                        data.put(&client2, b"k3".to_vec(), b"banana".to_vec()),
+                    ];
+                    future::join_all(puts).map(move |_| data)
+                })
+                .and_then(move |data| {
+                    let update = data.put(&client3, b"k2".to_vec(), b"blueberry".to_vec());
+                    update.and_then(move |_| {
+                        let delete = data.delete(&client3, b"k3".to_vec());
+                        delete.map(move |_| data)
+                    })
+                })
+                .and_then(move |data| {
+                    let lookups = vec![
+                        data.lookup(&client4, 0, b"a"),
+                        data.lookup(&client4, 0, b"b"),
+                    ];
+                    future::join_all(lookups).map(move |results| {
+                        assert_eq!(results[0], vec![b"k1".to_vec()]);
+                        assert_eq!(results[1], vec![b"k2".to_vec()]);
+                        data
+                    })
+                })
+                .and_then(move |data| {
+                    // Make the index diverge by writing to the primary data
+                    // directly.
+                    let actions = btree_map![
+                        b"k4".to_vec() => EntryAction::Ins(Value {
+                            content: b"cherry".to_vec(),
+                            entry_version: 0,
+                        })
+                    ];
+                    mutate(&client5, data.primary(), actions).map(move |_| data)
+                })
+                .and_then(move |data| {
+                    data.repair(&client6).map(move |count| {
+                        assert_eq!(count, 1);
+                        data
+                    })
+                })
+                .and_then(move |data| {
+                    data.lookup(&client7, 0, b"c").map(|keys| {
+                        assert_eq!(keys, vec![b"k4".to_vec()]);
+                    })
+                })
+        });
+    }
+}
@@ -0,0 +1,667 @@
+// Copyright 2017 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under (1) the MaidSafe.net Commercial License,
+// version 1.0 or later, or (2) The General Public License (GPL), version 3, depending on which
+// licence you accepted on initial access to the Software (the "Licences").
+//
+// By contributing code to the SAFE Network Software, or to this project generally, you agree to be
+// bound by the terms of the MaidSafe Contributor Agreement.  This, along with the Licenses can be
+// found in the root directory of this project at LICENSE, COPYING and CONTRIBUTOR.
+//
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.
+//
+// Please review the Licences for the specific language governing permissions and limitations
+// relating to use of the SAFE Network Software.
+
+//! Key-value store which spreads its entries across multiple `MutableData`
+//! shards.
+//!
+//! The store is rooted in a single `MutableData` holding the table of shards.
+//! Each key belongs to the shard selected by the hash of the key modulo the
+//! number of shards. When a shard reaches the maximum number of entries or the
+//! maximum size, the entries of all shards are copied to twice as many new
+//! shards and the table is switched to them.
+//!
+//! Deleted entries still count toward the limits of a `MutableData`, so they
+//! are not copied and the old shards are abandoned rather than reused. Copied
+//! entries keep their versions. Splitting is not coordinated between multiple
+//! writers, so concurrent writers should retry their mutations if the shard
+//! table changed under them.
+
+use client::{Client, MDataInfo, mdata_info};
+use errors::CoreError;
+use event_loop::CoreFuture;
+use futures::{Future, future};
+use futures::future::Loop;
+use maidsafe_utilities::serialisation::{deserialise, serialise};
+use routing::{ClientError, EntryAction, MAX_MUTABLE_DATA_SIZE_IN_BYTES, MutableData, PermissionSet,
+              User, Value};
+use std::collections::BTreeMap;
+use tiny_keccak::sha3_256;
+use utils::FutureExt;
+
+/// Key of the entry holding the shard table in the root `MutableData`.
+const SHARDS_KEY: &'static [u8] = b"shards";
+/// Maximum number of times a single mutation can trigger a split.
+const MAX_SPLITS: usize = 4;
+
+/// Key-value store spreading its entries across multiple `MutableData`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct KvStore {
+    root: MDataInfo,
+}
+
+#[derive(Clone)]
+struct ShardTable {
+    shards: Vec<MDataInfo>,
+    version: u64,
+}
+
+impl ShardTable {
+    fn shard_for(&self, key: &[u8]) -> &MDataInfo {
+        &self.shards[shard_index(key, self.shards.len())]
+    }
+}
+
+impl KvStore {
+    /// Open existing store rooted at the given `MutableData`.
+    pub fn new(root: MDataInfo) -> Self {
+        KvStore { root }
+    }
+
+    /// `MDataInfo` of the root of this store.
+    pub fn root(&self) -> &MDataInfo {
+        &self.root
+    }
+
+    /// Create new empty store rooted at the given `MutableData`. Both the root
+    /// and all the shards are created with the given permissions and are
+    /// private if `root` is private.
+    pub fn create<T: 'static>(
+        client: &Client<T>,
+        root: MDataInfo,
+        perms: BTreeMap<User, PermissionSet>,
+    ) -> Box<CoreFuture<Self>> {
+        let client2 = client.clone();
+        let owners = btree_set![fry!(client.owner_key())];
+        let shard = fry!(new_shard_info(&root));
+
+        let shard_data = fry!(MutableData::new(
+            shard.name,
+            shard.type_tag,
+            perms.clone(),
+            BTreeMap::new(),
+            owners.clone(),
+        ));
+
+        let key = fry!(root.enc_entry_key(SHARDS_KEY));
+        let content = fry!(encode_shards(&root, &[shard]));
+        let entries = btree_map![key => Value { content, entry_version: 0 }];
+        let root_data = fry!(MutableData::new(
+            root.name,
+            root.type_tag,
+            perms,
+            entries,
+            owners,
+        ));
+
+        client
+            .put_mdata(shard_data)
+            .and_then(move |_| client2.put_mdata(root_data))
+            .map(move |_| KvStore::new(root))
+            .into_box()
+    }
+
+    /// Get the value of the given key. Deleted entries are reported as
+    /// `NoSuchEntry`.
+    pub fn get<T: 'static>(&self, client: &Client<T>, key: &[u8]) -> Box<CoreFuture<Value>> {
+        let client = client.clone();
+        let key = key.to_vec();
+
+        self.fetch_table(&client)
+            .and_then(move |table| {
+                let shard = table.shard_for(&key).clone();
+                let enc_key = fry!(shard.enc_entry_key(&key));
+
+                client
+                    .get_mdata_value(shard.name, shard.type_tag, enc_key)
+                    .and_then(move |value| {
+                        if value.content.is_empty() {
+                            return Err(CoreError::RoutingClientError(ClientError::NoSuchEntry));
+                        }
+                        Ok(Value {
+                            content: shard.decrypt(&value.content)?,
+                            entry_version: value.entry_version,
+                        })
+                    })
+                    .into_box()
+            })
+            .into_box()
+    }
+
+    /// Insert new entry. A deleted entry with the same key is replaced.
+    pub fn insert<T: 'static>(
+        &self,
+        client: &Client<T>,
+        key: Vec<u8>,
+        content: Vec<u8>,
+    ) -> Box<CoreFuture<()>> {
+        self.mutate(
+            client,
+            key,
+            EntryAction::Ins(Value {
+                content,
+                entry_version: 0,
+            }),
+        )
+    }
+
+    /// Update existing entry. `version` must be the current version of the
+    /// entry incremented by one.
+    pub fn update<T: 'static>(
+        &self,
+        client: &Client<T>,
+        key: Vec<u8>,
+        content: Vec<u8>,
+        version: u64,
+    ) -> Box<CoreFuture<()>> {
+        self.mutate(
+            client,
+            key,
+            EntryAction::Update(Value {
+                content,
+                entry_version: version,
+            }),
+        )
+    }
+
+    /// Delete existing entry. `version` must be the current version of the
+    /// entry incremented by one.
+    pub fn delete<T: 'static>(
+        &self,
+        client: &Client<T>,
+        key: Vec<u8>,
+        version: u64,
+    ) -> Box<CoreFuture<()>> {
+        self.mutate(client, key, EntryAction::Del(version))
+    }
+
+    /// Get all entries of the store. The shard table is fetched again after
+    /// all shards were read and the listing is retried if it changed in the
+    /// meantime, so the result is consistent with a single table version.
+    pub fn entries<T: 'static>(
+        &self,
+        client: &Client<T>,
+    ) -> Box<CoreFuture<BTreeMap<Vec<u8>, Value>>> {
+        let store = self.clone();
+        let client = client.clone();
+
+        future::loop_fn((), move |_| {
+            let store2 = store.clone();
+            let client2 = client.clone();
+            let client3 = client.clone();
+
+            store
+                .fetch_table(&client)
+                .and_then(move |table| {
+                    let version = table.version;
+
+                    let fetches = table
+                        .shards
+                        .iter()
+                        .map(|shard| list_entries(&client2, shard))
+                        .collect::<Vec<_>>();
+
+                    future::join_all(fetches).map(move |shards| (shards, version))
+                })
+                .and_then(move |(shards, version)| {
+                    store2.fetch_table(&client3).map(move |table| {
+                        if table.version == version {
+                            Loop::Break(shards.into_iter().flat_map(|s| s).collect())
+                        } else {
+                            Loop::Continue(())
+                        }
+                    })
+                })
+        }).into_box()
+    }
+
+    /// Number of shards the store currently consists of.
+    pub fn shard_count<T: 'static>(&self, client: &Client<T>) -> Box<CoreFuture<usize>> {
+        self.fetch_table(client)
+            .map(|table| table.shards.len())
+            .into_box()
+    }
+
+    fn mutate<T: 'static>(
+        &self,
+        client: &Client<T>,
+        key: Vec<u8>,
+        action: EntryAction,
+    ) -> Box<CoreFuture<()>> {
+        let store = self.clone();
+        let client = client.clone();
+
+        future::loop_fn(0, move |splits| {
+            let store2 = store.clone();
+            let client2 = client.clone();
+            let client3 = client.clone();
+            let key = key.clone();
+            let action = action.clone();
+
+            store
+                .fetch_table(&client)
+                .and_then(move |table| {
+                    let shard = table.shard_for(&key).clone();
+                    resolve_insert(&client3, &shard, &key, action)
+                        .map(move |action| (table, shard, key, action))
+                })
+                .and_then(move |(table, shard, key, action)| {
+                    let actions = btree_map![key => action];
+                    let actions = fry!(mdata_info::encrypt_entry_actions(&shard, &actions));
+
+                    // An entry which doesn't fit even into an empty shard
+                    // would never fit, however many times the store is split.
+                    if entries_size(&actions) > MAX_MUTABLE_DATA_SIZE_IN_BYTES {
+                        return err!(CoreError::RoutingClientError(ClientError::DataTooLarge));
+                    }
+
+                    client2
+                        .mutate_mdata_entries(shard.name, shard.type_tag, actions)
+                        .then(move |res| match res {
+                            Ok(()) => ok!(Loop::Break(())),
+                            Err(ref error) if is_full(error) && splits < MAX_SPLITS => {
+                                store2
+                                    .split(&client2, table)
+                                    .map(move |_| Loop::Continue(splits + 1))
+                                    .into_box()
+                            }
+                            Err(error) => err!(error),
+                        })
+                        .into_box()
+                })
+        }).into_box()
+    }
+
+    // Double the number of shards. The entries of all shards are copied to
+    // new shards first and only then the table is switched to them, so readers
+    // always see every entry.
+    fn split<T: 'static>(&self, client: &Client<T>, table: ShardTable) -> Box<CoreFuture<()>> {
+        let root = self.root.clone();
+        let new_count = table.shards.len() * 2;
+
+        let mut new_shards = Vec::with_capacity(new_count);
+        for _ in 0..new_count {
+            new_shards.push(fry!(new_shard_info(&root)));
+        }
+
+        let client2 = client.clone();
+        let client3 = client.clone();
+        let first = table.shards[0].clone();
+
+        let fetches = table
+            .shards
+            .iter()
+            .map(|shard| list_entries(client, shard))
+            .collect::<Vec<_>>();
+
+        future::join_all(fetches)
+            .join(client.get_mdata_shell(first.name, first.type_tag))
+            .and_then(move |(shards, shell)| {
+                let mut contents = vec![BTreeMap::new(); new_count];
+                for (key, value) in shards.into_iter().flat_map(|entries| entries) {
+                    let _ = contents[shard_index(&key, new_count)].insert(key, value);
+                }
+
+                let puts = new_shards
+                    .iter()
+                    .zip(contents)
+                    .map(|(shard, entries)| -> Result<_, CoreError> {
+                        let entries = mdata_info::encrypt_entries(shard, &entries)?;
+                        let data = MutableData::new(
+                            shard.name,
+                            shard.type_tag,
+                            shell.permissions().clone(),
+                            entries,
+                            shell.owners().clone(),
+                        )?;
+                        Ok(client2.put_mdata(data))
+                    })
+                    .collect::<Result<Vec<_>, CoreError>>();
+                let puts = fry!(puts);
+
+                future::join_all(puts).map(move |_| new_shards).into_box()
+            })
+            .and_then(move |new_shards| {
+                let key = fry!(root.enc_entry_key(SHARDS_KEY));
+                let content = fry!(encode_shards(&root, &new_shards));
+                let actions = btree_map![
+                    key => EntryAction::Update(Value {
+                        content,
+                        entry_version: table.version + 1,
+                    })
+                ];
+
+                client3.mutate_mdata_entries(root.name, root.type_tag, actions)
+            })
+            .into_box()
+    }
+
+    fn fetch_table<T: 'static>(&self, client: &Client<T>) -> Box<CoreFuture<ShardTable>> {
+        let root = self.root.clone();
+        let key = fry!(root.enc_entry_key(SHARDS_KEY));
+
+        client
+            .get_mdata_value(root.name, root.type_tag, key)
+            .and_then(move |value| {
+                let shards = deserialise(&root.decrypt(&value.content)?)?;
+                Ok(ShardTable {
+                    shards,
+                    version: value.entry_version,
+                })
+            })
+            .into_box()
+    }
+}
+
+// Turn insertion of a key which was deleted before into update of the
+// deleted entry, as the deleted entry still exists in the shard.
+fn resolve_insert<T: 'static>(
+    client: &Client<T>,
+    shard: &MDataInfo,
+    key: &[u8],
+    action: EntryAction,
+) -> Box<CoreFuture<EntryAction>> {
+    let content = match action {
+        EntryAction::Ins(value) => value.content,
+        action => return ok!(action),
+    };
+    let enc_key = fry!(shard.enc_entry_key(key));
+
+    client
+        .get_mdata_value(shard.name, shard.type_tag, enc_key)
+        .then(move |res| match res {
+            Ok(ref value) if value.content.is_empty() => {
+                Ok(EntryAction::Update(Value {
+                    content,
+                    entry_version: value.entry_version + 1,
+                }))
+            }
+            Ok(_) |
+            Err(CoreError::RoutingClientError(ClientError::NoSuchEntry)) => {
+                Ok(EntryAction::Ins(Value {
+                    content,
+                    entry_version: 0,
+                }))
+            }
+            Err(error) => Err(error),
+        })
+        .into_box()
+}
+
+// Entries of the shard which are not deleted.
+fn list_entries<T: 'static>(
+    client: &Client<T>,
+    shard: &MDataInfo,
+) -> Box<CoreFuture<BTreeMap<Vec<u8>, Value>>> {
+    let shard = shard.clone();
+
+    client
+        .list_mdata_entries(shard.name, shard.type_tag)
+        .and_then(move |entries| {
+            let entries = entries
+                .into_iter()
+                .filter(|&(_, ref value)| !value.content.is_empty())
+                .collect();
+            mdata_info::decrypt_entries(&shard, &entries)
+        })
+        .into_box()
+}
+
+// Whether the mutation failed because the shard hit the entry count or the
+// size limit of `MutableData`.
+fn is_full(error: &CoreError) -> bool {
+    match *error {
+        CoreError::RoutingClientError(ClientError::TooManyEntries) |
+        CoreError::RoutingClientError(ClientError::DataTooLarge) => true,
+        _ => false,
+    }
+}
+
+// Size of the keys and contents of the entries the actions insert or update.
+fn entries_size(actions: &BTreeMap<Vec<u8>, EntryAction>) -> u64 {
+    actions
+        .iter()
+        .map(|(key, action)| match *action {
+            EntryAction::Ins(ref value) |
+            EntryAction::Update(ref value) => (key.len() + value.content.len()) as u64,
+            EntryAction::Del(_) => 0,
+        })
+        .sum()
+}
+
+fn new_shard_info(root: &MDataInfo) -> Result<MDataInfo, CoreError> {
+    if root.enc_info.is_some() {
+        MDataInfo::random_private(root.type_tag)
+    } else {
+        MDataInfo::random_public(root.type_tag)
+    }
+}
+
+fn encode_shards(root: &MDataInfo, shards: &[MDataInfo]) -> Result<Vec<u8>, CoreError> {
+    root.enc_entry_value(&serialise(&shards)?)
+}
+
+fn shard_index(key: &[u8], count: usize) -> usize {
+    let hash = sha3_256(key);
+    let value = hash[..8].iter().fold(0u64, |acc, byte| (acc << 8) | u64::from(*byte));
+    (value % count as u64) as usize
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use DIR_TAG;
+    use routing::{Action, MAX_MUTABLE_DATA_ENTRIES};
+    use utils::test_utils::random_client;
+
+    fn perms<T>(client: &Client<T>) -> BTreeMap<User, PermissionSet> {
+        btree_map![
+            User::Key(unwrap!(client.public_signing_key())) => PermissionSet::new()
+                .allow(Action::Insert)
+                .allow(Action::Update)
+                .allow(Action::Delete)
+        ]
+    }
+
+    #[test]
+    fn shard_index_is_stable() {
+        for count in 1..10 {
+            let index = shard_index(b"key", count);
+            assert!(index < count);
+            assert_eq!(index, shard_index(b"key", count));
+        }
+    }
+
+    // Basic CRUD on a store with a single shard.
+    #[test]
+    fn crud() {
+        random_client(|client| {
+            let client2 = client.clone();
+            let client3 = client.clone();
+            let client4 = client.clone();
+            let client5 = client.clone();
+            let root = unwrap!(MDataInfo::random_private(DIR_TAG));
+
+            KvStore::create(client, root, perms(client))
+                .and_then(move |store| {
+                    store
+                        .insert(&client2, b"key".to_vec(), b"value".to_vec())
+                        .map(move |_| store)
+                })
+                .and_then(move |store| {
+                    store.get(&client3, b"key").map(move |value| {
+                        assert_eq!(value.content, b"value".to_vec());
+                        assert_eq!(value.entry_version, 0);
+                        store
+                    })
+                })
+                .and_then(move |store| {
+                    store
+                        .update(&client4, b"key".to_vec(), b"other".to_vec(), 1)
+                        .map(move |_| store)
+                })
+                .and_then(move |store| {
+                    store.entries(&client5).map(move |entries| {
+                        assert_eq!(entries.len(), 1);
+                        assert_eq!(entries[&b"key".to_vec()].content, b"other".to_vec());
+                    })
+                })
+        });
+    }
+
+    // Splitting moves entries to new shards without losing any of them.
+    #[test]
+    fn split() {
+        random_client(|client| {
+            let client2 = client.clone();
+            let client3 = client.clone();
+            let client4 = client.clone();
+            let root = unwrap!(MDataInfo::random_public(DIR_TAG));
+
+            KvStore::create(client, root, perms(client))
+                .and_then(move |store| {
+                    let inserts = (0..10u8)
+                        .map(|i| store.insert(&client2, vec![i], vec![i]))
+                        .collect::<Vec<_>>();
+                    future::join_all(inserts).and_then(move |_| {
+                        store.fetch_table(&client2).and_then(move |table| {
+                            store.split(&client2, table).map(move |_| store)
+                        })
+                    })
+                })
+                .and_then(move |store| {
+                    store.shard_count(&client3).map(move |count| {
+                        assert_eq!(count, 2);
+                        store
+                    })
+                })
+                .and_then(move |store| {
+                    store.entries(&client4).map(move |entries| {
+                        assert_eq!(entries.len(), 10);
+                        for i in 0..10u8 {
+                            assert_eq!(entries[&vec![i]].content, vec![i]);
+                        }
+                    })
+                })
+        });
+    }
+
+    // Fill the single shard up to the entry limit. A deleted entry is not
+    // visible and can be inserted again without a split, but inserting a new
+    // key into the full shard splits the store.
+    #[test]
+    fn fill_shard() {
+        random_client(|client| {
+            let client2 = client.clone();
+            let client3 = client.clone();
+            let client4 = client.clone();
+            let client5 = client.clone();
+            let client6 = client.clone();
+            let root = unwrap!(MDataInfo::random_private(DIR_TAG));
+            let limit = MAX_MUTABLE_DATA_ENTRIES as usize;
+
+            KvStore::create(client, root, perms(client))
+                .and_then(move |store| {
+                    future::loop_fn(0, move |i| {
+                        if i == limit {
+                            return ok!(Loop::Break(store.clone()));
+                        }
+                        let key = format!("key{}", i).into_bytes();
+                        store
+                            .insert(&client2, key, vec![1])
+                            .map(move |_| Loop::Continue(i + 1))
+                            .into_box()
+                    })
+                })
+                .and_then(move |store| {
+                    store
+                        .delete(&client3, b"key0".to_vec(), 1)
+                        .and_then(move |_| {
+                            store.get(&client3, b"key0").then(move |res| {
+                                match res {
+                                    Err(CoreError::RoutingClientError(
+                                        ClientError::NoSuchEntry,
+                                    )) => (),
+                                    res => panic!("Unexpected {:?}", res),
+                                }
+                                Ok(store)
+                            })
+                        })
+                })
+                .and_then(move |store| {
+                    store.shard_count(&client4).map(move |count| {
+                        assert_eq!(count, 1);
+                        store
+                    })
+                })
+                .and_then(move |store| {
+                    // Replaces the deleted entry, so there is still room in the shard.
+                    let store2 = store.clone();
+                    let client7 = client5.clone();
+                    store
+                        .insert(&client5, b"key0".to_vec(), vec![3])
+                        .and_then(move |_| store2.shard_count(&client7))
+                        .map(move |count| {
+                            assert_eq!(count, 1);
+                            store
+                        })
+                })
+                .and_then(move |store| {
+                    let store2 = store.clone();
+                    let store3 = store.clone();
+                    let client7 = client6.clone();
+                    let client8 = client6.clone();
+                    store
+                        .insert(&client6, b"extra".to_vec(), vec![2])
+                        .and_then(move |_| store2.shard_count(&client7))
+                        .and_then(move |count| {
+                            assert!(count > 1);
+                            store3.entries(&client8)
+                        })
+                })
+                .map(move |entries| {
+                    assert_eq!(entries.len(), limit + 1);
+                    assert_eq!(entries[&b"extra".to_vec()].content, vec![2]);
+                    assert_eq!(entries[&b"key0".to_vec()].content, vec![3]);
+                })
+        });
+    }
+
+    // An entry larger than a whole shard is rejected without splitting.
+    #[test]
+    fn entry_too_large() {
+        random_client(|client| {
+            let client2 = client.clone();
+            let client3 = client.clone();
+            let root = unwrap!(MDataInfo::random_public(DIR_TAG));
+            let content = vec![0; MAX_MUTABLE_DATA_SIZE_IN_BYTES as usize + 1];
+
+            KvStore::create(client, root, perms(client))
+                .and_then(move |store| {
+                    store
+                        .insert(&client2, b"key".to_vec(), content)
+                        .then(move |res| {
+                            match res {
+                                Err(CoreError::RoutingClientError(ClientError::DataTooLarge)) => (),
+                                res => panic!("Unexpected {:?}", res),
+                            }
+                            store.shard_count(&client3)
+                        })
+                })
+                .map(|count| assert_eq!(count, 1))
+        });
+    }
+}
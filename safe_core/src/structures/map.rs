@@ -0,0 +1,335 @@
+// Copyright 2017 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under (1) the MaidSafe.net Commercial License,
+// version 1.0 or later, or (2) The General Public License (GPL), version 3, depending on which
+// licence you accepted on initial access to the Software (the "Licences").
+//
+// By contributing code to the SAFE Network Software, or to this project generally, you agree to be
+// bound by the terms of the MaidSafe Contributor Agreement.  This, along with the Licenses can be
+// found in the root directory of this project at LICENSE, COPYING and CONTRIBUTOR.
+//
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.
+//
+// Please review the Licences for the specific language governing permissions and limitations
+// relating to use of the SAFE Network Software.
+
+//! Typed map stored in a single `MutableData`.
+//!
+//! Keys and values are serialised and, if the map is private, encrypted with
+//! the keys of its `MDataInfo`, so apps don't have to encode the entries by
+//! hand. Updates and deletions fetch the current entry versions themselves.
+
+use client::{Client, MDataInfo, mdata_info};
+use errors::CoreError;
+use event_loop::CoreFuture;
+use futures::{Future, future};
+use maidsafe_utilities::serialisation::{deserialise, serialise};
+use routing::{ClientError, EntryAction, MutableData, PermissionSet, User, Value};
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use std::collections::BTreeMap;
+use std::fmt::{self, Debug, Formatter};
+use std::marker::PhantomData;
+use utils::FutureExt;
+
+/// Map from `K` to `V` stored in a single `MutableData`.
+pub struct MDataMap<K, V> {
+    info: MDataInfo,
+    _marker: PhantomData<(K, V)>,
+}
+
+impl<K, V> MDataMap<K, V>
+where
+    K: Serialize + DeserializeOwned + Ord + 'static,
+    V: Serialize + DeserializeOwned + 'static,
+{
+    /// Open existing map stored in the given `MutableData`.
+    pub fn new(info: MDataInfo) -> Self {
+        MDataMap {
+            info,
+            _marker: PhantomData,
+        }
+    }
+
+    /// `MDataInfo` of the `MutableData` this map is stored in.
+    pub fn info(&self) -> &MDataInfo {
+        &self.info
+    }
+
+    /// Create new empty map owned by the client, with the given permissions.
+    pub fn create<T: 'static>(
+        client: &Client<T>,
+        info: MDataInfo,
+        perms: BTreeMap<User, PermissionSet>,
+    ) -> Box<CoreFuture<Self>> {
+        let owners = btree_set![fry!(client.owner_key())];
+        let data = fry!(MutableData::new(
+            info.name,
+            info.type_tag,
+            perms,
+            BTreeMap::new(),
+            owners,
+        ));
+
+        client
+            .put_mdata(data)
+            .map(move |_| MDataMap::new(info))
+            .into_box()
+    }
+
+    /// Get the value of the given key, or `None` if there is no such entry.
+    pub fn get<T: 'static>(&self, client: &Client<T>, key: &K) -> Box<CoreFuture<Option<V>>> {
+        let info = self.info.clone();
+        let key = fry!(self.enc_key(key));
+
+        client
+            .get_mdata_value(info.name, info.type_tag, key)
+            .then(move |res| -> Result<_, CoreError> {
+                match res {
+                    // Removed entries are kept as empty tombstones.
+                    Ok(ref value) if value.content.is_empty() => Ok(None),
+                    Ok(value) => Ok(Some(deserialise(&info.decrypt(&value.content)?)?)),
+                    Err(CoreError::RoutingClientError(ClientError::NoSuchEntry)) => Ok(None),
+                    Err(error) => Err(error),
+                }
+            })
+            .into_box()
+    }
+
+    /// Get all entries of the map.
+    pub fn entries<T: 'static>(&self, client: &Client<T>) -> Box<CoreFuture<BTreeMap<K, V>>> {
+        let info = self.info.clone();
+
+        client
+            .list_mdata_entries(info.name, info.type_tag)
+            .and_then(move |entries| {
+                let entries: BTreeMap<_, _> = entries
+                    .into_iter()
+                    .filter(|&(_, ref value)| !value.content.is_empty())
+                    .collect();
+
+                let mut output = BTreeMap::new();
+                for (key, value) in mdata_info::decrypt_entries(&info, &entries)? {
+                    let _ = output.insert(deserialise(&key)?, deserialise(&value.content)?);
+                }
+                Ok(output)
+            })
+            .into_box()
+    }
+
+    /// Insert new entry.
+    pub fn insert<T: 'static>(&self, client: &Client<T>, key: K, value: V) -> Box<CoreFuture<()>> {
+        self.apply(client, MDataMapActions::new().insert(key, value))
+    }
+
+    /// Update existing entry.
+    pub fn update<T: 'static>(&self, client: &Client<T>, key: K, value: V) -> Box<CoreFuture<()>> {
+        self.apply(client, MDataMapActions::new().update(key, value))
+    }
+
+    /// Delete existing entry.
+    pub fn remove<T: 'static>(&self, client: &Client<T>, key: K) -> Box<CoreFuture<()>> {
+        self.apply(client, MDataMapActions::new().remove(key))
+    }
+
+    /// Apply all the actions in a single mutation. The current versions of
+    /// the updated and deleted entries are fetched first, so the mutation can
+    /// still fail if the entries are concurrently modified by somebody else.
+    pub fn apply<T: 'static>(
+        &self,
+        client: &Client<T>,
+        actions: MDataMapActions<K, V>,
+    ) -> Box<CoreFuture<()>> {
+        let info = self.info.clone();
+        let client2 = client.clone();
+
+        let mut inserts = BTreeMap::new();
+        let mut versioned = Vec::new();
+
+        for (key, change) in actions.changes {
+            let key = fry!(self.enc_key(&key));
+            match change {
+                Change::Insert(value) => {
+                    let content = fry!(self.enc_value(&value));
+                    let action = EntryAction::Ins(Value {
+                        content,
+                        entry_version: 0,
+                    });
+                    let _ = inserts.insert(key, action);
+                }
+                Change::Update(value) => versioned.push((key, Some(fry!(self.enc_value(&value))))),
+                Change::Remove => versioned.push((key, None)),
+            }
+        }
+
+        let (name, type_tag) = (info.name, info.type_tag);
+        let versions = versioned.into_iter().map(move |(key, content)| {
+            client
+                .get_mdata_value(name, type_tag, key.clone())
+                .map(move |value| {
+                    let entry_version = value.entry_version + 1;
+                    let action = match content {
+                        Some(content) => EntryAction::Update(Value {
+                            content,
+                            entry_version,
+                        }),
+                        None => EntryAction::Del(entry_version),
+                    };
+                    (key, action)
+                })
+        });
+
+        future::join_all(versions.collect::<Vec<_>>())
+            .and_then(move |versioned| {
+                let mut actions = inserts;
+                actions.extend(versioned);
+
+                if actions.is_empty() {
+                    return ok!(());
+                }
+
+                client2.mutate_mdata_entries(info.name, info.type_tag, actions)
+            })
+            .into_box()
+    }
+
+    fn enc_key(&self, key: &K) -> Result<Vec<u8>, CoreError> {
+        self.info.enc_entry_key(&serialise(key)?)
+    }
+
+    fn enc_value(&self, value: &V) -> Result<Vec<u8>, CoreError> {
+        self.info.enc_entry_value(&serialise(value)?)
+    }
+}
+
+impl<K, V> Clone for MDataMap<K, V> {
+    fn clone(&self) -> Self {
+        MDataMap {
+            info: self.info.clone(),
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<K, V> Debug for MDataMap<K, V> {
+    fn fmt(&self, formatter: &mut Formatter) -> fmt::Result {
+        write!(formatter, "MDataMap {{ info: {:?} }}", self.info)
+    }
+}
+
+enum Change<V> {
+    Insert(V),
+    Update(V),
+    Remove,
+}
+
+/// Batch of actions to apply to a `MDataMap` in a single mutation.
+pub struct MDataMapActions<K, V> {
+    changes: Vec<(K, Change<V>)>,
+}
+
+impl<K, V> MDataMapActions<K, V> {
+    /// Create empty batch.
+    pub fn new() -> Self {
+        MDataMapActions { changes: Vec::new() }
+    }
+
+    /// Insert new entry.
+    pub fn insert(mut self, key: K, value: V) -> Self {
+        self.changes.push((key, Change::Insert(value)));
+        self
+    }
+
+    /// Update existing entry.
+    pub fn update(mut self, key: K, value: V) -> Self {
+        self.changes.push((key, Change::Update(value)));
+        self
+    }
+
+    /// Delete existing entry.
+    pub fn remove(mut self, key: K) -> Self {
+        self.changes.push((key, Change::Remove));
+        self
+    }
+}
+
+impl<K, V> Default for MDataMapActions<K, V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use DIR_TAG;
+    use routing::Action;
+    use utils::test_utils::random_client;
+
+    #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+    struct Contact {
+        name: String,
+        age: u8,
+    }
+
+    // Typed entries survive the round-trip through a private map, with
+    // the versions of updated and deleted entries handled by the map.
+    #[test]
+    fn typed_entries() {
+        random_client(|client| {
+            let client2 = client.clone();
+            let client3 = client.clone();
+            let client4 = client.clone();
+            let client5 = client.clone();
+
+            let info = unwrap!(MDataInfo::random_private(DIR_TAG));
+            let perms = btree_map![
+                User::Key(unwrap!(client.public_signing_key())) => PermissionSet::new()
+                    .allow(Action::Insert)
+                    .allow(Action::Update)
+                    .allow(Action::Delete)
+            ];
+            let alice = Contact {
+                name: "Alice".to_string(),
+                age: 30,
+            };
+            let bob = Contact {
+                name: "Bob".to_string(),
+                age: 40,
+            };
+            let alice2 = alice.clone();
+
+            MDataMap::<String, Contact>::create(client, info, perms)
+                .and_then(move |map| {
+                    let actions = MDataMapActions::new()
+                        .insert("alice".to_string(), alice)
+                        .insert("bob".to_string(), bob);
+                    map.apply(&client2, actions).map(move |_| map)
+                })
+                .and_then(move |map| {
+                    let older = Contact {
+                        name: "Alice".to_string(),
+                        age: 31,
+                    };
+                    let actions = MDataMapActions::new()
+                        .update("alice".to_string(), older)
+                        .remove("bob".to_string());
+                    map.apply(&client3, actions).map(move |_| map)
+                })
+                .and_then(move |map| {
+                    map.get(&client4, &"bob".to_string())
+                        .map(move |bob| {
+                            assert_eq!(bob, None);
+                            map
+                        })
+                })
+                .and_then(move |map| map.entries(&client5))
+                .map(move |entries| {
+                    assert_eq!(entries.len(), 1);
+                    assert_eq!(entries["alice"].age, alice2.age + 1);
+                })
+        });
+    }
+}
@@ -0,0 +1,338 @@
+// Copyright 2017 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under (1) the MaidSafe.net Commercial License,
+// version 1.0 or later, or (2) The General Public License (GPL), version 3, depending on which
+// licence you accepted on initial access to the Software (the "Licences").
+//
+// By contributing code to the SAFE Network Software, or to this project generally, you agree to be
+// bound by the terms of the MaidSafe Contributor Agreement.  This, along with the Licenses can be
+// found in the root directory of this project at LICENSE, COPYING and CONTRIBUTOR.
+//
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.
+//
+// Please review the Licences for the specific language governing permissions and limitations
+// relating to use of the SAFE Network Software.
+
+//! Collaborative editing of shared `MutableData`. The entries are fetched,
+//! modified locally and committed. If somebody else modified the data in the
+//! meantime, the changes are rebased on top of the fresh entries, resolving
+//! conflicting keys with a pluggable `MergeStrategy`, and committed again.
+
+use client::{Client, MDataInfo, mdata_info};
+use errors::CoreError;
+use event_loop::CoreFuture;
+use futures::{Future, future};
+use futures::future::Loop;
+use routing::{ClientError, EntryAction, Value};
+use std::collections::BTreeMap;
+use std::rc::Rc;
+use utils::FutureExt;
+
+/// Maximum number of attempts to commit the changes.
+pub const MAX_ATTEMPTS: usize = 5;
+
+/// Strategy for resolving conflicting modifications of a single key.
+pub trait MergeStrategy {
+    /// Return the value the key should end up with (`None` meaning deleted),
+    /// given the value the local change was based on, the locally desired value
+    /// and the value currently stored in the network.
+    fn merge(
+        &self,
+        key: &[u8],
+        base: Option<&[u8]>,
+        local: Option<&[u8]>,
+        remote: Option<&[u8]>,
+    ) -> Option<Vec<u8>>;
+}
+
+/// Local changes overwrite whatever is stored in the network.
+#[derive(Clone, Copy, Debug)]
+pub struct LastWriterWins;
+
+impl MergeStrategy for LastWriterWins {
+    fn merge(
+        &self,
+        _key: &[u8],
+        _base: Option<&[u8]>,
+        local: Option<&[u8]>,
+        _remote: Option<&[u8]>,
+    ) -> Option<Vec<u8>> {
+        local.map(|value| value.to_vec())
+    }
+}
+
+/// Conflicts are resolved by the wrapped callback, which receives the same
+/// arguments as `MergeStrategy::merge`.
+pub struct MergeWith<F>(pub F);
+
+impl<F> MergeStrategy for MergeWith<F>
+where
+    F: Fn(&[u8], Option<&[u8]>, Option<&[u8]>, Option<&[u8]>) -> Option<Vec<u8>>,
+{
+    fn merge(
+        &self,
+        key: &[u8],
+        base: Option<&[u8]>,
+        local: Option<&[u8]>,
+        remote: Option<&[u8]>,
+    ) -> Option<Vec<u8>> {
+        (self.0)(key, base, local, remote)
+    }
+}
+
+// Decrypted entries, including the deleted ones.
+type Snapshot = BTreeMap<Vec<u8>, Value>;
+// Desired values of the modified keys (`None` for deletion).
+type Changes = BTreeMap<Vec<u8>, Option<Vec<u8>>>;
+
+/// Fetch the entries of the `MutableData`, pass them to `f` for modification
+/// and commit the result. Deleted entries are not passed to `f`. On version
+/// conflicts, the changes are merged with the current entries using `strategy`
+/// and retried, up to `MAX_ATTEMPTS` times.
+pub fn edit<T, F, M>(
+    client: &Client<T>,
+    info: MDataInfo,
+    f: F,
+    strategy: M,
+) -> Box<CoreFuture<()>>
+where
+    T: 'static,
+    F: FnOnce(&mut BTreeMap<Vec<u8>, Vec<u8>>) + 'static,
+    M: MergeStrategy + 'static,
+{
+    let client = client.clone();
+    let strategy = Rc::new(strategy);
+
+    fetch(&client, &info)
+        .and_then(move |base| {
+            let mut edited = contents(&base);
+            f(&mut edited);
+            let changes = diff(&contents(&base), &edited);
+
+            future::loop_fn((base, changes, 1), move |(base, changes, attempts)| {
+                let client2 = client.clone();
+                let info2 = info.clone();
+                let strategy = Rc::clone(&strategy);
+
+                commit(&client, &info, &base, &changes).then(move |res| match res {
+                    Ok(()) => ok!(Loop::Break(())),
+                    Err(CoreError::RoutingClientError(ClientError::InvalidEntryActions(_)))
+                        if attempts < MAX_ATTEMPTS => {
+                        fetch(&client2, &info2)
+                            .map(move |remote| {
+                                let changes = rebase(&*strategy, &base, &remote, changes);
+                                Loop::Continue((remote, changes, attempts + 1))
+                            })
+                            .into_box()
+                    }
+                    Err(error) => err!(error),
+                })
+            })
+        })
+        .into_box()
+}
+
+fn fetch<T: 'static>(client: &Client<T>, info: &MDataInfo) -> Box<CoreFuture<Snapshot>> {
+    let info = info.clone();
+
+    client
+        .list_mdata_entries(info.name, info.type_tag)
+        .and_then(move |entries| mdata_info::decrypt_entries_with_deleted(&info, &entries))
+        .into_box()
+}
+
+fn commit<T: 'static>(
+    client: &Client<T>,
+    info: &MDataInfo,
+    base: &Snapshot,
+    changes: &Changes,
+) -> Box<CoreFuture<()>> {
+    let mut actions = BTreeMap::new();
+
+    for (key, change) in changes {
+        let action = match (base.get(key), change.clone()) {
+            (Some(value), Some(content)) => {
+                EntryAction::Update(Value {
+                    content,
+                    entry_version: value.entry_version + 1,
+                })
+            }
+            (None, Some(content)) => {
+                EntryAction::Ins(Value {
+                    content,
+                    entry_version: 0,
+                })
+            }
+            (Some(value), None) if !value.content.is_empty() => {
+                EntryAction::Del(value.entry_version + 1)
+            }
+            _ => continue,
+        };
+        let _ = actions.insert(key.clone(), action);
+    }
+
+    if actions.is_empty() {
+        return ok!(());
+    }
+
+    let actions = fry!(mdata_info::encrypt_entry_actions(info, &actions));
+    client.mutate_mdata_entries(info.name, info.type_tag, actions)
+}
+
+// Values of the entries which are not deleted.
+fn contents(snapshot: &Snapshot) -> BTreeMap<Vec<u8>, Vec<u8>> {
+    snapshot
+        .iter()
+        .filter(|&(_, value)| !value.content.is_empty())
+        .map(|(key, value)| (key.clone(), value.content.clone()))
+        .collect()
+}
+
+fn diff(base: &BTreeMap<Vec<u8>, Vec<u8>>, edited: &BTreeMap<Vec<u8>, Vec<u8>>) -> Changes {
+    let mut changes = Changes::new();
+
+    for (key, value) in edited {
+        if base.get(key) != Some(value) {
+            let _ = changes.insert(key.clone(), Some(value.clone()));
+        }
+    }
+    for key in base.keys() {
+        if !edited.contains_key(key) {
+            let _ = changes.insert(key.clone(), None);
+        }
+    }
+
+    changes
+}
+
+// Resolve the changes of keys which were modified remotely since `base` was
+// fetched.
+fn rebase<M: MergeStrategy + ?Sized>(
+    strategy: &M,
+    base: &Snapshot,
+    remote: &Snapshot,
+    changes: Changes,
+) -> Changes {
+    let base = contents(base);
+    let remote = contents(remote);
+
+    changes
+        .into_iter()
+        .map(|(key, local)| {
+            let base_value = base.get(&key).map(|value| &value[..]);
+            let remote_value = remote.get(&key).map(|value| &value[..]);
+
+            let value = if base_value == remote_value {
+                local
+            } else {
+                strategy.merge(&key, base_value, local.as_ref().map(|v| &v[..]), remote_value)
+            };
+
+            (key, value)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use DIR_TAG;
+    use routing::{Action, MutableData, PermissionSet, User};
+    use utils::test_utils::random_client;
+
+    fn put_info<T: 'static>(client: &Client<T>, info: &MDataInfo) -> Box<CoreFuture<()>> {
+        let owners = btree_set![unwrap!(client.owner_key())];
+        let entries = btree_map![
+            b"a".to_vec() => Value { content: b"1".to_vec(), entry_version: 0 },
+            b"b".to_vec() => Value { content: b"1".to_vec(), entry_version: 0 }
+        ];
+        let entries = unwrap!(mdata_info::encrypt_entries(info, &entries));
+        let perms = btree_map![
+            User::Anyone => PermissionSet::new()
+                .allow(Action::Insert)
+                .allow(Action::Update)
+                .allow(Action::Delete)
+        ];
+        let data = unwrap!(MutableData::new(
+            info.name,
+            info.type_tag,
+            perms,
+            entries,
+            owners,
+        ));
+        client.put_mdata(data)
+    }
+
+    // Changes based on stale entries fail to commit and are then resolved by
+    // the merge callback, while changes of other keys are preserved.
+    #[test]
+    fn conflicting_edits() {
+        random_client(|client| {
+            let client2 = client.clone();
+            let client3 = client.clone();
+            let client4 = client.clone();
+            let client5 = client.clone();
+            let client6 = client.clone();
+
+            let info = unwrap!(MDataInfo::random_private(DIR_TAG));
+            let info2 = info.clone();
+            let info3 = info.clone();
+            let info4 = info.clone();
+            let info5 = info.clone();
+            let info6 = info.clone();
+
+            put_info(client, &info)
+                .and_then(move |_| fetch(&client2, &info2))
+                .and_then(move |stale| {
+                    edit(
+                        &client3,
+                        info3,
+                        |entries| {
+                            let _ = entries.insert(b"a".to_vec(), b"remote".to_vec());
+                        },
+                        LastWriterWins,
+                    ).map(move |_| stale)
+                })
+                .and_then(move |stale| {
+                    let base = contents(&stale);
+                    let mut edited = base.clone();
+                    let _ = edited.insert(b"a".to_vec(), b"local".to_vec());
+                    let _ = edited.remove(&b"b"[..]);
+                    let changes = diff(&base, &edited);
+
+                    commit(&client4, &info4, &stale, &changes).then(move |res| {
+                        match res {
+                            Err(CoreError::RoutingClientError(
+                                ClientError::InvalidEntryActions(_),
+                            )) => (),
+                            res => panic!("Unexpected {:?}", res),
+                        }
+                        Ok::<_, CoreError>((stale, changes))
+                    })
+                })
+                .and_then(move |(stale, changes)| {
+                    let remote = fetch(&client5, &info5);
+                    remote.and_then(move |remote| {
+                        let strategy = MergeWith(|_: &[u8],
+                         _: Option<&[u8]>,
+                         local: Option<&[u8]>,
+                         remote: Option<&[u8]>| {
+                            let mut merged = unwrap!(remote).to_vec();
+                            merged.extend_from_slice(unwrap!(local));
+                            Some(merged)
+                        });
+                        let changes = rebase(&strategy, &stale, &remote, changes);
+                        commit(&client5, &info5, &remote, &changes)
+                    })
+                })
+                .and_then(move |_| fetch(&client6, &info6))
+                .map(|snapshot| {
+                    let entries = contents(&snapshot);
+                    assert_eq!(entries[&b"a".to_vec()], b"remotelocal".to_vec());
+                    assert!(!entries.contains_key(&b"b".to_vec()));
+                })
+        });
+    }
+}
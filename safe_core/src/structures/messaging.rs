@@ -0,0 +1,377 @@
+// Copyright 2017 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under (1) the MaidSafe.net Commercial License,
+// version 1.0 or later, or (2) The General Public License (GPL), version 3, depending on which
+// licence you accepted on initial access to the Software (the "Licences").
+//
+// By contributing code to the SAFE Network Software, or to this project generally, you agree to be
+// bound by the terms of the MaidSafe Contributor Agreement.  This, along with the Licenses can be
+// found in the root directory of this project at LICENSE, COPYING and CONTRIBUTOR.
+//
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.
+//
+// Please review the Licences for the specific language governing permissions and limitations
+// relating to use of the SAFE Network Software.
+
+//! User-to-user messaging. Every user owns an inbox and an outbox. Anyone can
+//! insert messages, sealed to the owner's public encryption key, into the
+//! inbox. The owner deletes the messages once read, which serves as a read
+//! receipt for the sender, who keeps track of the sent messages in the outbox.
+
+use client::{Client, MDataInfo, mdata_info};
+use crypto::shared_box;
+use errors::CoreError;
+use event_loop::CoreFuture;
+use futures::Future;
+use maidsafe_utilities::serialisation::{deserialise, serialise};
+use rand;
+use routing::{Action, ClientError, EntryAction, MutableData, PermissionSet, User, Value, XorName};
+use rust_sodium::crypto::{box_, sealedbox};
+use std::collections::BTreeMap;
+use utils::FutureExt;
+
+/// Type tag of the inbox and outbox `MutableData`.
+pub const MESSAGING_TAG: u64 = 15_004;
+
+/// Identifier of a message.
+pub type MessageId = XorName;
+
+/// Everything needed to send a message to a user.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct MessagingAddress {
+    /// Name of the recipient's inbox
+    pub inbox: XorName,
+    /// Public encryption key of the recipient
+    pub enc_key: box_::PublicKey,
+}
+
+/// Decrypted message received into an inbox.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct Message {
+    /// Address to reply to
+    pub sender: MessagingAddress,
+    /// Content of the message
+    pub body: Vec<u8>,
+}
+
+/// Pair of inbox and outbox belonging to a single user.
+#[derive(Clone, Debug)]
+pub struct Mailbox {
+    inbox: MDataInfo,
+    outbox: MDataInfo,
+}
+
+impl Mailbox {
+    /// Open existing mailbox.
+    pub fn new(inbox: MDataInfo, outbox: MDataInfo) -> Self {
+        Mailbox { inbox, outbox }
+    }
+
+    /// Create new mailbox owned by the client.
+    pub fn create<T: 'static>(client: &Client<T>) -> Box<CoreFuture<Self>> {
+        let inbox = fry!(MDataInfo::random_public(MESSAGING_TAG));
+        let outbox = fry!(MDataInfo::random_private(MESSAGING_TAG));
+        let owners = btree_set![fry!(client.owner_key())];
+
+        // The client may be an app, which needs explicit permissions to record
+        // sent messages and delete the received ones.
+        let own_perms = PermissionSet::new()
+            .allow(Action::Insert)
+            .allow(Action::Update)
+            .allow(Action::Delete);
+        let own_key = User::Key(fry!(client.public_signing_key()));
+
+        let inbox_perms = btree_map![
+            User::Anyone => PermissionSet::new().allow(Action::Insert),
+            own_key => own_perms
+        ];
+        let inbox_data = fry!(MutableData::new(
+            inbox.name,
+            inbox.type_tag,
+            inbox_perms,
+            Default::default(),
+            owners.clone(),
+        ));
+        let outbox_data = fry!(MutableData::new(
+            outbox.name,
+            outbox.type_tag,
+            btree_map![own_key => own_perms],
+            Default::default(),
+            owners,
+        ));
+
+        let mailbox = Mailbox::new(inbox, outbox);
+
+        client
+            .put_mdata(inbox_data)
+            .join(client.put_mdata(outbox_data))
+            .map(move |_| mailbox)
+            .into_box()
+    }
+
+    /// `MDataInfo` of the inbox.
+    pub fn inbox(&self) -> &MDataInfo {
+        &self.inbox
+    }
+
+    /// `MDataInfo` of the outbox.
+    pub fn outbox(&self) -> &MDataInfo {
+        &self.outbox
+    }
+
+    /// Address other users can send messages to this mailbox at.
+    pub fn address<T: 'static>(&self, client: &Client<T>) -> Result<MessagingAddress, CoreError> {
+        Ok(MessagingAddress {
+            inbox: self.inbox.name,
+            enc_key: client.public_encryption_key()?,
+        })
+    }
+
+    /// Send message to the given address and record it in the outbox.
+    pub fn send<T: 'static>(
+        &self,
+        client: &Client<T>,
+        recipient: MessagingAddress,
+        body: Vec<u8>,
+    ) -> Box<CoreFuture<MessageId>> {
+        let id: MessageId = rand::random();
+        let message = Message {
+            sender: fry!(self.address(client)),
+            body,
+        };
+        let content = sealedbox::seal(&fry!(serialise(&message)), &recipient.enc_key);
+        let actions = btree_map![
+            id.0.to_vec() => EntryAction::Ins(Value {
+                content,
+                entry_version: 0,
+            })
+        ];
+
+        let outbox = self.outbox.clone();
+        let client2 = client.clone();
+
+        client
+            .mutate_mdata_entries(recipient.inbox, MESSAGING_TAG, actions)
+            .and_then(move |_| {
+                let actions = btree_map![
+                    id.0.to_vec() => EntryAction::Ins(Value {
+                        content: fry!(serialise(&recipient)),
+                        entry_version: 0,
+                    })
+                ];
+                let actions = fry!(mdata_info::encrypt_entry_actions(&outbox, &actions));
+                client2.mutate_mdata_entries(outbox.name, outbox.type_tag, actions)
+            })
+            .map(move |_| id)
+            .into_box()
+    }
+
+    /// List and decrypt the messages in the inbox. Messages which can't be
+    /// decrypted are skipped.
+    pub fn messages<T: 'static>(
+        &self,
+        client: &Client<T>,
+    ) -> Box<CoreFuture<Vec<(MessageId, Message)>>> {
+        let sk = fry!(client.secret_encryption_key());
+        let pk = fry!(client.public_encryption_key());
+
+        client
+            .list_mdata_entries(self.inbox.name, self.inbox.type_tag)
+            .map(move |entries| {
+                entries
+                    .into_iter()
+                    .filter_map(|(key, value)| match (
+                        message_id(&key),
+                        open_message(&value.content, &pk, &sk),
+                    ) {
+                        (Some(id), Some(message)) => Some((id, message)),
+                        _ => None,
+                    })
+                    .collect()
+            })
+            .into_box()
+    }
+
+    /// Delete the message from the inbox, marking it as read for its sender.
+    pub fn delete<T: 'static>(&self, client: &Client<T>, id: MessageId) -> Box<CoreFuture<()>> {
+        let inbox = self.inbox.clone();
+        let client2 = client.clone();
+
+        client
+            .get_mdata_value(inbox.name, inbox.type_tag, id.0.to_vec())
+            .and_then(move |value| {
+                if value.content.is_empty() {
+                    return err!(CoreError::RoutingClientError(ClientError::NoSuchEntry));
+                }
+
+                let actions = btree_map![
+                    id.0.to_vec() => EntryAction::Del(value.entry_version + 1)
+                ];
+                client2.mutate_mdata_entries(inbox.name, inbox.type_tag, actions)
+            })
+            .into_box()
+    }
+
+    /// List the messages sent from this mailbox along with their recipients.
+    pub fn sent<T: 'static>(
+        &self,
+        client: &Client<T>,
+    ) -> Box<CoreFuture<Vec<(MessageId, MessagingAddress)>>> {
+        let outbox = self.outbox.clone();
+
+        client
+            .list_mdata_entries(outbox.name, outbox.type_tag)
+            .and_then(move |entries| {
+                let entries = mdata_info::decrypt_entries(&outbox, &entries)?;
+                let mut sent = Vec::new();
+
+                for (key, value) in entries {
+                    if value.content.is_empty() {
+                        continue;
+                    }
+                    if let Some(id) = message_id(&key) {
+                        sent.push((id, deserialise(&value.content)?));
+                    }
+                }
+
+                Ok(sent)
+            })
+            .into_box()
+    }
+
+    /// Check whether the recipient has already read (deleted) the sent message.
+    pub fn is_read<T: 'static>(&self, client: &Client<T>, id: MessageId) -> Box<CoreFuture<bool>> {
+        let key = fry!(self.outbox.enc_entry_key(&id.0));
+        let outbox = self.outbox.clone();
+        let client2 = client.clone();
+
+        client
+            .get_mdata_value(outbox.name, outbox.type_tag, key)
+            .and_then(move |value| {
+                let content = fry!(outbox.decrypt(&value.content));
+                let recipient: MessagingAddress = fry!(deserialise(&content));
+
+                client2
+                    .get_mdata_value(recipient.inbox, MESSAGING_TAG, id.0.to_vec())
+                    .map(|value| value.content.is_empty())
+                    .into_box()
+            })
+            .into_box()
+    }
+}
+
+fn message_id(key: &[u8]) -> Option<MessageId> {
+    if key.len() != 32 {
+        return None;
+    }
+    let mut id = [0; 32];
+    id.copy_from_slice(key);
+    Some(XorName(id))
+}
+
+// Returns `None` for deleted messages and messages which can't be decrypted.
+fn open_message(
+    content: &[u8],
+    pk: &box_::PublicKey,
+    sk: &shared_box::SecretKey,
+) -> Option<Message> {
+    if content.is_empty() {
+        return None;
+    }
+    sealedbox::open(content, pk, sk).ok().and_then(
+        |plain_text| deserialise(&plain_text).ok(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use client::Client;
+    use utils;
+    use utils::test_utils::{random_client, setup_client};
+
+    // Message sent to another user can be read and deleted by them, which the
+    // sender observes as a read receipt.
+    #[test]
+    fn send_receive_delete() {
+        let locator = unwrap!(utils::generate_random_string(10));
+        let password = unwrap!(utils::generate_random_string(10));
+        let invitation = unwrap!(utils::generate_random_string(10));
+
+        let recipient_mailbox = {
+            let (locator, password) = (locator.clone(), password.clone());
+            setup_client(
+                |el_h, core_tx, net_tx| {
+                    Client::registered(&locator, &password, &invitation, el_h, core_tx, net_tx)
+                },
+                |client| Mailbox::create(client),
+            )
+        };
+        let recipient_mailbox2 = recipient_mailbox.clone();
+        let recipient_address = setup_client(
+            |el_h, core_tx, net_tx| Client::login(&locator, &password, el_h, core_tx, net_tx),
+            move |client| recipient_mailbox2.address(client),
+        );
+
+        // Send message and check it's unread.
+        let (sender_mailbox, id) = random_client(move |client| {
+            let client2 = client.clone();
+            let client3 = client.clone();
+            let client4 = client.clone();
+
+            Mailbox::create(client)
+                .and_then(move |mailbox| {
+                    mailbox
+                        .send(&client2, recipient_address, b"hello".to_vec())
+                        .map(move |id| (mailbox, id))
+                })
+                .and_then(move |(mailbox, id)| {
+                    mailbox.sent(&client3).map(move |sent| {
+                        assert_eq!(sent, vec![(id, recipient_address)]);
+                        (mailbox, id)
+                    })
+                })
+                .and_then(move |(mailbox, id)| {
+                    mailbox.is_read(&client4, id).map(move |read| {
+                        assert!(!read);
+                        (mailbox, id)
+                    })
+                })
+        });
+        let sender_mailbox2 = sender_mailbox.clone();
+
+        // Recipient reads and deletes the message.
+        setup_client(
+            |el_h, core_tx, net_tx| Client::login(&locator, &password, el_h, core_tx, net_tx),
+            move |client| {
+                let client2 = client.clone();
+                let client3 = client.clone();
+                let sender_inbox = sender_mailbox.inbox().name;
+
+                recipient_mailbox
+                    .messages(client)
+                    .and_then(move |messages| {
+                        assert_eq!(messages.len(), 1);
+                        assert_eq!(messages[0].0, id);
+                        assert_eq!(messages[0].1.sender.inbox, sender_inbox);
+                        assert_eq!(messages[0].1.body, b"hello".to_vec());
+
+                        recipient_mailbox.delete(&client2, id).map(
+                            move |_| recipient_mailbox,
+                        )
+                    })
+                    .and_then(move |recipient_mailbox| recipient_mailbox.messages(&client3))
+                    .map(|messages| assert!(messages.is_empty()))
+            },
+        );
+
+        // Sender sees the message as read.
+        random_client(move |client| {
+            sender_mailbox2.is_read(client, id).map(
+                |read| assert!(read),
+            )
+        });
+    }
+}
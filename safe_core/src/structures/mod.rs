@@ -0,0 +1,49 @@
+// Copyright 2017 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under (1) the MaidSafe.net Commercial License,
+// version 1.0 or later, or (2) The General Public License (GPL), version 3, depending on which
+// licence you accepted on initial access to the Software (the "Licences").
+//
+// By contributing code to the SAFE Network Software, or to this project generally, you agree to be
+// bound by the terms of the MaidSafe Contributor Agreement.  This, along with the Licenses can be
+// found in the root directory of this project at LICENSE, COPYING and CONTRIBUTOR.
+//
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.
+//
+// Please review the Licences for the specific language governing permissions and limitations
+// relating to use of the SAFE Network Software.
+
+/// Key-value store spread across multiple `MutableData` shards
+pub mod kv_store;
+/// Append-only log stored in chained `MutableData` segments
+pub mod append_log;
+/// Polling inbox channel between apps
+pub mod channel;
+/// Public names mapping services to NFS directories
+pub mod dns;
+/// Container shared among a group of users
+pub mod group;
+/// Secondary indexes over `MutableData`
+pub mod index;
+/// User-to-user messaging through inboxes and outboxes
+pub mod messaging;
+/// Collaborative editing of shared `MutableData`
+pub mod merge;
+/// Typed map stored in a single `MutableData`
+pub mod map;
+/// Registry of the `MutableData` created by an account
+pub mod owned_data;
+/// Standard public profile of a user
+pub mod profile;
+
+pub use self::kv_store::KvStore;
+pub use self::append_log::AppendLog;
+pub use self::map::{MDataMap, MDataMapActions};
+pub use self::channel::Channel;
+pub use self::group::{Member, SharedContainer};
+pub use self::index::{Index, IndexedData};
+pub use self::merge::{LastWriterWins, MergeStrategy, MergeWith, edit};
+pub use self::messaging::{Mailbox, Message, MessageId, MessagingAddress};
+pub use self::profile::Profile;
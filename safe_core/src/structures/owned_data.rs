@@ -0,0 +1,314 @@
+// Copyright 2017 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under (1) the MaidSafe.net Commercial License,
+// version 1.0 or later, or (2) The General Public License (GPL), version 3, depending on which
+// licence you accepted on initial access to the Software (the "Licences").
+//
+// By contributing code to the SAFE Network Software, or to this project generally, you agree to be
+// bound by the terms of the MaidSafe Contributor Agreement.  This, along with the Licenses can be
+// found in the root directory of this project at LICENSE, COPYING and CONTRIBUTOR.
+//
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.
+//
+// Please review the Licences for the specific language governing permissions and limitations
+// relating to use of the SAFE Network Software.
+
+//! Registry of the `MutableData` created by an account. Once enabled with
+//! `Client::set_owned_data_index`, every successful `put_mdata` of an account
+//! client records the data in an index owned by the account, so the account
+//! can later find out what data it owns. Entries whose data no longer exists
+//! can be pruned.
+//!
+//! The index is encrypted with the key of the account's config root, so only
+//! the account itself (not its apps) can read and update it.
+
+use client::{Client, MDataInfo};
+use errors::CoreError;
+use event_loop::CoreFuture;
+use futures::Future;
+use futures::future::{self, Loop};
+use maidsafe_utilities::serialisation::{deserialise, serialise};
+use routing::{ClientError, EntryAction, MutableData, Value, XorName};
+use std::collections::BTreeSet;
+use tiny_keccak::sha3_256;
+use utils::FutureExt;
+
+/// Type tag of the owned-data index `MutableData`.
+pub const OWNED_DATA_TAG: u64 = 15_006;
+
+/// Maximum number of attempts to record data when racing with other clients
+/// of the same account.
+const MAX_ATTEMPTS: usize = 10;
+
+/// `MDataInfo` of the owned-data index of the client's account. Fails for
+/// clients which aren't logged in to an account.
+pub fn index_info<T: 'static>(client: &Client<T>) -> Result<MDataInfo, CoreError> {
+    let owner_key = client.owner_key()?;
+    let config_root = client.config_root_dir()?;
+    let enc_info = config_root.enc_info.ok_or(CoreError::OperationForbidden)?;
+
+    let mut input = b"owned-data".to_vec();
+    input.extend_from_slice(&owner_key.0);
+
+    Ok(MDataInfo::new_private(
+        XorName(sha3_256(&input)),
+        OWNED_DATA_TAG,
+        enc_info,
+    ))
+}
+
+/// Record `MutableData` with the given name and tag in the index, creating
+/// the index if needed. Does nothing for clients without an account. The
+/// index is shared by all the clients of the account, so the recording is
+/// retried when it loses a race with another one.
+pub fn record<T: 'static>(client: &Client<T>, name: XorName, tag: u64) -> Box<CoreFuture<()>> {
+    if tag == OWNED_DATA_TAG {
+        return ok!(());
+    }
+    let info = match index_info(client) {
+        Ok(info) => info,
+        Err(_) => return ok!(()),
+    };
+    let client = client.clone();
+
+    future::loop_fn(0, move |attempts| {
+        record_once(&client, &info, name, tag).then(move |res| match res {
+            Ok(()) => Ok(Loop::Break(())),
+            Err(CoreError::RoutingClientError(ClientError::InvalidEntryActions(_))) |
+            Err(CoreError::RoutingClientError(ClientError::InvalidSuccessor(_))) |
+            Err(CoreError::RoutingClientError(ClientError::DataExists))
+                if attempts < MAX_ATTEMPTS => Ok(Loop::Continue(attempts + 1)),
+            Err(error) => Err(error),
+        })
+    }).into_box()
+}
+
+fn record_once<T: 'static>(
+    client: &Client<T>,
+    info: &MDataInfo,
+    name: XorName,
+    tag: u64,
+) -> Box<CoreFuture<()>> {
+    let plain = fry!(serialise(&(name, tag)));
+    let key = fry!(info.enc_entry_key(&plain));
+    let content = fry!(info.enc_entry_value(&plain));
+
+    let info = info.clone();
+    let client2 = client.clone();
+    let client3 = client.clone();
+
+    client
+        .get_mdata_value(info.name, info.type_tag, key.clone())
+        .then(move |res| match res {
+            Ok(ref value) if !value.content.is_empty() => ok!(()),
+            Ok(value) => {
+                let action = EntryAction::Update(Value {
+                    content,
+                    entry_version: value.entry_version + 1,
+                });
+                client2.mutate_mdata_entries(info.name, info.type_tag, btree_map![key => action])
+            }
+            Err(CoreError::RoutingClientError(ClientError::NoSuchEntry)) => {
+                let action = EntryAction::Ins(Value {
+                    content,
+                    entry_version: 0,
+                });
+                client2.mutate_mdata_entries(info.name, info.type_tag, btree_map![key => action])
+            }
+            Err(CoreError::RoutingClientError(ClientError::NoSuchData)) => {
+                create_index(&client3, &info, key, content)
+            }
+            Err(error) => err!(error),
+        })
+        .into_box()
+}
+
+/// List names and type tags of the data recorded in the index.
+pub fn list<T: 'static>(client: &Client<T>) -> Box<CoreFuture<BTreeSet<(XorName, u64)>>> {
+    let info = fry!(index_info(client));
+
+    client
+        .list_mdata_values(info.name, info.type_tag)
+        .then(move |res| {
+            let values = match res {
+                Ok(values) => values,
+                Err(CoreError::RoutingClientError(ClientError::NoSuchData)) => Vec::new(),
+                Err(error) => return Err(error),
+            };
+
+            values
+                .into_iter()
+                .filter(|value| !value.content.is_empty())
+                .map(|value| decode(&info, &value))
+                .collect()
+        })
+        .into_box()
+}
+
+/// Remove the given data from the index.
+pub fn remove<T: 'static>(client: &Client<T>, name: XorName, tag: u64) -> Box<CoreFuture<()>> {
+    let info = fry!(index_info(client));
+    let plain = fry!(serialise(&(name, tag)));
+    let key = fry!(info.enc_entry_key(&plain));
+    let client2 = client.clone();
+
+    client
+        .get_mdata_value(info.name, info.type_tag, key.clone())
+        .and_then(move |value| {
+            if value.content.is_empty() {
+                return ok!(());
+            }
+            let action = EntryAction::Update(Value {
+                content: Vec::new(),
+                entry_version: value.entry_version + 1,
+            });
+            client2.mutate_mdata_entries(info.name, info.type_tag, btree_map![key => action])
+        })
+        .into_box()
+}
+
+/// Remove the entries whose data no longer exists on the network from the
+/// index. Returns the names and type tags of the removed entries.
+pub fn prune<T: 'static>(client: &Client<T>) -> Box<CoreFuture<Vec<(XorName, u64)>>> {
+    let client2 = client.clone();
+    let client3 = client.clone();
+
+    list(client)
+        .and_then(move |owned| {
+            let checks = owned.into_iter().map(move |(name, tag)| {
+                client2.get_mdata_version(name, tag).then(move |res| match res {
+                    Ok(_) => Ok(None),
+                    Err(CoreError::RoutingClientError(ClientError::NoSuchData)) => {
+                        Ok(Some((name, tag)))
+                    }
+                    Err(error) => Err(error),
+                })
+            });
+            future::join_all(checks)
+        })
+        .and_then(move |results| {
+            let stale: Vec<_> = results.into_iter().filter_map(|stale| stale).collect();
+            let removals: Vec<_> = stale
+                .iter()
+                .map(|&(name, tag)| remove(&client3, name, tag))
+                .collect();
+
+            future::join_all(removals).map(move |_| stale)
+        })
+        .into_box()
+}
+
+fn decode(info: &MDataInfo, value: &Value) -> Result<(XorName, u64), CoreError> {
+    let plain = info.decrypt(&value.content)?;
+    Ok(deserialise(&plain)?)
+}
+
+fn create_index<T: 'static>(
+    client: &Client<T>,
+    info: &MDataInfo,
+    key: Vec<u8>,
+    content: Vec<u8>,
+) -> Box<CoreFuture<()>> {
+    let owner_key = fry!(client.owner_key());
+    let entries = btree_map![
+        key => Value {
+            content,
+            entry_version: 0,
+        }
+    ];
+    let data = fry!(MutableData::new(
+        info.name,
+        info.type_tag,
+        Default::default(),
+        entries,
+        btree_set![owner_key],
+    ));
+
+    client.put_mdata(data)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use DIR_TAG;
+    use rand;
+    use utils::test_utils::random_client;
+
+    // Nothing is recorded unless the index is enabled.
+    #[test]
+    fn disabled_by_default() {
+        random_client(|client| {
+            let client2 = client.clone();
+
+            let data = unwrap!(MutableData::new(
+                rand::random(),
+                DIR_TAG,
+                Default::default(),
+                Default::default(),
+                btree_set![unwrap!(client.owner_key())],
+            ));
+
+            client
+                .put_mdata(data)
+                .and_then(move |_| list(&client2))
+                .map(|owned| assert!(owned.is_empty()))
+        });
+    }
+
+    // Data put by the account is recorded, and pruned once it's gone.
+    #[test]
+    fn record_list_prune() {
+        random_client(|client| {
+            client.set_owned_data_index(true);
+
+            let client2 = client.clone();
+            let client3 = client.clone();
+            let client4 = client.clone();
+            let client5 = client.clone();
+
+            let owner_key = unwrap!(client.owner_key());
+            let name: XorName = rand::random();
+            let data = unwrap!(MutableData::new(
+                name,
+                DIR_TAG,
+                Default::default(),
+                Default::default(),
+                btree_set![owner_key],
+            ));
+
+            // Data created only in the index, e.g. by a previous session and
+            // deleted since.
+            let stale: XorName = rand::random();
+
+            client
+                .put_mdata(data)
+                .then(move |res| {
+                    unwrap!(res);
+                    record(&client2, stale, DIR_TAG)
+                })
+                .then(move |res| {
+                    unwrap!(res);
+                    list(&client3)
+                })
+                .then(move |res| {
+                    let owned = unwrap!(res);
+                    assert!(owned.contains(&(name, DIR_TAG)));
+                    assert!(owned.contains(&(stale, DIR_TAG)));
+                    assert!(!owned.iter().any(|&(_, tag)| tag == OWNED_DATA_TAG));
+
+                    prune(&client4)
+                })
+                .then(move |res| {
+                    let pruned = unwrap!(res);
+                    assert_eq!(pruned, vec![(stale, DIR_TAG)]);
+                    list(&client5)
+                })
+                .map(move |owned| {
+                    assert!(owned.contains(&(name, DIR_TAG)));
+                    assert!(!owned.contains(&(stale, DIR_TAG)));
+                })
+        });
+    }
+}
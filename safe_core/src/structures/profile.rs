@@ -0,0 +1,265 @@
+// Copyright 2017 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under (1) the MaidSafe.net Commercial License,
+// version 1.0 or later, or (2) The General Public License (GPL), version 3, depending on which
+// licence you accepted on initial access to the Software (the "Licences").
+//
+// By contributing code to the SAFE Network Software, or to this project generally, you agree to be
+// bound by the terms of the MaidSafe Contributor Agreement.  This, along with the Licenses can be
+// found in the root directory of this project at LICENSE, COPYING and CONTRIBUTOR.
+//
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.
+//
+// Please review the Licences for the specific language governing permissions and limitations
+// relating to use of the SAFE Network Software.
+
+//! Standard public profile of a user. The profile is a public `MutableData`
+//! whose name is derived from the account's owner key, so anyone who knows the
+//! key can find it. Apps should use this instead of their own schemas, so that
+//! identity data is shared between them.
+
+use client::Client;
+use errors::CoreError;
+use event_loop::CoreFuture;
+use futures::Future;
+use maidsafe_utilities::serialisation::{deserialise, serialise};
+use routing::{Action, ClientError, EntryAction, MutableData, PermissionSet, User, Value, XorName};
+use rust_sodium::crypto::{box_, sign};
+use std::collections::BTreeMap;
+use tiny_keccak::sha3_256;
+use utils::FutureExt;
+
+/// Type tag of the profile `MutableData`.
+pub const PROFILE_TAG: u64 = 15_005;
+
+const DISPLAY_NAME_KEY: &'static [u8] = b"display_name";
+const AVATAR_KEY: &'static [u8] = b"avatar";
+const SIGN_KEY_KEY: &'static [u8] = b"sign_key";
+const ENC_KEY_KEY: &'static [u8] = b"enc_key";
+
+/// Public profile of a user.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Profile {
+    /// Name to display to other users
+    pub display_name: String,
+    /// Name of the `ImmutableData` holding the data map of the avatar image
+    pub avatar: Option<XorName>,
+    /// Owner key of the account the profile belongs to
+    pub sign_key: sign::PublicKey,
+    /// Public encryption key of the account other users can encrypt data for
+    /// this user with. Apps don't know the encryption key of the account, so
+    /// it's only there once the account itself has published the profile.
+    pub enc_key: Option<box_::PublicKey>,
+}
+
+impl Profile {
+    /// Create profile of the client's account.
+    pub fn new<T: 'static>(
+        client: &Client<T>,
+        display_name: String,
+        avatar: Option<XorName>,
+    ) -> Result<Self, CoreError> {
+        let owner_key = client.owner_key()?;
+        let enc_key = if client.public_signing_key()? == owner_key {
+            Some(client.public_encryption_key()?)
+        } else {
+            None
+        };
+
+        Ok(Profile {
+            display_name,
+            avatar,
+            sign_key: owner_key,
+            enc_key,
+        })
+    }
+
+    /// Name of the profile `MutableData` belonging to the given owner key.
+    pub fn name(owner_key: &sign::PublicKey) -> XorName {
+        let mut input = b"profile".to_vec();
+        input.extend_from_slice(&owner_key.0);
+        XorName(sha3_256(&input))
+    }
+
+    /// Fetch profile of the account with the given owner key.
+    pub fn fetch<T: 'static>(
+        client: &Client<T>,
+        owner_key: &sign::PublicKey,
+    ) -> Box<CoreFuture<Self>> {
+        client
+            .list_mdata_entries(Self::name(owner_key), PROFILE_TAG)
+            .and_then(|entries| {
+                let display_name = String::from_utf8(field(&entries, DISPLAY_NAME_KEY)?.to_vec())
+                    .map_err(|_| CoreError::ReceivedUnexpectedData)?;
+
+                let enc_key = match field(&entries, ENC_KEY_KEY) {
+                    Ok(content) => Some(deserialise(content)?),
+                    Err(_) => None,
+                };
+
+                Ok(Profile {
+                    display_name,
+                    avatar: deserialise(field(&entries, AVATAR_KEY)?)?,
+                    sign_key: deserialise(field(&entries, SIGN_KEY_KEY)?)?,
+                    enc_key,
+                })
+            })
+            .into_box()
+    }
+
+    /// Publish the profile, creating it if it doesn't exist yet or updating
+    /// the existing one. The profile is owned by the account, so only the
+    /// account and its apps can publish it. An app publishing the profile
+    /// keeps the encryption key already published by the account.
+    pub fn publish<T: 'static>(&self, client: &Client<T>) -> Box<CoreFuture<()>> {
+        let owner_key = fry!(client.owner_key());
+        if self.sign_key != owner_key {
+            return err!(CoreError::OperationForbidden);
+        }
+
+        let name = Self::name(&owner_key);
+        let fields = fry!(self.fields());
+        let client2 = client.clone();
+
+        // The client may be an app, which needs explicit permissions to
+        // update the profile later.
+        let own_perms = PermissionSet::new()
+            .allow(Action::Insert)
+            .allow(Action::Update);
+        let own_key = User::Key(fry!(client.public_signing_key()));
+
+        let entries = fields
+            .iter()
+            .map(|(key, content)| {
+                (
+                    key.clone(),
+                    Value {
+                        content: content.clone(),
+                        entry_version: 0,
+                    },
+                )
+            })
+            .collect();
+        let data = fry!(MutableData::new(
+            name,
+            PROFILE_TAG,
+            btree_map![own_key => own_perms],
+            entries,
+            btree_set![owner_key],
+        ));
+
+        client
+            .put_mdata(data)
+            .or_else(move |error| match error {
+                CoreError::RoutingClientError(ClientError::DataExists) => {
+                    update(&client2, name, fields)
+                }
+                error => err!(error),
+            })
+            .into_box()
+    }
+
+    fn fields(&self) -> Result<BTreeMap<Vec<u8>, Vec<u8>>, CoreError> {
+        let mut fields = btree_map![
+            DISPLAY_NAME_KEY.to_vec() => self.display_name.as_bytes().to_vec(),
+            AVATAR_KEY.to_vec() => serialise(&self.avatar)?,
+            SIGN_KEY_KEY.to_vec() => serialise(&self.sign_key)?
+        ];
+        if let Some(ref enc_key) = self.enc_key {
+            let _ = fields.insert(ENC_KEY_KEY.to_vec(), serialise(enc_key)?);
+        }
+        Ok(fields)
+    }
+}
+
+fn field<'a>(entries: &'a BTreeMap<Vec<u8>, Value>, key: &[u8]) -> Result<&'a [u8], CoreError> {
+    match entries.get(key) {
+        Some(value) if !value.content.is_empty() => Ok(&value.content),
+        _ => Err(CoreError::RoutingClientError(ClientError::NoSuchEntry)),
+    }
+}
+
+fn update<T: 'static>(
+    client: &Client<T>,
+    name: XorName,
+    fields: BTreeMap<Vec<u8>, Vec<u8>>,
+) -> Box<CoreFuture<()>> {
+    let client2 = client.clone();
+
+    client
+        .list_mdata_entries(name, PROFILE_TAG)
+        .and_then(move |entries| {
+            let mut actions = BTreeMap::new();
+
+            for (key, content) in fields {
+                let action = match entries.get(&key) {
+                    Some(value) if value.content == content => continue,
+                    Some(value) => {
+                        EntryAction::Update(Value {
+                            content,
+                            entry_version: value.entry_version + 1,
+                        })
+                    }
+                    None => {
+                        EntryAction::Ins(Value {
+                            content,
+                            entry_version: 0,
+                        })
+                    }
+                };
+                let _ = actions.insert(key, action);
+            }
+
+            if actions.is_empty() {
+                return ok!(());
+            }
+            client2.mutate_mdata_entries(name, PROFILE_TAG, actions)
+        })
+        .into_box()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use utils::test_utils::random_client;
+
+    // Publish a profile, update it and fetch it back.
+    #[test]
+    fn publish_and_fetch() {
+        random_client(|client| {
+            let client2 = client.clone();
+            let client3 = client.clone();
+            let client4 = client.clone();
+
+            let profile = unwrap!(Profile::new(client, "Alice".to_string(), None));
+            let owner_key = profile.sign_key;
+            assert_eq!(profile.enc_key, Some(unwrap!(client.public_encryption_key())));
+
+            profile
+                .publish(client)
+                .and_then(move |_| {
+                    let mut profile = profile;
+                    profile.display_name = "Alice Liddell".to_string();
+                    profile.avatar = Some(XorName([1; 32]));
+                    profile.publish(&client2).map(move |_| profile)
+                })
+                .and_then(move |profile| {
+                    Profile::fetch(&client3, &owner_key).map(move |fetched| {
+                        assert_eq!(fetched, profile);
+                    })
+                })
+                .and_then(move |_| {
+                    let (other_key, _) = sign::gen_keypair();
+                    Profile::fetch(&client4, &other_key).then(|res| {
+                        match res {
+                            Err(CoreError::RoutingClientError(ClientError::NoSuchData)) => (),
+                            res => panic!("Unexpected {:?}", res),
+                        }
+                        Ok::<_, CoreError>(())
+                    })
+                })
+        });
+    }
+}
@@ -104,13 +104,9 @@ pub fn symmetric_decrypt(
 /// returned `String` will likely be around `4 * length` as most of the randomly-generated `char`s
 /// will consume 4 elements of the `String`.
 pub fn generate_random_string(length: usize) -> Result<String, CoreError> {
-    let mut os_rng = ::rand::OsRng::new().map_err(|error| {
-        error!("{:?}", error);
-        CoreError::RandomDataGenerationFailure
-    })?;
+    let mut rng = new_rng()?;
     Ok(
-        os_rng
-            .gen_iter::<char>()
+        rng.gen_iter::<char>()
             .filter(|c| *c != '\u{0}')
             .take(length)
             .collect(),
@@ -122,11 +118,35 @@ pub fn generate_random_vector<T>(length: usize) -> Result<Vec<T>, CoreError>
 where
     T: ::rand::Rand,
 {
-    let mut os_rng = ::rand::OsRng::new().map_err(|error| {
+    let mut rng = new_rng()?;
+    Ok(rng.gen_iter().take(length).collect())
+}
+
+/// Generate a random vector of given length to be used as secret material,
+/// e.g. a salt or key entropy. Unlike `generate_random_vector`, it is always
+/// drawn from the OS generator, even with mock routing.
+pub fn generate_secret_vector(length: usize) -> Result<Vec<u8>, CoreError> {
+    let mut rng = os_rng()?;
+    Ok(rng.gen_iter().take(length).collect())
+}
+
+fn os_rng() -> Result<::rand::OsRng, CoreError> {
+    ::rand::OsRng::new().map_err(|error| {
         error!("{:?}", error);
         CoreError::RandomDataGenerationFailure
-    })?;
-    Ok(os_rng.gen_iter().take(length).collect())
+    })
+}
+
+#[cfg(not(feature = "use-mock-routing"))]
+fn new_rng() -> Result<::rand::OsRng, CoreError> {
+    os_rng()
+}
+
+// With mock routing, the random data is drawn from the seedable generator of
+// the mock network, so that failing tests can be replayed.
+#[cfg(feature = "use-mock-routing")]
+fn new_rng() -> Result<::rand::XorShiftRng, CoreError> {
+    Ok(::client::mock_rng::random())
 }
 
 /// Derive Password, Keyword and PIN (in order)
@@ -135,11 +155,16 @@ pub fn derive_secrets(acc_locator: &[u8], acc_password: &[u8]) -> (Vec<u8>, Vec<
 
     let pin = sha512::hash(&locator_hash[DIGESTBYTES / 2..]).0.to_vec();
     let keyword = locator_hash.to_vec();
-    let password = sha512::hash(acc_password).0.to_vec();
+    let password = derive_password(acc_password);
 
     (password, keyword, pin)
 }
 
+/// Derive the Password alone, e.g. when changing it
+pub fn derive_password(acc_password: &[u8]) -> Vec<u8> {
+    sha512::hash(acc_password).0.to_vec()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
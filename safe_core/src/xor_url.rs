@@ -0,0 +1,164 @@
+// Copyright 2017 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under (1) the MaidSafe.net Commercial License,
+// version 1.0 or later, or (2) The General Public License (GPL), version 3, depending on which
+// licence you accepted on initial access to the Software (the "Licences").
+//
+// By contributing code to the SAFE Network Software, or to this project generally, you agree to be
+// bound by the terms of the MaidSafe Contributor Agreement.  This, along with the Licenses can be
+// found in the root directory of this project at LICENSE, COPYING and CONTRIBUTOR.
+//
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.
+//
+// Please review the Licences for the specific language governing permissions and limitations
+// relating to use of the SAFE Network Software.
+
+//! XOR-URLs address data directly by its location in the network, as
+//! `safe://<base32 of the kind, name and type tag of the data>`. Unlike
+//! public names, they don't need any registration.
+
+use routing::{XOR_NAME_LEN, XorName};
+
+/// Scheme of the URLs.
+pub const SAFE_URL_SCHEME: &'static str = "safe://";
+
+const IDATA_KIND: u8 = 0;
+const MDATA_KIND: u8 = 1;
+const BASE32_ALPHABET: &'static [u8] = b"abcdefghijklmnopqrstuvwxyz234567";
+
+/// Data addressed by a XOR-URL.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum XorUrl {
+    /// `ImmutableData` with the given name.
+    ImmutableData(XorName),
+    /// `MutableData` with the given name and type tag.
+    MutableData(XorName, u64),
+}
+
+impl XorUrl {
+    /// Encode as `safe://` URL.
+    pub fn encode(&self) -> String {
+        let mut bytes = Vec::with_capacity(1 + XOR_NAME_LEN + 8);
+
+        match *self {
+            XorUrl::ImmutableData(name) => {
+                bytes.push(IDATA_KIND);
+                bytes.extend_from_slice(&name.0);
+            }
+            XorUrl::MutableData(name, type_tag) => {
+                bytes.push(MDATA_KIND);
+                bytes.extend_from_slice(&name.0);
+                bytes.extend((0..8).rev().map(|byte| (type_tag >> (byte * 8)) as u8));
+            }
+        }
+
+        format!("{}{}", SAFE_URL_SCHEME, base32_encode(&bytes))
+    }
+
+    /// Decode the host of the URL. Returns `None` if the host is not a
+    /// XOR-URL, e.g. if it's a public name.
+    pub fn decode(url: &str) -> Option<Self> {
+        let bytes = match base32_decode(split_url(url).0) {
+            Some(ref bytes) if bytes.len() > XOR_NAME_LEN => bytes.clone(),
+            _ => return None,
+        };
+
+        let mut name = [0; XOR_NAME_LEN];
+        name.copy_from_slice(&bytes[1..XOR_NAME_LEN + 1]);
+        let rest = &bytes[XOR_NAME_LEN + 1..];
+
+        match (bytes[0], rest.len()) {
+            (IDATA_KIND, 0) => Some(XorUrl::ImmutableData(XorName(name))),
+            (MDATA_KIND, 8) => {
+                let type_tag = rest.iter().fold(0, |acc, byte| (acc << 8) | u64::from(*byte));
+                Some(XorUrl::MutableData(XorName(name), type_tag))
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Split the URL into its host and path, without the scheme and the slash
+/// separating them. The scheme is optional.
+pub fn split_url(url: &str) -> (&str, &str) {
+    let url = if url.starts_with(SAFE_URL_SCHEME) {
+        &url[SAFE_URL_SCHEME.len()..]
+    } else {
+        url
+    };
+
+    match url.find('/') {
+        Some(index) => (&url[..index], &url[index + 1..]),
+        None => (url, ""),
+    }
+}
+
+// RFC 4648 base32 in lower case and without padding, which keeps the URLs
+// usable as case-insensitive host names.
+fn base32_encode(bytes: &[u8]) -> String {
+    let mut output = String::with_capacity((bytes.len() * 8 + 4) / 5);
+    let mut buffer = 0u32;
+    let mut bits = 0;
+
+    for byte in bytes {
+        buffer = (buffer << 8) | u32::from(*byte);
+        bits += 8;
+        while bits >= 5 {
+            bits -= 5;
+            output.push(BASE32_ALPHABET[((buffer >> bits) & 0x1f) as usize] as char);
+        }
+    }
+    if bits > 0 {
+        output.push(BASE32_ALPHABET[((buffer << (5 - bits)) & 0x1f) as usize] as char);
+    }
+
+    output
+}
+
+fn base32_decode(input: &str) -> Option<Vec<u8>> {
+    let mut output = Vec::with_capacity(input.len() * 5 / 8);
+    let mut buffer = 0u32;
+    let mut bits = 0;
+
+    for c in input.bytes() {
+        let c = if c >= b'A' && c <= b'Z' { c - b'A' + b'a' } else { c };
+        let value = match BASE32_ALPHABET.iter().position(|a| *a == c) {
+            Some(value) => value as u32,
+            None => return None,
+        };
+        buffer = (buffer << 5) | value;
+        bits += 5;
+        if bits >= 8 {
+            bits -= 8;
+            output.push((buffer >> bits) as u8);
+        }
+    }
+
+    Some(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand;
+
+    #[test]
+    fn encode_and_decode() {
+        let idata = XorUrl::ImmutableData(rand::random());
+        let mdata = XorUrl::MutableData(rand::random(), 15_000);
+
+        for url in &[idata, mdata] {
+            let encoded = url.encode();
+            assert!(encoded.starts_with(SAFE_URL_SCHEME));
+            assert_eq!(XorUrl::decode(&encoded), Some(*url));
+            assert_eq!(XorUrl::decode(&format!("{}/index.html", encoded)), Some(*url));
+            let upper = encoded[SAFE_URL_SCHEME.len()..].to_uppercase();
+            assert_eq!(XorUrl::decode(&upper), Some(*url));
+        }
+
+        assert_eq!(XorUrl::decode("safe://www.example/index.html"), None);
+        assert_eq!(split_url("safe://example/a/b"), ("example", "a/b"));
+    }
+}